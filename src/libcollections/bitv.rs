@@ -754,6 +754,190 @@ impl Bitv {
         }
         self.set(insert_pos, elem);
     }
+
+    /// Builds the support structure for constant-time `rank` and `select`
+    /// queries on this bitvector, which can't be modified while it is alive.
+    ///
+    /// Building it takes two passes over the bits. It takes about one word
+    /// of memory per eight words of the bitvector, plus one word per set bit
+    /// in the stretches where set bits are very sparse, which is at most one
+    /// word per sixteen words of the bitvector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::bitv;
+    ///
+    /// let bv = bitv::from_bytes([0b01101000]);
+    /// let rs = bv.rank_select();
+    /// assert_eq!(rs.rank(3), 2);
+    /// assert_eq!(rs.select(2), Some(4));
+    /// assert_eq!(rs.select(3), None);
+    /// ```
+    pub fn rank_select<'a>(&'a self) -> RankSelect<'a> {
+        RankSelect::new(self)
+    }
+
+    // Returns the `i`th word of storage, with the bits past the end masked
+    #[inline]
+    fn masked_word(&self, i: uint) -> uint {
+        let w = self.storage[i];
+        let rem = self.nbits % uint::BITS;
+        if i + 1 == self.storage.len() && rem > 0 { w & ((1 << rem) - 1) } else { w }
+    }
+}
+
+static RANK_BLOCK_WORDS: uint = 8;
+static SELECT_SAMPLE: uint = 1024;
+static SELECT_SPARSE_BITS: uint = 1 << 20;
+
+/// The support structure for rank and select queries on a `Bitv`, created by
+/// `Bitv::rank_select`.
+///
+/// It stores the number of set bits before every block of eight words, so
+/// that `rank` only ever has to count the bits of a few words.
+///
+/// For `select`, the set bits are split into groups of 1024. A group
+/// spanning at least 2^20 bits stores the index of each of its bits, and
+/// `select` looks them up directly. Any other group spans at most 2^20 bits,
+/// so `select` binary searches at most 2^20 / 512 blocks for it, in eleven
+/// steps on 64-bit targets, and then counts the bits of at most eight words.
+/// Both take constant time.
+pub struct RankSelect<'a> {
+    bitv: &'a Bitv,
+    /// The number of set bits before each block
+    blocks: Vec<uint>,
+    /// Each `SELECT_SAMPLE` consecutive set bits
+    groups: Vec<SelectGroup>,
+    ones: uint,
+}
+
+enum SelectGroup {
+    /// A group spanning less than `SELECT_SPARSE_BITS` bits, by the index of
+    /// its first set bit
+    Dense(uint),
+    /// A group spanning `SELECT_SPARSE_BITS` bits or more, by the index of
+    /// each of its set bits
+    Sparse(Vec<uint>),
+}
+
+impl SelectGroup {
+    fn first(&self) -> uint {
+        match *self {
+            Dense(first) => first,
+            Sparse(ref bits) => bits[0],
+        }
+    }
+}
+
+impl<'a> RankSelect<'a> {
+    fn new(bitv: &'a Bitv) -> RankSelect<'a> {
+        let nwords = bitv.storage.len();
+        let mut blocks = Vec::with_capacity(nwords / RANK_BLOCK_WORDS + 1);
+        let mut firsts = Vec::new();
+        let mut ones = 0;
+        for i in range(0, nwords) {
+            if i % RANK_BLOCK_WORDS == 0 { blocks.push(ones) }
+            let w = bitv.masked_word(i);
+            let count = w.count_ones();
+            // Record the index of the first one of any group in this word
+            while firsts.len() * SELECT_SAMPLE < ones + count {
+                let mut v = w;
+                for _ in range(0, firsts.len() * SELECT_SAMPLE - ones) { v &= v - 1; }
+                firsts.push(i * uint::BITS + v.trailing_zeros());
+            }
+            ones += count;
+        }
+        if nwords % RANK_BLOCK_WORDS == 0 { blocks.push(ones) }
+
+        let mut groups = Vec::with_capacity(firsts.len());
+        for (g, &first) in firsts.iter().enumerate() {
+            let end = if g + 1 < firsts.len() { firsts[g + 1] } else { bitv.nbits };
+            if end - first < SELECT_SPARSE_BITS {
+                groups.push(Dense(first));
+                continue
+            }
+            let mut bits = Vec::with_capacity(SELECT_SAMPLE);
+            let mut word = first / uint::BITS;
+            // Skip the ones of the previous group in the first word
+            let mut w = bitv.masked_word(word) & !((1 << (first % uint::BITS)) - 1);
+            loop {
+                while w != 0 && bits.len() < SELECT_SAMPLE {
+                    bits.push(word * uint::BITS + w.trailing_zeros());
+                    w &= w - 1;
+                }
+                word += 1;
+                if bits.len() == SELECT_SAMPLE || word == nwords { break }
+                w = bitv.masked_word(word);
+            }
+            groups.push(Sparse(bits));
+        }
+        RankSelect { bitv: bitv, blocks: blocks, groups: groups, ones: ones }
+    }
+
+    /// Returns the number of set bits in the bitvector.
+    #[inline]
+    pub fn count_ones(&self) -> uint { self.ones }
+
+    /// Returns the number of set bits before index `i`.
+    ///
+    /// # Failure
+    ///
+    /// Fails if `i` is greater than the length of the bitvector.
+    pub fn rank(&self, i: uint) -> uint {
+        assert!(i <= self.bitv.nbits);
+        let word = i / uint::BITS;
+        let block = word / RANK_BLOCK_WORDS;
+        let mut rank = self.blocks[block];
+        for w in range(block * RANK_BLOCK_WORDS, word) {
+            rank += self.bitv.masked_word(w).count_ones();
+        }
+        let rem = i % uint::BITS;
+        if rem > 0 {
+            rank += (self.bitv.masked_word(word) & ((1 << rem) - 1)).count_ones();
+        }
+        rank
+    }
+
+    /// Returns the index of the set bit with rank `k`, that is the index of
+    /// the `k + 1`th set bit, or `None` if there are no more than `k` of
+    /// them.
+    pub fn select(&self, k: uint) -> Option<uint> {
+        if k >= self.ones { return None }
+
+        let group = k / SELECT_SAMPLE;
+        let first = match self.groups[group] {
+            Sparse(ref bits) => return Some(bits[k % SELECT_SAMPLE]),
+            Dense(first) => first,
+        };
+
+        // Find the last block of the group with at most k ones before it
+        let block_bits = RANK_BLOCK_WORDS * uint::BITS;
+        let mut lo = first / block_bits;
+        let mut hi = if group + 1 < self.groups.len() {
+            self.groups[group + 1].first() / block_bits
+        } else {
+            self.blocks.len() - 1
+        };
+        while lo < hi {
+            let mid = (lo + hi + 1) / 2;
+            if self.blocks[mid] <= k { lo = mid } else { hi = mid - 1 }
+        }
+
+        let mut left = k - self.blocks[lo];
+        let mut word = lo * RANK_BLOCK_WORDS;
+        loop {
+            let mut w = self.bitv.masked_word(word);
+            let count = w.count_ones();
+            if left < count {
+                // Clear the lowest set bits until the one we want is lowest
+                for _ in range(0, left) { w &= w - 1; }
+                return Some(word * uint::BITS + w.trailing_zeros());
+            }
+            left -= count;
+            word += 1;
+        }
+    }
 }
 
 /// Transforms a byte-vector into a `Bitv`. Each byte becomes eight bits,
@@ -1660,6 +1844,7 @@ mod tests {
 
     use {Set, Mutable, MutableSet, MutableSeq};
     use bitv::{Bitv, BitvSet, from_fn, from_bytes};
+    use bitv::{SELECT_SAMPLE, Sparse, Dense};
     use bitv;
     use vec::Vec;
 
@@ -2556,6 +2741,79 @@ mod tests {
         assert_eq!("{1, 2, 10, 50}".to_string(), s.to_string());
     }
 
+    #[test]
+    fn test_rank_select_small() {
+        let bv = from_bytes([0b10110110, 0b00000000, 0b10101010]);
+        let rs = bv.rank_select();
+        let ones = [0u, 2, 3, 5, 6, 16, 18, 20, 22];
+        assert_eq!(rs.count_ones(), ones.len());
+        for (k, &i) in ones.iter().enumerate() {
+            assert_eq!(rs.select(k), Some(i));
+            assert_eq!(rs.rank(i), k);
+            assert_eq!(rs.rank(i + 1), k + 1);
+        }
+        assert_eq!(rs.select(ones.len()), None);
+        assert_eq!(rs.rank(24), ones.len());
+
+        let empty = Bitv::new();
+        assert_eq!(empty.rank_select().rank(0), 0);
+        assert_eq!(empty.rank_select().select(0), None);
+    }
+
+    #[test]
+    fn test_rank_select_ignores_unused_bits() {
+        // The bits of the last word past the length are all set
+        let bv = Bitv::with_capacity(100, true);
+        let rs = bv.rank_select();
+        assert_eq!(rs.count_ones(), 100);
+        assert_eq!(rs.rank(100), 100);
+        assert_eq!(rs.select(99), Some(99));
+        assert_eq!(rs.select(100), None);
+    }
+
+    #[test]
+    fn test_rank_select_big() {
+        let mut r = rng();
+        for &density in [2u, 100, 5000].iter() {
+            let bv = from_fn(100000, |_| r.gen_range(0, density) == 0);
+            let rs = bv.rank_select();
+            let mut k = 0;
+            for (i, b) in bv.iter().enumerate() {
+                assert_eq!(rs.rank(i), k);
+                if b {
+                    assert_eq!(rs.select(k), Some(i));
+                    k += 1;
+                }
+            }
+            assert_eq!(rs.rank(bv.len()), k);
+            assert_eq!(rs.select(k), None);
+        }
+    }
+
+    #[test]
+    fn test_rank_select_sparse() {
+        // Dense groups of ones around a stretch of groups spanning more than
+        // SELECT_SPARSE_BITS bits
+        let spacing = 2000;
+        let mut ones = Vec::from_fn(2 * SELECT_SAMPLE, |i| i);
+        ones.push_all_move(Vec::from_fn(3 * SELECT_SAMPLE, |i| (i + 2) * spacing));
+        let end = (3 * SELECT_SAMPLE + 3) * spacing;
+        ones.push_all_move(Vec::from_fn(2 * SELECT_SAMPLE, |i| end + i));
+        let mut bv = Bitv::with_capacity(*ones.last().unwrap() + 1, false);
+        for &i in ones.iter() {
+            bv.set(i, true);
+        }
+        let rs = bv.rank_select();
+        assert_eq!(rs.count_ones(), ones.len());
+        for (k, &i) in ones.iter().enumerate() {
+            assert_eq!(rs.select(k), Some(i));
+            assert_eq!(rs.rank(i), k);
+        }
+        assert_eq!(rs.select(ones.len()), None);
+        assert!(rs.groups.iter().any(|g| match *g { Sparse(_) => true, Dense(_) => false }));
+        assert!(rs.groups.iter().any(|g| match *g { Sparse(_) => false, Dense(_) => true }));
+    }
+
     fn rng() -> rand::IsaacRng {
         let seed: &[_] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 0];
         rand::SeedableRng::from_seed(seed)
@@ -1,4 +1,4 @@
-// Copyright 2012-2013 The Rust Project Developers. See the COPYRIGHT
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
 // file at the top-level directory of this distribution and at
 // http://rust-lang.org/COPYRIGHT.
 //
@@ -7,913 +7,1719 @@
 // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
-//
-
-// NB. this is not deprecated for removal, just deprecating the
-// current implementation. If the major pain-points are addressed
-// (overuse of by-value self and .clone), this can be removed.
-#![deprecated = "the current implementation is extremely inefficient, \
-                 prefer a HashMap, TreeMap or TrieMap"]
-#![allow(deprecated)]
-
-//! Starting implementation of a B-tree for Rust.
-//! Structure inspired by Github user davidhalperin's gist.
 
-// A B-tree contains a root node (which contains a vector of elements),
-// a length (the height of the tree), and lower and upper bounds on the
-// number of elements that a given node can contain.
+//! An ordered map and set implemented as B-trees.
+//!
+//! Each node of a B-tree holds a small sorted array of keys, rather than the
+//! single key per node of a binary search tree. Lookups therefore touch far
+//! fewer nodes, and scan keys which sit next to each other in memory, which
+//! makes a `BTreeMap` much friendlier to the cache than a `TreeMap` and
+//! considerably more compact once it holds many elements. As with `TreeMap`,
+//! the only requirement is that the key implements `Ord`.
+//!
+//! ## Example
+//!
+//! ```{rust}
+//! use std::collections::BTreeMap;
+//! use std::collections::{Included, Excluded};
+//!
+//! let mut map = BTreeMap::new();
+//! for i in range(0u, 100) {
+//!     map.insert(i, i * i);
+//! }
+//!
+//! // Prints 10, 11 and 12, along with their squares
+//! for (k, v) in map.range(Included(&10), Excluded(&13)) {
+//!     println!("{}: {}", k, v);
+//! }
+//! ```
 
 use core::prelude::*;
 
 use alloc::boxed::Box;
+use core::default::Default;
 use core::fmt;
 use core::fmt::Show;
-
-use MutableSeq;
+use core::iter::Peekable;
+use core::iter;
+use core::kinds::marker;
+use core::mem::replace;
+use std::hash::{Writer, Hash};
+
+use {Mutable, Set, MutableSet, MutableMap, Map, MutableSeq};
+use {Bound, Included, Excluded, Unbounded};
 use vec::Vec;
-
-#[allow(missing_doc)]
-pub struct BTree<K, V> {
+use vec;
+
+// Every node other than the root has between `B` and `2 * B` children, and so
+// holds between `MIN_LEN` and `CAPACITY` keys. Nodes are searched linearly,
+// which at this size beats a binary search.
+static B: uint = 6;
+static MIN_LEN: uint = B - 1;
+static CAPACITY: uint = 2 * B - 1;
+
+/// An ordered map implemented as a B-tree.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+///
+/// map.insert(2i, "bar");
+/// map.insert(1i, "foo");
+/// map.insert(3i, "quux");
+///
+/// // In ascending order by keys
+/// for (key, value) in map.iter() {
+///     println!("{}: {}", key, value);
+/// }
+///
+/// // Prints 1, 2, 3
+/// for key in map.keys() {
+///     println!("{}", key);
+/// }
+///
+/// // Prints `foo`, `bar`, `quux`
+/// for value in map.values() {
+///     println!("{}", value);
+/// }
+///
+/// map.remove(&1);
+/// assert_eq!(map.len(), 2);
+///
+/// if !map.contains_key(&1) {
+///     println!("1 is no more");
+/// }
+///
+/// for key in range(0, 4) {
+///     match map.find(&key) {
+///         Some(val) => println!("{} has a value: {}", key, val),
+///         None => println!("{} not in map", key),
+///     }
+/// }
+///
+/// map.clear();
+/// assert!(map.is_empty());
+/// ```
+#[deriving(Clone)]
+pub struct BTreeMap<K, V> {
     root: Node<K, V>,
-    len: uint,
-    lower_bound: uint,
-    upper_bound: uint
-}
-
-impl<K: Ord, V> BTree<K, V> {
-    /// Returns new `BTree` with root node (leaf) and user-supplied lower bound
-    /// The lower bound applies to every node except the root node.
-    pub fn new(k: K, v: V, lb: uint) -> BTree<K, V> {
-        BTree {
-            root: Node::new_leaf(vec!(LeafElt::new(k, v))),
-            len: 1,
-            lower_bound: lb,
-            upper_bound: 2 * lb
-        }
-    }
-
-    /// Helper function for `clone`: returns new BTree with supplied root node,
-    /// length, and lower bound. For use when the length is known already.
-    fn new_with_node_len(n: Node<K, V>,
-                         length: uint,
-                         lb: uint) -> BTree<K, V> {
-        BTree {
-            root: n,
-            len: length,
-            lower_bound: lb,
-            upper_bound: 2 * lb
-        }
-    }
-}
-
-// We would probably want to remove the dependence on the Clone trait in the future.
-// It is here as a crutch to ensure values can be passed around through the tree's nodes
-// especially during insertions and deletions.
-impl<K: Clone + Ord, V: Clone> BTree<K, V> {
-    /// Returns the value of a given key, which may not exist in the tree.
-    /// Calls the root node's get method.
-    pub fn get(self, k: K) -> Option<V> {
-        return self.root.get(k);
-    }
-
-    /// An insert method that uses the `clone` method for support.
-    pub fn insert(mut self, k: K, v: V) -> BTree<K, V> {
-        let (a, b) = self.root.clone().insert(k, v, self.upper_bound.clone());
-        if b {
-            match a.clone() {
-                LeafNode(leaf) => {
-                    self.root = Node::new_leaf(leaf.clone().elts);
-                }
-                BranchNode(branch) => {
-                    self.root = Node::new_branch(branch.clone().elts,
-                                                 branch.clone().rightmost_child);
-                }
-            }
-        }
-        self
+    length: uint,
+}
+
+impl<K: PartialEq + Ord, V: PartialEq> PartialEq for BTreeMap<K, V> {
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        self.len() == other.len() &&
+            self.iter().zip(other.iter()).all(|(a, b)| a == b)
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for BTree<K, V> {
-    fn clone(&self) -> BTree<K, V> {
-        BTree::new_with_node_len(self.root.clone(), self.len, self.lower_bound)
+impl<K: Eq + Ord, V: Eq> Eq for BTreeMap<K, V> {}
+
+impl<K: Ord, V: PartialOrd> PartialOrd for BTreeMap<K, V> {
+    #[inline]
+    fn partial_cmp(&self, other: &BTreeMap<K, V>) -> Option<Ordering> {
+        iter::order::partial_cmp(self.iter(), other.iter())
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for BTree<K, V> {
-    fn eq(&self, other: &BTree<K, V>) -> bool {
-        self.root.cmp(&other.root) == Equal
+impl<K: Ord, V: Ord> Ord for BTreeMap<K, V> {
+    #[inline]
+    fn cmp(&self, other: &BTreeMap<K, V>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
     }
 }
 
-impl<K: Ord, V: Eq> Eq for BTree<K, V> {}
+impl<K: Ord + Show, V: Show> Show for BTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
 
-impl<K: Ord, V: Eq> PartialOrd for BTree<K, V> {
-    fn partial_cmp(&self, other: &BTree<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+        write!(f, "}}")
     }
 }
 
-impl<K: Ord, V: Eq> Ord for BTree<K, V> {
-    /// Returns an ordering based on the root nodes of each `BTree`.
-    fn cmp(&self, other: &BTree<K, V>) -> Ordering {
-        self.root.cmp(&other.root)
+impl<K: Ord, V> Collection for BTreeMap<K, V> {
+    fn len(&self) -> uint { self.length }
+}
+
+impl<K: Ord, V> Mutable for BTreeMap<K, V> {
+    fn clear(&mut self) {
+        self.root = Node::new_leaf();
+        self.length = 0
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for BTree<K, V> {
-    /// Returns a string representation of the `BTree`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.root.fmt(f)
+impl<K: Ord, V> Map<K, V> for BTreeMap<K, V> {
+    fn find<'a>(&'a self, key: &K) -> Option<&'a V> {
+        let mut node = &self.root;
+        loop {
+            match node.search(key) {
+                Found(i) => return Some(&node.vals[i]),
+                GoDown(i) => {
+                    if node.is_leaf() { return None }
+                    node = &*node.edges[i];
+                }
+            }
+        }
     }
 }
 
+impl<K: Ord, V> MutableMap<K, V> for BTreeMap<K, V> {
+    #[inline]
+    fn find_mut<'a>(&'a mut self, key: &K) -> Option<&'a mut V> {
+        self.root.find_mut(key)
+    }
 
-// Node types
-//
-// A node is either a LeafNode or a BranchNode, which contain either a Leaf or a Branch.
-// Branches contain BranchElts, which contain a left child (another node) and a key-value
-// pair. Branches also contain the rightmost child of the elements in the array.
-// Leaves contain LeafElts, which do not have children.
-enum Node<K, V> {
-    LeafNode(Leaf<K, V>),
-    BranchNode(Branch<K, V>)
+    fn swap(&mut self, key: K, value: V) -> Option<V> {
+        match self.root.insert(key, value) {
+            Fit(ret) => {
+                if ret.is_none() { self.length += 1 }
+                ret
+            }
+            Split(key, value, right) => {
+                // The root itself overflowed, so the tree grows a level
+                let left = replace(&mut self.root, Node::new_internal());
+                self.root.keys.push(key);
+                self.root.vals.push(value);
+                self.root.edges.push(box left);
+                self.root.edges.push(right);
+                self.length += 1;
+                None
+            }
+        }
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        let ret = self.root.remove(key);
+        if ret.is_some() {
+            self.length -= 1;
+            // The root's last two children were merged, so the tree shrinks
+            if self.root.keys.is_empty() && !self.root.is_leaf() {
+                let box child = self.root.edges.pop().unwrap();
+                self.root = child;
+            }
+        }
+        ret
+    }
 }
 
+impl<K: Ord, V> Default for BTreeMap<K,V> {
+    #[inline]
+    fn default() -> BTreeMap<K, V> { BTreeMap::new() }
+}
 
-impl<K: Ord, V> Node<K, V> {
-    /// Creates a new leaf node given a vector of elements.
-    fn new_leaf(vec: Vec<LeafElt<K, V>>) -> Node<K,V> {
-        LeafNode(Leaf::new(vec))
+impl<K: Ord, V> Index<K, V> for BTreeMap<K, V> {
+    #[inline]
+    fn index<'a>(&'a self, i: &K) -> &'a V {
+        self.find(i).expect("no entry found for key")
     }
+}
 
-    /// Creates a new branch node given a vector of an elements and a pointer to a rightmost child.
-    fn new_branch(vec: Vec<BranchElt<K, V>>, right: Box<Node<K, V>>)
-                  -> Node<K, V> {
-        BranchNode(Branch::new(vec, right))
+impl<K: Ord, V> BTreeMap<K, V> {
+    /// Creates an empty `BTreeMap`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map: BTreeMap<&str, int> = BTreeMap::new();
+    /// ```
+    pub fn new() -> BTreeMap<K, V> {
+        BTreeMap { root: Node::new_leaf(), length: 0 }
     }
 
-    /// Determines whether the given Node contains a Branch or a Leaf.
-    /// Used in testing.
-    fn is_leaf(&self) -> bool {
-        match self {
-            &LeafNode(..) => true,
-            &BranchNode(..) => false
-        }
+    /// Gets a lazy iterator over the keys in the map, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print "a", "b", "c" in order.
+    /// for x in map.keys() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        self.iter().map(|(k, _v)| k)
     }
 
-    /// A binary search function for Nodes.
-    /// Calls either the Branch's or the Leaf's bsearch function.
-    fn bsearch_node(&self, k: K) -> Option<uint> {
-         match self {
-             &LeafNode(ref leaf) => leaf.bsearch_leaf(k),
-             &BranchNode(ref branch) => branch.bsearch_branch(k)
-         }
-     }
-}
+    /// Gets a lazy iterator over the values in the map, in ascending order
+    /// with respect to the corresponding keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print 1, 2, 3 ordered by keys.
+    /// for x in map.values() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        self.iter().map(|(_k, v)| v)
+    }
 
-impl<K: Clone + Ord, V: Clone> Node<K, V> {
-    /// Returns the corresponding value to the provided key.
-    /// `get()` is called in different ways on a branch or a leaf.
-    fn get(&self, k: K) -> Option<V> {
-        match *self {
-            LeafNode(ref leaf) => return leaf.get(k),
-            BranchNode(ref branch) => return branch.get(k)
+    /// Gets a lazy iterator over the key-value pairs in the map, in ascending
+    /// order. The iterator is double-ended, so `.rev()` visits the pairs in
+    /// descending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print contents in ascending order
+    /// for (key, value) in map.iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    ///
+    /// // Print contents in descending order
+    /// for (key, value) in map.iter().rev() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            cursor: Cursor::new(&self.root, self.length, Unbounded, Unbounded),
+            marker: marker::ContravariantLifetime,
         }
     }
 
-    /// Matches on the `Node`, then performs and returns the appropriate insert method.
-    fn insert(self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        match self {
-            LeafNode(leaf) => leaf.insert(k, v, ub),
-            BranchNode(branch) => branch.insert(k, v, ub)
+    /// Gets a lazy iterator over the key-value pairs in the map, in ascending
+    /// order, with the values being mutable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Add 10 until we find "b"
+    /// for (key, value) in map.mut_iter() {
+    ///     *value += 10;
+    ///     if key == &"b" { break }
+    /// }
+    ///
+    /// assert_eq!(map.find(&"a"), Some(&11));
+    /// assert_eq!(map.find(&"b"), Some(&12));
+    /// assert_eq!(map.find(&"c"), Some(&3));
+    /// ```
+    pub fn mut_iter<'a>(&'a mut self) -> MutEntries<'a, K, V> {
+        MutEntries {
+            cursor: Cursor::new(&self.root, self.length, Unbounded, Unbounded),
+            marker: marker::ContravariantLifetime,
         }
     }
-}
 
-impl<K: Clone + Ord, V: Clone> Clone for Node<K, V> {
-    /// Returns a new `Node` based on whether or not it is a branch or a leaf.
-    fn clone(&self) -> Node<K, V> {
-        match *self {
-            LeafNode(ref leaf) => {
-                Node::new_leaf(leaf.elts.clone())
-            }
-            BranchNode(ref branch) => {
-                Node::new_branch(branch.elts.clone(),
-                                 branch.rightmost_child.clone())
-            }
+    /// Gets a lazy iterator that consumes the map, yielding its key-value
+    /// pairs in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Not possible with a regular `.iter()`
+    /// let vec: Vec<(&str, int)> = map.move_iter().collect();
+    /// assert_eq!(vec, vec![("a", 1), ("b", 2), ("c", 3)]);
+    /// ```
+    pub fn move_iter(self) -> MoveEntries<K, V> {
+        let mut entries = Vec::with_capacity(self.length);
+        self.root.flatten_into(&mut entries);
+        MoveEntries { iter: entries.move_iter() }
+    }
+
+    /// Gets a lazy iterator over the key-value pairs whose keys lie between
+    /// `min` and `max`, in ascending order. The iterator is empty if `min` is
+    /// past `max`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use std::collections::{Included, Excluded, Unbounded};
+    ///
+    /// let map: BTreeMap<int, &str> = vec![(2, "a"), (4, "b"), (6, "c"), (8, "d")]
+    ///                                    .move_iter().collect();
+    ///
+    /// let keys: Vec<int> = map.range(Included(&4), Excluded(&8))
+    ///                         .map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![4, 6]);
+    ///
+    /// let keys: Vec<int> = map.range(Excluded(&4), Unbounded)
+    ///                         .rev().map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![8, 6]);
+    /// ```
+    pub fn range<'a>(&'a self, min: Bound<&K>, max: Bound<&K>)
+                     -> Entries<'a, K, V> {
+        Entries {
+            cursor: Cursor::new(&self.root, self.length, min, max),
+            marker: marker::ContravariantLifetime,
+        }
+    }
+
+    /// Gets a lazy iterator over the key-value pairs whose keys lie between
+    /// `min` and `max`, in ascending order, with the values being mutable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use std::collections::{Included, Unbounded};
+    ///
+    /// let mut map: BTreeMap<int, int> = range(0, 10).map(|i| (i, i)).collect();
+    /// for (_, v) in map.mut_range(Included(&5), Unbounded) {
+    ///     *v = 0;
+    /// }
+    /// assert_eq!(map.find(&4), Some(&4));
+    /// assert_eq!(map.find(&5), Some(&0));
+    /// ```
+    pub fn mut_range<'a>(&'a mut self, min: Bound<&K>, max: Bound<&K>)
+                         -> MutEntries<'a, K, V> {
+        MutEntries {
+            cursor: Cursor::new(&self.root, self.length, min, max),
+            marker: marker::ContravariantLifetime,
         }
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for Node<K, V> {
-    fn eq(&self, other: &Node<K, V>) -> bool {
-        match *self{
-            BranchNode(ref branch) => {
-                if other.is_leaf() {
-                    return false;
-                }
-                match *other {
-                    BranchNode(ref branch2) => branch.cmp(branch2) == Equal,
-                    LeafNode(..) => false
+/// A position at each end of an iteration over a B-tree.
+///
+/// Each end is a stack of `(node, index)` pairs describing the path from the
+/// root down to the next element that end yields. At the front, the element
+/// at the top of the stack is `keys[index]` of its node; at the back it is
+/// `keys[index - 1]`. The pairs below the top describe the edges being
+/// descended, and are popped as soon as they run out of elements.
+///
+/// Raw pointers are used so that the same code can back both the shared and
+/// the mutable iterators; only values are ever handed out mutably, and the
+/// tree can't be reshaped while it's borrowed by an iterator.
+struct Cursor<K, V> {
+    front: Vec<(*const Node<K, V>, uint)>,
+    back: Vec<(*const Node<K, V>, uint)>,
+    // Set once the two ends have met
+    done: bool,
+    // An upper bound on the number of elements left, which is exact unless
+    // this is a range
+    remaining: uint,
+    exact: bool,
+}
+
+impl<K: Ord, V> Cursor<K, V> {
+    fn new(root: &Node<K, V>, len: uint, min: Bound<&K>, max: Bound<&K>)
+           -> Cursor<K, V> {
+        let exact = match (&min, &max) {
+            (&Unbounded, &Unbounded) => true,
+            _ => false,
+        };
+        let mut cursor = Cursor {
+            front: Vec::new(),
+            back: Vec::new(),
+            done: len == 0,
+            remaining: len,
+            exact: exact,
+        };
+        if len == 0 { return cursor }
+
+        let mut node = root;
+        loop {
+            // Find the first key in this node which may be in the range, and
+            // stop descending if it certainly is.
+            let (i, stop) = match min {
+                Unbounded => (0, false),
+                Included(k) => node.count_below(k, false),
+                Excluded(k) => {
+                    let (i, _) = node.count_below(k, true);
+                    (i, false)
                 }
-            }
-            LeafNode(ref leaf) => {
-                match *other {
-                    LeafNode(ref leaf2) => leaf.cmp(leaf2) == Equal,
-                    BranchNode(..) => false
+            };
+            cursor.front.push((node as *const Node<K, V>, i));
+            if stop || node.is_leaf() { break }
+            node = &*node.edges[i];
+        }
+        cursor.settle_front();
+
+        let mut node = root;
+        loop {
+            let (j, stop) = match max {
+                Unbounded => (node.keys.len(), false),
+                Included(k) => node.count_below(k, true),
+                Excluded(k) => {
+                    let (j, _) = node.count_below(k, false);
+                    (j, false)
                 }
-            }
+            };
+            cursor.back.push((node as *const Node<K, V>, j));
+            if stop || node.is_leaf() { break }
+            node = &*node.edges[j];
         }
+        cursor.settle_back();
+
+        // The range is empty if either end ran off the tree, or if the ends
+        // have already crossed.
+        let empty = match (cursor.front.last(), cursor.back.last()) {
+            (Some(&(f, i)), Some(&(b, j))) => unsafe {
+                (*f).keys[i] > (*b).keys[j - 1]
+            },
+            _ => true,
+        };
+        cursor.done = empty;
+        cursor
     }
 }
 
-impl<K: Ord, V: Eq> Eq for Node<K, V> {}
+impl<K, V> Cursor<K, V> {
+    fn next_front(&mut self) -> Option<(*const Node<K, V>, uint)> {
+        if self.done { return None }
+        let (node, i) = match self.front.pop() {
+            Some(top) => top,
+            None => return None,
+        };
+        let met = match self.back.last() {
+            Some(&(back, j)) => back == node && j == i + 1,
+            None => true,
+        };
+        self.done = met;
+        self.remaining -= 1;
+        self.push_front(node, i + 1);
+        Some((node, i))
+    }
 
-impl<K: Ord, V: Eq> PartialOrd for Node<K, V> {
-    fn partial_cmp(&self, other: &Node<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn next_back(&mut self) -> Option<(*const Node<K, V>, uint)> {
+        if self.done { return None }
+        let (node, j) = match self.back.pop() {
+            Some(top) => top,
+            None => return None,
+        };
+        let met = match self.front.last() {
+            Some(&(front, i)) => front == node && i + 1 == j,
+            None => true,
+        };
+        self.done = met;
+        self.remaining -= 1;
+        self.push_back(node, j - 1);
+        Some((node, j - 1))
     }
-}
 
-impl<K: Ord, V: Eq> Ord for Node<K, V> {
-    /// Implementation of `Ord` for `Node`s.
-    fn cmp(&self, other: &Node<K, V>) -> Ordering {
-        match *self {
-            LeafNode(ref leaf) => {
-                match *other {
-                    LeafNode(ref leaf2) => leaf.cmp(leaf2),
-                    BranchNode(_) => Less
-                }
+    // Pushes `(node, i)` onto the front stack, followed by the path down to
+    // the first element of edge `i`.
+    fn push_front(&mut self, mut node: *const Node<K, V>, mut i: uint) {
+        loop {
+            self.front.push((node, i));
+            let n = unsafe { &*node };
+            if n.is_leaf() { break }
+            node = &*n.edges[i] as *const Node<K, V>;
+            i = 0;
+        }
+        self.settle_front();
+    }
+
+    // Pushes `(node, j)` onto the back stack, followed by the path down to
+    // the last element of edge `j`.
+    fn push_back(&mut self, mut node: *const Node<K, V>, mut j: uint) {
+        loop {
+            self.back.push((node, j));
+            let n = unsafe { &*node };
+            if n.is_leaf() { break }
+            let child = &*n.edges[j];
+            node = child as *const Node<K, V>;
+            j = child.keys.len();
+        }
+        self.settle_back();
+    }
+
+    fn settle_front(&mut self) {
+        loop {
+            match self.front.last() {
+                Some(&(node, i)) if i >= unsafe { (*node).keys.len() } => {}
+                _ => break,
             }
-            BranchNode(ref branch) => {
-                match *other {
-                    BranchNode(ref branch2) => branch.cmp(branch2),
-                    LeafNode(_) => Greater
-                }
+            self.front.pop();
+        }
+    }
+
+    fn settle_back(&mut self) {
+        loop {
+            match self.back.last() {
+                Some(&(_, 0)) => {}
+                _ => break,
             }
+            self.back.pop();
         }
     }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        if self.done { return (0, Some(0)) }
+        (if self.exact { self.remaining } else { 0 }, Some(self.remaining))
+    }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Node<K, V> {
-    /// Returns a string representation of a `Node`.
-    /// Will iterate over the Node and show `Key: x, value: y, child: ()`
-    /// for all elements in the `Node`. `child` only exists if the `Node` contains
-    /// a branch.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            LeafNode(ref leaf) => leaf.fmt(f),
-            BranchNode(ref branch) => branch.fmt(f),
-        }
+/// Lazy double-ended iterator over a map
+pub struct Entries<'a, K:'a, V:'a> {
+    cursor: Cursor<K, V>,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// Lazy double-ended iterator over a map that allows for the mutation of
+/// the values.
+pub struct MutEntries<'a, K:'a, V:'a> {
+    cursor: Cursor<K, V>,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// Lazy double-ended iterator over a map that consumes the map while
+/// iterating
+pub struct MoveEntries<K, V> {
+    iter: vec::MoveItems<(K, V)>,
+}
+
+/// BTreeMap keys iterator.
+pub type Keys<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
+
+/// BTreeMap values iterator.
+pub type Values<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
+
+impl<'a, K, V> Iterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.cursor.next_front().map(|(node, i)| unsafe {
+            (&(*node).keys[i], &(*node).vals[i])
+        })
     }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.cursor.size_hint() }
 }
 
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        self.cursor.next_back().map(|(node, i)| unsafe {
+            (&(*node).keys[i], &(*node).vals[i])
+        })
+    }
+}
+
+impl<'a, K, V> Iterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.cursor.next_front().map(|(node, i)| unsafe {
+            let node = node as *mut Node<K, V>;
+            (&(*node).keys[i], (*node).vals.get_mut(i))
+        })
+    }
 
-// A leaf is a vector with elements that contain no children. A leaf also
-// does not contain a rightmost child.
-struct Leaf<K, V> {
-    elts: Vec<LeafElt<K, V>>
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.cursor.size_hint() }
 }
 
-// Vector of values with children, plus a rightmost child (greater than all)
-struct Branch<K, V> {
-    elts: Vec<BranchElt<K,V>>,
-    rightmost_child: Box<Node<K, V>>,
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.cursor.next_back().map(|(node, i)| unsafe {
+            let node = node as *mut Node<K, V>;
+            (&(*node).keys[i], (*node).vals.get_mut(i))
+        })
+    }
 }
 
+impl<K, V> Iterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> { self.iter.next() }
 
-impl<K: Ord, V> Leaf<K, V> {
-    /// Creates a new `Leaf` from a vector of `LeafElts`.
-    fn new(vec: Vec<LeafElt<K, V>>) -> Leaf<K, V> {
-        Leaf {
-            elts: vec
-        }
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.iter.size_hint() }
+}
+
+impl<K, V> DoubleEndedIterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> { self.iter.next_back() }
+}
+
+/// An implementation of the `Set` trait on top of the `BTreeMap` container.
+/// The only requirement is that the type of the elements contained ascribes
+/// to the `Ord` trait.
+///
+/// ## Example
+///
+/// ```{rust}
+/// use std::collections::BTreeSet;
+///
+/// let mut set = BTreeSet::new();
+///
+/// set.insert(2i);
+/// set.insert(1i);
+/// set.insert(3i);
+///
+/// for i in set.iter() {
+///    println!("{}", i) // prints 1, then 2, then 3
+/// }
+///
+/// set.remove(&3);
+///
+/// if !set.contains(&3) {
+///     println!("set does not contain a 3 anymore");
+/// }
+/// ```
+#[deriving(Clone)]
+pub struct BTreeSet<T> {
+    map: BTreeMap<T, ()>
+}
+
+impl<T: PartialEq + Ord> PartialEq for BTreeSet<T> {
+    #[inline]
+    fn eq(&self, other: &BTreeSet<T>) -> bool { self.map == other.map }
+}
+
+impl<T: Eq + Ord> Eq for BTreeSet<T> {}
+
+impl<T: Ord> PartialOrd for BTreeSet<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &BTreeSet<T>) -> Option<Ordering> {
+        self.map.partial_cmp(&other.map)
     }
+}
 
-    /// Searches a leaf for a spot for a new element using a binary search.
-    /// Returns `None` if the element is already in the vector.
-    fn bsearch_leaf(&self, k: K) -> Option<uint> {
-        let mut high: uint = self.elts.len();
-        let mut low: uint = 0;
-        let mut midpoint: uint = (high - low) / 2 ;
-        if midpoint == high {
-            midpoint = 0;
-        }
-        loop {
-            let order = self.elts[midpoint].key.cmp(&k);
-            match order {
-                Equal => {
-                    return None;
-                }
-                Greater => {
-                    if midpoint > 0 {
-                        if self.elts[midpoint - 1].key.cmp(&k) == Less {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = midpoint / 2;
-                            high = tmp;
-                            continue;
-                        }
-                    }
-                    else {
-                        return Some(0);
-                    }
-                }
-                Less => {
-                    if midpoint + 1 < self.elts.len() {
-                        if self.elts[midpoint + 1].key.cmp(&k) == Greater {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (high + low) / 2;
-                            low = tmp;
-                        }
-                    }
-                    else {
-                        return Some(self.elts.len());
-                    }
-                }
-            }
-        }
+impl<T: Ord> Ord for BTreeSet<T> {
+    #[inline]
+    fn cmp(&self, other: &BTreeSet<T>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
     }
 }
 
+impl<T: Ord + Show> Show for BTreeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
 
-impl<K: Clone + Ord, V: Clone> Leaf<K, V> {
-    /// Returns the corresponding value to the supplied key.
-    fn get(&self, k: K) -> Option<V> {
-        for s in self.elts.iter() {
-            let order = s.key.cmp(&k);
-            match order {
-                Equal => return Some(s.value.clone()),
-                _ => {}
-            }
+        for (i, x) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}", *x));
         }
-        return None;
+
+        write!(f, "}}")
+    }
+}
+
+impl<T: Ord> Collection for BTreeSet<T> {
+    #[inline]
+    fn len(&self) -> uint { self.map.len() }
+}
+
+impl<T: Ord> Mutable for BTreeSet<T> {
+    #[inline]
+    fn clear(&mut self) { self.map.clear() }
+}
+
+impl<T: Ord> Set<T> for BTreeSet<T> {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    fn is_disjoint(&self, other: &BTreeSet<T>) -> bool {
+        self.intersection(other).next().is_none()
     }
 
-    /// Uses `clone()` to facilitate inserting new elements into a tree.
-    fn insert(mut self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        let to_insert = LeafElt::new(k, v);
-        let index: Option<uint> = self.bsearch_leaf(to_insert.clone().key);
-        //Check index to see whether we actually inserted the element into the vector.
-        match index {
-            //If the index is None, the new element already exists in the vector.
-            None => {
-                return (Node::new_leaf(self.clone().elts), false);
+    fn is_subset(&self, other: &BTreeSet<T>) -> bool {
+        let mut x = self.iter();
+        let mut y = other.iter();
+        let mut a = x.next();
+        let mut b = y.next();
+        while a.is_some() {
+            if b.is_none() {
+                return false;
             }
-            //If there is an index, insert at that index.
-            Some(i) => {
-                if i >= self.elts.len() {
-                    self.elts.push(to_insert.clone());
-                }
-                else {
-                    self.elts.insert(i, to_insert.clone());
-                }
+
+            let a1 = a.unwrap();
+            let b1 = b.unwrap();
+
+            match b1.cmp(a1) {
+                Less => (),
+                Greater => return false,
+                Equal => a = x.next(),
             }
+
+            b = y.next();
         }
-        //If we have overfilled the vector (by making its size greater than the
-        //upper bound), we return a new Branch with one element and two children.
-        if self.elts.len() > ub {
-            let midpoint_opt = self.elts.remove(ub / 2);
-            let midpoint = midpoint_opt.unwrap();
-            let (left_leaf, right_leaf) = self.elts.partition(|le|
-                                                              le.key.cmp(&midpoint.key.clone())
-                                                              == Less);
-            let branch_return = Node::new_branch(vec!(BranchElt::new(midpoint.key.clone(),
-                                                                  midpoint.value.clone(),
-                                                             box Node::new_leaf(left_leaf))),
-                                            box Node::new_leaf(right_leaf));
-            return (branch_return, true);
-        }
-        (Node::new_leaf(self.elts.clone()), true)
+        true
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for Leaf<K, V> {
-    /// Returns a new `Leaf` with the same elts.
-    fn clone(&self) -> Leaf<K, V> {
-        Leaf::new(self.elts.clone())
-    }
+impl<T: Ord> MutableSet<T> for BTreeSet<T> {
+    #[inline]
+    fn insert(&mut self, value: T) -> bool { self.map.insert(value, ()) }
+
+    #[inline]
+    fn remove(&mut self, value: &T) -> bool { self.map.remove(value) }
 }
 
-impl<K: Ord, V: Eq> PartialEq for Leaf<K, V> {
-    fn eq(&self, other: &Leaf<K, V>) -> bool {
-        self.elts == other.elts
-    }
+impl<T: Ord> Default for BTreeSet<T> {
+    #[inline]
+    fn default() -> BTreeSet<T> { BTreeSet::new() }
 }
 
-impl<K: Ord, V: Eq> Eq for Leaf<K, V> {}
+impl<T: Ord> BTreeSet<T> {
+    /// Creates an empty `BTreeSet`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let mut set: BTreeSet<int> = BTreeSet::new();
+    /// ```
+    #[inline]
+    pub fn new() -> BTreeSet<T> { BTreeSet { map: BTreeMap::new() } }
+
+    /// Gets a lazy iterator over the values in the set, in ascending order.
+    /// The iterator is double-ended.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [1i, 4, 3, 5, 2].iter().map(|&x| x).collect();
+    ///
+    /// // Will print in ascending order.
+    /// for x in set.iter() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter<'a>(&'a self) -> SetItems<'a, T> {
+        SetItems { iter: self.map.iter() }
+    }
 
-impl<K: Ord, V: Eq> PartialOrd for Leaf<K, V> {
-    fn partial_cmp(&self, other: &Leaf<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Creates a consuming iterator, that is, one that moves each value out
+    /// of the set in ascending order. The set cannot be used after calling
+    /// this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [1i, 4, 3, 5, 2].iter().map(|&x| x).collect();
+    ///
+    /// // Not possible with a regular `.iter()`
+    /// let v: Vec<int> = set.move_iter().collect();
+    /// assert_eq!(v, vec![1, 2, 3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn move_iter(self) -> MoveSetItems<T> {
+        self.map.move_iter().map(|(value, _)| value)
     }
-}
 
-impl<K: Ord, V: Eq> Ord for Leaf<K, V> {
-    /// Returns an ordering based on the first element of each `Leaf`.
-    fn cmp(&self, other: &Leaf<K, V>) -> Ordering {
-        if self.elts.len() > other.elts.len() {
-            return Greater;
-        }
-        if self.elts.len() < other.elts.len() {
-            return Less;
-        }
-        self.elts[0].cmp(&other.elts[0])
+    /// Gets a lazy iterator over the values in the set which lie between
+    /// `min` and `max`, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use std::collections::{Included, Excluded};
+    ///
+    /// let set: BTreeSet<int> = range(0i, 10).collect();
+    /// let v: Vec<int> = set.range(Excluded(&3), Included(&6)).map(|&x| x).collect();
+    /// assert_eq!(v, vec![4, 5, 6]);
+    /// ```
+    #[inline]
+    pub fn range<'a>(&'a self, min: Bound<&T>, max: Bound<&T>) -> SetItems<'a, T> {
+        SetItems { iter: self.map.range(min, max) }
+    }
+
+    /// Visits the values representing the difference, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.difference(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2].iter().map(|&x| x).collect());
+    /// ```
+    pub fn difference<'a>(&'a self, other: &'a BTreeSet<T>) -> DifferenceItems<'a, T> {
+        DifferenceItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the symmetric difference, in ascending
+    /// order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.symmetric_difference(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2, 4, 5].iter().map(|&x| x).collect());
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a BTreeSet<T>)
+        -> SymDifferenceItems<'a, T> {
+        SymDifferenceItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the intersection, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [2, 3, 4].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.intersection(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [2, 3].iter().map(|&x| x).collect());
+    /// ```
+    pub fn intersection<'a>(&'a self, other: &'a BTreeSet<T>)
+        -> IntersectionItems<'a, T> {
+        IntersectionItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the union, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.union(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2, 3, 4, 5].iter().map(|&x| x).collect());
+    /// ```
+    pub fn union<'a>(&'a self, other: &'a BTreeSet<T>) -> UnionItems<'a, T> {
+        UnionItems{a: self.iter().peekable(), b: other.iter().peekable()}
     }
 }
 
+/// A lazy double-ended iterator over a set.
+pub struct SetItems<'a, T:'a> {
+    iter: Entries<'a, T, ()>
+}
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Leaf<K, V> {
-    /// Returns a string representation of a `Leaf`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, s) in self.elts.iter().enumerate() {
-            if i != 0 { try!(write!(f, " // ")) }
-            try!(write!(f, "{}", *s))
-        }
-        Ok(())
+/// A lazy iterator over a set that consumes the set while iterating.
+pub type MoveSetItems<T> = iter::Map<'static, (T, ()), T, MoveEntries<T, ()>>;
+
+/// A lazy iterator producing elements in the set difference (in-order).
+pub struct DifferenceItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set symmetric difference (in-order).
+pub struct SymDifferenceItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set intersection (in-order).
+pub struct IntersectionItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set union (in-order).
+pub struct UnionItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+impl<'a, T> Iterator<&'a T> for SetItems<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(value, _)| value)
     }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.iter.size_hint() }
 }
 
+impl<'a, T> DoubleEndedIterator<&'a T> for SetItems<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back().map(|(value, _)| value)
+    }
+}
 
-impl<K: Ord, V> Branch<K, V> {
-    /// Creates a new `Branch` from a vector of `BranchElts` and a rightmost child (a node).
-    fn new(vec: Vec<BranchElt<K, V>>, right: Box<Node<K, V>>)
-           -> Branch<K, V> {
-        Branch {
-            elts: vec,
-            rightmost_child: right
-        }
+/// Compare `x` and `y`, but return `short` if x is None and `long` if y is None
+fn cmp_opt<T: Ord>(x: Option<&T>, y: Option<&T>,
+                   short: Ordering, long: Ordering) -> Ordering {
+    match (x, y) {
+        (None    , _       ) => short,
+        (_       , None    ) => long,
+        (Some(x1), Some(y1)) => x1.cmp(y1),
     }
+}
 
-    fn bsearch_branch(&self, k: K) -> Option<uint> {
-        let mut midpoint: uint = self.elts.len() / 2;
-        let mut high: uint = self.elts.len();
-        let mut low: uint = 0u;
-        if midpoint == high {
-            midpoint = 0u;
-        }
+impl<'a, T: Ord> Iterator<&'a T> for DifferenceItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
         loop {
-            let order = self.elts[midpoint].key.cmp(&k);
-            match order {
-                Equal => {
-                    return None;
-                }
-                Greater => {
-                    if midpoint > 0 {
-                        if self.elts[midpoint - 1].key.cmp(&k) == Less {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (midpoint - low) / 2;
-                            high = tmp;
-                            continue;
-                        }
-                    }
-                    else {
-                        return Some(0);
-                    }
-                }
-                Less => {
-                    if midpoint + 1 < self.elts.len() {
-                        if self.elts[midpoint + 1].key.cmp(&k) == Greater {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (high - midpoint) / 2;
-                            low = tmp;
-                        }
-                    }
-                    else {
-                        return Some(self.elts.len());
-                    }
-                }
+            match cmp_opt(self.a.peek(), self.b.peek(), Less, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.a.next(); self.b.next(); }
+                Greater => { self.b.next(); }
             }
         }
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Branch<K, V> {
-    /// Returns the corresponding value to the supplied key.
-    /// If the key is not there, find the child that might hold it.
-    fn get(&self, k: K) -> Option<V> {
-        for s in self.elts.iter() {
-            let order = s.key.cmp(&k);
-            match order {
-                Less => return s.left.get(k),
-                Equal => return Some(s.value.clone()),
-                _ => {}
+impl<'a, T: Ord> Iterator<&'a T> for SymDifferenceItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match cmp_opt(self.a.peek(), self.b.peek(), Greater, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.a.next(); self.b.next(); }
+                Greater => return self.b.next(),
             }
         }
-        self.rightmost_child.get(k)
-    }
-
-    /// An insert method that uses `.clone()` for support.
-    fn insert(mut self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        let mut new_branch = Node::new_branch(self.clone().elts, self.clone().rightmost_child);
-        let mut outcome = false;
-        let index: Option<uint> = new_branch.bsearch_node(k.clone());
-        //First, find which path down the tree will lead to the appropriate leaf
-        //for the key-value pair.
-        match index.clone() {
-            None => {
-                return (Node::new_branch(self.clone().elts,
-                                         self.clone().rightmost_child),
-                        outcome);
-            }
-            Some(i) => {
-                if i == self.elts.len() {
-                    let new_outcome = self.clone().rightmost_child.insert(k.clone(),
-                                                                          v.clone(),
-                                                                          ub.clone());
-                    new_branch = new_outcome.clone().val0();
-                    outcome = new_outcome.val1();
-                }
-                else {
-                    let new_outcome = self.elts[i].left.clone().insert(k.clone(),
-                                                                       v.clone(),
-                                                                       ub.clone());
-                    new_branch = new_outcome.clone().val0();
-                    outcome = new_outcome.val1();
-                }
-                //Check to see whether a branch or a leaf was returned from the
-                //tree traversal.
-                match new_branch.clone() {
-                    //If we have a leaf, we do not need to resize the tree,
-                    //so we can return false.
-                    LeafNode(..) => {
-                        if i == self.elts.len() {
-                            self.rightmost_child = box new_branch.clone();
-                        }
-                        else {
-                            self.elts.get_mut(i).left = box new_branch.clone();
-                        }
-                        return (Node::new_branch(self.clone().elts,
-                                                 self.clone().rightmost_child),
-                                true);
-                    }
-                    //If we have a branch, we might need to refactor the tree.
-                    BranchNode(..) => {}
-                }
+    }
+}
+
+impl<'a, T: Ord> Iterator<&'a T> for IntersectionItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let o_cmp = match (self.a.peek(), self.b.peek()) {
+                (None    , _       ) => None,
+                (_       , None    ) => None,
+                (Some(a1), Some(b1)) => Some(a1.cmp(b1)),
+            };
+            match o_cmp {
+                None          => return None,
+                Some(Less)    => { self.a.next(); }
+                Some(Equal)   => { self.b.next(); return self.a.next() }
+                Some(Greater) => { self.b.next(); }
             }
         }
-        //If we inserted something into the tree, do the following:
-        if outcome {
-            match new_branch.clone() {
-                //If we have a new leaf node, integrate it into the current branch
-                //and return it, saying we have inserted a new element.
-                LeafNode(..) => {
-                    if index.unwrap() == self.elts.len() {
-                        self.rightmost_child = box new_branch;
-                    }
-                    else {
-                        self.elts.get_mut(index.unwrap()).left = box new_branch;
-                    }
-                    return (Node::new_branch(self.clone().elts,
-                                             self.clone().rightmost_child),
-                            true);
-                }
-                //If we have a new branch node, attempt to insert it into the tree
-                //as with the key-value pair, then check to see if the node is overfull.
-                BranchNode(branch) => {
-                    let new_elt = branch.elts[0].clone();
-                    let new_elt_index = self.bsearch_branch(new_elt.clone().key);
-                    match new_elt_index {
-                        None => {
-                            return (Node::new_branch(self.clone().elts,
-                                                     self.clone().rightmost_child),
-                                    false);
-                            }
-                        Some(i) => {
-                            self.elts.insert(i, new_elt);
-                            if i + 1 >= self.elts.len() {
-                                self.rightmost_child = branch.clone().rightmost_child;
-                            }
-                            else {
-                                self.elts.get_mut(i + 1).left =
-                                    branch.clone().rightmost_child;
-                            }
-                        }
-                    }
-                }
-            }
-            //If the current node is overfilled, create a new branch with one element
-            //and two children.
-            if self.elts.len() > ub {
-                let midpoint = self.elts.remove(ub / 2).unwrap();
-                let (new_left, new_right) = self.clone().elts.partition(|le|
-                                                                midpoint.key.cmp(&le.key)
-                                                                        == Greater);
-                new_branch = Node::new_branch(
-                    vec!(BranchElt::new(midpoint.clone().key,
-                                     midpoint.clone().value,
-                                     box Node::new_branch(new_left,
-                                                       midpoint.clone().left))),
-                    box Node::new_branch(new_right, self.clone().rightmost_child));
-                return (new_branch, true);
+    }
+}
+
+impl<'a, T: Ord> Iterator<&'a T> for UnionItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match cmp_opt(self.a.peek(), self.b.peek(), Greater, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.b.next(); return self.a.next() }
+                Greater => return self.b.next(),
             }
         }
-        (Node::new_branch(self.elts.clone(), self.rightmost_child.clone()), outcome)
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for Branch<K, V> {
-    /// Returns a new branch using the clone methods of the `Branch`'s internal variables.
-    fn clone(&self) -> Branch<K, V> {
-        Branch::new(self.elts.clone(), self.rightmost_child.clone())
-    }
+// A node holds its keys and values in two parallel sorted vectors. Internal
+// nodes also hold one more edge than they have keys, the subtree behind edge
+// `i` containing exactly the keys which sort between `keys[i - 1]` and
+// `keys[i]`. All leaves are at the same depth.
+#[deriving(Clone)]
+struct Node<K, V> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    // Empty for leaves
+    edges: Vec<Box<Node<K, V>>>,
 }
 
-impl<K: Ord, V: Eq> PartialEq for Branch<K, V> {
-    fn eq(&self, other: &Branch<K, V>) -> bool {
-        self.elts == other.elts
-    }
+enum SearchResult {
+    Found(uint),
+    GoDown(uint),
 }
 
-impl<K: Ord, V: Eq> Eq for Branch<K, V> {}
+enum InsertResult<K, V> {
+    // The insertion fitted within the node, possibly replacing a value
+    Fit(Option<V>),
+    // The node overflowed and was split around the returned key and value,
+    // with the returned node as its new right sibling
+    Split(K, V, Box<Node<K, V>>),
+}
 
-impl<K: Ord, V: Eq> PartialOrd for Branch<K, V> {
-    fn partial_cmp(&self, other: &Branch<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<K, V> Node<K, V> {
+    fn new_leaf() -> Node<K, V> {
+        Node {
+            keys: Vec::with_capacity(CAPACITY + 1),
+            vals: Vec::with_capacity(CAPACITY + 1),
+            edges: Vec::new(),
+        }
     }
-}
 
-impl<K: Ord, V: Eq> Ord for Branch<K, V> {
-    /// Compares the first elements of two `Branch`es to determine an
-    /// `Ordering`.
-    fn cmp(&self, other: &Branch<K, V>) -> Ordering {
-        if self.elts.len() > other.elts.len() {
-            return Greater;
+    fn new_internal() -> Node<K, V> {
+        Node {
+            keys: Vec::with_capacity(CAPACITY + 1),
+            vals: Vec::with_capacity(CAPACITY + 1),
+            edges: Vec::with_capacity(CAPACITY + 2),
         }
-        if self.elts.len() < other.elts.len() {
-            return Less;
+    }
+
+    #[inline]
+    fn is_leaf(&self) -> bool { self.edges.is_empty() }
+
+    // Splits an overfull node around its median key, which is returned along
+    // with the new right sibling holding everything after it.
+    fn split(&mut self) -> (K, V, Box<Node<K, V>>) {
+        let mid = self.keys.len() / 2;
+        let mut right = if self.is_leaf() {
+            box Node::new_leaf()
+        } else {
+            box Node::new_internal()
+        };
+        move_tail(&mut self.keys, mid + 1, &mut right.keys);
+        move_tail(&mut self.vals, mid + 1, &mut right.vals);
+        if !self.is_leaf() {
+            move_tail(&mut self.edges, mid + 1, &mut right.edges);
         }
-        self.elts[0].cmp(&other.elts[0])
+        (self.keys.pop().unwrap(), self.vals.pop().unwrap(), right)
     }
-}
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Branch<K, V> {
-    /// Returns a string representation of a `Branch`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, s) in self.elts.iter().enumerate() {
-            if i != 0 { try!(write!(f, " // ")) }
-            try!(write!(f, "{}", *s))
+    // Removes the greatest key in this subtree.
+    fn pop_max(&mut self) -> (K, V) {
+        if self.is_leaf() {
+            return (self.keys.pop().unwrap(), self.vals.pop().unwrap())
         }
-        write!(f, " // rightmost child: ({}) ", *self.rightmost_child)
+        let last = self.edges.len() - 1;
+        let ret = self.edges.get_mut(last).pop_max();
+        self.fix_child(last);
+        ret
     }
-}
 
-//A LeafElt contains no left child, but a key-value pair.
-struct LeafElt<K, V> {
-    key: K,
-    value: V
-}
+    // Restores the minimum size of the child behind edge `i` after a removal
+    // from it, by moving a key over from one of its siblings or, if they are
+    // both as small as they can be, merging it with one.
+    fn fix_child(&mut self, i: uint) {
+        if self.edges[i].keys.len() >= MIN_LEN { return }
+        if i > 0 && self.edges[i - 1].keys.len() > MIN_LEN {
+            self.steal_left(i);
+        } else if i + 1 < self.edges.len() &&
+                  self.edges[i + 1].keys.len() > MIN_LEN {
+            self.steal_right(i);
+        } else if i > 0 {
+            self.merge(i - 1);
+        } else {
+            self.merge(i);
+        }
+    }
 
-//A BranchElt has a left child in insertion to a key-value pair.
-struct BranchElt<K, V> {
-    left: Box<Node<K, V>>,
-    key: K,
-    value: V
-}
+    // Rotates the last key of child `i - 1` through this node into child `i`
+    fn steal_left(&mut self, i: uint) {
+        let (key, val, edge) = {
+            let left = self.edges.get_mut(i - 1);
+            (left.keys.pop().unwrap(), left.vals.pop().unwrap(), left.edges.pop())
+        };
+        let key = replace(self.keys.get_mut(i - 1), key);
+        let val = replace(self.vals.get_mut(i - 1), val);
+        let child = self.edges.get_mut(i);
+        child.keys.insert(0, key);
+        child.vals.insert(0, val);
+        match edge {
+            Some(edge) => child.edges.insert(0, edge),
+            None => {}
+        }
+    }
 
-impl<K: Ord, V> LeafElt<K, V> {
-    /// Creates a new `LeafElt` from a supplied key-value pair.
-    fn new(k: K, v: V) -> LeafElt<K, V> {
-        LeafElt {
-            key: k,
-            value: v
+    // Rotates the first key of child `i + 1` through this node into child `i`
+    fn steal_right(&mut self, i: uint) {
+        let (key, val, edge) = {
+            let right = self.edges.get_mut(i + 1);
+            (right.keys.remove(0).unwrap(), right.vals.remove(0).unwrap(),
+             right.edges.remove(0))
+        };
+        let key = replace(self.keys.get_mut(i), key);
+        let val = replace(self.vals.get_mut(i), val);
+        let child = self.edges.get_mut(i);
+        child.keys.push(key);
+        child.vals.push(val);
+        match edge {
+            Some(edge) => child.edges.push(edge),
+            None => {}
         }
     }
-}
 
-impl<K: Clone + Ord, V: Clone> Clone for LeafElt<K, V> {
-    /// Returns a new `LeafElt` by cloning the key and value.
-    fn clone(&self) -> LeafElt<K, V> {
-        LeafElt::new(self.key.clone(), self.value.clone())
+    // Merges child `i + 1`, along with the key separating the two, into
+    // child `i`
+    fn merge(&mut self, i: uint) {
+        let key = self.keys.remove(i).unwrap();
+        let val = self.vals.remove(i).unwrap();
+        let box Node { keys, vals, edges } = self.edges.remove(i + 1).unwrap();
+        let left = self.edges.get_mut(i);
+        left.keys.push(key);
+        left.vals.push(val);
+        left.keys.push_all_move(keys);
+        left.vals.push_all_move(vals);
+        left.edges.push_all_move(edges);
     }
-}
 
-impl<K: Ord, V: Eq> PartialEq for LeafElt<K, V> {
-    fn eq(&self, other: &LeafElt<K, V>) -> bool {
-        self.key == other.key && self.value == other.value
+    // Moves every key and value in this subtree onto `out`, in order.
+    fn flatten_into(self, out: &mut Vec<(K, V)>) {
+        let Node { keys, vals, edges } = self;
+        let mut edges = edges.move_iter();
+        for (key, val) in keys.move_iter().zip(vals.move_iter()) {
+            match edges.next() {
+                Some(box edge) => edge.flatten_into(out),
+                None => {}
+            }
+            out.push((key, val));
+        }
+        match edges.next() {
+            Some(box edge) => edge.flatten_into(out),
+            None => {}
+        }
     }
 }
 
-impl<K: Ord, V: Eq> Eq for LeafElt<K, V> {}
+impl<K: Ord, V> Node<K, V> {
+    fn search(&self, key: &K) -> SearchResult {
+        for (i, k) in self.keys.iter().enumerate() {
+            match key.cmp(k) {
+                Greater => {}
+                Equal => return Found(i),
+                Less => return GoDown(i),
+            }
+        }
+        GoDown(self.keys.len())
+    }
 
-impl<K: Ord, V: Eq> PartialOrd for LeafElt<K, V> {
-    fn partial_cmp(&self, other: &LeafElt<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+    // Returns the number of keys in this node which are less than `key` (or
+    // no greater than it, if `inclusive`), and whether `key` was found.
+    fn count_below(&self, key: &K, inclusive: bool) -> (uint, bool) {
+        match self.search(key) {
+            Found(i) if inclusive => (i + 1, true),
+            Found(i) => (i, true),
+            GoDown(i) => (i, false),
+        }
     }
-}
 
-impl<K: Ord, V: Eq> Ord for LeafElt<K, V> {
-    /// Returns an ordering based on the keys of the `LeafElt`s.
-    fn cmp(&self, other: &LeafElt<K, V>) -> Ordering {
-        self.key.cmp(&other.key)
+    fn find_mut<'a>(&'a mut self, key: &K) -> Option<&'a mut V> {
+        match self.search(key) {
+            Found(i) => Some(self.vals.get_mut(i)),
+            GoDown(_) if self.is_leaf() => None,
+            GoDown(i) => self.edges.get_mut(i).find_mut(key),
+        }
     }
-}
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for LeafElt<K, V> {
-    /// Returns a string representation of a `LeafElt`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Key: {}, value: {};", self.key, self.value)
+    fn insert(&mut self, key: K, val: V) -> InsertResult<K, V> {
+        let i = match self.search(&key) {
+            Found(i) => return Fit(Some(replace(self.vals.get_mut(i), val))),
+            GoDown(i) => i,
+        };
+        if self.is_leaf() {
+            self.keys.insert(i, key);
+            self.vals.insert(i, val);
+        } else {
+            match self.edges.get_mut(i).insert(key, val) {
+                Fit(ret) => return Fit(ret),
+                Split(key, val, right) => {
+                    self.keys.insert(i, key);
+                    self.vals.insert(i, val);
+                    self.edges.insert(i + 1, right);
+                }
+            }
+        }
+        if self.keys.len() <= CAPACITY { return Fit(None) }
+        let (key, val, right) = self.split();
+        Split(key, val, right)
     }
-}
 
-impl<K: Ord, V> BranchElt<K, V> {
-    /// Creates a new `BranchElt` from a supplied key, value, and left child.
-    fn new(k: K, v: V, n: Box<Node<K, V>>) -> BranchElt<K, V> {
-        BranchElt {
-            left: n,
-            key: k,
-            value: v
+    fn remove(&mut self, key: &K) -> Option<V> {
+        match self.search(key) {
+            Found(i) if self.is_leaf() => {
+                self.keys.remove(i);
+                self.vals.remove(i)
+            }
+            Found(i) => {
+                // Replace the key with its predecessor, which is in a leaf
+                let (k, v) = self.edges.get_mut(i).pop_max();
+                *self.keys.get_mut(i) = k;
+                let ret = replace(self.vals.get_mut(i), v);
+                self.fix_child(i);
+                Some(ret)
+            }
+            GoDown(_) if self.is_leaf() => None,
+            GoDown(i) => {
+                let ret = self.edges.get_mut(i).remove(key);
+                if ret.is_some() { self.fix_child(i) }
+                ret
+            }
         }
     }
 }
 
+// Moves the elements of `from` at index `at` and beyond onto the end of `to`.
+fn move_tail<T>(from: &mut Vec<T>, at: uint, to: &mut Vec<T>) {
+    let start = to.len();
+    while from.len() > at {
+        to.push(from.pop().unwrap());
+    }
+    to.mut_slice_from(start).reverse();
+}
 
-impl<K: Clone + Ord, V: Clone> Clone for BranchElt<K, V> {
-    /// Returns a new `BranchElt` by cloning the key, value, and left child.
-    fn clone(&self) -> BranchElt<K, V> {
-        BranchElt::new(self.key.clone(),
-                       self.value.clone(),
-                       self.left.clone())
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+    fn from_iter<T: Iterator<(K, V)>>(iter: T) -> BTreeMap<K, V> {
+        let mut map = BTreeMap::new();
+        map.extend(iter);
+        map
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for BranchElt<K, V>{
-    fn eq(&self, other: &BranchElt<K, V>) -> bool {
-        self.key == other.key && self.value == other.value
+impl<K: Ord, V> Extendable<(K, V)> for BTreeMap<K, V> {
+    #[inline]
+    fn extend<T: Iterator<(K, V)>>(&mut self, mut iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
     }
 }
 
-impl<K: Ord, V: Eq> Eq for BranchElt<K, V>{}
+impl<S: Writer, K: Ord + Hash<S>, V: Hash<S>> Hash<S> for BTreeMap<K, V> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
 
-impl<K: Ord, V: Eq> PartialOrd for BranchElt<K, V> {
-    fn partial_cmp(&self, other: &BranchElt<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<T: Ord> FromIterator<T> for BTreeSet<T> {
+    fn from_iter<Iter: Iterator<T>>(iter: Iter) -> BTreeSet<T> {
+        let mut set = BTreeSet::new();
+        set.extend(iter);
+        set
     }
 }
 
-impl<K: Ord, V: Eq> Ord for BranchElt<K, V> {
-    /// Fulfills `Ord` for `BranchElts`.
-    fn cmp(&self, other: &BranchElt<K, V>) -> Ordering {
-        self.key.cmp(&other.key)
+impl<T: Ord> Extendable<T> for BTreeSet<T> {
+    #[inline]
+    fn extend<Iter: Iterator<T>>(&mut self, mut iter: Iter) {
+        for elem in iter {
+            self.insert(elem);
+        }
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for BranchElt<K, V> {
-    /// Formats as a string containing the key, value, and child (which should recur to a
-    /// leaf). Consider changing in future to be more readable.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Key: {}, value: {}, (child: {})",
-               self.key, self.value, *self.left)
+impl<S: Writer, T: Ord + Hash<S>> Hash<S> for BTreeSet<T> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
     }
 }
 
 #[cfg(test)]
 mod test_btree {
     use std::prelude::*;
+    use std::rand::Rng;
+    use std::rand;
+
+    use {Map, MutableMap, Mutable};
+    use {Bound, Included, Excluded, Unbounded};
+    use treemap::TreeMap;
+    use super::{BTreeMap, Node, CAPACITY, MIN_LEN};
+
+    // Checks the structure of a subtree, returning its depth and size
+    fn check_node<K: Ord, V>(node: &Node<K, V>, root: bool) -> (uint, uint) {
+        assert_eq!(node.keys.len(), node.vals.len());
+        assert!(node.keys.len() <= CAPACITY);
+        if !root { assert!(node.keys.len() >= MIN_LEN) }
+        for pair in node.keys.as_slice().windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+        if node.is_leaf() { return (1, node.keys.len()) }
+
+        assert_eq!(node.edges.len(), node.keys.len() + 1);
+        let mut depth = None;
+        let mut size = node.keys.len();
+        for (i, edge) in node.edges.iter().enumerate() {
+            if i > 0 { assert!(edge.keys[0] > node.keys[i - 1]) }
+            if i < node.keys.len() { assert!(*edge.keys.last().unwrap() < node.keys[i]) }
+            let (d, s) = check_node(&**edge, false);
+            assert!(depth.is_none() || depth == Some(d));
+            depth = Some(d);
+            size += s;
+        }
+        (depth.unwrap() + 1, size)
+    }
 
-    use super::{BTree, Node, LeafElt};
+    fn check<K: Ord, V>(map: &BTreeMap<K, V>) {
+        let (_, size) = check_node(&map.root, true);
+        assert_eq!(size, map.len());
+    }
 
-    use MutableSeq;
+    #[test]
+    fn find_empty() {
+        let m: BTreeMap<int,int> = BTreeMap::new();
+        assert!(m.find(&5) == None);
+    }
 
-    //Tests the functionality of the insert methods (which are unfinished).
     #[test]
-    fn insert_test_one() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let is_insert = b.insert(2i, "xyz".to_string());
-        assert!(is_insert.root.is_leaf());
+    fn find_not_found() {
+        let mut m = BTreeMap::new();
+        assert!(m.insert(1i, 2i));
+        assert!(m.insert(5i, 3i));
+        assert!(m.insert(9i, 3i));
+        assert_eq!(m.find(&2), None);
     }
 
     #[test]
-    fn insert_test_two() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        //println!("{}", b.clone().insert(4, "ddd".to_string()).to_string());
-        assert!(b.insert(4, "ddd".to_string()).root.is_leaf());
+    fn test_find_mut() {
+        let mut m = BTreeMap::new();
+        for i in range(0i, 100) { assert!(m.insert(i, i)); }
+        *m.find_mut(&57).unwrap() = 1000;
+        assert_eq!(m.find(&57), Some(&1000));
+        assert_eq!(m.find_mut(&100), None);
     }
 
     #[test]
-    fn insert_test_three() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(4i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        //println!("{}", b.clone().insert(5, "eee".to_string()).to_string());
-        assert!(!b.insert(5, "eee".to_string()).root.is_leaf());
+    fn test_swap_and_pop() {
+        let mut m = BTreeMap::new();
+        assert_eq!(m.swap(1u, 2u), None);
+        assert_eq!(m.swap(1u, 3u), Some(2));
+        assert_eq!(m.len(), 1);
+        assert_eq!(m.pop(&1), Some(3));
+        assert_eq!(m.pop(&1), None);
+        assert!(m.is_empty());
     }
 
     #[test]
-    fn insert_test_four() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(4i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let mut b = BTree::new_with_node_len(n, 3, 2);
-        b = b.clone().insert(5, "eee".to_string());
-        b = b.clone().insert(6, "fff".to_string());
-        b = b.clone().insert(7, "ggg".to_string());
-        b = b.clone().insert(8, "hhh".to_string());
-        b = b.clone().insert(0, "omg".to_string());
-        //println!("{}", b.clone().to_string());
-        assert!(!b.root.is_leaf());
+    fn test_sequential() {
+        let n = 10000u;
+        let mut m = BTreeMap::new();
+        for i in range(0, n) { assert!(m.insert(i, i * 2)); }
+        check(&m);
+        for i in range(0, n) { assert_eq!(m.find(&i), Some(&(i * 2))); }
+        for i in range(0, n).rev() {
+            if i % 3 == 0 { assert_eq!(m.pop(&i), Some(i * 2)); }
+        }
+        check(&m);
+        for i in range(0, n) {
+            assert_eq!(m.contains_key(&i), i % 3 != 0);
+        }
+        m.clear();
+        check(&m);
+        assert!(m.iter().next().is_none());
     }
 
     #[test]
-    fn bsearch_test_one() {
-        let b = BTree::new(1i, "abc".to_string(), 2u);
-        assert_eq!(Some(1), b.root.bsearch_node(2));
+    fn test_rand_against_treemap() {
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(&[42u32]);
+        let mut btree = BTreeMap::new();
+        let mut tree = TreeMap::new();
+        for i in range(0u, 20000) {
+            let k = rng.gen_range(0u, 2000);
+            if rng.gen() {
+                assert_eq!(btree.swap(k, i), tree.swap(k, i));
+            } else {
+                assert_eq!(btree.pop(&k), tree.pop(&k));
+            }
+            if i % 1000 == 0 { check(&btree) }
+        }
+        check(&btree);
+        assert_eq!(btree.len(), tree.len());
+        assert!(btree.iter().zip(tree.iter()).all(|(a, b)| a == b));
+        assert!(btree.iter().rev().zip(tree.rev_iter()).all(|(a, b)| a == b));
     }
 
     #[test]
-    fn bsearch_test_two() {
-        let b = BTree::new(1i, "abc".to_string(), 2u);
-        assert_eq!(Some(0), b.root.bsearch_node(0));
+    fn test_iterator_both_ends() {
+        let m: BTreeMap<uint, uint> = range(0u, 1000).map(|i| (i, i)).collect();
+        let mut it = m.iter();
+        assert_eq!(it.size_hint(), (1000, Some(1000)));
+        let (mut lo, mut hi) = (0u, 1000u);
+        // Alternate ends irregularly until they meet in the middle
+        loop {
+            match it.next() {
+                Some((&k, _)) => { assert_eq!(k, lo); lo += 1; }
+                None => break,
+            }
+            for _ in range(0u, lo % 3) {
+                match it.next_back() {
+                    Some((&k, _)) => { hi -= 1; assert_eq!(k, hi); }
+                    None => break,
+                }
+            }
+        }
+        assert_eq!(lo, hi);
+        assert!(it.next_back().is_none());
+        assert_eq!(it.size_hint(), (0, Some(0)));
     }
 
     #[test]
-    fn bsearch_test_three() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(4i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(5i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        assert_eq!(Some(2), b.root.bsearch_node(3));
+    fn test_mut_iter() {
+        let mut m: BTreeMap<uint, uint> = range(0u, 500).map(|i| (i, i)).collect();
+        for (k, v) in m.mut_iter().rev() {
+            *v += *k;
+        }
+        for (k, v) in m.iter() {
+            assert_eq!(*v, *k * 2);
+        }
     }
 
     #[test]
-    fn bsearch_test_four() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(4i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(5i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        assert_eq!(Some(4), b.root.bsearch_node(800));
+    fn test_move_iter() {
+        let m: BTreeMap<uint, uint> = range(0u, 500).map(|i| (i, i + 1)).collect();
+        let v: Vec<(uint, uint)> = m.clone().move_iter().collect();
+        assert_eq!(v, range(0u, 500).map(|i| (i, i + 1)).collect());
+        let v: Vec<(uint, uint)> = m.move_iter().rev().collect();
+        assert_eq!(v, range(0u, 500).rev().map(|i| (i, i + 1)).collect());
+    }
+
+    fn range_keys(m: &BTreeMap<uint, ()>, min: Bound<&uint>, max: Bound<&uint>)
+                  -> Vec<uint> {
+        m.range(min, max).map(|(&k, _)| k).collect()
+    }
+
+    fn evens(lo: uint, hi: uint) -> Vec<uint> {
+        range(lo, hi).filter(|k| k % 2 == 0).collect()
     }
 
-    //Tests the functionality of the get method.
     #[test]
-    fn get_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let val = b.get(1);
-        assert_eq!(val, Some("abc".to_string()));
+    fn test_range() {
+        // Even keys only, so that bounds fall both on and between keys
+        let m: BTreeMap<uint, ()> = range(0u, 200).map(|i| (i * 2, ())).collect();
+
+        assert_eq!(range_keys(&m, Unbounded, Unbounded), evens(0, 400));
+        assert_eq!(range_keys(&m, Included(&10), Included(&20)), evens(10, 21));
+        assert_eq!(range_keys(&m, Included(&11), Included(&21)), evens(11, 22));
+        assert_eq!(range_keys(&m, Excluded(&10), Excluded(&20)), evens(11, 20));
+        assert_eq!(range_keys(&m, Excluded(&11), Excluded(&21)), evens(11, 21));
+        assert_eq!(range_keys(&m, Unbounded, Excluded(&100)), evens(0, 100));
+        assert_eq!(range_keys(&m, Included(&300), Unbounded), evens(300, 400));
+        assert_eq!(range_keys(&m, Included(&10), Included(&10)), vec![10]);
+        assert_eq!(range_keys(&m, Included(&10), Excluded(&10)), vec![]);
+        assert_eq!(range_keys(&m, Included(&11), Included(&11)), vec![]);
+        assert_eq!(range_keys(&m, Included(&20), Included(&10)), vec![]);
+        assert_eq!(range_keys(&m, Included(&1000), Unbounded), vec![]);
+        assert_eq!(range_keys(&m, Unbounded, Excluded(&0)), vec![]);
+
+        let rev: Vec<uint> = m.range(Included(&50), Excluded(&150))
+                              .rev().map(|(&k, _)| k).collect();
+        assert_eq!(rev, evens(50, 150).move_iter().rev().collect());
+
+        // Every combination of bounds over a smaller map
+        let m: BTreeMap<uint, ()> = range(0u, 60).map(|i| (i * 2, ())).collect();
+        for lo in range(0u, 122) {
+            for hi in range(0u, 122) {
+                let got: Vec<uint> = m.range(Included(&lo), Excluded(&hi))
+                                      .map(|(&k, _)| k).collect();
+                let want: Vec<uint> = m.keys().map(|&k| k)
+                                       .filter(|&k| lo <= k && k < hi).collect();
+                assert_eq!(got, want);
+            }
+        }
     }
 
-    //Tests the BTree's clone() method.
     #[test]
-    fn btree_clone_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = b.clone();
-        assert!(b.root == b2.root)
+    fn test_mut_range() {
+        let mut m: BTreeMap<uint, uint> = range(0u, 100).map(|i| (i, 0)).collect();
+        for (_, v) in m.mut_range(Excluded(&20), Included(&30)) {
+            *v = 1;
+        }
+        for (&k, &v) in m.iter() {
+            assert_eq!(v, if k > 20 && k <= 30 { 1 } else { 0 });
+        }
     }
 
-    //Tests the BTree's cmp() method when one node is "less than" another.
     #[test]
-    fn btree_cmp_test_less() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(2i, "bcd".to_string(), 2);
-        assert!(&b.cmp(&b2) == &Less)
+    fn test_eq_and_clone() {
+        let mut a = BTreeMap::new();
+        for i in range(0i, 100) { a.insert(i, i); }
+        let mut b = a.clone();
+        assert!(a == b);
+        b.insert(100, 100);
+        assert!(a != b);
+        assert!(a < b);
+        b.pop(&100);
+        *b.find_mut(&50).unwrap() = 0;
+        assert!(a != b);
     }
 
-    //Tests the BTree's cmp() method when two nodes are equal.
     #[test]
-    fn btree_cmp_test_eq() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(1i, "bcd".to_string(), 2);
-        assert!(&b.cmp(&b2) == &Equal)
+    fn test_show() {
+        let mut map = BTreeMap::new();
+        let empty: BTreeMap<int, int> = BTreeMap::new();
+
+        map.insert(1i, 2i);
+        map.insert(3i, 4i);
+
+        let map_str = format!("{}", map);
+
+        assert!(map_str == "{1: 2, 3: 4}".to_string());
+        assert_eq!(format!("{}", empty), "{}".to_string());
     }
 
-    //Tests the BTree's cmp() method when one node is "greater than" another.
     #[test]
-    fn btree_cmp_test_greater() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(2i, "bcd".to_string(), 2);
-        assert!(&b2.cmp(&b) == &Greater)
+    fn test_index() {
+        let mut map = BTreeMap::new();
+        map.insert(1i, 2i);
+        assert_eq!(map[1], 2);
     }
 
-    //Tests the BTree's to_string() method.
     #[test]
-    fn btree_tostr_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        assert_eq!(b.to_string(), "Key: 1, value: abc;".to_string())
+    #[should_fail]
+    fn test_index_nonexistent() {
+        let mut map = BTreeMap::new();
+        map.insert(1i, 2i);
+        map[4];
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use test::Bencher;
+
+    use super::BTreeMap;
+    use deque::bench::{insert_rand_n, insert_seq_n, find_rand_n, find_seq_n};
+
+    #[bench]
+    pub fn insert_rand_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_rand_n(100, &mut m, b);
     }
 
+    #[bench]
+    pub fn insert_rand_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_rand_n(10_000, &mut m, b);
+    }
+
+    #[bench]
+    pub fn insert_seq_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_seq_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn insert_seq_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_seq_n(10_000, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_rand_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_rand_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_rand_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_rand_n(10_000, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_seq_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_seq_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_seq_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_seq_n(10_000, &mut m, b);
+    }
+}
+
+#[cfg(test)]
+mod test_set {
+    use std::prelude::*;
+
+    use {Set, MutableSet, Mutable};
+    use {Included, Excluded};
+    use super::BTreeSet;
+
+    #[test]
+    fn test_basic() {
+        let mut s = BTreeSet::new();
+        for i in range(0i, 1000) { assert!(s.insert(i)); }
+        assert!(!s.insert(500));
+        assert!(s.contains(&999));
+        assert!(s.remove(&999));
+        assert!(!s.contains(&999));
+        assert_eq!(s.len(), 999);
+        s.clear();
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_iter_and_range() {
+        let s: BTreeSet<int> = range(0i, 100).rev().collect();
+        let v: Vec<int> = s.iter().map(|&x| x).collect();
+        assert_eq!(v, range(0i, 100).collect());
+        let v: Vec<int> = s.iter().rev().map(|&x| x).collect();
+        assert_eq!(v, range(0i, 100).rev().collect());
+        let v: Vec<int> = s.range(Included(&10), Excluded(&13)).map(|&x| x).collect();
+        assert_eq!(v, vec![10, 11, 12]);
+        let v: Vec<int> = s.move_iter().take(3).collect();
+        assert_eq!(v, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: BTreeSet<int> = [1i, 3, 5, 9, 11].iter().map(|&x| x).collect();
+        let b: BTreeSet<int> = [3i, 9, 14, 22].iter().map(|&x| x).collect();
+
+        let v: Vec<int> = a.intersection(&b).map(|&x| x).collect();
+        assert_eq!(v, vec![3, 9]);
+        let v: Vec<int> = a.difference(&b).map(|&x| x).collect();
+        assert_eq!(v, vec![1, 5, 11]);
+        let v: Vec<int> = a.symmetric_difference(&b).map(|&x| x).collect();
+        assert_eq!(v, vec![1, 5, 11, 14, 22]);
+        let v: Vec<int> = a.union(&b).map(|&x| x).collect();
+        assert_eq!(v, vec![1, 3, 5, 9, 11, 14, 22]);
+    }
+
+    #[test]
+    fn test_subset_and_disjoint() {
+        let a: BTreeSet<int> = [1i, 3, 5].iter().map(|&x| x).collect();
+        let b: BTreeSet<int> = range(0i, 10).collect();
+        let c: BTreeSet<int> = [2i, 4].iter().map(|&x| x).collect();
+        assert!(a.is_subset(&b));
+        assert!(b.is_superset(&a));
+        assert!(!b.is_subset(&a));
+        assert!(a.is_disjoint(&c));
+        assert!(!b.is_disjoint(&c));
+    }
+
+    #[test]
+    fn test_show() {
+        let mut set: BTreeSet<int> = BTreeSet::new();
+        let empty: BTreeSet<int> = BTreeSet::new();
+
+        set.insert(1);
+        set.insert(2);
+
+        let set_str = format!("{}", set);
+
+        assert!(set_str == "{1, 2}".to_string());
+        assert_eq!(format!("{}", empty), "{}".to_string());
+    }
 }
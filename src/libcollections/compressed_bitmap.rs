@@ -0,0 +1,771 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A compressed bitmap, for sets of `u32` which are too large or too sparse
+//! to be stored as a `BitvSet`.
+//!
+//! The values are split into chunks by their upper 16 bits, and each chunk
+//! stores its lower 16 bits either as a sorted array, when it has few values,
+//! or as a plain bitmap of 2^16 bits, when it has many. This is the layout
+//! of "roaring" bitmaps: a chunk never takes more than 8 KiB, and a chunk
+//! holding a single value takes a few bytes.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::CompressedBitmap;
+//!
+//! let evens: CompressedBitmap = range(0u32, 1000000).filter(|&x| x % 2 == 0).collect();
+//! let squares: CompressedBitmap = range(0u32, 1000).map(|x| x * x).collect();
+//!
+//! let even_squares = evens.intersection(&squares);
+//! assert_eq!(even_squares.len(), 500);
+//! assert!(even_squares.contains(&4));
+//! assert!(!even_squares.contains(&9));
+//! ```
+
+use core::prelude::*;
+
+use core::default::Default;
+use core::fmt;
+use core::slice;
+use std::hash;
+
+use {Mutable, Set, MutableSet, MutableSeq};
+use vec::Vec;
+
+// Chunks with more values than this are stored as bitmaps, as that takes less
+// space than an array
+static ARRAY_MAX: uint = 4096;
+static BITMAP_WORDS: uint = 1024;
+
+macro_rules! try_opt(
+    ($e:expr) => (match $e { Some(e) => e, None => return None })
+)
+
+/// A compressed set of `u32` values.
+///
+/// Besides the usual set operations, two bitmaps can be combined with
+/// `union`, `intersection`, `difference` and `symmetric_difference`, which
+/// work a chunk at a time and skip over the chunks only one side has.
+///
+/// Bitmaps can be serialized with `to_bytes`, and with libserialize.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct CompressedBitmap {
+    // Sorted by key, and none of them are empty
+    chunks: Vec<Chunk>,
+}
+
+#[deriving(Clone, PartialEq, Eq)]
+struct Chunk {
+    key: u16,
+    container: Container,
+}
+
+// A container is an array if and only if it has at most ARRAY_MAX values, so
+// the representation of a set is unique
+#[deriving(Clone, PartialEq, Eq)]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap(Vec<u64>, uint),
+}
+
+enum Op {
+    Union,
+    Intersection,
+    Difference,
+    SymmetricDifference,
+}
+
+impl Op {
+    // Whether a value in a, b or both is in the result
+    fn keep(&self, in_a: bool, in_b: bool) -> bool {
+        match *self {
+            Union => in_a || in_b,
+            Intersection => in_a && in_b,
+            Difference => in_a && !in_b,
+            SymmetricDifference => in_a != in_b,
+        }
+    }
+
+    fn word(&self, a: u64, b: u64) -> u64 {
+        match *self {
+            Union => a | b,
+            Intersection => a & b,
+            Difference => a & !b,
+            SymmetricDifference => a ^ b,
+        }
+    }
+}
+
+fn split(value: u32) -> (u16, u16) {
+    ((value >> 16) as u16, value as u16)
+}
+
+impl Container {
+    fn from_sorted(values: Vec<u16>) -> Container {
+        if values.len() <= ARRAY_MAX {
+            Array(values)
+        } else {
+            let mut words = Vec::from_elem(BITMAP_WORDS, 0u64);
+            for &v in values.iter() {
+                *words.get_mut(v as uint / 64) |= 1 << (v as uint % 64);
+            }
+            Bitmap(words, values.len())
+        }
+    }
+
+    fn from_words(words: Vec<u64>) -> Container {
+        let len = words.iter().fold(0, |acc, &w| acc + w.count_ones() as uint);
+        if len > ARRAY_MAX {
+            Bitmap(words, len)
+        } else {
+            Array(WordBits::new(words.as_slice()).collect())
+        }
+    }
+
+    fn len(&self) -> uint {
+        match *self {
+            Array(ref values) => values.len(),
+            Bitmap(_, len) => len,
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match *self {
+            Array(ref values) => values.as_slice().binary_search_elem(&low).found().is_some(),
+            Bitmap(ref words, _) => words[low as uint / 64] & (1 << (low as uint % 64)) != 0,
+        }
+    }
+
+    fn insert(&mut self, low: u16) -> bool {
+        let grown = match *self {
+            Array(ref mut values) => {
+                match values.as_slice().binary_search_elem(&low) {
+                    slice::Found(_) => return false,
+                    slice::NotFound(i) => values.insert(i, low),
+                }
+                if values.len() <= ARRAY_MAX { return true }
+                values.clone()
+            }
+            Bitmap(ref mut words, ref mut len) => {
+                let (word, bit) = (low as uint / 64, 1 << (low as uint % 64));
+                if words[word] & bit != 0 { return false }
+                *words.get_mut(word) |= bit;
+                *len += 1;
+                return true
+            }
+        };
+        *self = Container::from_sorted(grown);
+        true
+    }
+
+    fn remove(&mut self, low: u16) -> bool {
+        let shrunk: Vec<u16> = match *self {
+            Array(ref mut values) => {
+                return match values.as_slice().binary_search_elem(&low) {
+                    slice::Found(i) => { values.remove(i); true }
+                    slice::NotFound(_) => false,
+                }
+            }
+            Bitmap(ref mut words, ref mut len) => {
+                let (word, bit) = (low as uint / 64, 1 << (low as uint % 64));
+                if words[word] & bit == 0 { return false }
+                *words.get_mut(word) &= !bit;
+                *len -= 1;
+                if *len > ARRAY_MAX { return true }
+                WordBits::new(words.as_slice()).collect()
+            }
+        };
+        *self = Array(shrunk);
+        true
+    }
+
+    fn to_words(&self) -> Vec<u64> {
+        match *self {
+            Array(ref values) => {
+                let mut words = Vec::from_elem(BITMAP_WORDS, 0u64);
+                for &v in values.iter() {
+                    *words.get_mut(v as uint / 64) |= 1 << (v as uint % 64);
+                }
+                words
+            }
+            Bitmap(ref words, _) => words.clone(),
+        }
+    }
+
+    fn combine(&self, other: &Container, op: Op) -> Container {
+        match (self, other, op) {
+            (&Array(ref a), &Array(ref b), _) => {
+                Container::from_sorted(merge(a.as_slice(), b.as_slice(), op))
+            }
+            // Values can only be taken away from `a`, so it stays an array
+            (&Array(ref a), _, Intersection) | (&Array(ref a), _, Difference) => {
+                Array(a.iter().map(|&v| v)
+                       .filter(|&v| op.keep(true, other.contains(v)))
+                       .collect())
+            }
+            (_, &Array(ref b), Intersection) => {
+                Array(b.iter().map(|&v| v).filter(|&v| self.contains(v)).collect())
+            }
+            _ => {
+                let mut words = self.to_words();
+                for (a, &b) in words.mut_iter().zip(other.to_words().iter()) {
+                    *a = op.word(*a, b);
+                }
+                Container::from_words(words)
+            }
+        }
+    }
+
+    fn iter<'a>(&'a self) -> ContainerItems<'a> {
+        match *self {
+            Array(ref values) => ArrayItems(values.iter()),
+            Bitmap(ref words, _) => BitmapItems(WordBits::new(words.as_slice())),
+        }
+    }
+}
+
+// Merges two sorted arrays, keeping the values selected by `op`
+fn merge(a: &[u16], b: &[u16], op: Op) -> Vec<u16> {
+    let mut ret = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let ord = if i == a.len() {
+            Greater
+        } else if j == b.len() {
+            Less
+        } else {
+            a[i].cmp(&b[j])
+        };
+        match ord {
+            Less => { if op.keep(true, false) { ret.push(a[i]) } i += 1; }
+            Greater => { if op.keep(false, true) { ret.push(b[j]) } j += 1; }
+            Equal => { if op.keep(true, true) { ret.push(a[i]) } i += 1; j += 1; }
+        }
+    }
+    ret
+}
+
+impl CompressedBitmap {
+    /// Creates an empty `CompressedBitmap`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    /// let mut bitmap = CompressedBitmap::new();
+    /// ```
+    pub fn new() -> CompressedBitmap {
+        CompressedBitmap { chunks: Vec::new() }
+    }
+
+    fn find_chunk(&self, key: u16) -> slice::BinarySearchResult {
+        self.chunks.as_slice().binary_search(|chunk| chunk.key.cmp(&key))
+    }
+
+    fn combine(&self, other: &CompressedBitmap, op: Op) -> CompressedBitmap {
+        let (a, b) = (self.chunks.as_slice(), other.chunks.as_slice());
+        let mut chunks = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            let ord = if i == a.len() {
+                Greater
+            } else if j == b.len() {
+                Less
+            } else {
+                a[i].key.cmp(&b[j].key)
+            };
+            match ord {
+                Less => { if op.keep(true, false) { chunks.push(a[i].clone()) } i += 1; }
+                Greater => { if op.keep(false, true) { chunks.push(b[j].clone()) } j += 1; }
+                Equal => {
+                    let container = a[i].container.combine(&b[j].container, op);
+                    if container.len() > 0 {
+                        chunks.push(Chunk { key: a[i].key, container: container });
+                    }
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        CompressedBitmap { chunks: chunks }
+    }
+
+    /// Returns the union of `self` and `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    ///
+    /// let a: CompressedBitmap = vec![1u32, 2, 3].move_iter().collect();
+    /// let b: CompressedBitmap = vec![3u32, 100000].move_iter().collect();
+    /// let u = a.union(&b);
+    /// assert_eq!(u.iter().collect::<Vec<u32>>(), vec![1, 2, 3, 100000]);
+    /// ```
+    pub fn union(&self, other: &CompressedBitmap) -> CompressedBitmap {
+        self.combine(other, Union)
+    }
+
+    /// Returns the intersection of `self` and `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    ///
+    /// let a: CompressedBitmap = vec![1u32, 2, 3].move_iter().collect();
+    /// let b: CompressedBitmap = vec![3u32, 100000].move_iter().collect();
+    /// let i = a.intersection(&b);
+    /// assert_eq!(i.iter().collect::<Vec<u32>>(), vec![3]);
+    /// ```
+    pub fn intersection(&self, other: &CompressedBitmap) -> CompressedBitmap {
+        self.combine(other, Intersection)
+    }
+
+    /// Returns the values of `self` which aren't in `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    ///
+    /// let a: CompressedBitmap = vec![1u32, 2, 3].move_iter().collect();
+    /// let b: CompressedBitmap = vec![3u32, 100000].move_iter().collect();
+    /// let d = a.difference(&b);
+    /// assert_eq!(d.iter().collect::<Vec<u32>>(), vec![1, 2]);
+    /// ```
+    pub fn difference(&self, other: &CompressedBitmap) -> CompressedBitmap {
+        self.combine(other, Difference)
+    }
+
+    /// Returns the values which are in either `self` or `other`, but not in
+    /// both.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    ///
+    /// let a: CompressedBitmap = vec![1u32, 2, 3].move_iter().collect();
+    /// let b: CompressedBitmap = vec![3u32, 100000].move_iter().collect();
+    /// let s = a.symmetric_difference(&b);
+    /// assert_eq!(s.iter().collect::<Vec<u32>>(), vec![1, 2, 100000]);
+    /// ```
+    pub fn symmetric_difference(&self, other: &CompressedBitmap) -> CompressedBitmap {
+        self.combine(other, SymmetricDifference)
+    }
+
+    /// Unions in-place with the specified other bitmap.
+    #[inline]
+    pub fn union_with(&mut self, other: &CompressedBitmap) {
+        *self = self.union(other);
+    }
+
+    /// Intersects in-place with the specified other bitmap.
+    #[inline]
+    pub fn intersect_with(&mut self, other: &CompressedBitmap) {
+        *self = self.intersection(other);
+    }
+
+    /// Makes this bitmap the difference with the specified other bitmap
+    /// in-place.
+    #[inline]
+    pub fn difference_with(&mut self, other: &CompressedBitmap) {
+        *self = self.difference(other);
+    }
+
+    /// Makes this bitmap the symmetric difference with the specified other
+    /// bitmap in-place.
+    #[inline]
+    pub fn symmetric_difference_with(&mut self, other: &CompressedBitmap) {
+        *self = self.symmetric_difference(other);
+    }
+
+    /// Returns an iterator over the values of the bitmap, in ascending order.
+    pub fn iter<'a>(&'a self) -> Items<'a> {
+        Items { chunks: self.chunks.iter(), current: None }
+    }
+
+    /// Serializes the bitmap to bytes, in a format which doesn't depend on
+    /// the platform. `from_bytes` reads it back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::CompressedBitmap;
+    ///
+    /// let bitmap: CompressedBitmap = range(0u32, 10).collect();
+    /// let bytes = bitmap.to_bytes();
+    /// assert_eq!(CompressedBitmap::from_bytes(bytes.as_slice()), Some(bitmap));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // All integers are little-endian. The number of chunks is followed
+        // by each chunk's key and kind, and then either the number of values
+        // minus one and the values, or the 1024 words of the bitmap.
+        let mut bytes = Vec::new();
+        write_le(&mut bytes, self.chunks.len() as u64, 4);
+        for chunk in self.chunks.iter() {
+            write_le(&mut bytes, chunk.key as u64, 2);
+            match chunk.container {
+                Array(ref values) => {
+                    bytes.push(0);
+                    write_le(&mut bytes, (values.len() - 1) as u64, 2);
+                    for &v in values.iter() { write_le(&mut bytes, v as u64, 2) }
+                }
+                Bitmap(ref words, _) => {
+                    bytes.push(1);
+                    for &w in words.iter() { write_le(&mut bytes, w, 8) }
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Deserializes a bitmap serialized by `to_bytes`, returning `None` if
+    /// the bytes are not a valid bitmap.
+    pub fn from_bytes(bytes: &[u8]) -> Option<CompressedBitmap> {
+        let mut r = Reader { bytes: bytes, pos: 0 };
+        let nchunks = try_opt!(r.read(4)) as uint;
+        let mut chunks: Vec<Chunk> = Vec::new();
+        for _ in range(0, nchunks) {
+            let key = try_opt!(r.read(2)) as u16;
+            if chunks.last().map_or(false, |c| c.key >= key) { return None }
+            let container = match try_opt!(r.read(1)) {
+                0 => {
+                    let len = try_opt!(r.read(2)) as uint + 1;
+                    let mut values: Vec<u16> = Vec::with_capacity(len);
+                    for _ in range(0, len) {
+                        let v = try_opt!(r.read(2)) as u16;
+                        if values.last().map_or(false, |&last| last >= v) { return None }
+                        values.push(v);
+                    }
+                    Container::from_sorted(values)
+                }
+                1 => {
+                    let mut words = Vec::with_capacity(BITMAP_WORDS);
+                    for _ in range(0, BITMAP_WORDS) {
+                        words.push(try_opt!(r.read(8)));
+                    }
+                    let container = Container::from_words(words);
+                    if container.len() == 0 { return None }
+                    container
+                }
+                _ => return None,
+            };
+            chunks.push(Chunk { key: key, container: container });
+        }
+        if r.pos != bytes.len() { return None }
+        Some(CompressedBitmap { chunks: chunks })
+    }
+}
+
+fn write_le(bytes: &mut Vec<u8>, n: u64, size: uint) {
+    for i in range(0, size) {
+        bytes.push((n >> (8 * i)) as u8);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, size: uint) -> Option<u64> {
+        if self.bytes.len() - self.pos < size { return None }
+        let mut n = 0;
+        for i in range(0, size) {
+            n |= (self.bytes[self.pos + i] as u64) << (8 * i);
+        }
+        self.pos += size;
+        Some(n)
+    }
+}
+
+impl Default for CompressedBitmap {
+    #[inline]
+    fn default() -> CompressedBitmap { CompressedBitmap::new() }
+}
+
+impl Collection for CompressedBitmap {
+    fn len(&self) -> uint {
+        self.chunks.iter().fold(0, |acc, chunk| acc + chunk.container.len())
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool { self.chunks.is_empty() }
+}
+
+impl Mutable for CompressedBitmap {
+    #[inline]
+    fn clear(&mut self) {
+        self.chunks.clear()
+    }
+}
+
+impl Set<u32> for CompressedBitmap {
+    fn contains(&self, value: &u32) -> bool {
+        let (key, low) = split(*value);
+        match self.find_chunk(key) {
+            slice::Found(i) => self.chunks[i].container.contains(low),
+            slice::NotFound(_) => false,
+        }
+    }
+
+    fn is_disjoint(&self, other: &CompressedBitmap) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    fn is_subset(&self, other: &CompressedBitmap) -> bool {
+        self.difference(other).is_empty()
+    }
+}
+
+impl MutableSet<u32> for CompressedBitmap {
+    fn insert(&mut self, value: u32) -> bool {
+        let (key, low) = split(value);
+        match self.find_chunk(key) {
+            slice::Found(i) => self.chunks.get_mut(i).container.insert(low),
+            slice::NotFound(i) => {
+                self.chunks.insert(i, Chunk { key: key, container: Array(vec![low]) });
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, value: &u32) -> bool {
+        let (key, low) = split(*value);
+        let i = match self.find_chunk(key) {
+            slice::Found(i) => i,
+            slice::NotFound(_) => return false,
+        };
+        let removed = self.chunks.get_mut(i).container.remove(low);
+        if self.chunks[i].container.len() == 0 {
+            self.chunks.remove(i);
+        }
+        removed
+    }
+}
+
+impl FromIterator<u32> for CompressedBitmap {
+    fn from_iter<I: Iterator<u32>>(iterator: I) -> CompressedBitmap {
+        let mut ret = CompressedBitmap::new();
+        ret.extend(iterator);
+        ret
+    }
+}
+
+impl Extendable<u32> for CompressedBitmap {
+    #[inline]
+    fn extend<I: Iterator<u32>>(&mut self, mut iterator: I) {
+        for value in iterator {
+            self.insert(value);
+        }
+    }
+}
+
+impl fmt::Show for CompressedBitmap {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for n in self.iter() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{}", n));
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<S: hash::Writer> hash::Hash<S> for CompressedBitmap {
+    fn hash(&self, state: &mut S) {
+        for pos in self.iter() {
+            pos.hash(state);
+        }
+    }
+}
+
+// Iterates over the positions of the set bits of some words
+struct WordBits<'a> {
+    words: &'a [u64],
+    next_word: uint,
+    current: u64,
+}
+
+impl<'a> WordBits<'a> {
+    fn new(words: &'a [u64]) -> WordBits<'a> {
+        WordBits { words: words, next_word: 0, current: 0 }
+    }
+}
+
+impl<'a> Iterator<u16> for WordBits<'a> {
+    fn next(&mut self) -> Option<u16> {
+        while self.current == 0 {
+            if self.next_word == self.words.len() { return None }
+            self.current = self.words[self.next_word];
+            self.next_word += 1;
+        }
+        let bit = self.current.trailing_zeros();
+        self.current &= self.current - 1;
+        Some(((self.next_word - 1) * 64 + bit as uint) as u16)
+    }
+}
+
+enum ContainerItems<'a> {
+    ArrayItems(slice::Items<'a, u16>),
+    BitmapItems(WordBits<'a>),
+}
+
+impl<'a> Iterator<u16> for ContainerItems<'a> {
+    #[inline]
+    fn next(&mut self) -> Option<u16> {
+        match *self {
+            ArrayItems(ref mut iter) => iter.next().map(|&v| v),
+            BitmapItems(ref mut iter) => iter.next(),
+        }
+    }
+}
+
+/// An iterator over the values of a `CompressedBitmap`, in ascending order.
+pub struct Items<'a> {
+    chunks: slice::Items<'a, Chunk>,
+    current: Option<(u32, ContainerItems<'a>)>,
+}
+
+impl<'a> Iterator<u32> for Items<'a> {
+    fn next(&mut self) -> Option<u32> {
+        loop {
+            match self.current {
+                Some((high, ref mut iter)) => match iter.next() {
+                    Some(low) => return Some(high | low as u32),
+                    None => {}
+                },
+                None => {}
+            }
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.current = Some(((chunk.key as u32) << 16, chunk.container.iter()));
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::*;
+    use std::rand;
+    use std::rand::Rng;
+
+    use {Set, MutableSet, Mutable, TreeSet};
+    use super::{CompressedBitmap, Array, Bitmap, ARRAY_MAX};
+
+    fn check(bitmap: &CompressedBitmap, expected: &TreeSet<u32>) {
+        assert_eq!(bitmap.len(), expected.len());
+        assert_eq!(bitmap.iter().collect::<Vec<u32>>(),
+                   expected.iter().map(|&x| x).collect::<Vec<u32>>());
+        for chunk in bitmap.chunks.iter() {
+            match chunk.container {
+                Array(ref values) => assert!(values.len() > 0 && values.len() <= ARRAY_MAX),
+                Bitmap(_, len) => assert!(len > ARRAY_MAX),
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut bitmap = CompressedBitmap::new();
+        assert!(bitmap.insert(5));
+        assert!(!bitmap.insert(5));
+        assert!(bitmap.insert(1 << 20));
+        assert!(bitmap.insert(0xFFFFFFFF));
+        assert!(bitmap.contains(&5));
+        assert!(bitmap.contains(&(1 << 20)));
+        assert!(!bitmap.contains(&6));
+        assert_eq!(bitmap.len(), 3);
+
+        assert!(bitmap.remove(&(1 << 20)));
+        assert!(!bitmap.remove(&(1 << 20)));
+        assert_eq!(bitmap.iter().collect::<Vec<u32>>(), vec![5, 0xFFFFFFFF]);
+        bitmap.clear();
+        assert!(bitmap.is_empty());
+    }
+
+    #[test]
+    fn test_container_conversions() {
+        let mut bitmap = CompressedBitmap::new();
+        let mut expected = TreeSet::new();
+        for i in range(0u32, ARRAY_MAX as u32 + 10) {
+            bitmap.insert(i * 3);
+            expected.insert(i * 3);
+        }
+        check(&bitmap, &expected);
+        for i in range(0u32, 20) {
+            bitmap.remove(&(i * 3));
+            expected.remove(&(i * 3));
+        }
+        check(&bitmap, &expected);
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let mut rng = rand::weak_rng();
+        // Mix sparse and dense chunks
+        let gen = |rng: &mut rand::XorShiftRng| -> Vec<u32> {
+            let mut values = Vec::new();
+            for _ in range(0u, 20000) { values.push(rng.gen_range(0u32, 1 << 17)); }
+            for _ in range(0u, 100) { values.push(rng.gen::<u32>()); }
+            values
+        };
+        let (va, vb) = (gen(&mut rng), gen(&mut rng));
+        let a: CompressedBitmap = va.iter().map(|&x| x).collect();
+        let b: CompressedBitmap = vb.iter().map(|&x| x).collect();
+        let ta: TreeSet<u32> = va.iter().map(|&x| x).collect();
+        let tb: TreeSet<u32> = vb.iter().map(|&x| x).collect();
+
+        check(&a.union(&b), &ta.union(&tb).map(|&x| x).collect());
+        check(&a.intersection(&b), &ta.intersection(&tb).map(|&x| x).collect());
+        check(&a.difference(&b), &ta.difference(&tb).map(|&x| x).collect());
+        check(&a.symmetric_difference(&b),
+              &ta.symmetric_difference(&tb).map(|&x| x).collect());
+
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(a.difference(&b).is_disjoint(&b));
+        assert!(!a.is_disjoint(&b));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut rng = rand::weak_rng();
+        let mut bitmap = CompressedBitmap::new();
+        for _ in range(0u, 10000) { bitmap.insert(rng.gen_range(0u32, 1 << 16)); }
+        for _ in range(0u, 10) { bitmap.insert(rng.gen::<u32>()); }
+
+        let bytes = bitmap.to_bytes();
+        assert_eq!(CompressedBitmap::from_bytes(bytes.as_slice()), Some(bitmap));
+        assert!(CompressedBitmap::from_bytes(bytes.slice_to(bytes.len() - 1)).is_none());
+        assert_eq!(CompressedBitmap::from_bytes([0, 0, 0, 0]), Some(CompressedBitmap::new()));
+
+        // Unsorted values
+        assert!(CompressedBitmap::from_bytes([1, 0, 0, 0, 0, 0, 0, 1, 0,
+                                              2, 0, 1, 0]).is_none());
+    }
+
+    #[test]
+    fn test_show() {
+        let bitmap: CompressedBitmap = vec![3u32, 1, 70000].move_iter().collect();
+        assert_eq!(bitmap.to_string(), "{1, 3, 70000}".to_string());
+    }
+}
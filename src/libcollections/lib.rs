@@ -37,10 +37,12 @@ extern crate alloc;
 use core::prelude::Option;
 
 pub use bitv::{Bitv, BitvSet};
-pub use btree::BTree;
+pub use compressed_bitmap::CompressedBitmap;
+pub use btree::{BTreeMap, BTreeSet};
 pub use core::prelude::Collection;
 pub use dlist::DList;
 pub use enum_set::EnumSet;
+pub use persistent::{PersistentMap, PersistentSet, PersistentVec};
 pub use priority_queue::PriorityQueue;
 pub use ringbuf::RingBuf;
 pub use smallintmap::SmallIntMap;
@@ -53,8 +55,10 @@ mod macros;
 
 pub mod bitv;
 pub mod btree;
+pub mod compressed_bitmap;
 pub mod dlist;
 pub mod enum_set;
+pub mod persistent;
 pub mod priority_queue;
 pub mod ringbuf;
 pub mod smallintmap;
@@ -324,6 +328,18 @@ pub trait MutableSet<T>: Set<T> + Mutable {
     fn remove(&mut self, value: &T) -> bool;
 }
 
+/// An endpoint of a range of keys, as taken by the `range` methods of the
+/// ordered collections.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum Bound<T> {
+    /// The range includes the given key.
+    Included(T),
+    /// The range stops just short of the given key.
+    Excluded(T),
+    /// The range is not limited at this end.
+    Unbounded,
+}
+
 pub trait MutableSeq<T>: Mutable {
     /// Appends an element to the back of a collection.
     ///
@@ -0,0 +1,648 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A persistent ordered map and set, implemented as AVL trees.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use core::cmp;
+use core::default::Default;
+use core::fmt;
+use core::fmt::Show;
+use core::iter;
+use std::hash::{Writer, Hash};
+
+use {Map, Set, MutableSeq};
+use vec::Vec;
+
+/// A persistent ordered map.
+///
+/// Inserting into or removing from a `PersistentMap` leaves it untouched and
+/// returns a new version, which shares all but the O(log n) nodes on the path
+/// to the key with the original. Both versions can be used independently
+/// afterwards, and sent to other tasks without copying.
+///
+/// The keys and values on that path are cloned, so types which are expensive
+/// to clone are best stored behind an `Arc`.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::PersistentMap;
+///
+/// let v1 = PersistentMap::new().insert("a", 1i).insert("b", 2);
+/// let v2 = v1.insert("a", 10).remove(&"b");
+///
+/// assert_eq!(v1.find(&"a"), Some(&1));
+/// assert_eq!(v1.find(&"b"), Some(&2));
+/// assert_eq!(v2.find(&"a"), Some(&10));
+/// assert_eq!(v2.find(&"b"), None);
+/// ```
+pub struct PersistentMap<K, V> {
+    root: Tree<K, V>,
+    length: uint,
+}
+
+type Tree<K, V> = Option<Arc<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: Tree<K, V>,
+    right: Tree<K, V>,
+    height: uint,
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> PersistentMap<K, V> {
+    /// Creates an empty `PersistentMap`.
+    pub fn new() -> PersistentMap<K, V> {
+        PersistentMap { root: None, length: 0 }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map, in ascending
+    /// order of keys.
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V> {
+        let mut iter = Entries { stack: Vec::new(), remaining: self.length };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    /// Returns an iterator over the keys of the map, in ascending order.
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        self.iter().map(|(k, _v)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in ascending order of
+    /// their keys.
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        self.iter().map(|(_k, v)| v)
+    }
+}
+
+impl<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync> PersistentMap<K, V> {
+    /// Returns a new version of the map where `key` maps to `value`,
+    /// replacing any previous value for it.
+    pub fn insert(&self, key: K, value: V) -> PersistentMap<K, V> {
+        let (root, added) = insert(&self.root, key, value);
+        PersistentMap {
+            root: Some(root),
+            length: if added { self.length + 1 } else { self.length },
+        }
+    }
+
+    /// Returns a new version of the map without `key`. If the key isn't in
+    /// the map, this is O(log n) and the new version shares all of the map.
+    pub fn remove(&self, key: &K) -> PersistentMap<K, V> {
+        match remove(&self.root, key) {
+            Some(root) => PersistentMap { root: root, length: self.length - 1 },
+            None => self.clone(),
+        }
+    }
+}
+
+fn height<K: Send + Sync, V: Send + Sync>(tree: &Tree<K, V>) -> uint {
+    match *tree {
+        Some(ref node) => node.height,
+        None => 0,
+    }
+}
+
+fn node<K: Send + Sync, V: Send + Sync>(key: K, value: V, left: Tree<K, V>,
+                                        right: Tree<K, V>) -> Arc<Node<K, V>> {
+    let height = cmp::max(height(&left), height(&right)) + 1;
+    Arc::new(Node { key: key, value: value, left: left, right: right, height: height })
+}
+
+// Builds a node out of two subtrees whose heights differ by at most two,
+// rotating it to restore the AVL invariant
+fn balance<K: Clone + Send + Sync, V: Clone + Send + Sync>(key: K, value: V,
+                                                          left: Tree<K, V>,
+                                                          right: Tree<K, V>)
+                                                          -> Arc<Node<K, V>> {
+    let (hl, hr) = (height(&left), height(&right));
+    if hl > hr + 1 {
+        let l = left.unwrap();
+        if height(&l.left) >= height(&l.right) {
+            let right = node(key, value, l.right.clone(), right);
+            node(l.key.clone(), l.value.clone(), l.left.clone(), Some(right))
+        } else {
+            let lr = l.right.as_ref().unwrap();
+            let left = node(l.key.clone(), l.value.clone(), l.left.clone(), lr.left.clone());
+            let right = node(key, value, lr.right.clone(), right);
+            node(lr.key.clone(), lr.value.clone(), Some(left), Some(right))
+        }
+    } else if hr > hl + 1 {
+        let r = right.unwrap();
+        if height(&r.right) >= height(&r.left) {
+            let left = node(key, value, left, r.left.clone());
+            node(r.key.clone(), r.value.clone(), Some(left), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().unwrap();
+            let left = node(key, value, left, rl.left.clone());
+            let right = node(r.key.clone(), r.value.clone(), rl.right.clone(), r.right.clone());
+            node(rl.key.clone(), rl.value.clone(), Some(left), Some(right))
+        }
+    } else {
+        node(key, value, left, right)
+    }
+}
+
+// Returns the new tree, and whether the key wasn't in it before
+fn insert<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync>(tree: &Tree<K, V>,
+                                                               key: K, value: V)
+                                                               -> (Arc<Node<K, V>>, bool) {
+    let n = match *tree {
+        Some(ref n) => n,
+        None => return (node(key, value, None, None), true),
+    };
+    match key.cmp(&n.key) {
+        Less => {
+            let (left, added) = insert(&n.left, key, value);
+            (balance(n.key.clone(), n.value.clone(), Some(left), n.right.clone()), added)
+        }
+        Greater => {
+            let (right, added) = insert(&n.right, key, value);
+            (balance(n.key.clone(), n.value.clone(), n.left.clone(), Some(right)), added)
+        }
+        Equal => (node(key, value, n.left.clone(), n.right.clone()), false),
+    }
+}
+
+// Returns the new tree, or `None` if the key wasn't in it
+fn remove<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync>(tree: &Tree<K, V>,
+                                                               key: &K)
+                                                               -> Option<Tree<K, V>> {
+    let n = match *tree {
+        Some(ref n) => n,
+        None => return None,
+    };
+    match key.cmp(&n.key) {
+        Less => remove(&n.left, key).map(|left| {
+            Some(balance(n.key.clone(), n.value.clone(), left, n.right.clone()))
+        }),
+        Greater => remove(&n.right, key).map(|right| {
+            Some(balance(n.key.clone(), n.value.clone(), n.left.clone(), right))
+        }),
+        Equal => Some(match (&n.left, &n.right) {
+            (&None, right) => right.clone(),
+            (left, &None) => left.clone(),
+            (left, &Some(ref right)) => {
+                // Replace the node by the smallest one of its right subtree
+                let (key, value, right) = remove_min(&**right);
+                Some(balance(key, value, left.clone(), right))
+            }
+        }),
+    }
+}
+
+fn remove_min<K: Clone + Send + Sync, V: Clone + Send + Sync>(n: &Node<K, V>)
+                                                             -> (K, V, Tree<K, V>) {
+    match n.left {
+        None => (n.key.clone(), n.value.clone(), n.right.clone()),
+        Some(ref left) => {
+            let (key, value, left) = remove_min(&**left);
+            let n = balance(n.key.clone(), n.value.clone(), left, n.right.clone());
+            (key, value, Some(n))
+        }
+    }
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> Collection for PersistentMap<K, V> {
+    #[inline]
+    fn len(&self) -> uint { self.length }
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> Map<K, V> for PersistentMap<K, V> {
+    fn find<'a>(&'a self, key: &K) -> Option<&'a V> {
+        let mut current = &self.root;
+        loop {
+            match *current {
+                Some(ref n) => match key.cmp(&n.key) {
+                    Less => current = &n.left,
+                    Greater => current = &n.right,
+                    Equal => return Some(&n.value),
+                },
+                None => return None,
+            }
+        }
+    }
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> Clone for PersistentMap<K, V> {
+    /// Returns another handle to the same version of the map. This is O(1).
+    fn clone(&self) -> PersistentMap<K, V> {
+        PersistentMap { root: self.root.clone(), length: self.length }
+    }
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> Default for PersistentMap<K, V> {
+    #[inline]
+    fn default() -> PersistentMap<K, V> { PersistentMap::new() }
+}
+
+impl<K: Ord + Send + Sync, V: Send + Sync> Index<K, V> for PersistentMap<K, V> {
+    #[inline]
+    fn index<'a>(&'a self, i: &K) -> &'a V {
+        self.find(i).expect("no entry found for key")
+    }
+}
+
+impl<K: Ord + Clone + Send + Sync, V: Clone + Send + Sync> FromIterator<(K, V)>
+        for PersistentMap<K, V> {
+    fn from_iter<T: Iterator<(K, V)>>(mut iter: T) -> PersistentMap<K, V> {
+        iter.fold(PersistentMap::new(), |map, (k, v)| map.insert(k, v))
+    }
+}
+
+impl<K: Ord + Send + Sync, V: PartialEq + Send + Sync> PartialEq for PersistentMap<K, V> {
+    fn eq(&self, other: &PersistentMap<K, V>) -> bool {
+        self.len() == other.len() &&
+            self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<K: Ord + Send + Sync, V: Eq + Send + Sync> Eq for PersistentMap<K, V> {}
+
+impl<K: Ord + Send + Sync, V: PartialOrd + Send + Sync> PartialOrd for PersistentMap<K, V> {
+    #[inline]
+    fn partial_cmp(&self, other: &PersistentMap<K, V>) -> Option<Ordering> {
+        iter::order::partial_cmp(self.iter(), other.iter())
+    }
+}
+
+impl<K: Ord + Send + Sync, V: Ord + Send + Sync> Ord for PersistentMap<K, V> {
+    #[inline]
+    fn cmp(&self, other: &PersistentMap<K, V>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
+    }
+}
+
+impl<K: Ord + Show + Send + Sync, V: Show + Send + Sync> Show for PersistentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
+
+        write!(f, "}}")
+    }
+}
+
+impl<S: Writer, K: Ord + Hash<S> + Send + Sync, V: Hash<S> + Send + Sync> Hash<S>
+        for PersistentMap<K, V> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
+
+/// An iterator over the key-value pairs of a `PersistentMap`, in ascending
+/// order of keys.
+pub struct Entries<'a, K:'a, V:'a> {
+    // The nodes whose left subtree has been visited but not they themselves
+    stack: Vec<&'a Node<K, V>>,
+    remaining: uint,
+}
+
+/// An iterator over the keys of a `PersistentMap`.
+pub type Keys<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
+
+/// An iterator over the values of a `PersistentMap`.
+pub type Values<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
+
+impl<'a, K: Send + Sync, V: Send + Sync> Entries<'a, K, V> {
+    fn push_left(&mut self, mut tree: &'a Tree<K, V>) {
+        loop {
+            match *tree {
+                Some(ref n) => {
+                    self.stack.push(&**n);
+                    tree = &n.left;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl<'a, K: Send + Sync, V: Send + Sync> Iterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        match self.stack.pop() {
+            Some(n) => {
+                self.push_left(&n.right);
+                self.remaining -= 1;
+                Some((&n.key, &n.value))
+            }
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// A persistent ordered set, implemented as a `PersistentMap` with `()`
+/// values.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::PersistentSet;
+///
+/// let v1: PersistentSet<int> = [1i, 2, 3].iter().map(|&x| x).collect();
+/// let v2 = v1.remove(&2).insert(4);
+///
+/// assert!(v1.contains(&2));
+/// assert!(!v2.contains(&2));
+/// assert!(v2.contains(&4));
+/// ```
+pub struct PersistentSet<T> {
+    map: PersistentMap<T, ()>,
+}
+
+impl<T: Ord + Send + Sync> PersistentSet<T> {
+    /// Creates an empty `PersistentSet`.
+    #[inline]
+    pub fn new() -> PersistentSet<T> {
+        PersistentSet { map: PersistentMap::new() }
+    }
+
+    /// Returns an iterator over the values of the set, in ascending order.
+    #[inline]
+    pub fn iter<'a>(&'a self) -> SetItems<'a, T> {
+        SetItems { iter: self.map.iter() }
+    }
+}
+
+impl<T: Ord + Clone + Send + Sync> PersistentSet<T> {
+    /// Returns a new version of the set containing `value`.
+    #[inline]
+    pub fn insert(&self, value: T) -> PersistentSet<T> {
+        PersistentSet { map: self.map.insert(value, ()) }
+    }
+
+    /// Returns a new version of the set without `value`.
+    #[inline]
+    pub fn remove(&self, value: &T) -> PersistentSet<T> {
+        PersistentSet { map: self.map.remove(value) }
+    }
+}
+
+impl<T: Ord + Send + Sync> Collection for PersistentSet<T> {
+    #[inline]
+    fn len(&self) -> uint { self.map.len() }
+}
+
+impl<T: Ord + Send + Sync> Set<T> for PersistentSet<T> {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    fn is_disjoint(&self, other: &PersistentSet<T>) -> bool {
+        self.iter().all(|v| !other.contains(v))
+    }
+
+    fn is_subset(&self, other: &PersistentSet<T>) -> bool {
+        self.len() <= other.len() && self.iter().all(|v| other.contains(v))
+    }
+}
+
+impl<T: Ord + Send + Sync> Clone for PersistentSet<T> {
+    /// Returns another handle to the same version of the set. This is O(1).
+    #[inline]
+    fn clone(&self) -> PersistentSet<T> {
+        PersistentSet { map: self.map.clone() }
+    }
+}
+
+impl<T: Ord + Send + Sync> Default for PersistentSet<T> {
+    #[inline]
+    fn default() -> PersistentSet<T> { PersistentSet::new() }
+}
+
+impl<T: Ord + Clone + Send + Sync> FromIterator<T> for PersistentSet<T> {
+    fn from_iter<I: Iterator<T>>(mut iter: I) -> PersistentSet<T> {
+        iter.fold(PersistentSet::new(), |set, value| set.insert(value))
+    }
+}
+
+impl<T: Ord + Send + Sync> PartialEq for PersistentSet<T> {
+    #[inline]
+    fn eq(&self, other: &PersistentSet<T>) -> bool { self.map == other.map }
+}
+
+impl<T: Ord + Send + Sync> Eq for PersistentSet<T> {}
+
+impl<T: Ord + Send + Sync> PartialOrd for PersistentSet<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &PersistentSet<T>) -> Option<Ordering> {
+        self.map.partial_cmp(&other.map)
+    }
+}
+
+impl<T: Ord + Send + Sync> Ord for PersistentSet<T> {
+    #[inline]
+    fn cmp(&self, other: &PersistentSet<T>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
+    }
+}
+
+impl<T: Ord + Show + Send + Sync> Show for PersistentSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, x) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}", *x));
+        }
+
+        write!(f, "}}")
+    }
+}
+
+impl<S: Writer, T: Ord + Hash<S> + Send + Sync> Hash<S> for PersistentSet<T> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
+
+/// An iterator over the values of a `PersistentSet`, in ascending order.
+pub struct SetItems<'a, T:'a> {
+    iter: Entries<'a, T, ()>
+}
+
+impl<'a, T: Send + Sync> Iterator<&'a T> for SetItems<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(value, _)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test_map {
+    use std::prelude::*;
+    use std::rand::Rng;
+    use std::rand;
+
+    use Map;
+    use super::{PersistentMap, Tree, height};
+
+    // Checks the ordering and the AVL invariant, returning the height
+    fn check<K: Ord + Send + Sync, V: Send + Sync>(tree: &Tree<K, V>,
+                                                  lo: Option<&K>,
+                                                  hi: Option<&K>) -> uint {
+        match *tree {
+            None => 0,
+            Some(ref n) => {
+                lo.map(|lo| assert!(*lo < n.key));
+                hi.map(|hi| assert!(n.key < *hi));
+                let hl = check(&n.left, lo, Some(&n.key));
+                let hr = check(&n.right, Some(&n.key), hi);
+                assert!(hl <= hr + 1 && hr <= hl + 1);
+                assert_eq!(n.height, height(tree));
+                assert_eq!(n.height, 1 + if hl > hr { hl } else { hr });
+                n.height
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_find() {
+        let mut m = PersistentMap::new();
+        for i in range(0i, 100) {
+            m = m.insert(i, i * 2);
+        }
+        check(&m.root, None, None);
+        assert_eq!(m.len(), 100);
+        for i in range(0i, 100) {
+            assert_eq!(m.find(&i), Some(&(i * 2)));
+        }
+        assert_eq!(m.find(&100), None);
+
+        let m2 = m.insert(5, 0);
+        assert_eq!(m2.len(), 100);
+        assert_eq!(m2[5], 0);
+        assert_eq!(m[5], 10);
+    }
+
+    #[test]
+    fn test_remove() {
+        let m: PersistentMap<int, int> = range(0i, 10).map(|i| (i, i)).collect();
+        let m2 = m.remove(&3).remove(&7).remove(&42);
+        check(&m2.root, None, None);
+        assert_eq!(m2.len(), 8);
+        assert!(!m2.contains_key(&3));
+        assert!(!m2.contains_key(&7));
+        assert_eq!(m.len(), 10);
+        assert!(m.contains_key(&3));
+        assert_eq!(m.keys().map(|&k| k).collect::<Vec<int>>(),
+                   range(0i, 10).collect());
+        assert_eq!(m2.values().map(|&v| v).collect::<Vec<int>>(),
+                   vec![0, 1, 2, 4, 5, 6, 8, 9]);
+    }
+
+    #[test]
+    fn test_rand() {
+        let mut rng = rand::weak_rng();
+        let mut map = PersistentMap::new();
+        let mut keys = Vec::new();
+        for _ in range(0u, 1000) {
+            let k: int = rng.gen_range(0, 500);
+            map = map.insert(k, ());
+            keys.push(k);
+        }
+        check(&map.root, None, None);
+
+        rng.shuffle(keys.as_mut_slice());
+        for k in keys.iter() {
+            map = map.remove(k);
+            check(&map.root, None, None);
+            assert!(!map.contains_key(k));
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_show() {
+        let m = PersistentMap::new().insert(2i, 'b').insert(1, 'a');
+        assert_eq!(m.to_string(), "{1: a, 2: b}".to_string());
+        let empty: PersistentMap<int, char> = PersistentMap::new();
+        assert_eq!(empty.to_string(), "{}".to_string());
+    }
+
+    #[test]
+    fn test_send() {
+        let m: PersistentMap<int, int> = range(0i, 100).map(|i| (i, i)).collect();
+        let snapshot = m.clone();
+        let (tx, rx) = channel();
+        spawn(proc() {
+            tx.send(snapshot.values().fold(0, |a, &b| a + b));
+        });
+        let m = m.remove(&0).insert(1, 1000);
+        assert_eq!(rx.recv(), 4950);
+        assert_eq!(m.len(), 99);
+    }
+}
+
+#[cfg(test)]
+mod test_set {
+    use std::prelude::*;
+
+    use Set;
+    use super::PersistentSet;
+
+    #[test]
+    fn test_insert_remove() {
+        let a = PersistentSet::new().insert(3i).insert(1).insert(2).insert(1);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.iter().map(|&x| x).collect::<Vec<int>>(), vec![1, 2, 3]);
+
+        let b = a.remove(&2);
+        assert!(a.contains(&2));
+        assert!(!b.contains(&2));
+        assert_eq!(b.len(), 2);
+    }
+
+    #[test]
+    fn test_subset_disjoint() {
+        let a: PersistentSet<int> = range(0i, 5).collect();
+        let b: PersistentSet<int> = range(0i, 10).collect();
+        let c: PersistentSet<int> = range(10i, 20).collect();
+        assert!(a.is_subset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(b.is_superset(&a));
+        assert!(a.is_disjoint(&c));
+        assert!(!a.is_disjoint(&b));
+    }
+
+    #[test]
+    fn test_eq() {
+        let a: PersistentSet<int> = range(0i, 5).collect();
+        let b = a.insert(5).remove(&5);
+        assert!(a == b);
+        assert!(a != a.remove(&0));
+        assert_eq!(a.to_string(), "{0, 1, 2, 3, 4}".to_string());
+    }
+}
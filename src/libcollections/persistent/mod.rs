@@ -0,0 +1,43 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Persistent collections.
+//!
+//! The collections in this module are immutable: operations which would
+//! modify them return a new version of the collection instead, leaving the
+//! original untouched. Versions share most of their structure through `Arc`
+//! pointers, so making a new version costs O(log n) time and space, and
+//! cloning one is O(1).
+//!
+//! All versions are `Send` and `Sync` as long as their elements are, which
+//! makes them a cheap way to hand a snapshot of some data to another task
+//! while continuing to update it.
+//!
+//! ## Example
+//!
+//! ```{rust}
+//! use std::collections::PersistentMap;
+//!
+//! let mut history = vec![PersistentMap::new()];
+//! for (i, word) in ["foo", "bar", "baz"].iter().enumerate() {
+//!     let next = history.last().unwrap().insert(i, *word);
+//!     history.push(next);
+//! }
+//!
+//! // Every version is still available
+//! assert_eq!(history[1].len(), 1);
+//! assert_eq!(history[3].find(&2), Some(&"baz"));
+//! ```
+
+pub use self::map::{PersistentMap, PersistentSet};
+pub use self::vector::PersistentVec;
+
+pub mod map;
+pub mod vector;
@@ -0,0 +1,426 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A persistent vector, implemented as a radix trie with 32 elements or
+//! children per node.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use core::default::Default;
+use core::fmt;
+use core::iter;
+use std::hash::{Writer, Hash};
+
+use MutableSeq;
+use vec::Vec;
+
+static BITS: uint = 5;
+static WIDTH: uint = 1 << BITS;
+static MASK: uint = WIDTH - 1;
+
+/// A persistent vector.
+///
+/// Updating a `PersistentVec` leaves it untouched and returns a new version,
+/// which shares all but O(log n) of its nodes with the original. Both versions
+/// can be used independently afterwards, and sent to other tasks without
+/// copying.
+///
+/// The base of the logarithm is 32, so in practice `get`, `set`, `push` and
+/// `pop` only ever visit a handful of nodes. Updates clone the elements of
+/// one leaf, at most 32 of them.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::PersistentVec;
+///
+/// let v1 = PersistentVec::new().push(1i).push(2);
+/// let v2 = v1.push(3).set(0, 10);
+///
+/// assert_eq!(v1.iter().map(|&x| x).collect::<Vec<int>>(), vec![1, 2]);
+/// assert_eq!(v2.iter().map(|&x| x).collect::<Vec<int>>(), vec![10, 2, 3]);
+/// ```
+pub struct PersistentVec<T> {
+    root: Option<Arc<Node<T>>>,
+    length: uint,
+    // The number of index bits consumed by the branches above the leaves
+    shift: uint,
+}
+
+enum Node<T> {
+    Leaf(Vec<T>),
+    Branch(Vec<Arc<Node<T>>>),
+}
+
+impl<T: Send + Sync> PersistentVec<T> {
+    /// Creates an empty `PersistentVec`.
+    pub fn new() -> PersistentVec<T> {
+        PersistentVec { root: None, length: 0, shift: 0 }
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is out
+    /// of bounds.
+    pub fn get<'a>(&'a self, index: uint) -> Option<&'a T> {
+        if index >= self.length { return None }
+
+        let mut node = &**self.root.as_ref().unwrap();
+        let mut level = self.shift;
+        loop {
+            match *node {
+                Branch(ref children) => {
+                    node = &*children[(index >> level) & MASK];
+                    level -= BITS;
+                }
+                Leaf(ref items) => return Some(&items[index & MASK]),
+            }
+        }
+    }
+
+    /// Returns a reference to the last element, or `None` if the vector is
+    /// empty.
+    pub fn last<'a>(&'a self) -> Option<&'a T> {
+        if self.length == 0 { None } else { self.get(self.length - 1) }
+    }
+
+    /// Returns an iterator over the elements of the vector, in order.
+    pub fn iter<'a>(&'a self) -> Items<'a, T> {
+        Items { vec: self, index: 0, end: self.length }
+    }
+}
+
+impl<T: Clone + Send + Sync> PersistentVec<T> {
+    /// Returns a new version of the vector with `value` appended.
+    pub fn push(&self, value: T) -> PersistentVec<T> {
+        let (root, shift) = match self.root {
+            None => (new_path(0, value), 0),
+            Some(ref root) if self.length == 1 << (self.shift + BITS) => {
+                // The trie is full, so it grows a level
+                let branch = Branch(vec![root.clone(), new_path(self.shift, value)]);
+                (Arc::new(branch), self.shift + BITS)
+            }
+            Some(ref root) => {
+                (push_into(&**root, self.shift, self.length, value), self.shift)
+            }
+        };
+        PersistentVec { root: Some(root), length: self.length + 1, shift: shift }
+    }
+
+    /// Returns a new version of the vector with the last element removed, or
+    /// `None` if the vector is empty.
+    pub fn pop(&self) -> Option<PersistentVec<T>> {
+        let mut root = match self.root {
+            Some(ref root) => pop_from(&**root),
+            None => return None,
+        };
+
+        // Shrink the trie by a level for as long as its root only has one
+        // child, so that `push` can tell when it is full
+        let mut shift = self.shift;
+        loop {
+            let child = match root {
+                Some(ref node) => match **node {
+                    Branch(ref children) if children.len() == 1 => children[0].clone(),
+                    _ => break,
+                },
+                None => break,
+            };
+            root = Some(child);
+            shift -= BITS;
+        }
+
+        Some(PersistentVec { root: root, length: self.length - 1, shift: shift })
+    }
+
+    /// Returns a new version of the vector with the element at `index`
+    /// replaced by `value`.
+    ///
+    /// # Failure
+    ///
+    /// Fails if `index` is out of bounds.
+    pub fn set(&self, index: uint, value: T) -> PersistentVec<T> {
+        assert!(index < self.length,
+                "index out of bounds: the len is {} but the index is {}",
+                self.length, index);
+        let root = set_in(&**self.root.as_ref().unwrap(), self.shift, index, value);
+        PersistentVec { root: Some(root), length: self.length, shift: self.shift }
+    }
+}
+
+// Builds the nodes leading from `level` down to a leaf holding only `value`
+fn new_path<T: Send + Sync>(level: uint, value: T) -> Arc<Node<T>> {
+    if level == 0 {
+        Arc::new(Leaf(vec![value]))
+    } else {
+        Arc::new(Branch(vec![new_path(level - BITS, value)]))
+    }
+}
+
+// Copies the path to `index`, the first free slot below `node`, and stores
+// `value` there
+fn push_into<T: Clone + Send + Sync>(node: &Node<T>, level: uint, index: uint,
+                                     value: T) -> Arc<Node<T>> {
+    match *node {
+        Leaf(ref items) => {
+            let mut items = items.clone();
+            items.push(value);
+            Arc::new(Leaf(items))
+        }
+        Branch(ref children) => {
+            let mut children = children.clone();
+            let i = (index >> level) & MASK;
+            if i < children.len() {
+                let child = push_into(&*children[i], level - BITS, index, value);
+                *children.get_mut(i) = child;
+            } else {
+                children.push(new_path(level - BITS, value));
+            }
+            Arc::new(Branch(children))
+        }
+    }
+}
+
+// Copies the path to the last element below `node`, without it. Returns
+// `None` if that leaves the node empty.
+fn pop_from<T: Clone + Send + Sync>(node: &Node<T>) -> Option<Arc<Node<T>>> {
+    match *node {
+        Leaf(ref items) => {
+            if items.len() == 1 { return None }
+            let mut items = items.clone();
+            items.pop();
+            Some(Arc::new(Leaf(items)))
+        }
+        Branch(ref children) => {
+            let mut children = children.clone();
+            let last = children.pop().unwrap();
+            match pop_from(&*last) {
+                Some(child) => children.push(child),
+                None if children.is_empty() => return None,
+                None => {}
+            }
+            Some(Arc::new(Branch(children)))
+        }
+    }
+}
+
+fn set_in<T: Clone + Send + Sync>(node: &Node<T>, level: uint, index: uint,
+                                  value: T) -> Arc<Node<T>> {
+    match *node {
+        Leaf(ref items) => {
+            let mut items = items.clone();
+            *items.get_mut(index & MASK) = value;
+            Arc::new(Leaf(items))
+        }
+        Branch(ref children) => {
+            let mut children = children.clone();
+            let i = (index >> level) & MASK;
+            let child = set_in(&*children[i], level - BITS, index, value);
+            *children.get_mut(i) = child;
+            Arc::new(Branch(children))
+        }
+    }
+}
+
+impl<T: Send + Sync> Collection for PersistentVec<T> {
+    #[inline]
+    fn len(&self) -> uint { self.length }
+}
+
+impl<T: Send + Sync> Clone for PersistentVec<T> {
+    /// Returns another handle to the same version of the vector. This is O(1).
+    fn clone(&self) -> PersistentVec<T> {
+        PersistentVec { root: self.root.clone(), length: self.length, shift: self.shift }
+    }
+}
+
+impl<T: Send + Sync> Default for PersistentVec<T> {
+    #[inline]
+    fn default() -> PersistentVec<T> { PersistentVec::new() }
+}
+
+impl<T: Send + Sync> Index<uint, T> for PersistentVec<T> {
+    #[inline]
+    fn index<'a>(&'a self, index: &uint) -> &'a T {
+        match self.get(*index) {
+            Some(value) => value,
+            None => fail!("index out of bounds: the len is {} but the index is {}",
+                          self.length, *index),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync> FromIterator<T> for PersistentVec<T> {
+    fn from_iter<I: Iterator<T>>(mut iter: I) -> PersistentVec<T> {
+        iter.fold(PersistentVec::new(), |vec, value| vec.push(value))
+    }
+}
+
+impl<T: PartialEq + Send + Sync> PartialEq for PersistentVec<T> {
+    fn eq(&self, other: &PersistentVec<T>) -> bool {
+        self.len() == other.len() &&
+            self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Eq + Send + Sync> Eq for PersistentVec<T> {}
+
+impl<T: PartialOrd + Send + Sync> PartialOrd for PersistentVec<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &PersistentVec<T>) -> Option<Ordering> {
+        iter::order::partial_cmp(self.iter(), other.iter())
+    }
+}
+
+impl<T: Ord + Send + Sync> Ord for PersistentVec<T> {
+    #[inline]
+    fn cmp(&self, other: &PersistentVec<T>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
+    }
+}
+
+impl<T: fmt::Show + Send + Sync> fmt::Show for PersistentVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "["));
+
+        for (i, e) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}", *e));
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl<S: Writer, T: Hash<S> + Send + Sync> Hash<S> for PersistentVec<T> {
+    fn hash(&self, state: &mut S) {
+        self.len().hash(state);
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
+
+/// An iterator over the elements of a `PersistentVec`.
+pub struct Items<'a, T:'a> {
+    vec: &'a PersistentVec<T>,
+    index: uint,
+    end: uint,
+}
+
+impl<'a, T: Send + Sync> Iterator<&'a T> for Items<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        if self.index == self.end { return None }
+        self.index += 1;
+        self.vec.get(self.index - 1)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let left = self.end - self.index;
+        (left, Some(left))
+    }
+}
+
+impl<'a, T: Send + Sync> DoubleEndedIterator<&'a T> for Items<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.index == self.end { return None }
+        self.end -= 1;
+        self.vec.get(self.end)
+    }
+}
+
+impl<'a, T: Send + Sync> ExactSize<&'a T> for Items<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::*;
+
+    use super::PersistentVec;
+
+    fn to_vec(v: &PersistentVec<uint>) -> Vec<uint> {
+        v.iter().map(|&x| x).collect()
+    }
+
+    #[test]
+    fn test_push_get() {
+        let mut v = PersistentVec::new();
+        for i in range(0u, 2000) {
+            v = v.push(i);
+            assert_eq!(v.len(), i + 1);
+            assert_eq!(v.last(), Some(&i));
+        }
+        for i in range(0u, 2000) {
+            assert_eq!(v.get(i), Some(&i));
+            assert_eq!(v[i], i);
+        }
+        assert_eq!(v.get(2000), None);
+        assert_eq!(v.iter().rev().next(), Some(&1999));
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut v: PersistentVec<uint> = range(0u, 1100).collect();
+        for i in range(0u, 1100).rev() {
+            assert_eq!(v.last(), Some(&i));
+            v = v.pop().unwrap();
+            assert_eq!(v.len(), i);
+            assert_eq!(v.get(i), None);
+        }
+        assert!(v.pop().is_none());
+
+        // Popping and pushing across a level boundary
+        let v: PersistentVec<uint> = range(0u, 33).collect();
+        let v = v.pop().unwrap().push(32).push(33);
+        assert_eq!(to_vec(&v), range(0u, 34).collect());
+    }
+
+    #[test]
+    fn test_versions_are_independent() {
+        let v1: PersistentVec<uint> = range(0u, 100).collect();
+        let v2 = v1.set(50, 0).push(100);
+        let v3 = v1.pop().unwrap();
+
+        assert_eq!(to_vec(&v1), range(0u, 100).collect());
+        assert_eq!(v2[50], 0);
+        assert_eq!(v2.len(), 101);
+        assert_eq!(to_vec(&v3), range(0u, 99).collect());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_set_out_of_bounds() {
+        let v = PersistentVec::new().push(1i);
+        v.set(1, 2);
+    }
+
+    #[test]
+    fn test_eq_show() {
+        let a: PersistentVec<uint> = range(0u, 3).collect();
+        let b = PersistentVec::new().push(0u).push(1).push(2);
+        assert!(a == b);
+        assert!(a != b.pop().unwrap());
+        assert_eq!(a.to_string(), "[0, 1, 2]".to_string());
+    }
+
+    #[test]
+    fn test_send() {
+        let v: PersistentVec<uint> = range(0u, 100).collect();
+        let (tx, rx) = channel();
+        let snapshot = v.clone();
+        spawn(proc() {
+            tx.send(snapshot.iter().fold(0, |a, &b| a + b));
+        });
+        let v = v.set(0, 1000);
+        assert_eq!(rx.recv(), 4950);
+        assert_eq!(v[0], 1000);
+    }
+}
@@ -318,6 +318,36 @@ impl<V> SmallIntMap<V> {
             v.map(|v| (i, v))
         })
     }
+
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::SmallIntMap;
+    /// use std::collections::smallintmap::{Occupied, Vacant};
+    ///
+    /// let mut count: SmallIntMap<uint> = SmallIntMap::new();
+    ///
+    /// for &x in [1u, 2, 1, 3, 1].iter() {
+    ///     match count.entry(x) {
+    ///         Vacant(entry) => { entry.insert(1); }
+    ///         Occupied(mut entry) => { *entry.get_mut() += 1; }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(count[1], 3);
+    /// assert_eq!(count[3], 1);
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: uint) -> Entry<'a, V> {
+        let occupied = key < self.v.len() && self.v[key].is_some();
+        if occupied {
+            Occupied(OccupiedEntry { map: self, index: key })
+        } else {
+            Vacant(VacantEntry { map: self, index: key })
+        }
+    }
 }
 
 impl<V:Clone> SmallIntMap<V> {
@@ -518,6 +548,67 @@ pub type Keys<'a, T> =
 pub type Values<'a, T> =
     iter::Map<'static, (uint, &'a T), &'a T, Entries<'a, T>>;
 
+/// A view into a single slot of a `SmallIntMap`, as returned by `entry`.
+pub enum Entry<'a, V:'a> {
+    /// The key is present in the map.
+    Occupied(OccupiedEntry<'a, V>),
+    /// The key is absent from the map.
+    Vacant(VacantEntry<'a, V>),
+}
+
+/// A handle to a present entry of a `SmallIntMap`.
+pub struct OccupiedEntry<'a, V:'a> {
+    map: &'a mut SmallIntMap<V>,
+    index: uint,
+}
+
+/// A handle to an absent entry of a `SmallIntMap`.
+pub struct VacantEntry<'a, V:'a> {
+    map: &'a mut SmallIntMap<V>,
+    index: uint,
+}
+
+impl<'a, V> OccupiedEntry<'a, V> {
+    /// Returns a reference to the entry's value.
+    pub fn get<'b>(&'b self) -> &'b V {
+        self.map.v[self.index].get_ref()
+    }
+
+    /// Returns a mutable reference to the entry's value.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut V {
+        self.map.v.get_mut(self.index).get_mut_ref()
+    }
+
+    /// Converts the entry into a mutable reference bound to the map.
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.v.get_mut(self.index).get_mut_ref()
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn set(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    pub fn take(self) -> V {
+        self.map.v.get_mut(self.index).take().unwrap()
+    }
+}
+
+impl<'a, V> VacantEntry<'a, V> {
+    /// Inserts `value` under the entry's key, returning a mutable
+    /// reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let len = self.map.v.len();
+        if len <= self.index {
+            self.map.v.grow_fn(self.index - len + 1, |_| None);
+        }
+        let slot = self.map.v.get_mut(self.index);
+        *slot = Some(value);
+        slot.get_mut_ref()
+    }
+}
+
 #[cfg(test)]
 mod test_map {
     use std::prelude::*;
@@ -525,7 +616,7 @@ mod test_map {
     use hash;
 
     use {Map, MutableMap, Mutable, MutableSeq};
-    use super::SmallIntMap;
+    use super::{SmallIntMap, Occupied, Vacant};
 
     #[test]
     fn test_find_mut() {
@@ -887,6 +978,43 @@ mod test_map {
 
         map[4];
     }
+
+    #[test]
+    fn test_entry() {
+        let mut map = SmallIntMap::new();
+        map.insert(1, 10i);
+        map.insert(3, 30);
+
+        match map.entry(1) {
+            Occupied(mut e) => {
+                assert_eq!(*e.get(), 10);
+                assert_eq!(e.set(100), 10);
+                *e.get_mut() += 1;
+            }
+            Vacant(_) => fail!()
+        }
+        assert_eq!(map[1], 101);
+
+        match map.entry(7) {
+            Vacant(e) => { *e.insert(70) += 7; }
+            Occupied(_) => fail!()
+        }
+        assert_eq!(map[7], 77);
+        assert_eq!(map.len(), 3);
+
+        match map.entry(3) {
+            Occupied(e) => assert_eq!(e.take(), 30),
+            Vacant(_) => fail!()
+        }
+        assert!(map.find(&3).is_none());
+        assert_eq!(map.len(), 2);
+
+        match map.entry(3) {
+            Vacant(e) => { e.insert(33); }
+            Occupied(_) => fail!()
+        }
+        assert_eq!(map[3], 33);
+    }
 }
 
 #[cfg(test)]
@@ -41,6 +41,7 @@ use core::ptr;
 use std::hash::{Writer, Hash};
 
 use {Mutable, Set, MutableSet, MutableMap, Map, MutableSeq};
+use {Bound, Included, Excluded, Unbounded};
 use vec::Vec;
 
 /// This is implemented as an AA tree, which is a simplified variation of
@@ -531,6 +532,36 @@ macro_rules! bound_setup {
     }
 }
 
+// The mirror image of bound_setup, for an iterator driven backwards from the
+// last key which is not greater than `$k`, or less than it if `$inclusive` is
+// false.
+macro_rules! rev_bound_setup {
+    ($iter:expr, $k:expr, $inclusive:expr) => {
+        {
+            let mut iter = $iter;
+            loop {
+                if !iter.node.is_null() {
+                    let node_k = unsafe {&(*iter.node).key};
+                    match $k.cmp(node_k) {
+                        Less => iter.traverse_left_rev(),
+                        Greater => iter.traverse_right_rev(),
+                        Equal => {
+                            if $inclusive {
+                                iter.traverse_complete();
+                                return iter;
+                            } else {
+                                iter.traverse_left_rev()
+                            }
+                        }
+                    }
+                } else {
+                    iter.traverse_complete();
+                    return iter;
+                }
+            }
+        }
+    }
+}
 
 impl<K: Ord, V> TreeMap<K, V> {
     /// Gets a lazy iterator that should be initialized using
@@ -668,6 +699,415 @@ impl<K: Ord, V> TreeMap<K, V> {
     }
 }
 
+impl<K: Ord, V> TreeMap<K, V> {
+    /// Gets a lazy iterator over the key-value pairs whose keys lie between
+    /// `min` and `max`, in ascending order, which can also be iterated from
+    /// the back. The iterator is empty if `min` is past `max`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    /// use std::collections::{Included, Excluded, Unbounded};
+    ///
+    /// let mut map = TreeMap::new();
+    /// map.insert(2i, "a");
+    /// map.insert(4, "b");
+    /// map.insert(6, "c");
+    /// map.insert(8, "d");
+    ///
+    /// let keys: Vec<int> = map.range(Included(&4), Excluded(&8))
+    ///                         .map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![4, 6]);
+    ///
+    /// let keys: Vec<int> = map.range(Excluded(&4), Unbounded)
+    ///                         .map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![6, 8]);
+    ///
+    /// let keys: Vec<int> = map.range(Unbounded, Included(&6))
+    ///                         .rev().map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![6, 4, 2]);
+    /// ```
+    pub fn range<'a>(&'a self, min: Bound<&K>, max: Bound<&K>)
+                     -> RangeEntries<'a, K, V> {
+        let start = self.range_start(min);
+        let end = self.range_end(max);
+        if bounds_cross(min, max) {
+            return RangeEntries {
+                iter: self.empty_entries(),
+                back: self.empty_entries(),
+                start: start,
+                end: end
+            }
+        }
+        let iter = match min {
+            Unbounded => self.iter(),
+            Included(k) => self.lower_bound(k),
+            Excluded(k) => self.upper_bound(k),
+        };
+        let back = match max {
+            Unbounded => self.iter(),
+            Included(k) => self.rev_bound(k, true),
+            Excluded(k) => self.rev_bound(k, false),
+        };
+        RangeEntries { iter: iter, back: back, start: start, end: end }
+    }
+
+    /// Gets a lazy iterator over the key-value pairs whose keys lie between
+    /// `min` and `max`, in ascending order, with the values being mutable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    /// use std::collections::{Included, Excluded};
+    ///
+    /// let mut map: TreeMap<int, int> = range(0i, 10).map(|i| (i, i)).collect();
+    /// for (_, v) in map.mut_range(Included(&3), Excluded(&6)) {
+    ///     *v = 0;
+    /// }
+    ///
+    /// let values: Vec<int> = map.values().map(|&v| v).collect();
+    /// assert_eq!(values, vec![0, 1, 2, 0, 0, 0, 6, 7, 8, 9]);
+    /// ```
+    pub fn mut_range<'a>(&'a mut self, min: Bound<&K>, max: Bound<&K>)
+                         -> MutRangeEntries<'a, K, V> {
+        let start = self.range_start(min);
+        let end = self.range_end(max);
+        if bounds_cross(min, max) {
+            let iter = MutEntries {
+                stack: vec!(),
+                node: ptr::mut_null(),
+                remaining_min: 0,
+                remaining_max: 0
+            };
+            let back = MutEntries {
+                stack: vec!(),
+                node: ptr::mut_null(),
+                remaining_min: 0,
+                remaining_max: 0
+            };
+            return MutRangeEntries { iter: iter, back: back, start: start, end: end }
+        }
+        // Both ends walk down the same nodes, but the links between them
+        // can't be changed while the map is borrowed, and the ends stop
+        // before yielding the same entry twice, so no value is ever
+        // borrowed through both.
+        let this: *mut TreeMap<K, V> = self;
+        let iter = match min {
+            Unbounded => self.mut_iter(),
+            Included(k) => self.mut_lower_bound(k),
+            Excluded(k) => self.mut_upper_bound(k),
+        };
+        let back = unsafe {
+            match max {
+                Unbounded => (*this).mut_iter(),
+                Included(k) => (*this).mut_rev_bound(k, true),
+                Excluded(k) => (*this).mut_rev_bound(k, false),
+            }
+        };
+        MutRangeEntries { iter: iter, back: back, start: start, end: end }
+    }
+
+    fn empty_entries<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            stack: vec!(),
+            node: ptr::null(),
+            remaining_min: 0,
+            remaining_max: 0
+        }
+    }
+
+    // Returns an iterator to be driven backwards with `next_(false)` from the
+    // last entry whose key is not greater than `k`, or less than `k` if
+    // `inclusive` is false
+    fn rev_bound<'a>(&'a self, k: &K, inclusive: bool) -> Entries<'a, K, V> {
+        rev_bound_setup!(self.iter_for_traversal(), k, inclusive)
+    }
+
+    fn mut_rev_bound<'a>(&'a mut self, k: &K, inclusive: bool) -> MutEntries<'a, K, V> {
+        rev_bound_setup!(self.mut_iter_for_traversal(), k, inclusive)
+    }
+
+    // Returns the key of the last entry before the lower bound of a range, or
+    // null if there is none.
+    fn range_start(&self, min: Bound<&K>) -> *const K {
+        let mut iter = match min {
+            Unbounded => return ptr::null(),
+            Included(k) => self.rev_bound(k, false),
+            Excluded(k) => self.rev_bound(k, true),
+        };
+        match iter.next_(false) {
+            Some((k, _)) => k as *const K,
+            None => ptr::null(),
+        }
+    }
+
+    // Returns the key of the first entry past the upper bound of a range, or
+    // null if there is none.
+    fn range_end(&self, max: Bound<&K>) -> *const K {
+        let mut iter = match max {
+            Unbounded => return ptr::null(),
+            Included(k) => self.upper_bound(k),
+            Excluded(k) => self.lower_bound(k),
+        };
+        match iter.next() {
+            Some((k, _)) => k as *const K,
+            None => ptr::null(),
+        }
+    }
+
+    /// Splits the map in two at the given key. Every entry whose key is not
+    /// less than `key` is moved into the returned map, and the rest are kept.
+    ///
+    /// The tree is cut along the path to `key` in logarithmic time, without
+    /// moving any entry. Finding the sizes of the halves then takes time
+    /// linear in the size of the smaller one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    ///
+    /// let mut a: TreeMap<int, &str> = vec![(1, "a"), (2, "b"), (3, "c")]
+    ///                                     .move_iter().collect();
+    /// let b = a.split_off(&2);
+    ///
+    /// assert_eq!(a.keys().map(|&k| k).collect::<Vec<int>>(), vec![1]);
+    /// assert_eq!(b.keys().map(|&k| k).collect::<Vec<int>>(), vec![2, 3]);
+    /// ```
+    pub fn split_off(&mut self, key: &K) -> TreeMap<K, V> {
+        let (head, found, tail) = split_at(self.root.take(), key);
+        let tail = match found {
+            Some(node) => join(None, node, tail),
+            None => tail,
+        };
+        let head_len = count_first(&head, &tail, self.length);
+        let tail_len = self.length - head_len;
+        self.root = head;
+        self.length = head_len;
+        TreeMap { root: tail, length: tail_len }
+    }
+
+    /// Moves every entry of `other` into this map, leaving `other` empty. The
+    /// value from `other` is kept for keys present in both maps.
+    ///
+    /// The trees are merged by cutting this one around the keys of `other`,
+    /// without moving any entry, in O(m log(n / m + 1)) time for maps of
+    /// `m` and `n` entries with `m <= n`. When every key of one map is less
+    /// than every key of the other, the trees are joined in O(log(n)^2)
+    /// time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    ///
+    /// let mut a: TreeMap<int, &str> = vec![(1, "a"), (2, "b")].move_iter().collect();
+    /// let mut b: TreeMap<int, &str> = vec![(2, "x"), (3, "c")].move_iter().collect();
+    /// a.append(&mut b);
+    ///
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.move_iter().collect::<Vec<(int, &str)>>(),
+    ///            vec![(1, "a"), (2, "x"), (3, "c")]);
+    /// ```
+    pub fn append(&mut self, other: &mut TreeMap<K, V>) {
+        if other.is_empty() { return }
+        if self.is_empty() { return swap(self, other) }
+
+        let mut shared = 0;
+        let root = union(self.root.take(), other.root.take(), &mut shared);
+        self.root = root;
+        self.length += other.length - shared;
+        other.length = 0;
+    }
+
+    /// Retains only the entries for which `f` returns `true`, visiting them
+    /// in ascending order. `f` may also modify the values it is given.
+    ///
+    /// The entries are removed in place, and the remaining nodes joined back
+    /// together, in time linear in the size of the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    ///
+    /// let mut map: TreeMap<int, int> = range(0i, 8).map(|i| (i, i)).collect();
+    /// map.retain(|&k, v| { *v *= 10; k % 2 == 0 });
+    ///
+    /// assert_eq!(map.move_iter().collect::<Vec<(int, int)>>(),
+    ///            vec![(0, 0), (2, 20), (4, 40), (6, 60)]);
+    /// ```
+    pub fn retain(&mut self, mut f: |&K, &mut V| -> bool) {
+        let mut kept = 0;
+        let root = retain(self.root.take(), &mut f, &mut kept);
+        self.root = root;
+        self.length = kept;
+    }
+
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation. The tree is searched once; inserting or removing
+    /// through the entry rebalances along the remembered path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeMap;
+    /// use std::collections::treemap::{Occupied, Vacant};
+    ///
+    /// let mut count: TreeMap<&str, uint> = TreeMap::new();
+    ///
+    /// for x in vec!["a", "b", "a", "c", "a"].move_iter() {
+    ///     match count.entry(x) {
+    ///         Vacant(entry) => { entry.insert(1); }
+    ///         Occupied(mut entry) => { *entry.get_mut() += 1; }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(count["a"], 3);
+    /// assert_eq!(count["c"], 1);
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: K) -> Entry<'a, K, V> {
+        let mut path = Vec::new();
+        let mut slot: *mut Option<Box<TreeNode<K, V>>> = &mut self.root;
+        loop {
+            path.push(slot);
+            let next: *mut Option<Box<TreeNode<K, V>>> = match unsafe { &mut *slot } {
+                &Some(ref mut node) => match key.cmp(&node.key) {
+                    Less => &mut node.left,
+                    Greater => &mut node.right,
+                    Equal => return Occupied(OccupiedEntry { map: self, path: path }),
+                },
+                &None => return Vacant(VacantEntry { map: self, path: path, key: key }),
+            };
+            slot = next;
+        }
+    }
+}
+
+/// A view into a single node of a `TreeMap`, as returned by `entry`.
+pub enum Entry<'a, K:'a, V:'a> {
+    /// The key is present in the map.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// The key is absent from the map.
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// A handle to a present entry of a `TreeMap`.
+pub struct OccupiedEntry<'a, K:'a, V:'a> {
+    map: &'a mut TreeMap<K, V>,
+    // The links followed from the root, ending with the one holding the entry
+    path: Vec<*mut Option<Box<TreeNode<K, V>>>>,
+}
+
+/// A handle to an absent entry of a `TreeMap`.
+pub struct VacantEntry<'a, K:'a, V:'a> {
+    map: &'a mut TreeMap<K, V>,
+    // The links followed from the root, ending with the empty one where the
+    // key belongs
+    path: Vec<*mut Option<Box<TreeNode<K, V>>>>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    fn node(&self) -> *mut TreeNode<K, V> {
+        let slot = *self.path.last().unwrap();
+        let node: &mut TreeNode<K, V> = unsafe { &mut **(*slot).as_mut().unwrap() };
+        node as *mut TreeNode<K, V>
+    }
+
+    /// Returns a reference to the entry's value.
+    pub fn get<'b>(&'b self) -> &'b V {
+        unsafe { &(*self.node()).value }
+    }
+
+    /// Returns a mutable reference to the entry's value.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut V {
+        unsafe { &mut (*self.node()).value }
+    }
+
+    /// Converts the entry into a mutable reference bound to the map.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut (*self.node()).value }
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn set(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    pub fn take(self) -> V {
+        let OccupiedEntry { map, mut path } = self;
+        let slot = *path.last().unwrap();
+        let has_left = unsafe { (*slot).as_ref().unwrap().left.is_some() };
+
+        let value = if has_left {
+            // Move the in-order predecessor, the rightmost node of the left
+            // subtree, into this node and unlink it instead
+            let node = unsafe { (*slot).as_mut().unwrap() };
+            let mut heir: *mut Option<Box<TreeNode<K, V>>> = &mut node.left;
+            loop {
+                path.push(heir);
+                let right: *mut Option<Box<TreeNode<K, V>>> =
+                    unsafe { &mut (*heir).as_mut().unwrap().right };
+                if unsafe { (*right).is_none() } { break }
+                heir = right;
+            }
+            let box TreeNode { key, value, left, .. } = unsafe { (*heir).take().unwrap() };
+            unsafe { *heir = left; }
+            node.key = key;
+            replace(&mut node.value, value)
+        } else {
+            let box TreeNode { value, right, .. } = unsafe { (*slot).take().unwrap() };
+            unsafe { *slot = right; }
+            value
+        };
+
+        // Every link above the one that was unlinked still holds a node
+        for &slot in path.init().iter().rev() {
+            rebalance_after_remove(unsafe { (*slot).as_mut().unwrap() });
+        }
+        map.length -= 1;
+        value
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    /// Inserts `value` under the entry's key, returning a mutable
+    /// reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry { map, path, key } = self;
+        let slot = *path.last().unwrap();
+        // Nodes stay put in their boxes while the tree is rotated around
+        // them, so the value can be located before rebalancing
+        let value: *mut V = unsafe {
+            *slot = Some(box TreeNode::new(key, value));
+            &mut (*slot).as_mut().unwrap().value
+        };
+        for &slot in path.init().iter().rev() {
+            let node = unsafe { (*slot).as_mut().unwrap() };
+            skew(node);
+            split(node);
+        }
+        map.length += 1;
+        unsafe { &mut *value }
+    }
+}
+
+// Whether a range is empty no matter what the map holds, because its lower
+// bound lies past its upper bound
+fn bounds_cross<K: Ord>(min: Bound<&K>, max: Bound<&K>) -> bool {
+    match (min, max) {
+        (Included(a), Included(b)) => a > b,
+        (Included(a), Excluded(b)) |
+        (Excluded(a), Included(b)) |
+        (Excluded(a), Excluded(b)) => a >= b,
+        _ => false,
+    }
+}
+
 /// Lazy forward iterator over a map
 pub struct Entries<'a, K:'a, V:'a> {
     stack: Vec<&'a TreeNode<K, V>>,
@@ -717,6 +1157,105 @@ pub struct RevMutEntries<'a, K:'a, V:'a> {
     iter: MutEntries<'a, K, V>,
 }
 
+/// Lazy double-ended iterator over the entries of a map whose keys lie
+/// within a range
+pub struct RangeEntries<'a, K:'a, V:'a> {
+    iter: Entries<'a, K, V>,
+    // Driven backwards with `next_(false)`
+    back: Entries<'a, K, V>,
+    // The keys of the entries just outside what is left of the range, or null
+    // where it runs to the end of the map. They're only ever compared
+    // against, never read.
+    start: *const K,
+    end: *const K,
+}
+
+/// Lazy double-ended iterator over the entries of a map whose keys lie
+/// within a range, with the values being mutable
+pub struct MutRangeEntries<'a, K:'a, V:'a> {
+    iter: MutEntries<'a, K, V>,
+    back: MutEntries<'a, K, V>,
+    start: *const K,
+    end: *const K,
+}
+
+impl<'a, K, V> Iterator<(&'a K, &'a V)> for RangeEntries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        match self.iter.next() {
+            Some((k, v)) if k as *const K != self.end => {
+                self.start = k as *const K;
+                Some((k, v))
+            }
+            _ => {
+                // Don't run on past the end of the range
+                self.iter.stack.clear();
+                self.iter.node = ptr::null();
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let (_, max) = self.iter.size_hint();
+        (0, max)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a V)> for RangeEntries<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        match self.back.next_(false) {
+            Some((k, v)) if k as *const K != self.start => {
+                self.end = k as *const K;
+                Some((k, v))
+            }
+            _ => {
+                self.back.stack.clear();
+                self.back.node = ptr::null();
+                None
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Iterator<(&'a K, &'a mut V)> for MutRangeEntries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        match self.iter.next() {
+            Some((k, v)) if k as *const K != self.end => {
+                self.start = k as *const K;
+                Some((k, v))
+            }
+            _ => {
+                self.iter.stack.clear();
+                self.iter.node = ptr::mut_null();
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let (_, max) = self.iter.size_hint();
+        (0, max)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a mut V)> for MutRangeEntries<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        match self.back.next_(false) {
+            Some((k, v)) if k as *const K != self.start => {
+                self.end = k as *const K;
+                Some((k, v))
+            }
+            _ => {
+                self.back.stack.clear();
+                self.back.node = ptr::mut_null();
+                None
+            }
+        }
+    }
+}
+
 /// TreeMap keys iterator.
 pub type Keys<'a, K, V> =
     iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
@@ -807,6 +1346,22 @@ macro_rules! define_iterator {
                 self.node = $deref(addr!(& $($addr_mut)* node.right));
             }
 
+            /// traverse_left_rev and traverse_right_rev are their mirror
+            /// images, for initializing an iterator which is then driven
+            /// backwards with `next_(false)`.
+            #[inline]
+            fn traverse_left_rev(&mut self) {
+                let node = unsafe {addr!(& $($addr_mut)* *self.node)};
+                self.node = $deref(addr!(& $($addr_mut)* node.left));
+            }
+
+            #[inline]
+            fn traverse_right_rev(&mut self) {
+                let node = unsafe {addr!(& $($addr_mut)* *self.node)};
+                self.node = $deref(addr!(& $($addr_mut)* node.right));
+                self.stack.push(node);
+            }
+
             #[inline]
             fn traverse_complete(&mut self) {
                 if !self.node.is_null() {
@@ -941,6 +1496,20 @@ impl<'a, T> Iterator<&'a T> for SetItems<'a, T> {
     }
 }
 
+impl<'a, T> Iterator<&'a T> for SetRangeItems<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(value, _)| value)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator<&'a T> for SetRangeItems<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back().map(|(value, _)| value)
+    }
+}
+
 impl<'a, T> Iterator<&'a T> for RevSetItems<'a, T> {
     #[inline]
     fn next(&mut self) -> Option<&'a T> {
@@ -1220,6 +1789,76 @@ impl<T: Ord> TreeSet<T> {
         SetItems{iter: self.map.upper_bound(v)}
     }
 
+    /// Gets a lazy iterator over the values which lie between `min` and
+    /// `max`, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeSet;
+    /// use std::collections::{Included, Excluded};
+    ///
+    /// let set: TreeSet<int> = [2, 4, 6, 8].iter().map(|&x| x).collect();
+    /// let v: Vec<int> = set.range(Excluded(&2), Included(&6)).map(|&x| x).collect();
+    /// assert_eq!(v, vec![4, 6]);
+    /// ```
+    #[inline]
+    pub fn range<'a>(&'a self, min: Bound<&T>, max: Bound<&T>) -> SetRangeItems<'a, T> {
+        SetRangeItems{iter: self.map.range(min, max)}
+    }
+
+    /// Splits the set in two at the given value, moving every value not less
+    /// than `v` into the returned set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeSet;
+    ///
+    /// let mut a: TreeSet<int> = range(0i, 6).collect();
+    /// let b = a.split_off(&4);
+    /// assert_eq!(a.move_iter().collect::<Vec<int>>(), vec![0, 1, 2, 3]);
+    /// assert_eq!(b.move_iter().collect::<Vec<int>>(), vec![4, 5]);
+    /// ```
+    #[inline]
+    pub fn split_off(&mut self, v: &T) -> TreeSet<T> {
+        TreeSet{map: self.map.split_off(v)}
+    }
+
+    /// Moves every value of `other` into this set, leaving `other` empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeSet;
+    ///
+    /// let mut a: TreeSet<int> = [1, 3].iter().map(|&x| x).collect();
+    /// let mut b: TreeSet<int> = [2, 3, 4].iter().map(|&x| x).collect();
+    /// a.append(&mut b);
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.move_iter().collect::<Vec<int>>(), vec![1, 2, 3, 4]);
+    /// ```
+    #[inline]
+    pub fn append(&mut self, other: &mut TreeSet<T>) {
+        self.map.append(&mut other.map)
+    }
+
+    /// Retains only the values for which `f` returns `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TreeSet;
+    ///
+    /// let mut set: TreeSet<int> = range(0i, 10).collect();
+    /// set.retain(|&x| x % 3 == 0);
+    /// assert_eq!(set.move_iter().collect::<Vec<int>>(), vec![0, 3, 6, 9]);
+    /// ```
+    #[inline]
+    pub fn retain(&mut self, f: |&T| -> bool) {
+        self.map.retain(|k, _| f(k))
+    }
+
     /// Visits the values representing the difference, in ascending order.
     ///
     /// # Example
@@ -1329,6 +1968,11 @@ pub struct RevSetItems<'a, T:'a> {
     iter: RevEntries<'a, T, ()>
 }
 
+/// A lazy double-ended iterator over the values of a set within a range.
+pub struct SetRangeItems<'a, T:'a> {
+    iter: RangeEntries<'a, T, ()>
+}
+
 /// A lazy forward iterator over a set that consumes the set while iterating.
 pub type MoveSetItems<T> = iter::Map<'static, (T, ()), T, MoveEntries<T, ()>>;
 
@@ -1585,36 +2229,201 @@ fn remove<K: Ord, V>(node: &mut Option<Box<TreeNode<K, V>>>,
         };
 
         if rebalance {
-            let left_level = save.left.as_ref().map_or(0, |x| x.level);
-            let right_level = save.right.as_ref().map_or(0, |x| x.level);
+            rebalance_after_remove(save);
+            return ret;
+        }
+      }
+    }
+    return match node.take() {
+        Some(box TreeNode{value, ..}) => Some(value), None => fail!()
+    };
+}
 
-            // re-balance, if necessary
-            if left_level < save.level - 1 || right_level < save.level - 1 {
-                save.level -= 1;
+// Restores the AA invariants at a node after an entry was removed from one
+// of its subtrees
+fn rebalance_after_remove<K: Ord, V>(save: &mut Box<TreeNode<K, V>>) {
+    let left_level = save.left.as_ref().map_or(0, |x| x.level);
+    let right_level = save.right.as_ref().map_or(0, |x| x.level);
 
-                if right_level > save.level {
-                    let save_level = save.level;
-                    for x in save.right.mut_iter() { x.level = save_level }
-                }
+    // re-balance, if necessary
+    if left_level < save.level - 1 || right_level < save.level - 1 {
+        save.level -= 1;
 
-                skew(save);
+        if right_level > save.level {
+            let save_level = save.level;
+            for x in save.right.mut_iter() { x.level = save_level }
+        }
 
-                for right in save.right.mut_iter() {
-                    skew(right);
-                    for x in right.right.mut_iter() { skew(x) }
-                }
+        skew(save);
 
-                split(save);
-                for x in save.right.mut_iter() { split(x) }
-            }
+        for right in save.right.mut_iter() {
+            skew(right);
+            for x in right.right.mut_iter() { skew(x) }
+        }
 
-            return ret;
+        split(save);
+        for x in save.right.mut_iter() { split(x) }
+    }
+}
+
+fn level<K, V>(node: &Option<Box<TreeNode<K, V>>>) -> uint {
+    node.as_ref().map_or(0, |x| x.level)
+}
+
+// Joins two trees around a node whose key lies between the keys of the left
+// tree and those of the right one. The node is inserted where the spine of
+// the taller tree reaches the level of the other one, and the path back up
+// is rebalanced like after an insertion, so this takes time proportional to
+// the difference of the levels of the trees.
+fn join<K: Ord, V>(left: Option<Box<TreeNode<K, V>>>,
+                   mut mid: Box<TreeNode<K, V>>,
+                   right: Option<Box<TreeNode<K, V>>>)
+                   -> Option<Box<TreeNode<K, V>>> {
+    let (left_level, right_level) = (level(&left), level(&right));
+    if left_level > right_level {
+        let mut node = left.unwrap();
+        let node_right = node.right.take();
+        node.right = join(node_right, mid, right);
+        skew(&mut node);
+        split(&mut node);
+        Some(node)
+    } else if left_level < right_level {
+        let mut node = right.unwrap();
+        let node_left = node.left.take();
+        node.left = join(left, mid, node_left);
+        skew(&mut node);
+        split(&mut node);
+        Some(node)
+    } else {
+        mid.left = left;
+        mid.right = right;
+        mid.level = left_level + 1;
+        Some(mid)
+    }
+}
+
+// Joins two trees, the keys of the left one all being less than those of the
+// right one, around the smallest node of the right one
+fn join2<K: Ord, V>(left: Option<Box<TreeNode<K, V>>>,
+                    right: Option<Box<TreeNode<K, V>>>)
+                    -> Option<Box<TreeNode<K, V>>> {
+    match right {
+        Some(right) => {
+            let (rest, min) = pop_min(right);
+            join(left, min, rest)
         }
-      }
+        None => left,
     }
-    return match node.take() {
-        Some(box TreeNode{value, ..}) => Some(value), None => fail!()
+}
+
+// Unlinks the smallest node of a tree, returning the rest of the tree and
+// the node
+fn pop_min<K: Ord, V>(mut node: Box<TreeNode<K, V>>)
+                      -> (Option<Box<TreeNode<K, V>>>, Box<TreeNode<K, V>>) {
+    match node.left.take() {
+        Some(left) => {
+            let (rest, min) = pop_min(left);
+            node.left = rest;
+            rebalance_after_remove(&mut node);
+            (Some(node), min)
+        }
+        None => (node.right.take(), node),
+    }
+}
+
+// Cuts a tree into the nodes whose keys are less than `key`, the node whose
+// key is equal to it if there is one, and the nodes whose keys are greater,
+// in logarithmic time: the joins along the path to `key` take time
+// proportional to the differences of the levels of the subtrees they join,
+// which add up to the height of the tree.
+fn split_at<K: Ord, V>(node: Option<Box<TreeNode<K, V>>>, key: &K)
+                       -> (Option<Box<TreeNode<K, V>>>,
+                           Option<Box<TreeNode<K, V>>>,
+                           Option<Box<TreeNode<K, V>>>) {
+    let mut node = match node {
+        Some(node) => node,
+        None => return (None, None, None),
     };
+    let left = node.left.take();
+    let right = node.right.take();
+    match key.cmp(&node.key) {
+        Less => {
+            let (less, found, greater) = split_at(left, key);
+            (less, found, join(greater, node, right))
+        }
+        Greater => {
+            let (less, found, greater) = split_at(right, key);
+            (join(left, node, less), found, greater)
+        }
+        Equal => (left, Some(node), right),
+    }
+}
+
+// Merges two trees, keeping the nodes of `b` for the keys in both, which are
+// counted in `shared`
+fn union<K: Ord, V>(a: Option<Box<TreeNode<K, V>>>,
+                    b: Option<Box<TreeNode<K, V>>>,
+                    shared: &mut uint) -> Option<Box<TreeNode<K, V>>> {
+    let mut b = match b {
+        Some(b) => b,
+        None => return a,
+    };
+    if a.is_none() { return Some(b) }
+    let (less, found, greater) = split_at(a, &b.key);
+    if found.is_some() { *shared += 1 }
+    let b_left = b.left.take();
+    let b_right = b.right.take();
+    let left = union(less, b_left, shared);
+    let right = union(greater, b_right, shared);
+    join(left, b, right)
+}
+
+// Removes the nodes of a tree for which `f` returns false, visiting them in
+// ascending order, and counts the others in `kept`
+fn retain<K: Ord, V>(node: Option<Box<TreeNode<K, V>>>,
+                     f: &mut |&K, &mut V| -> bool,
+                     kept: &mut uint) -> Option<Box<TreeNode<K, V>>> {
+    let mut node = match node {
+        Some(node) => node,
+        None => return None,
+    };
+    let left = retain(node.left.take(), f, kept);
+    let keep = (*f)(&node.key, &mut node.value);
+    let right = retain(node.right.take(), f, kept);
+    if keep {
+        *kept += 1;
+        join(left, node, right)
+    } else {
+        join2(left, right)
+    }
+}
+
+// Counts the nodes of a tree, up to `limit`
+fn count_up_to<K, V>(node: &Option<Box<TreeNode<K, V>>>, limit: uint) -> uint {
+    if limit == 0 { return 0 }
+    match *node {
+        Some(ref node) => {
+            let left = count_up_to(&node.left, limit);
+            if left == limit { return limit }
+            left + 1 + count_up_to(&node.right, limit - left - 1)
+        }
+        None => 0,
+    }
+}
+
+// Returns the number of nodes of `a`, given that `a` and `b` have `total`
+// nodes between them, in time linear in the size of the smaller tree
+fn count_first<K, V>(a: &Option<Box<TreeNode<K, V>>>,
+                     b: &Option<Box<TreeNode<K, V>>>,
+                     total: uint) -> uint {
+    let mut limit = 1;
+    loop {
+        let n = count_up_to(a, limit);
+        if n < limit { return n }
+        let n = count_up_to(b, limit);
+        if n < limit { return total - n }
+        limit *= 2;
+    }
 }
 
 impl<K: Ord, V> FromIterator<(K, V)> for TreeMap<K, V> {
@@ -1674,7 +2483,8 @@ mod test_treemap {
     use std::rand;
 
     use {Map, MutableMap, Mutable, MutableSeq};
-    use super::{TreeMap, TreeNode};
+    use {Included, Excluded, Unbounded};
+    use super::{TreeMap, TreeNode, Occupied, Vacant};
 
     #[test]
     fn find_empty() {
@@ -2183,6 +2993,256 @@ mod test_treemap {
 
         map[4];
     }
+
+    #[test]
+    fn test_range() {
+        let m: TreeMap<int, int> = range(0i, 50).map(|i| (i * 2, i)).collect();
+
+        // Every combination of bounds, falling both on and between keys
+        for lo in range(-1i, 102) {
+            for hi in range(-1i, 102) {
+                let bounds = [(Included(&lo), Included(&hi)),
+                              (Included(&lo), Excluded(&hi)),
+                              (Excluded(&lo), Included(&hi)),
+                              (Excluded(&lo), Excluded(&hi)),
+                              (Unbounded, Included(&hi)),
+                              (Included(&lo), Unbounded)];
+                for &(min, max) in bounds.iter() {
+                    let got: Vec<int> = m.range(min, max).map(|(&k, _)| k).collect();
+                    let want: Vec<int> = m.keys().map(|&k| k).filter(|&k| {
+                        let above = match min {
+                            Included(&b) => k >= b,
+                            Excluded(&b) => k > b,
+                            Unbounded => true,
+                        };
+                        let below = match max {
+                            Included(&b) => k <= b,
+                            Excluded(&b) => k < b,
+                            Unbounded => true,
+                        };
+                        above && below
+                    }).collect();
+                    assert_eq!(got, want);
+
+                    let mut back: Vec<int> = m.range(min, max).rev().map(|(&k, _)| k)
+                                              .collect();
+                    back.reverse();
+                    assert_eq!(back, want);
+
+                    // Both ends at once
+                    let mut it = m.range(min, max);
+                    let (mut front, mut back) = (vec![], vec![]);
+                    loop {
+                        match it.next() {
+                            Some((&k, _)) => front.push(k),
+                            None => break,
+                        }
+                        match it.next_back() {
+                            Some((&k, _)) => back.push(k),
+                            None => break,
+                        }
+                    }
+                    assert!(it.next().is_none());
+                    assert!(it.next_back().is_none());
+                    back.reverse();
+                    front.push_all(back.as_slice());
+                    assert_eq!(front, want);
+                }
+            }
+        }
+
+        let all: Vec<int> = m.range(Unbounded, Unbounded).map(|(&k, _)| k).collect();
+        assert_eq!(all, m.keys().map(|&k| k).collect());
+
+        let mut it = m.range(Included(&10), Included(&12));
+        assert!(it.next().is_some());
+        assert!(it.next().is_some());
+        assert!(it.next().is_none());
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_mut_range() {
+        let mut m: TreeMap<int, int> = range(0i, 100).map(|i| (i, 0)).collect();
+        for (_, v) in m.mut_range(Excluded(&20), Included(&30)) {
+            *v = 1;
+        }
+        assert!(m.mut_range(Included(&30), Excluded(&30)).next().is_none());
+        for (_, v) in m.mut_range(Included(&90), Unbounded).rev().take(5) {
+            *v = 2;
+        }
+        assert!(m.mut_range(Included(&30), Excluded(&30)).next_back().is_none());
+        for (&k, &v) in m.iter() {
+            assert_eq!(v, if k > 20 && k <= 30 { 1 } else if k >= 95 { 2 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_split_off() {
+        for n in range(0i, 40) {
+            for at in range(-1i, n + 2) {
+                let mut a: TreeMap<int, int> = range(0i, n).map(|i| (i, -i)).collect();
+                let b = a.split_off(&at);
+                check_structure(&a);
+                check_structure(&b);
+                assert!(a.iter().all(|(&k, &v)| k < at && v == -k));
+                assert!(b.iter().all(|(&k, &v)| k >= at && v == -k));
+                assert_eq!(a.len() + b.len(), n as uint);
+                assert_eq!(a.iter().count(), a.len());
+                assert_eq!(b.iter().count(), b.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a: TreeMap<int, int> = range(0i, 100).map(|i| (i * 2, 0)).collect();
+        let mut b: TreeMap<int, int> = range(0i, 100).map(|i| (i * 3, 1)).collect();
+        a.append(&mut b);
+        check_structure(&a);
+        assert!(b.is_empty());
+
+        let keys: Vec<int> = a.keys().map(|&k| k).collect();
+        let want: Vec<int> = range(0i, 300).filter(|k| k % 2 == 0 || k % 3 == 0)
+                                           .collect();
+        assert_eq!(keys, want);
+        assert_eq!(a.len(), want.len());
+        for (&k, &v) in a.iter() {
+            assert_eq!(v, if k % 3 == 0 { 1 } else { 0 });
+        }
+
+        // Maps whose keys don't overlap, of very different sizes
+        for &(n, m) in [(1000i, 1i), (1, 1000), (500, 700)].iter() {
+            let mut low: TreeMap<int, int> = range(0, n).map(|i| (i, 0)).collect();
+            let mut high: TreeMap<int, int> = range(n, n + m).map(|i| (i, 1)).collect();
+            high.append(&mut low);
+            check_structure(&high);
+            assert_eq!(high.len(), (n + m) as uint);
+            assert_eq!(high.keys().map(|&k| k).collect::<Vec<int>>(),
+                       range(0, n + m).collect::<Vec<int>>());
+        }
+
+        // Appending into an empty map
+        let mut c = TreeMap::new();
+        c.append(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(c.len(), want.len());
+        a.append(&mut TreeMap::new());
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_split_off_append_rand() {
+        let seed: &[_] = &[7];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+        for _ in range(0u, 50) {
+            let n = rng.gen_range(0u, 2000);
+            let keys: Vec<int> = Vec::from_fn(n, |_| rng.gen_range(0i, 5000));
+            let mut a: TreeMap<int, int> = keys.iter().map(|&k| (k, k)).collect();
+            let len = a.len();
+            let at = rng.gen_range(-10i, 5010);
+            let mut b = a.split_off(&at);
+            check_structure(&a);
+            check_structure(&b);
+            assert_eq!(a.len(), a.iter().count());
+            assert_eq!(b.len(), b.iter().count());
+            assert!(a.keys().all(|&k| k < at));
+            assert!(b.keys().all(|&k| k >= at));
+
+            let mut c: TreeMap<int, int> = keys.iter().take(100).map(|&k| (k, -k)).collect();
+            b.append(&mut c);
+            a.append(&mut b);
+            check_structure(&a);
+            assert_eq!(a.len(), len);
+            assert_eq!(a.len(), a.iter().count());
+            for &k in keys.iter().take(100) {
+                assert_eq!(a.find(&k), Some(&-k));
+            }
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut m: TreeMap<int, int> = range(0i, 1000).map(|i| (i, i)).collect();
+        let mut seen = 0i;
+        m.retain(|&k, v| {
+            assert_eq!(k, seen);
+            seen += 1;
+            *v += 1;
+            k % 7 == 0
+        });
+        check_structure(&m);
+        assert_eq!(m.len(), 143);
+        for (&k, &v) in m.iter() {
+            assert!(k % 7 == 0);
+            assert_eq!(v, k + 1);
+        }
+        // The map is still usable after entries were removed
+        assert!(m.insert(1, 1));
+        assert!(m.pop(&700).is_some());
+        check_structure(&m);
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut m: TreeMap<int, int> = range(0i, 6).map(|i| (i, i * 10)).collect();
+
+        match m.entry(1) {
+            Occupied(mut e) => {
+                assert_eq!(*e.get(), 10);
+                assert_eq!(e.set(100), 10);
+                *e.get_mut() += 1;
+            }
+            Vacant(_) => fail!()
+        }
+        assert_eq!(m[1], 101);
+
+        match m.entry(10) {
+            Vacant(e) => *e.insert(1000) += 1,
+            Occupied(_) => fail!()
+        }
+        assert_eq!(m[10], 1001);
+        assert_eq!(m.len(), 7);
+        check_structure(&m);
+
+        match m.entry(3) {
+            Occupied(e) => assert_eq!(e.take(), 30),
+            Vacant(_) => fail!()
+        }
+        assert!(m.find(&3).is_none());
+        assert_eq!(m.len(), 6);
+        check_structure(&m);
+    }
+
+    #[test]
+    fn test_entry_rand() {
+        let mut map: TreeMap<uint, uint> = TreeMap::new();
+        let mut ctrl: TreeMap<uint, uint> = TreeMap::new();
+
+        let seed: &[_] = &[7];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+
+        for _ in range(0u, 2000) {
+            let k = rng.gen_range(0u, 200);
+            match map.entry(k) {
+                Occupied(e) => {
+                    if rng.gen() {
+                        assert_eq!(e.take(), ctrl.pop(&k).unwrap());
+                    } else {
+                        *e.into_mut() += 1;
+                        *ctrl.find_mut(&k).unwrap() += 1;
+                    }
+                }
+                Vacant(e) => {
+                    assert!(ctrl.insert(k, k));
+                    assert_eq!(*e.insert(k), k);
+                }
+            }
+            check_structure(&map);
+            assert_eq!(map.len(), ctrl.len());
+        }
+        assert!(map.iter().zip(ctrl.iter()).all(|(a, b)| a == b));
+    }
 }
 
 #[cfg(test)]
@@ -2251,6 +3311,7 @@ mod test_set {
     use std::hash;
 
     use {Set, MutableSet, Mutable, MutableMap, MutableSeq};
+    use {Included, Excluded};
     use super::{TreeMap, TreeSet};
 
     #[test]
@@ -2363,6 +3424,28 @@ mod test_set {
         }
     }
 
+    #[test]
+    fn test_range_split_append_retain() {
+        let mut s: TreeSet<int> = range(0i, 20).collect();
+        let v: Vec<int> = s.range(Included(&5), Excluded(&8)).map(|&x| x).collect();
+        assert_eq!(v, vec![5, 6, 7]);
+        let v: Vec<int> = s.range(Included(&5), Excluded(&8)).rev().map(|&x| x).collect();
+        assert_eq!(v, vec![7, 6, 5]);
+
+        let mut t = s.split_off(&10);
+        assert_eq!(s.len(), 10);
+        assert_eq!(t.len(), 10);
+        assert!(t.iter().all(|&x| x >= 10));
+
+        t.retain(|&x| x % 2 == 0);
+        assert_eq!(t.iter().map(|&x| x).collect::<Vec<int>>(), vec![10, 12, 14, 16, 18]);
+
+        s.append(&mut t);
+        assert!(t.is_empty());
+        assert_eq!(s.len(), 15);
+        assert!(s.contains(&18) && !s.contains(&19));
+    }
+
     #[test]
     fn test_move_iter_size_hint() {
         let s: TreeSet<int> = vec!(0i, 1).move_iter().collect();
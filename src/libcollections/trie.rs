@@ -23,9 +23,10 @@ use core::uint;
 use core::iter;
 use std::hash::{Writer, Hash};
 
-use {Mutable, Map, MutableMap, Set, MutableSet};
+use {Mutable, Map, MutableMap, Set, MutableSet, MutableSeq};
 use slice::{Items, MutItems};
 use slice;
+use vec::Vec;
 
 // FIXME: #5244: need to manually update the TrieNode constructor
 static SHIFT: uint = 4;
@@ -288,6 +289,51 @@ impl<T> TrieMap<T> {
 
         iter
     }
+
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation. The trie is walked once; the entry remembers the
+    /// child slots it passed through.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::TrieMap;
+    /// use std::collections::trie::{Occupied, Vacant};
+    ///
+    /// let mut count: TrieMap<uint> = TrieMap::new();
+    ///
+    /// for &x in [1u, 2, 1, 3, 1].iter() {
+    ///     match count.entry(x) {
+    ///         Vacant(entry) => { entry.insert(1); }
+    ///         Occupied(mut entry) => { *entry.get_mut() += 1; }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(count[1], 3);
+    /// assert_eq!(count[3], 1);
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: uint) -> Entry<'a, T> {
+        let mut path = Vec::new();
+        let mut count: *mut uint = &mut self.root.count;
+        let mut child: *mut Child<T> = &mut self.root.children[chunk(key, 0)];
+        let mut idx = 1;
+        loop {
+            path.push((count, child));
+            let (next_count, next_child) = match unsafe { &mut *child } {
+                &Internal(box ref mut x) => {
+                    (&mut x.count as *mut uint,
+                     &mut x.children[chunk(key, idx)] as *mut Child<T>)
+                }
+                &External(stored, _) if stored == key => {
+                    return Occupied(OccupiedEntry { map: self, path: path });
+                }
+                _ => return Vacant(VacantEntry { map: self, path: path, key: key, idx: idx }),
+            };
+            count = next_count;
+            child = next_child;
+            idx += 1;
+        }
+    }
 }
 
 // FIXME #5846 we want to be able to choose between &x and &mut x
@@ -857,6 +903,104 @@ fn remove<T>(count: &mut uint, child: &mut Child<T>, key: uint,
     return ret;
 }
 
+/// A view into a single slot of a `TrieMap`, as returned by `entry`.
+pub enum Entry<'a, T:'a> {
+    /// The key is present in the map.
+    Occupied(OccupiedEntry<'a, T>),
+    /// The key is absent from the map.
+    Vacant(VacantEntry<'a, T>),
+}
+
+/// A handle to a present entry of a `TrieMap`.
+pub struct OccupiedEntry<'a, T:'a> {
+    map: &'a mut TrieMap<T>,
+    // Each child slot visited from the root, with the count of the node
+    // holding it; the last slot holds the entry
+    path: Vec<(*mut uint, *mut Child<T>)>,
+}
+
+/// A handle to an absent entry of a `TrieMap`.
+pub struct VacantEntry<'a, T:'a> {
+    map: &'a mut TrieMap<T>,
+    // As for `OccupiedEntry`; the last slot is either empty or holds a
+    // different key that has to be split off
+    path: Vec<(*mut uint, *mut Child<T>)>,
+    key: uint,
+    idx: uint,
+}
+
+impl<'a, T> OccupiedEntry<'a, T> {
+    fn value(&self) -> *mut T {
+        let &(_, child) = self.path.last().unwrap();
+        match unsafe { &mut *child } {
+            &External(_, ref mut value) => value as *mut T,
+            _ => unreachable!()
+        }
+    }
+
+    /// Returns a reference to the entry's value.
+    pub fn get<'b>(&'b self) -> &'b T {
+        unsafe { &*self.value() }
+    }
+
+    /// Returns a mutable reference to the entry's value.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut T {
+        unsafe { &mut *self.value() }
+    }
+
+    /// Converts the entry into a mutable reference bound to the map.
+    pub fn into_mut(self) -> &'a mut T {
+        unsafe { &mut *self.value() }
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn set(&mut self, value: T) -> T {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    pub fn take(self) -> T {
+        let OccupiedEntry { map, mut path } = self;
+        let (count, child) = path.pop().unwrap();
+        let value = match mem::replace(unsafe { &mut *child }, Nothing) {
+            External(_, value) => value,
+            _ => unreachable!()
+        };
+        unsafe { *count -= 1; }
+
+        // Prune the internal nodes this leaves empty, as `remove` does
+        for &(count, child) in path.iter().rev() {
+            let empty = match unsafe { &*child } {
+                &Internal(ref x) => x.count == 0,
+                _ => false
+            };
+            if !empty { break }
+            unsafe {
+                *child = Nothing;
+                *count -= 1;
+            }
+        }
+        map.length -= 1;
+        value
+    }
+}
+
+impl<'a, T> VacantEntry<'a, T> {
+    /// Inserts `value` under the entry's key, returning a mutable
+    /// reference to it.
+    pub fn insert(self, value: T) -> &'a mut T {
+        let VacantEntry { map, path, key, idx } = self;
+        let &(count, child) = path.last().unwrap();
+        let child = unsafe {
+            insert(&mut *count, &mut *child, key, value, idx);
+            &mut *child
+        };
+        map.length += 1;
+        // The slot may have been split into a new internal node
+        find_mut(child, key, idx).unwrap()
+    }
+}
+
 /// A forward iterator over a map.
 pub struct Entries<'a, T:'a> {
     stack: [slice::Items<'a, Child<T>>, .. NUM_CHUNKS],
@@ -1023,6 +1167,7 @@ mod test_map {
 
     use {MutableMap, Map, MutableSeq};
     use super::{TrieMap, TrieNode, Internal, External, Nothing};
+    use super::{Occupied, Vacant};
 
     fn check_integrity<T>(trie: &TrieNode<T>) {
         assert!(trie.count != 0);
@@ -1429,6 +1574,52 @@ mod test_map {
 
         map[4];
     }
+
+    #[test]
+    fn test_entry() {
+        let mut m: TrieMap<uint> = range(0u, 6).map(|i| (i, i * 10)).collect();
+
+        match m.entry(1) {
+            Occupied(mut e) => {
+                assert_eq!(*e.get(), 10);
+                assert_eq!(e.set(100), 10);
+                *e.get_mut() += 1;
+            }
+            Vacant(_) => fail!()
+        }
+        assert_eq!(m[1], 101);
+
+        // Splits the external node holding 1
+        match m.entry(1 + (1 << 20)) {
+            Vacant(e) => *e.insert(1000) += 1,
+            Occupied(_) => fail!()
+        }
+        assert_eq!(m[1 + (1 << 20)], 1001);
+        assert_eq!(m.len(), 7);
+        check_integrity(&m.root);
+
+        match m.entry(1 + (1 << 20)) {
+            Occupied(e) => assert_eq!(e.take(), 1001),
+            Vacant(_) => fail!()
+        }
+        match m.entry(1) {
+            Occupied(e) => assert_eq!(e.take(), 101),
+            Vacant(_) => fail!()
+        }
+        assert!(m.find(&1).is_none());
+        assert_eq!(m.len(), 5);
+        check_integrity(&m.root);
+
+        for i in range(0u, 6) {
+            match m.entry(i) {
+                Occupied(e) => { e.take(); }
+                Vacant(e) => { e.insert(i); }
+            }
+        }
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[1], 1);
+        check_integrity(&m.root);
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +73,13 @@ impl Runtime for SimpleTask {
     // feet and running.
     fn yield_now(self: Box<SimpleTask>, _cur_task: Box<Task>) { fail!() }
     fn maybe_yield(self: Box<SimpleTask>, _cur_task: Box<Task>) { fail!() }
+    fn deschedule_timeout(self: Box<SimpleTask>,
+                          _times: uint,
+                          _ms: u64,
+                          _cur_task: Box<Task>,
+                          _f: |BlockedTask| -> Result<(), BlockedTask>) {
+        fail!()
+    }
     fn spawn_sibling(self: Box<SimpleTask>,
                      _cur_task: Box<Task>,
                      _opts: TaskOpts,
@@ -18,12 +18,15 @@
 //! contains the rust task itself in order to juggle around ownership of the
 //! values.
 
+use alloc::arc::Arc;
 use std::any::Any;
 use std::mem;
 use std::raw;
 use std::rt::Runtime;
+use std::rt::exclusive::Exclusive;
 use std::rt::local::Local;
 use std::rt::mutex::NativeMutex;
+use std::rt::rtio::LocalIo;
 use std::rt::rtio;
 use std::rt::stack;
 use std::rt::task::{Task, BlockedTask, TaskOpts};
@@ -321,6 +324,27 @@ impl GreenTask {
     }
 }
 
+// The state shared by a task blocked with a timeout and its timer
+struct Timeout {
+    fired: bool,
+    task: Option<BlockedTask>,
+}
+
+struct TimeoutCallback {
+    timeout: Arc<Exclusive<Timeout>>,
+}
+
+impl rtio::Callback for TimeoutCallback {
+    fn call(&mut self) {
+        let task = unsafe {
+            let mut timeout = self.timeout.lock();
+            timeout.fired = true;
+            timeout.task.take()
+        };
+        task.map(|t| t.reawaken());
+    }
+}
+
 impl Runtime for GreenTask {
     fn yield_now(mut self: Box<GreenTask>, cur_task: Box<Task>) {
         self.put_task(cur_task);
@@ -385,6 +409,55 @@ impl Runtime for GreenTask {
         }
     }
 
+    // Green tasks can't block their thread, so the timeout is implemented
+    // with a timer from the event loop. The timer is armed before the task is
+    // descheduled (homing it needs a running task), and the last of the
+    // blocked handles is handed to it once the task has blocked on the
+    // others. Whichever of the timer and the other handles fires first wakes
+    // the task up.
+    fn deschedule_timeout(self: Box<GreenTask>,
+                          times: uint,
+                          ms: u64,
+                          mut cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>) {
+        cur_task.put_runtime(self);
+        Local::put(cur_task);
+
+        let timeout = Arc::new(Exclusive::new(Timeout { fired: false, task: None }));
+        let mut timer = match LocalIo::borrow() {
+            Some(mut io) => io.get().timer_init().ok(),
+            None => None,
+        };
+        match timer {
+            Some(ref mut timer) => {
+                timer.oneshot(ms, box TimeoutCallback {
+                    timeout: timeout.clone(),
+                } as Box<rtio::Callback + Send>);
+            }
+            // Without a timer, time out right away. Callers check the time
+            // themselves and block again if their deadline hasn't passed, so
+            // this degrades to polling.
+            None => unsafe { timeout.lock().fired = true; },
+        }
+
+        let cur_task: Box<Task> = Local::take();
+        let mut handles = 0;
+        cur_task.deschedule(times + 1, |task| {
+            handles += 1;
+            if handles <= times { return f(task) }
+            let mut timeout = unsafe { timeout.lock() };
+            if timeout.fired {
+                Err(task)
+            } else {
+                timeout.task = Some(task);
+                Ok(())
+            }
+        });
+
+        // Cancels the timer if it hasn't fired yet
+        drop(timer);
+    }
+
     fn reawaken(mut self: Box<GreenTask>, to_wake: Box<Task>) {
         self.put_task(to_wake);
         assert!(self.sched.is_none());
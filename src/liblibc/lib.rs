@@ -95,6 +95,7 @@ pub use types::os::common::posix01::{timeval};
 pub use types::os::common::bsd44::{addrinfo, in_addr, in6_addr, sockaddr_storage};
 pub use types::os::common::bsd44::{ip_mreq, ip6_mreq, sockaddr, sockaddr_un};
 pub use types::os::common::bsd44::{sa_family_t, sockaddr_in, sockaddr_in6, socklen_t};
+pub use types::os::common::bsd44::{linger};
 pub use types::os::arch::c95::{c_char, c_double, c_float, c_int, c_uint};
 pub use types::os::arch::c95::{c_long, c_short, c_uchar, c_ulong, wchar_t};
 pub use types::os::arch::c95::{c_ushort, clock_t, ptrdiff_t, c_schar};
@@ -123,6 +124,7 @@ pub use consts::os::bsd44::{SO_REUSEADDR, SO_BROADCAST, SHUT_WR, IP_MULTICAST_LO
 pub use consts::os::bsd44::{IP_ADD_MEMBERSHIP, IP_DROP_MEMBERSHIP};
 pub use consts::os::bsd44::{IPV6_ADD_MEMBERSHIP, IPV6_DROP_MEMBERSHIP};
 pub use consts::os::bsd44::{IP_MULTICAST_TTL, IP_TTL, IP_HDRINCL, SHUT_RD};
+pub use consts::os::bsd44::{SO_LINGER, SO_SNDBUF, SO_RCVBUF, IPV6_V6ONLY};
 pub use consts::os::extra::{IPPROTO_RAW};
 
 pub use funcs::c95::ctype::{isalnum, isalpha, iscntrl, isdigit};
@@ -286,6 +288,10 @@ pub use funcs::posix01::unistd::{fdatasync};
 pub use types::os::arch::extra::{sockaddr_ll};
 #[cfg(target_os = "linux")] #[cfg(target_os = "android")]
 pub use consts::os::extra::{AF_PACKET};
+#[cfg(target_os = "linux")] #[cfg(target_os = "android")]
+pub use consts::os::bsd44::{TCP_QUICKACK};
+#[cfg(unix)]
+pub use consts::os::bsd44::{SO_REUSEPORT};
 
 #[cfg(unix, not(target_os = "freebsd"))]
 pub use consts::os::extra::{MAP_STACK};
@@ -458,6 +464,11 @@ pub mod types {
                     pub imr_interface: in_addr,
                 }
                 #[repr(C)]
+                pub struct linger {
+                    pub l_onoff: c_int,
+                    pub l_linger: c_int,
+                }
+                #[repr(C)]
                 pub struct ip6_mreq {
                     pub ipv6mr_multiaddr: in6_addr,
                     pub ipv6mr_interface: c_uint,
@@ -912,6 +923,11 @@ pub mod types {
                     pub imr_interface: in_addr,
                 }
                 #[repr(C)]
+                pub struct linger {
+                    pub l_onoff: c_int,
+                    pub l_linger: c_int,
+                }
+                #[repr(C)]
                 pub struct ip6_mreq {
                     pub ipv6mr_multiaddr: in6_addr,
                     pub ipv6mr_interface: c_uint,
@@ -1137,6 +1153,11 @@ pub mod types {
                     pub imr_interface: in_addr,
                 }
                 #[repr(C)]
+                pub struct linger {
+                    pub l_onoff: c_int,
+                    pub l_linger: c_int,
+                }
+                #[repr(C)]
                 pub struct ip6_mreq {
                     pub ipv6mr_multiaddr: in6_addr,
                     pub ipv6mr_interface: c_uint,
@@ -1352,6 +1373,11 @@ pub mod types {
                     pub imr_interface: in_addr,
                 }
                 #[repr(C)]
+                pub struct linger {
+                    pub l_onoff: u16,
+                    pub l_linger: u16,
+                }
+                #[repr(C)]
                 pub struct ip6_mreq {
                     pub ipv6mr_multiaddr: in6_addr,
                     pub ipv6mr_interface: c_uint,
@@ -1741,6 +1767,11 @@ pub mod types {
                     pub imr_interface: in_addr,
                 }
                 #[repr(C)]
+                pub struct linger {
+                    pub l_onoff: c_int,
+                    pub l_linger: c_int,
+                }
+                #[repr(C)]
                 pub struct ip6_mreq {
                     pub ipv6mr_multiaddr: in6_addr,
                     pub ipv6mr_interface: c_uint,
@@ -2123,6 +2154,10 @@ pub mod consts {
             pub static SO_BROADCAST: c_int = 32;
             pub static SO_REUSEADDR: c_int = 4;
             pub static SO_ERROR: c_int = 0x1007;
+            pub static SO_LINGER: c_int = 0x0080;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
+            pub static IPV6_V6ONLY: c_int = 27;
 
             pub static IFF_LOOPBACK: c_int = 4;
 
@@ -2938,6 +2973,12 @@ pub mod consts {
             pub static SO_BROADCAST: c_int = 6;
             pub static SO_REUSEADDR: c_int = 2;
             pub static SO_ERROR: c_int = 4;
+            pub static SO_LINGER: c_int = 13;
+            pub static SO_SNDBUF: c_int = 7;
+            pub static SO_RCVBUF: c_int = 8;
+            pub static SO_REUSEPORT: c_int = 15;
+            pub static IPV6_V6ONLY: c_int = 26;
+            pub static TCP_QUICKACK: c_int = 12;
 
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
@@ -2982,6 +3023,12 @@ pub mod consts {
             pub static SO_BROADCAST: c_int = 32;
             pub static SO_REUSEADDR: c_int = 4;
             pub static SO_ERROR: c_int = 4103;
+            pub static SO_LINGER: c_int = 0x0080;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
+            pub static SO_REUSEPORT: c_int = 0x0200;
+            pub static IPV6_V6ONLY: c_int = 26;
+            pub static TCP_QUICKACK: c_int = 12;
 
             pub static SHUT_RD: c_int = 0;
             pub static SHUT_WR: c_int = 1;
@@ -3458,6 +3505,11 @@ pub mod consts {
             pub static SO_BROADCAST: c_int = 0x0020;
             pub static SO_REUSEADDR: c_int = 0x0004;
             pub static SO_ERROR: c_int = 0x1007;
+            pub static SO_LINGER: c_int = 0x0080;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
+            pub static SO_REUSEPORT: c_int = 0x0200;
+            pub static IPV6_V6ONLY: c_int = 27;
 
             pub static IFF_LOOPBACK: c_int = 0x8;
 
@@ -3861,6 +3913,11 @@ pub mod consts {
             pub static SO_BROADCAST: c_int = 0x0020;
             pub static SO_REUSEADDR: c_int = 0x0004;
             pub static SO_ERROR: c_int = 0x1007;
+            pub static SO_LINGER: c_int = 0x0080;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
+            pub static SO_REUSEPORT: c_int = 0x0200;
+            pub static IPV6_V6ONLY: c_int = 27;
 
             pub static IFF_LOOPBACK: c_int = 0x8;
 
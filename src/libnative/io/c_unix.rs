@@ -16,8 +16,12 @@ pub use self::select::fd_set;
 pub use self::signal::{sigaction, siginfo, sigset_t};
 pub use self::signal::{SA_ONSTACK, SA_RESTART, SA_RESETHAND, SA_NOCLDSTOP};
 pub use self::signal::{SA_NODEFER, SA_NOCLDWAIT, SA_SIGINFO, SIGCHLD};
+pub use self::rlimit::{rlimit, rlim_t, RLIM_INFINITY, RLIMIT_CPU, RLIMIT_FSIZE};
+pub use self::rlimit::{RLIMIT_DATA, RLIMIT_STACK, RLIMIT_CORE, RLIMIT_NOFILE};
+pub use self::rlimit::{RLIMIT_AS, RLIMIT_NPROC};
 
 use libc;
+use std::rt::rtio;
 
 #[cfg(target_os = "macos")]
 #[cfg(target_os = "ios")]
@@ -56,8 +60,60 @@ pub static MSG_DONTWAIT: libc::c_int = 0x80;
 #[cfg(target_os = "android")]
 pub static MSG_DONTWAIT: libc::c_int = 0x40;
 
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static SO_BINDTODEVICE: libc::c_int = 25;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static SO_ATTACH_FILTER: libc::c_int = 26;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+pub static IP_BOUND_IF: libc::c_int = 25;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static SOL_PACKET: libc::c_int = 263;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static PACKET_ADD_MEMBERSHIP: libc::c_int = 1;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static PACKET_DROP_MEMBERSHIP: libc::c_int = 2;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static PACKET_MR_PROMISC: libc::c_ushort = 1;
+
+pub static IF_NAMESIZE: uint = 16;
+
 pub static WNOHANG: libc::c_int = 1;
 
+// The minimum value of IOV_MAX on all of the supported platforms.
+pub static IOV_MAX: uint = 1024;
+
+#[repr(C)]
+pub struct iovec {
+    pub iov_base: *mut libc::c_void,
+    pub iov_len: libc::size_t,
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[repr(C)]
+pub struct sock_fprog {
+    pub len: libc::c_ushort,
+    pub filter: *const rtio::FilterInstruction,
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[repr(C)]
+pub struct packet_mreq {
+    pub mr_ifindex: libc::c_int,
+    pub mr_type: libc::c_ushort,
+    pub mr_alen: libc::c_ushort,
+    pub mr_address: [libc::c_uchar, ..8],
+}
+
 extern {
     pub fn gettimeofday(timeval: *mut libc::timeval,
                         tzp: *mut libc::c_void) -> libc::c_int;
@@ -72,6 +128,10 @@ extern {
                       optval: *mut libc::c_void,
                       optlen: *mut libc::socklen_t) -> libc::c_int;
     pub fn ioctl(fd: libc::c_int, req: libc::c_ulong, ...) -> libc::c_int;
+    pub fn readv(fd: libc::c_int, iov: *const iovec,
+                 iovcnt: libc::c_int) -> libc::ssize_t;
+    pub fn writev(fd: libc::c_int, iov: *const iovec,
+                  iovcnt: libc::c_int) -> libc::ssize_t;
 
 
     pub fn waitpid(pid: libc::pid_t, status: *mut libc::c_int,
@@ -84,6 +144,89 @@ extern {
     pub fn sigaddset(set: *mut sigset_t, signum: libc::c_int) -> libc::c_int;
     pub fn sigdelset(set: *mut sigset_t, signum: libc::c_int) -> libc::c_int;
     pub fn sigemptyset(set: *mut sigset_t) -> libc::c_int;
+
+    pub fn setrlimit(resource: libc::c_int, rlim: *const rlimit) -> libc::c_int;
+
+    pub fn if_indextoname(ifindex: libc::c_uint,
+                          ifname: *mut libc::c_char) -> *mut libc::c_char;
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+mod rlimit {
+    use libc;
+
+    pub type rlim_t = libc::c_ulong;
+    pub static RLIM_INFINITY: rlim_t = !0;
+
+    #[repr(C)]
+    pub struct rlimit {
+        pub rlim_cur: rlim_t,
+        pub rlim_max: rlim_t,
+    }
+
+    pub static RLIMIT_CPU: libc::c_int = 0;
+    pub static RLIMIT_FSIZE: libc::c_int = 1;
+    pub static RLIMIT_DATA: libc::c_int = 2;
+    pub static RLIMIT_STACK: libc::c_int = 3;
+    pub static RLIMIT_CORE: libc::c_int = 4;
+    #[cfg(not(target_arch = "mips"), not(target_arch = "mipsel"))]
+    pub static RLIMIT_NPROC: libc::c_int = 6;
+    #[cfg(not(target_arch = "mips"), not(target_arch = "mipsel"))]
+    pub static RLIMIT_NOFILE: libc::c_int = 7;
+    #[cfg(not(target_arch = "mips"), not(target_arch = "mipsel"))]
+    pub static RLIMIT_AS: libc::c_int = 9;
+    #[cfg(target_arch = "mips")]
+    #[cfg(target_arch = "mipsel")]
+    pub static RLIMIT_NOFILE: libc::c_int = 5;
+    #[cfg(target_arch = "mips")]
+    #[cfg(target_arch = "mipsel")]
+    pub static RLIMIT_AS: libc::c_int = 6;
+    #[cfg(target_arch = "mips")]
+    #[cfg(target_arch = "mipsel")]
+    pub static RLIMIT_NPROC: libc::c_int = 8;
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+#[cfg(target_os = "freebsd")]
+#[cfg(target_os = "dragonfly")]
+mod rlimit {
+    use libc;
+
+    #[cfg(target_os = "macos")]
+    #[cfg(target_os = "ios")]
+    pub type rlim_t = u64;
+    #[cfg(target_os = "freebsd")]
+    #[cfg(target_os = "dragonfly")]
+    pub type rlim_t = i64;
+
+    #[cfg(target_os = "macos")]
+    #[cfg(target_os = "ios")]
+    pub static RLIM_INFINITY: rlim_t = (1 << 63) - 1;
+    #[cfg(target_os = "freebsd")]
+    #[cfg(target_os = "dragonfly")]
+    pub static RLIM_INFINITY: rlim_t = 0x7fffffffffffffff;
+
+    #[repr(C)]
+    pub struct rlimit {
+        pub rlim_cur: rlim_t,
+        pub rlim_max: rlim_t,
+    }
+
+    pub static RLIMIT_CPU: libc::c_int = 0;
+    pub static RLIMIT_FSIZE: libc::c_int = 1;
+    pub static RLIMIT_DATA: libc::c_int = 2;
+    pub static RLIMIT_STACK: libc::c_int = 3;
+    pub static RLIMIT_CORE: libc::c_int = 4;
+    pub static RLIMIT_NPROC: libc::c_int = 7;
+    pub static RLIMIT_NOFILE: libc::c_int = 8;
+    #[cfg(target_os = "macos")]
+    #[cfg(target_os = "ios")]
+    pub static RLIMIT_AS: libc::c_int = 5;
+    #[cfg(target_os = "freebsd")]
+    #[cfg(target_os = "dragonfly")]
+    pub static RLIMIT_AS: libc::c_int = 10;
 }
 
 #[cfg(target_os = "macos")]
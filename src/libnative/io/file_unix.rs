@@ -18,7 +18,9 @@ use std::mem;
 use std::rt::rtio;
 use std::rt::rtio::IoResult;
 
-use io::{retry, keep_going};
+use io;
+use io::{retry, keep_going, keep_going_vectored};
+use io::c;
 use io::util;
 
 pub type fd_t = libc::c_int;
@@ -29,7 +31,9 @@ struct Inner {
 }
 
 pub struct FileDesc {
-    inner: Arc<Inner>
+    inner: Arc<Inner>,
+    // The time reads through `RtioPipe` give up at, or 0 to wait forever
+    read_deadline: u64,
 }
 
 impl FileDesc {
@@ -42,10 +46,13 @@ impl FileDesc {
     /// Note that all I/O operations done on this object will be *blocking*, but
     /// they do not require the runtime to be active.
     pub fn new(fd: fd_t, close_on_drop: bool) -> FileDesc {
-        FileDesc { inner: Arc::new(Inner {
-            fd: fd,
-            close_on_drop: close_on_drop
-        }) }
+        FileDesc {
+            inner: Arc::new(Inner {
+                fd: fd,
+                close_on_drop: close_on_drop
+            }),
+            read_deadline: 0,
+        }
     }
 
     // FIXME(#10465) these functions should not be public, but anything in
@@ -79,6 +86,31 @@ impl FileDesc {
         }
     }
 
+    pub fn inner_readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let iovs = io::to_mut_iovecs(bufs);
+        if iovs.len() == 0 { return Ok(0) }
+        let ret = retry(|| unsafe {
+            c::readv(self.fd(), iovs.as_ptr(),
+                     iovs.len() as libc::c_int) as libc::c_int
+        });
+        if ret == 0 {
+            Err(util::eof())
+        } else if ret < 0 {
+            Err(super::last_error())
+        } else {
+            Ok(ret as uint)
+        }
+    }
+    pub fn inner_writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let fd = self.fd();
+        match keep_going_vectored(bufs, |iov, cnt| unsafe {
+            c::writev(fd, iov, cnt) as i64
+        }) {
+            (_, n) if n < 0 => Err(super::last_error()),
+            _ => Ok(()),
+        }
+    }
+
     pub fn fd(&self) -> fd_t { self.inner.fd }
 }
 
@@ -89,6 +121,12 @@ impl rtio::RtioFileStream for FileDesc {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.inner_write(buf)
     }
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        self.inner_readv(bufs).map(|i| i as int)
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.inner_writev(bufs)
+    }
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int> {
         match retry(|| unsafe {
             libc::pread(self.fd(), buf.as_ptr() as *mut _,
@@ -159,17 +197,24 @@ impl rtio::RtioFileStream for FileDesc {
             _ => Err(super::last_error()),
         }
     }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd()) }
 }
 
 impl rtio::RtioPipe for FileDesc {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        if self.read_deadline != 0 {
+            try!(util::await([self.fd()], Some(self.read_deadline), util::Readable));
+        }
         self.inner_read(buf)
     }
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.inner_write(buf)
     }
     fn clone(&self) -> Box<rtio::RtioPipe + Send> {
-        box FileDesc { inner: self.inner.clone() } as Box<rtio::RtioPipe + Send>
+        box FileDesc {
+            inner: self.inner.clone(),
+            read_deadline: 0,
+        } as Box<rtio::RtioPipe + Send>
     }
 
     // Only supported on named pipes currently. Note that this doesn't have an
@@ -182,8 +227,15 @@ impl rtio::RtioPipe for FileDesc {
     fn close_write(&mut self) -> IoResult<()> {
         Err(super::unimpl())
     }
-    fn set_timeout(&mut self, _t: Option<u64>) {}
-    fn set_read_timeout(&mut self, _t: Option<u64>) {}
+
+    // Only reads can time out: a write waits for the whole buffer to make it
+    // into the pipe.
+    fn set_timeout(&mut self, timeout: Option<u64>) {
+        self.set_read_timeout(timeout)
+    }
+    fn set_read_timeout(&mut self, timeout: Option<u64>) {
+        self.read_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
     fn set_write_timeout(&mut self, _t: Option<u64>) {}
 }
 
@@ -274,6 +326,21 @@ impl rtio::RtioFileStream for CFile {
         }
     }
 
+    // stdio has no notion of vectored I/O, so these go through the buffers one
+    // at a time to stay coherent with the FILE's own buffering.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.write(*buf));
+        }
+        Ok(())
+    }
+
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int> {
         self.flush().and_then(|()| self.fd.pread(buf, offset))
     }
@@ -314,6 +381,7 @@ impl rtio::RtioFileStream for CFile {
     fn fstat(&mut self) -> IoResult<rtio::FileStat> {
         self.flush().and_then(|()| self.fd.fstat())
     }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd.fd()) }
 }
 
 impl Drop for CFile {
@@ -329,10 +397,11 @@ pub fn open(path: &CString, fm: rtio::FileMode, fa: rtio::FileAccess)
         rtio::Open => 0,
         rtio::Append => libc::O_APPEND,
         rtio::Truncate => libc::O_TRUNC,
+        rtio::CreateNew => libc::O_CREAT | libc::O_EXCL,
     };
     // Opening with a write permission must silently create the file.
     let (flags, mode) = match fa {
-        rtio::Read => (flags | libc::O_RDONLY, 0),
+        rtio::Read => (flags | libc::O_RDONLY, libc::S_IRUSR | libc::S_IWUSR),
         rtio::Write => (flags | libc::O_WRONLY | libc::O_CREAT,
                         libc::S_IRUSR | libc::S_IWUSR),
         rtio::ReadWrite => (flags | libc::O_RDWR | libc::O_CREAT,
@@ -352,21 +421,30 @@ pub fn mkdir(p: &CString, mode: uint) -> IoResult<()> {
 }
 
 pub fn readdir(p: &CString) -> IoResult<Vec<CString>> {
+    readdir_entries(p).map(|entries| {
+        entries.move_iter().map(|e| e.path).collect()
+    })
+}
+
+pub fn readdir_entries(p: &CString) -> IoResult<Vec<rtio::DirEntry>> {
     use libc::{dirent_t};
     use libc::{opendir, readdir_r, closedir};
 
-    fn prune(root: &CString, dirs: Vec<Path>) -> Vec<CString> {
+    fn prune(root: &CString, dirs: Vec<(Path, u64)>) -> Vec<rtio::DirEntry> {
         let root = unsafe { CString::new(root.as_ptr(), false) };
         let root = Path::new(root);
 
-        dirs.move_iter().filter(|path| {
+        dirs.move_iter().filter(|&(ref path, _)| {
             path.as_vec() != b"." && path.as_vec() != b".."
-        }).map(|path| root.join(path).to_c_str()).collect()
+        }).map(|(path, kind)| {
+            rtio::DirEntry { path: root.join(path).to_c_str(), kind: kind }
+        }).collect()
     }
 
     extern {
         fn rust_dirent_t_size() -> libc::c_int;
         fn rust_list_dir_val(ptr: *mut dirent_t) -> *const libc::c_char;
+        fn rust_list_dir_type(ptr: *mut dirent_t) -> u32;
     }
 
     let size = unsafe { rust_dirent_t_size() };
@@ -383,7 +461,8 @@ pub fn readdir(p: &CString) -> IoResult<Vec<CString>> {
             let cstr = unsafe {
                 CString::new(rust_list_dir_val(entry_ptr), false)
             };
-            paths.push(Path::new(cstr));
+            let kind = unsafe { rust_list_dir_type(entry_ptr) };
+            paths.push((Path::new(cstr), kind as u64));
         }
         assert_eq!(unsafe { closedir(dir_ptr) }, 0);
         Ok(prune(p, paths))
@@ -115,6 +115,20 @@ impl rtio::RtioFileStream for FileDesc {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.inner_write(buf)
     }
+    // ReadFileScatter/WriteFileGather only operate on unbuffered handles with
+    // page-aligned buffers, so windows does vectored I/O one buffer at a time.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.inner_read(&mut **buf).map(|i| i as int),
+            None => Ok(0),
+        }
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.inner_write(*buf));
+        }
+        Ok(())
+    }
 
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int> {
         let mut read = 0;
@@ -192,6 +206,7 @@ impl rtio::RtioFileStream for FileDesc {
             _ => Err(super::last_error()),
         }
     }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd()) }
 }
 
 impl rtio::RtioPipe for FileDesc {
@@ -271,6 +286,7 @@ pub fn open(path: &CString, fm: rtio::FileMode, fa: rtio::FileAccess)
         rtio::Open => 0,
         rtio::Append => libc::O_APPEND,
         rtio::Truncate => libc::O_TRUNC,
+        rtio::CreateNew => 0,
     };
     let flags = match fa {
         rtio::Read => flags | libc::O_RDONLY,
@@ -295,6 +311,7 @@ pub fn open(path: &CString, fm: rtio::FileMode, fa: rtio::FileAccess)
         (rtio::Truncate, _) => libc::CREATE_ALWAYS,
         (rtio::Open, rtio::Read) => libc::OPEN_EXISTING,
         (rtio::Open, _) => libc::OPEN_ALWAYS,
+        (rtio::CreateNew, _) => libc::CREATE_NEW,
         (rtio::Append, rtio::Read) => {
             dwDesiredAccess |= libc::FILE_APPEND_DATA;
             libc::OPEN_EXISTING
@@ -344,20 +361,43 @@ pub fn mkdir(p: &CString, _mode: uint) -> IoResult<()> {
 }
 
 pub fn readdir(p: &CString) -> IoResult<Vec<CString>> {
+    readdir_entries(p).map(|entries| {
+        entries.move_iter().map(|e| e.path).collect()
+    })
+}
+
+pub fn readdir_entries(p: &CString) -> IoResult<Vec<rtio::DirEntry>> {
     use std::rt::libc_heap::malloc_raw;
 
-    fn prune(root: &CString, dirs: Vec<Path>) -> Vec<CString> {
+    fn prune(root: &CString, dirs: Vec<(Path, u64)>) -> Vec<rtio::DirEntry> {
         let root = unsafe { CString::new(root.as_ptr(), false) };
         let root = Path::new(root);
 
-        dirs.move_iter().filter(|path| {
+        dirs.move_iter().filter(|&(ref path, _)| {
             path.as_vec() != b"." && path.as_vec() != b".."
-        }).map(|path| root.join(path).to_c_str()).collect()
+        }).map(|(path, kind)| {
+            rtio::DirEntry { path: root.join(path).to_c_str(), kind: kind }
+        }).collect()
+    }
+
+    // Symlinks and junctions are both reparse points
+    fn kind(attrs: u32) -> u64 {
+        use libc::consts::os::extra::{FILE_ATTRIBUTE_REPARSE_POINT,
+                                      FILE_ATTRIBUTE_DIRECTORY};
+        let kind = if attrs & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            libc::S_IFLNK
+        } else if attrs & FILE_ATTRIBUTE_DIRECTORY != 0 {
+            libc::S_IFDIR
+        } else {
+            libc::S_IFREG
+        };
+        kind as u64
     }
 
     extern {
         fn rust_list_dir_wfd_size() -> libc::size_t;
         fn rust_list_dir_wfd_fp_buf(wfd: *mut libc::c_void) -> *const u16;
+        fn rust_list_dir_wfd_attrs(wfd: *mut libc::c_void) -> u32;
     }
     let star = Path::new(unsafe {
         CString::new(p.as_ptr(), false)
@@ -380,7 +420,8 @@ pub fn readdir(p: &CString) -> IoResult<Vec<CString>> {
                     let fp_trimmed = str::truncate_utf16_at_nul(fp_vec.as_slice());
                     let fp_str = String::from_utf16(fp_trimmed)
                             .expect("rust_list_dir_wfd_fp_buf returned invalid UTF-16");
-                    paths.push(Path::new(fp_str));
+                    let attrs = rust_list_dir_wfd_attrs(wfd_ptr as *mut c_void);
+                    paths.push((Path::new(fp_str), kind(attrs)));
                 }
                 more_files = libc::FindNextFileW(find_handle,
                                                  wfd_ptr as libc::HANDLE);
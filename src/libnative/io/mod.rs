@@ -26,6 +26,7 @@
 use libc::c_int;
 use libc;
 use std::c_str::CString;
+#[cfg(unix)] use std::cmp;
 use std::os;
 use std::rt::rtio;
 use std::rt::rtio::{IoResult, IoError};
@@ -74,6 +75,16 @@ pub mod pipe;
 #[path = "tty_windows.rs"]
 mod tty;
 
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[path = "poll_epoll.rs"]
+pub mod poll;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[path = "watch_inotify.rs"]
+pub mod watch;
+
 #[cfg(unix)]    #[path = "c_unix.rs"]  mod c;
 #[cfg(windows)] #[path = "c_windows.rs"] mod c;
 
@@ -155,6 +166,65 @@ fn keep_going(data: &[u8], f: |*const u8, uint| -> i64) -> i64 {
     return (origamt - amt) as i64;
 }
 
+#[cfg(unix)]
+fn to_iovecs(bufs: &[&[u8]]) -> Vec<c::iovec> {
+    bufs.iter().filter(|b| b.len() > 0).map(|b| {
+        c::iovec {
+            iov_base: b.as_ptr() as *mut libc::c_void,
+            iov_len: b.len() as libc::size_t,
+        }
+    }).collect()
+}
+
+#[cfg(unix)]
+fn to_mut_iovecs(bufs: &mut [&mut [u8]]) -> Vec<c::iovec> {
+    bufs.mut_iter().filter(|b| b.len() > 0).map(|b| {
+        c::iovec {
+            iov_base: b.as_mut_ptr() as *mut libc::c_void,
+            iov_len: b.len() as libc::size_t,
+        }
+    }).take(c::IOV_MAX).collect()
+}
+
+/// The vectored equivalent of `keep_going`. `f` is invoked with a window of at
+/// most IOV_MAX iovecs, and the window is advanced past whatever was written
+/// until every buffer has been written in its entirety.
+///
+/// Returns the total number of bytes written along with the last return value
+/// of `f`, which is -1 if an error stopped the write part of the way through.
+#[cfg(unix)]
+fn keep_going_vectored(bufs: &[&[u8]],
+                       f: |*const c::iovec, libc::c_int| -> i64) -> (uint, i64) {
+    let mut iovs = to_iovecs(bufs);
+    let mut start = 0;
+    let mut written = 0;
+    while start < iovs.len() {
+        let cnt = cmp::min(iovs.len() - start, c::IOV_MAX);
+        let ptr = iovs.slice_from(start).as_ptr();
+        let ret = retry(|| f(ptr, cnt as libc::c_int) as libc::c_int) as i64;
+        if ret <= 0 { return (written, ret) }
+
+        // Skip over all the buffers which were fully written, and trim the
+        // front of the one which was only partially written (if any).
+        let mut n = ret as uint;
+        written += n;
+        while n > 0 {
+            let iov = iovs.get_mut(start);
+            if n >= iov.iov_len as uint {
+                n -= iov.iov_len as uint;
+                start += 1;
+            } else {
+                iov.iov_base = unsafe {
+                    (iov.iov_base as *mut u8).offset(n as int) as *mut libc::c_void
+                };
+                iov.iov_len -= n as libc::size_t;
+                n = 0;
+            }
+        }
+    }
+    (written, written as i64)
+}
+
 /// Implementation of rt::rtio's IoFactory trait to generate handles to the
 /// native I/O functionality.
 pub struct IoFactory {
@@ -171,25 +241,44 @@ impl IoFactory {
 impl rtio::IoFactory for IoFactory {
     // networking
     fn tcp_connect(&mut self, addr: rtio::SocketAddr,
-                   timeout: Option<u64>)
+                   opts: &rtio::SocketOptions, timeout: Option<u64>)
         -> IoResult<Box<rtio::RtioTcpStream + Send>>
     {
-        net::TcpStream::connect(addr, timeout).map(|s| {
+        net::TcpStream::connect(addr, opts, timeout).map(|s| {
             box s as Box<rtio::RtioTcpStream + Send>
         })
     }
-    fn tcp_bind(&mut self, addr: rtio::SocketAddr)
+    fn tcp_bind(&mut self, addr: rtio::SocketAddr, opts: &rtio::SocketOptions)
                 -> IoResult<Box<rtio::RtioTcpListener + Send>> {
-        net::TcpListener::bind(addr).map(|s| {
+        net::TcpListener::bind(addr, opts).map(|s| {
             box s as Box<rtio::RtioTcpListener + Send>
         })
     }
-    fn udp_bind(&mut self, addr: rtio::SocketAddr)
+    fn udp_bind(&mut self, addr: rtio::SocketAddr, opts: &rtio::SocketOptions)
                 -> IoResult<Box<rtio::RtioUdpSocket + Send>> {
-        net::UdpSocket::bind(addr).map(|u| {
+        net::UdpSocket::bind(addr, opts).map(|u| {
             box u as Box<rtio::RtioUdpSocket + Send>
         })
     }
+    fn raw_bind(&mut self, addr: rtio::IpAddr, protocol: u8)
+                -> IoResult<Box<rtio::RtioRawSocket + Send>> {
+        net::RawSocket::bind(addr, protocol).map(|s| {
+            box s as Box<rtio::RtioRawSocket + Send>
+        })
+    }
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn packet_bind(&mut self, ifindex: uint, protocol: u16)
+                   -> IoResult<Box<rtio::RtioPacketSocket + Send>> {
+        net::PacketSocket::bind(ifindex, protocol).map(|s| {
+            box s as Box<rtio::RtioPacketSocket + Send>
+        })
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    fn packet_bind(&mut self, _ifindex: uint, _protocol: u16)
+                   -> IoResult<Box<rtio::RtioPacketSocket + Send>> {
+        Err(unimpl())
+    }
     fn unix_bind(&mut self, path: &CString)
                  -> IoResult<Box<rtio::RtioUnixListener + Send>> {
         pipe::UnixListener::bind(path).map(|s| {
@@ -245,6 +334,10 @@ impl rtio::IoFactory for IoFactory {
     fn fs_readdir(&mut self, path: &CString, _flags: c_int) -> IoResult<Vec<CString>> {
         file::readdir(path)
     }
+    fn fs_readdir_entries(&mut self, path: &CString, _flags: c_int)
+                          -> IoResult<Vec<rtio::DirEntry>> {
+        file::readdir_entries(path)
+    }
     fn fs_lstat(&mut self, path: &CString) -> IoResult<rtio::FileStat> {
         file::lstat(path)
     }
@@ -264,6 +357,21 @@ impl rtio::IoFactory for IoFactory {
                 mtime: u64) -> IoResult<()> {
         file::utime(src, atime, mtime)
     }
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<rtio::FsEventCallback + Send>)
+                -> IoResult<Box<rtio::RtioFsWatcher + Send>> {
+        watch::Watcher::new(path, recursive, cb).map(|w| {
+            box w as Box<rtio::RtioFsWatcher + Send>
+        })
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    fn fs_watch(&mut self, _path: &CString, _recursive: bool,
+                _cb: Box<rtio::FsEventCallback + Send>)
+                -> IoResult<Box<rtio::RtioFsWatcher + Send>> {
+        Err(unimpl())
+    }
 
     // misc
     fn timer_init(&mut self) -> IoResult<Box<rtio::RtioTimer + Send>> {
@@ -315,4 +423,13 @@ impl rtio::IoFactory for IoFactory {
               -> IoResult<Box<rtio::RtioSignal + Send>> {
         Err(unimpl())
     }
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        poll::Poller::new().map(|p| box p as Box<rtio::RtioPoller + Send>)
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        Err(unimpl())
+    }
 }
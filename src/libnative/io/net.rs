@@ -10,6 +10,9 @@
 
 use alloc::arc::Arc;
 use libc;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+use std::cmp;
 use std::mem;
 use std::ptr;
 use std::rt::mutex;
@@ -94,13 +97,14 @@ fn addr_to_sockaddr(addr: rtio::SocketAddr,
     }
 }
 
-fn socket(addr: rtio::SocketAddr, ty: libc::c_int) -> IoResult<sock_t> {
+fn socket(addr: rtio::SocketAddr, ty: libc::c_int,
+          protocol: libc::c_int) -> IoResult<sock_t> {
     unsafe {
         let fam = match addr.ip {
             rtio::Ipv4Addr(..) => libc::AF_INET,
             rtio::Ipv6Addr(..) => libc::AF_INET6,
         };
-        match libc::socket(fam, ty, 0) {
+        match libc::socket(fam, ty, protocol) {
             -1 => Err(os::last_error()),
             fd => Ok(fd),
         }
@@ -122,6 +126,82 @@ fn setsockopt<T>(fd: sock_t, opt: libc::c_int, val: libc::c_int,
     }
 }
 
+// Applies everything in `opts` to a freshly created socket. This must happen
+// before the socket is bound or connected, as some of these options (such as
+// SO_REUSEADDR and IPV6_V6ONLY) have no effect afterwards.
+fn set_socket_options(fd: sock_t, addr: rtio::SocketAddr,
+                      opts: &rtio::SocketOptions) -> IoResult<()> {
+    match opts.reuse_address {
+        Some(on) => try!(setsockopt(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR,
+                                    on as libc::c_int)),
+        None => {}
+    }
+    match opts.reuse_port {
+        Some(on) => try!(set_reuse_port(fd, on)),
+        None => {}
+    }
+    match opts.recv_buffer_size {
+        Some(n) => try!(setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVBUF,
+                                   n as libc::c_int)),
+        None => {}
+    }
+    match opts.send_buffer_size {
+        Some(n) => try!(setsockopt(fd, libc::SOL_SOCKET, libc::SO_SNDBUF,
+                                   n as libc::c_int)),
+        None => {}
+    }
+    match opts.linger {
+        Some(secs) => try!(set_linger(fd, secs)),
+        None => {}
+    }
+    match (opts.only_v6, addr.ip) {
+        (Some(on), rtio::Ipv6Addr(..)) => {
+            try!(setsockopt(fd, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY,
+                            on as libc::c_int))
+        }
+        _ => {}
+    }
+    match opts.quickack {
+        Some(on) => try!(set_quickack(fd, on)),
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_reuse_port(fd: sock_t, on: bool) -> IoResult<()> {
+    setsockopt(fd, libc::SOL_SOCKET, libc::SO_REUSEPORT, on as libc::c_int)
+}
+#[cfg(windows)]
+fn set_reuse_port(_fd: sock_t, _on: bool) -> IoResult<()> {
+    Err(super::unimpl())
+}
+
+#[cfg(unix)]
+fn set_linger(fd: sock_t, secs: Option<uint>) -> IoResult<()> {
+    setsockopt(fd, libc::SOL_SOCKET, libc::SO_LINGER, libc::linger {
+        l_onoff: secs.is_some() as libc::c_int,
+        l_linger: secs.unwrap_or(0) as libc::c_int,
+    })
+}
+#[cfg(windows)]
+fn set_linger(fd: sock_t, secs: Option<uint>) -> IoResult<()> {
+    setsockopt(fd, libc::SOL_SOCKET, libc::SO_LINGER, libc::linger {
+        l_onoff: secs.is_some() as u16,
+        l_linger: secs.unwrap_or(0) as u16,
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+fn set_quickack(fd: sock_t, on: bool) -> IoResult<()> {
+    setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_QUICKACK, on as libc::c_int)
+}
+#[cfg(not(target_os = "linux"), not(target_os = "android"))]
+fn set_quickack(_fd: sock_t, _on: bool) -> IoResult<()> {
+    Err(super::unimpl())
+}
+
 pub fn getsockopt<T: Copy>(fd: sock_t, opt: libc::c_int,
                            val: libc::c_int) -> IoResult<T> {
     unsafe {
@@ -219,6 +299,11 @@ pub struct TcpStream {
 struct Inner {
     fd: sock_t,
 
+    // Whether this socket has been placed in nonblocking mode through
+    // `set_nonblocking`. This is a property of the descriptor rather than of
+    // any one handle, so it is shared among all clones.
+    nonblocking: atomic::AtomicBool,
+
     // Unused on Linux, where this lock is not necessary.
     #[allow(dead_code)]
     lock: mutex::NativeMutex
@@ -231,15 +316,30 @@ pub struct Guard<'a> {
 
 impl Inner {
     fn new(fd: sock_t) -> Inner {
-        Inner { fd: fd, lock: unsafe { mutex::NativeMutex::new() } }
+        Inner {
+            fd: fd,
+            nonblocking: atomic::AtomicBool::new(false),
+            lock: unsafe { mutex::NativeMutex::new() },
+        }
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.nonblocking.load(atomic::SeqCst)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> IoResult<()> {
+        try!(util::set_nonblocking(self.fd, nonblocking));
+        self.nonblocking.store(nonblocking, atomic::SeqCst);
+        Ok(())
     }
 }
 
 impl TcpStream {
-    pub fn connect(addr: rtio::SocketAddr,
+    pub fn connect(addr: rtio::SocketAddr, opts: &rtio::SocketOptions,
                    timeout: Option<u64>) -> IoResult<TcpStream> {
-        let fd = try!(socket(addr, libc::SOCK_STREAM));
+        let fd = try!(socket(addr, libc::SOCK_STREAM, 0));
         let ret = TcpStream::new(Inner::new(fd));
+        try!(set_socket_options(fd, addr, opts));
 
         let mut storage = unsafe { mem::zeroed() };
         let len = addr_to_sockaddr(addr, &mut storage);
@@ -329,7 +429,11 @@ impl rtio::RtioTcpStream for TcpStream {
                        buf.len() as wrlen,
                        flags) as libc::c_int
         };
-        read(fd, self.read_deadline, dolock, doread)
+        if self.inner.is_nonblocking() {
+            read_nonblocking(doread)
+        } else {
+            read(fd, self.read_deadline, dolock, doread)
+        }
     }
 
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
@@ -342,11 +446,80 @@ impl rtio::RtioTcpStream for TcpStream {
                        len as wrlen,
                        flags) as i64
         };
-        match write(fd, self.write_deadline, buf, true, dolock, dowrite) {
+        let ret = if self.inner.is_nonblocking() {
+            write_nonblocking(buf, true, dowrite)
+        } else {
+            write(fd, self.write_deadline, buf, true, dolock, dowrite)
+        };
+        match ret {
             Ok(_) => Ok(()),
             Err(e) => Err(e)
         }
     }
+
+    // Timeouts and nonblocking mode are implemented entirely in terms of
+    // `read` and `write` above, so the vectored variants only take the fast
+    // path of a single readv/writev for plain blocking sockets.
+    #[cfg(unix)]
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        if self.read_deadline == 0 && !self.inner.is_nonblocking() {
+            let fd = self.fd();
+            let iovs = super::to_mut_iovecs(bufs);
+            if iovs.len() == 0 { return Ok(0) }
+            match retry(|| unsafe {
+                c::readv(fd, iovs.as_ptr(), iovs.len() as libc::c_int)
+                    as libc::c_int
+            }) {
+                0 => return Err(util::eof()),
+                -1 if util::wouldblock() => {}
+                -1 => return Err(os::last_error()),
+                n => return Ok(n as uint),
+            }
+        }
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+    #[cfg(windows)]
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+
+    #[cfg(unix)]
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let mut skip = 0;
+        if self.write_deadline == 0 && !self.inner.is_nonblocking() {
+            let fd = self.fd();
+            match super::keep_going_vectored(bufs, |iov, cnt| unsafe {
+                c::writev(fd, iov, cnt) as i64
+            }) {
+                // Some other handle has flipped on O_NONBLOCK (see the
+                // comments on timeouts below), so finish off the rest of the
+                // buffers with the emulated blocking writes.
+                (n, -1) if util::wouldblock() => { skip = n; }
+                (_, -1) => return Err(os::last_error()),
+                _ => return Ok(()),
+            }
+        }
+        for buf in bufs.iter() {
+            if skip >= buf.len() { skip -= buf.len(); continue }
+            try!(self.write(buf.slice_from(skip)));
+            skip = 0;
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.write(*buf));
+        }
+        Ok(())
+    }
+
     fn peer_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getpeername)
     }
@@ -356,6 +529,9 @@ impl rtio::RtioTcpStream for TcpStream {
     fn nodelay(&mut self) -> IoResult<()> {
         self.set_nodelay(true)
     }
+    fn quickack(&mut self, on: bool) -> IoResult<()> {
+        set_quickack(self.fd(), on)
+    }
     fn keepalive(&mut self, delay_in_seconds: uint) -> IoResult<()> {
         self.set_keepalive(Some(delay_in_seconds))
     }
@@ -395,6 +571,10 @@ impl rtio::RtioSocket for TcpStream {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd() as libc::c_int) }
 }
 
 impl Drop for Inner {
@@ -414,12 +594,17 @@ impl<'a> Drop for Guard<'a> {
 
 pub struct TcpListener {
     inner: Inner,
+    backlog: int,
 }
 
 impl TcpListener {
-    pub fn bind(addr: rtio::SocketAddr) -> IoResult<TcpListener> {
-        let fd = try!(socket(addr, libc::SOCK_STREAM));
-        let ret = TcpListener { inner: Inner::new(fd) };
+    pub fn bind(addr: rtio::SocketAddr,
+                opts: &rtio::SocketOptions) -> IoResult<TcpListener> {
+        let fd = try!(socket(addr, libc::SOCK_STREAM, 0));
+        let ret = TcpListener {
+            inner: Inner::new(fd),
+            backlog: opts.backlog.map(|n| n as int).unwrap_or(128),
+        };
 
         let mut storage = unsafe { mem::zeroed() };
         let len = addr_to_sockaddr(addr, &mut storage);
@@ -428,10 +613,11 @@ impl TcpListener {
         // On platforms with Berkeley-derived sockets, this allows
         // to quickly rebind a socket, without needing to wait for
         // the OS to clean up the previous one.
-        if cfg!(unix) {
+        if cfg!(unix) && opts.reuse_address.is_none() {
             try!(setsockopt(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR,
                             1 as libc::c_int));
         }
+        try!(set_socket_options(fd, addr, opts));
 
         match unsafe { libc::bind(fd, addrp, len) } {
             -1 => Err(os::last_error()),
@@ -488,7 +674,8 @@ impl TcpListener {
 impl rtio::RtioTcpListener for TcpListener {
     fn listen(self: Box<TcpListener>)
               -> IoResult<Box<rtio::RtioTcpAcceptor + Send>> {
-        self.native_listen(128).map(|a| {
+        let backlog = self.backlog;
+        self.native_listen(backlog).map(|a| {
             box a as Box<rtio::RtioTcpAcceptor + Send>
         })
     }
@@ -498,6 +685,10 @@ impl rtio::RtioSocket for TcpListener {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd() as libc::c_int) }
 }
 
 pub struct TcpAcceptor {
@@ -524,6 +715,10 @@ struct AcceptorInner {
 impl TcpAcceptor {
     pub fn fd(&self) -> sock_t { self.inner.listener.fd() }
 
+    fn is_nonblocking(&self) -> bool {
+        self.inner.listener.inner.is_nonblocking()
+    }
+
     #[cfg(unix)]
     pub fn native_accept(&mut self) -> IoResult<TcpStream> {
         // In implementing accept, the two main concerns are dealing with
@@ -540,12 +735,19 @@ impl TcpAcceptor {
         // To implement close_accept(), we have a self-pipe to ourselves which
         // is passed to select() along with the socket being accepted on. The
         // self-pipe is never written to unless close_accept() is called.
+        //
+        // A nonblocking acceptor makes only one attempt to accept, reporting
+        // EAGAIN if there is no pending connection. The listening socket is
+        // always in nonblocking mode here, so nothing need be toggled.
         let deadline = if self.deadline == 0 {None} else {Some(self.deadline)};
 
         while !self.inner.closed.load(atomic::SeqCst) {
             match retry(|| unsafe {
                 libc::accept(self.fd(), ptr::mut_null(), ptr::mut_null())
             }) {
+                -1 if util::wouldblock() && self.is_nonblocking() => {
+                    return Err(os::last_error())
+                }
                 -1 if util::wouldblock() => {}
                 -1 => return Err(os::last_error()),
                 fd => return Ok(TcpStream::new(Inner::new(fd as sock_t))),
@@ -579,10 +781,14 @@ impl TcpAcceptor {
         // succeeds otherwise, then we do a nonblocking poll via `accept` to
         // see if we can accept a connection. The connection is candidate to be
         // stolen, so we do all of this in a loop as well.
+        //
+        // A nonblocking acceptor simply polls the events with a timeout of 0.
         let events = [self.inner.abort.handle(), self.inner.accept.handle()];
 
         while !self.inner.closed.load(atomic::SeqCst) {
-            let ms = if self.deadline == 0 {
+            let ms = if self.is_nonblocking() {
+                0
+            } else if self.deadline == 0 {
                 c::WSA_INFINITE as u64
             } else {
                 let now = ::io::timer::now();
@@ -593,6 +799,13 @@ impl TcpAcceptor {
                                             ms as libc::DWORD, libc::FALSE)
             };
             match ret {
+                c::WSA_WAIT_TIMEOUT if self.is_nonblocking() => {
+                    return Err(IoError {
+                        code: libc::WSAEWOULDBLOCK as uint,
+                        extra: 0,
+                        detail: None,
+                    })
+                }
                 c::WSA_WAIT_TIMEOUT => {
                     return Err(util::timeout("accept timed out"))
                 }
@@ -637,6 +850,14 @@ impl rtio::RtioSocket for TcpAcceptor {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        // The listening descriptor already has O_NONBLOCK set (see
+        // `native_listen`), so only the accept strategy needs to change.
+        self.inner.listener.inner.nonblocking.store(nonblocking,
+                                                    atomic::SeqCst);
+        Ok(())
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd() as libc::c_int) }
 }
 
 impl rtio::RtioTcpAcceptor for TcpAcceptor {
@@ -691,13 +912,15 @@ pub struct UdpSocket {
 }
 
 impl UdpSocket {
-    pub fn bind(addr: rtio::SocketAddr) -> IoResult<UdpSocket> {
-        let fd = try!(socket(addr, libc::SOCK_DGRAM));
+    pub fn bind(addr: rtio::SocketAddr,
+                opts: &rtio::SocketOptions) -> IoResult<UdpSocket> {
+        let fd = try!(socket(addr, libc::SOCK_DGRAM, 0));
         let ret = UdpSocket {
             inner: Arc::new(Inner::new(fd)),
             read_deadline: 0,
             write_deadline: 0,
         };
+        try!(set_socket_options(fd, addr, opts));
 
         let mut storage = unsafe { mem::zeroed() };
         let len = addr_to_sockaddr(addr, &mut storage);
@@ -760,6 +983,10 @@ impl rtio::RtioSocket for UdpSocket {
     fn socket_name(&mut self) -> IoResult<rtio::SocketAddr> {
         sockname(self.fd(), libc::getsockname)
     }
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd() as libc::c_int) }
 }
 
 #[cfg(windows)] type msglen_t = libc::c_int;
@@ -774,7 +1001,7 @@ impl rtio::RtioUdpSocket for UdpSocket {
                 mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
 
         let dolock = || self.lock_nonblocking();
-        let n = try!(read(fd, self.read_deadline, dolock, |nb| unsafe {
+        let doread = |nb| unsafe {
             let flags = if nb {c::MSG_DONTWAIT} else {0};
             libc::recvfrom(fd,
                            buf.as_mut_ptr() as *mut libc::c_void,
@@ -782,7 +1009,12 @@ impl rtio::RtioUdpSocket for UdpSocket {
                            flags,
                            storagep,
                            &mut addrlen) as libc::c_int
-        }));
+        };
+        let n = if self.inner.is_nonblocking() {
+            try!(read_nonblocking(doread))
+        } else {
+            try!(read(fd, self.read_deadline, dolock, doread))
+        };
         sockaddr_to_addr(&storage, addrlen as uint).and_then(|addr| {
             Ok((n as uint, addr))
         })
@@ -805,7 +1037,11 @@ impl rtio::RtioUdpSocket for UdpSocket {
                          dstlen) as i64
         };
 
-        let n = try!(write(fd, self.write_deadline, buf, false, dolock, dowrite));
+        let n = if self.inner.is_nonblocking() {
+            try!(write_nonblocking(buf, false, dowrite))
+        } else {
+            try!(write(fd, self.write_deadline, buf, false, dolock, dowrite))
+        };
         if n != buf.len() {
             Err(util::short_write(n, "couldn't send entire packet at once"))
         } else {
@@ -877,6 +1113,375 @@ impl rtio::RtioUdpSocket for UdpSocket {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// Raw IP sockets
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct RawSocket {
+    inner: Arc<Inner>,
+    read_deadline: u64,
+    write_deadline: u64,
+}
+
+impl RawSocket {
+    pub fn bind(addr: rtio::IpAddr, protocol: u8) -> IoResult<RawSocket> {
+        let addr = rtio::SocketAddr { ip: addr, port: 0 };
+        let fd = try!(socket(addr, libc::SOCK_RAW, protocol as libc::c_int));
+        let ret = RawSocket {
+            inner: Arc::new(Inner::new(fd)),
+            read_deadline: 0,
+            write_deadline: 0,
+        };
+
+        let mut storage = unsafe { mem::zeroed() };
+        let len = addr_to_sockaddr(addr, &mut storage);
+        let addrp = &storage as *const _ as *const libc::sockaddr;
+
+        match unsafe { libc::bind(fd, addrp, len) } {
+            -1 => Err(os::last_error()),
+            _ => Ok(ret),
+        }
+    }
+
+    pub fn fd(&self) -> sock_t { self.inner.fd }
+
+    #[cfg(target_os = "linux")]
+    fn lock_nonblocking(&self) {}
+
+    #[cfg(not(target_os = "linux"))]
+    fn lock_nonblocking<'a>(&'a self) -> Guard<'a> {
+        let ret = Guard {
+            fd: self.fd(),
+            guard: unsafe { self.inner.lock.lock() },
+        };
+        assert!(util::set_nonblocking(self.fd(), true).is_ok());
+        ret
+    }
+}
+
+impl rtio::RtioRawSocket for RawSocket {
+    fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, rtio::IpAddr)> {
+        let fd = self.fd();
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let storagep = &mut storage as *mut _ as *mut libc::sockaddr;
+        let mut addrlen: libc::socklen_t =
+                mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        let dolock = || self.lock_nonblocking();
+        let doread = |nb| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::recvfrom(fd,
+                           buf.as_mut_ptr() as *mut libc::c_void,
+                           buf.len() as msglen_t,
+                           flags,
+                           storagep,
+                           &mut addrlen) as libc::c_int
+        };
+        let n = if self.inner.is_nonblocking() {
+            try!(read_nonblocking(doread))
+        } else {
+            try!(read(fd, self.read_deadline, dolock, doread))
+        };
+        sockaddr_to_addr(&storage, addrlen as uint).and_then(|addr| {
+            Ok((n as uint, addr.ip))
+        })
+    }
+
+    fn send_to(&mut self, buf: &[u8], dst: rtio::IpAddr) -> IoResult<()> {
+        let mut storage = unsafe { mem::zeroed() };
+        let dst = rtio::SocketAddr { ip: dst, port: 0 };
+        let dstlen = addr_to_sockaddr(dst, &mut storage);
+        let dstp = &storage as *const _ as *const libc::sockaddr;
+
+        let fd = self.fd();
+        let dolock = || self.lock_nonblocking();
+        let dowrite = |nb, buf: *const u8, len: uint| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::sendto(fd,
+                         buf as *const libc::c_void,
+                         len as msglen_t,
+                         flags,
+                         dstp,
+                         dstlen) as i64
+        };
+
+        let n = if self.inner.is_nonblocking() {
+            try!(write_nonblocking(buf, false, dowrite))
+        } else {
+            try!(write(fd, self.write_deadline, buf, false, dolock, dowrite))
+        };
+        if n != buf.len() {
+            Err(util::short_write(n, "couldn't send entire packet at once"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn bind_to_interface(&mut self, ifindex: uint) -> IoResult<()> {
+        bind_to_interface(self.fd(), ifindex)
+    }
+    fn attach_filter(&mut self, filter: &[rtio::FilterInstruction]) -> IoResult<()> {
+        attach_filter(self.fd(), filter)
+    }
+    fn header_included(&mut self, on: bool) -> IoResult<()> {
+        setsockopt(self.fd(), libc::IPPROTO_IP, libc::IP_HDRINCL,
+                   on as libc::c_int)
+    }
+    fn time_to_live(&mut self, ttl: int) -> IoResult<()> {
+        setsockopt(self.fd(), libc::IPPROTO_IP, libc::IP_TTL, ttl as libc::c_int)
+    }
+
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd() as libc::c_int) }
+
+    fn clone(&self) -> Box<rtio::RtioRawSocket + Send> {
+        box RawSocket {
+            inner: self.inner.clone(),
+            read_deadline: 0,
+            write_deadline: 0,
+        } as Box<rtio::RtioRawSocket + Send>
+    }
+
+    fn set_timeout(&mut self, timeout: Option<u64>) {
+        let deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+        self.read_deadline = deadline;
+        self.write_deadline = deadline;
+    }
+    fn set_read_timeout(&mut self, timeout: Option<u64>) {
+        self.read_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+    fn set_write_timeout(&mut self, timeout: Option<u64>) {
+        self.write_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+}
+
+// Binding to an interface by index isn't universally supported; linux only
+// binds by name, and most other platforms can't do it at all.
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+fn bind_to_interface(fd: sock_t, ifindex: uint) -> IoResult<()> {
+    let mut name = [0 as libc::c_char, ..c::IF_NAMESIZE];
+    unsafe {
+        if c::if_indextoname(ifindex as libc::c_uint,
+                             name.as_mut_ptr()).is_null() {
+            return Err(os::last_error())
+        }
+        match libc::setsockopt(fd, libc::SOL_SOCKET, c::SO_BINDTODEVICE,
+                               name.as_ptr() as *const libc::c_void,
+                               name.len() as libc::socklen_t) {
+            0 => Ok(()),
+            _ => Err(os::last_error()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+fn bind_to_interface(fd: sock_t, ifindex: uint) -> IoResult<()> {
+    setsockopt(fd, libc::IPPROTO_IP, c::IP_BOUND_IF, ifindex as libc::c_int)
+}
+
+#[cfg(not(target_os = "linux"), not(target_os = "android"),
+      not(target_os = "macos"), not(target_os = "ios"))]
+fn bind_to_interface(_fd: sock_t, _ifindex: uint) -> IoResult<()> {
+    Err(super::unimpl())
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+fn attach_filter(fd: sock_t, filter: &[rtio::FilterInstruction]) -> IoResult<()> {
+    use std::u16;
+
+    // Don't let the length be silently truncated into a shorter filter
+    if filter.len() > u16::MAX as uint {
+        return Err(IoError {
+            code: libc::EINVAL as uint,
+            extra: 0,
+            detail: Some("socket filter is too long".to_string()),
+        })
+    }
+    let prog = c::sock_fprog {
+        len: filter.len() as libc::c_ushort,
+        filter: filter.as_ptr(),
+    };
+    setsockopt(fd, libc::SOL_SOCKET, c::SO_ATTACH_FILTER, prog)
+}
+
+#[cfg(not(target_os = "linux"), not(target_os = "android"))]
+fn attach_filter(_fd: sock_t, _filter: &[rtio::FilterInstruction]) -> IoResult<()> {
+    Err(super::unimpl())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Packet sockets
+//
+// These are only available on linux, where they're AF_PACKET sockets. As linux
+// has MSG_DONTWAIT, there's no locking to be done around nonblocking reads and
+// writes.
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub struct PacketSocket {
+    inner: Arc<Inner>,
+    // The interface we're bound to, or 0 for all of them
+    ifindex: uint,
+    read_deadline: u64,
+    write_deadline: u64,
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+impl PacketSocket {
+    pub fn bind(ifindex: uint, protocol: u16) -> IoResult<PacketSocket> {
+        let fd = match unsafe {
+            libc::socket(libc::AF_PACKET, libc::SOCK_RAW,
+                         htons(protocol) as libc::c_int)
+        } {
+            -1 => return Err(os::last_error()),
+            fd => fd,
+        };
+        let ret = PacketSocket {
+            inner: Arc::new(Inner::new(fd)),
+            ifindex: ifindex,
+            read_deadline: 0,
+            write_deadline: 0,
+        };
+
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as libc::c_ushort;
+        addr.sll_protocol = htons(protocol);
+        addr.sll_ifindex = ifindex as libc::c_int;
+        let addrp = &addr as *const _ as *const libc::sockaddr;
+        let len = mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+
+        match unsafe { libc::bind(fd, addrp, len) } {
+            -1 => Err(os::last_error()),
+            _ => Ok(ret),
+        }
+    }
+
+    pub fn fd(&self) -> sock_t { self.inner.fd }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+impl rtio::RtioPacketSocket for PacketSocket {
+    fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, rtio::LinkAddr)> {
+        let fd = self.fd();
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        let addrp = &mut addr as *mut _ as *mut libc::sockaddr;
+        let mut addrlen: libc::socklen_t =
+                mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+
+        let doread = |nb| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::recvfrom(fd,
+                           buf.as_mut_ptr() as *mut libc::c_void,
+                           buf.len() as msglen_t,
+                           flags,
+                           addrp,
+                           &mut addrlen) as libc::c_int
+        };
+        let n = if self.inner.is_nonblocking() {
+            try!(read_nonblocking(doread))
+        } else {
+            try!(read(fd, self.read_deadline, || {}, doread))
+        };
+        let halen = cmp::min(addr.sll_halen as uint, addr.sll_addr.len());
+        Ok((n as uint, rtio::LinkAddr {
+            ifindex: addr.sll_ifindex as uint,
+            protocol: ntohs(addr.sll_protocol),
+            address: addr.sll_addr.slice_to(halen).to_vec(),
+        }))
+    }
+
+    fn send_to(&mut self, buf: &[u8], dst: &rtio::LinkAddr) -> IoResult<()> {
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        if dst.address.len() > addr.sll_addr.len() {
+            return Err(IoError {
+                code: libc::EINVAL as uint,
+                extra: 0,
+                detail: Some("hardware address is too long".to_string()),
+            })
+        }
+        addr.sll_family = libc::AF_PACKET as libc::c_ushort;
+        addr.sll_protocol = htons(dst.protocol);
+        addr.sll_ifindex = dst.ifindex as libc::c_int;
+        addr.sll_halen = dst.address.len() as libc::c_uchar;
+        for (slot, &b) in addr.sll_addr.mut_iter().zip(dst.address.iter()) {
+            *slot = b;
+        }
+        let dstp = &addr as *const _ as *const libc::sockaddr;
+        let dstlen = mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+
+        let fd = self.fd();
+        let dowrite = |nb, buf: *const u8, len: uint| unsafe {
+            let flags = if nb {c::MSG_DONTWAIT} else {0};
+            libc::sendto(fd,
+                         buf as *const libc::c_void,
+                         len as msglen_t,
+                         flags,
+                         dstp,
+                         dstlen) as i64
+        };
+
+        let n = if self.inner.is_nonblocking() {
+            try!(write_nonblocking(buf, false, dowrite))
+        } else {
+            try!(write(fd, self.write_deadline, buf, false, || {}, dowrite))
+        };
+        if n != buf.len() {
+            Err(util::short_write(n, "couldn't send entire frame at once"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn attach_filter(&mut self, filter: &[rtio::FilterInstruction]) -> IoResult<()> {
+        attach_filter(self.fd(), filter)
+    }
+
+    fn promiscuous(&mut self, on: bool) -> IoResult<()> {
+        let mreq = c::packet_mreq {
+            mr_ifindex: self.ifindex as libc::c_int,
+            mr_type: c::PACKET_MR_PROMISC,
+            mr_alen: 0,
+            mr_address: [0, ..8],
+        };
+        let opt = if on {c::PACKET_ADD_MEMBERSHIP} else {c::PACKET_DROP_MEMBERSHIP};
+        setsockopt(self.fd(), c::SOL_PACKET, opt, mreq)
+    }
+
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+    fn raw_fd(&self) -> Option<libc::c_int> { Some(self.fd()) }
+
+    fn clone(&self) -> Box<rtio::RtioPacketSocket + Send> {
+        box PacketSocket {
+            inner: self.inner.clone(),
+            ifindex: self.ifindex,
+            read_deadline: 0,
+            write_deadline: 0,
+        } as Box<rtio::RtioPacketSocket + Send>
+    }
+
+    fn set_timeout(&mut self, timeout: Option<u64>) {
+        let deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+        self.read_deadline = deadline;
+        self.write_deadline = deadline;
+    }
+    fn set_read_timeout(&mut self, timeout: Option<u64>) {
+        self.read_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+    fn set_write_timeout(&mut self, timeout: Option<u64>) {
+        self.write_deadline = timeout.map(|a| ::io::timer::now() + a).unwrap_or(0);
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Timeout helpers
 //
@@ -1032,6 +1637,39 @@ pub fn write<T>(fd: sock_t,
     }
 }
 
+// Sockets which have been placed into nonblocking mode bypass all of the above.
+// Readiness is the responsibility of whoever put the socket in nonblocking mode
+// (generally a poller), so these helpers make exactly one attempt at the I/O
+// and hand EAGAIN/EWOULDBLOCK straight back to the caller.
+
+pub fn read_nonblocking(read: |bool| -> libc::c_int) -> IoResult<uint> {
+    match retry(|| read(true)) {
+        0 => Err(util::eof()),
+        n if n < 0 => Err(os::last_error()),
+        n => Ok(n as uint)
+    }
+}
+
+pub fn write_nonblocking(buf: &[u8],
+                         write_everything: bool,
+                         write: |bool, *const u8, uint| -> i64)
+                         -> IoResult<uint> {
+    let mut written = 0;
+    while written < buf.len() {
+        let ptr = buf.slice_from(written).as_ptr();
+        let len = buf.len() - written;
+        match retry(|| write(true, ptr, len) as libc::c_int) {
+            -1 if util::wouldblock() && written > 0 => {
+                return Err(util::short_write(written, "short write"))
+            }
+            -1 => return Err(os::last_error()),
+            n => { written += n as uint; }
+        }
+        if !write_everything { break }
+    }
+    Ok(written)
+}
+
 #[cfg(windows)]
 mod os {
     use libc;
@@ -0,0 +1,196 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness notification for linux, backed by epoll
+//!
+//! A `Poller` owns a single epoll instance. Descriptors are registered along
+//! with a caller-chosen token which is stored in the `data` field of the
+//! epoll_event, so no bookkeeping of registered descriptors is needed on this
+//! side; the kernel hands the token straight back to us.
+//!
+//! The poller never takes ownership of the descriptors registered with it. It
+//! is up to the owner of each socket to deregister it before closing it (epoll
+//! will drop closed descriptors on its own, but only once every duplicate of
+//! the descriptor has been closed).
+
+use libc;
+use std::cmp;
+use std::i32;
+use std::rt::rtio;
+use std::rt::rtio::IoResult;
+
+use super::file::FileDesc;
+use super::{retry, last_error, mkerr_libc};
+
+static EPOLL_CTL_ADD: libc::c_int = 1;
+static EPOLL_CTL_DEL: libc::c_int = 2;
+static EPOLL_CTL_MOD: libc::c_int = 3;
+
+static EPOLLIN: u32 = 0x001;
+static EPOLLOUT: u32 = 0x004;
+static EPOLLERR: u32 = 0x008;
+static EPOLLHUP: u32 = 0x010;
+static EPOLLRDHUP: u32 = 0x2000;
+static EPOLLET: u32 = 1 << 31;
+
+static EPOLL_CLOEXEC: libc::c_int = 0o2000000;
+
+// The kernel declares this structure as packed on x86_64 only, so that the
+// layout is identical for 32 and 64-bit userspace.
+#[cfg(target_arch = "x86_64")]
+#[repr(C, packed)]
+struct epoll_event {
+    events: u32,
+    data: u64,
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[repr(C)]
+struct epoll_event {
+    events: u32,
+    data: u64,
+}
+
+extern {
+    fn epoll_create1(flags: libc::c_int) -> libc::c_int;
+    fn epoll_ctl(epfd: libc::c_int, op: libc::c_int, fd: libc::c_int,
+                 event: *mut epoll_event) -> libc::c_int;
+    fn epoll_wait(epfd: libc::c_int, events: *mut epoll_event,
+                  maxevents: libc::c_int, timeout: libc::c_int) -> libc::c_int;
+}
+
+pub struct Poller {
+    epfd: FileDesc,
+}
+
+impl Poller {
+    pub fn new() -> IoResult<Poller> {
+        match unsafe { epoll_create1(EPOLL_CLOEXEC) } {
+            -1 => Err(last_error()),
+            fd => Ok(Poller { epfd: FileDesc::new(fd, true) }),
+        }
+    }
+
+    fn ctl(&mut self, op: libc::c_int, fd: libc::c_int, token: uint,
+           interest: uint, edge: bool) -> IoResult<()> {
+        let mut event = epoll_event {
+            events: to_epoll(interest, edge),
+            data: token as u64,
+        };
+        mkerr_libc(unsafe {
+            epoll_ctl(self.epfd.fd(), op, fd, &mut event)
+        })
+    }
+}
+
+fn to_epoll(interest: uint, edge: bool) -> u32 {
+    let mut events = 0;
+    if interest & rtio::POLL_READABLE != 0 { events |= EPOLLIN | EPOLLRDHUP; }
+    if interest & rtio::POLL_WRITABLE != 0 { events |= EPOLLOUT; }
+    if edge { events |= EPOLLET; }
+    events
+}
+
+fn from_epoll(events: u32) -> uint {
+    let mut readiness = 0;
+    if events & EPOLLIN != 0 { readiness |= rtio::POLL_READABLE; }
+    if events & EPOLLOUT != 0 { readiness |= rtio::POLL_WRITABLE; }
+    if events & (EPOLLHUP | EPOLLRDHUP) != 0 {
+        readiness |= rtio::POLL_HANGUP;
+    }
+    if events & EPOLLERR != 0 { readiness |= rtio::POLL_ERROR; }
+    readiness
+}
+
+impl rtio::RtioPoller for Poller {
+    fn register(&mut self, fd: libc::c_int, token: uint, interest: uint,
+                edge: bool) -> IoResult<()> {
+        self.ctl(EPOLL_CTL_ADD, fd, token, interest, edge)
+    }
+
+    fn reregister(&mut self, fd: libc::c_int, token: uint, interest: uint,
+                  edge: bool) -> IoResult<()> {
+        self.ctl(EPOLL_CTL_MOD, fd, token, interest, edge)
+    }
+
+    fn deregister(&mut self, fd: libc::c_int) -> IoResult<()> {
+        // Kernels before 2.6.9 require a non-null event even though it is
+        // ignored for EPOLL_CTL_DEL.
+        self.ctl(EPOLL_CTL_DEL, fd, 0, 0, false)
+    }
+
+    fn poll(&mut self, events: &mut Vec<rtio::PollEvent>, max: uint,
+            timeout_ms: Option<u64>) -> IoResult<()> {
+        events.truncate(0);
+        let max = cmp::max(max, 1);
+        let mut buf: Vec<epoll_event> = Vec::with_capacity(max);
+
+        // Like `util::await`, the timeout is recalculated on each iteration in
+        // case we were interrupted by a signal.
+        let deadline = timeout_ms.map(|ms| ::io::timer::now() + ms);
+        let n = retry(|| {
+            let timeout = match deadline {
+                None => -1,
+                Some(deadline) => {
+                    let now = ::io::timer::now();
+                    if deadline < now {0} else {
+                        cmp::min(deadline - now, i32::MAX as u64) as libc::c_int
+                    }
+                }
+            };
+            unsafe {
+                epoll_wait(self.epfd.fd(), buf.as_mut_ptr(),
+                           max as libc::c_int, timeout)
+            }
+        });
+        if n == -1 { return Err(last_error()) }
+
+        unsafe { buf.set_len(n as uint); }
+        for event in buf.iter() {
+            events.push(rtio::PollEvent {
+                token: event.data as uint,
+                readiness: from_epoll(event.events),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rt::rtio::{RtioPoller, POLL_READABLE, POLL_WRITABLE};
+    use io::process;
+    use super::Poller;
+
+    #[test]
+    fn pipe_readiness() {
+        let (mut reader, mut writer) = process::pipe().unwrap();
+        let mut poller = Poller::new().unwrap();
+        poller.register(reader.fd(), 1, POLL_READABLE, false).unwrap();
+        poller.register(writer.fd(), 2, POLL_WRITABLE, false).unwrap();
+
+        let mut events = Vec::new();
+        poller.poll(&mut events, 8, Some(0)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].token, 2);
+
+        writer.inner_write([1]).unwrap();
+        poller.deregister(writer.fd()).unwrap();
+        poller.poll(&mut events, 8, Some(1000)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].token, 1);
+        assert!(events[0].readiness & POLL_READABLE != 0);
+
+        let mut buf = [0];
+        assert_eq!(reader.inner_read(buf).unwrap(), 1);
+        poller.poll(&mut events, 8, Some(0)).unwrap();
+        assert_eq!(events.len(), 0);
+    }
+}
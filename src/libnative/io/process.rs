@@ -53,6 +53,9 @@ pub struct Process {
 
     /// Deadline after which wait() will return
     deadline: u64,
+
+    /// The process group the child was spawned into, if it isn't ours
+    pgid: Option<pid_t>,
 }
 
 #[cfg(unix)]
@@ -103,12 +106,20 @@ impl Process {
 
         match res {
             Ok(res) => {
+                // A session leader is also the leader of its process group
+                let pgid = match cfg.pgid {
+                    Some(0) => Some(res.pid),
+                    Some(pgid) => Some(pgid as pid_t),
+                    None if cfg.detach && cfg!(unix) => Some(res.pid),
+                    None => None,
+                };
                 let p = Process {
                     pid: res.pid,
                     handle: res.handle,
                     exit_code: None,
                     exit_signal: None,
                     deadline: 0,
+                    pgid: pgid,
                 };
                 Ok((p, ret_io))
             }
@@ -128,6 +139,23 @@ impl rtio::RtioProcess for Process {
         self.deadline = timeout.map(|i| i + ::io::timer::now()).unwrap_or(0);
     }
 
+    fn kill_group(&mut self, signum: int) -> IoResult<()> {
+        // Unlike the child itself, the group can be signalled after the child
+        // has been reaped, as the id of a group can't be reused while any
+        // process remains in it.
+        #[cfg(unix)] use libc::EINVAL as ERROR;
+        #[cfg(windows)] use libc::ERROR_CALL_NOT_IMPLEMENTED as ERROR;
+
+        match self.pgid {
+            Some(pgid) => unsafe { killpid(-pgid, signum) },
+            None => Err(IoError {
+                code: ERROR as uint,
+                extra: 0,
+                detail: Some("process has no group of its own".to_string()),
+            }),
+        }
+    }
+
     fn wait(&mut self) -> IoResult<rtio::ProcessExit> {
         match self.exit_code {
             Some(code) => Ok(code),
@@ -304,6 +332,14 @@ fn spawn_process_os(cfg: ProcessConfig,
             detail: Some("unsupported gid/uid requested on windows".to_string()),
         })
     }
+    if cfg.pgid.is_some() || cfg.rlimits.len() > 0 {
+        return Err(IoError {
+            code: libc::ERROR_CALL_NOT_IMPLEMENTED as uint,
+            extra: 0,
+            detail: Some("unsupported process group or resource limit \
+                          requested on windows".to_string()),
+        })
+    }
 
     // To have the spawning semantics of unix/windows stay the same, we need to
     // read the *child's* PATH if one is provided. See #15149 for more details.
@@ -532,6 +568,7 @@ fn spawn_process_os(cfg: ProcessConfig,
                 -> IoResult<SpawnProcessResult>
 {
     use libc::funcs::posix88::unistd::{fork, dup2, close, chdir, execvp};
+    use libc::funcs::posix88::unistd::setpgid;
     use libc::funcs::bsd44::getdtablesize;
     use io::c;
 
@@ -558,6 +595,26 @@ fn spawn_process_os(cfg: ProcessConfig,
         assert_eq!(ret, 0);
     }
 
+    fn resource(resource: rtio::Resource) -> c_int {
+        match resource {
+            rtio::CpuTime => c::RLIMIT_CPU,
+            rtio::FileSize => c::RLIMIT_FSIZE,
+            rtio::DataSize => c::RLIMIT_DATA,
+            rtio::StackSize => c::RLIMIT_STACK,
+            rtio::CoreFileSize => c::RLIMIT_CORE,
+            rtio::OpenFiles => c::RLIMIT_NOFILE,
+            rtio::AddressSpace => c::RLIMIT_AS,
+            rtio::Processes => c::RLIMIT_NPROC,
+        }
+    }
+
+    fn to_rlim(limit: Option<u64>) -> c::rlim_t {
+        match limit {
+            Some(n) if (n as c::rlim_t) < c::RLIM_INFINITY => n as c::rlim_t,
+            _ => c::RLIM_INFINITY,
+        }
+    }
+
     let dirp = cfg.cwd.map(|c| c.as_ptr()).unwrap_or(ptr::null());
 
     let cfg = unsafe {
@@ -578,6 +635,15 @@ fn spawn_process_os(cfg: ProcessConfig,
             if pid < 0 {
                 return Err(super::last_error())
             } else if pid > 0 {
+                // Also move the child into its group from this side, so that
+                // the group is known to exist as soon as we return. This fails
+                // harmlessly if the child has already exec'd.
+                match cfg.pgid {
+                    Some(pgid) => {
+                        let _ = setpgid(pid, pgid as pid_t);
+                    }
+                    None => {}
+                }
                 drop(output);
                 let mut bytes = [0, ..4];
                 return match input.inner_read(bytes) {
@@ -685,6 +751,18 @@ fn spawn_process_os(cfg: ProcessConfig,
                 }
             }
 
+            // Limits are set while we still have any privileges needed to
+            // raise them, before dropping to another user.
+            for limit in cfg.rlimits.iter() {
+                let rlim = c::rlimit {
+                    rlim_cur: to_rlim(limit.soft),
+                    rlim_max: to_rlim(limit.hard),
+                };
+                if c::setrlimit(resource(limit.resource), &rlim) != 0 {
+                    fail(&mut output);
+                }
+            }
+
             match cfg.gid {
                 Some(u) => {
                     if libc::setgid(u as libc::gid_t) != 0 {
@@ -720,6 +798,14 @@ fn spawn_process_os(cfg: ProcessConfig,
                 // error, but ignore it anyway.
                 let _ = libc::setsid();
             }
+            match cfg.pgid {
+                Some(pgid) => {
+                    if setpgid(0, pgid as pid_t) != 0 {
+                        fail(&mut output);
+                    }
+                }
+                None => {}
+            }
             if !dirp.is_null() && chdir(dirp) == -1 {
                 fail(&mut output);
             }
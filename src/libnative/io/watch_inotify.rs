@@ -0,0 +1,347 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filesystem change notification for linux, backed by inotify
+//!
+//! Each watcher owns its own inotify instance, created (along with the watches
+//! on the initial tree) on the calling thread so that any errors can be
+//! reported straight away. The instance is then handed off to a helper thread
+//! which select()s over the inotify descriptors of all live watchers and runs
+//! their callbacks as events arrive, in much the same way as the timer helper
+//! thread in `timer_unix`.
+//!
+//! inotify itself is not recursive, so watching a tree means adding a watch
+//! for every directory within it. Directories created after the watcher was
+//! started are picked up by the helper thread when their creation is reported,
+//! but anything created within them before the new watch is in place will be
+//! missed.
+//!
+//! The path behind each watch is kept up to date as directories are moved
+//! around within the tree. Watches on directories moved out of it are removed,
+//! and so are all of them once the root itself moves, as where it went isn't
+//! reported.
+
+use libc;
+use std::c_str::CString;
+use std::collections::HashMap;
+use std::mem;
+use std::os;
+use std::ptr;
+use std::rt::rtio;
+use std::rt::rtio::IoResult;
+use std::sync::atomic;
+use std::comm;
+
+use io::c;
+use io::file;
+use io::file::FileDesc;
+use io::helper_thread::Helper;
+use io::util;
+use super::last_error;
+
+helper_init!(static mut HELPER: Helper<Req>)
+
+static IN_MODIFY: u32 = 0x00000002;
+static IN_ATTRIB: u32 = 0x00000004;
+static IN_MOVED_FROM: u32 = 0x00000040;
+static IN_MOVED_TO: u32 = 0x00000080;
+static IN_CREATE: u32 = 0x00000100;
+static IN_DELETE: u32 = 0x00000200;
+static IN_DELETE_SELF: u32 = 0x00000400;
+static IN_MOVE_SELF: u32 = 0x00000800;
+static IN_IGNORED: u32 = 0x00008000;
+static IN_ONLYDIR: u32 = 0x01000000;
+static IN_DONT_FOLLOW: u32 = 0x02000000;
+static IN_ISDIR: u32 = 0x40000000;
+
+static IN_CLOEXEC: libc::c_int = 0o2000000;
+static IN_NONBLOCK: libc::c_int = 0o4000;
+
+static WATCH_MASK: u32 = IN_MODIFY | IN_ATTRIB | IN_MOVED_FROM | IN_MOVED_TO |
+                         IN_CREATE | IN_DELETE | IN_DELETE_SELF | IN_MOVE_SELF;
+
+#[repr(C)]
+struct inotify_event {
+    wd: libc::c_int,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+    // followed by `len` bytes of nul-padded name
+}
+
+extern {
+    fn inotify_init1(flags: libc::c_int) -> libc::c_int;
+    fn inotify_add_watch(fd: libc::c_int, pathname: *const libc::c_char,
+                         mask: u32) -> libc::c_int;
+    fn inotify_rm_watch(fd: libc::c_int, wd: libc::c_int) -> libc::c_int;
+}
+
+pub struct Watcher {
+    id: uint,
+}
+
+struct Inner {
+    id: uint,
+    fd: FileDesc,
+    recursive: bool,
+    // the path of the directory (or file) behind each watch descriptor
+    watches: HashMap<libc::c_int, Vec<u8>>,
+    // the watch descriptor of the watched path itself
+    root: libc::c_int,
+    // directories moved away, by cookie, until they turn up elsewhere in
+    // the tree or are found to have left it
+    moved_from: Vec<(u32, Vec<u8>)>,
+    cb: Box<rtio::FsEventCallback + Send>,
+}
+
+#[allow(visible_private_types)]
+pub enum Req {
+    // Start delivering events for a new watcher
+    NewWatcher(Box<Inner>),
+
+    // Stop delivering events for the watcher with the given id, closing its
+    // inotify instance, and then acknowledge on the channel provided.
+    RemoveWatcher(uint, Sender<()>),
+}
+
+impl Inner {
+    // Adds a watch for `path`, and for everything below it if this watcher is
+    // recursive. Only directories are descended into, and symlinks are never
+    // followed below the root.
+    fn add(&mut self, path: &CString, root: bool) -> IoResult<()> {
+        let mut mask = WATCH_MASK;
+        if !root { mask |= IN_DONT_FOLLOW; }
+        if self.recursive { mask |= IN_ONLYDIR; }
+        let wd = unsafe {
+            inotify_add_watch(self.fd.fd(), path.as_ptr(), mask)
+        };
+        if wd == -1 {
+            // IN_ONLYDIR is only a way of finding directories without a
+            // separate stat(), so files are fine (if they're the root they're
+            // watched on their own).
+            if self.recursive && os::errno() == libc::ENOTDIR as int {
+                if !root { return Ok(()) }
+                self.recursive = false;
+                return self.add(path, root);
+            }
+            return Err(last_error())
+        }
+        self.watches.insert(wd, path.as_bytes_no_nul().to_vec());
+        if root { self.root = wd; }
+
+        if self.recursive {
+            for child in try!(file::readdir(path)).iter() {
+                // The directory may well be changing under our feet, so
+                // failing to watch some part of it isn't fatal.
+                let _ = self.add(child, false);
+            }
+        }
+        Ok(())
+    }
+
+    // Reads and dispatches all pending events, returning once the inotify
+    // instance has been drained.
+    fn dispatch(&mut self) {
+        let mut buf = [0u8, ..4096];
+        loop {
+            let n = match self.fd.inner_read(buf) {
+                Ok(n) => n,
+                Err(..) => break,
+            };
+            let mut pos = 0;
+            while pos + mem::size_of::<inotify_event>() <= n {
+                let event: inotify_event = unsafe {
+                    ptr::read(buf.as_ptr().offset(pos as int) as *const _)
+                };
+                pos += mem::size_of::<inotify_event>();
+                let name = buf.slice(pos, pos + event.len as uint);
+                pos += event.len as uint;
+                let name = match name.iter().position(|&b| b == 0) {
+                    Some(i) => name.slice_to(i),
+                    None => name,
+                };
+                self.event(event.wd, event.mask, event.cookie, name);
+            }
+        }
+        // Both halves of a move are queued together, so a directory which
+        // hasn't turned up again has left the tree, and whatever happens to
+        // it now would be reported under its old path.
+        for (_, from) in mem::replace(&mut self.moved_from, Vec::new()).move_iter() {
+            self.forget(from.as_slice());
+        }
+    }
+
+    // Updates the paths of `from` and everything below it, which moved to `to`
+    fn rename(&mut self, from: &[u8], to: &[u8]) {
+        for (_, path) in self.watches.mut_iter() {
+            if is_within(path.as_slice(), from) {
+                let mut moved = to.to_vec();
+                moved.push_all(path.slice_from(from.len()));
+                *path = moved;
+            }
+        }
+    }
+
+    // Stops watching `dir` and everything below it
+    fn forget(&mut self, dir: &[u8]) {
+        let stale: Vec<libc::c_int> = self.watches.iter().filter(|&(_, path)| {
+            is_within(path.as_slice(), dir)
+        }).map(|(&wd, _)| wd).collect();
+        for wd in stale.iter() {
+            unsafe { inotify_rm_watch(self.fd.fd(), *wd); }
+            self.watches.remove(wd);
+        }
+    }
+
+    fn event(&mut self, wd: libc::c_int, mask: u32, cookie: u32, name: &[u8]) {
+        if mask & IN_IGNORED != 0 {
+            self.watches.remove(&wd);
+            return
+        }
+        let mut path = match self.watches.find(&wd) {
+            Some(dir) => dir.clone(),
+            None => return,
+        };
+        if name.len() > 0 {
+            path.push(b'/');
+            path.push_all(name);
+        }
+        let bytes = path;
+        let path = bytes.as_slice().to_c_str();
+
+        let kind = if mask & IN_CREATE != 0 {
+            if self.recursive && mask & IN_ISDIR != 0 {
+                let _ = self.add(&path, false);
+            }
+            rtio::FsCreate
+        } else if mask & (IN_DELETE | IN_DELETE_SELF) != 0 {
+            rtio::FsDelete
+        } else if mask & IN_MOVE_SELF != 0 {
+            // Directories moved within the tree are taken care of through
+            // the events of their parents
+            if wd != self.root { return }
+            self.forget(bytes.as_slice());
+            rtio::FsRename
+        } else if mask & (IN_MOVED_FROM | IN_MOVED_TO) != 0 {
+            if self.recursive && mask & IN_ISDIR != 0 {
+                if mask & IN_MOVED_FROM != 0 {
+                    self.moved_from.push((cookie, bytes.clone()));
+                } else {
+                    let from = self.moved_from.iter().position(|&(c, _)| c == cookie);
+                    match from {
+                        Some(i) => {
+                            let (_, from) = self.moved_from.remove(i).unwrap();
+                            self.rename(from.as_slice(), bytes.as_slice());
+                        }
+                        None => { let _ = self.add(&path, false); }
+                    }
+                }
+            }
+            rtio::FsRename
+        } else if mask & (IN_MODIFY | IN_ATTRIB) != 0 {
+            rtio::FsModify
+        } else {
+            return
+        };
+        self.cb.call(rtio::FsEvent { kind: kind, path: path });
+    }
+}
+
+// Whether `path` is `dir` or is below it
+fn is_within(path: &[u8], dir: &[u8]) -> bool {
+    path.starts_with(dir) && (path.len() == dir.len() || path[dir.len()] == b'/')
+}
+
+fn helper(input: libc::c_int, messages: Receiver<Req>, _: ()) {
+    let mut input = FileDesc::new(input, true);
+    assert!(util::set_nonblocking(input.fd(), true).is_ok());
+    let mut active: Vec<Box<Inner>> = Vec::new();
+
+    'outer: loop {
+        let mut set: c::fd_set = unsafe { mem::zeroed() };
+        let mut max = input.fd();
+        c::fd_set(&mut set, input.fd());
+        for w in active.iter() {
+            c::fd_set(&mut set, w.fd.fd());
+            if w.fd.fd() > max { max = w.fd.fd(); }
+        }
+
+        match unsafe {
+            c::select(max + 1, &mut set, ptr::mut_null(), ptr::mut_null(),
+                      ptr::mut_null())
+        } {
+            -1 if os::errno() == libc::EINTR as int => continue,
+            -1 => fail!("helper thread failed in select() with error: {}",
+                        os::last_os_error()),
+            _ => {}
+        }
+
+        // All of the descriptors are nonblocking, so rather than figuring out
+        // which ones woke us up, just try reading from all of them.
+        let mut buf = [0, ..128];
+        while input.inner_read(buf).is_ok() {}
+        loop {
+            match messages.try_recv() {
+                Err(comm::Disconnected) => break 'outer,
+                Ok(NewWatcher(w)) => active.push(w),
+                Ok(RemoveWatcher(id, ack)) => {
+                    let i = active.iter().position(|w| w.id == id);
+                    drop(active.remove(i.expect("no watcher found")));
+                    ack.send(());
+                }
+                Err(..) => break,
+            }
+        }
+        for w in active.mut_iter() {
+            w.dispatch();
+        }
+    }
+}
+
+impl Watcher {
+    pub fn new(path: &CString, recursive: bool,
+               cb: Box<rtio::FsEventCallback + Send>) -> IoResult<Watcher> {
+        let fd = match unsafe { inotify_init1(IN_CLOEXEC | IN_NONBLOCK) } {
+            -1 => return Err(last_error()),
+            fd => FileDesc::new(fd, true),
+        };
+
+        static mut ID: atomic::AtomicUint = atomic::INIT_ATOMIC_UINT;
+        let id = unsafe { ID.fetch_add(1, atomic::Relaxed) };
+        let mut inner = box Inner {
+            id: id,
+            fd: fd,
+            recursive: recursive,
+            watches: HashMap::new(),
+            root: -1,
+            moved_from: Vec::new(),
+            cb: cb,
+        };
+        try!(inner.add(path, true));
+
+        unsafe {
+            HELPER.boot(|| {}, helper);
+            HELPER.send(NewWatcher(inner));
+        }
+        Ok(Watcher { id: id })
+    }
+}
+
+impl rtio::RtioFsWatcher for Watcher {}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // Wait for the helper thread to drop our callback, so that no events
+        // are delivered after the watcher is gone.
+        let (tx, rx) = channel();
+        unsafe { HELPER.send(RemoveWatcher(self.id, tx)); }
+        rx.recv();
+    }
+}
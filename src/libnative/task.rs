@@ -250,6 +250,65 @@ impl rt::Runtime for Ops {
         Local::put(cur_task);
     }
 
+    // This works like the multi-handle case of `deschedule`, with one extra
+    // handle kept here. If the condition variable times out, the extra handle
+    // is used to try to reclaim the task: if that fails, someone else is
+    // already waking us up, so we have to wait for their signal after all.
+    fn deschedule_timeout(mut self: Box<Ops>,
+                          times: uint,
+                          ms: u64,
+                          mut cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>) {
+        let me = &mut *self as *mut Ops;
+        cur_task.put_runtime(self);
+
+        unsafe {
+            let cur_task_dupe = &mut *cur_task as *mut Task;
+            let mut iter = BlockedTask::block(cur_task).make_selectable(times + 1);
+            let mut timeout_handle = iter.next();
+            let guard = (*me).lock.lock();
+            (*me).awoken = false;
+
+            match iter.map(f).filter_map(|a| a.err()).next() {
+                None => {}
+                Some(task) => {
+                    match task.wake() {
+                        Some(task) => {
+                            mem::forget(task);
+                            (*me).awoken = true;
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            let deadline = rt::time::now() + ms;
+            while !(*me).awoken {
+                if timeout_handle.is_none() {
+                    guard.wait();
+                    continue
+                }
+                let now = rt::time::now();
+                if now < deadline {
+                    guard.wait_timeout(deadline - now);
+                    continue
+                }
+                match timeout_handle.take().unwrap().wake() {
+                    Some(task) => {
+                        mem::forget(task);
+                        (*me).awoken = true;
+                    }
+                    None => {}
+                }
+            }
+            timeout_handle.map(|t| t.trash());
+
+            cur_task = mem::transmute(cur_task_dupe);
+        }
+
+        Local::put(cur_task);
+    }
+
     // See the comments on `deschedule` for why the task is forgotten here, and
     // why it's valid to do so.
     fn reawaken(mut self: Box<Ops>, mut to_wake: Box<Task>) {
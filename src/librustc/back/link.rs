@@ -20,7 +20,7 @@ use driver::config;
 use llvm;
 use llvm::ModuleRef;
 use metadata::common::LinkMeta;
-use metadata::{encoder, cstore, filesearch, csearch, loader, creader};
+use metadata::{encoder, cstore, filesearch, csearch, creader};
 use middle::trans::context::CrateContext;
 use middle::trans::common::gensym_name;
 use middle::ty;
@@ -39,7 +39,6 @@ use std::str;
 use std::string::String;
 use flate;
 use serialize::hex::ToHex;
-use syntax::abi;
 use syntax::ast;
 use syntax::ast_map::{PathElem, PathElems, PathName};
 use syntax::ast_map;
@@ -87,134 +86,135 @@ pub enum OutputType {
 }
 
 pub fn llvm_err(sess: &Session, msg: String) -> ! {
+    sess.fatal(llvm_err_msg(msg).as_slice())
+}
+
+/// Appends the last error reported by LLVM, if any, to `msg`.
+fn llvm_err_msg(msg: String) -> String {
     unsafe {
         let cstr = llvm::LLVMRustGetLastError();
         if cstr == ptr::null() {
-            sess.fatal(msg.as_slice());
+            msg
         } else {
             let err = CString::new(cstr, true);
             let err = String::from_utf8_lossy(err.as_bytes());
-            sess.fatal(format!("{}: {}",
-                               msg.as_slice(),
-                               err.as_slice()).as_slice());
+            format!("{}: {}", msg.as_slice(), err.as_slice())
         }
     }
 }
 
+/// Writes a module out with the code generator, returning the error from LLVM
+/// if it fails.
 pub fn write_output_file(
-        sess: &Session,
         target: llvm::TargetMachineRef,
         pm: llvm::PassManagerRef,
         m: ModuleRef,
         output: &Path,
-        file_type: llvm::FileType) {
+        file_type: llvm::FileType) -> Result<(), String> {
     unsafe {
         output.with_c_str(|output| {
             let result = llvm::LLVMRustWriteOutputFile(
                     target, pm, m, output, file_type);
-            if !result {
-                llvm_err(sess, "could not write output".to_string());
+            if result {
+                Ok(())
+            } else {
+                Err(llvm_err_msg("could not write output".to_string()))
             }
         })
     }
 }
 
+/// Returns the path of the file for one of the codegen units, like
+/// `foo.1.o` for `foo.o`, or `path` itself if there's only one unit.
+pub fn unit_path(path: &Path, unit: uint, units: uint) -> Path {
+    if units == 1 {
+        return path.clone()
+    }
+    match path.extension_str() {
+        Some(ext) => path.with_extension(format!("{}.{}", unit, ext)),
+        None => path.with_extension(format!("{}", unit)),
+    }
+}
+
 pub mod write {
 
     use super::super::lto;
-    use super::{write_output_file, OutputType};
+    use super::{llvm_err, unit_path, write_output_file, OutputType};
     use super::{OutputTypeAssembly, OutputTypeBitcode};
     use super::{OutputTypeExe, OutputTypeLlvmAssembly};
     use super::{OutputTypeObject};
-    use driver::driver::{CrateTranslation, OutputFilenames};
+    use driver::driver::{CrateTranslation, ModuleTranslation, OutputFilenames};
     use driver::config::NoDebugInfo;
     use driver::session::Session;
     use driver::config;
     use llvm;
     use llvm::{ModuleRef, TargetMachineRef, PassManagerRef};
+    use middle::dep_graph;
     use util::common::time;
-    use syntax::abi;
 
+    use std::any::Any;
     use std::c_str::ToCStr;
-    use std::io::{Command};
-    use libc::{c_uint, c_int};
+    use std::io::{Command, File};
+    use std::io::fs;
+    use libc::{c_char, c_uint, c_int, size_t};
     use std::str;
+    use std::sync::{Arc, Future};
+    use std::task::TaskBuilder;
 
-    // On android, we by default compile for armv7 processors. This enables
-    // things like double word CAS instructions (rather than emulating them)
-    // which are *far* more efficient. This is obviously undesirable in some
-    // cases, so if any sort of target feature is specified we don't append v7
-    // to the feature list.
-    //
-    // On iOS only armv7 and newer are supported. So it is useful to
-    // get all hardware potential via VFP3 (hardware floating point)
-    // and NEON (SIMD) instructions supported by LLVM.
-    // Note that without those flags various linking errors might
-    // arise as some of intrinsics are converted into function calls
-    // and nobody provides implementations those functions
+    // The target's own features, such as armv7 on android, are the default
+    // and any sort of target feature specified replaces them.
     fn target_feature<'a>(sess: &'a Session) -> &'a str {
-        match sess.targ_cfg.os {
-            abi::OsAndroid => {
-                if "" == sess.opts.cg.target_feature.as_slice() {
-                    "+v7"
-                } else {
-                    sess.opts.cg.target_feature.as_slice()
-                }
-            },
-            abi::OsiOS if sess.targ_cfg.arch == abi::Arm => {
-                "+v7,+thumb2,+vfp3,+neon"
-            },
-            _ => sess.opts.cg.target_feature.as_slice()
+        if "" == sess.opts.cg.target_feature.as_slice() {
+            sess.targ_cfg.target.features.as_slice()
+        } else {
+            sess.opts.cg.target_feature.as_slice()
         }
     }
 
-    pub fn run_passes(sess: &Session,
-                      trans: &CrateTranslation,
-                      output_types: &[OutputType],
-                      output: &OutputFilenames) {
-        let llmod = trans.module;
-        let llcx = trans.context;
-        unsafe {
-            configure_llvm(sess);
-
-            if sess.opts.cg.save_temps {
-                output.with_extension("no-opt.bc").with_c_str(|buf| {
-                    llvm::LLVMWriteBitcodeToFile(llmod, buf);
-                })
-            }
+    /// The settings to optimize and emit the module of a codegen unit with,
+    /// which the tasks doing so share.
+    struct ModuleConfig {
+        triple: String,
+        cpu: String,
+        features: String,
+        opt_level: llvm::CodeGenOptLevel,
+        reloc_model: llvm::RelocMode,
+        code_model: llvm::CodeGenModel,
+        use_softfp: bool,
+        no_fp_elim: bool,
+        ffunction_sections: bool,
+        fdata_sections: bool,
+        no_verify: bool,
+        no_prepopulate_passes: bool,
+        no_builtins: bool,
+        passes: Vec<String>,
+        time_passes: bool,
+    }
 
+    impl ModuleConfig {
+        fn new(sess: &Session, no_builtins: bool) -> ModuleConfig {
             let opt_level = match sess.opts.optimize {
               config::No => llvm::CodeGenLevelNone,
               config::Less => llvm::CodeGenLevelLess,
               config::Default => llvm::CodeGenLevelDefault,
               config::Aggressive => llvm::CodeGenLevelAggressive,
             };
-            let use_softfp = sess.opts.cg.soft_float;
 
             // FIXME: #11906: Omitting frame pointers breaks retrieving the value of a parameter.
-            // FIXME: #11954: mac64 unwinding may not work with fp elim
             let no_fp_elim = (sess.opts.debuginfo != NoDebugInfo) ||
-                             (sess.targ_cfg.os == abi::OsMacos &&
-                              sess.targ_cfg.arch == abi::X86_64);
+                             !sess.targ_cfg.target.eliminate_frame_pointer;
 
-            // OSX has -dead_strip, which doesn't rely on ffunction_sections
-            // FIXME(#13846) this should be enabled for windows
-            let ffunction_sections = sess.targ_cfg.os != abi::OsMacos &&
-                                     sess.targ_cfg.os != abi::OsWindows;
+            let ffunction_sections = sess.targ_cfg.target.function_sections;
             let fdata_sections = ffunction_sections;
 
-            let reloc_model = match sess.opts.cg.relocation_model.as_slice() {
+            let reloc_model = match super::relocation_model(sess) {
                 "pic" => llvm::RelocPIC,
                 "static" => llvm::RelocStatic,
                 "default" => llvm::RelocDefault,
                 "dynamic-no-pic" => llvm::RelocDynamicNoPic,
-                _ => {
-                    sess.err(format!("{} is not a valid relocation mode",
-                                     sess.opts
-                                         .cg
-                                         .relocation_model).as_slice());
-                    sess.abort_if_errors();
-                    return;
+                model => {
+                    sess.fatal(format!("{} is not a valid relocation mode",
+                                       model).as_slice());
                 }
             };
 
@@ -225,76 +225,109 @@ pub mod write {
                 "medium" => llvm::CodeModelMedium,
                 "large" => llvm::CodeModelLarge,
                 _ => {
-                    sess.err(format!("{} is not a valid code model",
-                                     sess.opts
-                                         .cg
-                                         .code_model).as_slice());
-                    sess.abort_if_errors();
-                    return;
+                    sess.fatal(format!("{} is not a valid code model",
+                                       sess.opts
+                                           .cg
+                                           .code_model).as_slice());
                 }
             };
 
-            let tm = sess.targ_cfg
-                         .target_strs
-                         .target_triple
-                         .as_slice()
-                         .with_c_str(|t| {
-                sess.opts.cg.target_cpu.as_slice().with_c_str(|cpu| {
-                    target_feature(sess).with_c_str(|features| {
-                        llvm::LLVMRustCreateTargetMachine(
-                            t, cpu, features,
-                            code_model,
-                            reloc_model,
-                            opt_level,
-                            true /* EnableSegstk */,
-                            use_softfp,
-                            no_fp_elim,
-                            ffunction_sections,
-                            fdata_sections,
-                        )
+            // The extra passes are checked here, as the tasks running them
+            // have no session to warn with.
+            unsafe {
+                let pm = llvm::LLVMCreatePassManager();
+                for pass in sess.opts.cg.passes.iter() {
+                    pass.as_slice().with_c_str(|s| {
+                        if !llvm::LLVMRustAddPass(pm, s) {
+                            sess.warn(format!("unknown pass {}, ignoring",
+                                              *pass).as_slice());
+                        }
                     })
-                })
-            });
-
-            // Create the two optimizing pass managers. These mirror what clang
-            // does, and are by populated by LLVM's default PassManagerBuilder.
-            // Each manager has a different set of passes, but they also share
-            // some common passes.
-            let fpm = llvm::LLVMCreateFunctionPassManagerForModule(llmod);
-            let mpm = llvm::LLVMCreatePassManager();
-
-            // If we're verifying or linting, add them to the function pass
-            // manager.
-            let addpass = |pass: &str| {
-                pass.as_slice().with_c_str(|s| llvm::LLVMRustAddPass(fpm, s))
-            };
-            if !sess.no_verify() { assert!(addpass("verify")); }
+                }
+                llvm::LLVMDisposePassManager(pm);
+            }
 
-            if !sess.opts.cg.no_prepopulate_passes {
-                llvm::LLVMRustAddAnalysisPasses(tm, fpm, llmod);
-                llvm::LLVMRustAddAnalysisPasses(tm, mpm, llmod);
-                populate_llvm_passes(fpm, mpm, llmod, opt_level,
-                                     trans.no_builtins);
+            ModuleConfig {
+                triple: sess.targ_cfg.target.llvm_target.clone(),
+                cpu: sess.opts.cg.target_cpu.clone(),
+                features: target_feature(sess).to_string(),
+                opt_level: opt_level,
+                reloc_model: reloc_model,
+                code_model: code_model,
+                use_softfp: sess.opts.cg.soft_float,
+                no_fp_elim: no_fp_elim,
+                ffunction_sections: ffunction_sections,
+                fdata_sections: fdata_sections,
+                no_verify: sess.no_verify(),
+                no_prepopulate_passes: sess.opts.cg.no_prepopulate_passes,
+                no_builtins: no_builtins,
+                passes: sess.opts.cg.passes.clone(),
+                time_passes: sess.time_passes(),
             }
+        }
+    }
 
-            for pass in sess.opts.cg.passes.iter() {
-                pass.as_slice().with_c_str(|s| {
-                    if !llvm::LLVMRustAddPass(mpm, s) {
-                        sess.warn(format!("unknown pass {}, ignoring",
-                                          *pass).as_slice());
-                    }
-                })
+    /// The files to write the module of a codegen unit to.
+    struct UnitOutputs {
+        /// The bitcode kept for rlibs and `-C save-temps`, before LTO.
+        temp_bitcode: Option<Path>,
+        bitcode: Option<Path>,
+        llvm_assembly: Option<Path>,
+        assembly: Option<Path>,
+        object: Option<Path>,
+    }
+
+    impl UnitOutputs {
+        fn for_unit(&self, unit: uint, units: uint) -> UnitOutputs {
+            let path = |p: &Option<Path>| p.as_ref().map(|p| unit_path(p, unit, units));
+            UnitOutputs {
+                temp_bitcode: path(&self.temp_bitcode),
+                bitcode: path(&self.bitcode),
+                llvm_assembly: path(&self.llvm_assembly),
+                assembly: path(&self.assembly),
+                object: path(&self.object),
             }
+        }
+    }
 
-            // Finally, run the actual optimization passes
-            time(sess.time_passes(), "llvm function passes", (), |()|
-                 llvm::LLVMRustRunFunctionPassManager(fpm, llmod));
-            time(sess.time_passes(), "llvm module passes", (), |()|
-                 llvm::LLVMRunPassManager(mpm, llmod));
+    /// The number of codegen units which files are written for, as LTO links
+    /// all of them into one.
+    pub fn output_units(sess: &Session, trans: &CrateTranslation) -> uint {
+        if sess.lto() { 1 } else { trans.modules.len() }
+    }
 
-            // Deallocate managers that we're now done with
-            llvm::LLVMDisposePassManager(fpm);
-            llvm::LLVMDisposePassManager(mpm);
+    pub fn run_passes(sess: &Session,
+                      trans: &CrateTranslation,
+                      output_types: &[OutputType],
+                      output: &OutputFilenames) {
+        unsafe {
+            configure_llvm(sess);
+            let module_config = Arc::new(ModuleConfig::new(sess, trans.no_builtins));
+
+            if sess.opts.cg.save_temps {
+                let path = output.with_extension("no-opt.bc");
+                for (unit, module) in trans.modules.iter().enumerate() {
+                    unit_path(&path, unit, trans.modules.len()).with_c_str(|buf| {
+                        llvm::LLVMWriteBitcodeToFile(module.llmod, buf);
+                    })
+                }
+            }
+
+            let modules = if sess.lto() {
+                link_units(sess, output, trans.modules.as_slice());
+                trans.modules.slice_to(1)
+            } else {
+                trans.modules.as_slice()
+            };
+            let units = modules.len();
+
+            let mut outputs = UnitOutputs {
+                temp_bitcode: None,
+                bitcode: None,
+                llvm_assembly: None,
+                assembly: None,
+                object: None,
+            };
 
             // Emit the bytecode if we're either saving our temporaries or
             // emitting an rlib. Whenever an rlib is created, the bytecode is
@@ -302,58 +335,15 @@ pub mod write {
             if sess.opts.cg.save_temps ||
                (sess.crate_types.borrow().contains(&config::CrateTypeRlib) &&
                 sess.opts.output_types.contains(&OutputTypeExe)) {
-                output.temp_path(OutputTypeBitcode).with_c_str(|buf| {
-                    llvm::LLVMWriteBitcodeToFile(llmod, buf);
-                })
-            }
-
-            if sess.lto() {
-                time(sess.time_passes(), "all lto passes", (), |()|
-                     lto::run(sess, llmod, tm, trans.reachable.as_slice()));
-
-                if sess.opts.cg.save_temps {
-                    output.with_extension("lto.bc").with_c_str(|buf| {
-                        llvm::LLVMWriteBitcodeToFile(llmod, buf);
-                    })
-                }
+                outputs.temp_bitcode = Some(output.temp_path(OutputTypeBitcode));
             }
 
-            // A codegen-specific pass manager is used to generate object
-            // files for an LLVM module.
-            //
-            // Apparently each of these pass managers is a one-shot kind of
-            // thing, so we create a new one for each type of output. The
-            // pass manager passed to the closure should be ensured to not
-            // escape the closure itself, and the manager should only be
-            // used once.
-            fn with_codegen(tm: TargetMachineRef, llmod: ModuleRef,
-                            no_builtins: bool, f: |PassManagerRef|) {
-                unsafe {
-                    let cpm = llvm::LLVMCreatePassManager();
-                    llvm::LLVMRustAddAnalysisPasses(tm, cpm, llmod);
-                    llvm::LLVMRustAddLibraryInfo(cpm, llmod, no_builtins);
-                    f(cpm);
-                    llvm::LLVMDisposePassManager(cpm);
-                }
-            }
-
-            let mut object_file = None;
             let mut needs_metadata = false;
             for output_type in output_types.iter() {
                 let path = output.path(*output_type);
                 match *output_type {
-                    OutputTypeBitcode => {
-                        path.with_c_str(|buf| {
-                            llvm::LLVMWriteBitcodeToFile(llmod, buf);
-                        })
-                    }
-                    OutputTypeLlvmAssembly => {
-                        path.with_c_str(|output| {
-                            with_codegen(tm, llmod, trans.no_builtins, |cpm| {
-                                llvm::LLVMRustPrintModule(cpm, llmod, output);
-                            })
-                        })
-                    }
+                    OutputTypeBitcode => outputs.bitcode = Some(path),
+                    OutputTypeLlvmAssembly => outputs.llvm_assembly = Some(path),
                     OutputTypeAssembly => {
                         // If we're not using the LLVM assembler, this function
                         // could be invoked specially with output_type_assembly,
@@ -366,58 +356,426 @@ pub mod write {
                             needs_metadata = true;
                             output.temp_path(OutputTypeAssembly)
                         };
-                        with_codegen(tm, llmod, trans.no_builtins, |cpm| {
-                            write_output_file(sess, tm, cpm, llmod, &path,
-                                            llvm::AssemblyFile);
-                        });
+                        outputs.assembly = Some(path);
                     }
                     OutputTypeObject => {
-                        object_file = Some(path);
+                        outputs.object = Some(path);
                     }
                     OutputTypeExe => {
-                        object_file = Some(output.temp_path(OutputTypeObject));
+                        outputs.object = Some(output.temp_path(OutputTypeObject));
                         needs_metadata = true;
                     }
                 }
             }
 
-            time(sess.time_passes(), "codegen passes", (), |()| {
-                match object_file {
+            if units > 1 && output.single_output_file.is_some() &&
+               (outputs.bitcode.is_some() || outputs.llvm_assembly.is_some() ||
+                sess.opts.output_types.contains(&OutputTypeAssembly)) {
+                sess.warn("writing a file for each codegen unit rather than \
+                           the one given with -o");
+            }
+
+            // With `-C incremental` the object files of the units which
+            // haven't changed are the ones kept from the last compilation
+            let cache = incremental_dir(sess, &outputs, trans.modules.len());
+            let reused: Vec<bool> = modules.iter().enumerate().map(|(unit, module)| {
+                match cache {
+                    Some(ref dir) if module.reuse_object => {
+                        restore_unit(dir, unit, &outputs.for_unit(unit, units))
+                    }
+                    _ => false,
+                }
+            }).collect();
+
+            if units == 1 && !reused[0] {
+                let llmod = modules[0].llmod;
+                let result = optimize_and_codegen(&*module_config, llmod, &outputs, |llmod, tm| {
+                    if sess.lto() {
+                        time(sess.time_passes(), "all lto passes", (), |()|
+                             lto::run(sess, llmod, tm, trans.reachable.as_slice()));
+
+                        if sess.opts.cg.save_temps {
+                            output.with_extension("lto.bc").with_c_str(|buf| {
+                                llvm::LLVMWriteBitcodeToFile(llmod, buf);
+                            })
+                        }
+                    }
+                });
+                match result {
+                    Ok(()) => {}
+                    Err(msg) => sess.fatal(msg.as_slice()),
+                }
+            } else if units > 1 {
+                run_parallel(sess, module_config.clone(), modules, reused.as_slice(), &outputs);
+            }
+
+            match cache {
+                Some(ref dir) => {
+                    for unit in range(0, units) {
+                        if !reused[unit] {
+                            keep_unit(sess, dir, unit, &outputs.for_unit(unit, units));
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            if units > 1 {
+                match outputs.object {
                     Some(ref path) => {
-                        with_codegen(tm, llmod, trans.no_builtins, |cpm| {
-                            write_output_file(sess, tm, cpm, llmod, path,
-                                            llvm::ObjectFile);
+                        let objects = Vec::from_fn(units, |unit| {
+                            unit_path(path, unit, units)
                         });
+                        link_unit_objects(sess, objects.as_slice(), path);
+                        if !sess.opts.cg.save_temps {
+                            for object in objects.iter() {
+                                super::remove(sess, object);
+                            }
+                        }
                     }
                     None => {}
                 }
-                if needs_metadata {
+            }
+
+            if needs_metadata {
+                let tm = create_target_machine(&*module_config);
+                let out = output.temp_path(OutputTypeObject)
+                                .with_extension("metadata.o");
+                time(sess.time_passes(), "codegen passes", (), |()| {
                     with_codegen(tm, trans.metadata_module,
                                  trans.no_builtins, |cpm| {
-                        let out = output.temp_path(OutputTypeObject)
-                                        .with_extension("metadata.o");
-                        write_output_file(sess, tm, cpm,
-                                        trans.metadata_module, &out,
-                                        llvm::ObjectFile);
+                        match write_output_file(tm, cpm, trans.metadata_module,
+                                                &out, llvm::ObjectFile) {
+                            Ok(()) => {}
+                            Err(msg) => sess.fatal(msg.as_slice()),
+                        }
                     })
-                }
-            });
+                });
+                llvm::LLVMRustDisposeTargetMachine(tm);
+            }
 
-            llvm::LLVMRustDisposeTargetMachine(tm);
+            // The metadata module belongs to the context of the first unit
             llvm::LLVMDisposeModule(trans.metadata_module);
-            llvm::LLVMDisposeModule(llmod);
-            llvm::LLVMContextDispose(llcx);
+            for module in modules.iter() {
+                llvm::LLVMDisposeModule(module.llmod);
+                llvm::LLVMContextDispose(module.llcx);
+            }
             if sess.time_llvm_passes() { llvm::LLVMRustPrintPassTimings(); }
         }
     }
 
-    pub fn run_assembler(sess: &Session, outputs: &OutputFilenames) {
+    /// Optimizes and writes out each of the codegen units which isn't
+    /// `reused` on a task of its own.
+    fn run_parallel(sess: &Session,
+                    config: Arc<ModuleConfig>,
+                    modules: &[ModuleTranslation],
+                    reused: &[bool],
+                    outputs: &UnitOutputs) {
+        let units = modules.len();
+        let futures: Vec<Future<Result<Result<(), String>, Box<Any + Send>>>> =
+            modules.iter().enumerate().filter(|&(unit, _)| !reused[unit]).map(|(unit, module)| {
+                let config = config.clone();
+                let llmod = module.llmod;
+                let outputs = outputs.for_unit(unit, units);
+                TaskBuilder::new().named(format!("codegen-{}", unit)).try_future(proc() {
+                    unsafe {
+                        optimize_and_codegen(&*config, llmod, &outputs, |_, _| {})
+                    }
+                })
+            }).collect();
+
+        for future in futures.move_iter() {
+            match future.unwrap() {
+                Ok(Ok(())) => {}
+                Ok(Err(msg)) => sess.err(msg.as_slice()),
+                Err(..) => sess.err("failed to optimize a codegen unit"),
+            }
+        }
+        sess.abort_if_errors();
+    }
+
+    /// Returns the directory to keep the object files of the codegen units
+    /// in with `-C incremental`, if they can be kept. Only object files and
+    /// their bitcode are, so with LTO or other kinds of output there's nothing
+    /// to keep, and the files from before are removed as out of date.
+    fn incremental_dir(sess: &Session, outputs: &UnitOutputs, units: uint) -> Option<Path> {
+        let dir = match sess.opts.cg.incremental {
+            Some(ref dir) => Path::new(dir.as_slice()),
+            None => return None,
+        };
+        // The dependency graph describing the kept files is written again
+        // once they have all been replaced.
+        match dep_graph::invalidate(sess) {
+            Ok(()) => {}
+            Err(e) => {
+                sess.fatal(format!("failed to remove the dependency graph: {}",
+                                   e).as_slice());
+            }
+        }
+        if !sess.lto() && outputs.object.is_some() && outputs.bitcode.is_none() &&
+           outputs.llvm_assembly.is_none() && outputs.assembly.is_none() {
+            return Some(dir);
+        }
+        for unit in range(0, units) {
+            for (kept, _) in kept_files(&dir, unit, outputs).move_iter() {
+                if kept.exists() {
+                    super::remove(sess, &kept);
+                }
+            }
+        }
+        None
+    }
+
+    /// The files kept for a codegen unit with `-C incremental`, each with the
+    /// output it's kept from.
+    fn kept_files(dir: &Path, unit: uint, outputs: &UnitOutputs) -> Vec<(Path, Path)> {
+        let mut files = Vec::new();
+        match outputs.object {
+            Some(ref path) => files.push((dir.join(format!("{}.o", unit)), path.clone())),
+            None => {}
+        }
+        match outputs.temp_bitcode {
+            Some(ref path) => files.push((dir.join(format!("{}.bc", unit)), path.clone())),
+            None => {}
+        }
+        files
+    }
+
+    /// Copies the files kept for a codegen unit to its outputs, returning
+    /// whether they were all there.
+    fn restore_unit(dir: &Path, unit: uint, outputs: &UnitOutputs) -> bool {
+        let files = kept_files(dir, unit, outputs);
+        files.iter().all(|&(ref kept, _)| kept.exists()) &&
+            files.iter().all(|&(ref kept, ref output)| fs::copy(kept, output).is_ok())
+    }
+
+    fn keep_unit(sess: &Session, dir: &Path, unit: uint, outputs: &UnitOutputs) {
+        for (kept, output) in kept_files(dir, unit, outputs).move_iter() {
+            match fs::copy(&output, &kept) {
+                Ok(()) => {}
+                Err(e) => {
+                    sess.err(format!("failed to keep `{}` for incremental compilation: {}",
+                                     output.display(), e).as_slice());
+                }
+            }
+        }
+    }
+
+    /// Optimizes the module of a codegen unit and writes out its files,
+    /// running `lto` over it before code generation. Returns the error from
+    /// LLVM if a file couldn't be written.
+    unsafe fn optimize_and_codegen(config: &ModuleConfig,
+                                   llmod: ModuleRef,
+                                   outputs: &UnitOutputs,
+                                   lto: |ModuleRef, TargetMachineRef|)
+                                   -> Result<(), String> {
+        let tm = create_target_machine(config);
+
+        // Create the two optimizing pass managers. These mirror what clang
+        // does, and are by populated by LLVM's default PassManagerBuilder.
+        // Each manager has a different set of passes, but they also share
+        // some common passes.
+        let fpm = llvm::LLVMCreateFunctionPassManagerForModule(llmod);
+        let mpm = llvm::LLVMCreatePassManager();
+
+        // If we're verifying or linting, add them to the function pass
+        // manager.
+        let addpass = |pass: &str| {
+            pass.as_slice().with_c_str(|s| llvm::LLVMRustAddPass(fpm, s))
+        };
+        if !config.no_verify { assert!(addpass("verify")); }
+
+        if !config.no_prepopulate_passes {
+            llvm::LLVMRustAddAnalysisPasses(tm, fpm, llmod);
+            llvm::LLVMRustAddAnalysisPasses(tm, mpm, llmod);
+            populate_llvm_passes(fpm, mpm, llmod, config.opt_level,
+                                 config.no_builtins);
+        }
+
+        // Unknown passes have already been warned about
+        for pass in config.passes.iter() {
+            pass.as_slice().with_c_str(|s| {
+                llvm::LLVMRustAddPass(mpm, s);
+            })
+        }
+
+        // Finally, run the actual optimization passes
+        time(config.time_passes, "llvm function passes", (), |()|
+             llvm::LLVMRustRunFunctionPassManager(fpm, llmod));
+        time(config.time_passes, "llvm module passes", (), |()|
+             llvm::LLVMRunPassManager(mpm, llmod));
+
+        // Deallocate managers that we're now done with
+        llvm::LLVMDisposePassManager(fpm);
+        llvm::LLVMDisposePassManager(mpm);
+
+        match outputs.temp_bitcode {
+            Some(ref path) => {
+                path.with_c_str(|buf| {
+                    llvm::LLVMWriteBitcodeToFile(llmod, buf);
+                })
+            }
+            None => {}
+        }
+
+        lto(llmod, tm);
+
+        let result = time(config.time_passes, "codegen passes", (), |()| {
+            codegen(config, tm, llmod, outputs)
+        });
+        llvm::LLVMRustDisposeTargetMachine(tm);
+        result
+    }
+
+    unsafe fn codegen(config: &ModuleConfig,
+                      tm: TargetMachineRef,
+                      llmod: ModuleRef,
+                      outputs: &UnitOutputs) -> Result<(), String> {
+        match outputs.bitcode {
+            Some(ref path) => {
+                path.with_c_str(|buf| {
+                    llvm::LLVMWriteBitcodeToFile(llmod, buf);
+                })
+            }
+            None => {}
+        }
+        match outputs.llvm_assembly {
+            Some(ref path) => {
+                path.with_c_str(|output| {
+                    with_codegen(tm, llmod, config.no_builtins, |cpm| {
+                        llvm::LLVMRustPrintModule(cpm, llmod, output);
+                    })
+                })
+            }
+            None => {}
+        }
+        match outputs.assembly {
+            Some(ref path) => {
+                let mut result = Ok(());
+                with_codegen(tm, llmod, config.no_builtins, |cpm| {
+                    result = write_output_file(tm, cpm, llmod, path,
+                                               llvm::AssemblyFile);
+                });
+                try!(result);
+            }
+            None => {}
+        }
+        match outputs.object {
+            Some(ref path) => {
+                let mut result = Ok(());
+                with_codegen(tm, llmod, config.no_builtins, |cpm| {
+                    result = write_output_file(tm, cpm, llmod, path,
+                                               llvm::ObjectFile);
+                });
+                try!(result);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    unsafe fn create_target_machine(config: &ModuleConfig) -> TargetMachineRef {
+        config.triple.as_slice().with_c_str(|t| {
+            config.cpu.as_slice().with_c_str(|cpu| {
+                config.features.as_slice().with_c_str(|features| {
+                    llvm::LLVMRustCreateTargetMachine(
+                        t, cpu, features,
+                        config.code_model,
+                        config.reloc_model,
+                        config.opt_level,
+                        true /* EnableSegstk */,
+                        config.use_softfp,
+                        config.no_fp_elim,
+                        config.ffunction_sections,
+                        config.fdata_sections,
+                    )
+                })
+            })
+        })
+    }
+
+    // A codegen-specific pass manager is used to generate object
+    // files for an LLVM module.
+    //
+    // Apparently each of these pass managers is a one-shot kind of
+    // thing, so we create a new one for each type of output. The
+    // pass manager passed to the closure should be ensured to not
+    // escape the closure itself, and the manager should only be
+    // used once.
+    fn with_codegen(tm: TargetMachineRef, llmod: ModuleRef,
+                    no_builtins: bool, f: |PassManagerRef|) {
+        unsafe {
+            let cpm = llvm::LLVMCreatePassManager();
+            llvm::LLVMRustAddAnalysisPasses(tm, cpm, llmod);
+            llvm::LLVMRustAddLibraryInfo(cpm, llmod, no_builtins);
+            f(cpm);
+            llvm::LLVMDisposePassManager(cpm);
+        }
+    }
+
+    /// Links the modules of the codegen units after the first into it, as LTO
+    /// works on a single module.
+    unsafe fn link_units(sess: &Session,
+                         output: &OutputFilenames,
+                         modules: &[ModuleTranslation]) {
+        let llmod = modules[0].llmod;
+        let units = modules.len();
+        for (unit, module) in modules.iter().enumerate().skip(1) {
+            let path = unit_path(&output.temp_path(OutputTypeBitcode), unit, units);
+            path.with_c_str(|buf| {
+                llvm::LLVMWriteBitcodeToFile(module.llmod, buf);
+            });
+            let bc = match File::open(&path).read_to_end() {
+                Ok(bc) => bc,
+                Err(e) => sess.fatal(format!("failed to read bytecode: {}",
+                                             e).as_slice())
+            };
+            if !llvm::LLVMRustLinkInExternalBitcode(llmod,
+                                                    bc.as_ptr() as *const c_char,
+                                                    bc.len() as size_t) {
+                llvm_err(sess, format!("failed to link codegen unit {}", unit));
+            }
+            if !sess.opts.cg.save_temps {
+                super::remove(sess, &path);
+            }
+            llvm::LLVMDisposeModule(module.llmod);
+            llvm::LLVMContextDispose(module.llcx);
+        }
+    }
+
+    /// Combines the object files of the codegen units into one.
+    fn link_unit_objects(sess: &Session, objects: &[Path], output: &Path) {
         let pname = super::get_cc_prog(sess);
         let mut cmd = Command::new(pname.as_slice());
+        cmd.args(sess.targ_cfg.target.pre_link_args.as_slice());
+        cmd.arg("-nostdlib").arg("-r").arg("-o").arg(output).args(objects);
+        run_cc(sess, pname.as_slice(), &cmd);
+    }
 
-        cmd.arg("-c").arg("-o").arg(outputs.path(OutputTypeObject))
-                               .arg(outputs.temp_path(OutputTypeAssembly));
-        debug!("{}", &cmd);
+    pub fn run_assembler(sess: &Session, outputs: &OutputFilenames, units: uint) {
+        let pname = super::get_cc_prog(sess);
+        let object = outputs.path(OutputTypeObject);
+        let objects = Vec::from_fn(units, |unit| unit_path(&object, unit, units));
+
+        for (unit, path) in objects.iter().enumerate() {
+            let mut cmd = Command::new(pname.as_slice());
+            cmd.arg("-c").arg("-o").arg(path)
+               .arg(unit_path(&outputs.temp_path(OutputTypeAssembly), unit, units));
+            run_cc(sess, pname.as_slice(), &cmd);
+        }
+
+        if units > 1 {
+            link_unit_objects(sess, objects.as_slice(), &object);
+            if !sess.opts.cg.save_temps {
+                for path in objects.iter() {
+                    super::remove(sess, path);
+                }
+            }
+        }
+    }
+
+    fn run_cc(sess: &Session, pname: &str, cmd: &Command) {
+        debug!("{}", cmd);
 
         match cmd.output() {
             Ok(prog) => {
@@ -425,7 +783,7 @@ pub mod write {
                     sess.err(format!("linking with `{}` failed: {}",
                                      pname,
                                      prog.status).as_slice());
-                    sess.note(format!("{}", &cmd).as_slice());
+                    sess.note(format!("{}", cmd).as_slice());
                     let mut note = prog.error.clone();
                     note.push_all(prog.output.as_slice());
                     sess.note(str::from_utf8(note.as_slice()).unwrap());
@@ -673,11 +1031,26 @@ pub fn find_crate_name(sess: Option<&Session>,
     "rust-out".to_string()
 }
 
-pub fn build_link_meta(sess: &Session, krate: &ast::Crate,
-                       name: String) -> LinkMeta {
+/// Builds the `LinkMeta` of the crate. `krate_fingerprint` is the fingerprint
+/// of the crate's context with `-C incremental`: symbols are named after it
+/// rather than the SVH, so the object files which are reused still link.
+pub fn build_link_meta(sess: &Session, krate: &ast::Crate, name: String,
+                       krate_fingerprint: Option<String>) -> LinkMeta {
+    let crate_hash = Svh::calculate(&sess.opts.cg.metadata, krate);
+    let symbol_hash = match krate_fingerprint {
+        Some(fingerprint) => {
+            let mut hasher = Sha256::new();
+            hasher.input_str(name.as_slice());
+            hasher.input_str("-");
+            hasher.input_str(fingerprint.as_slice());
+            Svh::new(truncated_hash_result(&mut hasher).as_slice())
+        }
+        None => crate_hash.clone(),
+    };
     let r = LinkMeta {
         crate_name: name,
-        crate_hash: Svh::calculate(&sess.opts.cg.metadata, krate),
+        crate_hash: crate_hash,
+        symbol_hash: symbol_hash,
     };
     info!("{}", r);
     return r;
@@ -702,7 +1075,7 @@ fn symbol_hash(tcx: &ty::ctxt,
     symbol_hasher.reset();
     symbol_hasher.input_str(link_meta.crate_name.as_slice());
     symbol_hasher.input_str("-");
-    symbol_hasher.input_str(link_meta.crate_hash.as_str());
+    symbol_hasher.input_str(link_meta.symbol_hash.as_str());
     for meta in tcx.sess.crate_metadata.borrow().iter() {
         symbol_hasher.input_str(meta.as_slice());
     }
@@ -860,14 +1233,16 @@ pub fn get_cc_prog(sess: &Session) -> String {
         None => {}
     }
 
-    // In the future, FreeBSD will use clang as default compiler.
-    // It would be flexible to use cc (system's default C compiler)
-    // instead of hard-coded gcc.
-    // For Windows, there is no cc command, so we add a condition to make it use gcc.
-    match sess.targ_cfg.os {
-        abi::OsWindows => "gcc",
-        _ => "cc",
-    }.to_string()
+    sess.targ_cfg.target.linker.clone()
+}
+
+/// The relocation model given with `-C relocation-model`, or the one of the
+/// target.
+pub fn relocation_model<'a>(sess: &'a Session) -> &'a str {
+    match sess.opts.cg.relocation_model {
+        Some(ref model) => model.as_slice(),
+        None => sess.targ_cfg.target.relocation_model.as_slice(),
+    }
 }
 
 pub fn get_ar_prog(sess: &Session) -> String {
@@ -898,7 +1273,7 @@ pub fn link_binary(sess: &Session,
     for &crate_type in sess.crate_types.borrow().iter() {
         if invalid_output_for_target(sess, crate_type) {
             sess.bug(format!("invalid output type `{}` for target os `{}`",
-                             crate_type, sess.targ_cfg.os).as_slice());
+                             crate_type, sess.targ_cfg.target.target_os).as_slice());
         }
         let out_file = link_binary_output(sess, trans, crate_type, outputs,
                                           crate_name);
@@ -923,22 +1298,22 @@ pub fn link_binary(sess: &Session,
 /// Default crate type is used when crate type isn't provided neither
 /// through cmd line arguments nor through crate attributes
 ///
-/// It is CrateTypeExecutable for all platforms but iOS as there is no
-/// way to run iOS binaries anyway without jailbreaking and
-/// interaction with Rust code through static library is the only
-/// option for now
+/// It is CrateTypeExecutable for all targets which can run executables, and
+/// CrateTypeStaticlib for the others, such as iOS, where interaction with
+/// Rust code through a static library is the only option
 pub fn default_output_for_target(sess: &Session) -> config::CrateType {
-    match sess.targ_cfg.os {
-        abi::OsiOS => config::CrateTypeStaticlib,
-        _ => config::CrateTypeExecutable
+    if sess.targ_cfg.target.executables {
+        config::CrateTypeExecutable
+    } else {
+        config::CrateTypeStaticlib
     }
 }
 
 /// Checks if target supports crate_type as output
 pub fn invalid_output_for_target(sess: &Session,
                                  crate_type: config::CrateType) -> bool {
-    match (sess.targ_cfg.os, crate_type) {
-        (abi::OsiOS, config::CrateTypeDylib) => true,
+    match crate_type {
+        config::CrateTypeDylib => !sess.targ_cfg.target.dynamic_linking,
         _ => false
     }
 }
@@ -960,32 +1335,21 @@ pub fn filename_for_input(sess: &Session,
             out_filename.with_filename(format!("lib{}.rlib", libname))
         }
         config::CrateTypeDylib => {
-            let (prefix, suffix) = match sess.targ_cfg.os {
-                abi::OsWindows => (loader::WIN32_DLL_PREFIX, loader::WIN32_DLL_SUFFIX),
-                abi::OsMacos => (loader::MACOS_DLL_PREFIX, loader::MACOS_DLL_SUFFIX),
-                abi::OsLinux => (loader::LINUX_DLL_PREFIX, loader::LINUX_DLL_SUFFIX),
-                abi::OsAndroid => (loader::ANDROID_DLL_PREFIX, loader::ANDROID_DLL_SUFFIX),
-                abi::OsFreebsd => (loader::FREEBSD_DLL_PREFIX, loader::FREEBSD_DLL_SUFFIX),
-                abi::OsDragonfly => (loader::DRAGONFLY_DLL_PREFIX, loader::DRAGONFLY_DLL_SUFFIX),
-                abi::OsiOS => unreachable!(),
-            };
+            let target = &sess.targ_cfg.target;
             out_filename.with_filename(format!("{}{}{}",
-                                               prefix,
+                                               target.dll_prefix,
                                                libname,
-                                               suffix))
+                                               target.dll_suffix))
         }
         config::CrateTypeStaticlib => {
             out_filename.with_filename(format!("lib{}.a", libname))
         }
         config::CrateTypeExecutable => {
-            match sess.targ_cfg.os {
-                abi::OsWindows => out_filename.with_extension("exe"),
-                abi::OsMacos |
-                abi::OsLinux |
-                abi::OsAndroid |
-                abi::OsFreebsd |
-                abi::OsDragonfly |
-                abi::OsiOS => out_filename.clone(),
+            let suffix = sess.targ_cfg.target.exe_suffix.as_slice();
+            if suffix.is_empty() {
+                out_filename.clone()
+            } else {
+                out_filename.with_extension(suffix.trim_left_chars('.'))
             }
         }
     }
@@ -1066,7 +1430,8 @@ fn link_rlib<'a>(sess: &'a Session,
         handler: handler,
         dst: out_filename.clone(),
         lib_search_paths: archive_search_paths(sess),
-        os: sess.targ_cfg.os,
+        lib_prefix: sess.targ_cfg.target.staticlib_prefix.clone(),
+        lib_suffix: sess.targ_cfg.target.staticlib_suffix.clone(),
         maybe_ar_prog: sess.opts.cg.ar.clone()
     };
     let mut ab = ArchiveBuilder::create(config);
@@ -1085,12 +1450,13 @@ fn link_rlib<'a>(sess: &'a Session,
     // symbol table of the archive.
     ab.update_symbols();
 
-    let mut ab = match sess.targ_cfg.os {
-        // For OSX/iOS, we must be careful to update symbols only when adding
-        // object files.  We're about to start adding non-object files, so run
-        // `ar` now to process the object files.
-        abi::OsMacos | abi::OsiOS => ab.build().extend(),
-        _ => ab,
+    // For OSX/iOS, we must be careful to update symbols only when adding
+    // object files.  We're about to start adding non-object files, so run
+    // `ar` now to process the object files.
+    let mut ab = if sess.targ_cfg.target.is_like_osx {
+        ab.build().extend()
+    } else {
+        ab
     };
 
     // Note that it is important that we add all of our non-object "magical
@@ -1136,50 +1502,58 @@ fn link_rlib<'a>(sess: &'a Session,
             remove(sess, &metadata);
 
             // For LTO purposes, the bytecode of this library is also inserted
-            // into the archive.
+            // into the archive, with a file for each codegen unit.
             //
             // Note that we make sure that the bytecode filename in the archive
             // is never exactly 16 bytes long by adding a 16 byte extension to
             // it. This is to work around a bug in LLDB that would cause it to
             // crash if the name of a file in an archive was exactly 16 bytes.
-            let bc_filename = obj_filename.with_extension("bc");
-            let bc_deflated_filename = obj_filename.with_extension("bytecode.deflate");
+            let units = trans.modules.len();
+            for unit in range(0, units) {
+                let bc_filename = unit_path(&obj_filename.with_extension("bc"),
+                                            unit, units);
+                let bc_deflated_filename = if unit == 0 {
+                    obj_filename.with_extension("bytecode.deflate")
+                } else {
+                    obj_filename.with_extension(format!("{}.bytecode.deflate", unit))
+                };
 
-            let bc_data = match fs::File::open(&bc_filename).read_to_end() {
-                Ok(buffer) => buffer,
-                Err(e) => sess.fatal(format!("failed to read bytecode: {}",
-                                             e).as_slice())
-            };
+                let bc_data = match fs::File::open(&bc_filename).read_to_end() {
+                    Ok(buffer) => buffer,
+                    Err(e) => sess.fatal(format!("failed to read bytecode: {}",
+                                                 e).as_slice())
+                };
 
-            let bc_data_deflated = match flate::deflate_bytes(bc_data.as_slice()) {
-                Some(compressed) => compressed,
-                None => sess.fatal(format!("failed to compress bytecode from {}",
-                                           bc_filename.display()).as_slice())
-            };
+                let bc_data_deflated = match flate::deflate_bytes(bc_data.as_slice()) {
+                    Some(compressed) => compressed,
+                    None => sess.fatal(format!("failed to compress bytecode from {}",
+                                               bc_filename.display()).as_slice())
+                };
 
-            let mut bc_file_deflated = match fs::File::create(&bc_deflated_filename) {
-                Ok(file) => file,
-                Err(e) => {
-                    sess.fatal(format!("failed to create compressed bytecode \
-                                        file: {}", e).as_slice())
-                }
-            };
+                let mut bc_file_deflated = match fs::File::create(&bc_deflated_filename) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        sess.fatal(format!("failed to create compressed bytecode \
+                                            file: {}", e).as_slice())
+                    }
+                };
 
-            match write_rlib_bytecode_object_v1(&mut bc_file_deflated,
-                                                bc_data_deflated.as_slice()) {
-                Ok(()) => {}
-                Err(e) => {
-                    sess.err(format!("failed to write compressed bytecode: \
-                                      {}", e).as_slice());
-                    sess.abort_if_errors()
-                }
-            };
+                match write_rlib_bytecode_object_v1(&mut bc_file_deflated,
+                                                    bc_data_deflated.as_slice()) {
+                    Ok(()) => {}
+                    Err(e) => {
+                        sess.err(format!("failed to write compressed bytecode: \
+                                          {}", e).as_slice());
+                        sess.abort_if_errors()
+                    }
+                };
 
-            ab.add_file(&bc_deflated_filename).unwrap();
-            remove(sess, &bc_deflated_filename);
-            if !sess.opts.cg.save_temps &&
-               !sess.opts.output_types.contains(&OutputTypeBitcode) {
-                remove(sess, &bc_filename);
+                ab.add_file(&bc_deflated_filename).unwrap();
+                remove(sess, &bc_deflated_filename);
+                if !sess.opts.cg.save_temps &&
+                   !sess.opts.output_types.contains(&OutputTypeBitcode) {
+                    remove(sess, &bc_filename);
+                }
             }
         }
 
@@ -1229,9 +1603,10 @@ fn write_rlib_bytecode_object_v1<T: Writer>(writer: &mut T,
 // metadata file).
 fn link_staticlib(sess: &Session, obj_filename: &Path, out_filename: &Path) {
     let ab = link_rlib(sess, None, obj_filename, out_filename);
-    let mut ab = match sess.targ_cfg.os {
-        abi::OsMacos | abi::OsiOS => ab.build().extend(),
-        _ => ab,
+    let mut ab = if sess.targ_cfg.target.is_like_osx {
+        ab.build().extend()
+    } else {
+        ab
     };
     ab.add_native_library("morestack").unwrap();
     ab.add_native_library("compiler-rt").unwrap();
@@ -1286,7 +1661,7 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
     let pname = get_cc_prog(sess);
     let mut cmd = Command::new(pname.as_slice());
 
-    cmd.args(sess.targ_cfg.target_strs.cc_args.as_slice());
+    cmd.args(sess.targ_cfg.target.pre_link_args.as_slice());
     link_args(&mut cmd, sess, dylib, tmpdir.path(),
               trans, obj_filename, out_filename);
 
@@ -1326,8 +1701,7 @@ fn link_natively(sess: &Session, trans: &CrateTranslation, dylib: bool,
 
     // On OSX, debuggers need this utility to get run to do some munging of
     // the symbols
-    if (sess.targ_cfg.os == abi::OsMacos || sess.targ_cfg.os == abi::OsiOS)
-        && (sess.opts.debuginfo != NoDebugInfo) {
+    if sess.targ_cfg.target.is_like_osx && (sess.opts.debuginfo != NoDebugInfo) {
             match Command::new("dsymutil").arg(out_filename).output() {
                 Ok(..) => {}
                 Err(e) => {
@@ -1369,18 +1743,15 @@ fn link_args(cmd: &mut Command,
     // all contents of this library. This way we're guaranteed that the linker
     // will include the __morestack symbol 100% of the time, always resolving
     // references to it even if the object above didn't use it.
-    match sess.targ_cfg.os {
-        abi::OsMacos | abi::OsiOS => {
-            let morestack = lib_path.join("libmorestack.a");
+    if sess.targ_cfg.target.is_like_osx {
+        let morestack = lib_path.join("libmorestack.a");
 
-            let mut v = b"-Wl,-force_load,".to_vec();
-            v.push_all(morestack.as_vec());
-            cmd.arg(v.as_slice());
-        }
-        _ => {
-            cmd.args(["-Wl,--whole-archive", "-lmorestack",
-                      "-Wl,--no-whole-archive"]);
-        }
+        let mut v = b"-Wl,-force_load,".to_vec();
+        v.push_all(morestack.as_vec());
+        cmd.arg(v.as_slice());
+    } else {
+        cmd.args(["-Wl,--whole-archive", "-lmorestack",
+                  "-Wl,--no-whole-archive"]);
     }
 
     // When linking a dynamic library, we put the metadata into a section of the
@@ -1397,7 +1768,7 @@ fn link_args(cmd: &mut Command,
     // subset we wanted.
     //
     // FIXME(#11937) we should invoke the system linker directly
-    if sess.targ_cfg.os != abi::OsWindows {
+    if !sess.targ_cfg.target.is_like_windows {
         cmd.arg("-nodefaultlibs");
     }
 
@@ -1405,7 +1776,7 @@ fn link_args(cmd: &mut Command,
     // already done the best it can do, and we also don't want to eliminate the
     // metadata. If we're building an executable, however, --gc-sections drops
     // the size of hello world from 1.8MB to 597K, a 67% reduction.
-    if !dylib && sess.targ_cfg.os != abi::OsMacos && sess.targ_cfg.os != abi::OsiOS {
+    if !dylib && !sess.targ_cfg.target.is_like_osx {
         cmd.arg("-Wl,--gc-sections");
     }
 
@@ -1415,15 +1786,15 @@ fn link_args(cmd: &mut Command,
     // relocation model of position independent code is not changed. This is a requirement to take
     // advantage of ASLR, as otherwise the functions in the executable are not randomized and can
     // be used during an exploit of a vulnerability in any code.
-    if sess.targ_cfg.os == abi::OsLinux {
+    if sess.targ_cfg.target.position_independent_executables {
         let mut args = sess.opts.cg.link_args.iter().chain(used_link_args.iter());
-        if !dylib && sess.opts.cg.relocation_model.as_slice() == "pic" &&
+        if !dylib && relocation_model(sess) == "pic" &&
             !args.any(|x| x.as_slice() == "-static") {
             cmd.arg("-pie");
         }
     }
 
-    if sess.targ_cfg.os == abi::OsLinux || sess.targ_cfg.os == abi::OsDragonfly {
+    if sess.targ_cfg.target.linker_is_gnu {
         // GNU-style linkers will use this to omit linking to libraries which
         // don't actually fulfill any relocations, but only for libraries which
         // follow this flag. Thus, use it before specifying libraries to link to.
@@ -1435,7 +1806,7 @@ fn link_args(cmd: &mut Command,
            sess.opts.optimize == config::Aggressive {
             cmd.arg("-Wl,-O1");
         }
-    } else if sess.targ_cfg.os == abi::OsMacos || sess.targ_cfg.os == abi::OsiOS {
+    } else if sess.targ_cfg.target.is_like_osx {
         // The dead_strip option to the linker specifies that functions and data
         // unreachable by the entry point will be removed. This is quite useful
         // with Rust's compilation model of compiling libraries at a time into
@@ -1449,7 +1820,7 @@ fn link_args(cmd: &mut Command,
         cmd.arg("-Wl,-dead_strip");
     }
 
-    if sess.targ_cfg.os == abi::OsWindows {
+    if sess.targ_cfg.target.is_like_windows {
         // Make sure that we link to the dynamic libgcc, otherwise cross-module
         // DWARF stack unwinding will not work.
         // This behavior may be overridden by --link-args "-static-libgcc"
@@ -1489,12 +1860,6 @@ fn link_args(cmd: &mut Command,
         cmd.arg("-Wl,--dynamicbase");
     }
 
-    if sess.targ_cfg.os == abi::OsAndroid {
-        // Many of the symbols defined in compiler-rt are also defined in libgcc.
-        // Android linker doesn't like that by default.
-        cmd.arg("-Wl,--allow-multiple-definition");
-    }
-
     // Take careful note of the ordering of the arguments we pass to the linker
     // here. Linkers will assume that things on the left depend on things to the
     // right. Things on the right cannot depend on things on the left. This is
@@ -1536,7 +1901,7 @@ fn link_args(cmd: &mut Command,
 
     if dylib {
         // On mac we need to tell the linker to let this library be rpathed
-        if sess.targ_cfg.os == abi::OsMacos {
+        if sess.targ_cfg.target.is_like_osx {
             cmd.args(["-dynamiclib", "-Wl,-dylib"]);
 
             if sess.opts.cg.rpath {
@@ -1549,24 +1914,12 @@ fn link_args(cmd: &mut Command,
         }
     }
 
-    if sess.targ_cfg.os == abi::OsFreebsd {
-        cmd.args(["-L/usr/local/lib",
-                  "-L/usr/local/lib/gcc46",
-                  "-L/usr/local/lib/gcc44"]);
-    }
-    else if sess.targ_cfg.os == abi::OsDragonfly {
-        cmd.args(["-L/usr/local/lib",
-                  "-L/usr/lib/gcc47",
-                  "-L/usr/lib/gcc44"]);
-    }
-
-
     // FIXME (#2397): At some point we want to rpath our guesses as to
     // where extern libraries might live, based on the
     // addl_lib_search_paths
     if sess.opts.cg.rpath {
         let sysroot = sess.sysroot();
-        let target_triple = sess.opts.target_triple.as_slice();
+        let target_triple = sess.targ_cfg.target_name.as_slice();
         let get_install_prefix_lib_path = || {
             let install_prefix = option_env!("CFG_PREFIX").expect("CFG_PREFIX");
             let tlib = filesearch::relative_target_lib_path(sysroot, target_triple);
@@ -1576,7 +1929,8 @@ fn link_args(cmd: &mut Command,
             path
         };
         let rpath_config = RPathConfig {
-            os: sess.targ_cfg.os,
+            is_like_osx: sess.targ_cfg.target.is_like_osx,
+            has_rpath: sess.targ_cfg.target.has_rpath,
             used_crates: sess.cstore.get_used_crates(cstore::RequireDynamic),
             out_filename: out_filename.clone(),
             get_install_prefix_lib_path: get_install_prefix_lib_path,
@@ -1595,9 +1949,11 @@ fn link_args(cmd: &mut Command,
     cmd.arg("-lcompiler-rt");
 
     // Finally add all the linker arguments provided on the command line along
-    // with any #[link_args] attributes found inside the crate
+    // with any #[link_args] attributes found inside the crate, and the ones
+    // the target asks for last
     cmd.args(sess.opts.cg.link_args.as_slice());
     cmd.args(used_link_args.as_slice());
+    cmd.args(sess.targ_cfg.target.post_link_args.as_slice());
 }
 
 // # Native library linking
@@ -1625,8 +1981,7 @@ fn add_local_native_libraries(cmd: &mut Command, sess: &Session) {
     // For those that support this, we ensure we pass the option if the library
     // was flagged "static" (most defaults are dynamic) to ensure that if
     // libfoo.a and libfoo.so both exist that the right one is chosen.
-    let takes_hints = sess.targ_cfg.os != abi::OsMacos &&
-                      sess.targ_cfg.os != abi::OsiOS;
+    let takes_hints = !sess.targ_cfg.target.is_like_osx;
 
     let libs = sess.cstore.get_used_libraries();
     let libs = libs.borrow();
@@ -1656,8 +2011,10 @@ fn add_local_native_libraries(cmd: &mut Command, sess: &Session) {
         } else {
             // -force_load is the OSX equivalent of --whole-archive, but it
             // involves passing the full path to the library to link.
+            let target = &sess.targ_cfg.target;
             let lib = archive::find_library(l.as_slice(),
-                                            sess.targ_cfg.os,
+                                            target.staticlib_prefix.as_slice(),
+                                            target.staticlib_suffix.as_slice(),
                                             search_path.as_slice(),
                                             &sess.diagnostic().handler);
             let mut v = b"-Wl,-force_load,".to_vec();
@@ -1730,7 +2087,7 @@ fn add_upstream_rust_crates(cmd: &mut Command, sess: &Session,
 
     // Converts a library file-stem into a cc -l argument
     fn unlib<'a>(config: &config::Config, stem: &'a [u8]) -> &'a [u8] {
-        if stem.starts_with("lib".as_bytes()) && config.os != abi::OsWindows {
+        if stem.starts_with("lib".as_bytes()) && !config.target.is_like_windows {
             stem.tailn(3)
         } else {
             stem
@@ -1776,7 +2133,8 @@ fn add_upstream_rust_crates(cmd: &mut Command, sess: &Session,
                     handler: handler,
                     dst: dst.clone(),
                     lib_search_paths: archive_search_paths(sess),
-                    os: sess.targ_cfg.os,
+                    lib_prefix: sess.targ_cfg.target.staticlib_prefix.clone(),
+                    lib_suffix: sess.targ_cfg.target.staticlib_suffix.clone(),
                     maybe_ar_prog: sess.opts.cg.ar.clone()
                 };
                 let mut archive = Archive::open(config);
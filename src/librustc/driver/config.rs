@@ -17,8 +17,8 @@ use driver::session::Session;
 
 use back;
 use back::link;
-use back::target_strs;
-use back::{arm, x86, x86_64, mips, mipsel};
+use back::target;
+use back::target::Target;
 use lint;
 
 use syntax::abi;
@@ -28,6 +28,7 @@ use syntax::attr;
 use syntax::attr::AttrMetaMethods;
 use syntax::diagnostic::{ColorConfig, Auto, Always, Never};
 use syntax::parse;
+use syntax::parse::token;
 use syntax::parse::token::InternedString;
 
 use std::collections::{HashSet, HashMap};
@@ -41,7 +42,10 @@ use llvm;
 pub struct Config {
     pub os: abi::Os,
     pub arch: abi::Architecture,
-    pub target_strs: target_strs::t,
+    pub target: Target,
+    /// The name the target is known by in crate metadata and in the
+    /// sysroot, which is `--target` but for the path of a specification.
+    pub target_name: String,
     pub int_type: IntTy,
     pub uint_type: UintTy,
 }
@@ -61,6 +65,12 @@ pub enum DebugInfoLevel {
     FullDebugInfo,
 }
 
+#[deriving(Clone, PartialEq)]
+pub enum ErrorFormat {
+    ErrorFormatHuman, // colored text, see --color
+    ErrorFormatJson, // one JSON object per diagnostic, see syntax::json
+}
+
 #[deriving(Clone)]
 pub struct Options {
     // The crate config requested for the session, which may be combined
@@ -95,6 +105,7 @@ pub struct Options {
     pub print_metas: (bool, bool),
     pub cg: CodegenOptions,
     pub color: ColorConfig,
+    pub error_format: ErrorFormat,
     pub externs: HashMap<String, Vec<String>>,
     pub crate_name: Option<String>,
     /// An optional name to use as the crate for std during std injection,
@@ -126,6 +137,7 @@ pub fn basic_options() -> Options {
         print_metas: (false, false),
         cg: basic_codegen_options(),
         color: Auto,
+        error_format: ErrorFormatHuman,
         externs: HashMap::new(),
         crate_name: None,
         alt_std_name: None,
@@ -188,7 +200,8 @@ debugging_opts!(
         FLOWGRAPH_PRINT_LOANS,
         FLOWGRAPH_PRINT_MOVES,
         FLOWGRAPH_PRINT_ASSIGNS,
-        FLOWGRAPH_PRINT_ALL
+        FLOWGRAPH_PRINT_ALL,
+        APPLY_SUGGESTIONS
     ]
     0
 )
@@ -232,7 +245,9 @@ pub fn debugging_opts_map() -> Vec<(&'static str, &'static str, u64)> {
      ("flowgraph-print-assigns", "Include assignment analysis data in \
                        --pretty flowgraph output", FLOWGRAPH_PRINT_ASSIGNS),
      ("flowgraph-print-all", "Include all dataflow analysis data in \
-                       --pretty flowgraph output", FLOWGRAPH_PRINT_ALL))
+                       --pretty flowgraph output", FLOWGRAPH_PRINT_ALL),
+     ("apply-suggestions", "Rewrite the source files with the changes suggested \
+                            by lints", APPLY_SUGGESTIONS))
 }
 
 /// Declare a macro that will define all CodegenOptions fields and parsers all
@@ -290,6 +305,13 @@ macro_rules! cgoptions(
             }
         }
 
+        fn parse_uint(slot: &mut uint, v: Option<&str>) -> bool {
+            match v.and_then(|s| from_str(s)) {
+                Some(n) => { *slot = n; true },
+                None => false,
+            }
+        }
+
         fn parse_list(slot: &mut Vec<String>, v: Option<&str>)
                       -> bool {
             match v {
@@ -339,7 +361,7 @@ cgoptions!(
         "use an external assembler rather than LLVM's integrated one"),
     no_redzone: bool = (false, parse_bool,
         "disable the use of the redzone"),
-    relocation_model: String = ("pic".to_string(), parse_string,
+    relocation_model: Option<String> = (None, parse_opt_string,
          "choose the relocation model to use (llc -relocation-model for details)"),
     code_model: String = ("default".to_string(), parse_string,
          "choose the code model to use (llc -code-model for details)"),
@@ -347,6 +369,10 @@ cgoptions!(
          "metadata to mangle symbol names with"),
     extra_filename: String = ("".to_string(), parse_string,
          "extra data to put in each output filename"),
+    codegen_units: uint = (1, parse_uint,
+        "divide the crate into N units to optimize in parallel"),
+    incremental: Option<String> = (None, parse_opt_string,
+        "directory to keep results in to reuse them in the next compilation"),
 )
 
 pub fn build_codegen_options(matches: &getopts::Matches) -> CodegenOptions
@@ -389,41 +415,27 @@ pub fn default_lib_output() -> CrateType {
 }
 
 pub fn default_configuration(sess: &Session) -> ast::CrateConfig {
-    let tos = match sess.targ_cfg.os {
-        abi::OsWindows =>   InternedString::new("windows"),
-        abi::OsMacos =>     InternedString::new("macos"),
-        abi::OsLinux =>     InternedString::new("linux"),
-        abi::OsAndroid =>   InternedString::new("android"),
-        abi::OsFreebsd =>   InternedString::new("freebsd"),
-        abi::OsDragonfly => InternedString::new("dragonfly"),
-        abi::OsiOS =>       InternedString::new("ios"),
-    };
-
-    // ARM is bi-endian, however using NDK seems to default
-    // to little-endian unless a flag is provided.
-    let (end,arch,wordsz) = match sess.targ_cfg.arch {
-        abi::X86 =>    ("little", "x86",    "32"),
-        abi::X86_64 => ("little", "x86_64", "64"),
-        abi::Arm =>    ("little", "arm",    "32"),
-        abi::Mips =>   ("big",    "mips",   "32"),
-        abi::Mipsel => ("little", "mipsel", "32")
-    };
-
-    let fam = match sess.targ_cfg.os {
-        abi::OsWindows => InternedString::new("windows"),
-        _ => InternedString::new("unix")
-    };
-
+    let target = &sess.targ_cfg.target;
     let mk = attr::mk_name_value_item_str;
-    return vec!(// Target bindings.
-         attr::mk_word_item(fam.clone()),
-         mk(InternedString::new("target_os"), tos),
-         mk(InternedString::new("target_family"), fam),
-         mk(InternedString::new("target_arch"), InternedString::new(arch)),
-         mk(InternedString::new("target_endian"), InternedString::new(end)),
+    let mut cfg = vec!(// Target bindings.
+         mk(InternedString::new("target_os"),
+            token::intern_and_get_ident(target.target_os.as_slice())),
+         mk(InternedString::new("target_arch"),
+            token::intern_and_get_ident(target.arch.as_slice())),
+         mk(InternedString::new("target_endian"),
+            token::intern_and_get_ident(target.target_endian.as_slice())),
          mk(InternedString::new("target_word_size"),
-            InternedString::new(wordsz))
+            token::intern_and_get_ident(target.target_word_size.as_slice()))
     );
+    match target.target_family {
+        Some(ref family) => {
+            let fam = token::intern_and_get_ident(family.as_slice());
+            cfg.push(attr::mk_word_item(fam.clone()));
+            cfg.push(mk(InternedString::new("target_family"), fam));
+        }
+        None => {}
+    }
+    cfg
 }
 
 pub fn append_configuration(cfg: &mut ast::CrateConfig,
@@ -445,76 +457,28 @@ pub fn build_configuration(sess: &Session) -> ast::CrateConfig {
     user_cfg.move_iter().collect::<Vec<_>>().append(default_cfg.as_slice())
 }
 
-pub fn get_os(triple: &str) -> Option<abi::Os> {
-    for &(name, os) in os_names.iter() {
-        if triple.contains(name) { return Some(os) }
-    }
-    None
-}
-static os_names : &'static [(&'static str, abi::Os)] = &[
-    ("mingw32",   abi::OsWindows),
-    ("win32",     abi::OsWindows),
-    ("windows",   abi::OsWindows),
-    ("darwin",    abi::OsMacos),
-    ("android",   abi::OsAndroid),
-    ("linux",     abi::OsLinux),
-    ("freebsd",   abi::OsFreebsd),
-    ("dragonfly", abi::OsDragonfly),
-    ("ios",       abi::OsiOS)];
-
-pub fn get_arch(triple: &str) -> Option<abi::Architecture> {
-    for &(arch, abi) in architecture_abis.iter() {
-        if triple.contains(arch) { return Some(abi) }
-    }
-    None
-}
-static architecture_abis : &'static [(&'static str, abi::Architecture)] = &[
-    ("i386",   abi::X86),
-    ("i486",   abi::X86),
-    ("i586",   abi::X86),
-    ("i686",   abi::X86),
-    ("i786",   abi::X86),
-
-    ("x86_64", abi::X86_64),
-
-    ("arm",    abi::Arm),
-    ("xscale", abi::Arm),
-    ("thumb",  abi::Arm),
-
-    ("mipsel", abi::Mipsel),
-    ("mips",   abi::Mips)];
-
 pub fn build_target_config(sopts: &Options) -> Config {
-    let os = match get_os(sopts.target_triple.as_slice()) {
-      Some(os) => os,
-      None => early_error("unknown operating system")
+    let target = match Target::search(sopts.target_triple.as_slice()) {
+        Ok(target) => target,
+        Err(e) => early_error(e.as_slice()),
     };
-    let arch = match get_arch(sopts.target_triple.as_slice()) {
-      Some(arch) => arch,
-      None => {
-          early_error(format!("unknown architecture: {}",
-                              sopts.target_triple.as_slice()).as_slice())
-      }
-    };
-    let (int_type, uint_type) = match arch {
-      abi::X86 => (ast::TyI32, ast::TyU32),
-      abi::X86_64 => (ast::TyI64, ast::TyU64),
-      abi::Arm => (ast::TyI32, ast::TyU32),
-      abi::Mips => (ast::TyI32, ast::TyU32),
-      abi::Mipsel => (ast::TyI32, ast::TyU32)
+    let arch = match target.arch() {
+        Some(arch) => arch,
+        None => {
+            early_error(format!("unknown architecture: {}",
+                                target.arch).as_slice())
+        }
     };
-    let target_triple = sopts.target_triple.clone();
-    let target_strs = match arch {
-      abi::X86 => x86::get_target_strs(target_triple, os),
-      abi::X86_64 => x86_64::get_target_strs(target_triple, os),
-      abi::Arm => arm::get_target_strs(target_triple, os),
-      abi::Mips => mips::get_target_strs(target_triple, os),
-      abi::Mipsel => mipsel::get_target_strs(target_triple, os)
+    let (int_type, uint_type) = match target.target_word_size.as_slice() {
+        "32" => (ast::TyI32, ast::TyU32),
+        "64" => (ast::TyI64, ast::TyU64),
+        w => early_error(format!("unsupported word size: {}", w).as_slice())
     };
     Config {
-        os: os,
+        os: target.os(),
         arch: arch,
-        target_strs: target_strs,
+        target: target,
+        target_name: target::target_name(sopts.target_triple.as_slice()),
         int_type: int_type,
         uint_type: uint_type,
     }
@@ -566,7 +530,8 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
         optflag("", "test", "Build a test harness"),
         optopt("", "target", "Target triple cpu-manufacturer-kernel[-os]
                             to compile for (see chapter 3.4 of http://www.sourceware.org/autobook/
-                            for details)", "TRIPLE"),
+                            for details), or the path of a JSON file specifying the target",
+               "TRIPLE"),
         optmulti("W", "warn", "Set lint warnings", "OPT"),
         optmulti("A", "allow", "Set lint allowed", "OPT"),
         optmulti("D", "deny", "Set lint denied", "OPT"),
@@ -578,6 +543,9 @@ pub fn optgroups() -> Vec<getopts::OptGroup> {
             auto   = colorize, if output goes to a tty (default);
             always = always colorize output;
             never  = never colorize output", "auto|always|never"),
+        optopt("", "error-format", "Configure the format of errors and other messages:
+            human = human-readable text (default);
+            json  = one JSON object per line, for tools", "human|json"),
         optmulti("", "extern", "Specify where an external rust library is located",
                  "NAME=PATH"),
     )
@@ -734,6 +702,9 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
                     --print-file-name");
     }
     let cg = build_codegen_options(matches);
+    if cg.codegen_units == 0 {
+        early_error("value for codegen units must be a positive nonzero integer");
+    }
 
     let color = match matches.opt_str("color").as_ref().map(|s| s.as_slice()) {
         Some("auto")   => Auto,
@@ -749,6 +720,19 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         }
     };
 
+    let error_format = match matches.opt_str("error-format").as_ref().map(|s| s.as_slice()) {
+        Some("human") => ErrorFormatHuman,
+        Some("json")  => ErrorFormatJson,
+
+        None => ErrorFormatHuman,
+
+        Some(arg) => {
+            early_error(format!("argument for --error-format must be human or json \
+                                 (instead was `{}`)",
+                                arg).as_slice())
+        }
+    };
+
     let mut externs = HashMap::new();
     for arg in matches.opt_strs("extern").iter() {
         let mut parts = arg.as_slice().splitn(1, '=');
@@ -787,6 +771,7 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         print_metas: print_metas,
         cg: cg,
         color: color,
+        error_format: error_format,
         externs: externs,
         crate_name: crate_name,
         alt_std_name: None
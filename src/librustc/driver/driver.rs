@@ -18,6 +18,7 @@ use llvm::{ContextRef, ModuleRef};
 use metadata::common::LinkMeta;
 use metadata::creader;
 use middle::{trans, freevars, stability, kind, ty, typeck, reachable};
+use middle::dep_graph;
 use middle::dependency_format;
 use middle;
 use plugin::load::Plugins;
@@ -34,6 +35,7 @@ use std::io::fs;
 use syntax::ast;
 use syntax::attr;
 use syntax::attr::{AttrMetaMethods};
+use syntax::diagnostic;
 use syntax::diagnostics;
 use syntax::parse;
 use syntax::parse::token;
@@ -88,6 +90,7 @@ pub fn compile_input(sess: Session,
         let analysis = phase_3_run_analysis_passes(sess, &expanded_crate,
                                                    ast_map, id);
         phase_save_analysis(&analysis.ty_cx.sess, &expanded_crate, &analysis, outdir);
+        phase_apply_suggestions(&analysis.ty_cx.sess);
         if stop_after_phase_3(&analysis.ty_cx.sess) { return; }
         let (tcx, trans) = phase_4_translate_to_llvm(expanded_crate, analysis);
 
@@ -370,6 +373,9 @@ pub fn phase_3_run_analysis_passes(sess: Session,
                             lang_items,
                             stability_index);
 
+    time(time_passes, "loading the dependency graph", (), |_|
+         dep_graph::load(&ty_cx, krate));
+
     // passes are timed inside typeck
     typeck::check_crate(&ty_cx, trait_map, krate);
 
@@ -441,15 +447,52 @@ pub fn phase_save_analysis(sess: &Session,
          middle::save::process_crate(sess, krate, analysis, odir));
 }
 
+/// Write the changes suggested by lints back to the source files, if
+/// requested with `-Z apply-suggestions`. Suggestions which conflict with
+/// one made before them are left for another run.
+pub fn phase_apply_suggestions(sess: &Session) {
+    if (sess.opts.debugging_opts & config::APPLY_SUGGESTIONS) == 0 {
+        return;
+    }
+    let suggestions = sess.suggestions.borrow();
+    let files = diagnostic::apply_suggestions(sess.codemap(), suggestions.as_slice(), |name| {
+        io::File::open(&Path::new(name)).read_to_string().ok()
+    });
+    for &(ref name, ref src) in files.iter() {
+        let path = Path::new(name.as_slice());
+        match io::File::create(&path).write_str(src.as_slice()) {
+            Ok(()) => {}
+            Err(e) => {
+                sess.err(format!("error writing suggestions to `{}`: {}",
+                                 path.display(), e).as_slice());
+            }
+        }
+    }
+}
+
+/// The LLVM module of one codegen unit, in a context of its own.
+pub struct ModuleTranslation {
+    pub llcx: ContextRef,
+    pub llmod: ModuleRef,
+    /// Whether the unit is unchanged since the last compilation with
+    /// `-C incremental`, so its object file from then can be used instead of
+    /// optimizing and emitting the module again.
+    pub reuse_object: bool,
+}
+
 pub struct CrateTranslation {
-    pub context: ContextRef,
-    pub module: ModuleRef,
+    /// The modules of the codegen units. The metadata module belongs to the
+    /// context of the first.
+    pub modules: Vec<ModuleTranslation>,
     pub metadata_module: ModuleRef,
     pub link: LinkMeta,
     pub metadata: Vec<u8>,
     pub reachable: Vec<String>,
     pub crate_formats: dependency_format::Dependencies,
     pub no_builtins: bool,
+    /// The dependency graph to write out once the object files are, with
+    /// `-C incremental`.
+    pub dep_graph: Option<dep_graph::SerializedDepGraph>,
 }
 
 /// Run the translation phase to LLVM, after which the AST and analysis can
@@ -477,11 +520,15 @@ pub fn phase_5_run_llvm_passes(sess: &Session,
         time(sess.time_passes(), "LLVM passes", (), |_|
             link::write::run_passes(sess, trans, [output_type], outputs));
 
-        link::write::run_assembler(sess, outputs);
+        let units = link::write::output_units(sess, trans);
+        link::write::run_assembler(sess, outputs, units);
 
         // Remove assembly source, unless --save-temps was specified
         if !sess.opts.cg.save_temps {
-            fs::unlink(&outputs.temp_path(link::OutputTypeAssembly)).unwrap();
+            let path = outputs.temp_path(link::OutputTypeAssembly);
+            for unit in range(0, units) {
+                fs::unlink(&link::unit_path(&path, unit, units)).unwrap();
+            }
         }
     } else {
         time(sess.time_passes(), "LLVM passes", (), |_|
@@ -490,6 +537,11 @@ pub fn phase_5_run_llvm_passes(sess: &Session,
                                     sess.opts.output_types.as_slice(),
                                     outputs));
     }
+
+    match trans.dep_graph {
+        Some(ref graph) => dep_graph::save(sess, graph),
+        None => {}
+    }
 }
 
 /// Run the linker on any artifacts that resulted from the LLVM run.
@@ -667,7 +719,7 @@ pub fn collect_crate_types(session: &Session,
         if !res {
             session.warn(format!("dropping unsupported crate type `{}` \
                                    for target os `{}`",
-                                 *crate_type, session.targ_cfg.os).as_slice());
+                                 *crate_type, session.targ_cfg.target.target_os).as_slice());
         }
 
         res
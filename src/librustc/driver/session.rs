@@ -21,6 +21,7 @@ use syntax::ast::NodeId;
 use syntax::codemap::Span;
 use syntax::diagnostic;
 use syntax::diagnostics;
+use syntax::json;
 use syntax::parse;
 use syntax::parse::token;
 use syntax::parse::ParseSess;
@@ -46,7 +47,11 @@ pub struct Session {
     pub local_crate_source_file: Option<Path>,
     pub working_dir: Path,
     pub lint_store: RefCell<lint::LintStore>,
-    pub lints: RefCell<NodeMap<Vec<(lint::LintId, codemap::Span, String)>>>,
+    pub lints: RefCell<NodeMap<Vec<(lint::LintId, codemap::Span, String,
+                                    Option<lint::Suggestion>)>>>,
+    /// The suggestions shown with lints which `-Z apply-suggestions` can make
+    /// to the source files, as spans and their replacements.
+    pub suggestions: RefCell<Vec<(codemap::Span, String)>>,
     pub node_id: Cell<ast::NodeId>,
     pub crate_types: RefCell<Vec<config::CrateType>>,
     pub crate_metadata: RefCell<Vec<String>>,
@@ -100,6 +105,9 @@ impl Session {
     pub fn fileline_note(&self, sp: Span, msg: &str) {
         self.diagnostic().fileline_note(sp, msg)
     }
+    pub fn span_suggestion(&self, sp: Span, msg: &str, suggestion: String) {
+        self.diagnostic().span_suggestion(sp, msg, suggestion)
+    }
     pub fn note(&self, msg: &str) {
         self.diagnostic().handler().note(msg)
     }
@@ -120,13 +128,29 @@ impl Session {
                     id: ast::NodeId,
                     sp: Span,
                     msg: String) {
+        self.add_lint_(lint, id, sp, msg, None)
+    }
+    pub fn add_lint_with_suggestion(&self,
+                                    lint: &'static lint::Lint,
+                                    id: ast::NodeId,
+                                    sp: Span,
+                                    msg: String,
+                                    suggestion: lint::Suggestion) {
+        self.add_lint_(lint, id, sp, msg, Some(suggestion))
+    }
+    fn add_lint_(&self,
+                 lint: &'static lint::Lint,
+                 id: ast::NodeId,
+                 sp: Span,
+                 msg: String,
+                 suggestion: Option<lint::Suggestion>) {
         let lint_id = lint::LintId::of(lint);
         let mut lints = self.lints.borrow_mut();
         match lints.find_mut(&id) {
-            Some(arr) => { arr.push((lint_id, sp, msg)); return; }
+            Some(arr) => { arr.push((lint_id, sp, msg, suggestion)); return; }
             None => {}
         }
-        lints.insert(id, vec!((lint_id, sp, msg)));
+        lints.insert(id, vec!((lint_id, sp, msg, suggestion)));
     }
     pub fn next_node_id(&self) -> ast::NodeId {
         self.reserve_node_ids(1)
@@ -193,7 +217,7 @@ impl Session {
     }
     pub fn target_filesearch<'a>(&'a self) -> filesearch::FileSearch<'a> {
         filesearch::FileSearch::new(self.sysroot(),
-                                    self.opts.target_triple.as_slice(),
+                                    self.targ_cfg.target_name.as_slice(),
                                     &self.opts.addl_lib_search_paths)
     }
     pub fn host_filesearch<'a>(&'a self) -> filesearch::FileSearch<'a> {
@@ -209,8 +233,13 @@ pub fn build_session(sopts: config::Options,
                      registry: diagnostics::registry::Registry)
                      -> Session {
     let codemap = codemap::CodeMap::new();
-    let diagnostic_handler =
-        diagnostic::default_handler(sopts.color, Some(registry));
+    let emitter: Box<diagnostic::Emitter + Send> = match sopts.error_format {
+        config::ErrorFormatHuman => {
+            box diagnostic::EmitterWriter::stderr(sopts.color, Some(registry))
+        }
+        config::ErrorFormatJson => box json::JsonEmitter::stderr(Some(registry)),
+    };
+    let diagnostic_handler = diagnostic::mk_handler(emitter);
     let span_diagnostic_handler =
         diagnostic::mk_span_handler(diagnostic_handler, codemap);
 
@@ -251,6 +280,7 @@ pub fn build_session_(sopts: config::Options,
         working_dir: os::getcwd(),
         lint_store: RefCell::new(lint::LintStore::new()),
         lints: RefCell::new(NodeMap::new()),
+        suggestions: RefCell::new(Vec::new()),
         node_id: Cell::new(1),
         crate_types: RefCell::new(Vec::new()),
         crate_metadata: RefCell::new(Vec::new()),
@@ -63,7 +63,7 @@ pub mod back {
     pub use rustc_back::mipsel;
     pub use rustc_back::rpath;
     pub use rustc_back::svh;
-    pub use rustc_back::target_strs;
+    pub use rustc_back::target;
     pub use rustc_back::x86;
     pub use rustc_back::x86_64;
 
@@ -84,6 +84,7 @@ pub mod middle {
     pub mod dataflow;
     pub mod dead;
     pub mod def;
+    pub mod dep_graph;
     pub mod dependency_format;
     pub mod effect;
     pub mod entry;
@@ -32,7 +32,7 @@ use middle::typeck::infer;
 use middle::{typeck, ty, def, pat_util, stability};
 use util::ppaux::{ty_to_string};
 use util::nodemap::NodeSet;
-use lint::{Context, LintPass, LintArray};
+use lint::{Context, LintPass, LintArray, Suggestion};
 
 use std::cmp;
 use std::collections::HashMap;
@@ -42,7 +42,8 @@ use syntax::abi;
 use syntax::ast_map;
 use syntax::attr::AttrMetaMethods;
 use syntax::attr;
-use syntax::codemap::Span;
+use syntax::codemap::{BytePos, Span};
+use syntax::codemap;
 use syntax::parse::token;
 use syntax::{ast, ast_util, visit};
 
@@ -760,16 +761,55 @@ impl NonCamelCaseTypes {
             )).collect()
         }
 
+        // The offset of the first occurrence of `name` in `s` which isn't
+        // part of a longer identifier
+        fn find_ident(s: &str, name: &str) -> Option<uint> {
+            fn is_ident_char(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+            let mut start = 0;
+            loop {
+                let lo = match s.slice_from(start).find_str(name) {
+                    Some(i) => start + i,
+                    None => return None,
+                };
+                let hi = lo + name.len();
+                if !s.slice_to(lo).chars().rev().next().map_or(false, is_ident_char) &&
+                   !s.slice_from(hi).chars().next().map_or(false, is_ident_char) {
+                    return Some(lo);
+                }
+                start = hi;
+            }
+        }
+
         let s = token::get_ident(ident);
 
         if !is_camel_case(ident) {
             let c = to_camel_case(s.get());
-            let m = if c.is_empty() {
-                format!("{} `{}` should have a camel case name such as `CamelCase`", sort, s)
-            } else {
-                format!("{} `{}` should have a camel case name such as `{}`", sort, s, c)
-            };
-            cx.span_lint(NON_CAMEL_CASE_TYPES, span, m.as_slice());
+            if c.is_empty() {
+                let m = format!("{} `{}` should have a camel case name such as `CamelCase`",
+                                sort, s);
+                cx.span_lint(NON_CAMEL_CASE_TYPES, span, m.as_slice());
+                return;
+            }
+
+            let m = format!("{} `{}` should have a camel case name such as `{}`", sort, s, c);
+            let snippet = cx.sess().codemap().span_to_snippet(span);
+            match snippet.and_then(|snippet| find_ident(snippet.as_slice(), s.get())) {
+                Some(i) => {
+                    // The uses of the name would need renaming as well, so
+                    // this isn't one to apply on its own
+                    let lo = span.lo + BytePos(i as u32);
+                    let hi = lo + BytePos(s.get().len() as u32);
+                    cx.span_lint_with_suggestion(NON_CAMEL_CASE_TYPES, span, m.as_slice(),
+                                                 Suggestion {
+                        span: codemap::mk_sp(lo, hi),
+                        msg: format!("rename it to `{}`", c),
+                        replacement: c,
+                        applicable: false,
+                    });
+                }
+                None => cx.span_lint(NON_CAMEL_CASE_TYPES, span, m.as_slice()),
+            }
         }
     }
 }
@@ -1022,9 +1062,31 @@ impl UnnecessaryParens {
             ast::ExprParen(ref inner) => {
                 let necessary = struct_lit_needs_parens && contains_exterior_struct_lit(&**inner);
                 if !necessary {
-                    cx.span_lint(UNNECESSARY_PARENS, value.span,
-                                 format!("unnecessary parentheses around {}",
-                                         msg).as_slice())
+                    let msg = format!("unnecessary parentheses around {}", msg);
+                    let cm = cx.sess().codemap();
+                    match cm.span_to_snippet(inner.span) {
+                        Some(snippet) => {
+                            // Keep `return(x)` from becoming `returnx`
+                            let before = codemap::mk_sp(value.span.lo - BytePos(1),
+                                                        value.span.lo);
+                            let needs_space = cm.span_to_snippet(before).map_or(false, |s| {
+                                s.as_slice().chars().all(|c| c.is_alphanumeric() || c == '_')
+                            });
+                            let replacement = if needs_space {
+                                format!(" {}", snippet)
+                            } else {
+                                snippet
+                            };
+                            cx.span_lint_with_suggestion(UNNECESSARY_PARENS, value.span,
+                                                         msg.as_slice(), Suggestion {
+                                span: value.span,
+                                msg: "remove these parentheses".to_string(),
+                                replacement: replacement,
+                                applicable: true,
+                            });
+                        }
+                        None => cx.span_lint(UNNECESSARY_PARENS, value.span, msg.as_slice()),
+                    }
                 }
             }
             _ => {}
@@ -1172,8 +1234,21 @@ impl UnusedMut {
         let used_mutables = cx.tcx.used_mut_nodes.borrow();
         for (_, v) in mutables.iter() {
             if !v.iter().any(|e| used_mutables.contains(e)) {
-                cx.span_lint(UNUSED_MUT, cx.tcx.map.span(*v.get(0)),
-                             "variable does not need to be mutable");
+                let span = cx.tcx.map.span(*v.get(0));
+                let msg = "variable does not need to be mutable";
+                match cx.sess().codemap().span_to_snippet(span) {
+                    Some(ref snippet) if snippet.as_slice().starts_with("mut ") => {
+                        cx.span_lint_with_suggestion(UNUSED_MUT, span, msg, Suggestion {
+                            span: span,
+                            msg: "remove `mut`".to_string(),
+                            replacement: snippet.as_slice().slice_from(3).trim_left().to_string(),
+                            // The other patterns of a match arm binding the
+                            // same name would be left with theirs
+                            applicable: v.len() == 1,
+                        });
+                    }
+                    _ => cx.span_lint(UNUSED_MUT, span, msg),
+                }
             }
         }
     }
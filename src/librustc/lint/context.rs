@@ -30,7 +30,7 @@ use middle::typeck::astconv::AstConv;
 use middle::typeck::infer;
 use driver::session::Session;
 use driver::early_error;
-use lint::{Level, LevelSource, Lint, LintId, LintArray, LintPass, LintPassObject};
+use lint::{Level, LevelSource, Lint, LintId, LintArray, LintPass, LintPassObject, Suggestion};
 use lint::{Default, CommandLine, Node, Allow, Warn, Deny, Forbid};
 use lint::builtin;
 
@@ -372,7 +372,8 @@ impl<'a> Context<'a> {
         self.lints.levels.find(&LintId::of(lint)).map_or(Allow, |&(lvl, _)| lvl)
     }
 
-    fn lookup_and_emit(&self, lint: &'static Lint, span: Option<Span>, msg: &str) {
+    fn lookup_and_emit(&self, lint: &'static Lint, span: Option<Span>, msg: &str,
+                       suggestion: Option<Suggestion>) {
         let (level, src) = match self.lints.levels.find(&LintId::of(lint)) {
             None => return,
             Some(&(Warn, src)) => {
@@ -383,16 +384,33 @@ impl<'a> Context<'a> {
         };
 
         raw_emit_lint(&self.tcx.sess, lint, (level, src), span, msg);
+
+        if level == Allow { return }
+        for suggestion in suggestion.move_iter() {
+            let sess = &self.tcx.sess;
+            sess.span_suggestion(suggestion.span, suggestion.msg.as_slice(),
+                                 suggestion.replacement.clone());
+            if suggestion.applicable {
+                sess.suggestions.borrow_mut().push((suggestion.span, suggestion.replacement));
+            }
+        }
     }
 
     /// Emit a lint at the appropriate level, with no associated span.
     pub fn lint(&self, lint: &'static Lint, msg: &str) {
-        self.lookup_and_emit(lint, None, msg);
+        self.lookup_and_emit(lint, None, msg, None);
     }
 
     /// Emit a lint at the appropriate level, for a particular span.
     pub fn span_lint(&self, lint: &'static Lint, span: Span, msg: &str) {
-        self.lookup_and_emit(lint, Some(span), msg);
+        self.lookup_and_emit(lint, Some(span), msg, None);
+    }
+
+    /// Emit a lint at the appropriate level, for a particular span, along
+    /// with a change to the code which would fix it.
+    pub fn span_lint_with_suggestion(&self, lint: &'static Lint, span: Span, msg: &str,
+                                     suggestion: Suggestion) {
+        self.lookup_and_emit(lint, Some(span), msg, Some(suggestion));
     }
 
     /**
@@ -666,8 +684,8 @@ impl<'a> IdVisitingOperation for Context<'a> {
         match self.tcx.sess.lints.borrow_mut().pop(&id) {
             None => {}
             Some(lints) => {
-                for (lint_id, span, msg) in lints.move_iter() {
-                    self.span_lint(lint_id.lint, span, msg.as_slice())
+                for (lint_id, span, msg, suggestion) in lints.move_iter() {
+                    self.lookup_and_emit(lint_id.lint, Some(span), msg.as_slice(), suggestion)
                 }
             }
         }
@@ -730,7 +748,7 @@ pub fn check_crate(tcx: &ty::ctxt,
     // If we missed any lints added to the session, then there's a bug somewhere
     // in the iteration code.
     for (id, v) in tcx.sess.lints.borrow().iter() {
-        for &(lint, span, ref msg) in v.iter() {
+        for &(lint, span, ref msg, _) in v.iter() {
             tcx.sess.span_bug(span,
                               format!("unprocessed lint {} at {}: {}",
                                       lint.as_str(), tcx.map.node_to_string(*id), *msg).as_slice())
@@ -248,6 +248,24 @@ pub enum LintSource {
 
 pub type LevelSource = (Level, LintSource);
 
+/// A change to the code which would fix a lint, shown along with it.
+#[deriving(Clone)]
+pub struct Suggestion {
+    /// The code to replace.
+    pub span: Span,
+
+    /// Describes the change, e.g. "remove these parentheses".
+    pub msg: String,
+
+    /// The code to replace the span with.
+    pub replacement: String,
+
+    /// Whether the change stands on its own, so that `-Z apply-suggestions`
+    /// can make it. Renaming a definition doesn't, as its uses would be left
+    /// with the old name.
+    pub applicable: bool,
+}
+
 pub mod builtin;
 
 mod context;
@@ -217,6 +217,10 @@ pub static tag_items_data_item_stability: uint = 0x92;
 pub struct LinkMeta {
     pub crate_name: String,
     pub crate_hash: Svh,
+    /// What symbol names and type ids hash the crate as. It's the SVH, except
+    /// with `-C incremental`, where the SVH changes with every function body
+    /// but object files are reused.
+    pub symbol_hash: Svh,
 }
 
 pub static tag_unboxed_closures: uint = 0x95;
@@ -245,3 +249,9 @@ pub static tag_type_param_def: uint = 0xa5;
 pub static tag_item_generics: uint = 0xa6;
 pub static tag_method_ty_generics: uint = 0xa7;
 
+// The dependency graph kept with `-C incremental`, which isn't part of the
+// crate metadata but uses the same encoding.
+pub static tag_dep_graph: uint = 0xa8;
+
+pub static tag_crate_symbol_hash: uint = 0xa9;
+
@@ -13,8 +13,9 @@
 //! Validates all used crates and extern libraries and loads their metadata
 
 use back::svh::Svh;
+use back::target::Target;
 use driver::session::Session;
-use driver::{driver, config};
+use driver::driver;
 use metadata::cstore;
 use metadata::cstore::{CStore, CrateSource};
 use metadata::decoder;
@@ -381,7 +382,7 @@ fn resolve_crate<'a>(e: &mut Env,
                 hash: hash.map(|a| &*a),
                 filesearch: e.sess.target_filesearch(),
                 os: e.sess.targ_cfg.os,
-                triple: e.sess.targ_cfg.target_strs.target_triple.as_slice(),
+                triple: e.sess.targ_cfg.target_name.as_slice(),
                 root: root,
                 rejected_via_hash: vec!(),
                 rejected_via_triple: vec!(),
@@ -431,10 +432,10 @@ impl<'a> PluginMetadataReader<'a> {
 
     pub fn read_plugin_metadata(&mut self, krate: &ast::ViewItem) -> PluginMetadata {
         let info = extract_crate_info(&self.env, krate).unwrap();
-        let target_triple = self.env.sess.targ_cfg.target_strs.target_triple.as_slice();
+        let target_triple = self.env.sess.targ_cfg.target_name.as_slice();
         let is_cross = target_triple != driver::host_triple();
         let mut should_link = info.should_link && !is_cross;
-        let os = config::get_os(driver::host_triple()).unwrap();
+        let os = Target::builtin(driver::host_triple()).unwrap().os();
         let mut load_ctxt = loader::Context {
             sess: self.env.sess,
             span: krate.span,
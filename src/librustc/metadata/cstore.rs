@@ -103,6 +103,11 @@ impl CStore {
         decoder::get_crate_hash(cdata.data())
     }
 
+    pub fn get_crate_symbol_hash(&self, cnum: ast::CrateNum) -> Svh {
+        let cdata = self.get_crate_data(cnum);
+        decoder::get_crate_symbol_hash(cdata.data())
+    }
+
     pub fn set_crate_data(&self, cnum: ast::CrateNum, data: Rc<crate_metadata>) {
         self.metas.borrow_mut().insert(cnum, data);
     }
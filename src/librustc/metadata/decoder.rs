@@ -1130,6 +1130,12 @@ pub fn get_crate_hash(data: &[u8]) -> Svh {
     Svh::new(hashdoc.as_str_slice())
 }
 
+pub fn get_crate_symbol_hash(data: &[u8]) -> Svh {
+    let cratedoc = rbml::Doc::new(data);
+    let hashdoc = reader::get_doc(cratedoc, tag_crate_symbol_hash);
+    Svh::new(hashdoc.as_str_slice())
+}
+
 pub fn maybe_get_crate_name(data: &[u8]) -> Option<String> {
     let cratedoc = rbml::Doc::new(data);
     reader::maybe_get_doc(cratedoc, tag_crate_crate_name).map(|doc| {
@@ -1911,6 +1911,12 @@ fn encode_hash(rbml_w: &mut Encoder, hash: &Svh) {
     rbml_w.end_tag();
 }
 
+fn encode_symbol_hash(rbml_w: &mut Encoder, hash: &Svh) {
+    rbml_w.start_tag(tag_crate_symbol_hash);
+    rbml_w.writer.write(hash.as_str().as_bytes());
+    rbml_w.end_tag();
+}
+
 fn encode_crate_name(rbml_w: &mut Encoder, crate_name: &str) {
     rbml_w.start_tag(tag_crate_crate_name);
     rbml_w.writer.write(crate_name.as_bytes());
@@ -2008,13 +2014,9 @@ fn encode_metadata_inner(wr: &mut SeekableMemWriter, parms: EncodeParams, krate:
     let mut rbml_w = writer::Encoder::new(wr);
 
     encode_crate_name(&mut rbml_w, ecx.link_meta.crate_name.as_slice());
-    encode_crate_triple(&mut rbml_w,
-                        tcx.sess
-                           .targ_cfg
-                           .target_strs
-                           .target_triple
-                           .as_slice());
+    encode_crate_triple(&mut rbml_w, tcx.sess.targ_cfg.target_name.as_slice());
     encode_hash(&mut rbml_w, &ecx.link_meta.crate_hash);
+    encode_symbol_hash(&mut rbml_w, &ecx.link_meta.symbol_hash);
     encode_dylib_dependency_formats(&mut rbml_w, &ecx);
 
     let mut i = rbml_w.writer.tell().unwrap();
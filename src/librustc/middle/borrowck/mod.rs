@@ -17,6 +17,7 @@ use middle::dataflow::DataFlowContext;
 use middle::dataflow::BitwiseOperator;
 use middle::dataflow::DataFlowOperator;
 use middle::def;
+use middle::dep_graph;
 use middle::expr_use_visitor as euv;
 use middle::mem_categorization as mc;
 use middle::ty;
@@ -63,7 +64,34 @@ pub type LoanDataFlow<'a> = DataFlowContext<'a, LoanDataFlowOperator>;
 impl<'a> Visitor<()> for BorrowckCtxt<'a> {
     fn visit_fn(&mut self, fk: &FnKind, fd: &FnDecl,
                 b: &Block, s: Span, n: NodeId, _: ()) {
-        borrowck_fn(self, fk, fd, b, s, n);
+        // With `-C incremental`, functions and methods whose source and uses
+        // haven't changed since they were last checked are skipped
+        let key = match self.tcx.dep_graph.item_key(n) {
+            Some(key) => key,
+            None => return borrowck_fn(self, fk, fd, b, s, n),
+        };
+        let node = dep_graph::BorrowCheck(key);
+        let mut bindings = MutBindings { ids: Vec::new() };
+        visit::walk_fn(&mut bindings, fk, fd, b, s, ());
+        if self.tcx.dep_graph.try_reuse(&node) {
+            // The lint about unused `mut`s relies on borrow checking. Only
+            // functions using all their `mut` bindings are reused, so mark
+            // them as used
+            let mut used_mut_nodes = self.tcx.used_mut_nodes.borrow_mut();
+            used_mut_nodes.extend(bindings.ids.move_iter());
+            return;
+        }
+        let tcx = self.tcx;
+        tcx.dep_graph.with_task(node.clone(), || {
+            dep_graph::read_item(tcx, n);
+            borrowck_fn(self, fk, fd, b, s, n);
+        });
+        // A function with unused `mut` bindings is checked again each time, so
+        // the lint warns about them each time
+        let used_mut_nodes = tcx.used_mut_nodes.borrow();
+        if !bindings.ids.iter().all(|id| used_mut_nodes.contains(id)) {
+            tcx.dep_graph.forget(&node);
+        }
     }
 
     fn visit_item(&mut self, item: &ast::Item, _: ()) {
@@ -71,6 +99,24 @@ impl<'a> Visitor<()> for BorrowckCtxt<'a> {
     }
 }
 
+/// Collects the mutable bindings of a function, which the lint about unused
+/// `mut`s checks.
+struct MutBindings {
+    ids: Vec<ast::NodeId>,
+}
+
+impl Visitor<()> for MutBindings {
+    fn visit_pat(&mut self, p: &ast::Pat, _: ()) {
+        match p.node {
+            ast::PatIdent(ast::BindByValue(ast::MutMutable), _, _) => {
+                self.ids.push(p.id);
+            }
+            _ => {}
+        }
+        visit::walk_pat(self, p, ());
+    }
+}
+
 pub fn check_crate(tcx: &ty::ctxt,
                    krate: &ast::Crate) {
     let mut bccx = BorrowckCtxt {
@@ -147,6 +147,7 @@ pub fn lookup_const_by_id(tcx: &ty::ctxt, def_id: ast::DefId)
                           -> Option<Gc<Expr>> {
     if ast_util::is_local(def_id) {
         {
+            tcx.dep_graph.read_use(&tcx.map, def_id.node);
             match tcx.map.find(def_id.node) {
                 None => None,
                 Some(ast_map::NodeItem(it)) => match it.node {
@@ -0,0 +1,682 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Tracking of which results of compiling a crate depend on which of its
+ * items, so that `-C incremental=<dir>` can reuse the results whose inputs
+ * haven't changed since the last compilation.
+ *
+ * The inputs are the source of each function, method and static of the
+ * crate (`Hir`), the signature of each function and method (`Signature`),
+ * and everything else (`Krate`): the types, traits and impls, the command
+ * line and the crates linked to. Each input has a fingerprint, a hash of its
+ * pretty-printed source. Items are known by their paths, as node ids change
+ * whenever an item before them does.
+ *
+ * The results are the borrow checking of each function and method
+ * (`BorrowCheck`) and the object file of each codegen unit (`TransUnit`).
+ * Computing a result is a task, and the inputs read while it runs are the
+ * edges of the graph. A result is clean if the last compilation computed it
+ * and none of the inputs it read have changed since.
+ *
+ * The graph is written to the incremental directory with the encoder used
+ * for crate metadata, and the object files of the codegen units are kept
+ * next to it by `back::link::write`.
+ *
+ * Anything which changes `Krate` invalidates every result, so adding an
+ * item or changing a type recompiles the whole crate. Only the bodies of
+ * functions and statics, and the signatures of functions, are tracked more
+ * precisely than that.
+ *
+ * Clean results save borrow checking the function, and optimizing and
+ * emitting the object file of the unit. Every other pass, type checking and
+ * translation to LLVM IR included, still runs over the whole crate: the
+ * inputs a codegen unit reads are only known once it has been translated
+ * again, which `is_unchanged` checks afterwards.
+ */
+
+use driver::config::NoDebugInfo;
+use driver::session::Session;
+use metadata::common::tag_dep_graph;
+use middle::def;
+use middle::ty;
+use middle::typeck;
+use util::nodemap::NodeMap;
+use util::sha2::{Digest, Sha256};
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::io::{File, IoError};
+use std::io::fs;
+use std::os;
+use rbml::io::SeekableMemWriter;
+use rbml::{reader, writer};
+use rbml;
+use serialize::{Decodable, Encodable};
+use syntax::ast;
+use syntax::ast_map;
+use syntax::codemap::Span;
+use syntax::print::pprust;
+use syntax::visit;
+use syntax::visit::Visitor;
+
+#[deriving(Clone, PartialEq, Eq, Hash, Encodable, Decodable, Show)]
+pub enum DepNode {
+    /// The source of a function, method or static, by its path.
+    Hir(String),
+    /// The signature of a function or method: its attributes, ABI and
+    /// declaration.
+    Signature(String),
+    /// Everything in and around the crate which isn't tracked per item.
+    /// Every result reads it, so any change to it invalidates them all.
+    Krate,
+    /// The borrow checking of a function or method.
+    BorrowCheck(String),
+    /// The translation of a codegen unit to an object file.
+    TransUnit(uint),
+}
+
+/// The dependency graph of a compilation, as written to the incremental
+/// directory.
+#[deriving(Encodable, Decodable)]
+pub struct SerializedDepGraph {
+    pub fingerprints: Vec<(DepNode, String)>,
+    /// The results computed, with the inputs each of them read.
+    pub edges: Vec<(DepNode, Vec<DepNode>)>,
+}
+
+pub struct DepGraph {
+    enabled: Cell<bool>,
+    /// The paths of the items tracked on their own.
+    keys: RefCell<NodeMap<String>>,
+    fingerprints: RefCell<HashMap<DepNode, String>>,
+    edges: RefCell<HashMap<DepNode, HashSet<DepNode>>>,
+    /// The results being computed, innermost last.
+    tasks: RefCell<Vec<DepNode>>,
+    previous_fingerprints: RefCell<HashMap<DepNode, String>>,
+    previous_edges: RefCell<HashMap<DepNode, Vec<DepNode>>>,
+}
+
+impl DepGraph {
+    pub fn new() -> DepGraph {
+        DepGraph {
+            enabled: Cell::new(false),
+            keys: RefCell::new(NodeMap::new()),
+            fingerprints: RefCell::new(HashMap::new()),
+            edges: RefCell::new(HashMap::new()),
+            tasks: RefCell::new(Vec::new()),
+            previous_fingerprints: RefCell::new(HashMap::new()),
+            previous_edges: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Whether dependencies are being tracked, which they are with
+    /// `-C incremental`.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// The path of the function, method or static with `id`, if it's tracked
+    /// on its own rather than as part of the item it's nested in.
+    pub fn item_key(&self, id: ast::NodeId) -> Option<String> {
+        self.keys.borrow().find(&id).map(|key| key.clone())
+    }
+
+    /// The fingerprint of `Krate`, if dependencies are being tracked.
+    pub fn krate_fingerprint(&self) -> Option<String> {
+        self.fingerprints.borrow().find(&Krate).map(|f| f.clone())
+    }
+
+    /// Runs `f` to compute `node`, recording the inputs read meanwhile as
+    /// its dependencies. Every result depends on `Krate`.
+    pub fn with_task<T>(&self, node: DepNode, f: || -> T) -> T {
+        if !self.is_enabled() {
+            return f();
+        }
+        self.edges.borrow_mut()
+            .find_or_insert_with(node.clone(), |_| HashSet::new())
+            .insert(Krate);
+        self.tasks.borrow_mut().push(node);
+        let ret = f();
+        self.tasks.borrow_mut().pop();
+        ret
+    }
+
+    /// Records that the result being computed reads `node`.
+    pub fn read(&self, node: DepNode) {
+        if !self.is_enabled() {
+            return;
+        }
+        let tasks = self.tasks.borrow();
+        match tasks.last() {
+            Some(task) => { self.edges.borrow_mut().get_mut(task).insert(node); }
+            None => {}
+        }
+    }
+
+    /// Records a read of the source of the item with `id`, or of the tracked
+    /// item it's nested in.
+    pub fn read_hir(&self, map: &ast_map::Map, id: ast::NodeId) {
+        match self.enclosing_key(map, id) {
+            Some(key) => self.read(Hir(key)),
+            None => {}
+        }
+    }
+
+    /// Records a use of the local item with `id`, which reads the signature
+    /// of a function or method, and the whole of a static, whose value may
+    /// be inlined.
+    pub fn read_use(&self, map: &ast_map::Map, id: ast::NodeId) {
+        match self.enclosing_key(map, id) {
+            Some(key) => {
+                let signature = Signature(key.clone());
+                if self.fingerprints.borrow().contains_key(&signature) {
+                    self.read(signature);
+                } else {
+                    self.read(Hir(key));
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn enclosing_key(&self, map: &ast_map::Map, mut id: ast::NodeId) -> Option<String> {
+        if !self.is_enabled() {
+            return None;
+        }
+        loop {
+            match self.item_key(id) {
+                Some(key) => return Some(key),
+                None => {}
+            }
+            let parent = map.get_parent(id);
+            if parent == id {
+                return None;
+            }
+            id = parent;
+        }
+    }
+
+    /// Returns whether `node` is clean, before computing it. If it is, the
+    /// inputs it read in the last compilation are kept as its dependencies,
+    /// as it won't be computed to read them again.
+    pub fn try_reuse(&self, node: &DepNode) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let reads = match self.previous_edges.borrow().find(node) {
+            Some(reads) => reads.clone(),
+            None => return false,
+        };
+        if !self.inputs_unchanged(reads.as_slice()) {
+            return false;
+        }
+        self.edges.borrow_mut().insert(node.clone(), reads.move_iter().collect());
+        true
+    }
+
+    /// Forgets the result computed for `node`, so that the next compilation
+    /// computes it again rather than reusing it.
+    pub fn forget(&self, node: &DepNode) {
+        self.edges.borrow_mut().remove(node);
+    }
+
+    /// Returns whether `node`, once computed again, read the same inputs as
+    /// in the last compilation and none of them have changed, so the result
+    /// kept from then is still good.
+    pub fn is_unchanged(&self, node: &DepNode) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let previous_edges = self.previous_edges.borrow();
+        let previous = match previous_edges.find(node) {
+            Some(reads) => reads,
+            None => return false,
+        };
+        let edges = self.edges.borrow();
+        let current = match edges.find(node) {
+            Some(reads) => reads,
+            None => return false,
+        };
+        previous.len() == current.len() &&
+            previous.iter().all(|read| current.contains(read)) &&
+            self.inputs_unchanged(previous.as_slice())
+    }
+
+    fn inputs_unchanged(&self, reads: &[DepNode]) -> bool {
+        let fingerprints = self.fingerprints.borrow();
+        let previous = self.previous_fingerprints.borrow();
+        reads.iter().all(|read| {
+            match (fingerprints.find(read), previous.find(read)) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            }
+        })
+    }
+
+    /// The graph to write out for the next compilation, if dependencies are
+    /// being tracked.
+    pub fn serialize(&self) -> Option<SerializedDepGraph> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let fingerprints = self.fingerprints.borrow();
+        let edges = self.edges.borrow();
+        Some(SerializedDepGraph {
+            fingerprints: fingerprints.iter().map(|(node, fingerprint)| {
+                (node.clone(), fingerprint.clone())
+            }).collect(),
+            edges: edges.iter().map(|(node, reads)| {
+                (node.clone(), reads.iter().map(|read| read.clone()).collect())
+            }).collect(),
+        })
+    }
+}
+
+/// Records a read of the item with `id`: of its source, and of each of the
+/// items it uses, as found by walking over it.
+pub fn read_item(tcx: &ty::ctxt, id: ast::NodeId) {
+    if !tcx.dep_graph.is_enabled() {
+        return;
+    }
+    tcx.dep_graph.read_hir(&tcx.map, id);
+    let mut visitor = UseVisitor { tcx: tcx };
+    match tcx.map.find(id) {
+        Some(ast_map::NodeItem(item)) => visit::walk_item(&mut visitor, &*item, ()),
+        Some(ast_map::NodeImplItem(ii)) => {
+            match *ii {
+                ast::MethodImplItem(m) => visit::walk_method_helper(&mut visitor, &*m, ()),
+            }
+        }
+        _ => {}
+    }
+}
+
+struct UseVisitor<'a> {
+    tcx: &'a ty::ctxt,
+}
+
+impl<'a> UseVisitor<'a> {
+    fn read_def_id(&self, def_id: ast::DefId) {
+        if def_id.krate == ast::LOCAL_CRATE {
+            self.tcx.dep_graph.read_use(&self.tcx.map, def_id.node);
+        }
+    }
+}
+
+impl<'a> Visitor<()> for UseVisitor<'a> {
+    fn visit_expr(&mut self, e: &ast::Expr, _: ()) {
+        match e.node {
+            ast::ExprPath(..) => {
+                match self.tcx.def_map.borrow().find_copy(&e.id) {
+                    Some(def::DefFn(def_id, _)) |
+                    Some(def::DefStaticMethod(def_id, _, _)) |
+                    Some(def::DefMethod(def_id, _)) |
+                    Some(def::DefStatic(def_id, _)) => self.read_def_id(def_id),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        let method_call = typeck::MethodCall::expr(e.id);
+        match self.tcx.method_map.borrow().find(&method_call) {
+            Some(&typeck::MethodCallee { origin: typeck::MethodStatic(def_id), .. }) => {
+                self.read_def_id(def_id)
+            }
+            _ => {}
+        }
+        visit::walk_expr(self, e, ());
+    }
+}
+
+/// Fingerprints the inputs of the crate and loads the graph written by the
+/// last compilation, when compiling with `-C incremental`.
+pub fn load(tcx: &ty::ctxt, krate: &ast::Crate) {
+    let dir = match tcx.sess.opts.cg.incremental {
+        Some(ref dir) => Path::new(dir.as_slice()),
+        None => return,
+    };
+    match fs::mkdir_recursive(&dir, io::UserRWX) {
+        Ok(()) => {}
+        Err(e) => {
+            tcx.sess.fatal(format!("failed to create the incremental directory `{}`: {}",
+                                   dir.display(), e).as_slice());
+        }
+    }
+
+    let mut collector = FingerprintCollector {
+        tcx: tcx,
+        keys: NodeMap::new(),
+        sources: HashMap::new(),
+    };
+    collector.add_crate_context();
+    collector.add_attrs(krate.attrs.as_slice());
+    visit::walk_crate(&mut collector, krate, ());
+
+    let graph = &tcx.dep_graph;
+    graph.enabled.set(true);
+    *graph.keys.borrow_mut() = collector.keys;
+    *graph.fingerprints.borrow_mut() = collector.sources.move_iter().map(|(node, src)| {
+        let mut hasher = Sha256::new();
+        hasher.input_str(src.as_slice());
+        (node, hasher.result_str())
+    }).collect();
+
+    let path = dir.join("dep-graph.bin");
+    if !path.exists() {
+        return;
+    }
+    match read_graph(&path) {
+        Ok(previous) => {
+            *graph.previous_fingerprints.borrow_mut() =
+                previous.fingerprints.move_iter().collect();
+            *graph.previous_edges.borrow_mut() = previous.edges.move_iter().collect();
+        }
+        Err(msg) => {
+            tcx.sess.warn(format!("ignoring the dependency graph `{}`: {}",
+                                  path.display(), msg).as_slice());
+        }
+    }
+}
+
+fn read_graph(path: &Path) -> Result<SerializedDepGraph, String> {
+    let data = try!(File::open(path).read_to_end().map_err(|e| e.to_string()));
+    let doc = match reader::maybe_get_doc(rbml::Doc::new(data.as_slice()), tag_dep_graph) {
+        Some(doc) => doc,
+        None => return Err("it's not a dependency graph".to_string()),
+    };
+    let mut decoder = reader::Decoder::new(doc);
+    Decodable::decode(&mut decoder).map_err(|e| format!("{}", e))
+}
+
+/// Writes out the dependency graph of this compilation, for the next one to
+/// load.
+pub fn save(sess: &Session, graph: &SerializedDepGraph) {
+    let dir = Path::new(sess.opts.cg.incremental.get_ref().as_slice());
+    let path = dir.join("dep-graph.bin");
+    let mut wr = SeekableMemWriter::new();
+    {
+        // Writing to memory can't fail
+        let mut rbml_w = writer::Encoder::new(&mut wr);
+        rbml_w.start_tag(tag_dep_graph).unwrap();
+        graph.encode(&mut rbml_w).unwrap();
+        rbml_w.end_tag().unwrap();
+    }
+    match File::create(&path).write(wr.get_ref()) {
+        Ok(()) => {}
+        Err(e) => {
+            sess.err(format!("failed to write the dependency graph `{}`: {}",
+                             path.display(), e).as_slice());
+        }
+    }
+}
+
+/// Removes the dependency graph of the last compilation, before the results
+/// it describes are overwritten. If this compilation fails, the next one
+/// starts afresh.
+pub fn invalidate(sess: &Session) -> Result<(), IoError> {
+    let dir = Path::new(sess.opts.cg.incremental.get_ref().as_slice());
+    let path = dir.join("dep-graph.bin");
+    if path.exists() { fs::unlink(&path) } else { Ok(()) }
+}
+
+/// Collects the source of each input, to be hashed into its fingerprint.
+/// Items sharing a path share their inputs, too.
+struct FingerprintCollector<'a> {
+    tcx: &'a ty::ctxt,
+    keys: NodeMap<String>,
+    sources: HashMap<DepNode, String>,
+}
+
+impl<'a> FingerprintCollector<'a> {
+    fn add(&mut self, node: DepNode, src: &str) {
+        let source = self.sources.find_or_insert_with(node, |_| String::new());
+        source.push_str(src);
+        source.push_char('\n');
+    }
+
+    fn add_item(&mut self, id: ast::NodeId, span: Span, src: &str,
+                signature: Option<String>) {
+        let key = self.tcx.map.path_to_string(id);
+        // A new or removed item can change what names resolve to elsewhere
+        self.add(Krate, format!("item {}", key).as_slice());
+        self.add(Hir(key.clone()), src);
+        // Debuginfo records where each line of code is, which the
+        // pretty-printed source doesn't tell
+        let tcx = self.tcx;
+        if tcx.sess.opts.debuginfo != NoDebugInfo {
+            let codemap = tcx.sess.codemap();
+            let snippet = codemap.span_to_snippet(span).unwrap_or(String::new());
+            self.add(Hir(key.clone()), codemap.span_to_string(span).as_slice());
+            self.add(Hir(key.clone()), snippet.as_slice());
+        }
+        match signature {
+            Some(signature) => self.add(Signature(key.clone()), signature.as_slice()),
+            None => {}
+        }
+        self.keys.insert(id, key);
+    }
+
+    fn add_method(&mut self, m: &ast::Method) {
+        let decl = match m.node {
+            ast::MethDecl(ident, ref generics, abi, ref explicit_self, fn_style,
+                          ref decl, _, _) => {
+                format!("{} {}", abi,
+                        pprust::fun_to_string(&**decl, fn_style, ident,
+                                              Some(explicit_self.node), generics))
+            }
+            ast::MethMac(..) => {
+                self.tcx.sess.span_bug(m.span, "macro in method position after expansion")
+            }
+        };
+        let signature = signature(m.attrs.as_slice(), decl.as_slice());
+        self.add_item(m.id, m.span, pprust::method_to_string(m).as_slice(),
+                      Some(signature));
+    }
+
+    fn add_attrs(&mut self, attrs: &[ast::Attribute]) {
+        for attr in attrs.iter() {
+            self.add(Krate, pprust::attribute_to_string(attr).as_slice());
+        }
+    }
+
+    /// Adds what's outside of the crate to `Krate`: the compiler, the
+    /// command line, which includes the codegen options and the target, and
+    /// the crates linked to.
+    fn add_crate_context(&mut self) {
+        let version = option_env!("CFG_VERSION").unwrap_or("unknown version");
+        self.add(Krate, version);
+        for arg in os::args().iter() {
+            self.add(Krate, arg.as_slice());
+        }
+        let mut crates = Vec::new();
+        self.tcx.sess.cstore.iter_crate_data(|cnum, data| {
+            crates.push(format!("extern crate {} {}", data.name,
+                                self.tcx.sess.cstore.get_crate_hash(cnum)));
+        });
+        // The crates are numbered in the order they're loaded in
+        crates.sort();
+        for krate in crates.iter() {
+            self.add(Krate, krate.as_slice());
+        }
+    }
+}
+
+fn signature(attrs: &[ast::Attribute], decl: &str) -> String {
+    let mut signature = String::new();
+    for attr in attrs.iter() {
+        signature.push_str(pprust::attribute_to_string(attr).as_slice());
+        signature.push_char('\n');
+    }
+    signature.push_str(decl);
+    signature
+}
+
+impl<'a> Visitor<()> for FingerprintCollector<'a> {
+    fn visit_item(&mut self, item: &ast::Item, _: ()) {
+        match item.node {
+            ast::ItemFn(ref decl, fn_style, abi, ref generics, _) => {
+                let decl = format!("{} {}", abi,
+                                   pprust::fun_to_string(&**decl, fn_style, item.ident,
+                                                         None, generics));
+                let signature = signature(item.attrs.as_slice(), decl.as_slice());
+                self.add_item(item.id, item.span, pprust::item_to_string(item).as_slice(),
+                              Some(signature));
+            }
+            ast::ItemStatic(..) => {
+                self.add_item(item.id, item.span, pprust::item_to_string(item).as_slice(),
+                              None);
+            }
+            ast::ItemImpl(ref generics, ref trait_ref, ref ty, ref impl_items) => {
+                let trait_name = match *trait_ref {
+                    Some(ref trait_ref) => pprust::path_to_string(&trait_ref.path),
+                    None => String::new(),
+                };
+                self.add(Krate, format!("impl{} {} for {}",
+                                        pprust::generics_to_string(generics),
+                                        trait_name,
+                                        pprust::ty_to_string(&**ty)).as_slice());
+                self.add_attrs(item.attrs.as_slice());
+                for impl_item in impl_items.iter() {
+                    match *impl_item {
+                        ast::MethodImplItem(m) => self.add_method(&*m),
+                    }
+                }
+            }
+            ast::ItemMod(..) => {
+                self.add(Krate, format!("mod {}",
+                                        self.tcx.map.path_to_string(item.id)).as_slice());
+                self.add_attrs(item.attrs.as_slice());
+                visit::walk_item(self, item, ());
+            }
+            _ => self.add(Krate, pprust::item_to_string(item).as_slice()),
+        }
+    }
+
+    fn visit_view_item(&mut self, view_item: &ast::ViewItem, _: ()) {
+        let src = pprust::to_string(|s| s.print_view_item(view_item));
+        self.add(Krate, src.as_slice());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DepGraph, DepNode, Hir, Signature, Krate, BorrowCheck, TransUnit};
+
+    fn new_graph(previous: &[(DepNode, &str)], current: &[(DepNode, &str)],
+                 previous_edges: Vec<(DepNode, Vec<DepNode>)>) -> DepGraph {
+        let graph = DepGraph::new();
+        graph.enabled.set(true);
+        *graph.previous_fingerprints.borrow_mut() = previous.iter().map(|&(ref node, f)| {
+            (node.clone(), f.to_string())
+        }).collect();
+        *graph.fingerprints.borrow_mut() = current.iter().map(|&(ref node, f)| {
+            (node.clone(), f.to_string())
+        }).collect();
+        *graph.previous_edges.borrow_mut() = previous_edges.move_iter().collect();
+        graph
+    }
+
+    fn hir(key: &str) -> DepNode { Hir(key.to_string()) }
+
+    fn borrowck(key: &str) -> DepNode { BorrowCheck(key.to_string()) }
+
+    #[test]
+    fn test_inputs_unchanged() {
+        let graph = new_graph([(Krate, "k"), (hir("f"), "f"), (hir("g"), "g")],
+                              [(Krate, "k"), (hir("f"), "f2"), (hir("h"), "h")],
+                              vec![]);
+        assert!(graph.inputs_unchanged([]));
+        assert!(graph.inputs_unchanged([Krate]));
+        assert!(!graph.inputs_unchanged([Krate, hir("f")]));
+        // Inputs which were added or removed count as changed
+        assert!(!graph.inputs_unchanged([hir("g")]));
+        assert!(!graph.inputs_unchanged([hir("h")]));
+    }
+
+    #[test]
+    fn test_try_reuse() {
+        let fingerprints = [(Krate, "k"), (hir("f"), "f"), (hir("g"), "g")];
+        let graph = new_graph(&fingerprints, &fingerprints,
+                              vec![(borrowck("f"), vec![Krate, hir("f")])]);
+        assert!(graph.try_reuse(&borrowck("f")));
+        // The reads are kept for the next compilation
+        let edges = graph.edges.borrow();
+        let reads = edges.find(&borrowck("f")).unwrap();
+        assert_eq!(reads.len(), 2);
+        assert!(reads.contains(&Krate) && reads.contains(&hir("f")));
+        drop(edges);
+        // Nothing is known about results the last compilation didn't compute
+        assert!(!graph.try_reuse(&borrowck("g")));
+    }
+
+    #[test]
+    fn test_try_reuse_changed() {
+        let edges = vec![(borrowck("f"), vec![Krate, hir("f")]),
+                         (borrowck("g"), vec![Krate, hir("g")])];
+        let graph = new_graph([(Krate, "k"), (hir("f"), "f"), (hir("g"), "g")],
+                              [(Krate, "k"), (hir("f"), "f2"), (hir("g"), "g")],
+                              edges.clone());
+        assert!(!graph.try_reuse(&borrowck("f")));
+        assert!(graph.edges.borrow().find(&borrowck("f")).is_none());
+        assert!(graph.try_reuse(&borrowck("g")));
+
+        // A change to `Krate` invalidates everything
+        let graph = new_graph([(Krate, "k"), (hir("f"), "f"), (hir("g"), "g")],
+                              [(Krate, "k2"), (hir("f"), "f"), (hir("g"), "g")],
+                              edges);
+        assert!(!graph.try_reuse(&borrowck("f")));
+        assert!(!graph.try_reuse(&borrowck("g")));
+    }
+
+    #[test]
+    fn test_try_reuse_disabled() {
+        let fingerprints = [(Krate, "k"), (hir("f"), "f")];
+        let graph = new_graph(&fingerprints, &fingerprints,
+                              vec![(borrowck("f"), vec![Krate, hir("f")])]);
+        graph.enabled.set(false);
+        assert!(!graph.try_reuse(&borrowck("f")));
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        let fingerprints = [(Krate, "k"), (hir("f"), "f"),
+                            (Signature("g".to_string()), "g")];
+        let graph = new_graph(&fingerprints, &fingerprints,
+                              vec![(TransUnit(0), vec![Krate, hir("f")]),
+                                   (TransUnit(1), vec![Krate, hir("f")]),
+                                   (TransUnit(2), vec![Krate, hir("f")])]);
+        graph.with_task(TransUnit(0), || graph.read(hir("f")));
+        assert!(graph.is_unchanged(&TransUnit(0)));
+        // Reading more or fewer inputs than before means the unit changed
+        graph.with_task(TransUnit(1), || {
+            graph.read(hir("f"));
+            graph.read(Signature("g".to_string()));
+        });
+        assert!(!graph.is_unchanged(&TransUnit(1)));
+        graph.with_task(TransUnit(2), || {});
+        assert!(!graph.is_unchanged(&TransUnit(2)));
+        // As does not having been computed in one of the compilations
+        assert!(!graph.is_unchanged(&TransUnit(3)));
+    }
+
+    #[test]
+    fn test_is_unchanged_input_changed() {
+        let graph = new_graph([(Krate, "k"), (hir("f"), "f")],
+                              [(Krate, "k"), (hir("f"), "f2")],
+                              vec![(TransUnit(0), vec![Krate, hir("f")]),
+                                   (TransUnit(1), vec![Krate])]);
+        graph.with_task(TransUnit(0), || graph.read(hir("f")));
+        graph.with_task(TransUnit(1), || {});
+        assert!(!graph.is_unchanged(&TransUnit(0)));
+        assert!(graph.is_unchanged(&TransUnit(1)));
+    }
+}
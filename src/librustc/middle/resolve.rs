@@ -51,6 +51,7 @@ use syntax::parse::token::special_names;
 use syntax::parse::token::special_idents;
 use syntax::parse::token;
 use syntax::codemap::{Span, DUMMY_SP, Pos};
+use syntax::codemap;
 use syntax::owned_slice::OwnedSlice;
 use syntax::visit;
 use syntax::visit::Visitor;
@@ -4990,11 +4991,24 @@ impl<'a> Resolver<'a> {
             let def = self.resolve_module_relative_path(path, namespace);
             match (def, unqualified_def) {
                 (Some((d, _)), Some((ud, _))) if d == ud => {
-                    self.session
-                        .add_lint(lint::builtin::UNNECESSARY_QUALIFICATION,
-                                  id,
-                                  path.span,
-                                  "unnecessary qualification".to_string());
+                    let msg = "unnecessary qualification".to_string();
+                    let has_params = path.segments.iter().any(|s| {
+                        !s.lifetimes.is_empty() || !s.types.is_empty()
+                    });
+                    if has_params {
+                        self.session.add_lint(lint::builtin::UNNECESSARY_QUALIFICATION,
+                                              id, path.span, msg);
+                    } else {
+                        let name = token::get_ident(path.segments.last().unwrap().identifier);
+                        self.session.add_lint_with_suggestion(
+                            lint::builtin::UNNECESSARY_QUALIFICATION,
+                            id, path.span, msg, lint::Suggestion {
+                                span: path.span,
+                                msg: "remove the qualification".to_string(),
+                                replacement: name.get().to_string(),
+                                applicable: true,
+                            });
+                    }
                 }
                 _ => ()
             }
@@ -5865,21 +5879,33 @@ impl<'a> Resolver<'a> {
         match vi.node {
             ViewItemExternCrate(..) => {} // ignore
             ViewItemUse(ref p) => {
+                // Removing the whole `use` takes its attributes along
+                let vi_span = match vi.attrs.as_slice().head() {
+                    Some(attr) => Span { lo: attr.span.lo, ..vi.span },
+                    None => vi.span,
+                };
                 match p.node {
-                    ViewPathSimple(_, _, id) => self.finalize_import(id, p.span),
+                    ViewPathSimple(_, _, id) => self.finalize_import(id, p.span, vi_span),
                     ViewPathList(_, ref list, _) => {
-                        for i in list.iter() {
-                            self.finalize_import(i.node.id(), i.span);
+                        let all_unused = list.iter().all(|i| self.is_unused_import(i.node.id()));
+                        for (n, i) in list.iter().enumerate() {
+                            // Removing an import from the list takes a comma
+                            // next to it along as well
+                            let removal = if all_unused {
+                                vi_span
+                            } else if n + 1 < list.len() {
+                                codemap::mk_sp(i.span.lo, list[n + 1].span.lo)
+                            } else if n > 0 {
+                                codemap::mk_sp(list[n - 1].span.hi, i.span.hi)
+                            } else {
+                                vi_span
+                            };
+                            self.finalize_import(i.node.id(), i.span, removal);
                         }
                     },
                     ViewPathGlob(_, id) => {
-                        if !self.used_imports.contains(&(id, TypeNS)) &&
-                           !self.used_imports.contains(&(id, ValueNS)) {
-                            self.session
-                                .add_lint(lint::builtin::UNUSED_IMPORTS,
-                                          id,
-                                          p.span,
-                                          "unused import".to_string());
+                        if self.is_unused_import(id) {
+                            self.add_unused_import_lint(id, p.span, vi_span);
                         }
                     },
                 }
@@ -5894,16 +5920,14 @@ impl<'a> Resolver<'a> {
     // which are used. In particular, this means that if an import could name either a
     // public or private item, we will check the correct thing, dependent on how the import
     // is used.
-    fn finalize_import(&mut self, id: NodeId, span: Span) {
+    //
+    // `removal` is the code to remove along with the import if it is unused.
+    fn finalize_import(&mut self, id: NodeId, span: Span, removal: Span) {
         debug!("finalizing import uses for {}",
                self.session.codemap().span_to_snippet(span));
 
-        if !self.used_imports.contains(&(id, TypeNS)) &&
-           !self.used_imports.contains(&(id, ValueNS)) {
-            self.session.add_lint(lint::builtin::UNUSED_IMPORTS,
-                                  id,
-                                  span,
-                                  "unused import".to_string());
+        if self.is_unused_import(id) {
+            self.add_unused_import_lint(id, span, removal);
         }
 
         let (v_priv, t_priv) = match self.last_private.find(&id) {
@@ -5944,6 +5968,24 @@ impl<'a> Resolver<'a> {
                                                 type_used: t_used});
     }
 
+    fn is_unused_import(&self, id: NodeId) -> bool {
+        !self.used_imports.contains(&(id, TypeNS)) &&
+        !self.used_imports.contains(&(id, ValueNS))
+    }
+
+    fn add_unused_import_lint(&self, id: NodeId, span: Span, removal: Span) {
+        self.session.add_lint_with_suggestion(lint::builtin::UNUSED_IMPORTS,
+                                              id,
+                                              span,
+                                              "unused import".to_string(),
+                                              lint::Suggestion {
+            span: removal,
+            msg: "remove the unused import".to_string(),
+            replacement: String::new(),
+            applicable: true,
+        });
+    }
+
     //
     // Diagnostics
     //
@@ -29,13 +29,14 @@ use back::link::{mangle_exported_name};
 use back::{link, abi};
 use driver::config;
 use driver::config::{NoDebugInfo, FullDebugInfo};
-use driver::driver::{CrateAnalysis, CrateTranslation};
+use driver::driver::{CrateAnalysis, CrateTranslation, ModuleTranslation};
 use driver::session::Session;
 use lint;
 use llvm::{BasicBlockRef, ModuleRef, ValueRef, Vector, get_param};
 use llvm;
 use metadata::{csearch, encoder, loader};
 use middle::astencode;
+use middle::dep_graph;
 use middle::lang_items::{LangItem, ExchangeMallocFnLangItem, StartFnLangItem};
 use middle::subst;
 use middle::weak_lang_items;
@@ -82,8 +83,9 @@ use util::nodemap::NodeMap;
 
 use arena::TypedArena;
 use libc::{c_uint, uint64_t};
-use std::c_str::ToCStr;
+use std::c_str::{CString, ToCStr};
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::{i8, i16, i32, i64};
 use syntax::abi::{X86, X86_64, Arm, Mips, Mipsel, Rust, RustCall};
@@ -1915,6 +1917,7 @@ pub fn trans_fn(ccx: &CrateContext,
     let _s = StatRecorder::new(ccx, ccx.tcx.map.path_to_string(id).to_string());
     debug!("trans_fn(param_substs={})", param_substs.repr(ccx.tcx()));
     let _icx = push_ctxt("trans_fn");
+    ccx.tcx.dep_graph.read_hir(&ccx.tcx.map, id);
     let fn_ty = ty::node_id_to_type(ccx.tcx(), id);
     let arg_types = ty::ty_fn_args(fn_ty);
     let output_type = ty::ty_fn_ret(fn_ty);
@@ -1934,6 +1937,32 @@ pub fn trans_fn(ccx: &CrateContext,
                   |bcx, _| bcx);
 }
 
+/// Gives the codegen units other than the current one a copy of the
+/// function `id` to inline if it's `#[inline]`, which they don't emit code
+/// for.
+pub fn trans_inline_copies(ccx: &CrateContext,
+                           decl: &ast::FnDecl,
+                           body: &ast::Block,
+                           id: ast::NodeId,
+                           attrs: &[ast::Attribute]) {
+    let inline = match attr::find_inline_attr(attrs) {
+        attr::InlineHint | attr::InlineAlways => true,
+        attr::InlineNone | attr::InlineNever => false,
+    };
+    if !inline || ccx.codegen_units() == 1 {
+        return
+    }
+    let unit = ccx.current_unit();
+    for other in range(0, ccx.codegen_units()) {
+        if other == unit { continue }
+        ccx.with_unit(other, || {
+            let llfn = get_item_val(ccx, id);
+            trans_fn(ccx, decl, body, llfn, &param_substs::empty(), id, attrs);
+            llvm::SetLinkage(llfn, llvm::AvailableExternallyLinkage);
+        });
+    }
+}
+
 pub fn trans_enum_variant(ccx: &CrateContext,
                           _enum_id: ast::NodeId,
                           variant: &ast::Variant,
@@ -2125,6 +2154,7 @@ impl<'a> Visitor<()> for TransItemVisitor<'a> {
 
 pub fn trans_item(ccx: &CrateContext, item: &ast::Item) {
     let _icx = push_ctxt("trans_item");
+    ccx.tcx.dep_graph.read_hir(&ccx.tcx.map, item.id);
     match item.node {
       ast::ItemFn(ref decl, _fn_style, abi, ref generics, ref body) => {
         if !generics.is_type_parameterized() {
@@ -2146,6 +2176,12 @@ pub fn trans_item(ccx: &CrateContext, item: &ast::Item) {
                          &param_substs::empty(),
                          item.id,
                          item.attrs.as_slice());
+
+                trans_inline_copies(ccx,
+                                    &**decl,
+                                    &**body,
+                                    item.id,
+                                    item.attrs.as_slice());
             }
         }
 
@@ -2162,7 +2198,7 @@ pub fn trans_item(ccx: &CrateContext, item: &ast::Item) {
                          item.id);
       }
       ast::ItemMod(ref m) => {
-        trans_mod(ccx, m);
+        ccx.with_unit(ccx.unit_for_module(item.id), || trans_mod(ccx, m));
       }
       ast::ItemEnum(ref enum_definition, _) => {
         enum_variant_size_lint(ccx, enum_definition, item.span, item.id);
@@ -2216,13 +2252,27 @@ pub fn trans_mod(ccx: &CrateContext, m: &ast::Mod) {
     }
 }
 
+/// Gives the LLVM value of an item internal linkage if it isn't reachable from
+/// other crates.
+///
+/// When the crate is split into several codegen units, the other units may
+/// still refer to the item, so it's left external until `internalize_symbols`
+/// has seen all of them. Items inlined from other crates are always private
+/// to the unit which translated them.
+pub fn update_linkage(ccx: &CrateContext, llval: ValueRef, id: ast::NodeId) {
+    if ccx.reachable.contains(&id) {
+        return
+    }
+    if ccx.codegen_units() == 1 || ccx.external_srcs.borrow().contains_key(&id) {
+        llvm::SetLinkage(llval, llvm::InternalLinkage);
+    }
+}
+
 fn finish_register_fn(ccx: &CrateContext, sp: Span, sym: String, node_id: ast::NodeId,
                       llfn: ValueRef) {
     ccx.item_symbols.borrow_mut().insert(node_id, sym);
 
-    if !ccx.reachable.contains(&node_id) {
-        llvm::SetLinkage(llfn, llvm::InternalLinkage);
-    }
+    update_linkage(ccx, llfn, node_id);
 
     // The stack exhaustion lang item shouldn't have a split stack because
     // otherwise it would continue to be exhausted (bad), and both it and the
@@ -2237,7 +2287,8 @@ fn finish_register_fn(ccx: &CrateContext, sp: Span, sym: String, node_id: ast::N
     }
 
 
-    if is_entry_fn(ccx.sess(), node_id) {
+    if is_entry_fn(ccx.sess(), node_id) && !ccx.entry_wrapper_created.get() {
+        ccx.entry_wrapper_created.set(true);
         create_entry_wrapper(ccx, sp, llfn);
     }
 }
@@ -2576,6 +2627,7 @@ fn exported_name(ccx: &CrateContext, id: ast::NodeId,
 
 pub fn get_item_val(ccx: &CrateContext, id: ast::NodeId) -> ValueRef {
     debug!("get_item_val(id=`{:?}`)", id);
+    ccx.tcx.dep_graph.read_use(&ccx.tcx.map, id);
 
     match ccx.item_vals.borrow().find_copy(&id) {
         Some(v) => return v,
@@ -2619,9 +2671,7 @@ pub fn get_item_val(ccx: &CrateContext, id: ast::NodeId) -> ValueRef {
                             llvm::LLVMAddGlobal(ccx.llmod, llty, buf)
                         });
 
-                        if !ccx.reachable.contains(&id) {
-                            llvm::SetLinkage(g, llvm::InternalLinkage);
-                        }
+                        update_linkage(ccx, g, id);
 
                         // Apply the `unnamed_addr` attribute if
                         // requested
@@ -2789,8 +2839,8 @@ pub fn get_item_val(ccx: &CrateContext, id: ast::NodeId) -> ValueRef {
     // foreign items (extern fns and extern statics) don't have internal
     // linkage b/c that doesn't quite make sense. Otherwise items can
     // have internal linkage if they're not reachable.
-    if !foreign && !ccx.reachable.contains(&id) {
-        llvm::SetLinkage(val, llvm::InternalLinkage);
+    if !foreign {
+        update_linkage(ccx, val, id);
     }
 
     ccx.item_vals.borrow_mut().insert(id, val);
@@ -2893,7 +2943,8 @@ pub fn trans_crate(krate: ast::Crate,
         }
     }
 
-    let link_meta = link::build_link_meta(&tcx.sess, &krate, name);
+    let link_meta = link::build_link_meta(&tcx.sess, &krate, name,
+                                          tcx.dep_graph.krate_fingerprint());
 
     // Append ".rs" to crate name as LLVM module identifier.
     //
@@ -2903,11 +2954,22 @@ pub fn trans_crate(krate: ast::Crate,
     // crashes if the module identifier is same as other symbols
     // such as a function name in the module.
     // 1. http://llvm.org/bugs/show_bug.cgi?id=11479
-    let mut llmod_id = link_meta.crate_name.clone();
-    llmod_id.push_str(".rs");
+    //
+    // The modules of the codegen units after the first are numbered, like
+    // "foo.1.rs".
+    let llmod_ids = Vec::from_fn(tcx.sess.opts.cg.codegen_units, |unit| {
+        let mut llmod_id = link_meta.crate_name.clone();
+        if unit > 0 {
+            llmod_id.push_str(format!(".{}", unit).as_slice());
+        }
+        llmod_id.push_str(".rs");
+        llmod_id
+    });
+    let crate_name = link_meta.crate_name.clone();
 
-    let ccx = CrateContext::new(llmod_id.as_slice(), tcx, exp_map2,
-                                Sha256::new(), link_meta, reachable);
+    let ccx = CrateContext::new(llmod_ids.as_slice(), crate_name.as_slice(),
+                                tcx, exp_map2, Sha256::new(), link_meta,
+                                reachable);
 
     // First, verify intrinsics.
     intrinsic::check_intrinsics(&ccx);
@@ -2915,12 +2977,16 @@ pub fn trans_crate(krate: ast::Crate,
     // Next, translate the module.
     {
         let _icx = push_ctxt("text");
-        trans_mod(&ccx, &krate.module);
+        ccx.with_unit(0, || trans_mod(&ccx, &krate.module));
     }
 
-    glue::emit_tydescs(&ccx);
-    if ccx.sess().opts.debuginfo != NoDebugInfo {
-        debuginfo::finalize(&ccx);
+    for unit in range(0, ccx.codegen_units()) {
+        ccx.with_unit(unit, || {
+            glue::emit_tydescs(&ccx);
+            if ccx.sess().opts.debuginfo != NoDebugInfo {
+                debuginfo::finalize(&ccx);
+            }
+        });
     }
 
     // Translate the metadata.
@@ -2954,9 +3020,14 @@ pub fn trans_crate(krate: ast::Crate,
         }
     }
 
-    let llcx = ccx.llcx;
     let link_meta = ccx.link_meta.clone();
-    let llmod = ccx.llmod;
+    let modules: Vec<ModuleTranslation> = ccx.locals.iter().enumerate().map(|(unit, local)| {
+        ModuleTranslation {
+            llcx: local.llcx,
+            llmod: local.llmod,
+            reuse_object: ccx.tcx.dep_graph.is_unchanged(&dep_graph::TransUnit(unit)),
+        }
+    }).collect();
 
     let mut reachable: Vec<String> = ccx.reachable.iter().filter_map(|id| {
         ccx.item_symbols.borrow().find(id).map(|s| s.to_string())
@@ -2986,18 +3057,81 @@ pub fn trans_crate(krate: ast::Crate,
     // referenced from rt/rust_try.ll
     reachable.push("rust_eh_personality_catch".to_string());
 
+    // With `-C incremental` the symbols are left alone, as the object files
+    // kept for the units which haven't changed rely on each other's symbols.
+    if ccx.codegen_units() > 1 && !ccx.tcx.dep_graph.is_enabled() {
+        let reachable: HashSet<String> = reachable.iter().map(|s| s.clone()).collect();
+        internalize_symbols(&ccx, &reachable);
+    }
+
     let metadata_module = ccx.metadata_llmod;
     let formats = ccx.tcx.dependency_formats.borrow().clone();
     let no_builtins = attr::contains_name(krate.attrs.as_slice(), "no_builtins");
+    let dep_graph = ccx.tcx.dep_graph.serialize();
 
     (ccx.tcx, CrateTranslation {
-        context: llcx,
-        module: llmod,
+        modules: modules,
         link: link_meta,
         metadata_module: metadata_module,
         metadata: metadata,
         reachable: reachable,
         crate_formats: formats,
         no_builtins: no_builtins,
+        dep_graph: dep_graph,
     })
 }
+
+/// Calls `f` with each of the global variables and functions of `llmod`.
+unsafe fn each_global(llmod: ModuleRef, f: |ValueRef|) {
+    let mut val = llvm::LLVMGetFirstGlobal(llmod);
+    while !val.is_null() {
+        f(val);
+        val = llvm::LLVMGetNextGlobal(val);
+    }
+    let mut val = llvm::LLVMGetFirstFunction(llmod);
+    while !val.is_null() {
+        f(val);
+        val = llvm::LLVMGetNextFunction(val);
+    }
+}
+
+/// Gives internal linkage to the definitions of the codegen units which are
+/// neither reachable from other crates nor used by another unit.
+fn internalize_symbols(ccx: &CrateContext, reachable: &HashSet<String>) {
+    unsafe {
+        // Collect the symbols which the units refer to without defining them.
+        // A definition which is only available externally relies on the one
+        // from another unit, so it counts as a reference too.
+        let mut referenced = HashSet::new();
+        for local in ccx.locals.iter() {
+            each_global(local.llmod, |val| {
+                let linkage = llvm::LLVMGetLinkage(val);
+                let is_external = linkage == llvm::ExternalLinkage as c_uint;
+                let is_decl = llvm::LLVMIsDeclaration(val) != 0;
+                if (is_external && is_decl) ||
+                   linkage == llvm::AvailableExternallyLinkage as c_uint {
+                    referenced.insert(llvm_symbol_name(val));
+                }
+            });
+        }
+
+        for local in ccx.locals.iter() {
+            each_global(local.llmod, |val| {
+                let linkage = llvm::LLVMGetLinkage(val);
+                if linkage != llvm::ExternalLinkage as c_uint ||
+                   llvm::LLVMIsDeclaration(val) != 0 {
+                    return
+                }
+                let name = llvm_symbol_name(val);
+                if !referenced.contains(&name) && !reachable.contains(&name) {
+                    llvm::SetLinkage(val, llvm::InternalLinkage);
+                }
+            });
+        }
+    }
+}
+
+unsafe fn llvm_symbol_name(val: ValueRef) -> String {
+    let name = CString::new(llvm::LLVMGetValueName(val), false);
+    String::from_utf8_lossy(name.as_bytes_no_nul()).to_string()
+}
@@ -44,6 +44,7 @@ impl<'a> Builder<'a> {
     }
 
     pub fn count_insn(&self, category: &str) {
+        self.ccx.n_llvm_insns.set(self.ccx.n_llvm_insns.get() + 1);
         if self.ccx.sess().trans_stats() {
             self.ccx.stats.n_llvm_insns.set(self.ccx
                                                 .stats
@@ -184,9 +184,12 @@ pub fn get_const_val(cx: &CrateContext,
             def_id = inline::maybe_instantiate_inline(cx, def_id);
         }
 
+        // Declaring the static is enough to translate its value. It's only
+        // defined by the codegen unit which translates its item, as every
+        // other unit refers to that definition.
         match cx.tcx.map.expect_item(def_id.node).node {
             ast::ItemStatic(_, ast::MutImmutable, _) => {
-                trans_const(cx, ast::MutImmutable, def_id.node);
+                base::get_item_val(cx, def_id.node);
             }
             _ => {}
         }
@@ -15,6 +15,7 @@ use llvm::{ContextRef, ModuleRef, ValueRef};
 use llvm::{TargetData};
 use llvm::mk_target_data;
 use metadata::common::LinkMeta;
+use middle::dep_graph;
 use middle::resolve;
 use middle::trans::adt;
 use middle::trans::base;
@@ -29,6 +30,7 @@ use util::nodemap::{NodeMap, NodeSet, DefIdMap};
 
 use std::cell::{Cell, RefCell};
 use std::c_str::ToCStr;
+use std::hash;
 use std::ptr;
 use std::rc::Rc;
 use std::collections::{HashMap, HashSet};
@@ -51,18 +53,16 @@ pub struct Stats {
     pub fn_stats: RefCell<Vec<(String, uint, uint)> >,
 }
 
-pub struct CrateContext {
+/// The parts of the crate context which hold LLVM values and types, of which
+/// there is one for each codegen unit. Each unit is translated into an LLVM
+/// module of its own, in a context of its own, so that the modules can be
+/// optimized in parallel.
+pub struct LocalCrateContext {
     pub llmod: ModuleRef,
     pub llcx: ContextRef,
-    pub metadata_llmod: ModuleRef,
-    pub td: TargetData,
     pub tn: TypeNames,
     pub externs: RefCell<ExternMap>,
     pub item_vals: RefCell<NodeMap<ValueRef>>,
-    pub exp_map2: resolve::ExportMap2,
-    pub reachable: NodeSet,
-    pub item_symbols: RefCell<NodeMap<String>>,
-    pub link_meta: LinkMeta,
     pub drop_glues: RefCell<HashMap<ty::t, ValueRef>>,
     pub tydescs: RefCell<HashMap<ty::t, Rc<tydesc_info>>>,
     /// Set when running emit_tydescs to enforce that no more tydescs are
@@ -73,13 +73,8 @@ pub struct CrateContext {
     /// Backwards version of the `external` map (inlined items to where they
     /// came from)
     pub external_srcs: RefCell<NodeMap<ast::DefId>>,
-    /// A set of static items which cannot be inlined into other crates. This
-    /// will prevent in IIItem() structures from being encoded into the metadata
-    /// that is generated
-    pub non_inlineable_statics: RefCell<NodeSet>,
     /// Cache instances of monomorphized functions
     pub monomorphized: RefCell<HashMap<MonoId, ValueRef>>,
-    pub monomorphizing: RefCell<DefIdMap<uint>>,
     /// Cache generated vtables
     pub vtables: RefCell<HashMap<(ty::t, MonoId), ValueRef>>,
     /// Cache of constant strings,
@@ -101,19 +96,12 @@ pub struct CrateContext {
     /// Cache of external const values
     pub extern_const_values: RefCell<DefIdMap<ValueRef>>,
 
-    pub impl_method_cache: RefCell<HashMap<(ast::DefId, ast::Name), ast::DefId>>,
-
     /// Cache of closure wrappers for bare fn's.
     pub closure_bare_wrapper_cache: RefCell<HashMap<ValueRef, ValueRef>>,
 
     pub lltypes: RefCell<HashMap<ty::t, Type>>,
     pub llsizingtypes: RefCell<HashMap<ty::t, Type>>,
-    pub adt_reprs: RefCell<HashMap<ty::t, Rc<adt::Repr>>>,
-    pub symbol_hasher: RefCell<Sha256>,
-    pub type_hashcodes: RefCell<HashMap<ty::t, String>>,
     pub all_llvm_symbols: RefCell<HashSet<String>>,
-    pub tcx: ty::ctxt,
-    pub stats: Stats,
     pub int_type: Type,
     pub opaque_vec_type: Type,
     pub builder: BuilderRef_res,
@@ -125,127 +113,238 @@ pub struct CrateContext {
 
     pub eh_personality: RefCell<Option<ValueRef>>,
 
+    /// The number of LLVM instructions translated into this unit, which is
+    /// used to hand out modules to the least loaded unit.
+    pub n_llvm_insns: Cell<uint>,
+
     intrinsics: RefCell<HashMap<&'static str, ValueRef>>,
 }
 
-impl CrateContext {
-    pub fn new(name: &str,
-               tcx: ty::ctxt,
-               emap2: resolve::ExportMap2,
-               symbol_hasher: Sha256,
-               link_meta: LinkMeta,
-               reachable: NodeSet)
-               -> CrateContext {
+/// The crate context, which is shared by all of the codegen units and
+/// dereferences to the `LocalCrateContext` of the unit being translated.
+pub struct CrateContext {
+    pub locals: Vec<LocalCrateContext>,
+    /// The index of the unit which is being translated into.
+    current: Cell<uint>,
+    pub metadata_llmod: ModuleRef,
+    pub td: TargetData,
+    pub exp_map2: resolve::ExportMap2,
+    pub reachable: NodeSet,
+    pub item_symbols: RefCell<NodeMap<String>>,
+    pub link_meta: LinkMeta,
+    /// A set of static items which cannot be inlined into other crates. This
+    /// will prevent in IIItem() structures from being encoded into the metadata
+    /// that is generated
+    pub non_inlineable_statics: RefCell<NodeSet>,
+    pub monomorphizing: RefCell<DefIdMap<uint>>,
+    pub impl_method_cache: RefCell<HashMap<(ast::DefId, ast::Name), ast::DefId>>,
+    pub adt_reprs: RefCell<HashMap<ty::t, Rc<adt::Repr>>>,
+    pub symbol_hasher: RefCell<Sha256>,
+    pub type_hashcodes: RefCell<HashMap<ty::t, String>>,
+    /// Set once the `main` function calling the entry function has been
+    /// created, so that only one unit defines it.
+    pub entry_wrapper_created: Cell<bool>,
+    pub tcx: ty::ctxt,
+    pub stats: Stats,
+}
+
+impl LocalCrateContext {
+    fn new(name: &str, sess: &Session) -> LocalCrateContext {
         unsafe {
             let llcx = llvm::LLVMContextCreate();
             let llmod = name.with_c_str(|buf| {
                 llvm::LLVMModuleCreateWithNameInContext(buf, llcx)
             });
-            let metadata_llmod = format!("{}_metadata", name).with_c_str(|buf| {
-                llvm::LLVMModuleCreateWithNameInContext(buf, llcx)
-            });
-            tcx.sess
-               .targ_cfg
-               .target_strs
-               .data_layout
-               .as_slice()
-               .with_c_str(|buf| {
-                llvm::LLVMSetDataLayout(llmod, buf);
-                llvm::LLVMSetDataLayout(metadata_llmod, buf);
-            });
-            tcx.sess
-               .targ_cfg
-               .target_strs
-               .target_triple
-               .as_slice()
-               .with_c_str(|buf| {
-                llvm::LLVMRustSetNormalizedTarget(llmod, buf);
-                llvm::LLVMRustSetNormalizedTarget(metadata_llmod, buf);
-            });
-
-            let td = mk_target_data(tcx.sess
-                                       .targ_cfg
-                                       .target_strs
-                                       .data_layout
-                                       .as_slice());
+            set_target(sess, llmod);
 
-            let dbg_cx = if tcx.sess.opts.debuginfo != NoDebugInfo {
+            let dbg_cx = if sess.opts.debuginfo != NoDebugInfo {
                 Some(debuginfo::CrateDebugContext::new(llmod))
             } else {
                 None
             };
 
-            let mut ccx = CrateContext {
+            LocalCrateContext {
                 llmod: llmod,
                 llcx: llcx,
-                metadata_llmod: metadata_llmod,
-                td: td,
                 tn: TypeNames::new(),
                 externs: RefCell::new(HashMap::new()),
                 item_vals: RefCell::new(NodeMap::new()),
-                exp_map2: emap2,
-                reachable: reachable,
-                item_symbols: RefCell::new(NodeMap::new()),
-                link_meta: link_meta,
                 drop_glues: RefCell::new(HashMap::new()),
                 tydescs: RefCell::new(HashMap::new()),
                 finished_tydescs: Cell::new(false),
                 external: RefCell::new(DefIdMap::new()),
                 external_srcs: RefCell::new(NodeMap::new()),
-                non_inlineable_statics: RefCell::new(NodeSet::new()),
                 monomorphized: RefCell::new(HashMap::new()),
-                monomorphizing: RefCell::new(DefIdMap::new()),
                 vtables: RefCell::new(HashMap::new()),
                 const_cstr_cache: RefCell::new(HashMap::new()),
                 const_globals: RefCell::new(HashMap::new()),
                 const_values: RefCell::new(NodeMap::new()),
                 extern_const_values: RefCell::new(DefIdMap::new()),
-                impl_method_cache: RefCell::new(HashMap::new()),
                 closure_bare_wrapper_cache: RefCell::new(HashMap::new()),
                 lltypes: RefCell::new(HashMap::new()),
                 llsizingtypes: RefCell::new(HashMap::new()),
-                adt_reprs: RefCell::new(HashMap::new()),
-                symbol_hasher: RefCell::new(symbol_hasher),
-                type_hashcodes: RefCell::new(HashMap::new()),
                 all_llvm_symbols: RefCell::new(HashSet::new()),
-                tcx: tcx,
-                stats: Stats {
-                    n_static_tydescs: Cell::new(0u),
-                    n_glues_created: Cell::new(0u),
-                    n_null_glues: Cell::new(0u),
-                    n_real_glues: Cell::new(0u),
-                    n_fns: Cell::new(0u),
-                    n_monos: Cell::new(0u),
-                    n_inlines: Cell::new(0u),
-                    n_closures: Cell::new(0u),
-                    n_llvm_insns: Cell::new(0u),
-                    llvm_insns: RefCell::new(HashMap::new()),
-                    fn_stats: RefCell::new(Vec::new()),
-                },
                 int_type: Type::from_ref(ptr::mut_null()),
                 opaque_vec_type: Type::from_ref(ptr::mut_null()),
                 builder: BuilderRef_res(llvm::LLVMCreateBuilderInContext(llcx)),
                 unboxed_closure_vals: RefCell::new(DefIdMap::new()),
                 dbg_cx: dbg_cx,
                 eh_personality: RefCell::new(None),
+                n_llvm_insns: Cell::new(0u),
                 intrinsics: RefCell::new(HashMap::new()),
-            };
+            }
+        }
+    }
+}
 
-            ccx.int_type = Type::int(&ccx);
-            ccx.opaque_vec_type = Type::opaque_vec(&ccx);
+impl Deref<LocalCrateContext> for CrateContext {
+    fn deref<'a>(&'a self) -> &'a LocalCrateContext {
+        &self.locals[self.current.get()]
+    }
+}
+
+fn set_target(sess: &Session, llmod: ModuleRef) {
+    unsafe {
+        sess.targ_cfg
+            .target
+            .data_layout
+            .as_slice()
+            .with_c_str(|buf| {
+            llvm::LLVMSetDataLayout(llmod, buf);
+        });
+        sess.targ_cfg
+            .target
+            .llvm_target
+            .as_slice()
+            .with_c_str(|buf| {
+            llvm::LLVMRustSetNormalizedTarget(llmod, buf);
+        });
+    }
+}
+
+impl CrateContext {
+    /// Creates a crate context with a codegen unit for each of the LLVM
+    /// module `names`.
+    pub fn new(names: &[String],
+               crate_name: &str,
+               tcx: ty::ctxt,
+               emap2: resolve::ExportMap2,
+               symbol_hasher: Sha256,
+               link_meta: LinkMeta,
+               reachable: NodeSet)
+               -> CrateContext {
+        let locals: Vec<LocalCrateContext> = names.iter().map(|name| {
+            LocalCrateContext::new(name.as_slice(), &tcx.sess)
+        }).collect();
+
+        // The metadata is written with the first unit's context
+        let metadata_llmod = format!("{}_metadata", crate_name).with_c_str(|buf| {
+            unsafe {
+                llvm::LLVMModuleCreateWithNameInContext(buf, locals[0].llcx)
+            }
+        });
+        set_target(&tcx.sess, metadata_llmod);
+
+        let td = mk_target_data(tcx.sess
+                                   .targ_cfg
+                                   .target
+                                   .data_layout
+                                   .as_slice());
+
+        let mut ccx = CrateContext {
+            locals: locals,
+            current: Cell::new(0),
+            metadata_llmod: metadata_llmod,
+            td: td,
+            exp_map2: emap2,
+            reachable: reachable,
+            item_symbols: RefCell::new(NodeMap::new()),
+            link_meta: link_meta,
+            non_inlineable_statics: RefCell::new(NodeSet::new()),
+            monomorphizing: RefCell::new(DefIdMap::new()),
+            impl_method_cache: RefCell::new(HashMap::new()),
+            adt_reprs: RefCell::new(HashMap::new()),
+            symbol_hasher: RefCell::new(symbol_hasher),
+            type_hashcodes: RefCell::new(HashMap::new()),
+            entry_wrapper_created: Cell::new(false),
+            tcx: tcx,
+            stats: Stats {
+                n_static_tydescs: Cell::new(0u),
+                n_glues_created: Cell::new(0u),
+                n_null_glues: Cell::new(0u),
+                n_real_glues: Cell::new(0u),
+                n_fns: Cell::new(0u),
+                n_monos: Cell::new(0u),
+                n_inlines: Cell::new(0u),
+                n_closures: Cell::new(0u),
+                n_llvm_insns: Cell::new(0u),
+                llvm_insns: RefCell::new(HashMap::new()),
+                fn_stats: RefCell::new(Vec::new()),
+            },
+        };
+
+        for unit in range(0, ccx.codegen_units()) {
+            ccx.current.set(unit);
+            let int_type = Type::int(&ccx);
+            ccx.locals.get_mut(unit).int_type = int_type;
+            let opaque_vec_type = Type::opaque_vec(&ccx);
+            ccx.locals.get_mut(unit).opaque_vec_type = opaque_vec_type;
 
             let mut str_slice_ty = Type::named_struct(&ccx, "str_slice");
             str_slice_ty.set_struct_body([Type::i8p(&ccx), ccx.int_type], false);
             ccx.tn.associate_type("str_slice", &str_slice_ty);
 
             ccx.tn.associate_type("tydesc", &Type::tydesc(&ccx, str_slice_ty));
+        }
+        ccx.current.set(0);
 
-            if ccx.sess().count_llvm_insns() {
-                base::init_insn_ctxt()
-            }
+        if ccx.sess().count_llvm_insns() {
+            base::init_insn_ctxt()
+        }
+
+        ccx
+    }
 
-            ccx
+    /// The number of codegen units the crate is translated into.
+    pub fn codegen_units(&self) -> uint {
+        self.locals.len()
+    }
+
+    /// The index of the codegen unit being translated into.
+    pub fn current_unit(&self) -> uint {
+        self.current.get()
+    }
+
+    /// Runs `f` with `unit` as the codegen unit being translated into. The
+    /// items read meanwhile are recorded as dependencies of the unit.
+    pub fn with_unit<T>(&self, unit: uint, f: || -> T) -> T {
+        let prev = self.current.get();
+        self.current.set(unit);
+        let ret = self.tcx.dep_graph.with_task(dep_graph::TransUnit(unit), f);
+        self.current.set(prev);
+        ret
+    }
+
+    /// Returns the codegen unit to translate the module `id` into.
+    pub fn unit_for_module(&self, id: ast::NodeId) -> uint {
+        if !self.tcx.dep_graph.is_enabled() {
+            return self.least_loaded_unit();
+        }
+        // With `-C incremental` a module has to go to the same unit each
+        // time for the unit to be reused, so it's picked by its path.
+        let path = self.tcx.map.path_to_string(id);
+        (hash::hash(&path) % self.codegen_units() as u64) as uint
+    }
+
+    /// Returns the codegen unit with the fewest LLVM instructions so far.
+    pub fn least_loaded_unit(&self) -> uint {
+        let mut best = 0;
+        for (unit, local) in self.locals.iter().enumerate() {
+            if local.n_llvm_insns.get() < self.locals[best].n_llvm_insns.get() {
+                best = unit;
+            }
         }
+        best
     }
 
     pub fn tcx<'a>(&'a self) -> &'a ty::ctxt {
@@ -552,9 +552,9 @@ impl TypeMap {
 
             // Get the crate hash as first part of the identifier.
             let crate_hash = if source_def_id.krate == ast::LOCAL_CRATE {
-                cx.link_meta.crate_hash.clone()
+                cx.link_meta.symbol_hash.clone()
             } else {
-                cx.sess().cstore.get_crate_hash(source_def_id.krate)
+                cx.sess().cstore.get_crate_symbol_hash(source_def_id.krate)
             };
 
             output.push_str(crate_hash.as_str());
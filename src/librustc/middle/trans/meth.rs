@@ -85,6 +85,11 @@ pub fn trans_impl(ccx: &CrateContext,
                              &param_substs::empty(),
                              method.id,
                              []);
+                    trans_inline_copies(ccx,
+                                        &*method.pe_fn_decl(),
+                                        &*method.pe_body(),
+                                        method.id,
+                                        method.attrs.as_slice());
                 }
                 let mut v = TransItemVisitor {
                     ccx: ccx,
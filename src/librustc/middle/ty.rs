@@ -16,6 +16,7 @@ use lint;
 use metadata::csearch;
 use middle::const_eval;
 use middle::def;
+use middle::dep_graph::DepGraph;
 use middle::dependency_format;
 use middle::freevars::CaptureModeMap;
 use middle::freevars;
@@ -546,6 +547,9 @@ pub struct ctxt {
 
     /// Maps closures to their capture clauses.
     pub capture_modes: RefCell<CaptureModeMap>,
+
+    /// Which results depend on which items, for `-C incremental`.
+    pub dep_graph: DepGraph,
 }
 
 pub enum tbox_flag {
@@ -1430,6 +1434,7 @@ pub fn mk_ctxt(s: Session,
         transmute_restrictions: RefCell::new(Vec::new()),
         stability: RefCell::new(stability),
         capture_modes: RefCell::new(capture_modes),
+        dep_graph: DepGraph::new(),
     }
 }
 
@@ -5119,7 +5124,7 @@ pub fn hash_crate_independent(tcx: &ctxt, t: t, svh: &Svh) -> u64 {
         let h = if ast_util::is_local(did) {
             svh.clone()
         } else {
-            tcx.sess.cstore.get_crate_hash(did.krate)
+            tcx.sess.cstore.get_crate_symbol_hash(did.krate)
         };
         h.as_str().hash(state);
         did.node.hash(state);
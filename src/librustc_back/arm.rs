@@ -8,18 +8,28 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use target_strs;
+use target::{Target, os_base};
 use syntax::abi;
 
-pub fn get_target_strs(target_triple: String, target_os: abi::Os) -> target_strs::t {
-    let cc_args = if target_triple.as_slice().contains("thumb") {
+pub fn target(target_triple: String, target_os: abi::Os) -> Target {
+    let pre_link_args = if target_triple.as_slice().contains("thumb") {
         vec!("-mthumb".to_string())
     } else {
         vec!("-marm".to_string())
     };
-    return target_strs::t {
-        module_asm: "".to_string(),
 
+    let mut base = os_base(target_os);
+    if target_os == abi::OsiOS {
+        // On iOS only armv7 and newer are supported. So it is useful to
+        // get all hardware potential via VFP3 (hardware floating point)
+        // and NEON (SIMD) instructions supported by LLVM.
+        // Note that without those flags various linking errors might
+        // arise as some of intrinsics are converted into function calls
+        // and nobody provides implementations those functions
+        base.features = "+v7,+thumb2,+vfp3,+neon".to_string();
+    }
+
+    return Target {
         data_layout: match target_os {
           abi::OsMacos => {
             "e-p:32:32:32\
@@ -70,8 +80,15 @@ pub fn get_target_strs(target_triple: String, target_os: abi::Os) -> target_strs
           }
         },
 
-        target_triple: target_triple,
+        llvm_target: target_triple,
+        // ARM is bi-endian, however using NDK seems to default
+        // to little-endian unless a flag is provided.
+        target_endian: "little".to_string(),
+        target_word_size: "32".to_string(),
+        arch: "arm".to_string(),
+
+        pre_link_args: pre_link_args,
 
-        cc_args: cc_args,
+        .. base
     };
 }
@@ -49,6 +49,6 @@ pub mod mipsel;
 pub mod rpath;
 pub mod sha2;
 pub mod svh;
-pub mod target_strs;
+pub mod target;
 pub mod x86;
 pub mod x86_64;
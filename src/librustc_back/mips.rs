@@ -8,13 +8,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use target_strs;
+use target::{Target, os_base};
 use syntax::abi;
 
-pub fn get_target_strs(target_triple: String, target_os: abi::Os) -> target_strs::t {
-    return target_strs::t {
-        module_asm: "".to_string(),
-
+pub fn target(target_triple: String, target_os: abi::Os) -> Target {
+    return Target {
         data_layout: match target_os {
           abi::OsMacos => {
             "E-p:32:32:32\
@@ -65,8 +63,13 @@ pub fn get_target_strs(target_triple: String, target_os: abi::Os) -> target_strs
           }
         },
 
-        target_triple: target_triple,
+        llvm_target: target_triple,
+        target_endian: "big".to_string(),
+        target_word_size: "32".to_string(),
+        arch: "mips".to_string(),
+
+        pre_link_args: Vec::new(),
 
-        cc_args: Vec::new(),
+        .. os_base(target_os)
     };
 }
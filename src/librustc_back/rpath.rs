@@ -12,11 +12,14 @@
 use std::collections::HashSet;
 use std::os;
 use std::io::IoError;
-use syntax::abi;
 use syntax::ast;
 
 pub struct RPathConfig<'a> {
-    pub os: abi::Os,
+    /// Whether the target's rpaths are relative to `@loader_path` rather
+    /// than `$ORIGIN`.
+    pub is_like_osx: bool,
+    /// Whether the target has rpaths at all.
+    pub has_rpath: bool,
     pub used_crates: Vec<(ast::CrateNum, Option<Path>)>,
     pub out_filename: Path,
     pub get_install_prefix_lib_path: ||:'a -> Path,
@@ -25,25 +28,13 @@ pub struct RPathConfig<'a> {
 
 pub fn get_rpath_flags(config: RPathConfig) -> Vec<String> {
 
-    // No rpath on windows
-    if config.os == abi::OsWindows {
+    // Some targets, like windows, have no rpath
+    if !config.has_rpath {
         return Vec::new();
     }
 
     let mut flags = Vec::new();
 
-    if config.os == abi::OsFreebsd {
-        flags.push_all(["-Wl,-rpath,/usr/local/lib/gcc46".to_string(),
-                        "-Wl,-rpath,/usr/local/lib/gcc44".to_string(),
-                        "-Wl,-z,origin".to_string()]);
-    }
-    else if config.os == abi::OsDragonfly {
-        flags.push_all(["-Wl,-rpath,/usr/lib/gcc47".to_string(),
-                        "-Wl,-rpath,/usr/lib/gcc44".to_string(),
-                        "-Wl,-z,origin".to_string()]);
-    }
-
-
     debug!("preparing the RPATH!");
 
     let libs = config.used_crates.clone();
@@ -107,14 +98,13 @@ fn get_rpath_relative_to_output(config: &mut RPathConfig,
                                 lib: &Path) -> String {
     use std::os;
 
-    assert!(config.os != abi::OsWindows);
+    assert!(config.has_rpath);
 
     // Mac doesn't appear to support $ORIGIN
-    let prefix = match config.os {
-        abi::OsAndroid | abi::OsLinux | abi::OsFreebsd | abi::OsDragonfly
-                          => "$ORIGIN",
-        abi::OsMacos => "@loader_path",
-        abi::OsWindows | abi::OsiOS => unreachable!()
+    let prefix = if config.is_like_osx {
+        "@loader_path"
+    } else {
+        "$ORIGIN"
     };
 
     let mut lib = (config.realpath)(&os::make_absolute(lib)).unwrap();
@@ -151,7 +141,6 @@ fn minimize_rpaths(rpaths: &[String]) -> Vec<String> {
 mod test {
     use super::{RPathConfig};
     use super::{minimize_rpaths, rpaths_to_flags, get_rpath_relative_to_output};
-    use syntax::abi;
 
     #[test]
     fn test_rpaths_to_flags() {
@@ -200,11 +189,10 @@ mod test {
     }
 
     #[test]
-    #[cfg(target_os = "linux")]
-    #[cfg(target_os = "android")]
     fn test_rpath_relative() {
         let config = &mut RPathConfig {
-            os: abi::OsLinux,
+            is_like_osx: false,
+            has_rpath: true,
             used_crates: Vec::new(),
             out_filename: Path::new("bin/rustc"),
             get_install_prefix_lib_path: || fail!(),
@@ -215,38 +203,10 @@ mod test {
     }
 
     #[test]
-    #[cfg(target_os = "freebsd")]
-    fn test_rpath_relative() {
-        let config = &mut RPathConfig {
-            os: abi::OsFreebsd,
-            used_crates: Vec::new(),
-            out_filename: Path::new("bin/rustc"),
-            get_install_prefix_lib_path: || fail!(),
-            realpath: |p| Ok(p.clone())
-        };
-        let res = get_rpath_relative_to_output(config, &Path::new("lib/libstd.so"));
-        assert_eq!(res.as_slice(), "$ORIGIN/../lib");
-    }
-
-    #[test]
-    #[cfg(target_os = "dragonfly")]
-    fn test_rpath_relative() {
-        let config = &mut RPathConfig {
-            os: abi::OsDragonfly,
-            used_crates: Vec::new(),
-            out_filename: Path::new("bin/rustc"),
-            get_install_prefix_lib_path: || fail!(),
-            realpath: |p| Ok(p.clone())
-        };
-        let res = get_rpath_relative_to_output(config, &Path::new("lib/libstd.so"));
-        assert_eq!(res.as_slice(), "$ORIGIN/../lib");
-    }
-
-    #[test]
-    #[cfg(target_os = "macos")]
-    fn test_rpath_relative() {
+    fn test_rpath_relative_osx() {
         let config = &mut RPathConfig {
-            os: abi::OsMacos,
+            is_like_osx: true,
+            has_rpath: true,
             used_crates: Vec::new(),
             out_filename: Path::new("bin/rustc"),
             get_install_prefix_lib_path: || fail!(),
@@ -0,0 +1,532 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Descriptions of the targets rustc can generate code for.
+//!
+//! A `Target` holds everything the compiler needs to know about a target
+//! which isn't baked into its code generation: the LLVM triple and data
+//! layout, the values of the `target_*` cfgs, the linker to run, the flags
+//! it takes and the names of the files it produces.
+//!
+//! The targets rustc knows about are built from their triple: the operating
+//! system provides the defaults (see `os_base`) and the architecture modules
+//! fill in the rest. Any other target can be described by passing a JSON
+//! file to `--target`:
+//!
+//! ```json
+//! {
+//!     "llvm-target": "x86_64-unknown-linux-gnu",
+//!     "data-layout": "e-p:64:64:64-i1:8:8-i8:8:8-i16:16:16-i32:32:32-...",
+//!     "target-endian": "little",
+//!     "target-word-size": "64",
+//!     "arch": "x86_64",
+//!     "target-os": "linux",
+//!     "target-family": "unix",
+//!     "linker": "x86_64-custom-linux-gcc",
+//!     "pre-link-args": ["-m64"],
+//!     "post-link-args": [],
+//!     "exe-suffix": "",
+//!     "dll-prefix": "lib",
+//!     "dll-suffix": ".so",
+//!     "relocation-model": "pic",
+//!     "linker-is-gnu": true,
+//!     "dynamic-linking": true
+//! }
+//! ```
+//!
+//! Only the fields up to `target-os` are required. The operating system is
+//! only the value of `cfg(target_os)`, so it may be anything. The other
+//! fields default to the values used for that operating system if rustc
+//! knows it, and otherwise to those of Windows if `is-like-windows` is set,
+//! of OSX if `is-like-osx` is set, or else of Linux; there is no default
+//! `target_family`. The architecture must be one rustc knows.
+//!
+//! Elsewhere, such as in crate metadata and in the paths of the target's
+//! libraries in the sysroot, a target described by a file is known by the
+//! file's name without its extension.
+
+use std::io::File;
+use serialize::json;
+use serialize::json::Json;
+use syntax::abi;
+
+use arm;
+use mips;
+use mipsel;
+use x86;
+use x86_64;
+
+#[deriving(Clone, Show)]
+pub struct Target {
+    /// The triple LLVM generates code for.
+    pub llvm_target: String,
+    pub data_layout: String,
+    /// The value of `cfg(target_endian)`, `little` or `big`.
+    pub target_endian: String,
+    /// The value of `cfg(target_word_size)`, which is also the width of
+    /// pointers and of `int`.
+    pub target_word_size: String,
+    /// The value of `cfg(target_arch)`.
+    pub arch: String,
+    /// The value of `cfg(target_os)`.
+    pub target_os: String,
+    /// The value of `cfg(target_family)`, which is also set as a cfg of its
+    /// own, like `cfg(unix)`.
+    pub target_family: Option<String>,
+    /// The program run to link, unless `-C linker` is given.
+    pub linker: String,
+    /// Arguments given to the linker before and after the ones of rustc.
+    pub pre_link_args: Vec<String>,
+    pub post_link_args: Vec<String>,
+    pub exe_suffix: String,
+    pub dll_prefix: String,
+    pub dll_suffix: String,
+    /// The relocation model used unless `-C relocation-model` is given.
+    pub relocation_model: String,
+    /// The features LLVM enables unless `-C target-feature` is given.
+    pub features: String,
+    pub staticlib_prefix: String,
+    pub staticlib_suffix: String,
+    /// Whether dynamic libraries can be built and linked to.
+    pub dynamic_linking: bool,
+    /// Whether executables can be built, rather than only libraries for
+    /// something else to link.
+    pub executables: bool,
+    /// Whether the linker is OSX's, which takes `-force_load` rather than
+    /// `--whole-archive`, strips dead code with `-dead_strip` rather than
+    /// `--gc-sections`, and makes rpaths relative to `@loader_path`.
+    pub is_like_osx: bool,
+    /// Whether the target follows the conventions of Windows, whose linker
+    /// pulls in the default libraries itself and needs to be told to keep
+    /// long section names.
+    pub is_like_windows: bool,
+    /// Whether the linker takes the flags of GNU ld which only some other
+    /// linkers do, `--as-needed` and `-O1`.
+    pub linker_is_gnu: bool,
+    /// Whether executables are linked as position independent when the
+    /// relocation model is `pic`.
+    pub position_independent_executables: bool,
+    /// Whether `-C rpath` adds rpaths to what is linked.
+    pub has_rpath: bool,
+    /// Whether each function and static goes in a section of its own, for
+    /// the linker to drop the unused ones.
+    pub function_sections: bool,
+    /// Whether frame pointers may be left out of optimized code.
+    pub eliminate_frame_pointer: bool,
+}
+
+impl Target {
+    /// Finds the target named by `--target`, which is either the triple of a
+    /// target built into rustc or the path of a JSON file describing one.
+    pub fn search(target: &str) -> Result<Target, String> {
+        if is_spec_file(target) {
+            let path = Path::new(target);
+            let contents = match File::open(&path).read_to_string() {
+                Ok(contents) => contents,
+                Err(e) => {
+                    return Err(format!("failed to read target specification `{}`: {}",
+                                       path.display(), e))
+                }
+            };
+            return match json::from_str(contents.as_slice()) {
+                Ok(json) => Target::from_json(&json).map_err(|e| {
+                    format!("invalid target specification `{}`: {}", path.display(), e)
+                }),
+                Err(e) => Err(format!("failed to parse target specification `{}`: {}",
+                                      path.display(), e)),
+            }
+        }
+        match Target::builtin(target) {
+            Some(target) => Ok(target),
+            None => Err(format!("unknown target `{}`", target)),
+        }
+    }
+
+    /// Returns the built-in target for `triple`, if rustc knows about its
+    /// operating system and architecture.
+    pub fn builtin(triple: &str) -> Option<Target> {
+        let os = match os_names.iter().find(|&&(name, _)| triple.contains(name)) {
+            Some(&(_, os)) => os,
+            None => return None,
+        };
+        let arch = match architecture_abis.iter().find(|&&(name, _)| triple.contains(name)) {
+            Some(&(_, arch)) => arch,
+            None => return None,
+        };
+        let triple = triple.to_string();
+        Some(match arch {
+            abi::X86 => x86::target(triple, os),
+            abi::X86_64 => x86_64::target(triple, os),
+            abi::Arm => arm::target(triple, os),
+            abi::Mips => mips::target(triple, os),
+            abi::Mipsel => mipsel::target(triple, os),
+        })
+    }
+
+    /// Reads a target from its JSON description.
+    pub fn from_json(json: &Json) -> Result<Target, String> {
+        let string = |key: &str, default: Option<&String>| -> Result<String, String> {
+            match json.find(&key.to_string()) {
+                Some(value) => match value.as_string() {
+                    Some(s) => Ok(s.to_string()),
+                    None => Err(format!("field `{}` must be a string", key)),
+                },
+                None => match default {
+                    Some(s) => Ok(s.clone()),
+                    None => Err(format!("field `{}` is missing", key)),
+                },
+            }
+        };
+        let boolean = |key: &str, default: bool| -> Result<bool, String> {
+            match json.find(&key.to_string()) {
+                Some(value) => match value.as_boolean() {
+                    Some(b) => Ok(b),
+                    None => Err(format!("field `{}` must be a boolean", key)),
+                },
+                None => Ok(default),
+            }
+        };
+        let list = |key: &str, default: &Vec<String>| -> Result<Vec<String>, String> {
+            let list = match json.find(&key.to_string()) {
+                Some(value) => value.as_list(),
+                None => return Ok(default.clone()),
+            };
+            let strings = list.and_then(|list| {
+                list.iter().map(|s| s.as_string().map(|s| s.to_string())).collect()
+            });
+            match strings {
+                Some(strings) => Ok(strings),
+                None => Err(format!("field `{}` must be a list of strings", key)),
+            }
+        };
+
+        // The defaults are those of the operating system, once it's known
+        // whose conventions it follows
+        let target_os = try!(string("target-os", None));
+        let base = os_base(os_conventions(target_os.as_slice(),
+                                          try!(boolean("is-like-osx", false)),
+                                          try!(boolean("is-like-windows", false))));
+
+        let target = Target {
+            llvm_target: try!(string("llvm-target", None)),
+            data_layout: try!(string("data-layout", None)),
+            target_endian: try!(string("target-endian", None)),
+            target_word_size: try!(string("target-word-size", None)),
+            arch: try!(string("arch", None)),
+            target_os: target_os,
+            target_family: match json.find(&"target-family".to_string()) {
+                Some(_) => Some(try!(string("target-family", None))),
+                None => None,
+            },
+            linker: try!(string("linker", Some(&base.linker))),
+            pre_link_args: try!(list("pre-link-args", &base.pre_link_args)),
+            post_link_args: try!(list("post-link-args", &base.post_link_args)),
+            exe_suffix: try!(string("exe-suffix", Some(&base.exe_suffix))),
+            dll_prefix: try!(string("dll-prefix", Some(&base.dll_prefix))),
+            dll_suffix: try!(string("dll-suffix", Some(&base.dll_suffix))),
+            relocation_model: try!(string("relocation-model", Some(&base.relocation_model))),
+            features: try!(string("features", Some(&base.features))),
+            staticlib_prefix: try!(string("staticlib-prefix", Some(&base.staticlib_prefix))),
+            staticlib_suffix: try!(string("staticlib-suffix", Some(&base.staticlib_suffix))),
+            dynamic_linking: try!(boolean("dynamic-linking", base.dynamic_linking)),
+            executables: try!(boolean("executables", base.executables)),
+            is_like_osx: try!(boolean("is-like-osx", base.is_like_osx)),
+            is_like_windows: try!(boolean("is-like-windows", base.is_like_windows)),
+            linker_is_gnu: try!(boolean("linker-is-gnu", base.linker_is_gnu)),
+            position_independent_executables:
+                try!(boolean("position-independent-executables",
+                             base.position_independent_executables)),
+            has_rpath: try!(boolean("has-rpath", base.has_rpath)),
+            function_sections: try!(boolean("function-sections", base.function_sections)),
+            eliminate_frame_pointer: try!(boolean("eliminate-frame-pointer",
+                                                  base.eliminate_frame_pointer)),
+        };
+
+        if target.target_endian.as_slice() != "little" &&
+           target.target_endian.as_slice() != "big" {
+            return Err(format!("unknown endianness `{}`", target.target_endian));
+        }
+        if target.target_word_size.as_slice() != "32" &&
+           target.target_word_size.as_slice() != "64" {
+            return Err(format!("unsupported word size `{}`", target.target_word_size));
+        }
+        if target.arch().is_none() {
+            return Err(format!("unsupported architecture `{}`", target.arch));
+        }
+        Ok(target)
+    }
+
+    /// The operating system whose conventions rustc follows for this target,
+    /// such as the calling conventions of `extern` functions and where the
+    /// metadata goes. That is the target's own for the operating systems
+    /// rustc knows; see `os_conventions` for the others.
+    pub fn os(&self) -> abi::Os {
+        os_conventions(self.target_os.as_slice(), self.is_like_osx, self.is_like_windows)
+    }
+
+    pub fn arch(&self) -> Option<abi::Architecture> {
+        match self.arch.as_slice() {
+            "x86" => Some(abi::X86),
+            "x86_64" => Some(abi::X86_64),
+            "arm" => Some(abi::Arm),
+            "mips" => Some(abi::Mips),
+            "mipsel" => Some(abi::Mipsel),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the name `target`, as given to `--target`, is known by: the
+/// triple of a built-in target, or the file stem of a JSON description.
+pub fn target_name(target: &str) -> String {
+    if is_spec_file(target) {
+        let path = Path::new(target);
+        path.filestem_str().unwrap_or(target).to_string()
+    } else {
+        target.to_string()
+    }
+}
+
+fn is_spec_file(target: &str) -> bool {
+    target.ends_with(".json")
+}
+
+/// Returns the operating system whose conventions are followed by
+/// `target_os`: the one it names if rustc knows it, or else Windows if the
+/// target is like Windows, OSX if it is like OSX, or Linux.
+pub fn os_conventions(target_os: &str, is_like_osx: bool, is_like_windows: bool) -> abi::Os {
+    match target_os {
+        "windows" => abi::OsWindows,
+        "macos" => abi::OsMacos,
+        "linux" => abi::OsLinux,
+        "android" => abi::OsAndroid,
+        "freebsd" => abi::OsFreebsd,
+        "dragonfly" => abi::OsDragonfly,
+        "ios" => abi::OsiOS,
+        _ if is_like_windows => abi::OsWindows,
+        _ if is_like_osx => abi::OsMacos,
+        _ => abi::OsLinux,
+    }
+}
+
+/// The parts of the built-in targets which depend only on the operating
+/// system. The architecture modules fill in the LLVM target, the data layout
+/// and the `target_arch`, `target_endian` and `target_word_size` cfgs.
+pub fn os_base(os: abi::Os) -> Target {
+    let mut base = Target {
+        llvm_target: String::new(),
+        data_layout: String::new(),
+        target_endian: String::new(),
+        target_word_size: String::new(),
+        arch: String::new(),
+        target_os: os.to_string(),
+        target_family: Some("unix".to_string()),
+        linker: "cc".to_string(),
+        pre_link_args: Vec::new(),
+        post_link_args: Vec::new(),
+        exe_suffix: String::new(),
+        dll_prefix: "lib".to_string(),
+        dll_suffix: ".so".to_string(),
+        relocation_model: "pic".to_string(),
+        features: String::new(),
+        staticlib_prefix: "lib".to_string(),
+        staticlib_suffix: ".a".to_string(),
+        dynamic_linking: true,
+        executables: true,
+        is_like_osx: false,
+        is_like_windows: false,
+        linker_is_gnu: false,
+        position_independent_executables: false,
+        has_rpath: true,
+        function_sections: true,
+        eliminate_frame_pointer: true,
+    };
+    match os {
+        abi::OsWindows => {
+            base.target_family = Some("windows".to_string());
+            base.linker = "gcc".to_string();
+            base.exe_suffix = ".exe".to_string();
+            base.dll_prefix = String::new();
+            base.dll_suffix = ".dll".to_string();
+            base.staticlib_prefix = String::new();
+            base.staticlib_suffix = ".lib".to_string();
+            base.is_like_windows = true;
+            base.has_rpath = false;
+            // FIXME(#13846) this should be enabled for windows
+            base.function_sections = false;
+        }
+        abi::OsMacos | abi::OsiOS => {
+            base.dll_suffix = ".dylib".to_string();
+            base.is_like_osx = true;
+            // OSX has -dead_strip, which doesn't rely on function sections
+            base.function_sections = false;
+            // There's no way to run iOS binaries without jailbreaking, so Rust
+            // code is only ever linked into an app as a static library
+            if os == abi::OsiOS {
+                base.dynamic_linking = false;
+                base.executables = false;
+                base.has_rpath = false;
+            }
+        }
+        abi::OsLinux => {
+            base.linker_is_gnu = true;
+            base.position_independent_executables = true;
+        }
+        abi::OsAndroid => {
+            // We by default compile for armv7 processors. This enables things
+            // like double word CAS instructions (rather than emulating them)
+            // which are *far* more efficient.
+            base.features = "+v7".to_string();
+            // Many of the symbols defined in compiler-rt are also defined in
+            // libgcc. Android linker doesn't like that by default.
+            base.post_link_args.push("-Wl,--allow-multiple-definition".to_string());
+        }
+        abi::OsFreebsd => {
+            base.post_link_args.push_all(["-L/usr/local/lib".to_string(),
+                                          "-L/usr/local/lib/gcc46".to_string(),
+                                          "-L/usr/local/lib/gcc44".to_string(),
+                                          "-Wl,-rpath,/usr/local/lib/gcc46".to_string(),
+                                          "-Wl,-rpath,/usr/local/lib/gcc44".to_string(),
+                                          "-Wl,-z,origin".to_string()]);
+        }
+        abi::OsDragonfly => {
+            base.linker_is_gnu = true;
+            base.post_link_args.push_all(["-L/usr/local/lib".to_string(),
+                                          "-L/usr/lib/gcc47".to_string(),
+                                          "-L/usr/lib/gcc44".to_string(),
+                                          "-Wl,-rpath,/usr/lib/gcc47".to_string(),
+                                          "-Wl,-rpath,/usr/lib/gcc44".to_string(),
+                                          "-Wl,-z,origin".to_string()]);
+        }
+    }
+    base
+}
+
+static os_names : &'static [(&'static str, abi::Os)] = &[
+    ("mingw32",   abi::OsWindows),
+    ("win32",     abi::OsWindows),
+    ("windows",   abi::OsWindows),
+    ("darwin",    abi::OsMacos),
+    ("android",   abi::OsAndroid),
+    ("linux",     abi::OsLinux),
+    ("freebsd",   abi::OsFreebsd),
+    ("dragonfly", abi::OsDragonfly),
+    ("ios",       abi::OsiOS)];
+
+static architecture_abis : &'static [(&'static str, abi::Architecture)] = &[
+    ("i386",   abi::X86),
+    ("i486",   abi::X86),
+    ("i586",   abi::X86),
+    ("i686",   abi::X86),
+    ("i786",   abi::X86),
+
+    ("x86_64", abi::X86_64),
+
+    ("arm",    abi::Arm),
+    ("xscale", abi::Arm),
+    ("thumb",  abi::Arm),
+
+    ("mipsel", abi::Mipsel),
+    ("mips",   abi::Mips)];
+
+#[cfg(test)]
+mod test {
+    use super::{Target, target_name};
+    use serialize::json;
+    use syntax::abi;
+
+    fn spec(extra: &str) -> Result<Target, String> {
+        let json = format!("{{\"llvm-target\": \"x86_64-unknown-linux-gnu\",
+                              \"data-layout\": \"e-p:64:64:64\",
+                              \"target-endian\": \"little\",
+                              \"target-word-size\": \"64\",
+                              \"arch\": \"x86_64\"{}}}", extra);
+        Target::from_json(&json::from_str(json.as_slice()).unwrap())
+    }
+
+    #[test]
+    fn builtin() {
+        let target = Target::builtin("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!(target.llvm_target.as_slice(), "x86_64-unknown-linux-gnu");
+        assert_eq!(target.target_os.as_slice(), "linux");
+        assert_eq!(target.target_family, Some("unix".to_string()));
+        assert_eq!(target.pre_link_args, vec!("-m64".to_string()));
+        assert_eq!(target.dll_suffix.as_slice(), ".so");
+
+        let target = Target::builtin("i686-pc-mingw32").unwrap();
+        assert_eq!(target.arch.as_slice(), "x86");
+        assert_eq!(target.target_word_size.as_slice(), "32");
+        assert_eq!(target.exe_suffix.as_slice(), ".exe");
+        assert_eq!(target.os(), abi::OsWindows);
+        assert!(target.is_like_windows);
+
+        let target = Target::builtin("arm-apple-ios").unwrap();
+        assert!(target.is_like_osx);
+        assert!(!target.dynamic_linking);
+        assert_eq!(target.features.as_slice(), "+v7,+thumb2,+vfp3,+neon");
+
+        assert!(Target::builtin("x86_64-unknown-plan9").is_none());
+        assert!(Target::search("sparc-unknown-linux-gnu").is_err());
+    }
+
+    #[test]
+    fn from_json() {
+        let target = spec(", \"target-os\": \"linux\",
+                           \"linker\": \"arm-none-eabi-gcc\",
+                           \"pre-link-args\": [\"-nostartfiles\"]").unwrap();
+        assert_eq!(target.target_os.as_slice(), "linux");
+        assert_eq!(target.target_family, None);
+        assert_eq!(target.linker.as_slice(), "arm-none-eabi-gcc");
+        assert_eq!(target.pre_link_args, vec!("-nostartfiles".to_string()));
+        assert_eq!(target.post_link_args, vec!());
+        assert_eq!(target.dll_suffix.as_slice(), ".so");
+        assert_eq!(target.relocation_model.as_slice(), "pic");
+        assert!(target.linker_is_gnu);
+        assert_eq!(target.os(), abi::OsLinux);
+
+        let target = spec(", \"target-os\": \"windows\", \"target-family\": \"quux\"").unwrap();
+        assert_eq!(target.os(), abi::OsWindows);
+        assert_eq!(target.target_family, Some("quux".to_string()));
+
+        // Other operating systems take the defaults of the one they're like
+        let target = spec(", \"target-os\": \"none\", \"linker-is-gnu\": false").unwrap();
+        assert_eq!(target.target_os.as_slice(), "none");
+        assert_eq!(target.os(), abi::OsLinux);
+        assert!(!target.linker_is_gnu);
+        assert!(!target.is_like_osx);
+
+        let target = spec(", \"target-os\": \"wince\", \"is-like-windows\": true").unwrap();
+        assert_eq!(target.os(), abi::OsWindows);
+        assert_eq!(target.dll_suffix.as_slice(), ".dll");
+        assert_eq!(target.staticlib_suffix.as_slice(), ".lib");
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert_eq!(spec("").unwrap_err(), "field `target-os` is missing".to_string());
+        assert_eq!(spec(", \"target-os\": 1").unwrap_err(),
+                   "field `target-os` must be a string".to_string());
+        assert_eq!(spec(", \"target-os\": \"linux\", \"pre-link-args\": [1]").unwrap_err(),
+                   "field `pre-link-args` must be a list of strings".to_string());
+        assert!(spec(", \"target-os\": \"linux\", \"arch\": \"sparc\"").is_err());
+        assert_eq!(spec(", \"target-os\": \"none\", \"is-like-osx\": \"yes\"").unwrap_err(),
+                   "field `is-like-osx` must be a boolean".to_string());
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(target_name("x86_64-unknown-linux-gnu").as_slice(),
+                   "x86_64-unknown-linux-gnu");
+        assert_eq!(target_name("my-awesome-platform.json").as_slice(),
+                   "my-awesome-platform");
+        assert_eq!(target_name("../specs/my-awesome-platform.json").as_slice(),
+                   "my-awesome-platform");
+    }
+}
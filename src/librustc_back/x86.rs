@@ -9,14 +9,11 @@
 // except according to those terms.
 
 
-use target_strs;
+use target::{Target, os_base};
 use syntax::abi;
 
-pub fn get_target_strs(target_triple: String, target_os: abi::Os)
-                       -> target_strs::t {
-    return target_strs::t {
-        module_asm: "".to_string(),
-
+pub fn target(target_triple: String, target_os: abi::Os) -> Target {
+    return Target {
         data_layout: match target_os {
           abi::OsMacos => {
             "e-p:32:32:32-i1:8:8-i8:8:8-i16:16:16\
@@ -54,8 +51,13 @@ pub fn get_target_strs(target_triple: String, target_os: abi::Os)
 
         },
 
-        target_triple: target_triple,
+        llvm_target: target_triple,
+        target_endian: "little".to_string(),
+        target_word_size: "32".to_string(),
+        arch: "x86".to_string(),
+
+        pre_link_args: vec!("-m32".to_string()),
 
-        cc_args: vec!("-m32".to_string()),
+        .. os_base(target_os)
     };
 }
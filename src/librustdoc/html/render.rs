@@ -472,7 +472,9 @@ fn write_shared(cx: &Context,
     // docs placed in the output directory, so this needs to be a synchronized
     // operation with respect to all other rustdocs running around.
     try!(mkdir(&cx.dst));
-    let _lock = ::flock::Lock::new(&cx.dst.join(".lock"));
+    let mut lock = try!(File::open_mode(&cx.dst.join(".lock"), io::Open,
+                                        io::ReadWrite));
+    try!(lock.lock_exclusive());
 
     // Add all the static files. These may already exist, but we just
     // overwrite them anyway to make sure that they're fresh and up-to-date.
@@ -59,7 +59,6 @@ pub mod plugins;
 pub mod stability_summary;
 pub mod visit_ast;
 pub mod test;
-mod flock;
 
 type Pass = (&'static str,                                      // name
              fn(clean::Crate) -> plugins::PluginResult,         // fn
@@ -63,6 +63,7 @@ pub mod rtio;
 pub mod stack;
 pub mod task;
 pub mod thread;
+pub mod time;
 pub mod unwind;
 
 /// The interface to the current runtime.
@@ -81,6 +82,11 @@ pub trait Runtime {
                   times: uint,
                   cur_task: Box<Task>,
                   f: |BlockedTask| -> Result<(), BlockedTask>);
+    fn deschedule_timeout(self: Box<Self>,
+                          times: uint,
+                          ms: u64,
+                          cur_task: Box<Task>,
+                          f: |BlockedTask| -> Result<(), BlockedTask>);
     fn reawaken(self: Box<Self>, to_wake: Box<Task>);
 
     // Miscellaneous calls which are very different depending on what context
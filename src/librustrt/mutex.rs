@@ -195,6 +195,19 @@ impl StaticNativeMutex {
     /// is unsafe because the mutex may not be currently locked.
     pub unsafe fn wait_noguard(&self) { self.inner.wait() }
 
+    /// Block on the internal condition variable for at most `ms`
+    /// milliseconds, returning `false` if the timeout elapsed.
+    ///
+    /// Like `wait_noguard`, this may wake up spuriously, in which case `true`
+    /// is returned as if the condition variable had been signaled.
+    ///
+    /// # Unsafety
+    ///
+    /// This method is unsafe for the same reasons as `wait_noguard`.
+    pub unsafe fn wait_timeout_noguard(&self, ms: u64) -> bool {
+        self.inner.wait_timeout(ms)
+    }
+
     /// Signals a thread in `wait` to wake up
     ///
     /// # Unsafety
@@ -302,6 +315,17 @@ impl NativeMutex {
     /// `StaticNativeMutex::wait_noguard`.
     pub unsafe fn wait_noguard(&self) { self.inner.wait_noguard() }
 
+    /// Block on the internal condition variable for at most `ms`
+    /// milliseconds, returning `false` if the timeout elapsed.
+    ///
+    /// # Unsafety
+    ///
+    /// This method is unsafe due to the same reasons as
+    /// `StaticNativeMutex::wait_timeout_noguard`.
+    pub unsafe fn wait_timeout_noguard(&self, ms: u64) -> bool {
+        self.inner.wait_timeout_noguard(ms)
+    }
+
     /// Signals a thread in `wait` to wake up
     ///
     /// # Unsafety
@@ -323,6 +347,12 @@ impl<'a> LockGuard<'a> {
         self.lock.wait_noguard()
     }
 
+    /// Block on the internal condition variable for at most `ms`
+    /// milliseconds, returning `false` if the timeout elapsed.
+    pub unsafe fn wait_timeout(&self, ms: u64) -> bool {
+        self.lock.wait_timeout_noguard(ms)
+    }
+
     /// Signals a thread in `wait` to wake up.
     pub unsafe fn signal(&self) {
         self.lock.signal_noguard()
@@ -342,6 +372,7 @@ mod imp {
     use self::os::{PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER,
                    pthread_mutex_t, pthread_cond_t};
     use core::cell::UnsafeCell;
+    use core::ptr;
 
     type pthread_mutexattr_t = libc::c_void;
     type pthread_condattr_t = libc::c_void;
@@ -494,6 +525,18 @@ mod imp {
         pub unsafe fn wait(&self) {
             pthread_cond_wait(self.cond.get(), self.lock.get());
         }
+        pub unsafe fn wait_timeout(&self, ms: u64) -> bool {
+            // pthread_cond_timedwait takes an absolute time of day
+            let mut now = libc::timeval { tv_sec: 0, tv_usec: 0 };
+            gettimeofday(&mut now, ptr::mut_null());
+            let nsec = (now.tv_usec as u64) * 1000 + (ms % 1000) * 1000000;
+            let deadline = libc::timespec {
+                tv_sec: now.tv_sec + (ms / 1000 + nsec / 1000000000) as libc::time_t,
+                tv_nsec: (nsec % 1000000000) as libc::c_long,
+            };
+            pthread_cond_timedwait(self.cond.get(), self.lock.get(),
+                                   &deadline) != libc::ETIMEDOUT
+        }
         pub unsafe fn trylock(&self) -> bool {
             pthread_mutex_trylock(self.lock.get()) == 0
         }
@@ -512,7 +555,13 @@ mod imp {
 
         fn pthread_cond_wait(cond: *mut pthread_cond_t,
                              lock: *mut pthread_mutex_t) -> libc::c_int;
+        fn pthread_cond_timedwait(cond: *mut pthread_cond_t,
+                                  lock: *mut pthread_mutex_t,
+                                  abstime: *const libc::timespec) -> libc::c_int;
         fn pthread_cond_signal(cond: *mut pthread_cond_t) -> libc::c_int;
+
+        fn gettimeofday(tp: *mut libc::timeval,
+                        tz: *mut libc::c_void) -> libc::c_int;
     }
 }
 
@@ -565,6 +614,19 @@ mod imp {
             self.lock();
         }
 
+        pub unsafe fn wait_timeout(&self, ms: u64) -> bool {
+            // Timeouts too long for a DWORD are as good as infinite
+            let ms = if ms >= libc::INFINITE as u64 {
+                libc::INFINITE - 1
+            } else {
+                ms as DWORD
+            };
+            self.unlock();
+            let ret = WaitForSingleObject(self.getcond() as HANDLE, ms);
+            self.lock();
+            ret != libc::consts::os::extra::WAIT_TIMEOUT
+        }
+
         pub unsafe fn signal(&self) {
             assert!(SetEvent(self.getcond() as HANDLE) != 0);
         }
@@ -679,6 +741,18 @@ mod test {
         }
     }
 
+    #[test]
+    fn smoke_cond_timeout() {
+        static mut lock: StaticNativeMutex = NATIVE_MUTEX_INIT;
+        unsafe {
+            let guard = lock.lock();
+            // Nothing signals the condition variable, so this has to time out
+            // eventually even if it wakes up spuriously
+            while guard.wait_timeout(10) {}
+            drop(guard);
+        }
+    }
+
     #[test]
     fn smoke_lock_noguard() {
         static mut lock: StaticNativeMutex = NATIVE_MUTEX_INIT;
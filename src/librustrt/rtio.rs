@@ -117,6 +117,36 @@ pub struct ProcessConfig<'a> {
     /// If true, the child process is spawned in a detached state. On unix, this
     /// means that the child is the leader of a new process group.
     pub detach: bool,
+
+    /// Moves the child into the process group with the given id, which
+    /// translates to a `setpgid` call in the child. An id of 0 makes the
+    /// child the leader of a new process group. This is not supported on
+    /// windows.
+    pub pgid: Option<uint>,
+
+    /// Resource limits to set with `setrlimit` in the child before it execs.
+    /// This is not supported on windows.
+    pub rlimits: &'a [ResourceLimit],
+}
+
+/// A resource whose use by a child process can be limited.
+pub enum Resource {
+    CpuTime,
+    FileSize,
+    DataSize,
+    StackSize,
+    CoreFileSize,
+    OpenFiles,
+    AddressSpace,
+    Processes,
+}
+
+/// A limit on a resource, as set by `setrlimit`. A limit of `None` is
+/// unlimited.
+pub struct ResourceLimit {
+    pub resource: Resource,
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
 }
 
 pub struct LocalIo<'a> {
@@ -188,12 +218,16 @@ impl<'a> LocalIo<'a> {
 
 pub trait IoFactory {
     // networking
-    fn tcp_connect(&mut self, addr: SocketAddr,
+    fn tcp_connect(&mut self, addr: SocketAddr, opts: &SocketOptions,
                    timeout: Option<u64>) -> IoResult<Box<RtioTcpStream + Send>>;
-    fn tcp_bind(&mut self, addr: SocketAddr)
+    fn tcp_bind(&mut self, addr: SocketAddr, opts: &SocketOptions)
                 -> IoResult<Box<RtioTcpListener + Send>>;
-    fn udp_bind(&mut self, addr: SocketAddr)
+    fn udp_bind(&mut self, addr: SocketAddr, opts: &SocketOptions)
                 -> IoResult<Box<RtioUdpSocket + Send>>;
+    fn raw_bind(&mut self, addr: IpAddr, protocol: u8)
+                -> IoResult<Box<RtioRawSocket + Send>>;
+    fn packet_bind(&mut self, ifindex: uint, protocol: u16)
+                   -> IoResult<Box<RtioPacketSocket + Send>>;
     fn unix_bind(&mut self, path: &CString)
                  -> IoResult<Box<RtioUnixListener + Send>>;
     fn unix_connect(&mut self, path: &CString,
@@ -215,6 +249,8 @@ pub trait IoFactory {
     fn fs_rename(&mut self, path: &CString, to: &CString) -> IoResult<()>;
     fn fs_readdir(&mut self, path: &CString, flags: c_int) ->
         IoResult<Vec<CString>>;
+    fn fs_readdir_entries(&mut self, path: &CString, flags: c_int) ->
+        IoResult<Vec<DirEntry>>;
     fn fs_lstat(&mut self, path: &CString) -> IoResult<FileStat>;
     fn fs_chown(&mut self, path: &CString, uid: int, gid: int) ->
         IoResult<()>;
@@ -223,6 +259,9 @@ pub trait IoFactory {
     fn fs_link(&mut self, src: &CString, dst: &CString) -> IoResult<()>;
     fn fs_utime(&mut self, src: &CString, atime: u64, mtime: u64) ->
         IoResult<()>;
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<FsEventCallback + Send>)
+                -> IoResult<Box<RtioFsWatcher + Send>>;
 
     // misc
     fn timer_init(&mut self) -> IoResult<Box<RtioTimer + Send>>;
@@ -235,6 +274,7 @@ pub trait IoFactory {
             -> IoResult<Box<RtioTTY + Send>>;
     fn signal(&mut self, signal: int, cb: Box<Callback + Send>)
         -> IoResult<Box<RtioSignal + Send>>;
+    fn poller_init(&mut self) -> IoResult<Box<RtioPoller + Send>>;
 }
 
 pub trait RtioTcpListener : RtioSocket {
@@ -253,9 +293,12 @@ pub trait RtioTcpAcceptor : RtioSocket {
 pub trait RtioTcpStream : RtioSocket {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint>;
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()>;
     fn peer_name(&mut self) -> IoResult<SocketAddr>;
     fn control_congestion(&mut self) -> IoResult<()>;
     fn nodelay(&mut self) -> IoResult<()>;
+    fn quickack(&mut self, on: bool) -> IoResult<()>;
     fn keepalive(&mut self, delay_in_seconds: uint) -> IoResult<()>;
     fn letdie(&mut self) -> IoResult<()>;
     fn clone(&self) -> Box<RtioTcpStream + Send>;
@@ -268,6 +311,10 @@ pub trait RtioTcpStream : RtioSocket {
 
 pub trait RtioSocket {
     fn socket_name(&mut self) -> IoResult<SocketAddr>;
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()>;
+    /// Returns the OS-level descriptor backing this socket, if there is one
+    /// which can be handed to an `RtioPoller`.
+    fn raw_fd(&self) -> Option<c_int>;
 }
 
 pub trait RtioUdpSocket : RtioSocket {
@@ -292,6 +339,45 @@ pub trait RtioUdpSocket : RtioSocket {
     fn set_write_timeout(&mut self, timeout_ms: Option<u64>);
 }
 
+/// A raw IP socket, which sends and receives packets of a single IP protocol
+/// such as ICMP.
+pub trait RtioRawSocket {
+    fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, IpAddr)>;
+    fn send_to(&mut self, buf: &[u8], dst: IpAddr) -> IoResult<()>;
+
+    fn bind_to_interface(&mut self, ifindex: uint) -> IoResult<()>;
+    fn attach_filter(&mut self, filter: &[FilterInstruction]) -> IoResult<()>;
+    /// IP_HDRINCL: whether packets sent include their own IP header.
+    fn header_included(&mut self, on: bool) -> IoResult<()>;
+    fn time_to_live(&mut self, ttl: int) -> IoResult<()>;
+
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()>;
+    fn raw_fd(&self) -> Option<c_int>;
+    fn clone(&self) -> Box<RtioRawSocket + Send>;
+    fn set_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_read_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>);
+}
+
+/// A link-layer packet socket, which sends and receives whole frames
+/// (including their link-layer header) on one or all interfaces.
+pub trait RtioPacketSocket {
+    fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, LinkAddr)>;
+    fn send_to(&mut self, buf: &[u8], dst: &LinkAddr) -> IoResult<()>;
+
+    fn attach_filter(&mut self, filter: &[FilterInstruction]) -> IoResult<()>;
+    /// Puts the interface the socket is bound to into (or out of)
+    /// promiscuous mode for as long as the socket is open.
+    fn promiscuous(&mut self, on: bool) -> IoResult<()>;
+
+    fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()>;
+    fn raw_fd(&self) -> Option<c_int>;
+    fn clone(&self) -> Box<RtioPacketSocket + Send>;
+    fn set_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_read_timeout(&mut self, timeout_ms: Option<u64>);
+    fn set_write_timeout(&mut self, timeout_ms: Option<u64>);
+}
+
 pub trait RtioTimer {
     fn sleep(&mut self, msecs: u64);
     fn oneshot(&mut self, msecs: u64, cb: Box<Callback + Send>);
@@ -301,6 +387,8 @@ pub trait RtioTimer {
 pub trait RtioFileStream {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<int>;
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int>;
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()>;
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int>;
     fn pwrite(&mut self, buf: &[u8], offset: u64) -> IoResult<()>;
     fn seek(&mut self, pos: i64, whence: SeekStyle) -> IoResult<u64>;
@@ -309,11 +397,17 @@ pub trait RtioFileStream {
     fn datasync(&mut self) -> IoResult<()>;
     fn truncate(&mut self, offset: i64) -> IoResult<()>;
     fn fstat(&mut self) -> IoResult<FileStat>;
+    /// Returns the OS-level descriptor backing this file, if there is one
+    /// which can be used to map the file into memory.
+    fn raw_fd(&self) -> Option<c_int>;
 }
 
 pub trait RtioProcess {
     fn id(&self) -> libc::pid_t;
     fn kill(&mut self, signal: int) -> IoResult<()>;
+    /// Sends a signal to every process in the process group which the child
+    /// was spawned into, if it was spawned into a group other than ours.
+    fn kill_group(&mut self, signal: int) -> IoResult<()>;
     fn wait(&mut self) -> IoResult<ProcessExit>;
     fn set_timeout(&mut self, timeout: Option<u64>);
 }
@@ -356,6 +450,56 @@ pub trait PausableIdleCallback {
 
 pub trait RtioSignal {}
 
+/// Dropping a watcher stops the delivery of events to its callback.
+pub trait RtioFsWatcher {}
+
+pub enum FsEventKind {
+    FsCreate,
+    FsModify,
+    FsDelete,
+    /// The path was either renamed from or to. Some implementations are unable
+    /// to distinguish creation and deletion from renames, and will report all
+    /// of them as this kind.
+    FsRename,
+}
+
+pub struct FsEvent {
+    pub kind: FsEventKind,
+    pub path: CString,
+}
+
+pub trait FsEventCallback {
+    fn call(&mut self, event: FsEvent);
+}
+
+/// Readiness bits used both to register interest with an `RtioPoller` and to
+/// report which conditions a descriptor was found in.
+pub static POLL_READABLE: uint = 1 << 0;
+pub static POLL_WRITABLE: uint = 1 << 1;
+pub static POLL_HANGUP: uint = 1 << 2;
+pub static POLL_ERROR: uint = 1 << 3;
+
+pub trait RtioPoller {
+    /// Starts watching `fd` for the readiness bits in `interest`. Events for
+    /// this descriptor are reported with the given `token`. If `edge` is true
+    /// then events are only reported on changes in readiness.
+    fn register(&mut self, fd: c_int, token: uint, interest: uint,
+                edge: bool) -> IoResult<()>;
+    fn reregister(&mut self, fd: c_int, token: uint, interest: uint,
+                  edge: bool) -> IoResult<()>;
+    fn deregister(&mut self, fd: c_int) -> IoResult<()>;
+    /// Waits for at most `timeout_ms` milliseconds (forever if `None`) for any
+    /// registered descriptor to become ready, replacing the contents of
+    /// `events` with at most `max` events.
+    fn poll(&mut self, events: &mut Vec<PollEvent>, max: uint,
+            timeout_ms: Option<u64>) -> IoResult<()>;
+}
+
+pub struct PollEvent {
+    pub token: uint,
+    pub readiness: uint,
+}
+
 pub struct IoError {
     pub code: uint,
     pub extra: uint,
@@ -389,6 +533,49 @@ pub struct SocketAddr {
     pub port: u16,
 }
 
+/// The address of a frame sent or received on a packet socket.
+pub struct LinkAddr {
+    /// The index of the interface, or 0 for any interface.
+    pub ifindex: uint,
+    /// The link-layer protocol (ethertype), in host byte order.
+    pub protocol: u16,
+    /// The hardware address of the other end, at most 8 bytes.
+    pub address: Vec<u8>,
+}
+
+/// A single classic BPF instruction, laid out as `struct sock_filter`.
+#[repr(C)]
+pub struct FilterInstruction {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+/// Options applied to a socket after it has been created but before it is
+/// bound or connected. Serializes the `std::io::net::SocketBuilder` builder.
+///
+/// Any option which is `None` is left at the platform's default.
+pub struct SocketOptions {
+    /// SO_REUSEADDR. Listeners on unix default to setting this.
+    pub reuse_address: Option<bool>,
+    /// SO_REUSEPORT. Not available on windows.
+    pub reuse_port: Option<bool>,
+    /// SO_RCVBUF, in bytes.
+    pub recv_buffer_size: Option<uint>,
+    /// SO_SNDBUF, in bytes.
+    pub send_buffer_size: Option<uint>,
+    /// SO_LINGER. `Some(None)` turns lingering off, while `Some(Some(n))`
+    /// lingers for up to `n` seconds.
+    pub linger: Option<Option<uint>>,
+    /// IPV6_V6ONLY. Ignored for IPv4 sockets.
+    pub only_v6: Option<bool>,
+    /// TCP_QUICKACK. Only available on linux.
+    pub quickack: Option<bool>,
+    /// The length of the queue of pending connections handed to `listen`.
+    pub backlog: Option<uint>,
+}
+
 pub enum StdioContainer {
     Ignored,
     InheritFd(i32),
@@ -404,6 +591,7 @@ pub enum FileMode {
     Open,
     Append,
     Truncate,
+    CreateNew,
 }
 
 pub enum FileAccess {
@@ -412,6 +600,14 @@ pub enum FileAccess {
     ReadWrite,
 }
 
+/// An entry returned by `fs_readdir_entries`.
+pub struct DirEntry {
+    pub path: CString,
+    /// The `S_IFMT` bits of the entry's mode, if they were returned by the
+    /// directory listing itself, or 0 if the type is unknown without a stat.
+    pub kind: u64,
+}
+
 pub struct FileStat {
     pub size: u64,
     pub kind: u64,
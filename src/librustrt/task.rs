@@ -421,6 +421,22 @@ impl Task {
         ops.deschedule(amt, self, f)
     }
 
+    /// Deschedules the current task like `deschedule`, but wakes it up again
+    /// after `ms` milliseconds if none of the `amt` blocked handles has been
+    /// woken by then.
+    ///
+    /// Whoever holds one of the handles when the timeout elapses will find that
+    /// waking it fails, as for the handles of a task blocked in a select, so
+    /// the callers of this function must be prepared to tell a timeout apart
+    /// from a wakeup by themselves.
+    pub fn deschedule_timeout(mut self: Box<Task>,
+                              amt: uint,
+                              ms: u64,
+                              f: |BlockedTask| -> ::core::result::Result<(), BlockedTask>) {
+        let ops = self.imp.take().unwrap();
+        ops.deschedule_timeout(amt, ms, self, f)
+    }
+
     /// Wakes up a previously blocked task, optionally specifying whether the
     /// current task can accept a change in scheduling. This function can only
     /// be called on tasks that were previously blocked in `deschedule`.
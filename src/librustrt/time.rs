@@ -0,0 +1,97 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The clock used for timeouts and deadlines on blocking operations
+//!
+//! Deadlines given to the runtime, such as those of `Receiver::recv_deadline`,
+//! are absolute values of this clock. It is monotonic, so unlike the time of
+//! day it does not jump around when the system clock is adjusted.
+
+/// Returns the current value of the runtime's monotonic clock, in
+/// milliseconds since an unspecified epoch.
+pub fn now() -> u64 {
+    imp::precise_time_ns() / 1000000
+}
+
+#[cfg(windows)]
+mod imp {
+    use libc;
+
+    pub fn precise_time_ns() -> u64 {
+        let mut ticks_per_s = 0;
+        assert_eq!(unsafe {
+            libc::QueryPerformanceFrequency(&mut ticks_per_s)
+        }, 1);
+        let ticks_per_s = if ticks_per_s == 0 {1} else {ticks_per_s};
+        let mut ticks = 0;
+        assert_eq!(unsafe {
+            libc::QueryPerformanceCounter(&mut ticks)
+        }, 1);
+
+        // Split the division to avoid overflowing the intermediate product
+        let ticks = ticks as u64;
+        let ticks_per_s = ticks_per_s as u64;
+        (ticks / ticks_per_s) * 1000000000 +
+            (ticks % ticks_per_s) * 1000000000 / ticks_per_s
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "ios")]
+mod imp {
+    use libc::{c_int, mach_timebase_info};
+
+    pub fn precise_time_ns() -> u64 {
+        let mut info = mach_timebase_info { numer: 0, denom: 0 };
+        unsafe {
+            mach_timebase_info(&mut info);
+            mach_absolute_time() * info.numer as u64 / info.denom as u64
+        }
+    }
+
+    extern {
+        fn mach_absolute_time() -> u64;
+        fn mach_timebase_info(info: *mut mach_timebase_info) -> c_int;
+    }
+}
+
+#[cfg(unix, not(target_os = "macos"), not(target_os = "ios"))]
+mod imp {
+    use libc::{c_int, timespec, CLOCK_MONOTONIC};
+
+    pub fn precise_time_ns() -> u64 {
+        let mut ts = timespec { tv_sec: 0, tv_nsec: 0 };
+        unsafe {
+            clock_gettime(CLOCK_MONOTONIC, &mut ts);
+        }
+        (ts.tv_sec as u64) * 1000000000 + (ts.tv_nsec as u64)
+    }
+
+    // Apparently android provides this in some other library?
+    #[cfg(not(target_os = "android"))]
+    #[link(name = "rt")]
+    extern {}
+
+    extern {
+        fn clock_gettime(clk_id: c_int, tp: *mut timespec) -> c_int;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::now;
+
+    #[test]
+    fn monotonic() {
+        let a = now();
+        let b = now();
+        assert!(b >= a);
+    }
+}
@@ -112,6 +112,16 @@ impl Unwinder {
     pub fn unwinding(&self) -> bool {
         self.unwinding
     }
+
+    /// Sets whether the task is unwinding, returning whether it was.
+    ///
+    /// This lets code which catches failure with `try` short of the task's
+    /// outermost frame carry on running, and run code which may itself fail
+    /// and be caught while the task is already unwinding. It's up to the
+    /// caller to restore the flag afterwards.
+    pub unsafe fn set_unwinding(&mut self, unwinding: bool) -> bool {
+        mem::replace(&mut self.unwinding, unwinding)
+    }
 }
 
 /// Invoke a closure, capturing the cause of failure if one occurs.
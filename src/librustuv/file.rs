@@ -420,6 +420,18 @@ impl rtio::RtioFileStream for FileWatcher {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.base_write(buf, -1)
     }
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<int> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.base_read(&mut **buf, -1),
+            None => Ok(0),
+        }
+    }
+    fn writev(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.base_write(*buf, -1));
+        }
+        Ok(())
+    }
     fn pread(&mut self, buf: &mut [u8], offset: u64) -> IoResult<int> {
         self.base_read(buf, offset as i64)
     }
@@ -458,6 +470,7 @@ impl rtio::RtioFileStream for FileWatcher {
         let _m = self.fire_homing_missile();
         FsRequest::fstat(&self.loop_, self.fd).map_err(uv_error_to_io_error)
     }
+    fn raw_fd(&self) -> Option<c_int> { Some(self.fd) }
 }
 
 #[cfg(test)]
@@ -0,0 +1,100 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use libc;
+use libc::{c_int, c_char};
+use std::c_str::CString;
+use std::rt::rtio::{RtioFsWatcher, FsEventCallback, FsEvent};
+use std::rt::rtio;
+
+use file::FsRequest;
+use homing::{HomingIO, HomeHandle};
+use super::{UvError, UvHandle};
+use uvll;
+use uvio::UvIoFactory;
+
+pub struct FsEventWatcher {
+    handle: *mut uvll::uv_fs_event_t,
+    home: HomeHandle,
+
+    // libuv reports names relative to the watched directory, or the name of
+    // the watched file itself, so this is the prefix they are joined to.
+    dir: Vec<u8>,
+    cb: Box<FsEventCallback + Send>,
+}
+
+impl FsEventWatcher {
+    pub fn new(io: &mut UvIoFactory, path: &CString, recursive: bool,
+               cb: Box<FsEventCallback + Send>)
+               -> Result<Box<FsEventWatcher>, UvError> {
+        let stat = try!(FsRequest::stat(&io.loop_, path));
+        let mut dir = path.as_bytes_no_nul().to_vec();
+        if stat.kind & libc::S_IFMT as u64 != libc::S_IFDIR as u64 {
+            match dir.as_slice().iter().rposition(|&b| b == b'/') {
+                Some(i) => dir.truncate(i),
+                None => dir.truncate(0),
+            }
+        }
+
+        let w = box FsEventWatcher {
+            handle: UvHandle::alloc(None::<FsEventWatcher>, uvll::UV_FS_EVENT),
+            home: io.make_handle(),
+            dir: dir,
+            cb: cb,
+        };
+        assert_eq!(unsafe {
+            uvll::uv_fs_event_init(io.uv_loop(), w.handle)
+        }, 0);
+
+        let flags = if recursive {uvll::UV_FS_EVENT_RECURSIVE} else {0};
+        match unsafe {
+            uvll::uv_fs_event_start(w.handle, fs_event_cb, path.as_ptr(), flags)
+        } {
+            0 => Ok(w.install()),
+            n => Err(UvError(n)),
+        }
+    }
+}
+
+extern fn fs_event_cb(handle: *mut uvll::uv_fs_event_t, filename: *const c_char,
+                      events: c_int, status: c_int) {
+    if status != 0 { return }
+    let w: &mut FsEventWatcher = unsafe { UvHandle::from_uv_handle(&handle) };
+
+    let mut path = w.dir.clone();
+    if !filename.is_null() {
+        let name = unsafe { CString::new(filename, false) };
+        if path.len() > 0 { path.push(b'/'); }
+        path.push_all(name.as_bytes_no_nul());
+    }
+    let kind = if events & uvll::UV_RENAME != 0 {
+        rtio::FsRename
+    } else {
+        rtio::FsModify
+    };
+    w.cb.call(FsEvent { kind: kind, path: path.as_slice().to_c_str() });
+}
+
+impl HomingIO for FsEventWatcher {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl UvHandle<uvll::uv_fs_event_t> for FsEventWatcher {
+    fn uv_handle(&self) -> *mut uvll::uv_fs_event_t { self.handle }
+}
+
+impl RtioFsWatcher for FsEventWatcher {}
+
+impl Drop for FsEventWatcher {
+    fn drop(&mut self) {
+        let _m = self.fire_homing_missile();
+        self.close();
+    }
+}
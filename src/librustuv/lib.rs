@@ -69,6 +69,7 @@ use std::task;
 
 pub use self::async::AsyncWatcher;
 pub use self::file::{FsRequest, FileWatcher};
+pub use self::fs_event::FsEventWatcher;
 pub use self::idle::IdleWatcher;
 pub use self::net::{TcpWatcher, TcpListener, TcpAcceptor, UdpWatcher};
 pub use self::pipe::{PipeWatcher, PipeListener, PipeAcceptor};
@@ -95,6 +96,7 @@ pub mod uvio;
 pub mod uvll;
 
 pub mod file;
+pub mod fs_event;
 pub mod net;
 pub mod idle;
 pub mod timer;
@@ -419,6 +421,7 @@ pub fn uv_error_to_io_error(uverr: UvError) -> IoError {
             uvll::ECANCELED => libc::ERROR_OPERATION_ABORTED,
             uvll::EADDRINUSE => libc::WSAEADDRINUSE,
             uvll::EPERM => libc::ERROR_ACCESS_DENIED,
+            uvll::ENOSYS => libc::ERROR_CALL_NOT_IMPLEMENTED,
             err => {
                 uvdebug!("uverr.code {}", err as int);
                 // FIXME: Need to map remaining uv error types
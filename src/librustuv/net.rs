@@ -144,6 +144,13 @@ fn socket_name(sk: SocketNameKind,
     }
 }
 
+// libuv owns the readiness of its handles, so they can be neither placed in
+// nonblocking mode nor handed to a separate poller, which is why none of the
+// sockets here have a raw descriptor either.
+fn nonblocking_unsupported() -> Result<(), IoError> {
+    Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 /// TCP implementation
 ////////////////////////////////////////////////////////////////////////////////
@@ -165,6 +172,7 @@ pub struct TcpWatcher {
 pub struct TcpListener {
     home: HomeHandle,
     handle: *mut uvll::uv_tcp_t,
+    backlog: c_int,
 }
 
 pub struct TcpAcceptor {
@@ -220,6 +228,11 @@ impl rtio::RtioSocket for TcpWatcher {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.handle)
     }
+
+    fn set_nonblocking(&mut self, _nonblocking: bool) -> Result<(), IoError> {
+        nonblocking_unsupported()
+    }
+    fn raw_fd(&self) -> Option<c_int> { None }
 }
 
 impl rtio::RtioTcpStream for TcpWatcher {
@@ -241,6 +254,22 @@ impl rtio::RtioTcpStream for TcpWatcher {
         self.stream.write(buf, guard.can_timeout).map_err(uv_error_to_io_error)
     }
 
+    // FIXME: uv_read_start/uv_write can take multiple buffers, but StreamWatcher
+    //        only knows how to deal with one at a time.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> Result<uint, IoError> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+
+    fn writev(&mut self, bufs: &[&[u8]]) -> Result<(), IoError> {
+        for buf in bufs.iter() {
+            try!(self.write(*buf));
+        }
+        Ok(())
+    }
+
     fn peer_name(&mut self) -> Result<rtio::SocketAddr, IoError> {
         let _m = self.fire_homing_missile();
         socket_name(TcpPeer, self.handle)
@@ -253,6 +282,10 @@ impl rtio::RtioTcpStream for TcpWatcher {
         })
     }
 
+    fn quickack(&mut self, _on: bool) -> Result<(), IoError> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
     fn nodelay(&mut self) -> Result<(), IoError> {
         let _m = self.fire_homing_missile();
         status_to_io_result(unsafe {
@@ -357,6 +390,7 @@ impl TcpListener {
         let l = box TcpListener {
             home: io.make_handle(),
             handle: handle,
+            backlog: 128,
         };
         let mut storage = unsafe { mem::zeroed() };
         let _len = addr_to_sockaddr(address, &mut storage);
@@ -369,6 +403,12 @@ impl TcpListener {
             n => Err(UvError(n))
         };
     }
+
+    /// Sets the length of the queue of pending connections which is requested
+    /// when this listener starts listening.
+    pub fn set_backlog(&mut self, backlog: uint) {
+        self.backlog = backlog as c_int;
+    }
 }
 
 impl HomingIO for TcpListener {
@@ -384,6 +424,11 @@ impl rtio::RtioSocket for TcpListener {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.handle)
     }
+
+    fn set_nonblocking(&mut self, _nonblocking: bool) -> Result<(), IoError> {
+        nonblocking_unsupported()
+    }
+    fn raw_fd(&self) -> Option<c_int> { None }
 }
 
 impl rtio::RtioTcpListener for TcpListener {
@@ -400,8 +445,9 @@ impl rtio::RtioTcpListener for TcpListener {
         }).install();
         self.handle = 0 as *mut _;
 
-        // FIXME: the 128 backlog should be configurable
-        match unsafe { uvll::uv_listen(acceptor.handle, 128, listen_cb) } {
+        match unsafe {
+            uvll::uv_listen(acceptor.handle, self.backlog, listen_cb)
+        } {
             0 => Ok(acceptor as Box<rtio::RtioTcpAcceptor + Send>),
             n => Err(uv_error_to_io_error(UvError(n))),
         }
@@ -447,6 +493,11 @@ impl rtio::RtioSocket for TcpAcceptor {
         let _m = self.fire_homing_missile();
         socket_name(Tcp, self.handle)
     }
+
+    fn set_nonblocking(&mut self, _nonblocking: bool) -> Result<(), IoError> {
+        nonblocking_unsupported()
+    }
+    fn raw_fd(&self) -> Option<c_int> { None }
 }
 
 impl UvHandle<uvll::uv_tcp_t> for TcpAcceptor {
@@ -573,6 +624,11 @@ impl rtio::RtioSocket for UdpWatcher {
         let _m = self.fire_homing_missile();
         socket_name(Udp, self.handle)
     }
+
+    fn set_nonblocking(&mut self, _nonblocking: bool) -> Result<(), IoError> {
+        nonblocking_unsupported()
+    }
+    fn raw_fd(&self) -> Option<c_int> { None }
 }
 
 impl rtio::RtioUdpSocket for UdpWatcher {
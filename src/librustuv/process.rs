@@ -37,6 +37,9 @@ pub struct Process {
     /// Lazily initialized timeout timer
     timer: Option<Box<TimerWatcher>>,
     timeout_state: TimeoutState,
+
+    /// Whether the child leads a process group of its own
+    detached: bool,
 }
 
 enum TimeoutState {
@@ -52,6 +55,11 @@ impl Process {
     /// occurred.
     pub fn spawn(io_loop: &mut UvIoFactory, cfg: rtio::ProcessConfig)
                 -> Result<(Box<Process>, Vec<Option<PipeWatcher>>), UvError> {
+        // libuv has no way of running code in the child before it execs
+        if cfg.pgid.is_some() || cfg.rlimits.len() > 0 {
+            return Err(UvError(uvll::ENOSYS))
+        }
+
         let mut io = vec![cfg.stdin, cfg.stdout, cfg.stderr];
         for slot in cfg.extra_io.iter() {
             io.push(*slot);
@@ -103,6 +111,7 @@ impl Process {
                     exit_status: None,
                     timer: None,
                     timeout_state: NoTimeout,
+                    detached: cfg.detach,
                 };
                 match unsafe {
                     uvll::uv_spawn(io_loop.uv_loop(), handle, &mut options)
@@ -247,6 +256,15 @@ impl rtio::RtioProcess for Process {
         }
     }
 
+    fn kill_group(&mut self, signal: int) -> IoResult<()> {
+        if !self.detached || cfg!(windows) {
+            return Err(uv_error_to_io_error(UvError(uvll::EINVAL)))
+        }
+        let pid = unsafe { uvll::process_pid(self.handle) };
+        Process::kill(-pid as libc::pid_t, signal)
+                .map_err(uv_error_to_io_error)
+    }
+
     fn wait(&mut self) -> IoResult<rtio::ProcessExit> {
         // Make sure (on the home scheduler) that we have an exit status listed
         let _m = self.fire_homing_missile();
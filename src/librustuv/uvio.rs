@@ -21,11 +21,12 @@ use std::rt::rtio::{ProcessConfig, IoFactory, EventLoop, IoResult};
 
 #[cfg(test)] use std::rt::thread::Thread;
 
-use super::{uv_error_to_io_error, Loop};
+use super::{uv_error_to_io_error, Loop, UvError};
 
 use addrinfo::GetAddrInfoRequest;
 use async::AsyncWatcher;
 use file::{FsRequest, FileWatcher};
+use fs_event::FsEventWatcher;
 use queue::QueuePool;
 use homing::HomeHandle;
 use idle::IdleWatcher;
@@ -136,34 +137,69 @@ impl UvIoFactory {
     }
 }
 
+// libuv creates its sockets inside of the bind/connect calls themselves, so
+// there's no point at which options could be applied beforehand. Only the
+// listen backlog (which is applied later on) is supported.
+fn check_socket_options(opts: &rtio::SocketOptions) -> IoResult<()> {
+    if opts.reuse_address.is_some() || opts.reuse_port.is_some() ||
+       opts.recv_buffer_size.is_some() || opts.send_buffer_size.is_some() ||
+       opts.linger.is_some() || opts.only_v6.is_some() ||
+       opts.quickack.is_some() {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    } else {
+        Ok(())
+    }
+}
+
 impl IoFactory for UvIoFactory {
     // Connect to an address and return a new stream
     // NB: This blocks the task waiting on the connection.
     // It would probably be better to return a future
-    fn tcp_connect(&mut self, addr: rtio::SocketAddr, timeout: Option<u64>)
+    fn tcp_connect(&mut self, addr: rtio::SocketAddr,
+                   opts: &rtio::SocketOptions, timeout: Option<u64>)
                    -> IoResult<Box<rtio::RtioTcpStream + Send>> {
+        try!(check_socket_options(opts));
         match TcpWatcher::connect(self, addr, timeout) {
             Ok(t) => Ok(box t as Box<rtio::RtioTcpStream + Send>),
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
 
-    fn tcp_bind(&mut self, addr: rtio::SocketAddr)
+    fn tcp_bind(&mut self, addr: rtio::SocketAddr, opts: &rtio::SocketOptions)
                 -> IoResult<Box<rtio::RtioTcpListener + Send>> {
+        try!(check_socket_options(opts));
         match TcpListener::bind(self, addr) {
-            Ok(t) => Ok(t as Box<rtio::RtioTcpListener + Send>),
+            Ok(mut t) => {
+                match opts.backlog {
+                    Some(n) => t.set_backlog(n),
+                    None => {}
+                }
+                Ok(t as Box<rtio::RtioTcpListener + Send>)
+            }
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
 
-    fn udp_bind(&mut self, addr: rtio::SocketAddr)
+    fn udp_bind(&mut self, addr: rtio::SocketAddr, opts: &rtio::SocketOptions)
                 -> IoResult<Box<rtio::RtioUdpSocket + Send>> {
+        try!(check_socket_options(opts));
         match UdpWatcher::bind(self, addr) {
             Ok(u) => Ok(box u as Box<rtio::RtioUdpSocket + Send>),
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
 
+    // libuv has no support for raw or packet sockets
+    fn raw_bind(&mut self, _addr: rtio::IpAddr, _protocol: u8)
+                -> IoResult<Box<rtio::RtioRawSocket + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
+    fn packet_bind(&mut self, _ifindex: uint, _protocol: u16)
+                   -> IoResult<Box<rtio::RtioPacketSocket + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
+
     fn timer_init(&mut self) -> IoResult<Box<rtio::RtioTimer + Send>> {
         Ok(TimerWatcher::new(self) as Box<rtio::RtioTimer + Send>)
     }
@@ -190,10 +226,11 @@ impl IoFactory for UvIoFactory {
             rtio::Open => 0,
             rtio::Append => libc::O_APPEND,
             rtio::Truncate => libc::O_TRUNC,
+            rtio::CreateNew => libc::O_CREAT | libc::O_EXCL,
         };
         // Opening with a write permission must silently create the file.
         let (flags, mode) = match fa {
-            rtio::Read => (flags | libc::O_RDONLY, 0),
+            rtio::Read => (flags | libc::O_RDONLY, libc::S_IRUSR | libc::S_IWUSR),
             rtio::Write => (flags | libc::O_WRONLY | libc::O_CREAT,
                             libc::S_IRUSR | libc::S_IWUSR),
             rtio::ReadWrite => (flags | libc::O_RDWR | libc::O_CREAT,
@@ -240,6 +277,18 @@ impl IoFactory for UvIoFactory {
         let r = FsRequest::readdir(&self.loop_, path, flags);
         r.map_err(uv_error_to_io_error)
     }
+    fn fs_readdir_entries(&mut self, path: &CString, flags: c_int)
+        -> IoResult<Vec<rtio::DirEntry>>
+    {
+        // uv_fs_readdir doesn't return the types of entries, so they are left
+        // unknown to be found out with a stat if needed.
+        let r = FsRequest::readdir(&self.loop_, path, flags);
+        r.map(|paths| {
+            paths.move_iter().map(|p| {
+                rtio::DirEntry { path: p, kind: 0 }
+            }).collect()
+        }).map_err(uv_error_to_io_error)
+    }
     fn fs_link(&mut self, src: &CString, dst: &CString) -> IoResult<()> {
         let r = FsRequest::link(&self.loop_, src, dst);
         r.map_err(uv_error_to_io_error)
@@ -262,6 +311,15 @@ impl IoFactory for UvIoFactory {
         let r = FsRequest::utime(&self.loop_, path, atime, mtime);
         r.map_err(uv_error_to_io_error)
     }
+    fn fs_watch(&mut self, path: &CString, recursive: bool,
+                cb: Box<rtio::FsEventCallback + Send>)
+        -> IoResult<Box<rtio::RtioFsWatcher + Send>>
+    {
+        match FsEventWatcher::new(self, path, recursive, cb) {
+            Ok(w) => Ok(w as Box<rtio::RtioFsWatcher + Send>),
+            Err(e) => Err(uv_error_to_io_error(e)),
+        }
+    }
 
     fn spawn(&mut self, cfg: ProcessConfig)
             -> IoResult<(Box<rtio::RtioProcess + Send>,
@@ -323,4 +381,8 @@ impl IoFactory for UvIoFactory {
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
+
+    fn poller_init(&mut self) -> IoResult<Box<rtio::RtioPoller + Send>> {
+        Err(uv_error_to_io_error(UvError(uvll::ENOSYS)))
+    }
 }
@@ -39,7 +39,7 @@ use libc::uintptr_t;
 
 pub use self::errors::{EACCES, ECONNREFUSED, ECONNRESET, EPIPE, ECONNABORTED,
                        ECANCELED, EBADF, ENOTCONN, ENOENT, EADDRNOTAVAIL,
-                       EADDRINUSE, EPERM};
+                       EADDRINUSE, EPERM, ENOSYS, EINVAL};
 
 pub static OK: c_int = 0;
 pub static EOF: c_int = -4095;
@@ -64,6 +64,8 @@ pub mod errors {
     pub static EADDRNOTAVAIL: c_int = -4090;
     pub static EADDRINUSE: c_int = -4091;
     pub static EPERM: c_int = -4048;
+    pub static ENOSYS: c_int = -4054;
+    pub static EINVAL: c_int = -4071;
 }
 #[cfg(not(windows))]
 pub mod errors {
@@ -82,6 +84,8 @@ pub mod errors {
     pub static EADDRNOTAVAIL : c_int = -libc::EADDRNOTAVAIL;
     pub static EADDRINUSE : c_int = -libc::EADDRINUSE;
     pub static EPERM: c_int = -libc::EPERM;
+    pub static ENOSYS: c_int = -libc::ENOSYS;
+    pub static EINVAL: c_int = -libc::EINVAL;
 }
 
 pub static PROCESS_SETUID: c_int = 1 << 0;
@@ -97,6 +101,10 @@ pub static STDIO_INHERIT_STREAM: c_int = 0x04;
 pub static STDIO_READABLE_PIPE: c_int = 0x10;
 pub static STDIO_WRITABLE_PIPE: c_int = 0x20;
 
+pub static UV_RENAME: c_int = 1;
+pub static UV_CHANGE: c_int = 2;
+pub static UV_FS_EVENT_RECURSIVE: c_uint = 4;
+
 #[cfg(unix)]
 pub type uv_buf_len_t = libc::size_t;
 #[cfg(windows)]
@@ -179,6 +187,7 @@ pub type uv_process_t = c_void;
 pub type uv_pipe_t = c_void;
 pub type uv_tty_t = c_void;
 pub type uv_signal_t = c_void;
+pub type uv_fs_event_t = c_void;
 pub type uv_shutdown_t = c_void;
 
 #[repr(C)]
@@ -273,6 +282,10 @@ pub type uv_exit_cb = extern "C" fn(handle: *mut uv_process_t,
 pub type uv_signal_cb = extern "C" fn(handle: *mut uv_signal_t,
                                       signum: c_int);
 pub type uv_fs_cb = extern "C" fn(req: *mut uv_fs_t);
+pub type uv_fs_event_cb = extern "C" fn(handle: *mut uv_fs_event_t,
+                                        filename: *const c_char,
+                                        events: c_int,
+                                        status: c_int);
 pub type uv_shutdown_cb = extern "C" fn(req: *mut uv_shutdown_t, status: c_int);
 
 #[cfg(unix)] pub type uv_uid_t = libc::types::os::arch::posix88::uid_t;
@@ -716,6 +729,13 @@ extern {
     pub fn uv_signal_start(h: *mut uv_signal_t, cb: uv_signal_cb,
                            signum: c_int) -> c_int;
     pub fn uv_signal_stop(handle: *mut uv_signal_t) -> c_int;
+
+    // fs events
+    pub fn uv_fs_event_init(loop_: *mut uv_loop_t,
+                            handle: *mut uv_fs_event_t) -> c_int;
+    pub fn uv_fs_event_start(handle: *mut uv_fs_event_t, cb: uv_fs_event_cb,
+                             path: *const c_char, flags: c_uint) -> c_int;
+    pub fn uv_fs_event_stop(handle: *mut uv_fs_event_t) -> c_int;
 }
 
 // libuv requires other native libraries on various platforms. These are all
@@ -16,7 +16,7 @@ use std::hash::{Hash, Hasher};
 
 use {Decodable, Encodable, Decoder, Encoder};
 use std::collections::{DList, RingBuf, TreeMap, TreeSet, HashMap, HashSet,
-                       TrieMap, TrieSet};
+                       TrieMap, TrieSet, CompressedBitmap};
 use std::collections::enum_set::{EnumSet, CLike};
 
 impl<
@@ -311,3 +311,19 @@ impl<E, D: Decoder<E>> Decodable<D, E> for TrieSet {
         })
     }
 }
+
+impl<E, S: Encoder<E>> Encodable<S, E> for CompressedBitmap {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        self.to_bytes().encode(s)
+    }
+}
+
+impl<E, D: Decoder<E>> Decodable<D, E> for CompressedBitmap {
+    fn decode(d: &mut D) -> Result<CompressedBitmap, E> {
+        let bytes: Vec<u8> = try!(Decodable::decode(d));
+        match CompressedBitmap::from_bytes(bytes.as_slice()) {
+            Some(bitmap) => Ok(bitmap),
+            None => Err(d.error("invalid compressed bitmap")),
+        }
+    }
+}
@@ -3291,6 +3291,24 @@ mod tests {
         assert_eq!(result, Err(ExpectedError("Number".to_string(), "a".to_string())));
     }
 
+    #[test]
+    fn test_compressed_bitmap_round_trip() {
+        use std::collections::CompressedBitmap;
+        // A chunk stored as a bitmap, and two stored as arrays
+        let bitmap: CompressedBitmap = range(0u32, 5000)
+            .chain(range(0u32, 10).map(|i| (1 << 20) + i * 3))
+            .chain(Some(0xffff_ffffu32).move_iter())
+            .collect();
+        let json_str = super::encode(&bitmap);
+        let mut decoder = Decoder::new(from_str(json_str.as_slice()).unwrap());
+        let decoded: CompressedBitmap = Decodable::decode(&mut decoder).unwrap();
+        assert_eq!(decoded, bitmap);
+
+        let mut decoder = Decoder::new(from_str("[1,2,3]").unwrap());
+        let result: DecodeResult<CompressedBitmap> = Decodable::decode(&mut decoder);
+        assert!(result.is_err());
+    }
+
     fn assert_stream_equal(src: &str,
                            expected: Vec<(JsonEvent, Vec<StackElement>)>) {
         let mut parser = Parser::new(src.chars());
@@ -1417,6 +1417,37 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
         }
     }
 
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation. The key is hashed and looked up exactly once; the
+    /// returned handle remembers where it was found, or the hash needed
+    /// to insert it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::collections::hashmap::{Occupied, Vacant};
+    ///
+    /// let mut count: HashMap<&str, uint> = HashMap::new();
+    ///
+    /// for x in vec!["a", "b", "a", "c", "a"].move_iter() {
+    ///     match count.entry(x) {
+    ///         Vacant(entry) => { entry.insert(1); }
+    ///         Occupied(mut entry) => { *entry.get_mut() += 1; }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(count["a"], 3);
+    /// assert_eq!(count["c"], 1);
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: K) -> Entry<'a, K, V, H> {
+        let hash = self.make_hash(&key);
+        match self.search_hashed(&hash, &key) {
+            Some(idx) => Occupied(OccupiedEntry { map: self, index: idx }),
+            None => Vacant(VacantEntry { map: self, hash: hash, key: key }),
+        }
+    }
+
     /// Retrieves a value for the given key.
     /// See [`find`](../trait.Map.html#tymethod.find) for a non-failing alternative.
     ///
@@ -1775,6 +1806,74 @@ pub type Keys<'a, K, V> =
 pub type Values<'a, K, V> =
     iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
 
+/// A view into a single location of a `HashMap`, as returned by `entry`.
+pub enum Entry<'a, K:'a, V:'a, H:'a> {
+    /// The key is present in the map.
+    Occupied(OccupiedEntry<'a, K, V, H>),
+    /// The key is absent from the map.
+    Vacant(VacantEntry<'a, K, V, H>),
+}
+
+/// A handle to a present entry of a `HashMap`.
+pub struct OccupiedEntry<'a, K:'a, V:'a, H:'a> {
+    map: &'a mut HashMap<K, V, H>,
+    index: table::FullIndex,
+}
+
+/// A handle to an absent entry of a `HashMap`. It keeps the key and its
+/// hash so that inserting does not hash again.
+pub struct VacantEntry<'a, K:'a, V:'a, H:'a> {
+    map: &'a mut HashMap<K, V, H>,
+    hash: table::SafeHash,
+    key: K,
+}
+
+impl<'a, K: Eq + Hash<S>, V, S, H: Hasher<S>> OccupiedEntry<'a, K, V, H> {
+    /// Returns a reference to the entry's value.
+    pub fn get<'b>(&'b self) -> &'b V {
+        let (_, v) = self.map.table.read(&self.index);
+        v
+    }
+
+    /// Returns a mutable reference to the entry's value.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut V {
+        let (_, v) = self.map.table.read_mut(&self.index);
+        v
+    }
+
+    /// Converts the entry into a mutable reference bound to the map.
+    pub fn into_mut(self) -> &'a mut V {
+        let (_, v) = self.map.table.read_mut(&self.index);
+        v
+    }
+
+    /// Replaces the entry's value, returning the old one.
+    pub fn set(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, returning its value.
+    pub fn take(self) -> V {
+        let OccupiedEntry { map, index } = self;
+        let value = map.pop_internal(index).unwrap();
+
+        // Shrink the table like `pop` does. That resizes before searching,
+        // but resizing would lose this entry's index, so do it afterwards.
+        let new_size = map.table.size();
+        map.make_some_room(new_size);
+        value
+    }
+}
+
+impl<'a, K: Eq + Hash<S>, V, S, H: Hasher<S>> VacantEntry<'a, K, V, H> {
+    /// Inserts `value` under the entry's key, returning a mutable
+    /// reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry { map, hash, key } = self;
+        map.insert_hashed(hash, key, value)
+    }
+}
+
 impl<K: Eq + Hash<S>, V, S, H: Hasher<S> + Default> FromIterator<(K, V)> for HashMap<K, V, H> {
     fn from_iter<T: Iterator<(K, V)>>(iter: T) -> HashMap<K, V, H> {
         let (lower, _) = iter.size_hint();
@@ -2225,7 +2324,7 @@ pub type SetAlgebraItems<'a, T, H> =
 mod test_map {
     use prelude::*;
 
-    use super::HashMap;
+    use super::{HashMap, Occupied, Vacant};
     use cmp::Equiv;
     use hash;
     use iter::{Iterator,range_inclusive,range_step_inclusive};
@@ -2736,6 +2835,63 @@ mod test_map {
 
         map[4];
     }
+
+    #[test]
+    fn test_entry() {
+        let mut map: HashMap<int, int> = range(0i, 6).map(|i| (i, i * 10)).collect();
+
+        // Existing key: read, replace and modify in place
+        match map.entry(1) {
+            Occupied(mut e) => {
+                assert_eq!(*e.get(), 10);
+                assert_eq!(e.set(100), 10);
+                *e.get_mut() += 1;
+            }
+            Vacant(_) => fail!()
+        }
+        assert_eq!(map[1], 101);
+        assert_eq!(map.len(), 6);
+
+        // Existing key: remove
+        match map.entry(2) {
+            Occupied(e) => assert_eq!(e.take(), 20),
+            Vacant(_) => fail!()
+        }
+        assert_eq!(map.find(&2), None);
+        assert_eq!(map.len(), 5);
+
+        // Missing key: insert
+        match map.entry(10) {
+            Vacant(e) => *e.insert(1000) += 1,
+            Occupied(_) => fail!()
+        }
+        assert_eq!(map[10], 1001);
+        assert_eq!(map.len(), 6);
+
+        // Enough inserts through entries to force the table to grow
+        for i in range(100i, 200) {
+            match map.entry(i) {
+                Vacant(e) => { e.insert(i); }
+                Occupied(_) => fail!()
+            }
+        }
+        assert_eq!(map.len(), 106);
+        for i in range(100i, 200) {
+            assert_eq!(map[i], i);
+        }
+
+        // And enough removals through entries to shrink it again
+        let grown_cap = map.table.capacity();
+        for i in range(100i, 200) {
+            match map.entry(i) {
+                Occupied(e) => assert_eq!(e.take(), i),
+                Vacant(_) => fail!()
+            }
+        }
+        assert_eq!(map.len(), 6);
+        assert!(map.table.capacity() < grown_cap);
+        assert_eq!(map[10], 1001);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,676 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A hash map that remembers the order of its entries.
+//!
+//! Entries are threaded on a doubly-linked list running from the front
+//! (oldest) to the back (newest). By default the order is the order in
+//! which keys were first inserted; a map created with `with_access_order`
+//! instead moves an entry to the back whenever it is looked up or updated
+//! through `&mut self`, which is the bookkeeping an LRU cache needs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::LinkedHashMap;
+//!
+//! let mut map = LinkedHashMap::new();
+//! map.insert("b", 2i);
+//! map.insert("a", 1);
+//! map.insert("c", 3);
+//! map.insert("b", 20);
+//!
+//! let keys: Vec<&str> = map.keys().map(|&k| k).collect();
+//! assert_eq!(keys, vec!["b", "a", "c"]);
+//!
+//! assert_eq!(map.pop_front(), Some(("b", 20)));
+//! assert_eq!(map.pop_back(), Some(("c", 3)));
+//! assert_eq!(map.len(), 1);
+//! ```
+
+use clone::Clone;
+use cmp::{PartialEq, Eq};
+use collections::{HashMap, Collection, Mutable, Map, MutableMap};
+use default::Default;
+use fmt;
+use hash::Hash;
+use iter::{Iterator, DoubleEndedIterator, ExactSize, FromIterator, Extendable};
+use iter;
+use kinds::marker;
+use mem;
+use ops::Drop;
+use option::{Some, None, Option};
+use boxed::Box;
+use ptr;
+use result::{Ok, Err};
+
+struct KeyRef<K> { k: *const K }
+
+struct LinkedEntry<K, V> {
+    next: *mut LinkedEntry<K, V>,
+    prev: *mut LinkedEntry<K, V>,
+    key: K,
+    value: V,
+}
+
+/// A hash map that iterates in insertion or access order.
+pub struct LinkedHashMap<K, V> {
+    map: HashMap<KeyRef<K>, Box<LinkedEntry<K, V>>>,
+    // Sentinel of the circular list; its `next` is the front and its `prev`
+    // the back. Its key and value are never initialized.
+    head: *mut LinkedEntry<K, V>,
+    access_order: bool,
+}
+
+impl<S, K: Hash<S>> Hash<S> for KeyRef<K> {
+    fn hash(&self, state: &mut S) {
+        unsafe { (*self.k).hash(state) }
+    }
+}
+
+impl<K: PartialEq> PartialEq for KeyRef<K> {
+    fn eq(&self, other: &KeyRef<K>) -> bool {
+        unsafe{ (*self.k).eq(&*other.k) }
+    }
+}
+
+impl<K: Eq> Eq for KeyRef<K> {}
+
+impl<K, V> LinkedEntry<K, V> {
+    fn new(k: K, v: V) -> LinkedEntry<K, V> {
+        LinkedEntry {
+            key: k,
+            value: v,
+            next: ptr::mut_null(),
+            prev: ptr::mut_null(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> LinkedHashMap<K, V> {
+    /// Create an empty map that keeps its entries in insertion order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// let mut map: LinkedHashMap<int, &str> = LinkedHashMap::new();
+    /// ```
+    pub fn new() -> LinkedHashMap<K, V> {
+        LinkedHashMap::with_map(HashMap::new(), false)
+    }
+
+    /// Create an empty map in insertion order with space for at least
+    /// `capacity` entries.
+    pub fn with_capacity(capacity: uint) -> LinkedHashMap<K, V> {
+        LinkedHashMap::with_map(HashMap::with_capacity(capacity), false)
+    }
+
+    /// Create an empty map that keeps its entries in access order: looking
+    /// an entry up with `get` or `find_mut`, or overwriting its value, moves
+    /// it to the back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::with_access_order();
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    /// map.get(&1);
+    ///
+    /// assert_eq!(map.front(), Some((&2, &"b")));
+    /// assert_eq!(map.back(), Some((&1, &"a")));
+    /// ```
+    pub fn with_access_order() -> LinkedHashMap<K, V> {
+        LinkedHashMap::with_map(HashMap::new(), true)
+    }
+
+    fn with_map(map: HashMap<KeyRef<K>, Box<LinkedEntry<K, V>>>,
+                access_order: bool) -> LinkedHashMap<K, V> {
+        let map = LinkedHashMap {
+            map: map,
+            head: unsafe{ mem::transmute(box mem::uninitialized::<LinkedEntry<K, V>>()) },
+            access_order: access_order,
+        };
+        unsafe {
+            (*map.head).next = map.head;
+            (*map.head).prev = map.head;
+        }
+        map
+    }
+
+    /// Return a reference to the value corresponding to the key. In an
+    /// access-ordered map the entry is moved to the back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// let mut map = LinkedHashMap::new();
+    ///
+    /// map.insert(1i, "a");
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    pub fn get<'a>(&'a mut self, k: &K) -> Option<&'a V> {
+        match self.find_mut(k) {
+            Some(v) => Some(&*v),
+            None => None
+        }
+    }
+
+    /// Return whether entries are kept in access order rather than
+    /// insertion order.
+    pub fn is_access_ordered(&self) -> bool {
+        self.access_order
+    }
+
+    /// Remove and return the entry at the front: the oldest insertion, or
+    /// the least recently used entry in an access-ordered map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// let mut map = LinkedHashMap::new();
+    ///
+    /// map.insert(2i, "b");
+    /// map.insert(1, "a");
+    ///
+    /// assert_eq!(map.pop_front(), Some((2, "b")));
+    /// assert_eq!(map.pop_front(), Some((1, "a")));
+    /// assert_eq!(map.pop_front(), None);
+    /// ```
+    pub fn pop_front(&mut self) -> Option<(K, V)> {
+        let front = unsafe { (*self.head).next };
+        self.pop_entry(front)
+    }
+
+    /// Remove and return the entry at the back: the newest insertion, or
+    /// the most recently used entry in an access-ordered map.
+    pub fn pop_back(&mut self) -> Option<(K, V)> {
+        let back = unsafe { (*self.head).prev };
+        self.pop_entry(back)
+    }
+
+    fn pop_entry(&mut self, node: *mut LinkedEntry<K, V>) -> Option<(K, V)> {
+        if node == self.head {
+            return None;
+        }
+        self.detach(node);
+        match self.map.pop(&KeyRef{k: unsafe { &(*node).key }}) {
+            Some(box LinkedEntry { key, value, .. }) => Some((key, value)),
+            None => fail!("linked entry missing from the map")
+        }
+    }
+
+    #[inline]
+    fn detach(&mut self, node: *mut LinkedEntry<K, V>) {
+        unsafe {
+            (*(*node).prev).next = (*node).next;
+            (*(*node).next).prev = (*node).prev;
+        }
+    }
+
+    #[inline]
+    fn attach(&mut self, node: *mut LinkedEntry<K, V>) {
+        unsafe {
+            (*node).next = self.head;
+            (*node).prev = (*self.head).prev;
+            (*self.head).prev = node;
+            (*(*node).prev).next = node;
+        }
+    }
+
+    // Move an entry to the back if the map is access-ordered
+    #[inline]
+    fn touch(&mut self, node: *mut LinkedEntry<K, V>) {
+        if self.access_order {
+            self.detach(node);
+            self.attach(node);
+        }
+    }
+
+    /// Return the entry at the front without removing it.
+    pub fn front<'a>(&'a self) -> Option<(&'a K, &'a V)> {
+        self.iter().next()
+    }
+
+    /// Return the entry at the back without removing it.
+    pub fn back<'a>(&'a self) -> Option<(&'a K, &'a V)> {
+        self.iter().next_back()
+    }
+
+    /// An iterator visiting all key-value pairs from front to back. Use
+    /// `.rev()` to go from back to front.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// let mut map = LinkedHashMap::new();
+    ///
+    /// map.insert("a", 1i);
+    /// map.insert("b", 2);
+    ///
+    /// // Prints `a: 1` then `b: 2`
+    /// for (key, value) in map.iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            front: unsafe { (*self.head).next as *const LinkedEntry<K, V> },
+            back: unsafe { (*self.head).prev as *const LinkedEntry<K, V> },
+            remaining: self.map.len(),
+            marker: marker::ContravariantLifetime,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs from front to back, with
+    /// mutable references to the values. Iterating does not reorder the map.
+    pub fn mut_iter<'a>(&'a mut self) -> MutEntries<'a, K, V> {
+        MutEntries {
+            front: unsafe { (*self.head).next },
+            back: unsafe { (*self.head).prev },
+            remaining: self.map.len(),
+            marker: marker::ContravariantLifetime,
+        }
+    }
+
+    /// An iterator visiting all keys from front to back.
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        self.iter().map(|(k, _v)| k)
+    }
+
+    /// An iterator visiting all values from front to back.
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        self.iter().map(|(_k, v)| v)
+    }
+
+    /// Create a consuming iterator, that is, one that moves each key-value
+    /// pair out of the map from front to back.
+    pub fn move_iter(self) -> MoveEntries<K, V> {
+        MoveEntries { map: self }
+    }
+}
+
+impl<K: Hash + Eq, V> Collection for LinkedHashMap<K, V> {
+    fn len(&self) -> uint {
+        self.map.len()
+    }
+}
+
+impl<K: Hash + Eq, V> Mutable for LinkedHashMap<K, V> {
+    fn clear(&mut self) {
+        self.map.clear();
+        unsafe {
+            (*self.head).next = self.head;
+            (*self.head).prev = self.head;
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Map<K, V> for LinkedHashMap<K, V> {
+    fn find<'a>(&'a self, k: &K) -> Option<&'a V> {
+        match self.map.find(&KeyRef{k: k}) {
+            Some(node) => Some(&node.value),
+            None => None
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> MutableMap<K, V> for LinkedHashMap<K, V> {
+    /// Return a mutable reference to the value corresponding to the key. In
+    /// an access-ordered map the entry is moved to the back.
+    fn find_mut<'a>(&'a mut self, k: &K) -> Option<&'a mut V> {
+        let node_ptr: *mut LinkedEntry<K, V> = match self.map.find_mut(&KeyRef{k: k}) {
+            Some(node) => &mut **node,
+            None => return None
+        };
+        self.touch(node_ptr);
+        Some(unsafe { &mut (*node_ptr).value })
+    }
+
+    /// Insert a key-value pair, returning the previous value if the key was
+    /// present. A new key goes to the back; an existing key keeps its place
+    /// unless the map is access-ordered.
+    fn swap(&mut self, k: K, v: V) -> Option<V> {
+        let (node_ptr, node_opt, old) = match self.map.find_mut(&KeyRef{k: &k}) {
+            Some(node) => {
+                let old = mem::replace(&mut node.value, v);
+                let node_ptr: *mut LinkedEntry<K, V> = &mut **node;
+                (node_ptr, None, Some(old))
+            }
+            None => {
+                let mut node = box LinkedEntry::new(k, v);
+                let node_ptr: *mut LinkedEntry<K, V> = &mut *node;
+                (node_ptr, Some(node), None)
+            }
+        };
+        match node_opt {
+            None => self.touch(node_ptr),
+            Some(node) => {
+                let keyref = unsafe { &(*node_ptr).key };
+                self.map.swap(KeyRef{k: keyref}, node);
+                self.attach(node_ptr);
+            }
+        }
+        old
+    }
+
+    fn pop(&mut self, k: &K) -> Option<V> {
+        match self.map.pop(&KeyRef{k: k}) {
+            Some(mut node) => {
+                self.detach(&mut *node);
+                let box LinkedEntry { value, .. } = node;
+                Some(value)
+            }
+            None => None
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for LinkedHashMap<K, V> {
+    fn default() -> LinkedHashMap<K, V> { LinkedHashMap::new() }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Clone for LinkedHashMap<K, V> {
+    fn clone(&self) -> LinkedHashMap<K, V> {
+        let mut map = LinkedHashMap::with_map(HashMap::with_capacity(self.len()),
+                                              self.access_order);
+        for (k, v) in self.iter() {
+            map.insert(k.clone(), v.clone());
+        }
+        map
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq> PartialEq for LinkedHashMap<K, V> {
+    /// Two maps are equal if they hold equal entries in the same order.
+    fn eq(&self, other: &LinkedHashMap<K, V>) -> bool {
+        self.len() == other.len() && iter::order::eq(self.iter(), other.iter())
+    }
+}
+
+impl<K: Hash + Eq, V: Eq> Eq for LinkedHashMap<K, V> {}
+
+impl<K: Hash + Eq + fmt::Show, V: fmt::Show> fmt::Show for LinkedHashMap<K, V> {
+    /// Return a string that lists the key-value pairs from front to back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
+
+        write!(f, "}}")
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for LinkedHashMap<K, V> {
+    fn from_iter<T: Iterator<(K, V)>>(iter: T) -> LinkedHashMap<K, V> {
+        let (lower, _) = iter.size_hint();
+        let mut map = LinkedHashMap::with_capacity(lower);
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V> Extendable<(K, V)> for LinkedHashMap<K, V> {
+    fn extend<T: Iterator<(K, V)>>(&mut self, mut iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<K, V> Drop for LinkedHashMap<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let node: Box<LinkedEntry<K, V>> = mem::transmute(self.head);
+            // Prevent compiler from trying to drop the un-initialized field in the sigil node.
+            let box internal_node = node;
+            let LinkedEntry { next: _, prev: _, key: k, value: v } = internal_node;
+            mem::forget(k);
+            mem::forget(v);
+        }
+    }
+}
+
+/// LinkedHashMap iterator
+pub struct Entries<'a, K:'a, V:'a> {
+    front: *const LinkedEntry<K, V>,
+    back: *const LinkedEntry<K, V>,
+    remaining: uint,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// LinkedHashMap mutable values iterator
+pub struct MutEntries<'a, K:'a, V:'a> {
+    front: *mut LinkedEntry<K, V>,
+    back: *mut LinkedEntry<K, V>,
+    remaining: uint,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// LinkedHashMap move iterator
+pub struct MoveEntries<K, V> {
+    map: LinkedHashMap<K, V>,
+}
+
+/// LinkedHashMap keys iterator
+pub type Keys<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
+
+/// LinkedHashMap values iterator
+pub type Values<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
+
+impl<'a, K, V> Iterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.front;
+            self.front = node.next as *const LinkedEntry<K, V>;
+            Some((&node.key, &node.value))
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.back;
+            self.back = node.prev as *const LinkedEntry<K, V>;
+            Some((&node.key, &node.value))
+        }
+    }
+}
+
+impl<'a, K, V> ExactSize<(&'a K, &'a V)> for Entries<'a, K, V> {}
+
+impl<'a, K, V> Iterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &mut *self.front;
+            self.front = node.next;
+            Some((&node.key, &mut node.value))
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &mut *self.back;
+            self.back = node.prev;
+            Some((&node.key, &mut node.value))
+        }
+    }
+}
+
+impl<'a, K, V> ExactSize<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {}
+
+impl<K: Hash + Eq, V> Iterator<(K, V)> for MoveEntries<K, V> {
+    fn next(&mut self) -> Option<(K, V)> {
+        self.map.pop_front()
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.map.len(), Some(self.map.len()))
+    }
+}
+
+impl<K: Hash + Eq, V> DoubleEndedIterator<(K, V)> for MoveEntries<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.map.pop_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+    use super::LinkedHashMap;
+
+    fn keys(map: &LinkedHashMap<int, int>) -> Vec<int> {
+        map.keys().map(|&k| k).collect()
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = LinkedHashMap::new();
+        for &k in [3i, 1, 4, 5, 9, 2, 6].iter() {
+            assert!(map.insert(k, k * 10));
+        }
+        assert_eq!(keys(&map), vec![3, 1, 4, 5, 9, 2, 6]);
+
+        // Updating or looking up a key leaves it where it was
+        assert_eq!(map.swap(4, 44), Some(40));
+        assert_eq!(map.get(&3), Some(&30));
+        *map.find_mut(&1).unwrap() += 1;
+        assert_eq!(keys(&map), vec![3, 1, 4, 5, 9, 2, 6]);
+        assert_eq!(map.find(&4), Some(&44));
+        assert_eq!(map.find(&1), Some(&11));
+
+        let rev: Vec<int> = map.iter().rev().map(|(&k, _)| k).collect();
+        assert_eq!(rev, vec![6, 2, 9, 5, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_access_order() {
+        let mut map = LinkedHashMap::with_access_order();
+        assert!(map.is_access_ordered());
+        for k in range(0i, 5) {
+            map.insert(k, k);
+        }
+        map.get(&1);
+        map.insert(3, 33);
+        *map.find_mut(&0).unwrap() += 1;
+        // `find` takes `&self` and so never reorders
+        map.find(&2);
+        assert_eq!(keys(&map), vec![2, 4, 1, 3, 0]);
+        assert_eq!(map.front(), Some((&2, &2)));
+        assert_eq!(map.back(), Some((&0, &1)));
+    }
+
+    #[test]
+    fn test_pop_front_back() {
+        let mut map: LinkedHashMap<int, int> = range(0i, 4).map(|k| (k, -k)).collect();
+        assert_eq!(map.pop_front(), Some((0, 0)));
+        assert_eq!(map.pop_back(), Some((3, -3)));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.pop_back(), Some((2, -2)));
+        assert_eq!(map.pop_back(), Some((1, -1)));
+        assert_eq!(map.pop_front(), None);
+        assert_eq!(map.pop_back(), None);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_pop_unlinks() {
+        let mut map: LinkedHashMap<int, int> = range(0i, 5).map(|k| (k, k)).collect();
+        assert_eq!(map.pop(&2), Some(2));
+        assert_eq!(map.pop(&2), None);
+        assert!(map.remove(&0));
+        assert_eq!(keys(&map), vec![1, 3, 4]);
+        map.insert(2, 2);
+        assert_eq!(keys(&map), vec![1, 3, 4, 2]);
+        assert_eq!(map.front(), Some((&1, &1)));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut map: LinkedHashMap<int, int> = range(0i, 5).map(|k| (k, k)).collect();
+        map.clear();
+        assert!(map.is_empty());
+        assert!(map.iter().next().is_none());
+        map.insert(7, 7);
+        assert_eq!(keys(&map), vec![7]);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut map: LinkedHashMap<int, int> = range(0i, 6).map(|k| (k, k)).collect();
+        for (_, v) in map.mut_iter().rev() {
+            *v *= 2;
+        }
+        let mut it = map.iter();
+        assert_eq!(it.size_hint(), (6, Some(6)));
+        assert_eq!(it.next(), Some((&0, &0)));
+        assert_eq!(it.next_back(), Some((&5, &10)));
+        assert_eq!(it.next(), Some((&1, &2)));
+        assert_eq!(it.size_hint(), (3, Some(3)));
+        let values: Vec<int> = map.values().map(|&v| v).collect();
+        assert_eq!(values, vec![0, 2, 4, 6, 8, 10]);
+
+        let mut moved = map.clone().move_iter();
+        assert_eq!(moved.next_back(), Some((5, 10)));
+        let rest: Vec<(int, int)> = moved.collect();
+        assert_eq!(rest, vec![(0, 0), (1, 2), (2, 4), (3, 6), (4, 8)]);
+    }
+
+    #[test]
+    fn test_eq_and_show() {
+        let a: LinkedHashMap<int, int> = vec![(2, 20), (1, 10)].move_iter().collect();
+        let b: LinkedHashMap<int, int> = vec![(1, 10), (2, 20)].move_iter().collect();
+        assert!(a == a.clone());
+        assert!(a != b);
+        assert_eq!(a.to_string(), "{2: 20, 1: 10}".to_string());
+        assert_eq!(b.to_string(), "{1: 10, 2: 20}".to_string());
+    }
+}
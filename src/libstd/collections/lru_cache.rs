@@ -37,57 +37,19 @@
 //! assert!(cache.get(&2).is_none());
 //! ```
 
-use cmp::{PartialEq, Eq};
-use collections::{HashMap, Collection, Mutable, MutableMap};
+use cmp::Eq;
+use collections::{Collection, Mutable, MutableMap, LinkedHashMap};
 use fmt;
 use hash::Hash;
-use iter::{range, Iterator};
-use mem;
-use ops::Drop;
-use option::{Some, None, Option};
-use boxed::Box;
-use ptr;
+use iter::{range, Iterator, DoubleEndedIterator};
+use option::Option;
 use result::{Ok, Err};
 
-struct KeyRef<K> { k: *const K }
-
-struct LruEntry<K, V> {
-    next: *mut LruEntry<K, V>,
-    prev: *mut LruEntry<K, V>,
-    key: K,
-    value: V,
-}
-
 /// An LRU Cache.
 pub struct LruCache<K, V> {
-    map: HashMap<KeyRef<K>, Box<LruEntry<K, V>>>,
+    // Access-ordered, so the least-recently-used pair is at the front
+    map: LinkedHashMap<K, V>,
     max_size: uint,
-    head: *mut LruEntry<K, V>,
-}
-
-impl<S, K: Hash<S>> Hash<S> for KeyRef<K> {
-    fn hash(&self, state: &mut S) {
-        unsafe { (*self.k).hash(state) }
-    }
-}
-
-impl<K: PartialEq> PartialEq for KeyRef<K> {
-    fn eq(&self, other: &KeyRef<K>) -> bool {
-        unsafe{ (*self.k).eq(&*other.k) }
-    }
-}
-
-impl<K: Eq> Eq for KeyRef<K> {}
-
-impl<K, V> LruEntry<K, V> {
-    fn new(k: K, v: V) -> LruEntry<K, V> {
-        LruEntry {
-            key: k,
-            value: v,
-            next: ptr::mut_null(),
-            prev: ptr::mut_null(),
-        }
-    }
 }
 
 impl<K: Hash + Eq, V> LruCache<K, V> {
@@ -100,16 +62,10 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// let mut cache: LruCache<int, &str> = LruCache::new(10);
     /// ```
     pub fn new(capacity: uint) -> LruCache<K, V> {
-        let cache = LruCache {
-            map: HashMap::new(),
+        LruCache {
+            map: LinkedHashMap::with_access_order(),
             max_size: capacity,
-            head: unsafe{ mem::transmute(box mem::uninitialized::<LruEntry<K, V>>()) },
-        };
-        unsafe {
-            (*cache.head).next = cache.head;
-            (*cache.head).prev = cache.head;
         }
-        return cache;
     }
 
     /// Put a key-value pair into cache.
@@ -126,32 +82,9 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.get(&2), Some(&"b"));
     /// ```
     pub fn put(&mut self, k: K, v: V) {
-        let (node_ptr, node_opt) = match self.map.find_mut(&KeyRef{k: &k}) {
-            Some(node) => {
-                node.value = v;
-                let node_ptr: *mut LruEntry<K, V> = &mut **node;
-                (node_ptr, None)
-            }
-            None => {
-                let mut node = box LruEntry::new(k, v);
-                let node_ptr: *mut LruEntry<K, V> = &mut *node;
-                (node_ptr, Some(node))
-            }
-        };
-        match node_opt {
-            None => {
-                // Existing node, just update LRU position
-                self.detach(node_ptr);
-                self.attach(node_ptr);
-            }
-            Some(node) => {
-                let keyref = unsafe { &(*node_ptr).key };
-                self.map.swap(KeyRef{k: keyref}, node);
-                self.attach(node_ptr);
-                if self.len() > self.capacity() {
-                    self.remove_lru();
-                }
-            }
+        self.map.insert(k, v);
+        if self.len() > self.capacity() {
+            self.remove_lru();
         }
     }
 
@@ -172,21 +105,7 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.get(&2), Some(&"c"));
     /// ```
     pub fn get<'a>(&'a mut self, k: &K) -> Option<&'a V> {
-        let (value, node_ptr_opt) = match self.map.find_mut(&KeyRef{k: k}) {
-            None => (None, None),
-            Some(node) => {
-                let node_ptr: *mut LruEntry<K, V> = &mut **node;
-                (Some(unsafe { &(*node_ptr).value }), Some(node_ptr))
-            }
-        };
-        match node_ptr_opt {
-            None => (),
-            Some(node_ptr) => {
-                self.detach(node_ptr);
-                self.attach(node_ptr);
-            }
-        }
-        return value;
+        self.map.get(k)
     }
 
     /// Remove and return a value corresponding to the key from the cache.
@@ -205,10 +124,7 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.len(), 0);
     /// ```
     pub fn pop(&mut self, k: &K) -> Option<V> {
-        match self.map.pop(&KeyRef{k: k}) {
-            None => None,
-            Some(lru_entry) => Some(lru_entry.value)
-        }
+        self.map.pop(k)
     }
 
     /// Return the maximum number of key-value pairs the cache can hold.
@@ -264,29 +180,7 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
 
     #[inline]
     fn remove_lru(&mut self) {
-        if self.len() > 0 {
-            let lru = unsafe { (*self.head).prev };
-            self.detach(lru);
-            self.map.pop(&KeyRef{k: unsafe { &(*lru).key }});
-        }
-    }
-
-    #[inline]
-    fn detach(&mut self, node: *mut LruEntry<K, V>) {
-        unsafe {
-            (*(*node).prev).next = (*node).next;
-            (*(*node).next).prev = (*node).prev;
-        }
-    }
-
-    #[inline]
-    fn attach(&mut self, node: *mut LruEntry<K, V>) {
-        unsafe {
-            (*node).next = (*self.head).next;
-            (*node).prev = self.head;
-            (*self.head).next = node;
-            (*(*node).next).prev = node;
-        }
+        self.map.pop_front();
     }
 }
 
@@ -295,17 +189,9 @@ impl<A: fmt::Show + Hash + Eq, B: fmt::Show> fmt::Show for LruCache<A, B> {
     /// used to least-recently used.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(f, "{{"));
-        let mut cur = self.head;
-        for i in range(0, self.len()) {
+        for (i, (k, v)) in self.map.iter().rev().enumerate() {
             if i > 0 { try!(write!(f, ", ")) }
-            unsafe {
-                cur = (*cur).next;
-                try!(write!(f, "{}", (*cur).key));
-            }
-            try!(write!(f, ": "));
-            unsafe {
-                try!(write!(f, "{}", (*cur).value));
-            }
+            try!(write!(f, "{}: {}", *k, *v));
         }
         write!(f, r"}}")
     }
@@ -325,20 +211,6 @@ impl<K: Hash + Eq, V> Mutable for LruCache<K, V> {
     }
 }
 
-#[unsafe_destructor]
-impl<K, V> Drop for LruCache<K, V> {
-    fn drop(&mut self) {
-        unsafe {
-            let node: Box<LruEntry<K, V>> = mem::transmute(self.head);
-            // Prevent compiler from trying to drop the un-initialized field in the sigil node.
-            let box internal_node = node;
-            let LruEntry { next: _, prev: _, key: k, value: v } = internal_node;
-            mem::forget(k);
-            mem::forget(v);
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use prelude::*;
@@ -391,6 +263,13 @@ mod tests {
         assert_eq!(opt1.unwrap(), 10);
         assert!(cache.get(&1).is_none());
         assert_eq!(cache.len(), 1);
+
+        // The popped pair no longer counts towards eviction order
+        cache.put(3, 30);
+        cache.put(4, 40);
+        assert!(cache.get(&2).is_none());
+        assert_opt_eq(cache.get(&3), 30);
+        assert_opt_eq(cache.get(&4), 40);
     }
 
     #[test]
@@ -430,5 +309,7 @@ mod tests {
         assert!(cache.get(&1).is_none());
         assert!(cache.get(&2).is_none());
         assert_eq!(cache.to_string(), "{}".to_string());
+        cache.put(3, 30);
+        assert_eq!(cache.to_string(), "{3: 30}".to_string());
     }
 }
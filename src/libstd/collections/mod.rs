@@ -16,14 +16,19 @@
 
 pub use core_collections::{Collection, Mutable, Map, MutableMap};
 pub use core_collections::{Set, MutableSet, Deque, MutableSeq};
-pub use core_collections::{Bitv, BitvSet, BTree, DList, EnumSet};
+pub use core_collections::{Bound, Included, Excluded, Unbounded};
+pub use core_collections::{Bitv, BitvSet, BTreeMap, BTreeSet, CompressedBitmap, DList};
+pub use core_collections::EnumSet;
+pub use core_collections::{PersistentMap, PersistentSet, PersistentVec};
 pub use core_collections::{PriorityQueue, RingBuf, SmallIntMap};
 pub use core_collections::{TreeMap, TreeSet, TrieMap, TrieSet};
-pub use core_collections::{bitv, btree, dlist, enum_set};
-pub use core_collections::{priority_queue, ringbuf, smallintmap, treemap, trie};
+pub use core_collections::{bitv, btree, compressed_bitmap, dlist, enum_set};
+pub use core_collections::{persistent, priority_queue, ringbuf, smallintmap, treemap, trie};
 
 pub use self::hashmap::{HashMap, HashSet};
+pub use self::linked_hashmap::LinkedHashMap;
 pub use self::lru_cache::LruCache;
 
 pub mod hashmap;
+pub mod linked_hashmap;
 pub mod lru_cache;
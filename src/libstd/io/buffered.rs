@@ -13,9 +13,9 @@
 //! Buffering wrappers for I/O traits
 
 use cmp;
-use collections::Collection;
+use collections::{Collection, MutableSeq};
 use io::{Reader, Writer, Stream, Buffer, DEFAULT_BUF_SIZE, IoResult};
-use iter::ExactSize;
+use iter::{ExactSize, Iterator};
 use ops::Drop;
 use option::{Some, None, Option};
 use result::{Ok, Err};
@@ -202,6 +202,31 @@ impl<W: Writer> Writer for BufferedWriter<W> {
         }
     }
 
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let len = bufs.iter().fold(0, |n, b| n + b.len());
+        if self.pos + len <= self.buf.len() {
+            for buf in bufs.iter() {
+                let dst = self.buf.mut_slice_from(self.pos);
+                slice::bytes::copy_memory(dst, *buf);
+                self.pos += buf.len();
+            }
+            return Ok(())
+        }
+
+        // Everything doesn't fit, so hand the buffered data to the underlying
+        // writer in the same call as the new data.
+        let ret = {
+            let mut all = Vec::with_capacity(bufs.len() + 1);
+            if self.pos != 0 {
+                all.push(self.buf.slice_to(self.pos));
+            }
+            all.push_all(bufs);
+            self.inner.get_mut_ref().write_vectored(all.as_slice())
+        };
+        self.pos = 0;
+        ret
+    }
+
     fn flush(&mut self) -> IoResult<()> {
         self.flush_buf().and_then(|()| self.inner.get_mut_ref().flush())
     }
@@ -482,6 +507,26 @@ mod test {
                    writer.get_ref().get_ref());
     }
 
+    #[test]
+    fn test_buffered_writer_vectored() {
+        let mut writer = BufferedWriter::with_capacity(4, MemWriter::new());
+
+        let bufs: &[&[u8]] = &[&[0], &[1, 2]];
+        writer.write_vectored(bufs).unwrap();
+        let a: &[_] = &[];
+        assert_eq!(a, writer.get_ref().get_ref());
+
+        let bufs: &[&[u8]] = &[&[3, 4], &[5]];
+        writer.write_vectored(bufs).unwrap();
+        let a: &[_] = &[0, 1, 2, 3, 4, 5];
+        assert_eq!(a, writer.get_ref().get_ref());
+
+        writer.write([6]).unwrap();
+        writer.flush().unwrap();
+        let a: &[_] = &[0, 1, 2, 3, 4, 5, 6];
+        assert_eq!(a, writer.get_ref().get_ref());
+    }
+
     #[test]
     fn test_buffered_writer_inner_flushes() {
         let mut w = BufferedWriter::with_capacity(3, MemWriter::new());
@@ -54,8 +54,10 @@ fs::unlink(&path);
 use c_str::ToCStr;
 use clone::Clone;
 use collections::{Collection, MutableSeq};
+use comm::{Sender, Receiver, channel};
 use io::standard_error;
 use io::{FilePermission, Write, UnstableFileStat, Open, FileAccess, FileMode};
+use io::FileType;
 use io::{IoResult, IoError, FileStat, SeekStyle, Seek, Writer, Reader};
 use io::{Read, Truncate, SeekCur, SeekSet, ReadWrite, SeekEnd, Append};
 use io::UpdateIoError;
@@ -63,16 +65,22 @@ use io;
 use iter::Iterator;
 use kinds::Send;
 use libc;
+use mem;
 use option::{Some, None, Option};
+use os;
 use boxed::Box;
 use path::{Path, GenericPath};
 use path;
 use result::{Err, Ok};
+use raw;
 use rt::rtio::LocalIo;
 use rt::rtio;
-use slice::ImmutableSlice;
+use slice::{ImmutableSlice, Slice};
 use string::String;
+use to_string::ToString;
+use uint;
 use vec::Vec;
+use vec;
 
 /// Unconstrained file access type that exposes read and write operations
 ///
@@ -89,6 +97,8 @@ pub struct File {
     fd: Box<rtio::RtioFileStream + Send>,
     path: Path,
     last_nread: int,
+    // The kind of lock held through this file, if any: whether it's exclusive
+    locked: Option<bool>,
 }
 
 impl File {
@@ -137,6 +147,7 @@ impl File {
             Open => rtio::Open,
             Append => rtio::Append,
             Truncate => rtio::Truncate,
+            CreateNew => rtio::CreateNew,
         };
         let rtio_access = match access {
             Read => rtio::Read,
@@ -148,7 +159,8 @@ impl File {
                 File {
                     path: path.clone(),
                     fd: fd,
-                    last_nread: -1
+                    last_nread: -1,
+                    locked: None,
                 }
             })
         }).map_err(IoError::from_rtio_error);
@@ -258,6 +270,362 @@ impl File {
         err.update_err("couldn't fstat file",
                        |e| format!("{}; path={}", e, self.path.display()))
     }
+
+    /// Maps `len` bytes of this file, starting at `offset`, into memory.
+    ///
+    /// The returned mapping remains valid after this file is closed. The file
+    /// must have been opened for reading, and additionally for writing if
+    /// `mode` is `MapReadWrite`. The mapped range must lie within the current
+    /// bounds of the file; to map more of a file after it has been extended
+    /// with `truncate`, see `MappedFile::resize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #![allow(experimental)]
+    /// use std::io::File;
+    /// use std::io::fs::{MapReadOnly, AdviseSequential};
+    ///
+    /// let mut file = File::open(&Path::new("index.log")).unwrap();
+    /// let size = file.stat().unwrap().size as uint;
+    /// let map = file.map(0, size, MapReadOnly).unwrap();
+    /// map.advise(AdviseSequential).unwrap();
+    /// let newlines = map.as_slice().iter().filter(|&&b| b == b'\n').count();
+    /// ```
+    #[experimental = "the range argument may change types"]
+    pub fn map(&mut self, offset: u64, len: uint,
+               mode: MapMode) -> IoResult<MappedFile> {
+        let mut map = MappedFile {
+            map: None,
+            pad: 0,
+            len: 0,
+            offset: offset,
+            mode: mode,
+        };
+        try!(map.resize(self, len));
+        Ok(map)
+    }
+
+    /// Acquires a shared lock on this file, blocking until any exclusive lock
+    /// held elsewhere has been released.
+    ///
+    /// On Unix, locks are advisory: they only exclude other processes (or
+    /// other `File`s) which also take locks, and do nothing to stop plain
+    /// reads and writes. On Windows they are mandatory: while a file is locked
+    /// exclusively, reading or writing it through any other handle fails, and
+    /// so does writing it while it's locked shared.
+    ///
+    /// A lock is held until `unlock` is called or the file is closed. Taking a
+    /// lock on a file which is already locked through this `File` converts the
+    /// existing lock to the new kind. This isn't atomic: the lock is released
+    /// first, so another process may get it in between. If the new lock can't
+    /// be taken, the one held before is taken again, waiting for it if need
+    /// be.
+    ///
+    /// Waiting for a lock blocks the whole thread, so under green tasks it
+    /// stalls every other task on the same scheduler until the lock is
+    /// granted. The `try_lock_*` methods don't wait.
+    pub fn lock_shared(&mut self) -> IoResult<()> {
+        self.lock(false, true)
+    }
+
+    /// Acquires an exclusive lock on this file, blocking until all other
+    /// locks held elsewhere have been released.
+    ///
+    /// See `lock_shared` for the semantics of locks.
+    pub fn lock_exclusive(&mut self) -> IoResult<()> {
+        self.lock(true, true)
+    }
+
+    /// Attempts to acquire a shared lock on this file without blocking.
+    ///
+    /// # Error
+    ///
+    /// If the file is exclusively locked elsewhere, this returns an error of
+    /// kind `ResourceUnavailable`.
+    pub fn try_lock_shared(&mut self) -> IoResult<()> {
+        self.lock(false, false)
+    }
+
+    /// Attempts to acquire an exclusive lock on this file without blocking.
+    ///
+    /// # Error
+    ///
+    /// If the file is locked elsewhere, this returns an error of kind
+    /// `ResourceUnavailable`.
+    pub fn try_lock_exclusive(&mut self) -> IoResult<()> {
+        self.lock(true, false)
+    }
+
+    /// Releases the lock held on this file, if any.
+    pub fn unlock(&mut self) -> IoResult<()> {
+        let fd = try!(self.lock_fd());
+        let err = unlock_fd(fd);
+        if err.is_ok() { self.locked = None; }
+        err.update_err("couldn't unlock file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
+
+    fn lock(&mut self, exclusive: bool, block: bool) -> IoResult<()> {
+        let fd = try!(self.lock_fd());
+        let held = self.locked;
+        let err = match held {
+            Some(held) if held == exclusive => Ok(()),
+            // Windows can't convert a lock, and flock() converts one by
+            // releasing it first anyway, so it's done by hand on both
+            Some(held) => {
+                match unlock_fd(fd) {
+                    Ok(()) => {
+                        self.locked = None;
+                        let err = lock_fd(fd, exclusive, block);
+                        if err.is_err() && lock_fd(fd, held, true).is_ok() {
+                            self.locked = Some(held);
+                        }
+                        err
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            None => lock_fd(fd, exclusive, block),
+        };
+        if err.is_ok() { self.locked = Some(exclusive); }
+        err.update_err("couldn't lock file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
+
+    fn lock_fd(&self) -> IoResult<libc::c_int> {
+        match self.fd.raw_fd() {
+            Some(fd) => Ok(fd),
+            None => Err(IoError {
+                kind: io::IoUnavailable,
+                desc: "file has no descriptor to lock",
+                detail: None,
+            }),
+        }
+    }
+}
+
+// Whole-file locks are taken with flock() rather than fcntl(), as fcntl()
+// locks belong to the process rather than the descriptor and so are dropped
+// as soon as any descriptor for the file is closed.
+#[cfg(unix)]
+mod flock {
+    use libc;
+
+    pub static LOCK_SH: libc::c_int = 1;
+    pub static LOCK_EX: libc::c_int = 2;
+    pub static LOCK_NB: libc::c_int = 4;
+    pub static LOCK_UN: libc::c_int = 8;
+
+    extern {
+        pub fn flock(fd: libc::c_int, operation: libc::c_int) -> libc::c_int;
+    }
+}
+
+#[cfg(unix)]
+fn lock_fd(fd: libc::c_int, exclusive: bool, block: bool) -> IoResult<()> {
+    let mut op = if exclusive {flock::LOCK_EX} else {flock::LOCK_SH};
+    if !block { op |= flock::LOCK_NB; }
+    loop {
+        match unsafe { flock::flock(fd, op) } {
+            0 => return Ok(()),
+            _ if os::errno() == libc::EINTR as int => {}
+            _ => return Err(IoError::last_error()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unlock_fd(fd: libc::c_int) -> IoResult<()> {
+    match unsafe { flock::flock(fd, flock::LOCK_UN) } {
+        0 => Ok(()),
+        _ => Err(IoError::last_error()),
+    }
+}
+
+#[cfg(windows)]
+mod flock {
+    use libc;
+
+    pub static LOCKFILE_FAIL_IMMEDIATELY: libc::DWORD = 0x00000001;
+    pub static LOCKFILE_EXCLUSIVE_LOCK: libc::DWORD = 0x00000002;
+    pub static ERROR_LOCK_VIOLATION: libc::DWORD = 33;
+
+    #[allow(non_snake_case)]
+    extern "system" {
+        pub fn LockFileEx(hFile: libc::HANDLE,
+                          dwFlags: libc::DWORD,
+                          dwReserved: libc::DWORD,
+                          nNumberOfBytesToLockLow: libc::DWORD,
+                          nNumberOfBytesToLockHigh: libc::DWORD,
+                          lpOverlapped: libc::LPOVERLAPPED) -> libc::BOOL;
+        pub fn UnlockFileEx(hFile: libc::HANDLE,
+                            dwReserved: libc::DWORD,
+                            nNumberOfBytesToLockLow: libc::DWORD,
+                            nNumberOfBytesToLockHigh: libc::DWORD,
+                            lpOverlapped: libc::LPOVERLAPPED) -> libc::BOOL;
+    }
+}
+
+// Windows locks are mandatory byte-range locks, so the whole possible range of
+// the file is locked to emulate flock().
+#[cfg(windows)]
+fn lock_fd(fd: libc::c_int, exclusive: bool, block: bool) -> IoResult<()> {
+    let mut flags = 0;
+    if exclusive { flags |= flock::LOCKFILE_EXCLUSIVE_LOCK; }
+    if !block { flags |= flock::LOCKFILE_FAIL_IMMEDIATELY; }
+    let mut overlapped: libc::OVERLAPPED = unsafe { mem::zeroed() };
+    match unsafe {
+        let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+        flock::LockFileEx(handle, flags, 0, !0, !0, &mut overlapped)
+    } {
+        0 if os::errno() == flock::ERROR_LOCK_VIOLATION as int => {
+            Err(IoError {
+                kind: io::ResourceUnavailable,
+                desc: "file is locked",
+                detail: None,
+            })
+        }
+        0 => Err(IoError::last_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(windows)]
+fn unlock_fd(fd: libc::c_int) -> IoResult<()> {
+    let mut overlapped: libc::OVERLAPPED = unsafe { mem::zeroed() };
+    match unsafe {
+        let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+        flock::UnlockFileEx(handle, 0, !0, !0, &mut overlapped)
+    } {
+        0 => Err(IoError::last_error()),
+        _ => Ok(()),
+    }
+}
+
+/// A writer which replaces the contents of a file atomically.
+///
+/// Data is written to a temporary file alongside the target, which only
+/// replaces the target once `commit` is called (after the data has been
+/// flushed to disk). Other processes opening the target therefore either see
+/// its old contents or all of its new contents, never a partially written
+/// file, and a crash part way through leaves the old contents intact.
+///
+/// If an `AtomicFile` is dropped without being committed, the temporary file
+/// is removed and the target is left untouched.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![allow(unused_must_use)]
+/// use std::io::fs::AtomicFile;
+///
+/// let mut file = AtomicFile::new(&Path::new("config.toml")).unwrap();
+/// file.write_str("answer = 42\n");
+/// file.commit();
+/// ```
+pub struct AtomicFile {
+    // None once the file has been committed
+    file: Option<File>,
+    path: Path,
+}
+
+impl AtomicFile {
+    /// Creates a new, empty temporary file which will replace `path` when
+    /// committed. The temporary file is created in the same directory as
+    /// `path`, so that it resides on the same filesystem, and if `path`
+    /// exists, with the same permissions.
+    pub fn new(path: &Path) -> IoResult<AtomicFile> {
+        use rand;
+
+        let name = match path.filename() {
+            Some(name) => name,
+            None => return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "path has no file name",
+                detail: Some(format!("path={}", path.display())),
+            }),
+        };
+        let mut file = None;
+        while file.is_none() {
+            let mut tmpname = Vec::from_slice(b".");
+            tmpname.push_all(name);
+            tmpname.push_all(format!(".{:x}.tmp",
+                                     rand::random::<u32>()).as_bytes());
+            let tmp = path.with_filename(tmpname);
+            // Never open a file someone else created, in case they're
+            // writing to it too
+            match File::open_mode(&tmp, io::CreateNew, io::Write) {
+                Ok(f) => file = Some(f),
+                Err(..) if tmp.exists() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // Removes the temporary file if anything else fails
+        let atomic = AtomicFile { file: file, path: path.clone() };
+        match stat(path) {
+            Ok(stat) => try!(chmod(atomic.file.get_ref().path(), stat.perm)),
+            Err(..) => {}
+        }
+        Ok(atomic)
+    }
+
+    /// Returns the path of the file which will be replaced on commit.
+    pub fn path<'a>(&'a self) -> &'a Path { &self.path }
+
+    /// Returns the temporary file which is being written.
+    pub fn file<'a>(&'a mut self) -> &'a mut File {
+        self.file.get_mut_ref()
+    }
+
+    /// Flushes the written data to disk and then renames the temporary file
+    /// over the target.
+    ///
+    /// # Error
+    ///
+    /// If any step fails the temporary file is removed and the target is left
+    /// as it was.
+    pub fn commit(mut self) -> IoResult<()> {
+        let tmp = self.file.get_ref().path().clone();
+        {
+            let file = self.file.get_mut_ref();
+            try!(file.flush());
+            try!(file.fsync());
+        }
+        // Windows can't rename a file which is still open
+        mem::drop(self.file.take());
+        rename(&tmp, &self.path).map_err(|e| {
+            let _ = unlink(&tmp);
+            e
+        })
+    }
+}
+
+impl Writer for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.file.get_mut_ref().write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.file.get_mut_ref().write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.file.get_mut_ref().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        match self.file.take() {
+            Some(file) => {
+                let tmp = file.path().clone();
+                mem::drop(file);
+                let _ = unlink(&tmp);
+            }
+            None => {}
+        }
+    }
 }
 
 /// Unlink a file from the underlying filesystem.
@@ -366,12 +734,23 @@ pub fn lstat(path: &Path) -> IoResult<FileStat> {
                    |e| format!("{}; path={}", e, path.display()))
 }
 
-fn from_rtio(s: rtio::FileStat) -> FileStat {
+fn file_type(kind: u64) -> FileType {
     #[cfg(windows)]
     type Mode = libc::c_int;
     #[cfg(unix)]
     type Mode = libc::mode_t;
 
+    match (kind as Mode) & libc::S_IFMT {
+        libc::S_IFREG => io::TypeFile,
+        libc::S_IFDIR => io::TypeDirectory,
+        libc::S_IFIFO => io::TypeNamedPipe,
+        libc::S_IFBLK => io::TypeBlockSpecial,
+        libc::S_IFLNK => io::TypeSymlink,
+        _ => io::TypeUnknown,
+    }
+}
+
+fn from_rtio(s: rtio::FileStat) -> FileStat {
     let rtio::FileStat {
         size, kind, perm, created, modified,
         accessed, device, inode, rdev,
@@ -380,14 +759,7 @@ fn from_rtio(s: rtio::FileStat) -> FileStat {
 
     FileStat {
         size: size,
-        kind: match (kind as Mode) & libc::S_IFMT {
-            libc::S_IFREG => io::TypeFile,
-            libc::S_IFDIR => io::TypeDirectory,
-            libc::S_IFIFO => io::TypeNamedPipe,
-            libc::S_IFBLK => io::TypeBlockSpecial,
-            libc::S_IFLNK => io::TypeSymlink,
-            _ => io::TypeUnknown,
-        },
+        kind: file_type(kind),
         perm: FilePermission::from_bits_truncate(perm as u32),
         created: created,
         modified: modified,
@@ -697,6 +1069,292 @@ impl Iterator<Path> for Directories {
     }
 }
 
+/// A builder for, and iterator over, a walk of a directory tree.
+///
+/// Unlike `walk_dir`, each entry is yielded as a `DirEntry`, which carries the
+/// type of the entry as reported by the directory listing itself (where the
+/// platform supports it) and caches its metadata once looked up, so that the
+/// tree can be filtered without calling `stat` on every file in it.
+///
+/// By default the whole tree is walked, including the root itself at depth
+/// 0, each directory is yielded before its contents, and symlinks are not
+/// followed. Errors encountered along the way (such as unreadable
+/// directories) are yielded as they are found, and the walk carries on past
+/// them.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::fs::WalkDir;
+/// use std::io::TypeFile;
+///
+/// // Print all of the files in the tree below `src`, skipping hidden
+/// // directories.
+/// let walk = WalkDir::new(&Path::new("src")).min_depth(1).prune(|entry| {
+///     entry.path().filename().map_or(false, |name| name[0] == b'.')
+/// });
+/// for entry in walk {
+///     let mut entry = match entry {
+///         Ok(entry) => entry,
+///         Err(e) => { println!("{}", e); continue }
+///     };
+///     if entry.file_type().ok() == Some(TypeFile) {
+///         println!("{}", entry.path().display());
+///     }
+/// }
+/// ```
+pub struct WalkDir<'a> {
+    // Taken on the first call to `next`
+    root: Option<Path>,
+    min_depth: uint,
+    max_depth: uint,
+    follow_links: bool,
+    contents_first: bool,
+    prune: Option<|&mut DirEntry|: 'a -> bool>,
+
+    // One listing for each directory being walked, the last being the
+    // deepest.
+    stack: Vec<DirList>,
+    // An error to be yielded before anything else
+    pending: Option<IoError>,
+}
+
+struct DirList {
+    entries: vec::MoveItems<rtio::DirEntry>,
+    // The directory itself, when its contents are yielded first
+    dir: Option<DirEntry>,
+    // The device and inode of the directory, when following links
+    id: Option<(u64, u64)>,
+}
+
+/// An entry in a directory tree, yielded by `WalkDir`.
+pub struct DirEntry {
+    path: Path,
+    depth: uint,
+    // The type from the directory listing, if it was known. For symlinks this
+    // is the type of the link itself.
+    kind: Option<FileType>,
+    // Whether symlinks are followed, and so whether `stat` describes the
+    // target of a link rather than the link itself.
+    follow: bool,
+    stat: Option<FileStat>,
+}
+
+impl DirEntry {
+    /// Returns the path of this entry, which is the root of the walk joined
+    /// with the path to this entry from the root.
+    pub fn path<'a>(&'a self) -> &'a Path { &self.path }
+
+    /// Consumes this entry, returning its path.
+    pub fn into_path(self) -> Path { self.path }
+
+    /// Returns the depth of this entry below the root of the walk, the root
+    /// itself being at depth 0.
+    pub fn depth(&self) -> uint { self.depth }
+
+    /// Returns the type of this entry.
+    ///
+    /// This doesn't touch the filesystem if the directory listing included
+    /// the type of the entry. If symlinks are being followed, the type of a
+    /// symlink is that of its target.
+    pub fn file_type(&mut self) -> IoResult<FileType> {
+        match self.kind {
+            Some(io::TypeSymlink) if self.follow => {}
+            Some(kind) => return Ok(kind),
+            None => {}
+        }
+        self.stat().map(|stat| stat.kind)
+    }
+
+    /// Returns information about this entry, looking it up on the first call
+    /// and returning the same information on subsequent calls.
+    ///
+    /// If symlinks are being followed, the information returned for a symlink
+    /// is that of its target, otherwise it is that of the link itself.
+    pub fn stat(&mut self) -> IoResult<FileStat> {
+        match self.stat {
+            Some(stat) => return Ok(stat),
+            None => {}
+        }
+        let stat = try!(if self.follow {
+            stat(&self.path)
+        } else {
+            lstat(&self.path)
+        });
+        self.stat = Some(stat);
+        Ok(stat)
+    }
+}
+
+fn readdir_entries(path: &Path) -> IoResult<Vec<rtio::DirEntry>> {
+    let err = LocalIo::maybe_raise(|io| {
+        io.fs_readdir_entries(&path.to_c_str(), 0)
+    }).map_err(IoError::from_rtio_error);
+    err.update_err("couldn't read directory",
+                   |e| format!("{}; path={}", e, path.display()))
+}
+
+impl<'a> WalkDir<'a> {
+    /// Creates a walk of the tree rooted at `root`, with the default options.
+    pub fn new(root: &Path) -> WalkDir<'a> {
+        WalkDir {
+            root: Some(root.clone()),
+            min_depth: 0,
+            max_depth: uint::MAX,
+            follow_links: false,
+            contents_first: false,
+            prune: None,
+            stack: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Only yields entries at least `depth` levels below the root. Setting
+    /// this to 1 skips the root itself. Shallower directories are still
+    /// walked.
+    pub fn min_depth(mut self, depth: uint) -> WalkDir<'a> {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Doesn't descend into directories `depth` levels below the root, so
+    /// nothing deeper than `depth` is yielded. Setting this to 0 yields only
+    /// the root.
+    pub fn max_depth(mut self, depth: uint) -> WalkDir<'a> {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets whether symlinks are followed. When they are, links to
+    /// directories are walked as though they were the directory itself, and
+    /// an error is yielded in place of the contents of any link which leads
+    /// back to one of its own ancestors. The root is always followed.
+    pub fn follow_links(mut self, follow: bool) -> WalkDir<'a> {
+        self.follow_links = follow;
+        self
+    }
+
+    /// Sets whether each directory is yielded after its contents rather than
+    /// before them, which is the order needed to, for example, remove a tree.
+    pub fn contents_first(mut self, contents_first: bool) -> WalkDir<'a> {
+        self.contents_first = contents_first;
+        self
+    }
+
+    /// Skips every entry for which `prune` returns true, along with
+    /// everything below it. The root is subject to pruning too.
+    pub fn prune(mut self, prune: |&mut DirEntry|: 'a -> bool) -> WalkDir<'a> {
+        self.prune = Some(prune);
+        self
+    }
+
+    // Decides what to do with an entry which has just been found, returning
+    // it if it should be yielded straight away.
+    fn visit(&mut self, mut entry: DirEntry) -> Option<IoResult<DirEntry>> {
+        let pruned = match self.prune {
+            Some(ref mut prune) => (*prune)(&mut entry),
+            None => false,
+        };
+        if pruned { return None }
+
+        let is_dir = match entry.file_type() {
+            Ok(kind) => kind == io::TypeDirectory,
+            Err(e) => return Some(Err(e)),
+        };
+        let wanted = entry.depth >= self.min_depth;
+        if is_dir && entry.depth < self.max_depth {
+            match self.list(&mut entry) {
+                Ok(mut list) => {
+                    if self.contents_first {
+                        if wanted { list.dir = Some(entry); }
+                        self.stack.push(list);
+                        return None
+                    }
+                    self.stack.push(list);
+                }
+                // The directory is still yielded, followed by the error.
+                Err(e) => self.pending = Some(e),
+            }
+        }
+        if wanted { Some(Ok(entry)) } else { None }
+    }
+
+    fn list(&mut self, dir: &mut DirEntry) -> IoResult<DirList> {
+        let id = if self.follow_links {
+            let stat = try!(dir.stat());
+            let id = (stat.unstable.device, stat.unstable.inode);
+            if self.stack.iter().any(|list| list.id == Some(id)) {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "filesystem loop found",
+                    detail: Some(format!("path={}", dir.path.display())),
+                })
+            }
+            // Platforms without inode numbers report them as 0, in which
+            // case loops can't be detected.
+            if stat.unstable.inode == 0 { None } else { Some(id) }
+        } else {
+            None
+        };
+        let entries = try!(readdir_entries(&dir.path));
+        Ok(DirList { entries: entries.move_iter(), dir: None, id: id })
+    }
+}
+
+impl<'a> Iterator<IoResult<DirEntry>> for WalkDir<'a> {
+    fn next(&mut self) -> Option<IoResult<DirEntry>> {
+        match self.root.take() {
+            Some(root) => {
+                let root = DirEntry {
+                    path: root,
+                    depth: 0,
+                    kind: None,
+                    follow: true,
+                    stat: None,
+                };
+                match self.visit(root) {
+                    Some(entry) => return Some(entry),
+                    None => {}
+                }
+            }
+            None => {}
+        }
+
+        loop {
+            match self.pending.take() {
+                Some(e) => return Some(Err(e)),
+                None => {}
+            }
+            let depth = self.stack.len();
+            let next = match self.stack.mut_last() {
+                Some(list) => list.entries.next(),
+                None => return None,
+            };
+            match next {
+                Some(rtio::DirEntry { path, kind }) => {
+                    let entry = DirEntry {
+                        path: Path::new(path),
+                        depth: depth,
+                        kind: if kind == 0 {None} else {Some(file_type(kind))},
+                        follow: self.follow_links,
+                        stat: None,
+                    };
+                    match self.visit(entry) {
+                        Some(entry) => return Some(entry),
+                        None => {}
+                    }
+                }
+                None => {
+                    match self.stack.pop().unwrap().dir {
+                        Some(dir) => return Some(Ok(dir)),
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Recursively create a directory and all of its parent components if they
 /// are missing.
 ///
@@ -810,18 +1468,15 @@ pub fn change_file_times(path: &Path, atime: u64, mtime: u64) -> IoResult<()> {
                    |e| format!("{}; path={}", e, path.display()))
 }
 
-impl Reader for File {
-    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+impl File {
+    fn update_read(&mut self, result: IoResult<int>) -> IoResult<uint> {
         fn update_err<T>(result: IoResult<T>, file: &File) -> IoResult<T> {
             result.update_err("couldn't read file",
                               |e| format!("{}; path={}",
                                           e, file.path.display()))
         }
 
-        let result = update_err(self.fd.read(buf)
-                                    .map_err(IoError::from_rtio_error), self);
-
-        match result {
+        match update_err(result, self) {
             Ok(read) => {
                 self.last_nread = read;
                 match read {
@@ -834,12 +1489,30 @@ impl Reader for File {
     }
 }
 
+impl Reader for File {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        let result = self.fd.read(buf).map_err(IoError::from_rtio_error);
+        self.update_read(result)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        let result = self.fd.readv(bufs).map_err(IoError::from_rtio_error);
+        self.update_read(result)
+    }
+}
+
 impl Writer for File {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         let err = self.fd.write(buf).map_err(IoError::from_rtio_error);
         err.update_err("couldn't write to file",
                        |e| format!("{}; path={}", e, self.path.display()))
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let err = self.fd.writev(bufs).map_err(IoError::from_rtio_error);
+        err.update_err("couldn't write to file",
+                       |e| format!("{}; path={}", e, self.path.display()))
+    }
 }
 
 impl Seek for File {
@@ -868,6 +1541,414 @@ impl Seek for File {
     }
 }
 
+/// How the memory of a `MappedFile` relates to the file it was mapped from.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum MapMode {
+    /// The mapping can only be read from. Writing to it will fail the task.
+    MapReadOnly,
+    /// The mapping can be read and written, and writes are carried through to
+    /// the underlying file (and are visible to other mappings of it).
+    MapReadWrite,
+    /// The mapping can be read and written, but writes are private to this
+    /// mapping and never reach the underlying file. This is not supported on
+    /// windows.
+    MapCopyOnWrite,
+}
+
+/// Hints to the OS about how the memory of a `MappedFile` will be accessed.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum MapAdvice {
+    /// No special treatment, the default.
+    AdviseNormal,
+    /// Pages will be accessed in order, so may be read ahead aggressively and
+    /// dropped soon after they have been accessed.
+    AdviseSequential,
+    /// Pages will be accessed in a random order, so read-ahead is pointless.
+    AdviseRandom,
+    /// The mapping will be accessed soon, so it should be read in ahead of
+    /// time.
+    AdviseWillNeed,
+    /// The mapping will not be accessed in the near future.
+    AdviseDontNeed,
+}
+
+/// A region of a file mapped into memory, created by `File::map`.
+///
+/// The contents of the mapping can be accessed with `as_slice` and, for
+/// writable mappings, `as_mut_slice`. The mapping is released when this value
+/// is dropped.
+///
+/// Note that the contents of a shared mapping may change at any time if the
+/// underlying file is modified, whether through this process or another. If
+/// the file is shrunk, accessing the portion of the mapping past the new end
+/// of the file will crash the process.
+// FIXME: this should implement Deref<[u8]> and DerefMut<[u8]> once those
+//        traits accept unsized targets.
+#[experimental]
+pub struct MappedFile {
+    // None for empty mappings, as the OS will refuse to create those
+    map: Option<os::MemoryMap>,
+    // Mappings have to start on a multiple of the allocation granularity, so
+    // this is the distance from the start of `map` to the byte at `offset`.
+    pad: uint,
+    len: uint,
+    offset: u64,
+    mode: MapMode,
+}
+
+fn map_error(err: os::MapError) -> IoError {
+    let kind = match err {
+        os::ErrFdNotAvail => io::PermissionDenied,
+        os::ErrInvalidFd | os::ErrUnaligned | os::ErrNoMapSupport |
+        os::ErrZeroLength | os::ErrUnsupProt |
+        os::ErrUnsupOffset => io::InvalidInput,
+        _ => io::OtherIoError,
+    };
+    IoError {
+        kind: kind,
+        desc: "couldn't map file",
+        detail: Some(err.to_string()),
+    }
+}
+
+fn map_unavailable(desc: &'static str) -> IoError {
+    IoError { kind: io::IoUnavailable, desc: desc, detail: None }
+}
+
+impl MappedFile {
+    /// Changes the length of this mapping to `len` bytes, keeping the same
+    /// starting offset and mode.
+    ///
+    /// This is typically used to grow the mapping after the file has been
+    /// extended with `File::truncate`. As with `File::map`, the mapped range
+    /// must lie within the current bounds of `file`, which should be the file
+    /// this mapping was originally created from.
+    ///
+    /// The mapping may move in memory as a result of this call. If an error
+    /// is returned the mapping will be left empty.
+    pub fn resize(&mut self, file: &mut File, len: uint) -> IoResult<()> {
+        let size = try!(file.stat()).size;
+        if self.offset > size || len as u64 > size - self.offset {
+            return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "mapping extends past the end of the file",
+                detail: Some(format!("offset={}; len={}; size={}; path={}",
+                                     self.offset, len, size,
+                                     file.path.display())),
+            })
+        }
+        let fd = match file.fd.raw_fd() {
+            Some(fd) => fd,
+            None => return Err(map_unavailable("file has no descriptor")),
+        };
+
+        // Unmap the old region first, windows refuses to map a file twice.
+        self.map = None;
+        self.len = 0;
+
+        let granularity = os::MemoryMap::granularity() as u64;
+        let start = self.offset - self.offset % granularity;
+        if start > uint::MAX as u64 {
+            return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "offset is too large to map on this platform",
+                detail: None,
+            })
+        }
+        self.pad = (self.offset - start) as uint;
+        if len == 0 { return Ok(()) }
+
+        let mut opts = vec![os::MapReadable, os::MapFd(fd),
+                            os::MapOffset(start as uint)];
+        if self.mode != MapReadOnly {
+            opts.push(os::MapWritable);
+        }
+        opts.push(try!(MappedFile::sharing(self.mode)));
+
+        let map = try!(os::MemoryMap::new(self.pad + len, opts.as_slice())
+                           .map_err(map_error));
+        self.map = Some(map);
+        self.len = len;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn sharing(mode: MapMode) -> IoResult<os::MapOption> {
+        use libc::consts::os::posix88::MAP_SHARED;
+        let flags = match mode {
+            MapCopyOnWrite => libc::MAP_PRIVATE,
+            MapReadOnly | MapReadWrite => MAP_SHARED,
+        };
+        Ok(os::MapNonStandardFlags(flags | libc::MAP_FILE))
+    }
+
+    #[cfg(windows)]
+    fn sharing(mode: MapMode) -> IoResult<os::MapOption> {
+        match mode {
+            // Windows file mappings are always shared, the flags are ignored
+            MapReadOnly | MapReadWrite => Ok(os::MapNonStandardFlags(0)),
+            MapCopyOnWrite => {
+                Err(map_unavailable("copy-on-write mappings are unsupported"))
+            }
+        }
+    }
+
+    /// Returns the offset into the file at which this mapping starts.
+    pub fn offset(&self) -> u64 { self.offset }
+
+    /// Returns the mode this mapping was created with.
+    pub fn mode(&self) -> MapMode { self.mode }
+
+    /// Returns a mutable view of the contents of this mapping.
+    ///
+    /// # Failure
+    ///
+    /// Fails if this mapping was created with `MapReadOnly`.
+    pub fn as_mut_slice<'a>(&'a mut self) -> &'a mut [u8] {
+        assert!(self.mode != MapReadOnly,
+                "cannot write to a read-only mapping");
+        unsafe { mem::transmute(self.as_slice()) }
+    }
+
+    /// Writes any modifications made to this mapping back to the file,
+    /// blocking until they have been written. This has no effect on
+    /// `MapCopyOnWrite` mappings.
+    pub fn flush(&self) -> IoResult<()> {
+        self.sync(true)
+    }
+
+    /// Schedules any modifications made to this mapping to be written back to
+    /// the file, without waiting for the writes to complete.
+    pub fn flush_async(&self) -> IoResult<()> {
+        self.sync(false)
+    }
+
+    #[cfg(unix)]
+    fn sync(&self, wait: bool) -> IoResult<()> {
+        use libc::consts::os::posix88::{MS_SYNC, MS_ASYNC};
+        use libc::funcs::posix88::mman::msync;
+        let map = match self.map { Some(ref map) => map, None => return Ok(()) };
+        let flags = if wait {MS_SYNC} else {MS_ASYNC};
+        match unsafe {
+            msync(map.data() as *mut libc::c_void,
+                  (self.pad + self.len) as libc::size_t, flags)
+        } {
+            0 => Ok(()),
+            _ => Err(IoError::last_error()),
+        }
+    }
+
+    #[cfg(windows)]
+    fn sync(&self, _wait: bool) -> IoResult<()> {
+        extern "system" {
+            fn FlushViewOfFile(base: *const libc::c_void,
+                               len: libc::SIZE_T) -> libc::BOOL;
+        }
+        let map = match self.map { Some(ref map) => map, None => return Ok(()) };
+        // FlushViewOfFile never waits for the disk, the best that can be
+        // done without the file's handle.
+        match unsafe {
+            FlushViewOfFile(map.data() as *const libc::c_void,
+                            (self.pad + self.len) as libc::SIZE_T)
+        } {
+            0 => Err(IoError::last_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Advises the OS of how this mapping is going to be accessed, allowing it
+    /// to tune read-ahead and caching. This is purely a hint, and is ignored
+    /// on platforms which don't support it.
+    pub fn advise(&self, advice: MapAdvice) -> IoResult<()> {
+        self.madvise(advice)
+    }
+
+    #[cfg(unix)]
+    fn madvise(&self, advice: MapAdvice) -> IoResult<()> {
+        use libc::consts::os::bsd44::{MADV_NORMAL, MADV_SEQUENTIAL};
+        use libc::consts::os::bsd44::{MADV_RANDOM, MADV_WILLNEED};
+        use libc::consts::os::bsd44::MADV_DONTNEED;
+        use libc::funcs::bsd44::madvise;
+        let map = match self.map { Some(ref map) => map, None => return Ok(()) };
+        let advice = match advice {
+            AdviseNormal => MADV_NORMAL,
+            AdviseSequential => MADV_SEQUENTIAL,
+            AdviseRandom => MADV_RANDOM,
+            AdviseWillNeed => MADV_WILLNEED,
+            AdviseDontNeed => MADV_DONTNEED,
+        };
+        match unsafe {
+            madvise(map.data() as *mut libc::c_void,
+                    (self.pad + self.len) as libc::size_t, advice)
+        } {
+            0 => Ok(()),
+            _ => Err(IoError::last_error()),
+        }
+    }
+
+    #[cfg(windows)]
+    fn madvise(&self, _advice: MapAdvice) -> IoResult<()> { Ok(()) }
+}
+
+impl Slice<u8> for MappedFile {
+    fn as_slice<'a>(&'a self) -> &'a [u8] {
+        match self.map {
+            Some(ref map) => unsafe {
+                mem::transmute(raw::Slice {
+                    data: map.data().offset(self.pad as int) as *const u8,
+                    len: self.len,
+                })
+            },
+            None => &[],
+        }
+    }
+}
+
+impl Collection for MappedFile {
+    fn len(&self) -> uint { self.len }
+}
+
+/// The kinds of change reported by a `Watcher`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum WatchEventKind {
+    /// A file or directory was created.
+    Created,
+    /// The contents or metadata of a file or directory changed.
+    Modified,
+    /// A file or directory was deleted.
+    Removed,
+    /// A file or directory was renamed, and this is either its old or new
+    /// name. Some platforms cannot distinguish creation and deletion from
+    /// renames, and report every change to a directory's entries this way.
+    Renamed,
+}
+
+/// A change to the filesystem, as reported by a `Watcher`.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct WatchEvent {
+    /// What happened to the path.
+    pub kind: WatchEventKind,
+    /// The path which changed. This is always the watched path joined with
+    /// the name of the entry which changed (if any) below it.
+    pub path: Path,
+}
+
+/// Watcher provides a receiver of changes to files and directory trees.
+///
+/// Events are delivered as they are reported by the operating system, and
+/// may be coalesced or split in platform-specific ways; they should be taken
+/// as a hint to go and look at the path in question rather than as an exact
+/// record of what happened. Watching is currently supported on Linux (native
+/// and green tasks), and on other platforms supported by libuv for green
+/// tasks.
+///
+/// Watcher automatically stops watching all of its paths once it is out of
+/// scope.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![allow(unused_must_use)]
+/// use std::io::fs::Watcher;
+/// use std::io::timer::Timer;
+/// use std::time::Duration;
+///
+/// let mut watcher = Watcher::new();
+/// watcher.watch(&Path::new("src"), true);
+/// let mut timer = Timer::new().unwrap();
+/// let timeout = timer.oneshot(Duration::seconds(10));
+///
+/// loop {
+///     select! {
+///         event = watcher.rx.recv() => {
+///             println!("{}: {}", event.kind, event.path.display());
+///         },
+///         () = timeout.recv() => break
+///     }
+/// }
+/// ```
+pub struct Watcher {
+    /// The handles for each watched path, which stop watching when dropped
+    handles: Vec<(Path, Box<rtio::RtioFsWatcher + Send>)>,
+    /// This is where all the handles send events, which are received by the
+    /// clients from the receiver.
+    tx: Sender<WatchEvent>,
+
+    /// Clients of Watcher can `recv()` on this receiver. This is exposed to
+    /// allow selection over it as well as manipulation of the receiver
+    /// directly.
+    pub rx: Receiver<WatchEvent>,
+}
+
+impl Watcher {
+    /// Creates a new watcher. Once created, paths are watched via the `watch`
+    /// method (otherwise nothing will ever be received).
+    pub fn new() -> Watcher {
+        let (tx, rx) = channel();
+        Watcher {
+            handles: Vec::new(),
+            tx: tx,
+            rx: rx,
+        }
+    }
+
+    /// Starts watching `path` for changes. If `path` is a directory, changes
+    /// to its entries are reported as well, and if `recursive` is true then
+    /// so are changes anywhere in the tree below it.
+    ///
+    /// Watching a path which is already being watched by this watcher does
+    /// nothing and succeeds.
+    ///
+    /// # Error
+    ///
+    /// This function will return an error if `path` does not exist, or if the
+    /// current platform or runtime cannot watch it.
+    pub fn watch(&mut self, path: &Path, recursive: bool) -> IoResult<()> {
+        struct WatchCallback {
+            tx: Sender<WatchEvent>,
+        }
+        impl rtio::FsEventCallback for WatchCallback {
+            fn call(&mut self, event: rtio::FsEvent) {
+                let kind = match event.kind {
+                    rtio::FsCreate => Created,
+                    rtio::FsModify => Modified,
+                    rtio::FsDelete => Removed,
+                    rtio::FsRename => Renamed,
+                };
+                // The watcher may have gone away while the event was in
+                // flight, in which case nobody is interested any more.
+                let _ = self.tx.send_opt(WatchEvent {
+                    kind: kind,
+                    path: Path::new(event.path),
+                });
+            }
+        }
+
+        if self.handles.iter().any(|&(ref p, _)| p == path) {
+            return Ok(())
+        }
+        let err = LocalIo::maybe_raise(|io| {
+            io.fs_watch(&path.to_c_str(), recursive, box WatchCallback {
+                tx: self.tx.clone(),
+            })
+        }).map_err(IoError::from_rtio_error);
+        let handle = try!(err.update_err("couldn't watch path",
+                                         |e| format!("{}; path={}",
+                                                     e, path.display())));
+        self.handles.push((path.clone(), handle));
+        Ok(())
+    }
+
+    /// Stops watching `path`. Events for it which have already been received
+    /// may still be returned by `recv`.
+    pub fn unwatch(&mut self, path: &Path) {
+        match self.handles.iter().position(|&(ref p, _)| p == path) {
+            Some(i) => mem::drop(self.handles.remove(i)),
+            None => {}
+        }
+    }
+}
+
 impl path::Path {
     /// Get information on the file, directory, etc at this path.
     ///
@@ -918,7 +1999,8 @@ fn mode_string(mode: FileMode) -> &'static str {
     match mode {
         super::Open => "open",
         super::Append => "append",
-        super::Truncate => "truncate"
+        super::Truncate => "truncate",
+        super::CreateNew => "create new",
     }
 }
 
@@ -1255,6 +2337,79 @@ mod test {
         check!(rmdir_recursive(dir));
     })
 
+    iotest!(fn file_test_walk_dir_builder() {
+        use io::fs::WalkDir;
+
+        let tmpdir = tmpdir();
+        let dir = &tmpdir.join("walk_dir");
+        check!(mkdir_recursive(&dir.join("a/b"), io::UserRWX));
+        check!(mkdir_recursive(&dir.join(".hidden"), io::UserRWX));
+        check!(File::create(&dir.join("a/b/file")));
+        check!(File::create(&dir.join(".hidden/file")));
+
+        fn names(root: &Path, walk: WalkDir) -> Vec<String> {
+            walk.map(|entry| {
+                let entry = check!(entry);
+                let path = entry.path().path_relative_from(root).unwrap();
+                path.as_str().unwrap().replace("\\", "/")
+            }).collect()
+        }
+
+        let mut all = names(dir, WalkDir::new(dir));
+        all.sort();
+        assert_eq!(all, vec!(".".to_string(), ".hidden".to_string(),
+                             ".hidden/file".to_string(), "a".to_string(),
+                             "a/b".to_string(), "a/b/file".to_string()));
+
+        let pruned = names(dir, WalkDir::new(dir).min_depth(1).prune(|e| {
+            e.path().filename() == Some(b".hidden")
+        }));
+        assert_eq!(pruned.len(), 3);
+        assert!(pruned.iter().all(|p| p.as_slice().starts_with("a")));
+
+        let shallow = names(dir, WalkDir::new(dir).min_depth(1).max_depth(1));
+        assert_eq!(shallow.len(), 2);
+
+        let post = names(dir, WalkDir::new(dir).prune(|e| {
+            e.path().filename() == Some(b".hidden")
+        }).contents_first(true));
+        assert_eq!(post, vec!("a/b/file".to_string(), "a/b".to_string(),
+                              "a".to_string(), ".".to_string()));
+
+        for entry in WalkDir::new(dir) {
+            let mut entry = check!(entry);
+            let kind = check!(entry.file_type());
+            assert_eq!(kind, check!(entry.stat()).kind);
+            assert_eq!(kind == io::TypeDirectory, entry.path().is_dir());
+        }
+    })
+
+    iotest!(fn file_test_walk_dir_links() {
+        use io::fs::WalkDir;
+
+        let tmpdir = tmpdir();
+        let dir = &tmpdir.join("walk_dir");
+        check!(mkdir_recursive(&dir.join("a"), io::UserRWX));
+        check!(symlink(dir, &dir.join("a/loop")));
+
+        // Without following links, the link is just another entry
+        let mut n = 0u;
+        for entry in WalkDir::new(dir) {
+            let mut entry = check!(entry);
+            if entry.depth() == 2 {
+                assert_eq!(check!(entry.file_type()), io::TypeSymlink);
+            }
+            n += 1;
+        }
+        assert_eq!(n, 3);
+
+        // Following them, the loop is reported but doesn't stop the walk
+        let results: Vec<io::IoResult<io::fs::DirEntry>> =
+            WalkDir::new(dir).follow_links(true).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    } #[ignore(cfg(windows))])
+
     iotest!(fn recursive_mkdir() {
         let tmpdir = tmpdir();
         let dir = tmpdir.join("d1/d2");
@@ -1533,6 +2688,278 @@ mod test {
         drop(file);
     })
 
+    iotest!(fn map_read_write() {
+        use io::fs::{MapReadOnly, MapReadWrite, MapCopyOnWrite, AdviseRandom};
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("map.txt");
+        let mut file = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+        check!(file.write(b"hello world"));
+        check!(file.fsync());
+
+        {
+            let map = check!(file.map(6, 5, MapReadOnly));
+            check!(map.advise(AdviseRandom));
+            assert_eq!(map.as_slice(), b"world");
+        }
+        {
+            let mut map = check!(file.map(0, 5, MapCopyOnWrite));
+            map.as_mut_slice()[0] = b'j';
+            assert_eq!(map.as_slice(), b"jello");
+        }
+        {
+            let mut map = check!(file.map(0, 5, MapReadWrite));
+            map.as_mut_slice()[0] = b'y';
+            check!(map.flush());
+        }
+        assert_eq!(check!(File::open(&path).read_to_end()),
+                   Vec::from_slice(b"yello world"));
+
+        // mapping past the end of the file is an error
+        assert!(file.map(6, 6, MapReadOnly).is_err());
+    } #[ignore(cfg(windows))])
+
+    iotest!(fn map_resize() {
+        use io::fs::MapReadWrite;
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("map.txt");
+        let mut file = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+
+        let mut map = check!(file.map(0, 0, MapReadWrite));
+        assert_eq!(map.len(), 0);
+        check!(file.truncate(4));
+        check!(map.resize(&mut file, 4));
+        map.as_mut_slice().copy_from(b"abcd");
+        check!(map.flush());
+        drop(map);
+        assert_eq!(check!(File::open(&path).read_to_end()),
+                   Vec::from_slice(b"abcd"));
+    })
+
+    iotest!(fn file_vectored() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("vectored");
+        check!(File::create(&path).write_vectored(["hello".as_bytes(), "".as_bytes(),
+                                                   " ".as_bytes(), "world".as_bytes()]));
+
+        // Each read fills the buffers in order, though it may stop short
+        let mut file = check!(File::open(&path));
+        let mut data = Vec::new();
+        loop {
+            let mut a = [0u8, ..3];
+            let mut b = [0u8, ..4];
+            let mut empty: [u8, ..0] = [];
+            let n = {
+                let mut bufs = [a.as_mut_slice(), empty.as_mut_slice(), b.as_mut_slice()];
+                match file.read_vectored(bufs) {
+                    Ok(n) => n,
+                    Err(ref e) if e.kind == io::EndOfFile => break,
+                    Err(e) => fail!("{}", e),
+                }
+            };
+            assert!(n <= 7);
+            if n > 3 {
+                data.push_all(a.as_slice());
+                data.push_all(b.slice_to(n - 3));
+            } else {
+                data.push_all(a.slice_to(n));
+            }
+        }
+        assert_eq!(data.as_slice(), "hello world".as_bytes());
+    })
+
+    iotest!(fn file_vectored_many_buffers() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("vectored");
+        // More buffers than one writev() takes, some of them empty
+        let chunks = Vec::from_fn(5000, |i| Vec::from_elem(i % 7, i as u8));
+        let bufs: Vec<&[u8]> = chunks.iter().map(|c| c.as_slice()).collect();
+        check!(File::create(&path).write_vectored(bufs.as_slice()));
+
+        let mut expected = Vec::new();
+        for chunk in chunks.iter() {
+            expected.push_all(chunk.as_slice());
+        }
+        assert_eq!(check!(File::open(&path).read_to_end()), expected);
+    })
+
+    iotest!(fn file_locks() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+        let mut b = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+
+        check!(a.lock_shared());
+        check!(b.try_lock_shared());
+        match b.try_lock_exclusive() {
+            Err(ref e) if e.kind == io::ResourceUnavailable => {}
+            r => fail!("exclusive lock over a shared lock: {}", r),
+        }
+        check!(a.unlock());
+        check!(b.try_lock_exclusive());
+        match a.try_lock_shared() {
+            Err(ref e) if e.kind == io::ResourceUnavailable => {}
+            r => fail!("shared lock over an exclusive lock: {}", r),
+        }
+        drop(b);
+        check!(a.try_lock_exclusive());
+    })
+
+    iotest!(fn failed_lock_conversion_keeps_lock() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+        let mut b = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+        let mut c = check!(File::open_mode(&path, io::Open, io::ReadWrite));
+
+        check!(a.lock_shared());
+        check!(b.lock_shared());
+        match a.try_lock_exclusive() {
+            Err(ref e) if e.kind == io::ResourceUnavailable => {}
+            r => fail!("exclusive lock over a shared lock: {}", r),
+        }
+        // `a` is left with the shared lock it had
+        drop(b);
+        match c.try_lock_exclusive() {
+            Err(ref e) if e.kind == io::ResourceUnavailable => {}
+            r => fail!("exclusive lock over a shared lock: {}", r),
+        }
+        check!(a.unlock());
+        check!(c.try_lock_exclusive());
+    })
+
+    iotest!(fn atomic_file() {
+        use io::fs::AtomicFile;
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("atomic");
+        check!(File::create(&path).write(b"old"));
+
+        {
+            let mut file = check!(AtomicFile::new(&path));
+            check!(file.write(b"new"));
+            assert_eq!(check!(readdir(tmpdir.path())).len(), 2);
+        }
+        assert_eq!(check!(readdir(tmpdir.path())).len(), 1);
+        assert_eq!(check!(File::open(&path).read_to_end()),
+                   Vec::from_slice(b"old"));
+
+        let mut file = check!(AtomicFile::new(&path));
+        check!(file.write(b"new"));
+        check!(file.commit());
+        assert_eq!(check!(readdir(tmpdir.path())).len(), 1);
+        assert_eq!(check!(File::open(&path).read_to_end()),
+                   Vec::from_slice(b"new"));
+    })
+
+    iotest!(fn atomic_file_keeps_permissions() {
+        use io::fs::AtomicFile;
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("atomic");
+        let perm = io::UserRead | io::UserWrite | io::GroupRead;
+        check!(File::create(&path).write(b"old"));
+        check!(chmod(&path, perm));
+
+        let mut file = check!(AtomicFile::new(&path));
+        check!(file.write(b"new"));
+        check!(file.commit());
+        assert_eq!(check!(stat(&path)).perm, perm);
+    } #[cfg(unix)])
+
+    // Waits for an event of `kind` on `path`, skipping any others
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn wait_for(watcher: &io::fs::Watcher, path: &Path, kind: io::fs::WatchEventKind) {
+        loop {
+            match watcher.rx.recv_timeout(5000) {
+                Ok(ref event) if event.path == *path && event.kind == kind => return,
+                Ok(..) => {}
+                Err(..) => fail!("no {} event for {}", kind, path.display()),
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn watch_dir(created: io::fs::WatchEventKind, removed: io::fs::WatchEventKind) {
+        use io::fs::{Watcher, Modified};
+
+        let tmpdir = tmpdir();
+        let dir = tmpdir.join("watched");
+        check!(mkdir(&dir, io::UserRWX));
+        let mut watcher = Watcher::new();
+        check!(watcher.watch(&dir, false));
+        check!(watcher.watch(&dir, false));
+
+        let sub = dir.join("sub");
+        check!(mkdir(&sub, io::UserRWX));
+        wait_for(&watcher, &sub, created);
+
+        let file = dir.join("file");
+        let mut f = check!(File::create(&file));
+        wait_for(&watcher, &file, created);
+        check!(f.write(b"foo"));
+        check!(f.flush());
+        wait_for(&watcher, &file, Modified);
+        drop(f);
+        check!(unlink(&file));
+        wait_for(&watcher, &file, removed);
+
+        watcher.unwatch(&dir);
+        assert!(watcher.watch(&tmpdir.join("missing"), false).is_err());
+    }
+
+    // libuv reports entries being created or removed as renames
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn watch_dir_green() {
+        use io::fs::Renamed;
+        watch_dir(Renamed, Renamed)
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn watch_dir_native() {
+        use io::fs::{Created, Removed};
+        use native;
+        let (tx, rx) = channel();
+        native::task::spawn(proc() { tx.send(watch_dir(Created, Removed)) });
+        rx.recv();
+    }
+
+    // A directory moved within a recursively watched tree is reported under
+    // its new name afterwards
+    #[test]
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    fn watch_moved_dir_native() {
+        use io::fs::{Watcher, Created, Renamed, rename};
+        use native;
+        let (tx, rx) = channel();
+        native::task::spawn(proc() {
+            let tmpdir = tmpdir();
+            let dir = tmpdir.join("watched");
+            check!(mkdir(&dir, io::UserRWX));
+            let mut watcher = Watcher::new();
+            check!(watcher.watch(&dir, true));
+
+            let a = dir.join("a");
+            check!(mkdir(&a, io::UserRWX));
+            wait_for(&watcher, &a, Created);
+            let b = dir.join("b");
+            check!(rename(&a, &b));
+            wait_for(&watcher, &b, Renamed);
+            check!(File::create(&b.join("file")));
+            wait_for(&watcher, &b.join("file"), Created);
+            tx.send(());
+        });
+        rx.recv();
+    }
+
     iotest!(fn open_flavors() {
         let tmpdir = tmpdir();
 
@@ -1591,6 +3018,11 @@ mod test {
         }
         assert!(check!(stat(&tmpdir.join("h"))).size == 3,
                 "truncate didn't truncate");
+
+        check!(File::open_mode(&tmpdir.join("i"), io::CreateNew, io::Write));
+        assert!(tmpdir.join("i").exists());
+        assert!(File::open_mode(&tmpdir.join("i"), io::CreateNew, io::Write).is_err());
+        assert!(File::open_mode(&tmpdir.join("h"), io::CreateNew, io::Read).is_err());
     })
 
     iotest!(fn utime() {
@@ -18,6 +18,7 @@ use option::None;
 use result::{Err, Ok};
 use io;
 use io::{Reader, Writer, Seek, Buffer, IoError, SeekStyle, IoResult};
+use iter::Iterator;
 use slice;
 use slice::{Slice, ImmutableSlice, MutableSlice};
 use vec::Vec;
@@ -90,6 +91,15 @@ impl Writer for MemWriter {
         self.buf.push_all(buf);
         Ok(())
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        let len = bufs.iter().fold(0, |n, b| n + b.len());
+        self.buf.reserve_additional(len);
+        for buf in bufs.iter() {
+            self.buf.push_all(*buf);
+        }
+        Ok(())
+    }
 }
 
 /// Reads from an owned byte vector
@@ -350,6 +360,16 @@ mod test {
         assert_eq!(writer.get_ref(), b);
     }
 
+    #[test]
+    fn test_mem_writer_vectored() {
+        let mut writer = MemWriter::new();
+        let bufs: &[&[u8]] = &[&[0], &[], &[1, 2, 3]];
+        writer.write_vectored(bufs).unwrap();
+        writer.write_vectored(&[]).unwrap();
+        let b: &[_] = &[0, 1, 2, 3];
+        assert_eq!(writer.get_ref(), b);
+    }
+
     #[test]
     fn test_buf_writer() {
         let mut buf = [0 as u8, ..8];
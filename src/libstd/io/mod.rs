@@ -418,6 +418,7 @@ impl IoError {
         ioerr.detail = detail;
         ioerr.kind = match ioerr.kind {
             TimedOut if extra > 0 => ShortWrite(extra),
+            ResourceUnavailable if extra > 0 => ShortWrite(extra),
             k => k,
         };
         return ioerr;
@@ -554,6 +555,25 @@ pub trait Reader {
     /// not to return 0 if you can avoid it.
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint>;
 
+    /// Read bytes into a sequence of buffers, filling each buffer completely
+    /// before moving on to the next. Returns the total number of bytes read.
+    /// Like `read`, the number of bytes read may be less than the combined
+    /// length of the buffers.
+    ///
+    /// The default implementation reads into the first non-empty buffer with a
+    /// single call to `read`. Readers backed by the OS override this to fill
+    /// all of the buffers with one call to `readv`.
+    ///
+    /// # Error
+    ///
+    /// See `read()` for details.
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        match bufs.mut_iter().find(|b| b.len() > 0) {
+            Some(buf) => self.read(&mut **buf),
+            None => Ok(0),
+        }
+    }
+
     // Convenient helper methods based on the above methods
 
     /// Reads at least `min` bytes and places them in `buf`.
@@ -947,10 +967,16 @@ pub trait Reader {
 
 impl Reader for Box<Reader+'static> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> { self.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.read_vectored(bufs)
+    }
 }
 
 impl<'a> Reader for &'a mut Reader+'a {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> { self.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.read_vectored(bufs)
+    }
 }
 
 /// Returns a slice of `v` between `start` and `end`.
@@ -1007,6 +1033,9 @@ pub struct RefReader<'a, R:'a> {
 
 impl<'a, R: Reader> Reader for RefReader<'a, R> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> { self.inner.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.inner.read_vectored(bufs)
+    }
 }
 
 impl<'a, R: Buffer> Buffer for RefReader<'a, R> {
@@ -1040,6 +1069,25 @@ pub trait Writer {
     /// data (if any) was actually written.
     fn write(&mut self, buf: &[u8]) -> IoResult<()>;
 
+    /// Write the entirety of each of the given buffers, in order
+    ///
+    /// This is equivalent to writing the concatenation of all of `bufs`, but
+    /// writers backed by the OS override this to hand all of the buffers over
+    /// with one call to `writev`, sparing the caller from first copying them
+    /// into a single buffer. The default implementation calls `write` once for
+    /// each buffer.
+    ///
+    /// # Errors
+    ///
+    /// See `write()` for details. As with `write`, if an error is returned it
+    /// is unknown how much of the data was actually written.
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        for buf in bufs.iter() {
+            try!(self.write(*buf));
+        }
+        Ok(())
+    }
+
     /// Flush this output stream, ensuring that all intermediately buffered
     /// contents reach their destination.
     ///
@@ -1283,6 +1331,11 @@ impl Writer for Box<Writer+'static> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> IoResult<()> { self.write(buf) }
 
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.write_vectored(bufs)
+    }
+
     #[inline]
     fn flush(&mut self) -> IoResult<()> { self.flush() }
 }
@@ -1291,6 +1344,11 @@ impl<'a> Writer for &'a mut Writer+'a {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> IoResult<()> { self.write(buf) }
 
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.write_vectored(bufs)
+    }
+
     #[inline]
     fn flush(&mut self) -> IoResult<()> { self.flush() }
 }
@@ -1328,6 +1386,11 @@ impl<'a, W: Writer> Writer for RefWriter<'a, W> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> IoResult<()> { self.inner.write(buf) }
 
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.inner.write_vectored(bufs)
+    }
+
     #[inline]
     fn flush(&mut self) -> IoResult<()> { self.inner.flush() }
 }
@@ -1680,6 +1743,8 @@ pub enum FileMode {
     Append,
     /// Opens a file, truncating it if it already exists.
     Truncate,
+    /// Creates a new file, failing if anything already exists at its path.
+    CreateNew,
 }
 
 /// Access permissions with which the file should be opened. `File`s
@@ -10,18 +10,160 @@
 
 //! Networking I/O
 
+use option::{Option, Some, None};
 use rt::rtio;
 use self::ip::{Ipv4Addr, Ipv6Addr, IpAddr};
+use time::Duration;
 
 pub use self::addrinfo::get_host_addresses;
 
 pub mod addrinfo;
+pub mod poll;
 pub mod tcp;
 pub mod udp;
+pub mod raw;
 pub mod ip;
 // FIXME(#12093) - this should not be called unix
 pub mod unix;
 
+/// A builder for TCP and UDP sockets which need options set on them before
+/// they are bound or connected.
+///
+/// Each of the options defaults to the platform's default behavior, with the
+/// exception that TCP listeners on unix platforms are created with
+/// `SO_REUSEADDR` unless `reuse_address(false)` is specified. Once configured,
+/// the builder can be used to create any number of sockets with
+/// `tcp_connect`, `tcp_bind` or `udp_bind`.
+///
+/// Not all options are supported by every platform or runtime. Requesting an
+/// unsupported option will cause socket creation to fail with an error of kind
+/// `IoUnavailable`.
+///
+/// # Example
+///
+/// ```no_run
+/// # #![allow(experimental)]
+/// use std::io::{Listener, Acceptor};
+/// use std::io::net::SocketBuilder;
+///
+/// let listener = SocketBuilder::new()
+///                              .reuse_port(true)
+///                              .backlog(1024)
+///                              .tcp_bind("0.0.0.0", 8080);
+/// let mut acceptor = listener.listen();
+/// for stream in acceptor.incoming() {
+///     // handle the connection
+/// #   drop(stream);
+/// }
+/// ```
+#[experimental = "the set of options is likely to change"]
+pub struct SocketBuilder {
+    reuse_address: Option<bool>,
+    reuse_port: Option<bool>,
+    recv_buffer_size: Option<uint>,
+    send_buffer_size: Option<uint>,
+    linger: Option<Option<uint>>,
+    only_v6: Option<bool>,
+    quickack: Option<bool>,
+    backlog: Option<uint>,
+}
+
+impl SocketBuilder {
+    /// Creates a new builder with every option left at its default.
+    pub fn new() -> SocketBuilder {
+        SocketBuilder {
+            reuse_address: None,
+            reuse_port: None,
+            recv_buffer_size: None,
+            send_buffer_size: None,
+            linger: None,
+            only_v6: None,
+            quickack: None,
+            backlog: None,
+        }
+    }
+
+    /// Sets `SO_REUSEADDR`, allowing a listener to bind to an address which
+    /// still has connections lingering in `TIME_WAIT` from a previous process.
+    pub fn reuse_address<'a>(&'a mut self, on: bool) -> &'a mut SocketBuilder {
+        self.reuse_address = Some(on);
+        self
+    }
+
+    /// Sets `SO_REUSEPORT`, allowing multiple sockets to bind to the same
+    /// address and port. This is not supported on windows.
+    pub fn reuse_port<'a>(&'a mut self, on: bool) -> &'a mut SocketBuilder {
+        self.reuse_port = Some(on);
+        self
+    }
+
+    /// Sets the size of the kernel's receive buffer (`SO_RCVBUF`), in bytes.
+    ///
+    /// The kernel may round or scale this value, see `socket(7)`.
+    pub fn recv_buffer_size<'a>(&'a mut self,
+                                bytes: uint) -> &'a mut SocketBuilder {
+        self.recv_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Sets the size of the kernel's send buffer (`SO_SNDBUF`), in bytes.
+    ///
+    /// The kernel may round or scale this value, see `socket(7)`.
+    pub fn send_buffer_size<'a>(&'a mut self,
+                                bytes: uint) -> &'a mut SocketBuilder {
+        self.send_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Sets `SO_LINGER`. With a duration, closing the socket will block for
+    /// up to that long while unsent data is delivered. With `None`, lingering
+    /// is turned off. The duration is rounded down to whole seconds.
+    pub fn linger<'a>(&'a mut self,
+                      dur: Option<Duration>) -> &'a mut SocketBuilder {
+        self.linger = Some(dur.map(|d| {
+            if d < Duration::seconds(0) {0} else {d.num_seconds() as uint}
+        }));
+        self
+    }
+
+    /// Sets `IPV6_V6ONLY`, restricting an IPv6 socket to IPv6 traffic only.
+    /// This has no effect on IPv4 sockets.
+    pub fn only_v6<'a>(&'a mut self, on: bool) -> &'a mut SocketBuilder {
+        self.only_v6 = Some(on);
+        self
+    }
+
+    /// Sets `TCP_QUICKACK`, disabling delayed acknowledgements. This is only
+    /// supported on linux.
+    ///
+    /// Note that the kernel may clear this flag over the lifetime of the
+    /// connection, see also `TcpStream::set_quickack`.
+    pub fn quickack<'a>(&'a mut self, on: bool) -> &'a mut SocketBuilder {
+        self.quickack = Some(on);
+        self
+    }
+
+    /// Sets the length of the queue of pending connections of TCP listeners.
+    /// This defaults to 128.
+    pub fn backlog<'a>(&'a mut self, backlog: uint) -> &'a mut SocketBuilder {
+        self.backlog = Some(backlog);
+        self
+    }
+
+    fn options(&self) -> rtio::SocketOptions {
+        rtio::SocketOptions {
+            reuse_address: self.reuse_address,
+            reuse_port: self.reuse_port,
+            recv_buffer_size: self.recv_buffer_size,
+            send_buffer_size: self.send_buffer_size,
+            linger: self.linger,
+            only_v6: self.only_v6,
+            quickack: self.quickack,
+            backlog: self.backlog,
+        }
+    }
+}
+
 fn to_rtio(ip: IpAddr) -> rtio::IpAddr {
     match ip {
         Ipv4Addr(a, b, c, d) => rtio::Ipv4Addr(a, b, c, d),
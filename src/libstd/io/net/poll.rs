@@ -0,0 +1,256 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness-based multiplexing of sockets
+//!
+//! A `Poller` allows a single native thread to wait on many sockets at once.
+//! Sockets are registered with the poller along with a token of the caller's
+//! choosing and the set of readiness conditions they are interested in. Each
+//! call to `poll` then reports which of the registered sockets have become
+//! ready, identified by their tokens.
+//!
+//! Sockets which are driven by a poller should be placed in nonblocking mode
+//! with their `set_nonblocking` method. Reads and writes on such sockets never
+//! block; an operation which cannot make progress fails with
+//! `ResourceUnavailable` and should be retried once the poller reports the
+//! socket as ready again.
+//!
+//! Polling is currently only supported by the native runtime on Linux (where
+//! it is backed by epoll). Creating a `Poller` elsewhere will return an error
+//! of kind `IoUnavailable`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #![allow(experimental)]
+//! use std::io::{TcpListener, Listener, Acceptor};
+//! use std::io::net::poll::{Poller, Readable, Level};
+//!
+//! let mut acceptor = TcpListener::bind("127.0.0.1", 8482).listen().unwrap();
+//! acceptor.set_nonblocking(true).unwrap();
+//!
+//! let mut poller = Poller::new().unwrap();
+//! poller.register(&acceptor, 0, Readable, Level).unwrap();
+//!
+//! let mut events = Vec::new();
+//! loop {
+//!     poller.poll(&mut events, 64, None).unwrap();
+//!     for event in events.iter() {
+//!         if event.token == 0 {
+//!             let stream = acceptor.accept();
+//!             // register the new stream with its own token...
+//! #           drop(stream);
+//!         }
+//!     }
+//! }
+//! ```
+
+#![experimental]
+
+use collections::MutableSeq;
+use io::{IoResult, IoError, InvalidInput};
+use iter::Iterator;
+use kinds::Send;
+use libc;
+use boxed::Box;
+use ops::{BitOr, BitAnd, Sub, Not};
+use option::{Option, Some, None};
+use result::{Ok, Err};
+use rt::rtio::{IoFactory, LocalIo, RtioPoller};
+use rt::rtio;
+use vec::Vec;
+
+bitflags!(
+    #[doc = "A set of readiness conditions of a socket."]
+    #[deriving(Show)]
+    flags Interest: uint {
+        #[doc = "The socket can be read from (or accepted on) without \
+                 blocking."]
+        static Readable = rtio::POLL_READABLE,
+        #[doc = "The socket can be written to without blocking."]
+        static Writable = rtio::POLL_WRITABLE,
+        #[doc = "The remote end of the socket has hung up. This is only ever \
+                 reported, it need not be registered for."]
+        static Hangup = rtio::POLL_HANGUP,
+        #[doc = "An error is pending on the socket. This is only ever \
+                 reported, it need not be registered for."]
+        static Error = rtio::POLL_ERROR
+    }
+)
+
+/// How readiness is reported for a registered socket.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum Trigger {
+    /// The socket is reported by every call to `poll` for as long as it is
+    /// ready.
+    Level,
+    /// The socket is only reported when it transitions to being ready. The
+    /// caller must drain the socket until it reports `ResourceUnavailable`
+    /// before another event will be delivered.
+    Edge,
+}
+
+/// A readiness notification returned from `Poller::poll`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Event {
+    /// The token the socket was registered with.
+    pub token: uint,
+    /// The conditions the socket was found to be in.
+    pub readiness: Interest,
+}
+
+/// An I/O object which can be registered with a `Poller`.
+pub trait Evented {
+    /// Returns the raw descriptor of this object, or `None` if the current
+    /// runtime does not expose one.
+    #[doc(hidden)]
+    fn raw_fd(&self) -> Option<libc::c_int>;
+}
+
+/// A set of sockets which can be waited on for readiness.
+///
+/// A poller does not take ownership of the sockets registered with it. A socket
+/// must be deregistered before it is dropped, otherwise it may continue to be
+/// reported by the poller.
+pub struct Poller {
+    obj: Box<RtioPoller + Send>,
+    events: Vec<rtio::PollEvent>,
+}
+
+fn fd_of<E: Evented>(io: &E) -> IoResult<libc::c_int> {
+    match io.raw_fd() {
+        Some(fd) => Ok(fd),
+        None => Err(IoError {
+            kind: InvalidInput,
+            desc: "this I/O object cannot be registered with a poller",
+            detail: None,
+        })
+    }
+}
+
+impl Poller {
+    /// Creates a new poller with no registered sockets.
+    pub fn new() -> IoResult<Poller> {
+        LocalIo::maybe_raise(|io| {
+            io.poller_init().map(|p| Poller { obj: p, events: Vec::new() })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Starts watching `io` for the conditions in `interest`.
+    ///
+    /// Events for this socket will be reported with the provided `token`. It is
+    /// an error to register the same socket twice, use `reregister` instead.
+    pub fn register<E: Evented>(&mut self, io: &E, token: uint,
+                                interest: Interest,
+                                trigger: Trigger) -> IoResult<()> {
+        let fd = try!(fd_of(io));
+        self.obj.register(fd, token, interest.bits(), trigger == Edge)
+            .map_err(IoError::from_rtio_error)
+    }
+
+    /// Changes the token, interest or trigger of an already registered
+    /// socket.
+    pub fn reregister<E: Evented>(&mut self, io: &E, token: uint,
+                                  interest: Interest,
+                                  trigger: Trigger) -> IoResult<()> {
+        let fd = try!(fd_of(io));
+        self.obj.reregister(fd, token, interest.bits(), trigger == Edge)
+            .map_err(IoError::from_rtio_error)
+    }
+
+    /// Stops watching `io`.
+    pub fn deregister<E: Evented>(&mut self, io: &E) -> IoResult<()> {
+        let fd = try!(fd_of(io));
+        self.obj.deregister(fd).map_err(IoError::from_rtio_error)
+    }
+
+    /// Waits for registered sockets to become ready.
+    ///
+    /// The contents of `events` are replaced with at most `max` readiness
+    /// notifications. This blocks until at least one socket is ready or until
+    /// `timeout_ms` milliseconds have elapsed, in which case `events` will be
+    /// left empty. A timeout of `None` waits indefinitely, and a timeout of
+    /// `Some(0)` returns immediately.
+    ///
+    /// Returns the number of events received.
+    pub fn poll(&mut self, events: &mut Vec<Event>, max: uint,
+                timeout_ms: Option<u64>) -> IoResult<uint> {
+        events.truncate(0);
+        try!(self.obj.poll(&mut self.events, max, timeout_ms)
+                 .map_err(IoError::from_rtio_error));
+        for e in self.events.iter() {
+            events.push(Event {
+                token: e.token,
+                readiness: Interest::from_bits_truncate(e.readiness),
+            });
+        }
+        Ok(events.len())
+    }
+}
+
+#[cfg(test, target_os = "linux")]
+mod test {
+    use prelude::*;
+    use io::{TcpListener, TcpStream, Listener, Acceptor, ResourceUnavailable};
+    use io::test::next_test_ip4;
+    use native;
+    use super::*;
+
+    fn run_native(f: proc():Send) {
+        let (tx, rx) = channel();
+        native::task::spawn(proc() { tx.send(f()) });
+        rx.recv();
+    }
+
+    #[test]
+    fn accept_and_read() {
+        run_native(proc() {
+            let addr = next_test_ip4();
+            let ip = addr.ip.to_string();
+            let mut a = TcpListener::bind(ip.as_slice(), addr.port)
+                                    .listen().unwrap();
+            a.set_nonblocking(true).unwrap();
+            match a.accept() {
+                Err(ref e) if e.kind == ResourceUnavailable => {}
+                Err(e) => fail!("{}", e),
+                Ok(..) => fail!("accepted a connection that doesn't exist"),
+            }
+
+            let mut poller = Poller::new().unwrap();
+            poller.register(&a, 0, Readable, Level).unwrap();
+            let mut events = Vec::new();
+            assert_eq!(poller.poll(&mut events, 8, Some(0)).unwrap(), 0);
+
+            let mut c = TcpStream::connect(ip.as_slice(), addr.port).unwrap();
+            assert_eq!(poller.poll(&mut events, 8, Some(1000)).unwrap(), 1);
+            assert_eq!(events[0].token, 0);
+
+            let mut s = a.accept().unwrap();
+            s.set_nonblocking(true).unwrap();
+            poller.register(&s, 1, Readable, Edge).unwrap();
+            let mut buf = [0];
+            match s.read(buf) {
+                Err(ref e) if e.kind == ResourceUnavailable => {}
+                Err(e) => fail!("{}", e),
+                Ok(..) => fail!("read data that was never sent"),
+            }
+
+            c.write([7]).unwrap();
+            poller.poll(&mut events, 8, Some(1000)).unwrap();
+            assert!(events.iter().any(|e| e.token == 1 &&
+                                          e.readiness.contains(Readable)));
+            assert_eq!(s.read(buf).unwrap(), 1);
+            assert_eq!(buf[0], 7);
+
+            poller.deregister(&s).unwrap();
+            poller.deregister(&a).unwrap();
+        })
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Raw IP and link-layer packet sockets
+//!
+//! A `RawSocket` sends and receives the packets of a single IP protocol, such
+//! as ICMP, with the kernel taking care of little more than routing. This is
+//! what tools like `ping` and `traceroute` are built on. On IPv4, received
+//! packets include their IP header.
+//!
+//! A `PacketSocket` sits below IP entirely and sends and receives whole
+//! link-layer frames, which makes it suitable for packet capture. Packet
+//! sockets are only available on Linux.
+//!
+//! Both kinds of socket generally require elevated privileges to create (on
+//! Linux, the `CAP_NET_RAW` capability), and are currently only supported by
+//! the native runtime. Creating either elsewhere returns an error of kind
+//! `IoUnavailable`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #![allow(experimental)]
+//! use std::io::net::ip::Ipv4Addr;
+//! use std::io::net::raw::{RawSocket, IPPROTO_ICMP};
+//!
+//! let mut socket = RawSocket::bind(Ipv4Addr(0, 0, 0, 0), IPPROTO_ICMP).unwrap();
+//!
+//! let mut buf = [0, ..1500];
+//! loop {
+//!     let (amt, src) = socket.recv_from(buf).unwrap();
+//!     println!("{} bytes of ICMP from {}", amt, src);
+//! }
+//! ```
+
+#![experimental]
+
+use clone::Clone;
+use io::net::ip::IpAddr;
+use io::net::poll::Evented;
+use io::{IoResult, IoError};
+use iter::Iterator;
+use kinds::Send;
+use libc;
+use boxed::Box;
+use option::Option;
+use result::{Ok, Err};
+use rt::rtio::{RtioRawSocket, RtioPacketSocket, IoFactory, LocalIo};
+use rt::rtio;
+use slice::ImmutableSlice;
+use vec::Vec;
+
+/// The IP protocol number of ICMP.
+pub static IPPROTO_ICMP: u8 = 1;
+/// The IP protocol number of ICMPv6.
+pub static IPPROTO_ICMPV6: u8 = 58;
+/// The IP protocol number reserved for raw packets. A raw socket created with
+/// this protocol can only send, and always includes its own IP header.
+pub static IPPROTO_RAW: u8 = 255;
+
+/// The link-layer protocol matching every frame, for use with `PacketSocket`.
+pub static ETH_P_ALL: u16 = 0x0003;
+/// The link-layer protocol of IPv4.
+pub static ETH_P_IP: u16 = 0x0800;
+/// The link-layer protocol of ARP.
+pub static ETH_P_ARP: u16 = 0x0806;
+/// The link-layer protocol of IPv6.
+pub static ETH_P_IPV6: u16 = 0x86DD;
+
+/// A single instruction of a classic BPF program, as understood by the
+/// kernel's socket filters.
+///
+/// A filter is a sequence of these which is run over every packet a socket
+/// would receive. The packet is dropped if the program returns 0, and is
+/// otherwise truncated to the length returned.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct FilterInstruction {
+    /// The opcode of the instruction.
+    pub code: u16,
+    /// For conditional jumps, the offset to jump by if the test is true.
+    pub jt: u8,
+    /// For conditional jumps, the offset to jump by if the test is false.
+    pub jf: u8,
+    /// The generic operand of the instruction.
+    pub k: u32,
+}
+
+fn filter_to_rtio(filter: &[FilterInstruction]) -> Vec<rtio::FilterInstruction> {
+    filter.iter().map(|i| {
+        rtio::FilterInstruction { code: i.code, jt: i.jt, jf: i.jf, k: i.k }
+    }).collect()
+}
+
+/// A socket for sending and receiving the packets of a single IP protocol.
+pub struct RawSocket {
+    obj: Box<RtioRawSocket + Send>,
+}
+
+impl RawSocket {
+    /// Creates a raw socket for the IP protocol numbered `protocol`, bound to
+    /// the local address `addr`. Whether the socket is IPv4 or IPv6 is
+    /// determined by `addr`; use the unspecified address to receive packets
+    /// sent to any address.
+    pub fn bind(addr: IpAddr, protocol: u8) -> IoResult<RawSocket> {
+        LocalIo::maybe_raise(|io| {
+            io.raw_bind(super::to_rtio(addr), protocol).map(|s| {
+                RawSocket { obj: s }
+            })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Receives a single packet from the socket. On success, returns the
+    /// number of bytes read and the address the packet came from.
+    ///
+    /// Packets which don't fit in `buf` are truncated.
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, IpAddr)> {
+        match self.obj.recv_from(buf) {
+            Ok((amt, ip)) => Ok((amt, super::from_rtio(ip))),
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        }
+    }
+
+    /// Sends a single packet to the given address.
+    pub fn send_to(&mut self, buf: &[u8], dst: IpAddr) -> IoResult<()> {
+        self.obj.send_to(buf, super::to_rtio(dst))
+            .map_err(IoError::from_rtio_error)
+    }
+
+    /// Restricts the socket to the network interface with the given index,
+    /// so that it only sends and receives packets through that interface.
+    ///
+    /// This is supported on Linux and OSX only.
+    pub fn bind_to_interface(&mut self, ifindex: uint) -> IoResult<()> {
+        self.obj.bind_to_interface(ifindex).map_err(IoError::from_rtio_error)
+    }
+
+    /// Attaches a BPF program to the socket, replacing any attached before,
+    /// so that only the packets it accepts are received.
+    ///
+    /// This is supported on Linux only.
+    pub fn attach_filter(&mut self, filter: &[FilterInstruction]) -> IoResult<()> {
+        let filter = filter_to_rtio(filter);
+        self.obj.attach_filter(filter.as_slice()).map_err(IoError::from_rtio_error)
+    }
+
+    /// Sets whether packets sent on this IPv4 socket include their own IP
+    /// header (`IP_HDRINCL`). Otherwise the kernel builds the header.
+    pub fn set_header_included(&mut self, on: bool) -> IoResult<()> {
+        self.obj.header_included(on).map_err(IoError::from_rtio_error)
+    }
+
+    /// Sets the TTL of packets sent on this IPv4 socket.
+    pub fn set_ttl(&mut self, ttl: int) -> IoResult<()> {
+        self.obj.time_to_live(ttl).map_err(IoError::from_rtio_error)
+    }
+
+    /// Sets the read/write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_timeout(timeout_ms)
+    }
+
+    /// Sets the read timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+
+    /// Places this socket into or out of nonblocking mode.
+    ///
+    /// For more information, see `TcpStream::set_nonblocking`
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.obj.set_nonblocking(nonblocking).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Evented for RawSocket {
+    fn raw_fd(&self) -> Option<libc::c_int> { self.obj.raw_fd() }
+}
+
+impl Clone for RawSocket {
+    /// Creates a new handle to this raw socket. As with `UdpSocket`, each
+    /// packet is only received by one of the handles.
+    fn clone(&self) -> RawSocket {
+        RawSocket { obj: self.obj.clone() }
+    }
+}
+
+/// The address of a frame sent or received on a `PacketSocket`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct LinkAddr {
+    /// The index of the interface the frame was received on or is to be sent
+    /// through.
+    pub ifindex: uint,
+    /// The link-layer protocol of the frame, such as `ETH_P_IP`.
+    pub protocol: u16,
+    /// The hardware address of the other end, at most 8 bytes long.
+    pub address: Vec<u8>,
+}
+
+/// A socket for sending and receiving link-layer frames.
+///
+/// Frames are received and sent whole, including their link-layer header.
+pub struct PacketSocket {
+    obj: Box<RtioPacketSocket + Send>,
+}
+
+impl PacketSocket {
+    /// Creates a packet socket receiving frames of the given link-layer
+    /// `protocol`, such as `ETH_P_ALL`, from the interface with index
+    /// `ifindex`. An index of 0 receives frames from every interface.
+    pub fn bind(ifindex: uint, protocol: u16) -> IoResult<PacketSocket> {
+        LocalIo::maybe_raise(|io| {
+            io.packet_bind(ifindex, protocol).map(|s| {
+                PacketSocket { obj: s }
+            })
+        }).map_err(IoError::from_rtio_error)
+    }
+
+    /// Receives a single frame from the socket. On success, returns the
+    /// number of bytes read and the address of the frame.
+    ///
+    /// Frames which don't fit in `buf` are truncated.
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> IoResult<(uint, LinkAddr)> {
+        match self.obj.recv_from(buf) {
+            Ok((amt, addr)) => {
+                Ok((amt, LinkAddr {
+                    ifindex: addr.ifindex,
+                    protocol: addr.protocol,
+                    address: addr.address,
+                }))
+            }
+            Err(e) => Err(IoError::from_rtio_error(e)),
+        }
+    }
+
+    /// Sends a single frame out of the interface given by `dst`. The frame
+    /// must start with its link-layer header.
+    pub fn send_to(&mut self, buf: &[u8], dst: &LinkAddr) -> IoResult<()> {
+        let dst = rtio::LinkAddr {
+            ifindex: dst.ifindex,
+            protocol: dst.protocol,
+            address: dst.address.clone(),
+        };
+        self.obj.send_to(buf, &dst).map_err(IoError::from_rtio_error)
+    }
+
+    /// Attaches a BPF program to the socket, replacing any attached before,
+    /// so that only the frames it accepts are received.
+    pub fn attach_filter(&mut self, filter: &[FilterInstruction]) -> IoResult<()> {
+        let filter = filter_to_rtio(filter);
+        self.obj.attach_filter(filter.as_slice()).map_err(IoError::from_rtio_error)
+    }
+
+    /// Puts the interface this socket is bound to into (or takes it out of)
+    /// promiscuous mode. The interface leaves promiscuous mode when the
+    /// socket is closed.
+    pub fn set_promiscuous(&mut self, on: bool) -> IoResult<()> {
+        self.obj.promiscuous(on).map_err(IoError::from_rtio_error)
+    }
+
+    /// Sets the read/write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_timeout(timeout_ms)
+    }
+
+    /// Sets the read timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the write timeout for this socket.
+    ///
+    /// For more information, see `TcpStream::set_timeout`
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+
+    /// Places this socket into or out of nonblocking mode.
+    ///
+    /// For more information, see `TcpStream::set_nonblocking`
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.obj.set_nonblocking(nonblocking).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Evented for PacketSocket {
+    fn raw_fd(&self) -> Option<libc::c_int> { self.obj.raw_fd() }
+}
+
+impl Clone for PacketSocket {
+    /// Creates a new handle to this packet socket. Each frame is only
+    /// received by one of the handles.
+    fn clone(&self) -> PacketSocket {
+        PacketSocket { obj: self.obj.clone() }
+    }
+}
+
+/// Looks up the index of the network interface with the given name, such as
+/// `eth0`, for use with `RawSocket::bind_to_interface` or `PacketSocket`.
+#[cfg(unix)]
+pub fn interface_index(name: &str) -> IoResult<uint> {
+    use c_str::ToCStr;
+
+    match name.with_c_str(|name| unsafe { libc::if_nametoindex(name) }) {
+        0 => Err(IoError::last_error()),
+        n => Ok(n as uint),
+    }
+}
+
+#[cfg(test, target_os = "linux")]
+mod test {
+    use prelude::*;
+    use io::{IoUnavailable, PermissionDenied, TimedOut};
+    use io::net::ip::Ipv4Addr;
+    use io::net::udp::UdpSocket;
+    use io::test::next_test_ip4;
+    use libc;
+    use native;
+    use super::*;
+
+    fn is_root() -> bool { unsafe { libc::getuid() == 0 } }
+
+    // Raw and packet sockets only exist under the native runtime
+    fn run_native(f: proc():Send) {
+        let (tx, rx) = channel();
+        native::task::spawn(proc() { tx.send(f()) });
+        rx.recv();
+    }
+
+    // Builds an ICMP echo request with the given identifier
+    fn echo_request(id: u16) -> Vec<u8> {
+        let mut packet = vec![8u8, 0, 0, 0, (id >> 8) as u8, id as u8, 0, 1];
+        let mut sum = 0u32;
+        for pair in packet.as_slice().chunks(2) {
+            sum += (pair[0] as u32 << 8) | pair[1] as u32;
+        }
+        while sum > 0xffff { sum = (sum & 0xffff) + (sum >> 16); }
+        let sum = !(sum as u16);
+        *packet.get_mut(2) = (sum >> 8) as u8;
+        *packet.get_mut(3) = sum as u8;
+        packet
+    }
+
+    iotest!(fn loopback_index() {
+        assert!(interface_index("lo").unwrap() > 0);
+        assert!(interface_index("no-such-interface-exists").is_err());
+    })
+
+    #[test]
+    fn raw_icmp_echo() { run_native(proc() {
+        if !is_root() { return }
+        let localhost = Ipv4Addr(127, 0, 0, 1);
+        let mut socket = RawSocket::bind(localhost, IPPROTO_ICMP).unwrap();
+        socket.set_read_timeout(Some(5000));
+        socket.send_to(echo_request(0x1234).as_slice(), localhost).unwrap();
+
+        // We see both our own request and the reply; after the IPv4 header
+        // (20 bytes on loopback) comes the ICMP type and then the identifier.
+        let mut buf = [0, ..1500];
+        let mut seen = 0u;
+        while seen < 2 {
+            let (amt, src) = socket.recv_from(buf).unwrap();
+            assert_eq!(src, localhost);
+            assert!(amt >= 28);
+            if buf[24] == 0x12 && buf[25] == 0x34 {
+                assert!(buf[20] == 8 || buf[20] == 0);
+                seen += 1;
+            }
+        }
+    }) }
+
+    #[test]
+    fn packet_filter() { run_native(proc() {
+        if !is_root() { return }
+        let lo = interface_index("lo").unwrap();
+        let addr = next_test_ip4();
+        let mut udp = UdpSocket::bind(addr).unwrap();
+        let mut buf = [0, ..1500];
+
+        // "ret #-1", accepting every frame in full
+        let mut socket = PacketSocket::bind(lo, ETH_P_ALL).unwrap();
+        socket.attach_filter([FilterInstruction {
+            code: 0x06, jt: 0, jf: 0, k: 0xffffffff,
+        }]).unwrap();
+        socket.set_read_timeout(Some(5000));
+        udp.send_to([1, 2, 3], addr).unwrap();
+        let (amt, from) = socket.recv_from(buf).unwrap();
+        assert!(amt > 3);
+        assert_eq!(from.ifindex, lo);
+        assert_eq!(from.protocol, ETH_P_IP);
+
+        // "ret #0", dropping every frame
+        let mut socket = PacketSocket::bind(lo, ETH_P_ALL).unwrap();
+        socket.attach_filter([FilterInstruction {
+            code: 0x06, jt: 0, jf: 0, k: 0,
+        }]).unwrap();
+        socket.set_read_timeout(Some(100));
+        udp.send_to([1, 2, 3], addr).unwrap();
+        match socket.recv_from(buf) {
+            Err(ref e) if e.kind == TimedOut => {}
+            Err(e) => fail!("{}", e),
+            Ok(..) => fail!("received a frame the filter should drop"),
+        }
+    }) }
+
+    #[test]
+    fn unprivileged_fails() { run_native(proc() {
+        if is_root() { return }
+        let addr = Ipv4Addr(127, 0, 0, 1);
+        match RawSocket::bind(addr, IPPROTO_ICMP) {
+            Err(ref e) if e.kind == PermissionDenied => {}
+            Err(e) => fail!("{}", e),
+            Ok(..) => fail!("created a raw socket without privileges"),
+        }
+        match PacketSocket::bind(0, ETH_P_ALL) {
+            Err(ref e) if e.kind == PermissionDenied => {}
+            Err(e) => fail!("{}", e),
+            Ok(..) => fail!("created a packet socket without privileges"),
+        }
+    }) }
+
+    #[test]
+    fn green_unavailable() {
+        let addr = Ipv4Addr(127, 0, 0, 1);
+        match RawSocket::bind(addr, IPPROTO_ICMP) {
+            Err(ref e) if e.kind == IoUnavailable => {}
+            Err(e) => fail!("{}", e),
+            Ok(..) => fail!("created a raw socket under the green runtime"),
+        }
+        match PacketSocket::bind(0, ETH_P_ALL) {
+            Err(ref e) if e.kind == IoUnavailable => {}
+            Err(e) => fail!("{}", e),
+            Ok(..) => fail!("created a packet socket under the green runtime"),
+        }
+    }
+}
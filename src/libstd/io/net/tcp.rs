@@ -25,11 +25,14 @@ use slice::ImmutableSlice;
 use result::{Ok,Err};
 use io::net::addrinfo::get_host_addresses;
 use io::net::ip::SocketAddr;
+use io::net::poll::Evented;
+use io::net::SocketBuilder;
 use io::{IoError, ConnectionFailed, InvalidInput};
 use io::{Reader, Writer, Listener, Acceptor};
 use io::{standard_error, TimedOut};
 use from_str::FromStr;
 use kinds::Send;
+use libc;
 use option::{None, Some, Option};
 use boxed::Box;
 use rt::rtio::{IoFactory, LocalIo, RtioSocket, RtioTcpListener};
@@ -67,6 +70,11 @@ impl TcpStream {
     /// `host` can be a hostname or IP address string. If no error is
     /// encountered, then `Ok(stream)` is returned.
     pub fn connect(host: &str, port: u16) -> IoResult<TcpStream> {
+        TcpStream::connect_opts(host, port, &SocketBuilder::new().options())
+    }
+
+    fn connect_opts(host: &str, port: u16,
+                    opts: &rtio::SocketOptions) -> IoResult<TcpStream> {
         let addresses = match FromStr::from_str(host) {
             Some(addr) => vec!(addr),
             None => try!(get_host_addresses(host))
@@ -79,7 +87,7 @@ impl TcpStream {
         for addr in addresses.iter() {
             let addr = rtio::SocketAddr{ ip: super::to_rtio(*addr), port: port };
             let result = LocalIo::maybe_raise(|io| {
-                io.tcp_connect(addr, None).map(TcpStream::new)
+                io.tcp_connect(addr, opts, None).map(TcpStream::new)
             });
             match result {
                 Ok(stream) => {
@@ -108,6 +116,13 @@ impl TcpStream {
     #[experimental = "the timeout argument may eventually change types"]
     pub fn connect_timeout(addr: SocketAddr,
                            timeout: Duration) -> IoResult<TcpStream> {
+        TcpStream::connect_timeout_opts(addr, timeout,
+                                        &SocketBuilder::new().options())
+    }
+
+    fn connect_timeout_opts(addr: SocketAddr, timeout: Duration,
+                            opts: &rtio::SocketOptions)
+                            -> IoResult<TcpStream> {
         if timeout <= Duration::milliseconds(0) {
             return Err(standard_error(TimedOut));
         }
@@ -115,7 +130,8 @@ impl TcpStream {
         let SocketAddr { ip, port } = addr;
         let addr = rtio::SocketAddr { ip: super::to_rtio(ip), port: port };
         LocalIo::maybe_raise(|io| {
-            io.tcp_connect(addr, Some(timeout.num_milliseconds() as u64)).map(TcpStream::new)
+            io.tcp_connect(addr, opts, Some(timeout.num_milliseconds() as u64))
+              .map(TcpStream::new)
         }).map_err(IoError::from_rtio_error)
     }
 
@@ -149,6 +165,16 @@ impl TcpStream {
         }.map_err(IoError::from_rtio_error)
     }
 
+    /// Sets the quickack flag on this connection, disabling (or re-enabling)
+    /// delayed acknowledgements. This is only supported on linux.
+    ///
+    /// The kernel may clear this flag by itself as the connection is used, so
+    /// it may need to be set repeatedly, for example after each read.
+    #[experimental]
+    pub fn set_quickack(&mut self, quickack: bool) -> IoResult<()> {
+        self.obj.quickack(quickack).map_err(IoError::from_rtio_error)
+    }
+
     /// Sets the keepalive timeout to the timeout specified.
     ///
     /// If the value specified is `None`, then the keepalive flag is cleared on
@@ -272,6 +298,26 @@ impl TcpStream {
     pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
         self.obj.set_write_timeout(timeout_ms)
     }
+
+    /// Places this stream into or out of nonblocking mode.
+    ///
+    /// While in nonblocking mode, reads and writes on this stream will never
+    /// block. An operation which cannot make any progress fails with an error
+    /// of kind `ResourceUnavailable`, and a write which could only be
+    /// partially completed fails with `ShortWrite`. Timeouts have no effect on
+    /// a nonblocking stream.
+    ///
+    /// This is intended to be used along with a `Poller` (see the
+    /// `std::io::net::poll` module) to learn when the stream is ready. Note
+    /// that this mode is shared by all cloned handles of this stream.
+    #[experimental]
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.obj.set_nonblocking(nonblocking).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Evented for TcpStream {
+    fn raw_fd(&self) -> Option<libc::c_int> { self.obj.raw_fd() }
 }
 
 impl Clone for TcpStream {
@@ -292,12 +338,20 @@ impl Reader for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         self.obj.read(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> IoResult<uint> {
+        self.obj.readv(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 impl Writer for TcpStream {
     fn write(&mut self, buf: &[u8]) -> IoResult<()> {
         self.obj.write(buf).map_err(IoError::from_rtio_error)
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> IoResult<()> {
+        self.obj.writev(bufs).map_err(IoError::from_rtio_error)
+    }
 }
 
 /// A structure representing a socket server. This listener is used to create a
@@ -349,6 +403,11 @@ impl TcpListener {
     /// to this listener. The port allocated can be queried via the
     /// `socket_name` function.
     pub fn bind(addr: &str, port: u16) -> IoResult<TcpListener> {
+        TcpListener::bind_opts(addr, port, &SocketBuilder::new().options())
+    }
+
+    fn bind_opts(addr: &str, port: u16,
+                 opts: &rtio::SocketOptions) -> IoResult<TcpListener> {
         match FromStr::from_str(addr) {
             Some(ip) => {
                 let addr = rtio::SocketAddr{
@@ -356,7 +415,7 @@ impl TcpListener {
                     port: port,
                 };
                 LocalIo::maybe_raise(|io| {
-                    io.tcp_bind(addr).map(|l| TcpListener { obj: l })
+                    io.tcp_bind(addr, opts).map(|l| TcpListener { obj: l })
                 }).map_err(IoError::from_rtio_error)
             }
             None => {
@@ -489,6 +548,22 @@ impl TcpAcceptor {
     pub fn close_accept(&mut self) -> IoResult<()> {
         self.obj.close_accept().map_err(IoError::from_rtio_error)
     }
+
+    /// Places this acceptor into or out of nonblocking mode.
+    ///
+    /// A nonblocking acceptor returns an error of kind `ResourceUnavailable`
+    /// from `accept` if there is no pending connection. Streams returned from
+    /// `accept` start out in blocking mode regardless of this setting.
+    ///
+    /// Like `close_accept`, this affects all cloned handles of this acceptor.
+    #[experimental]
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.obj.set_nonblocking(nonblocking).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Evented for TcpAcceptor {
+    fn raw_fd(&self) -> Option<libc::c_int> { self.obj.raw_fd() }
 }
 
 impl Acceptor<TcpStream> for TcpAcceptor {
@@ -519,6 +594,29 @@ impl Clone for TcpAcceptor {
     }
 }
 
+impl SocketBuilder {
+    /// Opens a TCP connection to a remote host with the options of this
+    /// builder. See `TcpStream::connect` for details.
+    pub fn tcp_connect(&self, host: &str, port: u16) -> IoResult<TcpStream> {
+        TcpStream::connect_opts(host, port, &self.options())
+    }
+
+    /// Opens a TCP connection to a remote socket address with the options of
+    /// this builder, timing out after the specified duration. See
+    /// `TcpStream::connect_timeout` for details.
+    #[experimental = "the timeout argument may eventually change types"]
+    pub fn tcp_connect_timeout(&self, addr: SocketAddr,
+                               timeout: Duration) -> IoResult<TcpStream> {
+        TcpStream::connect_timeout_opts(addr, timeout, &self.options())
+    }
+
+    /// Creates a TCP listener bound to the specified IP and port with the
+    /// options of this builder. See `TcpListener::bind` for details.
+    pub fn tcp_bind(&self, addr: &str, port: u16) -> IoResult<TcpListener> {
+        TcpListener::bind_opts(addr, port, &self.options())
+    }
+}
+
 #[cfg(test)]
 #[allow(experimental)]
 mod test {
@@ -535,6 +633,117 @@ mod test {
         }
     } #[ignore(cfg(windows))] #[ignore(cfg(target_os = "android"))])
 
+    iotest!(fn builder_backlog() {
+        let addr = next_test_ip4();
+        let ip_str = addr.ip.to_string();
+        let port = addr.port;
+        let listener = io::net::SocketBuilder::new()
+                                        .backlog(1)
+                                        .tcp_bind(ip_str.as_slice(), port);
+        let mut acceptor = listener.listen();
+
+        spawn(proc() {
+            let mut stream = io::net::SocketBuilder::new()
+                                        .tcp_connect("localhost", port);
+            stream.write([144]).unwrap();
+        });
+
+        let mut stream = acceptor.accept();
+        let mut buf = [0];
+        stream.read(buf).unwrap();
+        assert!(buf[0] == 144);
+    })
+
+    #[test]
+    fn builder_options() {
+        use io::net::SocketBuilder;
+        use io::net::poll::Evented;
+        use io::test::{next_test_ip4, next_test_ip6};
+        use libc;
+        use native;
+        use native::io::net::sock_t;
+        use time::Duration;
+
+        // Reads an option back through the raw descriptor
+        fn sockopt<T: Copy, S: Evented>(s: &S, level: libc::c_int,
+                                        name: libc::c_int) -> T {
+            let fd = s.raw_fd().unwrap() as sock_t;
+            native::io::net::getsockopt(fd, level, name).unwrap()
+        }
+        fn flag<S: Evented>(s: &S, level: libc::c_int, name: libc::c_int) -> bool {
+            sockopt::<libc::c_int, S>(s, level, name) != 0
+        }
+        fn check_common<S: Evented>(s: &S) {
+            assert!(flag(s, libc::SOL_SOCKET, libc::SO_REUSEADDR));
+            // The kernel may round the sizes up, linux doubles them
+            let rcvbuf: libc::c_int = sockopt(s, libc::SOL_SOCKET, libc::SO_RCVBUF);
+            assert!(rcvbuf >= 16 * 1024);
+            let sndbuf: libc::c_int = sockopt(s, libc::SOL_SOCKET, libc::SO_SNDBUF);
+            assert!(sndbuf >= 16 * 1024);
+            let linger: libc::linger = sockopt(s, libc::SOL_SOCKET, libc::SO_LINGER);
+            assert!(linger.l_onoff != 0);
+            assert_eq!(linger.l_linger as uint, 1);
+            check_reuse_port(s);
+        }
+
+        #[cfg(unix)]
+        fn set_reuse_port(builder: &mut SocketBuilder) { builder.reuse_port(true); }
+        #[cfg(unix)]
+        fn check_reuse_port<S: Evented>(s: &S) {
+            assert!(flag(s, libc::SOL_SOCKET, libc::SO_REUSEPORT));
+        }
+        #[cfg(windows)]
+        fn set_reuse_port(_builder: &mut SocketBuilder) {}
+        #[cfg(windows)]
+        fn check_reuse_port<S: Evented>(_s: &S) {}
+
+        #[cfg(target_os = "linux")] #[cfg(target_os = "android")]
+        fn set_quickack(builder: &mut SocketBuilder) { builder.quickack(true); }
+        #[cfg(target_os = "linux")] #[cfg(target_os = "android")]
+        fn check_quickack(s: &TcpStream) {
+            assert!(flag(s, libc::IPPROTO_TCP, libc::TCP_QUICKACK));
+        }
+        #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+        fn set_quickack(_builder: &mut SocketBuilder) {}
+        #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+        fn check_quickack(_s: &TcpStream) {}
+
+        let (tx, rx) = channel();
+        native::task::spawn(proc() {
+            let addr = next_test_ip4();
+            let ip_str = addr.ip.to_string();
+            let mut builder = SocketBuilder::new();
+            builder.reuse_address(true)
+                   .recv_buffer_size(16 * 1024)
+                   .send_buffer_size(16 * 1024)
+                   .linger(Some(Duration::seconds(1)));
+            set_reuse_port(&mut builder);
+            set_quickack(&mut builder);
+            let mut acceptor = builder.tcp_bind(ip_str.as_slice(), addr.port)
+                                      .listen().unwrap();
+            check_common(&acceptor);
+            let stream = builder.tcp_connect(ip_str.as_slice(), addr.port).unwrap();
+            check_common(&stream);
+            check_quickack(&stream);
+            drop(acceptor.accept().unwrap());
+            drop(stream);
+            drop(acceptor);
+
+            // With SO_REUSEADDR the address may be rebound straight away,
+            // despite the connection above lingering in TIME_WAIT.
+            builder.tcp_bind(ip_str.as_slice(), addr.port).unwrap();
+
+            let addr = next_test_ip6();
+            let mut builder = SocketBuilder::new();
+            builder.only_v6(true);
+            let acceptor = builder.tcp_bind(addr.ip.to_string().as_slice(), addr.port)
+                                  .listen().unwrap();
+            assert!(flag(&acceptor, libc::IPPROTO_IPV6, libc::IPV6_V6ONLY));
+            tx.send(());
+        });
+        rx.recv();
+    }
+
     iotest!(fn connect_error() {
         match TcpStream::connect("0.0.0.0", 1) {
             Ok(..) => fail!(),
@@ -628,6 +837,49 @@ mod test {
         assert!(buf[0] == 99);
     })
 
+    iotest!(fn vectored() {
+        let addr = next_test_ip4();
+        let ip_str = addr.ip.to_string();
+        let port = addr.port;
+        let mut acceptor = TcpListener::bind(ip_str.as_slice(), port).listen();
+
+        spawn(proc() {
+            let mut stream = TcpStream::connect(ip_str.as_slice(), port).unwrap();
+            stream.write_vectored(["hello".as_bytes(), "".as_bytes(),
+                                   " world".as_bytes()]).unwrap();
+            // Far more than the socket buffers hold, so each writev() only
+            // gets part of it out
+            let chunk = Vec::from_elem(1024, 7u8);
+            let bufs = Vec::from_elem(4096, chunk.as_slice());
+            stream.write_vectored(bufs.as_slice()).unwrap();
+        });
+
+        let mut stream = acceptor.accept().unwrap();
+        let mut data = Vec::new();
+        loop {
+            let mut a = [0u8, ..1000];
+            let mut b = [0u8, ..3000];
+            let n = {
+                let mut bufs = [a.as_mut_slice(), b.as_mut_slice()];
+                match stream.read_vectored(bufs) {
+                    Ok(n) => n,
+                    Err(ref e) if e.kind == EndOfFile => break,
+                    Err(e) => fail!("{}", e),
+                }
+            };
+            assert!(n <= 4000);
+            if n > 1000 {
+                data.push_all(a.as_slice());
+                data.push_all(b.slice_to(n - 1000));
+            } else {
+                data.push_all(a.slice_to(n));
+            }
+        }
+        assert_eq!(data.len(), 11 + 4096 * 1024);
+        assert_eq!(data.slice_to(11), "hello world".as_bytes());
+        assert!(data.slice_from(11).iter().all(|&b| b == 7));
+    })
+
     iotest!(fn smoke_test_ip6() {
         let addr = next_test_ip6();
         let ip_str = addr.ip.to_string();
@@ -17,8 +17,11 @@
 
 use clone::Clone;
 use io::net::ip::{SocketAddr, IpAddr};
+use io::net::poll::Evented;
+use io::net::SocketBuilder;
 use io::{Reader, Writer, IoResult, IoError};
 use kinds::Send;
+use libc;
 use boxed::Box;
 use option::Option;
 use result::{Ok, Err};
@@ -63,10 +66,15 @@ pub struct UdpSocket {
 impl UdpSocket {
     /// Creates a UDP socket from the given socket address.
     pub fn bind(addr: SocketAddr) -> IoResult<UdpSocket> {
+        UdpSocket::bind_opts(addr, &SocketBuilder::new().options())
+    }
+
+    fn bind_opts(addr: SocketAddr,
+                 opts: &rtio::SocketOptions) -> IoResult<UdpSocket> {
         let SocketAddr { ip, port } = addr;
         LocalIo::maybe_raise(|io| {
             let addr = rtio::SocketAddr { ip: super::to_rtio(ip), port: port };
-            io.udp_bind(addr).map(|s| UdpSocket { obj: s })
+            io.udp_bind(addr, opts).map(|s| UdpSocket { obj: s })
         }).map_err(IoError::from_rtio_error)
     }
 
@@ -202,6 +210,18 @@ impl UdpSocket {
     pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) {
         self.obj.set_write_timeout(timeout_ms)
     }
+
+    /// Places this socket into or out of nonblocking mode.
+    ///
+    /// For more information, see `TcpStream::set_nonblocking`
+    #[experimental]
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> IoResult<()> {
+        self.obj.set_nonblocking(nonblocking).map_err(IoError::from_rtio_error)
+    }
+}
+
+impl Evented for UdpSocket {
+    fn raw_fd(&self) -> Option<libc::c_int> { self.obj.raw_fd() }
 }
 
 impl Clone for UdpSocket {
@@ -219,6 +239,14 @@ impl Clone for UdpSocket {
     }
 }
 
+impl SocketBuilder {
+    /// Creates a UDP socket bound to the given socket address with the
+    /// options of this builder. See `UdpSocket::bind` for details.
+    pub fn udp_bind(&self, addr: SocketAddr) -> IoResult<UdpSocket> {
+        UdpSocket::bind_opts(addr, &self.options())
+    }
+}
+
 /// A type that allows convenient usage of a UDP stream connected to one
 /// address via the `Reader` and `Writer` traits.
 pub struct UdpStream {
@@ -96,6 +96,18 @@ impl PipeStream {
             }
         }
     }
+
+    /// Sets the read timeout for this pipe.
+    ///
+    /// After the timeout has passed, pending and future reads will fail with
+    /// a `TimedOut` error. For more information, see `TcpStream::set_timeout`
+    ///
+    /// With the native runtime on windows, reads from the pipes of a child
+    /// process don't time out.
+    #[experimental = "the timeout argument may change in type and value"]
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.obj.set_read_timeout(timeout_ms)
+    }
 }
 
 impl Clone for PipeStream {
@@ -14,9 +14,11 @@
 
 use prelude::*;
 
+use cmp;
 use fmt;
 use os;
 use io::{IoResult, IoError};
+use io::timer::Timer;
 use io;
 use libc;
 use mem;
@@ -24,6 +26,8 @@ use rt::rtio::{RtioProcess, ProcessConfig, IoFactory, LocalIo};
 use rt::rtio;
 use c_str::CString;
 use collections::HashMap;
+use time::Duration;
+use uint;
 
 /// Signal a process to exit, without forcibly killing it. Corresponds to
 /// SIGTERM on unix platforms.
@@ -112,6 +116,9 @@ pub struct Command {
     uid: Option<uint>,
     gid: Option<uint>,
     detach: bool,
+    pgid: Option<uint>,
+    rlimits: Vec<(Resource, Option<u64>, Option<u64>)>,
+    output_limit: Option<uint>,
 }
 
 // FIXME (#12938): Until DST lands, we cannot decompose &str into & and str, so
@@ -146,6 +153,9 @@ impl Command {
             uid: None,
             gid: None,
             detach: false,
+            pgid: None,
+            rlimits: Vec::new(),
+            output_limit: None,
         }
     }
 
@@ -258,6 +268,47 @@ impl Command {
         self
     }
 
+    /// Makes the child process the leader of a new session, and so also of a
+    /// new process group, by calling `setsid` in the child. This is the same
+    /// as `detached`, and can't be combined with `setpgid`.
+    pub fn setsid<'a>(&'a mut self) -> &'a mut Command {
+        self.detached()
+    }
+
+    /// Moves the child process into the process group `pgid` by calling
+    /// `setpgid` in the child before it execs. A `pgid` of 0 makes the child
+    /// the leader of a new process group, whose id is the child's own.
+    ///
+    /// The whole group can then be signalled with `Process::signal_group`,
+    /// which also reaches any grandchildren that haven't moved elsewhere.
+    /// Setting this on windows, or in combination with `setsid`, will cause
+    /// the spawn to fail.
+    pub fn setpgid<'a>(&'a mut self, pgid: uint) -> &'a mut Command {
+        self.pgid = Some(pgid);
+        self
+    }
+
+    /// Limits the child process's use of `resource` by calling `setrlimit` in
+    /// the child before it execs. A limit of `None` means no limit.
+    ///
+    /// Setting a resource limit on windows will cause the spawn to fail, as
+    /// will a failing `setrlimit` call on unix (for example when raising a
+    /// hard limit without the privileges to do so).
+    pub fn setrlimit<'a>(&'a mut self, resource: Resource, soft: Option<u64>,
+                         hard: Option<u64>) -> &'a mut Command {
+        self.rlimits.push((resource, soft, hard));
+        self
+    }
+
+    /// Limits the amount of output collected by `output` and
+    /// `output_with_timeout` to `limit` bytes for each of stdout and stderr.
+    /// Anything the child writes beyond the limit is still read, so that the
+    /// child doesn't block, but it is discarded.
+    pub fn output_limit<'a>(&'a mut self, limit: uint) -> &'a mut Command {
+        self.output_limit = Some(limit);
+        self
+    }
+
     /// Executes the command as a child process, which is returned.
     pub fn spawn(&self) -> IoResult<Process> {
         fn to_rtio(p: StdioContainer) -> rtio::StdioContainer {
@@ -267,8 +318,28 @@ impl Command {
                 CreatePipe(a, b) => rtio::CreatePipe(a, b),
             }
         }
+        fn to_rtio_resource(r: Resource) -> rtio::Resource {
+            match r {
+                CpuTime => rtio::CpuTime,
+                FileSize => rtio::FileSize,
+                DataSize => rtio::DataSize,
+                StackSize => rtio::StackSize,
+                CoreFileSize => rtio::CoreFileSize,
+                OpenFiles => rtio::OpenFiles,
+                AddressSpace => rtio::AddressSpace,
+                Processes => rtio::Processes,
+            }
+        }
         let extra_io: Vec<rtio::StdioContainer> =
             self.extra_io.iter().map(|x| to_rtio(*x)).collect();
+        let rlimits: Vec<rtio::ResourceLimit> =
+            self.rlimits.iter().map(|&(resource, soft, hard)| {
+                rtio::ResourceLimit {
+                    resource: to_rtio_resource(resource),
+                    soft: soft,
+                    hard: hard,
+                }
+            }).collect();
         LocalIo::maybe_raise(|io| {
             let env = match self.env {
                 None => None,
@@ -287,6 +358,8 @@ impl Command {
                 uid: self.uid,
                 gid: self.gid,
                 detach: self.detach,
+                pgid: self.pgid,
+                rlimits: rlimits.as_slice(),
             };
             io.spawn(cfg).map(|(p, io)| {
                 let mut io = io.move_iter().map(|p| {
@@ -322,7 +395,159 @@ impl Command {
     /// println!("stderr: {}", String::from_utf8_lossy(output.error.as_slice()));
     /// ```
     pub fn output(&self) -> IoResult<ProcessOutput> {
-        self.spawn().and_then(|p| p.wait_with_output())
+        if self.output_limit.is_none() {
+            return self.spawn().and_then(|p| p.wait_with_output());
+        }
+        self.collect_output(None).map(|out| {
+            ProcessOutput {
+                status: out.status,
+                output: out.output,
+                error: out.error,
+            }
+        })
+    }
+
+    /// Executes the command as a child process, waiting at most `timeout` for
+    /// it to finish and collecting all of its output.
+    ///
+    /// The stdout and stderr of the child are drained at the same time, so a
+    /// child which fills up one of them while we're reading the other can't
+    /// deadlock. If the child hasn't exited and closed both of them by the
+    /// time `timeout` has elapsed then it is killed, along with its process
+    /// group if it was spawned into one with `setpgid` or `setsid`. The output
+    /// collected up to that point is returned with `timed_out` set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Command;
+    /// use std::time::Duration;
+    ///
+    /// let out = match Command::new("make").output_with_timeout(Duration::minutes(5)) {
+    ///     Ok(out) => out,
+    ///     Err(e) => fail!("failed to execute process: {}", e),
+    /// };
+    ///
+    /// if out.timed_out {
+    ///     println!("gave up on make, it said: {}",
+    ///              String::from_utf8_lossy(out.output.as_slice()));
+    /// }
+    /// ```
+    pub fn output_with_timeout(&self, timeout: Duration)
+                               -> IoResult<TimedOutput> {
+        self.collect_output(Some(timeout))
+    }
+
+    // Both runtimes are driven the same way here: a task is spawned to read
+    // each pipe, and the chunks they read are collected over a channel, which
+    // is selected on along with the deadline. The readers' reads time out at
+    // the deadline too, so they don't outlive it waiting on a pipe which some
+    // other process, like a child of the child, still has open. (Pipes can't
+    // time out on windows with the native runtime, so there the readers only
+    // exit once the pipes are closed.)
+    fn collect_output(&self, timeout: Option<Duration>) -> IoResult<TimedOutput> {
+        // How often to check the deadline once the child has closed its
+        // output but hasn't yet exited.
+        static WAIT_INTERVAL_MS: u64 = 50;
+
+        let mut p = try!(self.spawn());
+        drop(p.stdin.take());
+
+        let read_timeout = timeout.map(|t| cmp::max(t.num_milliseconds(), 0) as u64);
+        let (tx, rx) = channel();
+        let mut open = 0u;
+        let streams = vec![p.stdout.take(), p.stderr.take()];
+        for (i, stream) in streams.move_iter().enumerate() {
+            let stream = match stream {
+                Some(stream) => stream,
+                None => continue,
+            };
+            let tx = tx.clone();
+            open += 1;
+            spawn(proc() {
+                let mut stream = stream;
+                stream.set_read_timeout(read_timeout);
+                let mut buf = [0u8, ..4096];
+                loop {
+                    let chunk = match stream.read(buf) {
+                        Ok(n) => Some(buf.slice_to(n).to_vec()),
+                        Err(..) => None,
+                    };
+                    let done = chunk.is_none();
+                    // Stop reading if nobody is collecting the output anymore
+                    if tx.send_opt((i, chunk)).is_err() || done { break }
+                }
+            });
+        }
+        drop(tx);
+
+        let (_never, mut deadline) = channel();
+        let _timer = match timeout {
+            Some(timeout) => {
+                let mut timer = try!(Timer::new());
+                deadline = timer.oneshot(timeout);
+                Some(timer)
+            }
+            None => None,
+        };
+
+        let limit = self.output_limit.unwrap_or(uint::MAX);
+        let mut out = TimedOutput {
+            status: ExitStatus(0),
+            output: Vec::new(),
+            error: Vec::new(),
+            timed_out: false,
+            truncated: false,
+        };
+        while open > 0 && !out.timed_out {
+            select! {
+                chunk = rx.recv() => match chunk {
+                    (i, Some(data)) => out.push(i, data.as_slice(), limit),
+                    (_, None) => open -= 1
+                },
+                () = deadline.recv() => out.timed_out = true
+            }
+        }
+
+        let mut status = None;
+        while status.is_none() && !out.timed_out {
+            if timeout.is_some() {
+                p.set_timeout(Some(WAIT_INTERVAL_MS));
+            }
+            match p.wait() {
+                Ok(s) => status = Some(s),
+                Err(ref e) if e.kind == io::TimedOut => {
+                    out.timed_out = deadline.try_recv().is_ok();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        p.set_timeout(None);
+
+        if out.timed_out {
+            // Whatever is left in the group goes too, even if the child itself
+            // has already exited
+            if self.pgid.is_some() || self.detach {
+                let _ = p.signal_group(MustDieSignal);
+            }
+            // Pick up anything that was read before we gave up
+            loop {
+                match rx.try_recv() {
+                    Ok((i, Some(data))) => out.push(i, data.as_slice(), limit),
+                    Ok((_, None)) => {}
+                    Err(..) => break,
+                }
+            }
+        }
+        out.status = match status {
+            Some(status) => status,
+            None => {
+                // The child may have exited in the meantime, which is fine
+                let _ = p.signal_kill();
+                try!(p.wait())
+            }
+        };
+        Ok(out)
     }
 
     /// Executes a command as a child process, waiting for it to finish and
@@ -343,6 +568,68 @@ impl Command {
     pub fn status(&self) -> IoResult<ProcessExit> {
         self.spawn().and_then(|mut p| p.wait())
     }
+
+    /// Executes this command and `other` as two child processes, with the
+    /// stdout of this command connected directly to the stdin of `other`, as
+    /// in the shell pipeline `self | other`.
+    ///
+    /// The stdout configuration of this command and the stdin configuration
+    /// of `other` are ignored, everything else is spawned as configured. The
+    /// processes are returned in the same order as they appear in the
+    /// pipeline.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Command;
+    ///
+    /// let (_ls, mut wc) = match Command::new("ls").pipe_to(&Command::new("wc")) {
+    ///     Ok(pair) => pair,
+    ///     Err(e) => fail!("failed to execute pipeline: {}", e),
+    /// };
+    ///
+    /// let output = wc.stdout.as_mut().unwrap().read_to_end();
+    /// ```
+    pub fn pipe_to(&self, other: &Command) -> IoResult<(Process, Process)> {
+        struct Closer { fd: libc::c_int }
+
+        let os::Pipe { reader, writer } = try!(unsafe { os::pipe() });
+        let reader = Closer { fd: reader };
+        let writer = Closer { fd: writer };
+
+        // Neither end of the pipe may leak into the other process (or any
+        // other child spawned concurrently), otherwise the reader would never
+        // see the end of its input.
+        try!(set_cloexec(reader.fd));
+        try!(set_cloexec(writer.fd));
+
+        let first = try!(self.clone().stdout(InheritFd(writer.fd)).spawn());
+        drop(writer);
+        let second = other.clone().stdin(InheritFd(reader.fd)).spawn();
+        // Close our read end before `first` could be waited on by its
+        // destructor, so that it can't block forever writing to the pipe.
+        drop(reader);
+        return second.map(|second| (first, second));
+
+        impl Drop for Closer {
+            fn drop(&mut self) {
+                let _ = unsafe { libc::close(self.fd) };
+            }
+        }
+
+        #[cfg(unix)]
+        fn set_cloexec(fd: libc::c_int) -> IoResult<()> {
+            use libc::consts::os::posix01::F_SETFD;
+            match unsafe { libc::fcntl(fd, F_SETFD, 1 as libc::c_int) } {
+                -1 => Err(IoError::last_error()),
+                _ => Ok(()),
+            }
+        }
+
+        // Pipes from `os::pipe` are already not inheritable on windows
+        #[cfg(windows)]
+        fn set_cloexec(_fd: libc::c_int) -> IoResult<()> { Ok(()) }
+    }
 }
 
 impl fmt::Show for Command {
@@ -369,6 +656,37 @@ pub struct ProcessOutput {
     pub error: Vec<u8>,
 }
 
+/// The output of a process run by `Command::output_with_timeout`.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct TimedOutput {
+    /// The status (exit code) of the process. If the process timed out, this
+    /// is the status it had once it was killed.
+    pub status: ProcessExit,
+    /// The data that the process wrote to stdout.
+    pub output: Vec<u8>,
+    /// The data that the process wrote to stderr.
+    pub error: Vec<u8>,
+    /// Whether the process was still running, or still had its stdout or
+    /// stderr open, when the timeout elapsed. If so, `output` and `error`
+    /// only hold what had been read by then.
+    pub timed_out: bool,
+    /// Whether any output was discarded because of `Command::output_limit`.
+    pub truncated: bool,
+}
+
+impl TimedOutput {
+    // Appends a chunk read from stdout (0) or stderr (1), up to `limit` bytes
+    // in total for each.
+    fn push(&mut self, stream: uint, data: &[u8], limit: uint) {
+        let dst = if stream == 0 {&mut self.output} else {&mut self.error};
+        let room = limit - dst.len();
+        if data.len() > room {
+            self.truncated = true;
+        }
+        dst.push_all(data.slice_to(cmp::min(room, data.len())));
+    }
+}
+
 /// Describes what to do with a standard io stream for a child process.
 #[deriving(Clone)]
 pub enum StdioContainer {
@@ -390,6 +708,30 @@ pub enum StdioContainer {
     CreatePipe(bool /* readable */, bool /* writable */),
 }
 
+/// A resource whose use by a child process can be limited with
+/// `Command::setrlimit`. These correspond to the `RLIMIT_*` resources of
+/// `setrlimit(2)`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum Resource {
+    /// The amount of CPU time the process may use, in seconds.
+    CpuTime,
+    /// The largest file the process may create, in bytes.
+    FileSize,
+    /// The maximum size of the process's data segment, in bytes.
+    DataSize,
+    /// The maximum size of the process's stack, in bytes.
+    StackSize,
+    /// The largest core file the process may dump, in bytes.
+    CoreFileSize,
+    /// One more than the highest file descriptor the process may open.
+    OpenFiles,
+    /// The maximum size of the process's virtual memory, in bytes.
+    AddressSpace,
+    /// The maximum number of processes which may exist for the process's
+    /// real user id.
+    Processes,
+}
+
 /// Describes the result of a process after it has terminated.
 /// Note that Windows have no signals, so the result is usually ExitStatus.
 #[deriving(PartialEq, Eq, Clone)]
@@ -464,6 +806,20 @@ impl Process {
         self.handle.kill(signal).map_err(IoError::from_rtio_error)
     }
 
+    /// Sends the specified signal to every process in the child's process
+    /// group.
+    ///
+    /// This requires that the child was spawned into a process group other
+    /// than ours, with `Command::setpgid` or `Command::setsid`; an error of
+    /// kind `InvalidInput` is returned otherwise. Unlike `signal`, this may
+    /// still succeed after the child itself has exited, for as long as any
+    /// other process remains in the group.
+    ///
+    /// Process groups are not supported on windows.
+    pub fn signal_group(&mut self, signal: int) -> IoResult<()> {
+        self.handle.kill_group(signal).map_err(IoError::from_rtio_error)
+    }
+
     /// Sends a signal to this child requesting that it exits. This is
     /// equivalent to sending a SIGTERM on unix platforms.
     pub fn signal_exit(&mut self) -> IoResult<()> {
@@ -723,6 +1079,47 @@ mod tests {
         assert!(Command::new("/bin/ls").uid(0).gid(0).spawn().is_err());
     })
 
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn pipe_to_works() {
+        let mut echo = Command::new("echo");
+        echo.arg("foobar");
+        let (mut first, mut second) = echo.pipe_to(&Command::new("cat")).unwrap();
+        let out = read_all(second.stdout.get_mut_ref() as &mut Reader);
+        assert!(first.wait().unwrap().success());
+        assert!(second.wait().unwrap().success());
+        assert_eq!(out, "foobar\n".to_string());
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn setrlimit_works() {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg("ulimit -n")
+           .setrlimit(OpenFiles, Some(64), Some(64));
+        assert_eq!(run_output(cmd), "64\n".to_string());
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn signal_group_works() {
+        let mut p = Command::new("/bin/sh")
+                            .arg("-c").arg("sleep 1000 & wait")
+                            .setpgid(0)
+                            .spawn().unwrap();
+        p.signal_group(MustDieSignal).unwrap();
+        assert!(!p.wait().unwrap().success());
+
+        // The orphaned sleep was in the group too, so it goes away as well
+        for _ in range(0i, 20) {
+            if p.signal_group(0).is_err() { return }
+            timer::sleep(Duration::milliseconds(100));
+        }
+        fail!("never saw the process group go away");
+    })
+
+    #[cfg(windows)]
+    iotest!(fn setpgid_fails_on_windows() {
+        assert!(Command::new("test").setpgid(0).spawn().is_err());
+    })
+
     #[cfg(not(target_os="android"))]
     iotest!(fn test_process_status() {
         let mut status = Command::new("false").status().unwrap();
@@ -763,6 +1160,58 @@ mod tests {
         assert!(!error.is_empty());
     })
 
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn test_output_with_timeout() {
+        let out = Command::new("/bin/sh")
+                          .arg("-c").arg("echo foo; echo bar >&2")
+                          .output_with_timeout(Duration::seconds(10)).unwrap();
+        assert!(out.status.success());
+        assert!(!out.timed_out);
+        assert!(!out.truncated);
+        assert_eq!(out.output.as_slice(), b"foo\n");
+        assert_eq!(out.error.as_slice(), b"bar\n");
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn test_output_with_timeout_kills() {
+        let out = Command::new("/bin/sh")
+                          .arg("-c").arg("echo foo; exec sleep 1000")
+                          .output_with_timeout(Duration::milliseconds(500))
+                          .unwrap();
+        assert!(out.timed_out);
+        assert!(!out.status.success());
+        assert_eq!(out.output.as_slice(), b"foo\n");
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn test_output_with_timeout_grandchild() {
+        // The shell exits right away, but the sleep it leaves behind keeps
+        // stdout and stderr open until it's killed along with its session
+        let out = Command::new("/bin/sh")
+                          .arg("-c").arg("echo foo; sleep 1000 &")
+                          .detached()
+                          .output_with_timeout(Duration::milliseconds(500))
+                          .unwrap();
+        assert!(out.timed_out);
+        assert!(out.status.success());
+        assert_eq!(out.output.as_slice(), b"foo\n");
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn test_output_limit() {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg("echo 0123456789; echo 0123456789 >&2")
+           .output_limit(4);
+        let out = cmd.output_with_timeout(Duration::seconds(10)).unwrap();
+        assert!(out.status.success());
+        assert!(out.truncated);
+        assert_eq!(out.output.as_slice(), b"0123");
+        assert_eq!(out.error.as_slice(), b"0123");
+
+        let out = cmd.output().unwrap();
+        assert_eq!(out.output.as_slice(), b"0123");
+    })
+
     #[cfg(not(target_os="android"))]
     iotest!(fn test_finish_once() {
         let mut prog = Command::new("false").spawn().unwrap();
@@ -967,6 +1416,13 @@ mod tests {
         assert!(!p.wait().unwrap().success());
     })
 
+    iotest!(fn signal_group_needs_group() {
+        let mut p = sleeper();
+        assert!(p.signal_group(PleaseExitSignal).is_err());
+        p.signal_kill().unwrap();
+        assert!(!p.wait().unwrap().success());
+    })
+
     iotest!(fn test_zero() {
         let mut p = sleeper();
         p.signal_kill().unwrap();
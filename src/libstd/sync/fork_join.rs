@@ -0,0 +1,775 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A work-stealing pool for fork-join parallelism.
+//!
+//! Each task of a `ForkJoinPool` owns a Chase-Lev deque (see
+//! `std::sync::deque`). Work forked by `join` or `Scope::spawn` is pushed on
+//! the local deque, and idle tasks steal from the other end of their
+//! siblings' deques, so the oldest and largest pieces of work are the ones
+//! which migrate. A task waiting for forked work to finish runs other jobs in
+//! the meantime instead of blocking.
+//!
+//! Closures given to `join` and `scope` may borrow from the caller's stack,
+//! since neither returns before all the work it forked has completed. They
+//! must be `Sync`, as they may run on another task.
+//!
+//! A job which a task steals from another, or takes from those submitted to
+//! the pool, has its failure caught on the task's own stack, so that the
+//! failure only reaches whoever waits for the job: `join` and `scope` fail in
+//! turn, and forcing the `Future` of a spawned job fails. Dropping the pool
+//! runs the jobs already submitted, then waits for its tasks to exit.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::ForkJoinPool;
+//!
+//! fn sum(pool: &ForkJoinPool, v: &[uint]) -> uint {
+//!     if v.len() <= 1024 {
+//!         return v.iter().fold(0, |a, &b| a + b);
+//!     }
+//!     let (left, right) = v.split_at(v.len() / 2);
+//!     let (a, b) = pool.join(|| sum(pool, left), || sum(pool, right));
+//!     a + b
+//! }
+//!
+//! let pool = ForkJoinPool::new(4);
+//! let v = Vec::from_fn(100000, |i| i);
+//! assert_eq!(sum(&pool, v.as_slice()), 99999 * 100000 / 2);
+//! ```
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use collections::{Deque, MutableSeq, RingBuf};
+use comm::{channel, Sender};
+use core::kinds::marker;
+use core::mem;
+use rt::local::Local;
+use rt::task::Task;
+use rt::unwind;
+use sync::atomic::{AtomicBool, AtomicUint, SeqCst};
+use sync::deque::{BufferPool, Worker, Stealer, Data, Empty, Abort};
+use sync::{Future, Mutex};
+use task;
+use vec;
+use vec::Vec;
+
+local_data_key!(WORKER_THREAD: *const WorkerThread)
+
+// Condition variables of the registry's mutex
+static WORK_AVAILABLE: uint = 0;
+static WORKER_EXITED: uint = 1;
+
+// States of a job which a task waits on
+static PENDING: uint = 0;
+static DONE: uint = 1;
+static FAILED: uint = 2;
+
+/// A pool of tasks which run forked work, balancing it by work stealing.
+pub struct ForkJoinPool {
+    registry: Arc<Registry>,
+}
+
+/// Work spawned within `ForkJoinPool::scope`, which may borrow anything
+/// that outlives the lifetime `'a`.
+pub struct Scope<'a> {
+    registry: Arc<Registry>,
+    pending: AtomicUint,
+    failed: AtomicBool,
+    marker: marker::InvariantLifetime<'a>,
+}
+
+// The state shared by the pool's handle and its tasks
+struct Registry {
+    state: Mutex<RegistryState>,
+    // Tasks waiting for work, which have to be woken up when some is pushed
+    sleeping: AtomicUint,
+}
+
+struct RegistryState {
+    // Jobs submitted from outside the pool
+    injected: RingBuf<JobRef>,
+    terminating: bool,
+    live: uint,
+}
+
+// A type-erased pointer to a job. Jobs forked by `join`, or run on behalf of
+// a task outside the pool, live in the frame of the task which waits for
+// them; other jobs are boxed procs which are freed when run. A job which is
+// cancelled is dropped without being run.
+struct JobRef {
+    data: *const (),
+    // The `join` or scope which forked the job, which is the only frame it
+    // may fail on
+    origin: *const (),
+    run: unsafe fn(*const ()),
+    cancel: unsafe fn(*const ()),
+}
+
+impl JobRef {
+    unsafe fn execute(self) { (self.run)(self.data) }
+    unsafe fn cancel(self) { (self.cancel)(self.data) }
+}
+
+struct StackJob<'a, R> {
+    func: Option<||:'a + Sync -> R>,
+    result: Option<R>,
+    state: AtomicUint,
+    // Woken once the job completes, if the waiting task is outside the pool
+    waiter: Option<Sender<()>>,
+    // Told once the job completes, to wake up a pool task waiting for it
+    registry: *const Registry,
+}
+
+impl<'a, R: Send> StackJob<'a, R> {
+    fn new(func: ||:'a + Sync -> R, registry: &Registry,
+           waiter: Option<Sender<()>>) -> StackJob<'a, R> {
+        StackJob {
+            func: Some(func),
+            result: None,
+            state: AtomicUint::new(PENDING),
+            waiter: waiter,
+            registry: registry as *const Registry,
+        }
+    }
+
+    // Unsafe because the job must stay put, and stay alive, until its state
+    // is no longer PENDING
+    unsafe fn as_job_ref(&self) -> JobRef {
+        let data = self as *const StackJob<'a, R> as *const ();
+        JobRef {
+            data: data,
+            origin: data,
+            run: run_stack_job::<R>,
+            cancel: cancel_stack_job::<R>,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.state.load(SeqCst) != PENDING
+    }
+
+    fn into_result(self) -> R {
+        match self.state.load(SeqCst) {
+            DONE => self.result.unwrap(),
+            _ => fail!("a job run by a ForkJoinPool failed"),
+        }
+    }
+}
+
+// The lifetime of the job's closure is erased here and in `cancel_stack_job`.
+// That is sound because the frame owning the job doesn't return before the
+// job is no longer PENDING, even when it unwinds (see `Unwinding`), so the
+// closure can't outlive what it borrows.
+unsafe fn run_stack_job<R: Send>(data: *const ()) {
+    let job = data as *mut StackJob<'static, R>;
+    let _completion = Completion {
+        state: &(*job).state,
+        waiter: (*job).waiter.take(),
+        registry: (*job).registry,
+    };
+    let func = (*job).func.take().unwrap();
+    (*job).result = Some(func());
+}
+
+unsafe fn cancel_stack_job<R: Send>(data: *const ()) {
+    let job = data as *mut StackJob<'static, R>;
+    // Only ever called while unwinding, so this marks the job as failed
+    let _completion = Completion {
+        state: &(*job).state,
+        waiter: (*job).waiter.take(),
+        registry: (*job).registry,
+    };
+}
+
+// Publishes the outcome of a stack job, even if running it failed
+struct Completion {
+    state: *const AtomicUint,
+    waiter: Option<Sender<()>>,
+    // The pool task completing the job keeps the registry alive
+    registry: *const Registry,
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        let state = if task::failing() { FAILED } else { DONE };
+        unsafe { (*self.state).store(state, SeqCst) }
+        // The job may be gone as soon as its state is set, but the sender has
+        // been moved out of it
+        match self.waiter.take() {
+            Some(tx) => { let _ = tx.send_opt(()); }
+            None => {}
+        }
+        unsafe { (*self.registry).notify_done() }
+    }
+}
+
+fn heap_job(f: proc():Send, origin: *const ()) -> JobRef {
+    JobRef {
+        // The job owns the box until it is run or cancelled
+        data: unsafe { mem::transmute(box f) },
+        origin: origin,
+        run: run_heap_job,
+        cancel: cancel_heap_job,
+    }
+}
+
+unsafe fn run_heap_job(data: *const ()) {
+    let job: Box<proc():Send> = mem::transmute(data);
+    let f = *job;
+    f()
+}
+
+unsafe fn cancel_heap_job(data: *const ()) {
+    let _job: Box<proc():Send> = mem::transmute(data);
+}
+
+// Marks a scope's job as finished, whether it ran, failed or was cancelled
+struct ScopeJobDone {
+    scope: *const Scope<'static>,
+}
+
+impl Drop for ScopeJobDone {
+    fn drop(&mut self) {
+        unsafe {
+            // The scope may end as soon as it has no pending jobs, but the
+            // pool task running this one keeps the registry alive
+            let registry = &*(*self.scope).registry as *const Registry;
+            if task::failing() {
+                (*self.scope).failed.store(true, SeqCst);
+            }
+            (*self.scope).pending.fetch_sub(1, SeqCst);
+            (*registry).notify_done();
+        }
+    }
+}
+
+impl Registry {
+    fn inject(&self, job: JobRef) {
+        let mut state = self.state.lock();
+        state.injected.push(job);
+        state.cond.signal_on(WORK_AVAILABLE);
+    }
+
+    // Wakes up a sleeping task, if there is one, after work was pushed on a
+    // deque
+    fn notify(&self) {
+        if self.sleeping.load(SeqCst) > 0 {
+            let state = self.state.lock();
+            state.cond.signal_on(WORK_AVAILABLE);
+        }
+    }
+
+    // Wakes up the sleeping tasks after a job completed, since one of them
+    // may be waiting for it
+    fn notify_done(&self) {
+        if self.sleeping.load(SeqCst) > 0 {
+            let state = self.state.lock();
+            state.cond.broadcast_on(WORK_AVAILABLE);
+        }
+    }
+}
+
+// The state of one of the pool's tasks, which lives on its stack
+struct WorkerThread {
+    registry: Arc<Registry>,
+    index: uint,
+    worker: Worker<JobRef>,
+    stealers: Vec<Stealer<JobRef>>,
+}
+
+// Sets whether this task is unwinding, returning whether it was
+unsafe fn set_unwinding(unwinding: bool) -> bool {
+    let mut task = Local::borrow(None::<Task>);
+    task.unwinder.set_unwinding(unwinding)
+}
+
+fn spawn_worker(registry: Arc<Registry>, index: uint,
+                worker: Worker<JobRef>, stealers: Vec<Stealer<JobRef>>) {
+    task::spawn(proc() {
+        let thread = WorkerThread {
+            registry: registry,
+            index: index,
+            worker: worker,
+            stealers: stealers,
+        };
+        WORKER_THREAD.replace(Some(&thread as *const WorkerThread));
+        thread.main_loop();
+    });
+}
+
+impl WorkerThread {
+    // The pool task running on this task, if it belongs to `registry`
+    fn current(registry: &Arc<Registry>) -> Option<*const WorkerThread> {
+        let thread = match WORKER_THREAD.get() {
+            Some(thread) => *thread,
+            None => return None,
+        };
+        let same = unsafe {
+            &*(*thread).registry as *const Registry == &**registry as *const Registry
+        };
+        if same { Some(thread) } else { None }
+    }
+
+    fn push(&self, job: JobRef) {
+        self.worker.push(job);
+        self.registry.notify();
+    }
+
+    fn pop(&self) -> Option<JobRef> {
+        self.worker.pop()
+    }
+
+    // Steals from the other tasks' deques, starting with the next one along
+    fn steal(&self) -> Option<JobRef> {
+        let n = self.stealers.len();
+        for i in range(1, n) {
+            let victim = &self.stealers[(self.index + i) % n];
+            loop {
+                match victim.steal() {
+                    Data(job) => return Some(job),
+                    Empty => break,
+                    Abort => {}
+                }
+            }
+        }
+        None
+    }
+
+    fn main_loop(&self) {
+        loop {
+            let job = match self.pop().or_else(|| self.steal()) {
+                Some(job) => Some(job),
+                None => self.wait_for_work(),
+            };
+            match job {
+                // Nothing on this task's stack forked the job
+                Some(job) => unsafe { self.execute_isolated(job) },
+                None => break,
+            }
+        }
+    }
+
+    // Runs a job which none of the frames on this task's stack forked,
+    // catching its failure so that the failure stops there: whoever waits for
+    // the job is told, and our frames carry on. The job runs on our stack, so
+    // the work it forks goes on our deque.
+    //
+    // We may be unwinding already, when waiting out the jobs of a frame which
+    // failed, so the task is only marked as unwinding while the job runs if
+    // the job itself fails.
+    unsafe fn execute_isolated(&self, job: JobRef) {
+        let unwinding = set_unwinding(false);
+        let mut job = Some(job);
+        let _ = unwind::try(|| job.take().unwrap().execute());
+        set_unwinding(unwinding);
+    }
+
+    // Blocks until there is work, or returns `None` once the pool is
+    // shutting down and none is left
+    fn wait_for_work(&self) -> Option<JobRef> {
+        let mut state = self.registry.state.lock();
+        // Announce ourselves before looking again, so that whoever pushes
+        // work after our last look knows to wake us up
+        self.registry.sleeping.fetch_add(1, SeqCst);
+        let mut job;
+        loop {
+            job = state.injected.pop_front().or_else(|| self.steal());
+            if job.is_some() || state.terminating { break }
+            state.cond.wait_on(WORK_AVAILABLE);
+        }
+        self.registry.sleeping.fetch_sub(1, SeqCst);
+        job
+    }
+
+    // Blocks until `done` returns true, or until there is a job to run which
+    // isn't on this task's deque
+    fn wait_for_job<'a>(&self, done: &mut ||:'a -> bool) -> Option<JobRef> {
+        let mut state = self.registry.state.lock();
+        // As in `wait_for_work`, whoever pushes or completes a job after our
+        // last look knows to wake us up
+        self.registry.sleeping.fetch_add(1, SeqCst);
+        let mut job = None;
+        while !(*done)() {
+            job = state.injected.pop_front().or_else(|| self.steal());
+            if job.is_some() { break }
+            state.cond.wait_on(WORK_AVAILABLE);
+        }
+        self.registry.sleeping.fetch_sub(1, SeqCst);
+        job
+    }
+
+    // Runs other jobs until `done` returns true. Only the jobs forked by
+    // `origin` run on this stack, since failing would fail the frame of
+    // `origin` anyway; others are isolated from it.
+    fn wait_until(&self, origin: *const (), mut done: || -> bool) {
+        while !done() {
+            let job = match self.pop().or_else(|| self.steal()) {
+                Some(job) => Some(job),
+                None => self.wait_for_job(&mut done),
+            };
+            match job {
+                Some(job) => unsafe {
+                    if job.origin == origin {
+                        job.execute()
+                    } else {
+                        self.execute_isolated(job)
+                    }
+                },
+                None => {}
+            }
+        }
+    }
+
+    fn join<RA: Send, RB: Send>(&self, a: ||:Sync -> RA, b: ||:Sync -> RB) -> (RA, RB) {
+        let job_b = StackJob::new(b, &*self.registry, None);
+        let ra = {
+            let job_ref = unsafe { job_b.as_job_ref() };
+            let origin = job_ref.origin;
+            self.push(job_ref);
+            let _guard = Unwinding {
+                thread: self,
+                origin: origin,
+                finished: || job_b.finished(),
+            };
+            let ra = a();
+            // Runs `b` right here unless it has been stolen
+            self.wait_until(origin, || job_b.finished());
+            ra
+        };
+        (ra, job_b.into_result())
+    }
+}
+
+impl Drop for WorkerThread {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock();
+        state.live -= 1;
+        state.cond.broadcast_on(WORKER_EXITED);
+    }
+}
+
+// Keeps the frame of a `join` or `scope` alive while it unwinds until none of
+// the jobs it forked can run any more
+struct Unwinding<'a> {
+    thread: &'a WorkerThread,
+    origin: *const (),
+    finished: ||:'a -> bool,
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for Unwinding<'a> {
+    fn drop(&mut self) {
+        if !task::failing() { return }
+        // The jobs the frame forked which are still on this task's deque may
+        // not run any more. They are on top of it: the frames inside this one
+        // have cancelled theirs already, and below are those of the frames
+        // outside it, which stay.
+        loop {
+            match self.thread.pop() {
+                Some(job) => {
+                    if job.origin != self.origin {
+                        self.thread.worker.push(job);
+                        break
+                    }
+                    unsafe { job.cancel() }
+                }
+                None => break,
+            }
+        }
+        // Jobs which were stolen have to be waited out. Other work can be
+        // done in the meantime, as long as it doesn't run on this stack.
+        while !(self.finished)() {
+            match self.thread.wait_for_job(&mut self.finished) {
+                Some(job) => unsafe { self.thread.execute_isolated(job) },
+                None => {}
+            }
+        }
+    }
+}
+
+impl ForkJoinPool {
+    /// Spawns a pool of `n_tasks` tasks.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if `n_tasks` is less than 1.
+    pub fn new(n_tasks: uint) -> ForkJoinPool {
+        assert!(n_tasks >= 1);
+
+        let registry = Arc::new(Registry {
+            state: Mutex::new_with_condvars(RegistryState {
+                injected: RingBuf::new(),
+                terminating: false,
+                live: n_tasks,
+            }, 2),
+            sleeping: AtomicUint::new(0),
+        });
+
+        let buffers = BufferPool::new();
+        let (workers, stealers) = vec::unzip(range(0, n_tasks).map(|_| buffers.deque()));
+        for (index, worker) in workers.move_iter().enumerate() {
+            spawn_worker(registry.clone(), index, worker, stealers.clone());
+        }
+
+        ForkJoinPool { registry: registry }
+    }
+
+    /// Runs `a` and `b`, potentially in parallel, and returns both results.
+    ///
+    /// Called from one of the pool's tasks, `a` runs right away and `b` is
+    /// made available to be stolen; called from anywhere else, the calling
+    /// task blocks until both have run in the pool.
+    ///
+    /// # Failure
+    ///
+    /// Fails if either closure fails.
+    pub fn join<RA: Send, RB: Send>(&self, a: ||:Sync -> RA, b: ||:Sync -> RB) -> (RA, RB) {
+        self.in_worker(|thread| thread.join(|| a(), || b()))
+    }
+
+    /// Runs `f` with a scope in which jobs borrowing from the caller's stack
+    /// can be spawned, and returns once `f` and all of those jobs are done.
+    ///
+    /// # Failure
+    ///
+    /// Fails if `f` or any of the jobs spawned in the scope fail.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::ForkJoinPool;
+    ///
+    /// let pool = ForkJoinPool::new(4);
+    /// let mut pixels = Vec::from_elem(1024, 0u8);
+    ///
+    /// pool.scope(|scope| {
+    ///     for (i, row) in pixels.as_mut_slice().mut_chunks(64).enumerate() {
+    ///         scope.spawn(proc() {
+    ///             for p in row.mut_iter() { *p = i as u8; }
+    ///         });
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(pixels[1000], 15);
+    /// ```
+    pub fn scope<'a, R: Send>(&self, f: |&Scope<'a>|:Sync -> R) -> R {
+        self.in_worker(|thread| {
+            let scope = Scope {
+                registry: thread.registry.clone(),
+                pending: AtomicUint::new(0),
+                failed: AtomicBool::new(false),
+                marker: marker::InvariantLifetime,
+            };
+            let origin = &scope as *const Scope<'a> as *const ();
+            let result = {
+                let _guard = Unwinding {
+                    thread: thread,
+                    origin: origin,
+                    finished: || scope.pending.load(SeqCst) == 0,
+                };
+                let result = f(&scope);
+                thread.wait_until(origin, || scope.pending.load(SeqCst) == 0);
+                result
+            };
+            if scope.failed.load(SeqCst) {
+                fail!("a job spawned in a ForkJoinPool scope failed");
+            }
+            result
+        })
+    }
+
+    /// Submits `f` to run in the pool, returning a future for its result.
+    ///
+    /// If `f` fails, forcing the future fails.
+    pub fn spawn<T: Send>(&self, f: proc():Send -> T) -> Future<T> {
+        let (tx, rx) = channel();
+        self.registry.inject(heap_job(proc() {
+            // Don't fail if the future has been dropped
+            let _ = tx.send_opt(f());
+        }, 0 as *const ()));
+        Future::from_receiver(rx)
+    }
+
+    // Runs `f` on one of the pool's tasks: the current one if it belongs to
+    // the pool, or else by submitting `f` and blocking until it has run
+    fn in_worker<R: Send>(&self, f: |&WorkerThread|:Sync -> R) -> R {
+        match WorkerThread::current(&self.registry) {
+            Some(thread) => return f(unsafe { &*thread }),
+            None => {}
+        }
+
+        let (tx, rx) = channel();
+        let registry = &self.registry;
+        let job = StackJob::new(|| {
+            let thread = WorkerThread::current(registry).unwrap();
+            f(unsafe { &*thread })
+        }, &**registry, Some(tx));
+        self.registry.inject(unsafe { job.as_job_ref() });
+        let _ = rx.recv_opt();
+        job.into_result()
+    }
+}
+
+impl Drop for ForkJoinPool {
+    /// Runs the jobs which were submitted to the pool, then waits for its
+    /// tasks to exit.
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock();
+        state.terminating = true;
+        state.cond.broadcast_on(WORK_AVAILABLE);
+        while state.live > 0 {
+            state.cond.wait_on(WORKER_EXITED);
+        }
+    }
+}
+
+impl<'a> Scope<'a> {
+    /// Spawns `f` to run in the pool before the scope ends.
+    pub fn spawn(&self, f: proc():'a + Sync) {
+        self.pending.fetch_add(1, SeqCst);
+        let done = ScopeJobDone {
+            scope: self as *const Scope<'a> as *const Scope<'static>,
+        };
+        let job: proc():'a + Sync = proc() {
+            let _done = done;
+            f()
+        };
+        // Erasing `'a` to make the job `Send` is sound because the scope
+        // doesn't end before the job has run or been cancelled: `scope` waits
+        // for all of its jobs, and if it unwinds, `Unwinding` cancels those
+        // which haven't started and waits out the others. Nothing the job
+        // borrows can go away while it may still run.
+        let origin = self as *const Scope<'a> as *const ();
+        let job = heap_job(unsafe { mem::transmute(job) }, origin);
+        match WorkerThread::current(&self.registry) {
+            Some(thread) => unsafe { (*thread).push(job) },
+            None => self.registry.inject(job),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prelude::*;
+    use sync::{Arc, Future};
+    use sync::atomic::{AtomicBool, AtomicUint, SeqCst};
+    use task;
+    use super::ForkJoinPool;
+
+    fn fib(pool: &ForkJoinPool, n: uint) -> uint {
+        if n < 2 { return n }
+        let (a, b) = pool.join(|| fib(pool, n - 1), || fib(pool, n - 2));
+        a + b
+    }
+
+    #[test]
+    fn test_join() {
+        let pool = ForkJoinPool::new(4);
+        assert_eq!(pool.join(|| 1i, || "two"), (1, "two"));
+        assert_eq!(fib(&pool, 20), 6765);
+    }
+
+    #[test]
+    fn test_join_borrows() {
+        let pool = ForkJoinPool::new(2);
+        let mut v = Vec::from_fn(1000, |i| i);
+        {
+            let (left, right) = v.as_mut_slice().split_at_mut(500);
+            pool.join(|| for x in left.mut_iter() { *x *= 2 },
+                      || for x in right.mut_iter() { *x *= 3 });
+        }
+        assert_eq!(v[10], 20);
+        assert_eq!(v[600], 1800);
+    }
+
+    #[test]
+    fn test_scope() {
+        let pool = ForkJoinPool::new(3);
+        let mut v = Vec::from_elem(100, 0u);
+        let total = AtomicUint::new(0);
+        pool.scope(|scope| {
+            for (i, chunk) in v.as_mut_slice().mut_chunks(10).enumerate() {
+                let total = &total;
+                scope.spawn(proc() {
+                    for x in chunk.mut_iter() { *x = i; }
+                    total.fetch_add(chunk.len(), SeqCst);
+                });
+            }
+        });
+        assert_eq!(total.load(SeqCst), 100);
+        assert!(v.iter().enumerate().all(|(j, &x)| x == j / 10));
+    }
+
+    #[test]
+    fn test_spawn_future() {
+        let pool = ForkJoinPool::new(2);
+        let mut futures: Vec<Future<uint>> = range(0u, 20).map(|i| pool.spawn(proc() i * i)).collect();
+        let squares: Vec<uint> = futures.mut_iter().map(|f| f.get()).collect();
+        assert_eq!(squares, range(0u, 20).map(|i| i * i).collect());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_join_propagates_failure() {
+        let pool = ForkJoinPool::new(2);
+        pool.join(|| 1i, || -> int fail!());
+    }
+
+    #[test]
+    fn test_recovers_from_failure() {
+        let pool = Arc::new(ForkJoinPool::new(2));
+        for _ in range(0u, 4) {
+            let p = pool.clone();
+            let r = task::try(proc() {
+                p.scope(|scope| scope.spawn(proc() fail!()));
+            });
+            assert!(r.is_err());
+
+            let p = pool.clone();
+            assert!(task::try(proc() p.spawn(proc() -> int fail!()).unwrap()).is_err());
+        }
+        // The pool's tasks are still there
+        assert_eq!(fib(&*pool, 15), 610);
+    }
+
+    #[test]
+    fn test_failure_is_isolated() {
+        let pool = ForkJoinPool::new(2);
+        let stolen = AtomicBool::new(false);
+        let (a, b) = pool.join(|| {
+            // Wait for the other task to steal `b`, so that this one picks up
+            // the failing job while waiting for `b` to finish
+            while !stolen.load(SeqCst) { task::deschedule() }
+            1i
+        }, || {
+            stolen.store(true, SeqCst);
+            let failing = pool.spawn(proc() -> int fail!());
+            task::try(proc() { let mut failing = failing; failing.get() }).is_err()
+        });
+        // The failure didn't reach the `join` waiting on the same task
+        assert_eq!((a, b), (1, true));
+    }
+
+    #[test]
+    fn test_shutdown_runs_submitted_jobs() {
+        let count = Arc::new(AtomicUint::new(0));
+        {
+            let pool = ForkJoinPool::new(2);
+            for _ in range(0u, 100) {
+                let count = count.clone();
+                pool.spawn(proc() { count.fetch_add(1, SeqCst); });
+            }
+        }
+        assert_eq!(count.load(SeqCst), 100);
+    }
+}
@@ -21,6 +21,7 @@
 pub use core_sync::atomic;
 
 pub use core_sync::{deque, mpmc_bounded_queue, mpsc_queue, spsc_queue};
+pub use core_sync::concurrent_hashmap;
 pub use core_sync::{Arc, Weak, Mutex, MutexGuard, Condvar, Barrier};
 pub use core_sync::{RWLock, RWLockReadGuard, RWLockWriteGuard};
 pub use core_sync::{Semaphore, SemaphoreGuard};
@@ -29,8 +30,10 @@ pub use core_sync::one::{Once, ONCE_INIT};
 #[deprecated = "use atomic instead"]
 pub use core_sync::atomic as atomics;
 
+pub use self::fork_join::{ForkJoinPool, Scope};
 pub use self::future::Future;
 pub use self::task_pool::TaskPool;
 
+mod fork_join;
 mod future;
 mod task_pool;
@@ -0,0 +1,648 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bounded broadcast channels
+//!
+//! Every value sent on a broadcast channel is received by each of its
+//! subscribers. A subscriber is a `Receiver`: more can be created at any time
+//! with `Sender::subscribe`, which only sees the values sent from then on, or
+//! by cloning an existing one, which starts from the same position.
+//!
+//! The channel holds at most `capacity` values which haven't been received by
+//! every subscriber. What happens when a send would exceed that is up to the
+//! `Overflow` policy of the channel: either the oldest value is dropped, and
+//! the subscribers which hadn't received it yet are told how many values they
+//! missed, or the sender blocks until the slowest subscriber catches up.
+//!
+//! Receivers can be added to a `Select` set like any other.
+//!
+//! # Example
+//!
+//! ```
+//! use std::comm::broadcast;
+//!
+//! let (tx, rx1) = broadcast::channel(16, broadcast::Block);
+//! let rx2 = tx.subscribe();
+//!
+//! spawn(proc() {
+//!     for event in range(0i, 3) { tx.send(event); }
+//! });
+//!
+//! assert_eq!(rx1.iter().collect::<Vec<int>>(), vec![0, 1, 2]);
+//! assert_eq!(rx2.iter().collect::<Vec<int>>(), vec![0, 1, 2]);
+//! ```
+
+// Implementation-wise this is a mutex-protected ring of values, like the `sync`
+// flavor of channels. Each value is stored along with the number of
+// subscribers which have yet to receive it, and it is dropped from the front of
+// the ring once that reaches zero. Subscribers only keep the sequence number of
+// the next value they'll receive, so values are cloned for each subscriber but
+// the last one, which gets to move the value out.
+//
+// Unlike the other flavors, values are cloned while the lock is held, so
+// `Clone` implementations must not block.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use alloc::boxed::Box;
+use collections::{Deque, MutableSeq, RingBuf, Vec};
+use core::cell::{Cell, UnsafeCell};
+use core::cmp;
+use core::kinds::marker;
+use core::mem;
+use rustrt::local::Local;
+use rustrt::mutex::{NativeMutex, LockGuard};
+use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
+
+use atomic;
+use comm::select;
+
+/// What a broadcast channel does when a send would exceed its capacity.
+#[deriving(PartialEq, Clone, Show)]
+pub enum Overflow {
+    /// The oldest value is dropped to make room. Subscribers which hadn't
+    /// received it will get a `Lagged` error.
+    DropOldest,
+    /// The sender blocks until every subscriber has received the oldest
+    /// value.
+    Block,
+}
+
+/// The reasons why a broadcast receiver could not return a value.
+#[deriving(PartialEq, Clone, Show)]
+pub enum TryRecvError {
+    /// No value is available right now, but more may be sent.
+    Empty,
+    /// All of the senders have hung up and every value has been received.
+    Disconnected,
+    /// The receiver fell behind and the given number of values were dropped
+    /// before it could receive them. Receiving again returns the oldest value
+    /// still available.
+    Lagged(u64),
+}
+
+/// The sending half of a broadcast channel. It can be cloned to send from
+/// several tasks.
+pub struct Sender<T> {
+    inner: Arc<UnsafeCell<Packet<T>>>,
+    // can't share in an arc
+    marker: marker::NoSync,
+}
+
+/// A subscriber of a broadcast channel.
+pub struct Receiver<T> {
+    inner: Arc<UnsafeCell<Packet<T>>>,
+    // The sequence number of the next value for this subscriber
+    next: Cell<u64>,
+    // The id of this receiver in the list of waiters, while it selects
+    waiter: Cell<Option<uint>>,
+    // can't share in an arc
+    marker: marker::NoSync,
+}
+
+/// An iterator over messages on a broadcast receiver, which blocks waiting
+/// for messages, skips over lagged ones, and returns `None` once all senders
+/// have hung up and every message has been received.
+pub struct Messages<'a, T:'a> {
+    rx: &'a Receiver<T>
+}
+
+struct Packet<T> {
+    senders: atomic::AtomicUint,
+
+    /// The state field is protected by this mutex
+    lock: NativeMutex,
+    state: UnsafeCell<State<T>>,
+}
+
+struct State<T> {
+    buf: RingBuf<Slot<T>>,
+    head: u64,           // The sequence number of the front of `buf`
+    cap: uint,
+    overflow: Overflow,
+    subscribers: uint,
+    disconnected: bool,  // Have all the senders gone away?
+    waiters: Vec<(uint, BlockedTask)>,  // Receivers blocked on this channel
+    next_waiter: uint,
+    blocked_senders: Vec<BlockedTask>,
+}
+
+struct Slot<T> {
+    value: T,
+    remaining: uint,  // Subscribers which have yet to receive this value
+}
+
+/// Creates a new broadcast channel holding at most `capacity` values,
+/// returning the sender and a first subscriber.
+///
+/// # Failure
+///
+/// Fails if `capacity` is 0.
+pub fn channel<T: Send + Clone>(capacity: uint,
+                                overflow: Overflow) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channels need a capacity of at least 1");
+    let a = Arc::new(UnsafeCell::new(Packet {
+        senders: atomic::AtomicUint::new(1),
+        lock: unsafe { NativeMutex::new() },
+        state: UnsafeCell::new(State {
+            buf: RingBuf::with_capacity(capacity),
+            head: 0,
+            cap: capacity,
+            overflow: overflow,
+            subscribers: 1,
+            disconnected: false,
+            waiters: Vec::new(),
+            next_waiter: 0,
+            blocked_senders: Vec::new(),
+        }),
+    }));
+    (Sender::new(a.clone()), Receiver::new(a, 0))
+}
+
+impl<T> State<T> {
+    fn end(&self) -> u64 { self.head + self.buf.len() as u64 }
+
+    // Pops the values at the front which every subscriber has received.
+    // They're handed back to be dropped outside of the lock.
+    fn pop_received(&mut self, dropped: &mut Vec<T>) {
+        loop {
+            match self.buf.front() {
+                Some(slot) if slot.remaining == 0 => {}
+                _ => break,
+            }
+            dropped.push(self.buf.pop_front().unwrap().value);
+            self.head += 1;
+        }
+    }
+
+    // Counts a subscriber in or out of every value from `from` onwards
+    fn adjust_remaining(&mut self, from: u64, subscribed: bool) {
+        let start = (cmp::max(from, self.head) - self.head) as uint;
+        for i in range(start, self.buf.len()) {
+            let slot = self.buf.get_mut(i);
+            if subscribed { slot.remaining += 1 } else { slot.remaining -= 1 }
+        }
+    }
+}
+
+impl<T: Send + Clone> Packet<T> {
+    // Locks this channel, returning a guard for the state and the mutable state
+    // itself. See the `sync` flavor for why this is ok.
+    fn lock<'a>(&'a self) -> (LockGuard<'a>, &'a mut State<T>) {
+        unsafe {
+            let guard = self.lock.lock();
+            (guard, &mut *self.state.get())
+        }
+    }
+
+    fn send(&self, t: T) {
+        let (guard, state) = self.lock();
+        let mut dropped = Vec::new();
+
+        while state.subscribers > 0 && state.buf.len() == state.cap {
+            match state.overflow {
+                DropOldest => {
+                    dropped.push(state.buf.pop_front().unwrap().value);
+                    state.head += 1;
+                }
+                Block => {
+                    // Atomically block, unlocking in the meantime
+                    let me: Box<Task> = Local::take();
+                    me.deschedule(1, |task| {
+                        state.blocked_senders.push(task);
+                        unsafe { self.lock.unlock_noguard(); }
+                        Ok(())
+                    });
+                    unsafe { self.lock.lock_noguard(); }
+                }
+            }
+        }
+
+        // With nobody to receive it, the value is dropped right away
+        let waiters = if state.subscribers == 0 {
+            dropped.push(t);
+            Vec::new()
+        } else {
+            state.buf.push(Slot { value: t, remaining: state.subscribers });
+            mem::replace(&mut state.waiters, Vec::new())
+        };
+        mem::drop((state, guard));
+
+        for (_, task) in waiters.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    fn try_recv(&self, next: &Cell<u64>) -> Result<T, TryRecvError> {
+        let (guard, state) = self.lock();
+        let seq = next.get();
+
+        if seq < state.head {
+            next.set(state.head);
+            return Err(Lagged(state.head - seq))
+        }
+        if seq == state.end() {
+            return Err(if state.disconnected {Disconnected} else {Empty})
+        }
+
+        let idx = (seq - state.head) as uint;
+        next.set(seq + 1);
+        let value = {
+            let slot = state.buf.get_mut(idx);
+            slot.remaining -= 1;
+            if idx == 0 && slot.remaining == 0 { None } else { Some(slot.value.clone()) }
+        };
+        let value = match value {
+            Some(value) => value,
+            // We're the last one to receive the oldest value: move it out
+            None => {
+                state.head += 1;
+                state.buf.pop_front().unwrap().value
+            }
+        };
+
+        // Values received by everyone may now be dropped, making room for
+        // blocked senders
+        let mut dropped = Vec::new();
+        state.pop_received(&mut dropped);
+        self.wakeup_senders(guard, state);
+        Ok(value)
+    }
+
+    fn subscribe(&self, from: Option<u64>) -> u64 {
+        let (_g, state) = self.lock();
+        state.subscribers += 1;
+        match from {
+            Some(from) => {
+                state.adjust_remaining(from, true);
+                from
+            }
+            None => state.end(),
+        }
+    }
+
+    fn unsubscribe(&self, next: u64) {
+        let (guard, state) = self.lock();
+        state.subscribers -= 1;
+        state.adjust_remaining(next, false);
+        let mut dropped = Vec::new();
+        state.pop_received(&mut dropped);
+        self.wakeup_senders(guard, state);
+    }
+
+    // Wakes up every blocked sender, as there's room for one more value or
+    // there's nobody left to receive
+    fn wakeup_senders(&self, guard: LockGuard, state: &mut State<T>) {
+        let senders = if state.buf.len() < state.cap || state.subscribers == 0 {
+            mem::replace(&mut state.blocked_senders, Vec::new())
+        } else {
+            Vec::new()
+        };
+        mem::drop((state, guard));
+        for task in senders.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    fn drop_chan(&self) {
+        // Only flag the channel as disconnected if we're the last channel
+        match self.senders.fetch_sub(1, atomic::SeqCst) {
+            1 => {}
+            _ => return
+        }
+
+        let (guard, state) = self.lock();
+        state.disconnected = true;
+        let waiters = mem::replace(&mut state.waiters, Vec::new());
+        mem::drop((state, guard));
+        for (_, task) in waiters.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    fn can_recv(&self, next: u64) -> bool {
+        let (_g, state) = self.lock();
+        state.disconnected || next != state.end()
+    }
+
+    fn start_selection(&self, next: u64, waiter: &Cell<Option<uint>>,
+                       task: BlockedTask) -> Result<(), BlockedTask> {
+        let (_g, state) = self.lock();
+        if state.disconnected || next != state.end() {
+            return Err(task)
+        }
+        let id = state.next_waiter;
+        state.next_waiter += 1;
+        state.waiters.push((id, task));
+        waiter.set(Some(id));
+        Ok(())
+    }
+
+    fn abort_selection(&self, next: u64, waiter: &Cell<Option<uint>>) -> bool {
+        let (_g, state) = self.lock();
+        let id = waiter.get().unwrap();
+        waiter.set(None);
+        match state.waiters.iter().position(|&(i, _)| i == id) {
+            Some(pos) => state.waiters.remove(pos).unwrap().val1().trash(),
+            None => {}
+        }
+        state.disconnected || next != state.end()
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send + Clone> Drop for Packet<T> {
+    fn drop(&mut self) {
+        assert_eq!(self.senders.load(atomic::SeqCst), 0);
+        let (_g, state) = self.lock();
+        assert_eq!(state.subscribers, 0);
+        assert!(state.waiters.is_empty());
+        assert!(state.blocked_senders.is_empty());
+    }
+}
+
+impl<T: Send + Clone> Sender<T> {
+    fn new(inner: Arc<UnsafeCell<Packet<T>>>) -> Sender<T> {
+        Sender { inner: inner, marker: marker::NoSync }
+    }
+
+    fn packet<'a>(&'a self) -> &'a Packet<T> {
+        unsafe { &*self.inner.get() }
+    }
+
+    /// Sends a value to every current subscriber of this channel.
+    ///
+    /// If the channel is full, this either drops the oldest value or blocks,
+    /// depending on its `Overflow` policy. A value sent while there are no
+    /// subscribers is dropped.
+    pub fn send(&self, t: T) {
+        self.packet().send(t)
+    }
+
+    /// Creates a new subscriber, which will receive the values sent from now
+    /// on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let next = self.packet().subscribe(None);
+        Receiver::new(self.inner.clone(), next)
+    }
+}
+
+impl<T: Send + Clone> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.packet().senders.fetch_add(1, atomic::SeqCst);
+        Sender::new(self.inner.clone())
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send + Clone> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.packet().drop_chan();
+    }
+}
+
+impl<T: Send + Clone> Receiver<T> {
+    fn new(inner: Arc<UnsafeCell<Packet<T>>>, next: u64) -> Receiver<T> {
+        Receiver {
+            inner: inner,
+            next: Cell::new(next),
+            waiter: Cell::new(None),
+            marker: marker::NoSync,
+        }
+    }
+
+    fn packet<'a>(&'a self) -> &'a Packet<T> {
+        unsafe { &*self.inner.get() }
+    }
+
+    /// Blocks waiting for the next value on this receiver, skipping over any
+    /// values which were dropped before it could receive them.
+    ///
+    /// # Failure
+    ///
+    /// Fails if all of the senders have hung up and every value has been
+    /// received. Use `recv_opt` to handle that case instead.
+    pub fn recv(&self) -> T {
+        match self.recv_opt() {
+            Ok(t) => t,
+            Err(()) => fail!("receiving on a closed channel"),
+        }
+    }
+
+    /// Blocks waiting for the next value on this receiver, skipping over any
+    /// values which were dropped before it could receive them. Returns `Err`
+    /// if all of the senders have hung up and every value has been received.
+    pub fn recv_opt(&self) -> Result<T, ()> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(Disconnected) => return Err(()),
+                Err(Lagged(..)) => {}
+                Err(Empty) => select::block_on(self, None),
+            }
+        }
+    }
+
+    /// Attempts to return the next value on this receiver without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.packet().try_recv(&self.next)
+    }
+
+    /// Blocks waiting for the next value on this receiver for at most `ms`
+    /// milliseconds, returning `Err(Empty)` if the timeout elapses.
+    pub fn recv_timeout(&self, ms: u64) -> Result<T, TryRecvError> {
+        self.recv_deadline(time::now() + ms)
+    }
+
+    /// Blocks waiting for the next value on this receiver until the runtime's
+    /// clock reaches `deadline`. See `Receiver::recv_deadline` in `std::comm`.
+    pub fn recv_deadline(&self, deadline: u64) -> Result<T, TryRecvError> {
+        loop {
+            match self.try_recv() {
+                Err(Empty) => {}
+                ret => return ret,
+            }
+            let now = time::now();
+            if now >= deadline { return Err(Empty) }
+            select::block_on(self, Some(deadline - now));
+        }
+    }
+
+    /// Returns an iterator which will block waiting for messages, but never
+    /// `fail!`. It will return `None` when the channel has hung up.
+    pub fn iter<'a>(&'a self) -> Messages<'a, T> {
+        Messages { rx: self }
+    }
+}
+
+impl<T: Send + Clone> Clone for Receiver<T> {
+    /// Creates a new subscriber, which will receive the same values as this
+    /// one from now on.
+    fn clone(&self) -> Receiver<T> {
+        let next = self.packet().subscribe(Some(self.next.get()));
+        Receiver::new(self.inner.clone(), next)
+    }
+}
+
+impl<T: Send + Clone> select::Packet for Receiver<T> {
+    fn can_recv(&self) -> bool {
+        self.packet().can_recv(self.next.get())
+    }
+    fn start_selection(&self, task: BlockedTask) -> Result<(), BlockedTask> {
+        self.packet().start_selection(self.next.get(), &self.waiter, task)
+    }
+    fn abort_selection(&self) -> bool {
+        self.packet().abort_selection(self.next.get(), &self.waiter)
+    }
+}
+
+impl<T: Send + Clone> select::Selectable<T> for Receiver<T> {
+    fn recv_selected(&self) -> Result<T, ()> { self.recv_opt() }
+}
+
+impl<'a, T: Send + Clone> Iterator<T> for Messages<'a, T> {
+    fn next(&mut self) -> Option<T> { self.rx.recv_opt().ok() }
+}
+
+#[unsafe_destructor]
+impl<T: Send + Clone> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.packet().unsubscribe(self.next.get());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // The names of both this module and `comm` are glob imported into each
+    // test, so the ones they share are named through their modules.
+
+    test!(fn smoke() {
+        let (tx, rx1) = broadcast::channel(4, DropOldest);
+        let rx2 = rx1.clone();
+        tx.send(1i);
+        tx.send(2);
+        assert_eq!(rx1.recv(), 1);
+        assert_eq!(rx1.recv(), 2);
+        assert_eq!(rx1.try_recv(), Err(broadcast::Empty));
+        assert_eq!(rx2.recv(), 1);
+        drop(tx);
+        assert_eq!(rx2.recv(), 2);
+        assert_eq!(rx1.try_recv(), Err(broadcast::Disconnected));
+        assert_eq!(rx2.recv_opt(), Err(()));
+    })
+
+    test!(fn subscribe_sees_later_values() {
+        let (tx, rx1) = broadcast::channel(4, DropOldest);
+        tx.send(1i);
+        let rx2 = tx.subscribe();
+        tx.send(2);
+        assert_eq!(rx2.recv(), 2);
+        assert_eq!(rx1.recv(), 1);
+
+        // A clone starts where the original is
+        let rx3 = rx1.clone();
+        assert_eq!(rx3.recv(), 2);
+        assert_eq!(rx1.recv(), 2);
+    })
+
+    test!(fn no_subscribers() {
+        let (tx, rx) = broadcast::channel(1, Block);
+        drop(rx);
+        // Neither blocks nor fails
+        tx.send(1i);
+        tx.send(2i);
+        let rx = tx.subscribe();
+        tx.send(3);
+        assert_eq!(rx.recv(), 3);
+    })
+
+    test!(fn drop_oldest_lags() {
+        let (tx, rx) = broadcast::channel(2, DropOldest);
+        for i in range(0i, 5) { tx.send(i); }
+        assert_eq!(rx.try_recv(), Err(Lagged(3)));
+        assert_eq!(rx.recv(), 3);
+        assert_eq!(rx.recv(), 4);
+
+        // recv skips the lag
+        for i in range(5i, 10) { tx.send(i); }
+        assert_eq!(rx.recv(), 8);
+    })
+
+    test!(fn block_waits_for_slowest() {
+        let (tx, fast) = broadcast::channel(2, Block);
+        let slow = fast.clone();
+        let (done_tx, done_rx) = ::comm::channel();
+        spawn(proc() {
+            for i in range(0i, 10) { tx.send(i); }
+            done_tx.send(());
+        });
+        for i in range(0i, 2) { assert_eq!(fast.recv(), i); }
+        // The sender can't get more than two values ahead of `slow`
+        assert_eq!(done_rx.try_recv(), Err(::comm::Empty));
+        for i in range(0i, 10) { assert_eq!(slow.recv(), i); }
+        for i in range(2i, 10) { assert_eq!(fast.recv(), i); }
+        done_rx.recv();
+    })
+
+    test!(fn dropped_subscriber_unblocks_sender() {
+        let (tx, fast) = broadcast::channel(1, Block);
+        let slow = fast.clone();
+        let (done_tx, done_rx) = ::comm::channel();
+        spawn(proc() {
+            for i in range(0i, 3) { tx.send(i); }
+            done_tx.send(());
+        });
+        assert_eq!(fast.recv(), 0);
+        drop(slow);
+        assert_eq!(fast.recv(), 1);
+        assert_eq!(fast.recv(), 2);
+        done_rx.recv();
+    })
+
+    test!(fn values_are_dropped() {
+        use std::sync::Arc;
+
+        let (tx, rx1) = broadcast::channel(4, DropOldest);
+        let rx2 = rx1.clone();
+        let value = Arc::new(());
+        let weak = value.downgrade();
+        tx.send(value);
+        drop(rx1.recv());
+        assert!(weak.upgrade().is_some());
+        drop(rx2.recv());
+        assert!(weak.upgrade().is_none());
+
+        let value = Arc::new(());
+        let weak = value.downgrade();
+        tx.send(value);
+        drop(rx1);
+        assert!(weak.upgrade().is_some());
+        drop(rx2);
+        assert!(weak.upgrade().is_none());
+    })
+
+    test!(fn select() {
+        let (tx, rx1) = broadcast::channel(4, DropOldest);
+        let rx2 = rx1.clone();
+        let (_other_tx, other_rx) = ::comm::channel::<int>();
+
+        spawn(proc() { tx.send(7i); });
+        for rx in [rx1, rx2].iter() {
+            let s = Select::new();
+            let mut h1 = s.handle(&other_rx);
+            let mut h2 = s.handle(rx);
+            unsafe { h1.add(); h2.add(); }
+            assert_eq!(s.wait(), h2.id());
+            assert_eq!(h2.recv(), 7);
+        }
+    })
+}
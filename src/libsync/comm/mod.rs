@@ -43,6 +43,12 @@
 //!    "rendezvous" channel where each sender atomically hands off a message to
 //!    a receiver.
 //!
+//! Two more kinds of channels live in submodules. The `mpmc` module provides
+//! channels whose receivers can also be cloned, with each message going to
+//! exactly one of them, which is handy to fan work out to a pool of tasks. The
+//! `broadcast` module provides bounded channels where every receiver sees every
+//! message. Receivers of either kind can be used with `Select`.
+//!
 //! ## Failure Propagation
 //!
 //! In addition to being a core primitive for communicating in rust, channels
@@ -123,23 +129,22 @@
 //! rx.recv();
 //! ```
 //!
-//! Reading from a channel with a timeout requires to use a Timer together
-//! with the channel. You can use the select! macro to select either and
-//! handle the timeout case. This first example will break out of the loop
-//! after 10 seconds no matter what:
+//! Reading from a channel with a timeout is done with `recv_timeout`, or with
+//! `recv_deadline` when several operations share a deadline. This first
+//! example will break out of the loop after 10 seconds no matter what:
 //!
 //! ```no_run
-//! use std::io::timer::Timer;
-//! use std::time::Duration;
+//! use std::comm::Disconnected;
+//! use std::rt::time;
 //!
 //! let (tx, rx) = channel::<int>();
-//! let mut timer = Timer::new().unwrap();
-//! let timeout = timer.oneshot(Duration::seconds(10));
+//! let deadline = time::now() + 10000;
 //!
 //! loop {
-//!     select! {
-//!         val = rx.recv() => println!("Received {}", val),
-//!         () = timeout.recv() => {
+//!     match rx.recv_deadline(deadline) {
+//!         Ok(val) => println!("Received {}", val),
+//!         Err(Disconnected) => break,
+//!         Err(..) => {
 //!             println!("timed out, total time was more than 10 seconds")
 //!             break;
 //!         }
@@ -147,29 +152,25 @@
 //! }
 //! ```
 //!
-//! This second example is more costly since it allocates a new timer every
-//! time a message is received, but it allows you to timeout after the channel
-//! has been inactive for 5 seconds:
+//! This second example times out after the channel has been inactive for 5
+//! seconds:
 //!
 //! ```no_run
-//! use std::io::timer::Timer;
-//! use std::time::Duration;
-//!
 //! let (tx, rx) = channel::<int>();
-//! let mut timer = Timer::new().unwrap();
 //!
 //! loop {
-//!     let timeout = timer.oneshot(Duration::seconds(5));
-//!
-//!     select! {
-//!         val = rx.recv() => println!("Received {}", val),
-//!         () = timeout.recv() => {
-//!             println!("timed out, no message received in 5 seconds")
+//!     match rx.recv_timeout(5000) {
+//!         Ok(val) => println!("Received {}", val),
+//!         Err(..) => {
+//!             println!("no message received in 5 seconds, or hung up")
 //!             break;
 //!         }
 //!     }
 //! }
 //! ```
+//!
+//! To wait on several receivers at once with a timeout, use
+//! `Select::wait_timeout`.
 
 // A description of how Rust's channel implementation works
 //
@@ -331,8 +332,9 @@ use core::mem;
 use core::cell::UnsafeCell;
 use rustrt::local::Local;
 use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
 
-pub use comm::select::{Select, Handle};
+pub use comm::select::{Select, Handle, Selectable};
 pub use comm::duplex::{DuplexStream, duplex};
 
 macro_rules! test (
@@ -361,7 +363,9 @@ macro_rules! test (
     )
 )
 
+pub mod broadcast;
 mod duplex;
+pub mod mpmc;
 mod oneshot;
 mod select;
 mod shared;
@@ -948,6 +952,48 @@ impl<T: Send> Receiver<T> {
         }
     }
 
+    /// Blocks waiting for a value on this receiver for at most `ms`
+    /// milliseconds.
+    ///
+    /// This behaves like `try_recv`, except that it waits for data to arrive
+    /// before giving up: `Err(Empty)` is returned if the timeout elapses, and
+    /// `Err(Disconnected)` if the channel hangs up before a value arrives.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::comm::Empty;
+    ///
+    /// let (tx, rx) = channel::<int>();
+    /// assert_eq!(rx.recv_timeout(10), Err(Empty));
+    ///
+    /// tx.send(1);
+    /// assert_eq!(rx.recv_timeout(10), Ok(1));
+    /// ```
+    #[experimental]
+    pub fn recv_timeout(&self, ms: u64) -> Result<T, TryRecvError> {
+        self.recv_deadline(time::now() + ms)
+    }
+
+    /// Blocks waiting for a value on this receiver until the runtime's clock
+    /// reaches `deadline`.
+    ///
+    /// The deadline is an absolute value of the clock in `std::rt::time`, in
+    /// milliseconds, which makes it easy to share a single deadline between
+    /// several operations. Otherwise this is the same as `recv_timeout`.
+    #[experimental]
+    pub fn recv_deadline(&self, deadline: u64) -> Result<T, TryRecvError> {
+        loop {
+            match self.try_recv() {
+                Err(Empty) => {}
+                ret => return ret,
+            }
+            let now = time::now();
+            if now >= deadline { return Err(Empty) }
+            select::block_on(self, Some(deadline - now));
+        }
+    }
+
     /// Returns an iterator which will block waiting for messages, but never
     /// `fail!`. It will return `None` when the channel has hung up.
     #[unstable]
@@ -1043,6 +1089,10 @@ impl<T: Send> select::Packet for Receiver<T> {
     }
 }
 
+impl<T: Send> select::Selectable<T> for Receiver<T> {
+    fn recv_selected(&self) -> Result<T, ()> { self.recv_opt() }
+}
+
 #[unstable]
 impl<'a, T: Send> Iterator<T> for Messages<'a, T> {
     fn next(&mut self) -> Option<T> { self.rx.recv_opt().ok() }
@@ -1349,6 +1399,61 @@ mod test {
         assert_eq!(rx.try_recv(), Err(Empty));
     })
 
+    test!(fn recv_timeout_elapses() {
+        let (_tx, rx) = channel::<int>();
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        assert_eq!(rx.recv_timeout(0), Err(Empty));
+    })
+
+    test!(fn recv_timeout_data() {
+        let (tx, rx) = channel();
+        tx.send(1i);
+        assert_eq!(rx.recv_timeout(10000), Ok(1));
+
+        // And again, once the channel has been upgraded to a stream
+        tx.send(2);
+        tx.send(3);
+        assert_eq!(rx.recv_timeout(10000), Ok(2));
+        assert_eq!(rx.recv_timeout(10000), Ok(3));
+    })
+
+    test!(fn recv_timeout_woken_by_send() {
+        let (tx, rx) = channel();
+        let (tx2, rx2) = channel();
+        spawn(proc() {
+            rx2.recv();
+            tx.send(1i);
+        });
+        tx2.send(());
+        assert_eq!(rx.recv_timeout(10000), Ok(1));
+    })
+
+    test!(fn recv_timeout_disconnected() {
+        let (tx, rx) = channel::<int>();
+        spawn(proc() { drop(tx); });
+        assert_eq!(rx.recv_timeout(10000), Err(Disconnected));
+    })
+
+    test!(fn recv_timeout_then_recv() {
+        let (tx, rx) = channel();
+        let tx2 = tx.clone();
+        for _ in range(0u, 3) {
+            assert_eq!(rx.recv_timeout(1), Err(Empty));
+        }
+        spawn(proc() { tx.send(1i); tx2.send(2); });
+        assert_eq!(rx.recv(), 1);
+        assert_eq!(rx.recv(), 2);
+    })
+
+    test!(fn recv_deadline_passed() {
+        use rustrt::time;
+
+        let (tx, rx) = channel();
+        assert_eq!(rx.recv_deadline(time::now() - 1), Err(Empty));
+        tx.send(1i);
+        assert_eq!(rx.recv_deadline(0), Ok(1));
+    })
+
     test!(fn oneshot_multi_task_recv_then_send() {
         let (tx, rx) = channel::<Box<int>>();
         spawn(proc() {
@@ -1814,6 +1919,14 @@ mod sync_tests {
         assert_eq!(rx.try_recv(), Err(Empty));
     })
 
+    test!(fn recv_timeout() {
+        let (tx, rx) = sync_channel::<int>(0);
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        spawn(proc() { tx.send(1); });
+        assert_eq!(rx.recv_timeout(10000), Ok(1));
+        assert_eq!(rx.recv_timeout(10000), Err(Disconnected));
+    })
+
     test!(fn oneshot_multi_task_recv_then_send() {
         let (tx, rx) = sync_channel::<Box<int>>(0);
         spawn(proc() {
@@ -0,0 +1,531 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Multi-producer, multi-consumer channels
+//!
+//! Unlike the receiving half of `channel`, the `Receiver` of an MPMC channel
+//! can be cloned. Every value sent is received by exactly one of the
+//! receivers, so cloning the receiver is a way to fan work out to a number of
+//! tasks, each pulling values as fast as it can process them.
+//!
+//! As with `channel`, sends never block. The receivers can be added to a
+//! `Select` set like any other.
+//!
+//! # Example
+//!
+//! ```
+//! use std::comm::mpmc;
+//!
+//! let (tx, rx) = mpmc::channel();
+//! let (done_tx, done_rx) = channel();
+//!
+//! for _ in range(0u, 4) {
+//!     let rx = rx.clone();
+//!     let done_tx = done_tx.clone();
+//!     spawn(proc() {
+//!         let mut sum = 0u;
+//!         for n in rx.iter() { sum += n; }
+//!         done_tx.send(sum);
+//!     });
+//! }
+//! drop(rx);
+//!
+//! for n in range(0u, 100) { tx.send(n); }
+//! drop(tx);
+//!
+//! let total = range(0u, 4).fold(0, |a, _| a + done_rx.recv());
+//! assert_eq!(total, 4950);
+//! ```
+
+// Implementation-wise this is a mutex-protected buffer, in the same spirit as
+// the `sync` flavor of channels: the mutex is a native one and no user code
+// runs while it is held. Blocking receivers register in a list of waiters and
+// each send wakes up one of them, so that a receiver is never woken up for
+// nothing unless it loses a race to another receiver which wasn't blocked.
+//
+// Receivers which are selecting are waiters as well. One of them may be woken
+// up by a send and then pick another receiver of its set, so a selecting
+// receiver which was woken up passes the wakeup on to another waiter when it
+// aborts its selection.
+
+use core::prelude::*;
+
+use alloc::arc::Arc;
+use collections::{Deque, MutableSeq, RingBuf, Vec};
+use core::cell::{Cell, UnsafeCell};
+use core::kinds::marker;
+use core::mem;
+use rustrt::mutex::{NativeMutex, LockGuard};
+use rustrt::task::BlockedTask;
+use rustrt::time;
+
+use atomic;
+use comm::{TryRecvError, Empty, Disconnected};
+use comm::select;
+
+/// The sending half of an MPMC channel. It can be cloned to send from several
+/// tasks.
+pub struct Sender<T> {
+    inner: Arc<UnsafeCell<Packet<T>>>,
+    // can't share in an arc
+    marker: marker::NoSync,
+}
+
+/// The receiving half of an MPMC channel. It can be cloned to receive from
+/// several tasks, each value going to only one of the receivers.
+pub struct Receiver<T> {
+    inner: Arc<UnsafeCell<Packet<T>>>,
+    // The id of this receiver in the list of waiters, while it selects
+    waiter: Cell<Option<uint>>,
+    // can't share in an arc
+    marker: marker::NoSync,
+}
+
+/// An iterator over messages on an MPMC receiver, which blocks waiting for
+/// messages and returns `None` once all senders have hung up and the channel
+/// has been drained.
+pub struct Messages<'a, T:'a> {
+    rx: &'a Receiver<T>
+}
+
+struct Packet<T> {
+    senders: atomic::AtomicUint,
+    receivers: atomic::AtomicUint,
+
+    /// The state field is protected by this mutex
+    lock: NativeMutex,
+    state: UnsafeCell<State<T>>,
+}
+
+struct State<T> {
+    buf: RingBuf<T>,
+    disconnected: bool,  // Have all the senders gone away?
+    port_dropped: bool,  // Have all the receivers gone away?
+    waiters: Vec<(uint, BlockedTask)>,  // Receivers blocked on this channel
+    next_waiter: uint,
+}
+
+/// Creates a new MPMC channel, returning the sender/receiver halves.
+///
+/// Both halves can be cloned. All data sent on any of the senders will
+/// become available to one of the receivers, in the order it was sent.
+pub fn channel<T: Send>() -> (Sender<T>, Receiver<T>) {
+    let a = Arc::new(UnsafeCell::new(Packet {
+        senders: atomic::AtomicUint::new(1),
+        receivers: atomic::AtomicUint::new(1),
+        lock: unsafe { NativeMutex::new() },
+        state: UnsafeCell::new(State {
+            buf: RingBuf::new(),
+            disconnected: false,
+            port_dropped: false,
+            waiters: Vec::new(),
+            next_waiter: 0,
+        }),
+    }));
+    (Sender::new(a.clone()), Receiver::new(a))
+}
+
+/// Wakes up a task, dropping the lock at the correct time
+fn wakeup(task: Option<BlockedTask>, guard: LockGuard) {
+    // We need to be careful to wake up the waiting task *outside* of the mutex
+    // in case it incurs a context switch.
+    mem::drop(guard);
+    task.map(|t| t.wake().map(|t| t.reawaken()));
+}
+
+impl<T: Send> Packet<T> {
+    // Locks this channel, returning a guard for the state and the mutable state
+    // itself. See the `sync` flavor for why this is ok.
+    fn lock<'a>(&'a self) -> (LockGuard<'a>, &'a mut State<T>) {
+        unsafe {
+            let guard = self.lock.lock();
+            (guard, &mut *self.state.get())
+        }
+    }
+
+    fn send(&self, t: T) -> Result<(), T> {
+        let (guard, state) = self.lock();
+        if state.port_dropped { return Err(t) }
+        state.buf.push(t);
+        let task = if state.waiters.is_empty() {
+            None
+        } else {
+            state.waiters.remove(0).map(|(_, task)| task)
+        };
+        wakeup(task, guard);
+        Ok(())
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let (_g, state) = self.lock();
+        match state.buf.pop_front() {
+            Some(t) => Ok(t),
+            None if state.disconnected => Err(Disconnected),
+            None => Err(Empty),
+        }
+    }
+
+    fn drop_chan(&self) {
+        // Only flag the channel as disconnected if we're the last channel
+        match self.senders.fetch_sub(1, atomic::SeqCst) {
+            1 => {}
+            _ => return
+        }
+
+        let (guard, state) = self.lock();
+        state.disconnected = true;
+        let waiters = mem::replace(&mut state.waiters, Vec::new());
+        mem::drop((state, guard));
+        for (_, task) in waiters.move_iter() {
+            task.wake().map(|t| t.reawaken());
+        }
+    }
+
+    fn drop_port(&self) {
+        match self.receivers.fetch_sub(1, atomic::SeqCst) {
+            1 => {}
+            _ => return
+        }
+
+        // The buffered data is ours to destroy, outside of the lock
+        let (guard, state) = self.lock();
+        state.port_dropped = true;
+        let _data = mem::replace(&mut state.buf, RingBuf::new());
+        mem::drop((state, guard));
+    }
+
+    fn can_recv(&self) -> bool {
+        let (_g, state) = self.lock();
+        state.disconnected || state.buf.len() > 0
+    }
+
+    fn start_selection(&self, waiter: &Cell<Option<uint>>,
+                       task: BlockedTask) -> Result<(), BlockedTask> {
+        let (_g, state) = self.lock();
+        if state.disconnected || state.buf.len() > 0 {
+            return Err(task)
+        }
+        let id = state.next_waiter;
+        state.next_waiter += 1;
+        state.waiters.push((id, task));
+        waiter.set(Some(id));
+        Ok(())
+    }
+
+    fn abort_selection(&self, waiter: &Cell<Option<uint>>) -> bool {
+        let (guard, state) = self.lock();
+        let id = waiter.get().unwrap();
+        waiter.set(None);
+        let ready = state.disconnected || state.buf.len() > 0;
+        match state.waiters.iter().position(|&(i, _)| i == id) {
+            Some(pos) => {
+                let (_, task) = state.waiters.remove(pos).unwrap();
+                task.trash();
+                ready
+            }
+            // A sender woke us up, but we may be about to receive from
+            // another channel of our selection. Pass the wakeup on, so that
+            // the data isn't left behind while other receivers sleep.
+            None => {
+                let task = if state.buf.len() > 0 && !state.waiters.is_empty() {
+                    state.waiters.remove(0).map(|(_, task)| task)
+                } else {
+                    None
+                };
+                wakeup(task, guard);
+                ready
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for Packet<T> {
+    fn drop(&mut self) {
+        assert_eq!(self.senders.load(atomic::SeqCst), 0);
+        assert_eq!(self.receivers.load(atomic::SeqCst), 0);
+        let (_g, state) = self.lock();
+        assert!(state.waiters.is_empty());
+    }
+}
+
+impl<T: Send> Sender<T> {
+    fn new(inner: Arc<UnsafeCell<Packet<T>>>) -> Sender<T> {
+        Sender { inner: inner, marker: marker::NoSync }
+    }
+
+    fn packet<'a>(&'a self) -> &'a Packet<T> {
+        unsafe { &*self.inner.get() }
+    }
+
+    /// Sends a value on this channel, to be received by one of its receivers.
+    /// This never blocks.
+    ///
+    /// # Failure
+    ///
+    /// Fails if all of the receivers have hung up. Use `send_opt` to handle
+    /// that case instead.
+    pub fn send(&self, t: T) {
+        if self.send_opt(t).is_err() {
+            fail!("sending on a closed channel");
+        }
+    }
+
+    /// Attempts to send a value on this channel, returning it back if all of
+    /// the receivers have hung up.
+    pub fn send_opt(&self, t: T) -> Result<(), T> {
+        self.packet().send(t)
+    }
+}
+
+impl<T: Send> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.packet().senders.fetch_add(1, atomic::SeqCst);
+        Sender::new(self.inner.clone())
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.packet().drop_chan();
+    }
+}
+
+impl<T: Send> Receiver<T> {
+    fn new(inner: Arc<UnsafeCell<Packet<T>>>) -> Receiver<T> {
+        Receiver { inner: inner, waiter: Cell::new(None), marker: marker::NoSync }
+    }
+
+    fn packet<'a>(&'a self) -> &'a Packet<T> {
+        unsafe { &*self.inner.get() }
+    }
+
+    /// Blocks waiting for a value on this receiver.
+    ///
+    /// # Failure
+    ///
+    /// Fails if all of the senders have hung up and no data is left. Use
+    /// `recv_opt` to handle that case instead.
+    pub fn recv(&self) -> T {
+        match self.recv_opt() {
+            Ok(t) => t,
+            Err(()) => fail!("receiving on a closed channel"),
+        }
+    }
+
+    /// Blocks waiting for a value on this receiver, returning `Err` if all of
+    /// the senders have hung up and no data is left.
+    pub fn recv_opt(&self) -> Result<T, ()> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(Disconnected) => return Err(()),
+                Err(Empty) => select::block_on(self, None),
+            }
+        }
+    }
+
+    /// Attempts to return a pending value on this receiver without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.packet().try_recv()
+    }
+
+    /// Blocks waiting for a value on this receiver for at most `ms`
+    /// milliseconds. See `Receiver::recv_timeout` in `std::comm`.
+    pub fn recv_timeout(&self, ms: u64) -> Result<T, TryRecvError> {
+        self.recv_deadline(time::now() + ms)
+    }
+
+    /// Blocks waiting for a value on this receiver until the runtime's clock
+    /// reaches `deadline`. See `Receiver::recv_deadline` in `std::comm`.
+    pub fn recv_deadline(&self, deadline: u64) -> Result<T, TryRecvError> {
+        loop {
+            match self.try_recv() {
+                Err(Empty) => {}
+                ret => return ret,
+            }
+            let now = time::now();
+            if now >= deadline { return Err(Empty) }
+            select::block_on(self, Some(deadline - now));
+        }
+    }
+
+    /// Returns an iterator which will block waiting for messages, but never
+    /// `fail!`. It will return `None` when the channel has hung up.
+    pub fn iter<'a>(&'a self) -> Messages<'a, T> {
+        Messages { rx: self }
+    }
+}
+
+impl<T: Send> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        self.packet().receivers.fetch_add(1, atomic::SeqCst);
+        Receiver::new(self.inner.clone())
+    }
+}
+
+impl<T: Send> select::Packet for Receiver<T> {
+    fn can_recv(&self) -> bool {
+        self.packet().can_recv()
+    }
+    fn start_selection(&self, task: BlockedTask) -> Result<(), BlockedTask> {
+        self.packet().start_selection(&self.waiter, task)
+    }
+    fn abort_selection(&self) -> bool {
+        self.packet().abort_selection(&self.waiter)
+    }
+}
+
+impl<T: Send> select::Selectable<T> for Receiver<T> {
+    fn recv_selected(&self) -> Result<T, ()> { self.recv_opt() }
+}
+
+impl<'a, T: Send> Iterator<T> for Messages<'a, T> {
+    fn next(&mut self) -> Option<T> { self.rx.recv_opt().ok() }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.packet().drop_port();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // The names of both this module and `comm` are glob imported into each
+    // test, so channels are named through their modules.
+
+    test!(fn smoke() {
+        let (tx, rx) = mpmc::channel();
+        tx.send(1i);
+        assert_eq!(rx.recv(), 1);
+        assert_eq!(rx.try_recv(), Err(Empty));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(Disconnected));
+        assert_eq!(rx.recv_opt(), Err(()));
+    })
+
+    test!(fn send_to_closed() {
+        let (tx, rx) = mpmc::channel();
+        let rx2 = rx.clone();
+        drop(rx);
+        assert_eq!(tx.send_opt(1i), Ok(()));
+        drop(rx2);
+        assert_eq!(tx.send_opt(2i), Err(2));
+    })
+
+    test!(fn fan_out() {
+        let (tx, rx) = mpmc::channel();
+        let (done_tx, done_rx) = ::comm::channel();
+        for _ in range(0u, 4) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            spawn(proc() {
+                let mut received = Vec::new();
+                for n in rx.iter() { received.push(n); }
+                done_tx.send(received);
+            });
+        }
+        drop(rx);
+
+        for i in range(0u, 1000) { tx.send(i); }
+        drop(tx);
+
+        let mut all: Vec<uint> = Vec::new();
+        for _ in range(0u, 4) {
+            all.push_all(done_rx.recv().as_slice());
+        }
+        all.sort();
+        assert_eq!(all, range(0u, 1000).collect());
+    })
+
+    test!(fn many_senders() {
+        let (tx, rx) = mpmc::channel();
+        for i in range(0u, 10) {
+            let tx = tx.clone();
+            spawn(proc() {
+                for _ in range(0u, 100) { tx.send(i); }
+            });
+        }
+        drop(tx);
+        let rx2 = rx.clone();
+        let total = rx.iter().take(500).fold(0, |a, b| a + b) +
+                    rx2.iter().fold(0, |a, b| a + b);
+        assert_eq!(total, 4500);
+    })
+
+    test!(fn blocked_receivers_all_wake_on_hangup() {
+        let (tx, rx) = mpmc::channel::<int>();
+        let (done_tx, done_rx) = ::comm::channel();
+        for _ in range(0u, 3) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            spawn(proc() {
+                assert_eq!(rx.recv_opt(), Err(()));
+                done_tx.send(());
+            });
+        }
+        drop(tx);
+        for _ in range(0u, 3) { done_rx.recv(); }
+    })
+
+    test!(fn recv_timeout() {
+        let (tx, rx) = mpmc::channel::<int>();
+        assert_eq!(rx.recv_timeout(10), Err(Empty));
+        tx.send(1);
+        assert_eq!(rx.recv_timeout(10), Ok(1));
+    })
+
+    test!(fn select() {
+        let (tx1, rx1) = mpmc::channel::<int>();
+        let (tx2, rx2) = ::comm::channel::<int>();
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h2 = s.handle(&rx2);
+        unsafe { h1.add(); h2.add(); }
+
+        spawn(proc() { tx1.send(1); });
+        assert_eq!(s.wait(), h1.id());
+        assert_eq!(h1.recv(), 1);
+
+        spawn(proc() { tx2.send(2); });
+        assert_eq!(s.wait(), h2.id());
+        assert_eq!(h2.recv(), 2);
+    })
+
+    test!(fn select_passes_wakeup_on() {
+        // Two receivers selecting over the same channel and their own
+        // channels, and a single value: whoever is woken up and doesn't take
+        // it must not leave it stranded.
+        let (tx, rx) = mpmc::channel::<int>();
+        let (done_tx, done_rx) = ::comm::channel();
+        let mut others = Vec::new();
+        for _ in range(0u, 2) {
+            let rx = rx.clone();
+            let done_tx = done_tx.clone();
+            let (other_tx, other_rx) = ::comm::channel::<()>();
+            others.push(other_tx);
+            spawn(proc() {
+                let s = Select::new();
+                let mut h1 = s.handle(&other_rx);
+                let mut h2 = s.handle(&rx);
+                unsafe { h1.add(); h2.add(); }
+                s.wait();
+                done_tx.send(());
+            });
+        }
+        tx.send(1);
+        done_rx.recv();
+        for other in others.iter() { let _ = other.send_opt(()); }
+        done_rx.recv();
+    })
+}
@@ -61,6 +61,7 @@ use core::mem;
 use core::uint;
 use rustrt::local::Local;
 use rustrt::task::{Task, BlockedTask};
+use rustrt::time;
 
 use comm::Receiver;
 
@@ -88,7 +89,7 @@ pub struct Handle<'rx, T:'rx> {
 
     // due to our fun transmutes, we be sure to place this at the end. (nothing
     // previous relies on T)
-    rx: &'rx Receiver<T>,
+    rx: &'rx Selectable<T>+'rx,
 }
 
 struct Packets { cur: *mut Handle<'static, ()> }
@@ -100,6 +101,42 @@ pub trait Packet {
     fn abort_selection(&self) -> bool;
 }
 
+/// The receiving half of a channel which can be added to a `Select` set.
+///
+/// This is implemented by `Receiver` as well as by the receivers of the
+/// `mpmc` and `broadcast` channels.
+pub trait Selectable<T>: Packet {
+    /// Blocks waiting for a value, returning `Err` if the channel has hung up.
+    /// This is what `Handle::recv_opt` calls once the receiver is selected.
+    fn recv_selected(&self) -> Result<T, ()>;
+}
+
+/// Blocks the current task until `packet` may be ready to receive, as a
+/// `Select` over that packet alone would, giving up after `timeout`
+/// milliseconds if there is one.
+///
+/// Nothing is promised about the packet once this returns: the task may have
+/// been woken up by the timeout, or another receiver of a multi-consumer
+/// channel may already have taken the data. Callers loop around this.
+pub fn block_on(packet: &Packet, timeout: Option<u64>) {
+    let mut started = false;
+    let task: Box<Task> = Local::take();
+    {
+        let block = |task| {
+            let ret = packet.start_selection(task);
+            started = ret.is_ok();
+            ret
+        };
+        match timeout {
+            Some(ms) => task.deschedule_timeout(1, ms, block),
+            None => task.deschedule(1, block),
+        }
+    }
+    if started {
+        packet.abort_selection();
+    }
+}
+
 impl Select {
     /// Creates a new selection structure. This set is initially empty and
     /// `wait` will fail!() if called.
@@ -118,7 +155,7 @@ impl Select {
     /// Creates a new handle into this receiver set for a new receiver. Note
     /// that this does *not* add the receiver to the receiver set, for that you
     /// must call the `add` method on the handle itself.
-    pub fn handle<'a, T: Send>(&'a self, rx: &'a Receiver<T>) -> Handle<'a, T> {
+    pub fn handle<'a, T: Send, R: 'a + Selectable<T>>(&'a self, rx: &'a R) -> Handle<'a, T> {
         let id = self.next_id.get();
         self.next_id.set(id + 1);
         Handle {
@@ -142,8 +179,21 @@ impl Select {
         self.wait2(true)
     }
 
+    /// Waits for an event on this receiver set for at most `ms` milliseconds.
+    ///
+    /// This is the same as `wait`, except that `None` is returned if the
+    /// timeout elapses before any of the receivers has an event available.
+    pub fn wait_timeout(&self, ms: u64) -> Option<uint> {
+        self.wait_deadline(true, Some(time::now() + ms))
+    }
+
     /// Helper method for skipping the preflight checks during testing
     fn wait2(&self, do_preflight_checks: bool) -> uint {
+        self.wait_deadline(do_preflight_checks, None).unwrap()
+    }
+
+    fn wait_deadline(&self, do_preflight_checks: bool,
+                     deadline: Option<u64>) -> Option<uint> {
         // Note that this is currently an inefficient implementation. We in
         // theory have knowledge about all receivers in the set ahead of time,
         // so this method shouldn't really have to iterate over all of them yet
@@ -164,66 +214,87 @@ impl Select {
         //
         // Most notably, the iterations over all of the receivers shouldn't be
         // necessary.
+        //
+        // With a deadline, the task may also be woken up by the runtime with
+        // no receiver ready, in which case the whole process starts over
+        // unless the deadline has passed.
         unsafe {
-            let mut amt = 0;
-            for p in self.iter() {
-                amt += 1;
-                if do_preflight_checks && (*p).packet.can_recv() {
-                    return (*p).id;
+            loop {
+                let mut amt = 0;
+                for p in self.iter() {
+                    amt += 1;
+                    if do_preflight_checks && (*p).packet.can_recv() {
+                        return Some((*p).id);
+                    }
                 }
-            }
-            assert!(amt > 0);
-
-            let mut ready_index = amt;
-            let mut ready_id = uint::MAX;
-            let mut iter = self.iter().enumerate();
-
-            // Acquire a number of blocking contexts, and block on each one
-            // sequentially until one fails. If one fails, then abort
-            // immediately so we can go unblock on all the other receivers.
-            let task: Box<Task> = Local::take();
-            task.deschedule(amt, |task| {
-                // Prepare for the block
-                let (i, handle) = iter.next().unwrap();
-                match (*handle).packet.start_selection(task) {
-                    Ok(()) => Ok(()),
-                    Err(task) => {
-                        ready_index = i;
-                        ready_id = (*handle).id;
-                        Err(task)
+                assert!(amt > 0);
+
+                let timeout = match deadline {
+                    Some(deadline) => {
+                        let now = time::now();
+                        if now >= deadline { return None }
+                        Some(deadline - now)
+                    }
+                    None => None,
+                };
+
+                let mut ready_index = amt;
+                let mut ready_id = uint::MAX;
+                let mut iter = self.iter().enumerate();
+
+                // Acquire a number of blocking contexts, and block on each one
+                // sequentially until one fails. If one fails, then abort
+                // immediately so we can go unblock on all the other receivers.
+                let task: Box<Task> = Local::take();
+                {
+                    let block = |task| {
+                        // Prepare for the block
+                        let (i, handle) = iter.next().unwrap();
+                        match (*handle).packet.start_selection(task) {
+                            Ok(()) => Ok(()),
+                            Err(task) => {
+                                ready_index = i;
+                                ready_id = (*handle).id;
+                                Err(task)
+                            }
+                        }
+                    };
+                    match timeout {
+                        Some(ms) => task.deschedule_timeout(amt, ms, block),
+                        None => task.deschedule(amt, block),
                     }
                 }
-            });
-
-            // Abort the selection process on each receiver. If the abort
-            // process returns `true`, then that means that the receiver is
-            // ready to receive some data. Note that this also means that the
-            // receiver may have yet to have fully read the `to_wake` field and
-            // woken us up (although the wakeup is guaranteed to fail).
-            //
-            // This situation happens in the window of where a sender invokes
-            // increment(), sees -1, and then decides to wake up the task. After
-            // all this is done, the sending thread will set `selecting` to
-            // `false`. Until this is done, we cannot return. If we were to
-            // return, then a sender could wake up a receiver which has gone
-            // back to sleep after this call to `select`.
-            //
-            // Note that it is a "fairly small window" in which an increment()
-            // views that it should wake a thread up until the `selecting` bit
-            // is set to false. For now, the implementation currently just spins
-            // in a yield loop. This is very distasteful, but this
-            // implementation is already nowhere near what it should ideally be.
-            // A rewrite should focus on avoiding a yield loop, and for now this
-            // implementation is tying us over to a more efficient "don't
-            // iterate over everything every time" implementation.
-            for handle in self.iter().take(ready_index) {
-                if (*handle).packet.abort_selection() {
-                    ready_id = (*handle).id;
+
+                // Abort the selection process on each receiver. If the abort
+                // process returns `true`, then that means that the receiver is
+                // ready to receive some data. Note that this also means that the
+                // receiver may have yet to have fully read the `to_wake` field and
+                // woken us up (although the wakeup is guaranteed to fail).
+                //
+                // This situation happens in the window of where a sender invokes
+                // increment(), sees -1, and then decides to wake up the task. After
+                // all this is done, the sending thread will set `selecting` to
+                // `false`. Until this is done, we cannot return. If we were to
+                // return, then a sender could wake up a receiver which has gone
+                // back to sleep after this call to `select`.
+                //
+                // Note that it is a "fairly small window" in which an increment()
+                // views that it should wake a thread up until the `selecting` bit
+                // is set to false. For now, the implementation currently just spins
+                // in a yield loop. This is very distasteful, but this
+                // implementation is already nowhere near what it should ideally be.
+                // A rewrite should focus on avoiding a yield loop, and for now this
+                // implementation is tying us over to a more efficient "don't
+                // iterate over everything every time" implementation.
+                for handle in self.iter().take(ready_index) {
+                    if (*handle).packet.abort_selection() {
+                        ready_id = (*handle).id;
+                    }
                 }
-            }
 
-            assert!(ready_id != uint::MAX);
-            return ready_id;
+                if ready_id != uint::MAX { return Some(ready_id) }
+                assert!(deadline.is_some());
+            }
         }
     }
 
@@ -237,11 +308,16 @@ impl<'rx, T: Send> Handle<'rx, T> {
 
     /// Receive a value on the underlying receiver. Has the same semantics as
     /// `Receiver.recv`
-    pub fn recv(&mut self) -> T { self.rx.recv() }
+    pub fn recv(&mut self) -> T {
+        match self.rx.recv_selected() {
+            Ok(t) => t,
+            Err(()) => fail!("receiving on a closed channel"),
+        }
+    }
     /// Block to receive a value on the underlying receiver, returning `Some` on
     /// success or `None` if the channel disconnects. This function has the same
     /// semantics as `Receiver.recv_opt`
-    pub fn recv_opt(&mut self) -> Result<T, ()> { self.rx.recv_opt() }
+    pub fn recv_opt(&mut self) -> Result<T, ()> { self.rx.recv_selected() }
 
     /// Adds this handle to the receiver set that the handle was created from. This
     /// method can be called multiple times, but it has no effect if `add` was
@@ -560,6 +636,20 @@ mod test {
         )
     })
 
+    test!(fn wait_timeout() {
+        let (tx1, rx1) = channel::<int>();
+        let (_tx2, rx2) = channel::<int>();
+        let s = Select::new();
+        let mut h1 = s.handle(&rx1);
+        let mut h2 = s.handle(&rx2);
+        unsafe { h1.add(); h2.add(); }
+        assert_eq!(s.wait_timeout(10), None);
+
+        spawn(proc() { tx1.send(1); });
+        assert_eq!(s.wait_timeout(10000), Some(h1.id()));
+        assert_eq!(h1.recv(), 1);
+    })
+
     test!(fn preflight4() {
         let (tx, rx) = channel();
         tx.send(());
@@ -0,0 +1,497 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A hash map which many tasks can read and write at once.
+//!
+//! The table is split into a fixed number of shards, each a small chained
+//! hash table behind its own reader-writer lock. A key's hash picks its
+//! shard, so tasks working on different keys rarely wait for each other and
+//! readers of the same shard proceed in parallel. Shards grow independently,
+//! so a resize only ever stalls the keys of one shard.
+//!
+//! Since no reference into the map may outlive a lock, lookups either clone
+//! the value out (`get`) or run a closure on it while the shard is locked
+//! (`get_with`, `compute`). Such closures must not use the map themselves,
+//! or they may deadlock on the shard they were called from.
+//!
+//! The map is meant to be shared with `Arc`:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::sync::concurrent_hashmap::ConcurrentHashMap;
+//!
+//! let words = Arc::new(ConcurrentHashMap::new());
+//! let (tx, rx) = channel();
+//!
+//! for line in ["a b", "b c", "c a b"].iter() {
+//!     let (words, tx) = (words.clone(), tx.clone());
+//!     spawn(proc() {
+//!         for word in line.words() {
+//!             words.compute(word, |_, n| Some(n.unwrap_or(0u) + 1));
+//!         }
+//!         tx.send(());
+//!     });
+//! }
+//! for _ in range(0u, 3) { rx.recv(); }
+//!
+//! assert_eq!(words.get(&"b"), Some(3));
+//! ```
+
+use core::prelude::*;
+
+use collections::{Vec, MutableSeq};
+use collections::hash::{Hash, Hasher};
+use collections::hash::sip::SipHasher;
+use collections::vec;
+use core::mem;
+use core::num::next_power_of_two;
+
+use lock::RWLock;
+
+static DEFAULT_SHARDS: uint = 32;
+static INITIAL_BUCKETS: uint = 8;
+
+// One independently locked part of the table. Collisions are chained in
+// per-bucket vectors, each entry keeping its full hash so that growing the
+// shard and most failed comparisons never touch the key.
+struct Shard<K, V> {
+    buckets: Vec<Vec<(u64, K, V)>>,
+    len: uint,
+}
+
+impl<K: Eq, V> Shard<K, V> {
+    fn new() -> Shard<K, V> {
+        Shard { buckets: Vec::from_fn(INITIAL_BUCKETS, |_| Vec::new()), len: 0 }
+    }
+
+    #[inline]
+    fn bucket(&self, hash: u64) -> uint {
+        hash as uint & (self.buckets.len() - 1)
+    }
+
+    fn position(&self, hash: u64, k: &K) -> Option<(uint, uint)> {
+        let b = self.bucket(hash);
+        for (i, &(h, ref key, _)) in self.buckets[b].iter().enumerate() {
+            if h == hash && *key == *k {
+                return Some((b, i));
+            }
+        }
+        None
+    }
+
+    fn find<'a>(&'a self, hash: u64, k: &K) -> Option<&'a V> {
+        match self.position(hash, k) {
+            Some((b, i)) => match self.buckets[b][i] {
+                (_, _, ref value) => Some(value)
+            },
+            None => None
+        }
+    }
+
+    fn insert(&mut self, hash: u64, k: K, v: V) -> Option<V> {
+        match self.position(hash, &k) {
+            Some((b, i)) => match *self.buckets.get_mut(b).get_mut(i) {
+                (_, _, ref mut value) => return Some(mem::replace(value, v))
+            },
+            None => {}
+        }
+        if self.len >= self.buckets.len() {
+            self.grow();
+        }
+        let b = self.bucket(hash);
+        self.buckets.get_mut(b).push((hash, k, v));
+        self.len += 1;
+        None
+    }
+
+    fn remove(&mut self, hash: u64, k: &K) -> Option<V> {
+        match self.position(hash, k) {
+            Some((b, i)) => {
+                self.len -= 1;
+                self.buckets.get_mut(b).swap_remove(i).map(|(_, _, v)| v)
+            }
+            None => None
+        }
+    }
+
+    fn grow(&mut self) {
+        let new_len = self.buckets.len() * 2;
+        let old = mem::replace(&mut self.buckets, Vec::from_fn(new_len, |_| Vec::new()));
+        for bucket in old.move_iter() {
+            for (hash, k, v) in bucket.move_iter() {
+                let b = self.bucket(hash);
+                self.buckets.get_mut(b).push((hash, k, v));
+            }
+        }
+    }
+}
+
+/// A concurrent hash map, split into shards which are locked independently.
+pub struct ConcurrentHashMap<K, V, H = SipHasher> {
+    hasher: H,
+    shards: Vec<RWLock<Shard<K, V>>>,
+}
+
+impl<K: Eq + Hash + Send + Sync, V: Send + Sync> ConcurrentHashMap<K, V, SipHasher> {
+    /// Creates an empty map with the default number of shards.
+    ///
+    /// The default hasher uses fixed keys, as this crate has no source of
+    /// randomness; maps keyed by untrusted input should be created with
+    /// `with_concurrency_and_hasher` and a randomly keyed hasher instead.
+    pub fn new() -> ConcurrentHashMap<K, V, SipHasher> {
+        ConcurrentHashMap::with_concurrency(DEFAULT_SHARDS)
+    }
+
+    /// Creates an empty map split into at least `shards` shards, which
+    /// bounds how many tasks can write to it at once without waiting.
+    pub fn with_concurrency(shards: uint) -> ConcurrentHashMap<K, V, SipHasher> {
+        ConcurrentHashMap::with_concurrency_and_hasher(shards, SipHasher::new())
+    }
+}
+
+impl<K: Eq + Hash<S> + Send + Sync, V: Send + Sync, S, H: Hasher<S>>
+        ConcurrentHashMap<K, V, H> {
+    /// Creates an empty map split into at least `shards` shards, which
+    /// hashes its keys with `hasher`.
+    pub fn with_concurrency_and_hasher(shards: uint, hasher: H)
+                                       -> ConcurrentHashMap<K, V, H> {
+        let shards = next_power_of_two(if shards == 0 { 1 } else { shards });
+        ConcurrentHashMap {
+            hasher: hasher,
+            shards: Vec::from_fn(shards, |_| RWLock::new(Shard::new())),
+        }
+    }
+
+    // The low bits of a hash choose a bucket within a shard, so the shard
+    // is chosen from the high ones
+    #[inline]
+    fn shard<'a>(&'a self, hash: u64) -> &'a RWLock<Shard<K, V>> {
+        &self.shards[(hash >> 32) as uint & (self.shards.len() - 1)]
+    }
+
+    /// Looks up `k` and, if it is present, returns the result of applying
+    /// `f` to its value. `f` runs with the key's shard read-locked.
+    pub fn get_with<T>(&self, k: &K, f: |&V| -> T) -> Option<T> {
+        let hash = self.hasher.hash(k);
+        let shard = self.shard(hash).read();
+        shard.find(hash, k).map(f)
+    }
+
+    /// Returns true if the map contains a value for `k`.
+    pub fn contains_key(&self, k: &K) -> bool {
+        let hash = self.hasher.hash(k);
+        self.shard(hash).read().find(hash, k).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the value previously stored
+    /// under the key, if any.
+    pub fn insert(&self, k: K, v: V) -> Option<V> {
+        let hash = self.hasher.hash(&k);
+        self.shard(hash).write().insert(hash, k, v)
+    }
+
+    /// Removes `k` from the map, returning its value if it was present.
+    pub fn remove(&self, k: &K) -> Option<V> {
+        let hash = self.hasher.hash(k);
+        self.shard(hash).write().remove(hash, k)
+    }
+
+    /// Atomically replaces the value stored under `k` with the result of
+    /// `f`, which is given the current value, if any. Returning `None`
+    /// removes the key. No other task can observe or modify the key while
+    /// `f` runs, as its shard is write-locked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::concurrent_hashmap::ConcurrentHashMap;
+    ///
+    /// let counts = ConcurrentHashMap::new();
+    /// for word in "a b a c a".words() {
+    ///     counts.compute(word, |_, n| Some(n.unwrap_or(0u) + 1));
+    /// }
+    /// assert_eq!(counts.get(&"a"), Some(3));
+    ///
+    /// counts.compute("a", |_, _| None);
+    /// assert!(!counts.contains_key(&"a"));
+    /// ```
+    pub fn compute(&self, k: K, f: |&K, Option<V>| -> Option<V>) {
+        let hash = self.hasher.hash(&k);
+        let mut shard = self.shard(hash).write();
+        let old = shard.remove(hash, &k);
+        match f(&k, old) {
+            Some(v) => { shard.insert(hash, k, v); }
+            None => {}
+        }
+    }
+
+    /// Returns the number of entries. Shards are counted one at a time, so
+    /// under concurrent modification the result is only an estimate.
+    pub fn len(&self) -> uint {
+        self.shards.iter().fold(0, |n, shard| n + shard.read().len)
+    }
+
+    /// Returns true if the map holds no entries, with the same caveat as
+    /// `len`.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.read().len == 0)
+    }
+
+    /// Removes every entry, one shard at a time.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            *shard.write() = Shard::new();
+        }
+    }
+}
+
+impl<K: Eq + Hash<S> + Send + Sync, V: Clone + Send + Sync, S, H: Hasher<S>>
+        ConcurrentHashMap<K, V, H> {
+    /// Returns a copy of the value stored under `k`.
+    pub fn get(&self, k: &K) -> Option<V> {
+        self.get_with(k, |v| v.clone())
+    }
+}
+
+impl<K: Eq + Clone + Send + Sync, V: Clone + Send + Sync, H> ConcurrentHashMap<K, V, H> {
+    /// Returns an iterator over copies of the entries, in no particular
+    /// order.
+    ///
+    /// The iterator copies out one shard at a time under its read lock and
+    /// holds no lock between calls to `next`, so the map may be modified,
+    /// even from the loop body, while it is being iterated. Each entry is
+    /// seen at most once; entries inserted or removed during the iteration
+    /// may or may not be seen.
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V, H> {
+        Entries { map: self, shard: 0, buffer: Vec::new().move_iter() }
+    }
+}
+
+/// An iterator over copies of the entries of a `ConcurrentHashMap`.
+pub struct Entries<'a, K:'a, V:'a, H:'a> {
+    map: &'a ConcurrentHashMap<K, V, H>,
+    shard: uint,
+    buffer: vec::MoveItems<(K, V)>,
+}
+
+impl<'a, K: Eq + Clone + Send + Sync, V: Clone + Send + Sync, H>
+        Iterator<(K, V)> for Entries<'a, K, V, H> {
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            match self.buffer.next() {
+                Some(entry) => return Some(entry),
+                None => {}
+            }
+            if self.shard == self.map.shards.len() {
+                return None;
+            }
+            let shard = self.map.shards[self.shard].read();
+            self.shard += 1;
+            let mut entries = Vec::with_capacity(shard.len);
+            for bucket in shard.buckets.iter() {
+                for &(_, ref k, ref v) in bucket.iter() {
+                    entries.push((k.clone(), v.clone()));
+                }
+            }
+            self.buffer = entries.move_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::*;
+    use native;
+
+    use Arc;
+    use super::ConcurrentHashMap;
+
+    #[test]
+    fn smoke() {
+        let map = ConcurrentHashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(1i, 10i), None);
+        assert_eq!(map.insert(2, 20), None);
+        assert_eq!(map.insert(1, 11), Some(10));
+        assert_eq!(map.get(&1), Some(11));
+        assert_eq!(map.get_with(&2, |v| *v * 2), Some(40));
+        assert_eq!(map.get(&3), None);
+        assert!(map.contains_key(&2));
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.remove(&1), Some(11));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.len(), 1);
+
+        map.compute(3, |_, old| { assert!(old.is_none()); Some(30) });
+        map.compute(3, |_, old| old.map(|v| v + 3));
+        assert_eq!(map.get(&3), Some(33));
+        map.compute(2, |_, _| None);
+        assert!(!map.contains_key(&2));
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn grow() {
+        let map = ConcurrentHashMap::with_concurrency(1);
+        for i in range(0u, 10000) {
+            assert_eq!(map.insert(i, i), None);
+        }
+        assert_eq!(map.len(), 10000);
+        for i in range(0u, 10000) {
+            assert_eq!(map.get(&i), Some(i));
+        }
+        for i in range(0u, 10000).filter(|i| i % 2 == 0) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert_eq!(map.len(), 5000);
+        assert!(map.iter().all(|(k, v)| k == v && k % 2 == 1));
+    }
+
+    #[test]
+    fn iterate_while_modifying() {
+        let map = ConcurrentHashMap::new();
+        for i in range(0u, 100) {
+            map.insert(i, i);
+        }
+        let mut seen = 0u;
+        for (k, v) in map.iter() {
+            assert_eq!(k, v);
+            // No shard lock is held here
+            map.remove(&k);
+            map.insert(k + 1000, v);
+            seen += 1;
+        }
+        assert!(seen >= 100);
+        assert!(range(0u, 100).all(|i| !map.contains_key(&i)));
+    }
+
+    #[test]
+    fn concurrent() {
+        let ntasks = 8u;
+        let nkeys = 1000u;
+        let map = Arc::new(ConcurrentHashMap::with_concurrency(4));
+        let (tx, rx) = channel();
+
+        for t in range(0, ntasks) {
+            let map = map.clone();
+            let tx = tx.clone();
+            native::task::spawn(proc() {
+                for i in range(0, nkeys) {
+                    map.insert(t * nkeys + i, t);
+                    map.compute(-1i as uint, |_, n| Some(n.unwrap_or(0) + 1));
+                    if i % 2 == 0 {
+                        assert_eq!(map.remove(&(t * nkeys + i)), Some(t));
+                    }
+                }
+                tx.send(());
+            });
+        }
+        for _ in range(0, ntasks) {
+            rx.recv();
+        }
+
+        assert_eq!(map.get(&(-1i as uint)), Some(ntasks * nkeys));
+        assert_eq!(map.len(), ntasks * nkeys / 2 + 1);
+        for t in range(0, ntasks) {
+            for i in range(0, nkeys) {
+                let expected = if i % 2 == 0 { None } else { Some(t) };
+                assert_eq!(map.get(&(t * nkeys + i)), expected);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::prelude::*;
+    use std::collections::HashMap;
+    use native;
+    use test::Bencher;
+
+    use {Arc, RWLock};
+    use super::ConcurrentHashMap;
+
+    static TASKS: uint = 8;
+    static OPS: uint = 1000;
+    static KEYS: uint = 512;
+
+    // Runs `op` OPS times on each of TASKS native tasks sharing `map`
+    fn contend<M: Send + Sync>(map: &Arc<M>, op: fn(&M, uint)) {
+        let (tx, rx) = channel();
+        for t in range(0, TASKS) {
+            let map = map.clone();
+            let tx = tx.clone();
+            native::task::spawn(proc() {
+                for i in range(0, OPS) {
+                    op(&*map, t * OPS + i);
+                }
+                tx.send(());
+            });
+        }
+        for _ in range(0, TASKS) {
+            rx.recv();
+        }
+    }
+
+    fn concurrent_op(map: &ConcurrentHashMap<uint, uint>, i: uint) {
+        if i % 4 == 0 {
+            map.insert(i % KEYS, i);
+        } else {
+            map.get(&(i * 7 % KEYS));
+        }
+    }
+
+    fn rwlock_op(map: &RWLock<HashMap<uint, uint>>, i: uint) {
+        if i % 4 == 0 {
+            map.write().insert(i % KEYS, i);
+        } else {
+            map.read().find(&(i * 7 % KEYS)).map(|&v| v);
+        }
+    }
+
+    #[bench]
+    fn find_concurrent_hashmap(b: &mut Bencher) {
+        let map = ConcurrentHashMap::new();
+        for i in range(0u, KEYS) { map.insert(i, i); }
+        let mut k = 0u;
+        b.iter(|| {
+            k = (k + 7) % KEYS;
+            map.get(&k)
+        });
+    }
+
+    #[bench]
+    fn find_rwlock_hashmap(b: &mut Bencher) {
+        let map = RWLock::new(HashMap::new());
+        for i in range(0u, KEYS) { map.write().insert(i, i); }
+        let mut k = 0u;
+        b.iter(|| {
+            k = (k + 7) % KEYS;
+            map.read().find(&k).map(|&v| v)
+        });
+    }
+
+    #[bench]
+    fn contended_concurrent_hashmap(b: &mut Bencher) {
+        let map = Arc::new(ConcurrentHashMap::new());
+        for i in range(0u, KEYS) { map.insert(i, i); }
+        b.iter(|| contend(&map, concurrent_op));
+    }
+
+    #[bench]
+    fn contended_rwlock_hashmap(b: &mut Bencher) {
+        let map = Arc::new(RWLock::new(HashMap::new()));
+        for i in range(0u, KEYS) { map.write().insert(i, i); }
+        b.iter(|| contend(&map, rwlock_op));
+    }
+}
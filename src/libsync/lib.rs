@@ -59,6 +59,7 @@ pub mod spsc_queue;
 pub mod mpsc_queue;
 pub mod mpmc_bounded_queue;
 pub mod deque;
+pub mod concurrent_hashmap;
 
 // Low-level concurrency primitives
 
@@ -120,6 +120,27 @@ impl<'a> Condvar<'a> {
         PoisonOnFail::check(*self.poison.flag, self.name);
     }
 
+    /// Atomically exit the associated lock and block until a signal is sent,
+    /// or until `ms` milliseconds have elapsed.
+    ///
+    /// Returns false if the timeout elapsed without a signal. Either way the
+    /// lock is held again once this returns.
+    ///
+    /// wait_timeout(ms) is equivalent to wait_timeout_on(0, ms).
+    #[inline]
+    pub fn wait_timeout(&self, ms: u64) -> bool { self.wait_timeout_on(0, ms) }
+
+    /// Atomically exit the associated lock and block on a specified condvar
+    /// until a signal is sent on that same condvar, or until `ms`
+    /// milliseconds have elapsed. Returns false if the timeout elapsed.
+    #[inline]
+    pub fn wait_timeout_on(&self, condvar_id: uint, ms: u64) -> bool {
+        assert!(!*self.poison.flag);
+        let signaled = self.inner.cond().wait_timeout_on(condvar_id, ms);
+        PoisonOnFail::check(*self.poison.flag, self.name);
+        signaled
+    }
+
     /// Wake up a blocked task. Returns false if there was no blocked task.
     #[inline]
     pub fn signal(&self) -> bool { self.signal_on(0) }
@@ -485,6 +506,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_mutex_arc_condvar_timeout() {
+        let arc = Arc::new(Mutex::new(0i));
+        let arc2 = arc.clone();
+
+        let mut lock = arc.lock();
+        assert!(!lock.cond.wait_timeout(10));
+        *lock += 1;
+        task::spawn(proc() {
+            let mut lock = arc2.lock();
+            *lock += 1;
+            lock.cond.signal();
+        });
+        while *lock < 2 {
+            assert!(lock.cond.wait_timeout(100000));
+        }
+    }
+
     #[test] #[should_fail]
     fn test_arc_condvar_poison() {
         let arc = Arc::new(Mutex::new(1i));
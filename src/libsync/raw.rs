@@ -25,7 +25,7 @@ use core::cell::UnsafeCell;
 use collections::{Vec, MutableSeq};
 
 use mutex;
-use comm::{Receiver, Sender, channel};
+use comm::{Receiver, Sender, Empty, channel};
 
 /****************************************************************************
  * Internals
@@ -232,6 +232,21 @@ impl<'a> Condvar<'a> {
     ///
     /// wait() is equivalent to wait_on(0).
     pub fn wait_on(&self, condvar_id: uint) {
+        self.wait_timeout_opt(condvar_id, None, "cond.wait_on()");
+    }
+
+    /// As wait(), but gives up after `ms` milliseconds. Returns false if the
+    /// timeout elapsed before a signal was received, in which case the lock
+    /// has still been reacquired.
+    pub fn wait_timeout(&self, ms: u64) -> bool { self.wait_timeout_on(0, ms) }
+
+    /// As wait_timeout(), but with a specified condvar_id. See wait_on.
+    pub fn wait_timeout_on(&self, condvar_id: uint, ms: u64) -> bool {
+        self.wait_timeout_opt(condvar_id, Some(ms), "cond.wait_timeout_on()")
+    }
+
+    fn wait_timeout_opt(&self, condvar_id: uint, timeout: Option<u64>,
+                        act: &str) -> bool {
         let mut wait_end = None;
         let mut out_of_bounds = None;
         // Release lock, 'atomically' enqueuing ourselves in so doing.
@@ -254,12 +269,33 @@ impl<'a> Condvar<'a> {
 
         // If deschedule checks start getting inserted anywhere, we can be
         // killed before or after enqueueing.
-        check_cvar_bounds(out_of_bounds, condvar_id, "cond.wait_on()", || {
+        check_cvar_bounds(out_of_bounds, condvar_id, act, || {
             // Unconditionally "block". (Might not actually block if a
             // signaller already sent -- I mean 'unconditionally' in contrast
             // with acquire().)
             (|| {
-                let _ = wait_end.take().unwrap().recv();
+                let wait_end = wait_end.take().unwrap();
+                match timeout {
+                    None => { let _ = wait_end.recv(); true }
+                    Some(ms) => match wait_end.recv_timeout(ms) {
+                        Err(Empty) => {
+                            // Signals are sent under the little lock, so once
+                            // our wait end is gone under it, no signal can be
+                            // lost on us. One may have arrived since the
+                            // timeout though.
+                            let mut wait_end = Some(wait_end);
+                            let mut signaled = false;
+                            unsafe {
+                                self.sem.with(|_| {
+                                    let wait_end = wait_end.take().unwrap();
+                                    signaled = wait_end.try_recv().is_ok();
+                                })
+                            }
+                            signaled
+                        }
+                        _ => true,
+                    },
+                }
             }).finally(|| {
                 // Reacquire the condvar.
                 match self.order {
@@ -821,6 +857,29 @@ mod tests {
     fn test_mutex_cond_broadcast_none() {
         test_mutex_cond_broadcast_helper(0);
     }
+    #[test]
+    fn test_mutex_cond_wait_timeout() {
+        let m = Arc::new(Mutex::new());
+
+        // Nobody signals
+        {
+            let lock = m.lock();
+            assert!(!lock.cond.wait_timeout(10));
+        }
+        // The lock was reacquired, and a later signal isn't lost on the
+        // timed-out waiter
+        let (tx, rx) = channel();
+        let m2 = m.clone();
+        task::spawn(proc() {
+            let lock = m2.lock();
+            tx.send(());
+            assert!(lock.cond.wait_timeout(100000));
+        });
+        rx.recv(); // Wait until child gets in the mutex
+        let lock = m.lock();
+        assert!(lock.cond.signal());
+    }
+
     #[test]
     fn test_mutex_cond_no_waiter() {
         let m = Arc::new(Mutex::new());
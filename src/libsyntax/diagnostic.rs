@@ -15,9 +15,11 @@ use codemap;
 use diagnostics;
 
 use std::cell::{RefCell, Cell};
+use std::cmp;
 use std::fmt;
 use std::io;
 use std::iter::range;
+use std::rc::Rc;
 use std::string::String;
 use term::WriterWrapper;
 use term;
@@ -35,18 +37,24 @@ pub enum RenderSpan {
     /// A FileLine renders with just a line for the message prefixed
     /// by file:linenum.
     FileLine(Span),
+
+    /// A Suggestion renders with both with an initial line for the
+    /// message, prefixed by file:linenum, followed by the source code
+    /// covered by the span as it would read with the span replaced by
+    /// the given string.
+    Suggestion(Span, String),
 }
 
 impl RenderSpan {
-    fn span(self) -> Span {
-        match self {
-            FullSpan(s) | FileLine(s) => s
+    fn span(&self) -> Span {
+        match *self {
+            FullSpan(s) | FileLine(s) | Suggestion(s, _) => s
         }
     }
     fn is_full_span(&self) -> bool {
         match self {
             &FullSpan(..) => true,
-            &FileLine(..) => false,
+            &FileLine(..) | &Suggestion(..) => false,
         }
     }
 }
@@ -110,6 +118,11 @@ impl SpanHandler {
     pub fn fileline_note(&self, sp: Span, msg: &str) {
         self.handler.custom_emit(&self.cm, FileLine(sp), msg, Note);
     }
+    /// Suggests replacing the code at `sp` with `suggestion`, as a note to
+    /// the last error or warning.
+    pub fn span_suggestion(&self, sp: Span, msg: &str, suggestion: String) {
+        self.handler.custom_emit(&self.cm, Suggestion(sp, suggestion), msg, Note);
+    }
     pub fn span_bug(&self, sp: Span, msg: &str) -> ! {
         self.handler.emit(Some((&self.cm, sp)), msg, Bug);
         fail!(ExplicitBug);
@@ -387,6 +400,14 @@ fn emit(dst: &mut EmitterWriter, cm: &codemap::CodeMap, rsp: RenderSpan,
     let sp = rsp.span();
     let ss = cm.span_to_string(sp);
     let lines = cm.span_to_lines(sp);
+    match rsp {
+        Suggestion(_, ref suggestion) => {
+            try!(print_diagnostic(dst, ss.as_slice(), lvl, msg, code));
+            try!(show_suggestion(dst, cm, sp, suggestion.as_slice()));
+            return print_macro_backtrace(dst, cm, sp);
+        }
+        FullSpan(..) | FileLine(..) => {}
+    }
     if custom {
         // we want to tell compiletest/runtest to look at the last line of the
         // span (since `custom_highlight_lines` displays an arrow to the end of
@@ -521,6 +542,35 @@ fn custom_highlight_lines(w: &mut EmitterWriter,
                        term::attr::ForegroundColor(lvl.color()))
 }
 
+/// Prints the lines covered by `sp` as they would read with the span replaced
+/// by `suggestion`.
+fn show_suggestion(w: &mut EmitterWriter,
+                   cm: &codemap::CodeMap,
+                   sp: Span,
+                   suggestion: &str) -> io::IoResult<()> {
+    let lo = cm.lookup_byte_offset(sp.lo);
+    let hi = cm.lookup_byte_offset(sp.hi).pos.to_uint();
+    let fm = &*lo.fm;
+    let lo = lo.pos.to_uint();
+    let src = fm.src.as_slice();
+
+    // Extend the span to the start of its first line and the end of its last
+    let begin = src.slice_to(lo).rfind('\n').map_or(0, |i| i + 1);
+    let end = src.slice_from(hi).find('\n').map_or(src.len(), |i| hi + i);
+    let suggested = format!("{}{}{}", src.slice(begin, lo), suggestion, src.slice(hi, end));
+
+    let first_line = cm.lookup_char_pos(sp.lo).line;
+    for (i, line) in suggested.as_slice().lines().enumerate() {
+        if i == MAX_LINES {
+            let s = format!("{}:{} ", fm.name, first_line + i - 1);
+            try!(write!(&mut w.dst, "{0:1$}...\n", "", s.len()));
+            break;
+        }
+        try!(write!(&mut w.dst, "{}:{} {}\n", fm.name, first_line + i, line));
+    }
+    Ok(())
+}
+
 fn print_macro_backtrace(w: &mut EmitterWriter,
                          cm: &codemap::CodeMap,
                          sp: Span)
@@ -552,3 +602,159 @@ pub fn expect<T:Clone>(diag: &SpanHandler, opt: Option<T>, msg: || -> String)
        None => diag.handler().bug(msg().as_slice()),
     }
 }
+
+/// Applies each suggestion, a span and its replacement, to the source of the
+/// file the span points into, and returns the name and new source of each
+/// file which changed. A suggestion is skipped if it comes from a macro
+/// expansion or overlaps one earlier in the list. Removing all the code on a
+/// line removes the whole line.
+///
+/// The edits are made to the contents of each file as `read_original` returns
+/// them, since the codemap's copy has lost any byte order mark and may have
+/// gained a newline at the end. A file whose contents no longer match the
+/// codemap's is left alone.
+pub fn apply_suggestions(cm: &codemap::CodeMap, suggestions: &[(Span, String)],
+                         read_original: |&str| -> Option<String>)
+                         -> Vec<(String, String)> {
+    // The edits to each file, as byte ranges of its source
+    let mut files: Vec<(Rc<codemap::FileMap>, Vec<(uint, uint, &str)>)> = Vec::new();
+    for &(sp, ref replacement) in suggestions.iter() {
+        if sp.expn_info.is_some() || sp.hi < sp.lo { continue }
+        let start = cm.lookup_byte_offset(sp.lo);
+        let end = cm.lookup_byte_offset(sp.hi);
+        let fm = start.fm.clone();
+        if !fm.is_real_file() || fm.start_pos != end.fm.start_pos { continue }
+
+        let (mut lo, mut hi) = (start.pos.to_uint(), end.pos.to_uint());
+        if replacement.is_empty() {
+            let src = fm.src.as_slice();
+            let begin = src.slice_to(lo).rfind('\n').map_or(0, |i| i + 1);
+            let eol = src.slice_from(hi).find('\n').map_or(src.len(), |i| hi + i + 1);
+            if src.slice(begin, lo).trim().is_empty() && src.slice(hi, eol).trim().is_empty() {
+                lo = begin;
+                hi = eol;
+            }
+        }
+
+        let i = match files.iter().position(|&(ref f, _)| f.start_pos == fm.start_pos) {
+            Some(i) => i,
+            None => {
+                files.push((fm, Vec::new()));
+                files.len() - 1
+            }
+        };
+        match *files.get_mut(i) {
+            (_, ref mut edits) => {
+                // Two insertions at the same place conflict as well
+                if !edits.iter().any(|&(l, h, _)| (lo < h && l < hi) || lo == l) {
+                    edits.push((lo, hi, replacement.as_slice()));
+                }
+            }
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (fm, mut edits) in files.move_iter() {
+        let original = match read_original(fm.name.as_slice()) {
+            Some(original) => original,
+            None => continue,
+        };
+        let bom_len = if original.as_slice().starts_with("\ufeff") { "\ufeff".len() } else { 0 };
+        let (bom, src) = (original.as_slice().slice_to(bom_len),
+                          original.as_slice().slice_from(bom_len));
+        let appended = fm.src.len() == src.len() + 1 && fm.src.as_slice().ends_with("\n");
+        if !fm.src.as_slice().starts_with(src) || (fm.src.len() != src.len() && !appended) {
+            continue
+        }
+
+        edits.sort_by(|&(a, _, _), &(b, _, _)| a.cmp(&b));
+        let mut out = String::with_capacity(original.len());
+        out.push_str(bom);
+        let mut pos = 0;
+        for &(lo, hi, replacement) in edits.iter() {
+            // Edits may reach into the newline only the codemap has
+            let (lo, hi) = (cmp::min(lo, src.len()), cmp::min(hi, src.len()));
+            out.push_str(src.slice(pos, lo));
+            out.push_str(replacement);
+            pos = hi;
+        }
+        out.push_str(src.slice_from(pos));
+        changed.push((fm.name.clone(), out));
+    }
+    changed
+}
+
+#[cfg(test)]
+mod test {
+    use codemap::{BytePos, CodeMap, Span};
+    use super::apply_suggestions;
+
+    fn sp(lo: u32, hi: u32) -> Span {
+        Span { lo: BytePos(lo), hi: BytePos(hi), expn_info: None }
+    }
+
+    fn apply(src: &str, suggestions: &[(Span, &str)]) -> String {
+        let cm = CodeMap::new();
+        cm.new_filemap("lib.rs".to_string(), src.to_string());
+        let suggestions: Vec<(Span, String)> = suggestions.iter().map(|&(sp, s)| {
+            (sp, s.to_string())
+        }).collect();
+        let mut files = apply_suggestions(&cm, suggestions.as_slice(), |name| {
+            assert_eq!(name, "lib.rs");
+            Some(src.to_string())
+        });
+        assert_eq!(files.len(), 1);
+        let (name, src) = files.pop().unwrap();
+        assert_eq!(name.as_slice(), "lib.rs");
+        src
+    }
+
+    #[test]
+    fn replaces_in_order() {
+        let src = "if (x) { y = (1); }";
+        assert_eq!(apply(src, [(sp(13, 16), "1"), (sp(3, 6), "x")]).as_slice(),
+                   "if x { y = 1; }");
+    }
+
+    #[test]
+    fn skips_overlapping() {
+        let src = "let mut x = (((1)));";
+        assert_eq!(apply(src, [(sp(12, 19), "((1))"), (sp(13, 18), "(1)")]).as_slice(),
+                   "let mut x = ((1));");
+        assert_eq!(apply(src, [(sp(4, 4), "a"), (sp(4, 4), "b")]).as_slice(),
+                   "let amut x = (((1)));");
+    }
+
+    #[test]
+    fn removes_empty_lines() {
+        let src = "use a;\n    use b; use c;\nfn main() {}\n";
+        assert_eq!(apply(src, [(sp(0, 6), "")]).as_slice(),
+                   "    use b; use c;\nfn main() {}\n");
+        assert_eq!(apply(src, [(sp(11, 17), "")]).as_slice(),
+                   "use a;\n     use c;\nfn main() {}\n");
+    }
+
+    #[test]
+    fn keeps_bom_and_missing_newline() {
+        let src = "\ufefflet x = (1);";
+        assert_eq!(apply(src, [(sp(8, 11), "1")]).as_slice(), "\ufefflet x = 1;");
+        let src = "use a;\nuse b;";
+        assert_eq!(apply(src, [(sp(7, 13), "")]).as_slice(), "use a;\n");
+        assert_eq!(apply(src, [(sp(13, 13), " // b")]).as_slice(), "use a;\nuse b; // b");
+    }
+
+    #[test]
+    fn ignores_fake_and_changed_files() {
+        let cm = CodeMap::new();
+        cm.new_filemap("<anon>".to_string(), "(1)".to_string());
+        assert!(apply_suggestions(&cm, [(sp(0, 3), "1".to_string())], |_| fail!()).is_empty());
+
+        let cm = CodeMap::new();
+        cm.new_filemap("lib.rs".to_string(), "(1)".to_string());
+        let suggestions = [(sp(0, 3), "1".to_string())];
+        assert!(apply_suggestions(&cm, suggestions.as_slice(), |_| {
+            Some("(2)".to_string())
+        }).is_empty());
+        assert!(apply_suggestions(&cm, suggestions.as_slice(), |_| None).is_empty());
+    }
+}
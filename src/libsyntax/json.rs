@@ -0,0 +1,323 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A diagnostic emitter which writes machine-readable JSON.
+//!
+//! Each diagnostic is written as one JSON object on a line of its own:
+//!
+//! ```json
+//! {
+//!     "message": "unresolved name `foo`.",
+//!     "code": {"code": "E0001", "explanation": null},
+//!     "level": "error",
+//!     "spans": [{
+//!         "file_name": "lib.rs",
+//!         "byte_start": 21, "byte_end": 24,
+//!         "line_start": 2, "line_end": 2,
+//!         "column_start": 5, "column_end": 8,
+//!         "expansion": null,
+//!         "suggested_replacement": null
+//!     }],
+//!     "children": []
+//! }
+//! ```
+//!
+//! Line and column numbers start at 1, and `byte_end` and `column_end` are
+//! exclusive. A span coming out of a macro expansion has an `expansion` with
+//! the span of the macro invocation, which may itself have an `expansion`,
+//! the name of the macro and the span of its definition if known.
+//!
+//! A note suggesting a change to the code has the replacement for its span
+//! as `suggested_replacement`.
+//!
+//! The notes which follow an error or a warning are its `children`, so each
+//! diagnostic is only written once the next one starts, or the emitter is
+//! dropped.
+
+use codemap::{CodeMap, Span, Pos, DUMMY_SP, MacroAttribute, MacroBang};
+use diagnostic::{Emitter, Level, RenderSpan, FullSpan, FileLine, Suggestion};
+use diagnostic::{Error, Warning, Note};
+use diagnostics::registry::Registry;
+
+use std::io;
+use serialize::json;
+
+pub struct JsonEmitter {
+    dst: Box<Writer + Send>,
+    registry: Option<Registry>,
+    /// The last error or warning, which the notes following it are attached
+    /// to.
+    pending: Option<Diagnostic>,
+}
+
+#[deriving(Encodable)]
+struct Diagnostic {
+    message: String,
+    code: Option<DiagnosticCode>,
+    level: String,
+    spans: Vec<DiagnosticSpan>,
+    children: Vec<Diagnostic>,
+}
+
+#[deriving(Encodable)]
+struct DiagnosticCode {
+    code: String,
+    /// The text shown by `--explain`, if there is one.
+    explanation: Option<&'static str>,
+}
+
+#[deriving(Encodable)]
+struct DiagnosticSpan {
+    file_name: String,
+    byte_start: uint,
+    byte_end: uint,
+    line_start: uint,
+    line_end: uint,
+    column_start: uint,
+    column_end: uint,
+    expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
+    /// The code suggested to replace the span with, if any.
+    suggested_replacement: Option<String>,
+}
+
+#[deriving(Encodable)]
+struct DiagnosticSpanMacroExpansion {
+    /// The span of the macro invocation.
+    span: DiagnosticSpan,
+    /// The name of the macro, like `foo!` or `#[deriving]`.
+    macro_decl_name: String,
+    /// The span of the macro definition, if it was defined in source.
+    def_site_span: Option<DiagnosticSpan>,
+}
+
+impl JsonEmitter {
+    pub fn stderr(registry: Option<Registry>) -> JsonEmitter {
+        JsonEmitter::new(box io::stderr(), registry)
+    }
+
+    pub fn new(dst: Box<Writer + Send>, registry: Option<Registry>) -> JsonEmitter {
+        JsonEmitter { dst: dst, registry: registry, pending: None }
+    }
+
+    fn push(&mut self, spans: Vec<DiagnosticSpan>, msg: &str, code: Option<&str>,
+            lvl: Level) {
+        let diag = Diagnostic {
+            message: msg.to_string(),
+            code: code.map(|code| DiagnosticCode {
+                code: code.to_string(),
+                explanation: self.registry.as_ref().and_then(|r| r.find_description(code)),
+            }),
+            level: lvl.to_string(),
+            spans: spans,
+            children: Vec::new(),
+        };
+
+        if lvl == Note && self.pending.is_some() {
+            self.pending.as_mut().unwrap().children.push(diag);
+            return
+        }
+        self.flush();
+        if lvl == Error || lvl == Warning {
+            self.pending = Some(diag);
+        } else {
+            // Fatal errors and bugs abort the compilation right away, so
+            // there's nothing more to wait for
+            self.write(&diag);
+        }
+    }
+
+    fn write(&mut self, diag: &Diagnostic) {
+        let json = json::encode(diag);
+        match writeln!(self.dst, "{}", json) {
+            Ok(()) => {}
+            Err(e) => fail!("failed to print diagnostics: {}", e),
+        }
+    }
+
+    /// Writes out the last error or warning.
+    fn flush(&mut self) {
+        match self.pending.take() {
+            Some(diag) => self.write(&diag),
+            None => {}
+        }
+    }
+}
+
+impl Emitter for JsonEmitter {
+    fn emit(&mut self, cmsp: Option<(&CodeMap, Span)>,
+            msg: &str, code: Option<&str>, lvl: Level) {
+        let spans = match cmsp {
+            Some((cm, sp)) => DiagnosticSpan::from_span(cm, sp).move_iter().collect(),
+            None => Vec::new(),
+        };
+        self.push(spans, msg, code, lvl);
+    }
+
+    fn custom_emit(&mut self, cm: &CodeMap,
+                   sp: RenderSpan, msg: &str, lvl: Level) {
+        let (sp, replacement) = match sp {
+            FullSpan(sp) | FileLine(sp) => (sp, None),
+            Suggestion(sp, replacement) => (sp, Some(replacement)),
+        };
+        let span = DiagnosticSpan::from_span(cm, sp).map(|span| DiagnosticSpan {
+            suggested_replacement: replacement,
+            ..span
+        });
+        self.push(span.move_iter().collect(), msg, None, lvl);
+    }
+}
+
+impl Drop for JsonEmitter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl DiagnosticSpan {
+    // Returns `None` for the dummy span, when there are no files to point to
+    fn from_span(cm: &CodeMap, sp: Span) -> Option<DiagnosticSpan> {
+        if cm.files.borrow().len() == 0 && sp == DUMMY_SP {
+            return None
+        }
+
+        let start = cm.lookup_char_pos(sp.lo);
+        let end = cm.lookup_char_pos(sp.hi);
+        let expansion = sp.expn_info.and_then(|ei| {
+            DiagnosticSpan::from_span(cm, ei.call_site).map(|call_site| {
+                let (pre, post) = match ei.callee.format {
+                    MacroAttribute => ("#[", "]"),
+                    MacroBang => ("", "!"),
+                };
+                box DiagnosticSpanMacroExpansion {
+                    span: call_site,
+                    macro_decl_name: format!("{}{}{}", pre, ei.callee.name, post),
+                    def_site_span: ei.callee.span.and_then(|sp| {
+                        DiagnosticSpan::from_span(cm, sp)
+                    }),
+                }
+            })
+        });
+
+        Some(DiagnosticSpan {
+            file_name: start.file.name.clone(),
+            byte_start: cm.lookup_byte_offset(sp.lo).pos.to_uint(),
+            byte_end: cm.lookup_byte_offset(sp.hi).pos.to_uint(),
+            line_start: start.line,
+            line_end: end.line,
+            column_start: start.col.to_uint() + 1,
+            column_end: end.col.to_uint() + 1,
+            expansion: expansion,
+            suggested_replacement: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use codemap::{BytePos, CodeMap, Span, DUMMY_SP};
+    use diagnostic;
+    use super::JsonEmitter;
+
+    use serialize::json;
+    use std::io::{ChanReader, ChanWriter};
+
+    // Emits some diagnostics and returns the lines written
+    fn emit(f: |&diagnostic::SpanHandler|) -> Vec<json::Json> {
+        let (tx, rx) = channel();
+        {
+            let cm = CodeMap::new();
+            let fm = cm.new_filemap("lib.rs".to_string(),
+                                    "fn main() {\n    foo();\n}\n".to_string());
+            fm.next_line(BytePos(0));
+            fm.next_line(BytePos(12));
+            fm.next_line(BytePos(23));
+
+            let emitter = JsonEmitter::new(box ChanWriter::new(tx), None);
+            let handler = diagnostic::mk_handler(box emitter);
+            f(&diagnostic::mk_span_handler(handler, cm));
+        }
+        let output = ChanReader::new(rx).read_to_string().unwrap();
+        output.as_slice().lines().map(|line| json::from_str(line).unwrap()).collect()
+    }
+
+    fn sp(lo: u32, hi: u32) -> Span {
+        Span { lo: BytePos(lo), hi: BytePos(hi), expn_info: None }
+    }
+
+    fn get<'a>(json: &'a json::Json, key: &str) -> &'a json::Json {
+        json.find(&key.to_string()).unwrap()
+    }
+
+    #[test]
+    fn error_with_code() {
+        let diags = emit(|h| h.span_err_with_code(sp(16, 19), "unresolved name", "E0001"));
+        assert_eq!(diags.len(), 1);
+        let diag = &diags[0];
+        assert_eq!(get(diag, "message").as_string(), Some("unresolved name"));
+        assert_eq!(get(diag, "level").as_string(), Some("error"));
+        assert_eq!(get(get(diag, "code"), "code").as_string(), Some("E0001"));
+        assert!(get(get(diag, "code"), "explanation").is_null());
+
+        let span = &get(diag, "spans").as_list().unwrap()[0];
+        assert_eq!(get(span, "file_name").as_string(), Some("lib.rs"));
+        for &(key, value) in [("byte_start", 16u64), ("byte_end", 19),
+                              ("line_start", 2), ("line_end", 2),
+                              ("column_start", 5), ("column_end", 8)].iter() {
+            assert_eq!(get(span, key).as_u64(), Some(value));
+        }
+        assert!(get(span, "expansion").is_null());
+        assert!(get(span, "suggested_replacement").is_null());
+    }
+
+    #[test]
+    fn suggestion() {
+        let diags = emit(|h| {
+            h.span_warn(sp(16, 19), "unnecessary call");
+            h.span_suggestion(sp(16, 21), "remove it", String::new());
+        });
+        assert_eq!(diags.len(), 1);
+        let children = get(&diags[0], "children").as_list().unwrap();
+        assert_eq!(get(&children[0], "message").as_string(), Some("remove it"));
+        let span = &get(&children[0], "spans").as_list().unwrap()[0];
+        assert_eq!(get(span, "byte_end").as_u64(), Some(21));
+        assert_eq!(get(span, "suggested_replacement").as_string(), Some(""));
+    }
+
+    #[test]
+    fn notes_are_children() {
+        let diags = emit(|h| {
+            h.span_warn(sp(16, 19), "first");
+            h.span_note(sp(0, 2), "a note");
+            h.handler().note("another note");
+            h.handler().err("second");
+        });
+        assert_eq!(diags.len(), 2);
+        let children = get(&diags[0], "children").as_list().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(get(&children[0], "message").as_string(), Some("a note"));
+        assert_eq!(get(&children[1], "spans").as_list().unwrap().len(), 0);
+        assert_eq!(get(&diags[1], "message").as_string(), Some("second"));
+        assert_eq!(get(&diags[1], "children").as_list().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn dummy_span() {
+        let (tx, rx) = channel();
+        {
+            let emitter = JsonEmitter::new(box ChanWriter::new(tx), None);
+            let handler = diagnostic::mk_handler(box emitter);
+            let h = diagnostic::mk_span_handler(handler, CodeMap::new());
+            h.span_err(DUMMY_SP, "nowhere");
+        }
+        let output = ChanReader::new(rx).read_to_string().unwrap();
+        let diag = json::from_str(output.as_slice().trim()).unwrap();
+        assert_eq!(get(&diag, "spans").as_list().unwrap().len(), 0);
+    }
+}
@@ -60,6 +60,7 @@ pub mod attr;
 pub mod codemap;
 pub mod crateid;
 pub mod diagnostic;
+pub mod json;
 pub mod fold;
 pub mod owned_slice;
 pub mod parse;
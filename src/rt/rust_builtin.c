@@ -17,6 +17,7 @@
 #if !defined(__WIN32__)
 #include <sys/time.h>
 #include <sys/types.h>
+#include <sys/stat.h>
 #include <dirent.h>
 #include <signal.h>
 #include <unistd.h>
@@ -127,6 +128,39 @@ rust_list_dir_wfd_fp_buf(void* wfd) {
 }
 #endif
 
+uint32_t
+#if defined(__WIN32__)
+rust_list_dir_wfd_attrs(WIN32_FIND_DATAW* wfd) {
+    return wfd->dwFileAttributes;
+}
+#else
+rust_list_dir_wfd_attrs(void* wfd) {
+    return 0;
+}
+#endif
+
+// Returns the S_IFMT bits of the type of a directory entry, or 0 if the type
+// isn't known without a stat().
+uint32_t
+#if defined(__WIN32__) || !defined(DT_UNKNOWN)
+rust_list_dir_type(void* entry_ptr) {
+    return 0;
+}
+#else
+rust_list_dir_type(struct dirent* entry_ptr) {
+    switch (entry_ptr->d_type) {
+        case DT_REG: return S_IFREG;
+        case DT_DIR: return S_IFDIR;
+        case DT_LNK: return S_IFLNK;
+        case DT_FIFO: return S_IFIFO;
+        case DT_BLK: return S_IFBLK;
+        case DT_CHR: return S_IFCHR;
+        case DT_SOCK: return S_IFSOCK;
+        default: return 0;
+    }
+}
+#endif
+
 typedef struct {
     int32_t tm_sec;
     int32_t tm_min;
@@ -0,0 +1,27 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+#![warn(unnecessary_qualification)]
+
+use std::collections::{HashMap};
+
+pub mod foo {
+    pub fn bar() -> int { 1 }
+
+    pub fn baz() -> int {
+        bar() + 1
+    }
+}
+
+pub fn total(map: &HashMap<int, int>) -> int {
+    let total = foo::baz() + map.len() as int;
+    total
+}
@@ -1,4 +1,4 @@
-// Copyright 2012 The Rust Project Developers. See the COPYRIGHT
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
 // file at the top-level directory of this distribution and at
 // http://rust-lang.org/COPYRIGHT.
 //
@@ -8,11 +8,14 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-#![allow(non_camel_case_types)]
+#![crate_type = "rlib"]
 
-pub struct t {
-    pub module_asm: String,
-    pub data_layout: String,
-    pub target_triple: String,
-    pub cc_args: Vec<String> ,
+mod a {
+    pub fn one() -> uint { ::b::private() - 1 }
 }
+
+mod b {
+    pub fn private() -> uint { 2 }
+}
+
+pub fn one() -> uint { a::one() }
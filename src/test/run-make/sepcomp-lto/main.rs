@@ -0,0 +1,23 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate lib;
+
+mod a {
+    pub fn two() -> uint { ::lib::one() + ::b::one() }
+}
+
+mod b {
+    pub fn one() -> uint { ::lib::one() }
+}
+
+fn main() {
+    assert_eq!(a::two(), 2);
+}
@@ -0,0 +1,68 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C codegen-units=3
+
+// Test #[inline] methods, in inherent and trait impls, called from other
+// codegen units.
+
+mod a {
+    pub struct Counter {
+        pub count: uint,
+    }
+
+    impl Counter {
+        #[inline]
+        pub fn bump(&mut self, by: uint) -> uint {
+            self.count += by;
+            self.count
+        }
+    }
+
+    impl ::Double for Counter {
+        #[inline]
+        fn double(&self) -> uint { self.count * 2 }
+    }
+}
+
+mod b {
+    use Double;
+    use a::Counter;
+
+    pub fn three() -> uint {
+        let mut c = Counter { count: 1 };
+        c.bump(2)
+    }
+
+    pub fn eight() -> uint {
+        let c = Counter { count: 4 };
+        c.double()
+    }
+}
+
+mod c {
+    use a::Counter;
+
+    pub fn five() -> uint {
+        let mut c = Counter { count: 0 };
+        c.bump(2);
+        c.bump(3)
+    }
+}
+
+trait Double {
+    fn double(&self) -> uint;
+}
+
+fn main() {
+    assert_eq!(b::three(), 3);
+    assert_eq!(b::eight(), 8);
+    assert_eq!(c::five(), 5);
+}
@@ -0,0 +1,42 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C codegen-units=3
+
+// Test references to statics and their values between codegen units.
+
+static ONE: uint = 1;
+
+mod a {
+    pub static TWO: uint = ::ONE + 1;
+    pub static mut COUNT: uint = 0;
+
+    pub fn bump() -> uint {
+        unsafe {
+            COUNT += super::b::THREE;
+            COUNT
+        }
+    }
+}
+
+mod b {
+    pub static THREE: uint = ::a::TWO + ::ONE;
+    pub static THREE_REF: &'static uint = &::a::TWO;
+}
+
+fn main() {
+    assert_eq!(ONE, 1);
+    assert_eq!(a::TWO, 2);
+    assert_eq!(b::THREE, 3);
+    assert_eq!(*b::THREE_REF, 2);
+    assert_eq!(a::bump(), 3);
+    assert_eq!(a::bump(), 6);
+    unsafe { assert_eq!(a::COUNT, 6); }
+}